@@ -0,0 +1,195 @@
+//! `ultrasearch jobs show`: read an index-worker job file the way the worker does and flag
+//! jobs it will not be able to extract.
+//!
+//! The service writes `{ "version": 1, "jobs": [...] }`; older services wrote a bare array of
+//! jobs, which the worker still accepts. Both parse here, and the report says which one it was.
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// `--max-bytes` the worker applies to a job that carries none.
+pub const WORKER_DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+/// One job as the worker reads it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobEntry {
+    pub volume_id: u16,
+    pub file_id: u64,
+    pub path: PathBuf,
+    #[serde(default)]
+    pub max_bytes: Option<usize>,
+    #[serde(default)]
+    pub max_chars: Option<usize>,
+    /// Size when the job was queued.
+    #[serde(default)]
+    pub file_size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>,
+    #[serde(default)]
+    pub attempts: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobFileFormat {
+    /// `{ "version": 1, "jobs": [...] }`.
+    Versioned,
+    /// A bare array of jobs.
+    Legacy,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobReport {
+    #[serde(flatten)]
+    pub job: JobEntry,
+    /// Why the worker will skip or fail this job; empty when it looks extractable.
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobFileReport {
+    pub file: PathBuf,
+    pub format: JobFileFormat,
+    pub jobs: Vec<JobReport>,
+}
+
+impl JobFileReport {
+    pub fn warning_count(&self) -> usize {
+        self.jobs.iter().map(|j| j.warnings.len()).sum()
+    }
+}
+
+#[derive(Deserialize)]
+struct VersionedJobFile {
+    version: u32,
+    #[serde(default)]
+    jobs: Vec<JobEntry>,
+}
+
+/// Parse `file` in either format, as the worker's `load_jobs` does. Unlike the worker, an empty
+/// file is reported rather than rejected.
+pub fn load_job_file(file: &Path) -> Result<(JobFileFormat, Vec<JobEntry>)> {
+    let bytes =
+        fs::read(file).with_context(|| format!("cannot open job file {}", file.display()))?;
+    match serde_json::from_slice::<VersionedJobFile>(&bytes) {
+        Ok(batch) if batch.version != 1 => {
+            bail!("unsupported job file version {}", batch.version)
+        }
+        Ok(batch) => Ok((JobFileFormat::Versioned, batch.jobs)),
+        Err(_) => {
+            let jobs = serde_json::from_slice(&bytes).with_context(|| {
+                format!(
+                    "{} is neither a versioned job file nor a legacy job array",
+                    file.display()
+                )
+            })?;
+            Ok((JobFileFormat::Legacy, jobs))
+        }
+    }
+}
+
+/// Load `file` and check each job against the file system.
+pub fn inspect_job_file(file: &Path) -> Result<JobFileReport> {
+    let (format, jobs) = load_job_file(file)?;
+    Ok(JobFileReport {
+        file: file.to_path_buf(),
+        format,
+        jobs: jobs
+            .into_iter()
+            .map(|job| JobReport {
+                warnings: job_warnings(&job),
+                job,
+            })
+            .collect(),
+    })
+}
+
+fn job_warnings(job: &JobEntry) -> Vec<String> {
+    let meta = match fs::metadata(&job.path) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return vec!["missing: the file no longer exists".to_string()];
+        }
+        Err(e) => return vec![format!("unreadable: {e}")],
+    };
+    if !meta.is_file() {
+        return vec!["not a regular file".to_string()];
+    }
+    let max_bytes = job
+        .max_bytes
+        .map_or(WORKER_DEFAULT_MAX_BYTES, |max| max as u64);
+    if meta.len() > max_bytes {
+        vec![format!(
+            "oversized: {} bytes is over max_bytes {max_bytes}; the worker will skip it",
+            meta.len()
+        )]
+    } else {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job_json(path: &Path, max_bytes: Option<usize>) -> serde_json::Value {
+        serde_json::json!({
+            "volume_id": 1,
+            "file_id": 42,
+            "path": path,
+            "max_bytes": max_bytes,
+            "file_size": 5,
+            "priority": "content",
+        })
+    }
+
+    #[test]
+    fn both_formats_parse_and_problem_jobs_are_flagged() {
+        let dir = tempfile::tempdir().unwrap();
+        let present = dir.path().join("notes.txt");
+        fs::write(&present, "hello").unwrap();
+        let missing = dir.path().join("gone.txt");
+
+        let versioned = dir.path().join("batch.json");
+        fs::write(
+            &versioned,
+            serde_json::json!({
+                "version": 1,
+                "jobs": [job_json(&present, None), job_json(&missing, None)],
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let report = inspect_job_file(&versioned).unwrap();
+        assert_eq!(report.format, JobFileFormat::Versioned);
+        assert_eq!(report.jobs.len(), 2);
+        assert_eq!(report.jobs[0].job.priority.as_deref(), Some("content"));
+        assert!(report.jobs[0].warnings.is_empty());
+        assert!(report.jobs[1].warnings[0].starts_with("missing"));
+        assert_eq!(report.warning_count(), 1);
+
+        let legacy = dir.path().join("legacy.json");
+        fs::write(
+            &legacy,
+            serde_json::json!([job_json(&present, Some(2))]).to_string(),
+        )
+        .unwrap();
+        let report = inspect_job_file(&legacy).unwrap();
+        assert_eq!(report.format, JobFileFormat::Legacy);
+        assert!(report.jobs[0].warnings[0].starts_with("oversized"));
+    }
+
+    #[test]
+    fn unknown_versions_and_garbage_are_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("jobs.json");
+        fs::write(&file, r#"{"version": 2, "jobs": []}"#).unwrap();
+        let err = inspect_job_file(&file).unwrap_err();
+        assert!(err.to_string().contains("version 2"), "{err}");
+
+        fs::write(&file, "not json").unwrap();
+        assert!(inspect_job_file(&file).is_err());
+    }
+}
@@ -2,4 +2,52 @@
 //! Currently the CLI is implemented in `main.rs`; this module exists to keep
 //! the crate structure consistent and to host shared helpers as the CLI grows.
 
+use anyhow::{Result, anyhow};
+use core_types::config::AppConfig;
+use serde_json::Value;
+
+pub mod jobs;
+pub mod path_style;
+pub mod repl;
+
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Look up a dotted key such as `scheduler.idle_warm_seconds` in `cfg`. An unknown key fails
+/// with the keys valid at the level where the lookup stopped.
+pub fn config_value(cfg: &AppConfig, key: &str) -> Result<Value> {
+    let mut value = serde_json::to_value(cfg)?;
+    for (depth, part) in key.split('.').enumerate() {
+        let Value::Object(mut map) = value else {
+            let parent = key.split('.').take(depth).collect::<Vec<_>>().join(".");
+            return Err(anyhow!("{parent} is not a section; cannot look up {key}"));
+        };
+        value = map.remove(part).ok_or_else(|| {
+            let known: Vec<&str> = map.keys().map(String::as_str).collect();
+            anyhow!(
+                "unknown config key {key} (expected one of: {})",
+                known.join(", ")
+            )
+        })?;
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_value_follows_dotted_keys() {
+        let cfg = AppConfig::default();
+        assert_eq!(
+            config_value(&cfg, "scheduler.idle_warm_seconds").unwrap(),
+            Value::from(15)
+        );
+        assert!(config_value(&cfg, "scheduler").unwrap().is_object());
+
+        let err = config_value(&cfg, "scheduler.idle_warm").unwrap_err();
+        assert!(err.to_string().contains("idle_warm_seconds"), "{err}");
+        let err = config_value(&cfg, "scheduler.idle_warm_seconds.x").unwrap_err();
+        assert!(err.to_string().contains("not a section"), "{err}");
+    }
+}
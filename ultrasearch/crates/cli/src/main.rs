@@ -1,12 +1,22 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand, ValueEnum};
+use cli::config_value;
+use cli::jobs::{JobFileReport, inspect_job_file};
+use cli::path_style::PathStyle;
+use cli::repl::{HELP, ReplCommand, parse_line};
 use console::style;
-use core_types::config::{default_config_path, load_or_create_config};
+use core_types::DocKey;
+use core_types::config::{
+    SavedSearch, SavedSearchMode, check_config_file, default_config_path, load_or_create_config,
+};
 #[cfg(not(windows))]
 use ipc::MetricsSnapshot;
+use ipc::query::{ParseError, parse_query};
 use ipc::{
-    QueryExpr, ReloadConfigRequest, RescanRequest, SearchMode, SearchRequest, SearchResponse,
-    StatusRequest, StatusResponse, TermExpr, TermModifier,
+    FieldKind, GetDocRequest, GetDocResponse, ListVolumesRequest, ListVolumesResponse, PlanRequest,
+    PlanResponse, QueryExpr, ReindexRequest, ReindexScope, ReloadConfigRequest, RescanRequest,
+    SearchHit, SearchMode, SearchRequest, SearchResponse, StatusRequest, StatusResponse,
+    TermModifier,
 };
 use uuid::Uuid;
 
@@ -25,6 +35,10 @@ struct Cli {
     #[arg(long)]
     pipe: Option<String>,
 
+    /// How to write file paths in search and get output (`forward` uses `/` separators).
+    #[arg(long, global = true, value_enum, default_value_t = PathStyle::Native)]
+    path_style: PathStyle,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -33,7 +47,7 @@ struct Cli {
 enum Commands {
     /// Run a search query over IPC.
     Search {
-        /// Query string (full-text or filename).
+        /// Query, e.g. `report ext:pdf size:>1M` or `(draft OR final) -tmp`.
         query: String,
         /// Limit results.
         #[arg(short, long, default_value_t = 20)]
@@ -41,12 +55,83 @@ enum Commands {
         /// Offset for pagination.
         #[arg(short = 'o', long, default_value_t = 0)]
         offset: u32,
+        /// Resume after a cursor printed by a previous page (overrides --offset).
+        #[arg(long)]
+        cursor: Option<String>,
         /// Search mode (auto/name/content/hybrid).
         #[arg(short, long, value_enum, default_value_t = ModeArg::Auto)]
         mode: ModeArg,
         /// Optional timeout in milliseconds.
         #[arg(long)]
         timeout_ms: Option<u64>,
+        /// Show how the service planned and scored the query.
+        #[arg(long)]
+        explain: bool,
+        /// Match file names case-sensitively.
+        #[arg(long)]
+        case_sensitive: bool,
+        /// Match the query anywhere inside file names (needs `search.name_ngrams`).
+        #[arg(long)]
+        substring: bool,
+        /// Only print how many docs match; no hits are fetched.
+        #[arg(long)]
+        count: bool,
+        /// Comma-separated hit fields to return (default: all).
+        #[arg(long, value_enum, value_delimiter = ',')]
+        fields: Vec<HitFieldArg>,
+        /// Also show files under `search.excluded_paths` (recycle bin, temp folders).
+        #[arg(long)]
+        include_system: bool,
+        /// Suggest spelling corrections when a name search finds nothing.
+        #[arg(long)]
+        suggest: bool,
+        /// Show scores relative to the top hit (0-1) instead of raw BM25.
+        #[arg(long)]
+        normalize_scores: bool,
+        /// Output as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// List the most recently modified files, newest first.
+    Recent {
+        /// Number of files to show.
+        #[arg(short, long, default_value_t = 20)]
+        limit: u32,
+        /// Also show files under `search.excluded_paths` (recycle bin, temp folders).
+        #[arg(long)]
+        include_system: bool,
+        /// Output as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Search interactively: type queries, page with j/k, `:open N` a hit, `:help` for more.
+    Repl {
+        /// Hits per page.
+        #[arg(short, long, default_value_t = 20)]
+        limit: u32,
+        /// Initial search mode (auto/name/content/hybrid); change it with `:mode`.
+        #[arg(short, long, value_enum, default_value_t = ModeArg::Auto)]
+        mode: ModeArg,
+    },
+
+    /// Show which mode `--mode auto` would pick for a query, without running it.
+    Plan {
+        /// Query, in the same syntax as `search`.
+        query: String,
+        /// Output as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Fetch documents by the keys a search printed.
+    Get {
+        /// Comma-separated keys, each base64 (`AAMAAAAABNI`) or `<volume>:0x<frn_hex>`.
+        keys: String,
+        /// Include the extracted text from the content index.
+        #[arg(long)]
+        content: bool,
         /// Output as JSON.
         #[arg(long)]
         json: bool,
@@ -73,11 +158,97 @@ enum Commands {
         json: bool,
     },
 
+    /// List the volumes the service indexes, with their content-index setting.
+    Volumes {
+        /// Also list discovered volumes the config leaves out.
+        #[arg(long)]
+        all: bool,
+        /// Output as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Drop and rebuild indexes from a fresh MFT scan and re-extraction.
+    Reindex {
+        /// Only rebuild this volume (e.g. `C:\`); defaults to every configured volume.
+        #[arg(long)]
+        volume: Option<String>,
+        /// Rebuild only the content index.
+        #[arg(long, conflicts_with = "meta_only")]
+        content_only: bool,
+        /// Rebuild only the metadata index.
+        #[arg(long)]
+        meta_only: bool,
+        /// Output as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+
     /// Show or edit the config on disk (ProgramData).
     Config {
         #[command(subcommand)]
         sub: ConfigCmd,
     },
+
+    /// Save queries under a name and run them later.
+    Saved {
+        #[command(subcommand)]
+        sub: SavedCmd,
+    },
+
+    /// Inspect index-worker job files.
+    Jobs {
+        #[command(subcommand)]
+        sub: JobsCmd,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum JobsCmd {
+    /// List the jobs in a job file and flag missing or oversized files.
+    Show {
+        /// Job file, e.g. one left in `paths.jobs_dir`.
+        file: std::path::PathBuf,
+        /// Output as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum SavedCmd {
+    /// Save (or replace) a named query.
+    Add {
+        /// Name to recall it by.
+        name: String,
+        /// Query, in the same syntax as `search`.
+        query: String,
+        /// Search mode (auto/name/content/hybrid).
+        #[arg(short, long, value_enum, default_value_t = ModeArg::Auto)]
+        mode: ModeArg,
+    },
+    /// List saved searches.
+    List {
+        /// Output as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Forget a saved search.
+    Remove {
+        /// Name of the saved search.
+        name: String,
+    },
+    /// Run a saved search.
+    Run {
+        /// Name of the saved search.
+        name: String,
+        /// Limit results.
+        #[arg(short, long, default_value_t = 20)]
+        limit: u32,
+        /// Output as JSON.
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -88,6 +259,20 @@ enum ConfigCmd {
         #[arg(long)]
         json: bool,
     },
+    /// Print one value as JSON, e.g. `scheduler.idle_warm_seconds` or `features`.
+    Get {
+        /// Dotted key into the effective config.
+        key: String,
+    },
+    /// Load a config file as the service would and report whether it is valid.
+    Validate {
+        /// File to check (default: the effective config path).
+        #[arg(long)]
+        file: Option<std::path::PathBuf>,
+        /// Output as JSON.
+        #[arg(long)]
+        json: bool,
+    },
     /// Set volumes and content-index volumes in the config file.
     SetVolumes {
         /// Volumes to include (e.g., C:\ D:\). If omitted, defaults to all discovered NTFS volumes.
@@ -110,6 +295,49 @@ enum ModeArg {
     Hybrid,
 }
 
+impl From<ModeArg> for SavedSearchMode {
+    fn from(mode: ModeArg) -> Self {
+        match mode {
+            ModeArg::Auto => SavedSearchMode::Auto,
+            ModeArg::Name => SavedSearchMode::Name,
+            ModeArg::Content => SavedSearchMode::Content,
+            ModeArg::Hybrid => SavedSearchMode::Hybrid,
+        }
+    }
+}
+
+impl From<ModeArg> for SearchMode {
+    fn from(mode: ModeArg) -> Self {
+        match mode {
+            ModeArg::Auto => SearchMode::Auto,
+            ModeArg::Name => SearchMode::NameOnly,
+            ModeArg::Content => SearchMode::Content,
+            ModeArg::Hybrid => SearchMode::Hybrid,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum HitFieldArg {
+    Name,
+    Path,
+    Ext,
+    Size,
+    Modified,
+}
+
+impl From<HitFieldArg> for FieldKind {
+    fn from(field: HitFieldArg) -> Self {
+        match field {
+            HitFieldArg::Name => FieldKind::Name,
+            HitFieldArg::Path => FieldKind::Path,
+            HitFieldArg::Ext => FieldKind::Ext,
+            HitFieldArg::Size => FieldKind::Size,
+            HitFieldArg::Modified => FieldKind::Modified,
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenvy::dotenv().ok();
@@ -119,14 +347,87 @@ async fn main() -> Result<()> {
             ref query,
             limit,
             offset,
+            ref cursor,
             mode,
             timeout_ms,
+            explain,
+            case_sensitive,
+            substring,
+            count,
+            ref fields,
+            include_system,
+            suggest,
+            normalize_scores,
+            json,
+        } => {
+            let mut req =
+                build_search_request(query, limit, offset, cursor.clone(), timeout_ms, mode)?;
+            req.explain = explain;
+            req.case_sensitive = case_sensitive;
+            req.count_only = count;
+            req.include_system = include_system;
+            req.suggest = suggest;
+            req.normalize_scores = normalize_scores;
+            if !fields.is_empty() {
+                req.fields = Some(fields.iter().copied().map(FieldKind::from).collect());
+            }
+            if substring && let QueryExpr::Term(term) = &mut req.query {
+                term.modifier = TermModifier::Substring;
+            }
+            let mut resp = pipe(&cli).search(req).await?;
+            cli.path_style.apply_to_search(&mut resp);
+            if count {
+                output(resp, json, print_count_response)?;
+            } else {
+                output(resp, json, print_search_response)?;
+            }
+        }
+        Commands::Recent {
+            limit,
+            include_system,
             json,
         } => {
-            let req = build_search_request(query, limit, offset, timeout_ms, mode);
-            let resp = pipe(&cli).search(req).await?;
+            // An empty query matches every file; the service orders them by `modified`.
+            let mut req = SearchRequest::default()
+                .with_limit(limit)
+                .with_mode(SearchMode::NameOnly)
+                .with_sort(FieldKind::Modified, true);
+            req.id = Uuid::new_v4();
+            req.include_system = include_system;
+            let mut resp = pipe(&cli).search(req).await?;
+            cli.path_style.apply_to_search(&mut resp);
             output(resp, json, print_search_response)?;
         }
+        Commands::Repl { limit, mode } => run_repl(&cli, limit, mode).await?,
+        Commands::Plan { ref query, json } => {
+            let req = PlanRequest {
+                id: Uuid::new_v4(),
+                query: parse_query_arg(query)?,
+            };
+            let resp = pipe(&cli).plan(req).await?;
+            output(resp, json, print_plan_response)?;
+        }
+        Commands::Get {
+            ref keys,
+            content,
+            json,
+        } => {
+            let keys = DocKey::parse_many(keys).map_err(|e| anyhow::anyhow!("{e}: {keys}"))?;
+            let mut resps = Vec::with_capacity(keys.len());
+            for key in keys {
+                let req = GetDocRequest {
+                    id: Uuid::new_v4(),
+                    key,
+                    include_content: content,
+                };
+                let mut resp = pipe(&cli).get_doc(req).await?;
+                cli.path_style.apply_to_doc(&mut resp);
+                resps.push(resp);
+            }
+            output(resps, json, |resps| {
+                resps.iter().try_for_each(print_get_doc_response)
+            })?;
+        }
         Commands::Status { json } => {
             let req = StatusRequest { id: Uuid::new_v4() };
             let resp = pipe(&cli).status(req).await?;
@@ -162,6 +463,49 @@ async fn main() -> Result<()> {
                 Ok(())
             })?;
         }
+        Commands::Volumes { all, json } => {
+            let req = ListVolumesRequest {
+                id: Uuid::new_v4(),
+                include_unindexed: all,
+            };
+            let resp = pipe(&cli).list_volumes(req).await?;
+            output(resp, json, print_volumes)?;
+        }
+        Commands::Reindex {
+            ref volume,
+            content_only,
+            meta_only,
+            json,
+        } => {
+            let scope = match (content_only, meta_only) {
+                (true, _) => ReindexScope::ContentOnly,
+                (_, true) => ReindexScope::MetaOnly,
+                _ => ReindexScope::All,
+            };
+            let req = ReindexRequest {
+                id: Uuid::new_v4(),
+                volume: volume.clone(),
+                scope,
+            };
+            let resp = pipe(&cli).reindex(req).await?;
+            output(resp, json, |r| {
+                println!(
+                    "{} {}",
+                    style("Reindex:").green(),
+                    if r.accepted { "started" } else { "rejected" }
+                );
+                if let Some(msg) = &r.message {
+                    println!("  {}", msg);
+                }
+                if r.accepted {
+                    println!(
+                        "{}",
+                        style("  Progress shows in `ultrasearch status` (Scheduler).").dim()
+                    );
+                }
+                Ok(())
+            })?;
+        }
         Commands::Config { sub } => match sub {
             ConfigCmd::Show { json } => {
                 let path = default_config_path();
@@ -183,6 +527,29 @@ async fn main() -> Result<()> {
                     println!("{toml}");
                 }
             }
+            ConfigCmd::Get { key } => {
+                let cfg = load_or_create_config(None)?;
+                println!("{}", config_value(&cfg, &key)?);
+            }
+            ConfigCmd::Validate { file, json } => {
+                let path = file.unwrap_or_else(default_config_path);
+                let result = check_config_file(&path);
+                if json {
+                    println!(
+                        "{}",
+                        serde_json::json!({
+                            "path": path,
+                            "valid": result.is_ok(),
+                            "error": result.as_ref().err().map(|e| format!("{e:#}")),
+                        })
+                    );
+                } else if result.is_ok() {
+                    println!("{} {}", style("Config valid:").green(), path.display());
+                }
+                if let Err(e) = result {
+                    return Err(e.context(format!("invalid config {}", path.display())));
+                }
+            }
             ConfigCmd::SetVolumes {
                 volume,
                 content_volume,
@@ -206,11 +573,7 @@ async fn main() -> Result<()> {
                 cfg.volumes = vols;
                 cfg.content_index_volumes = content;
                 let path = default_config_path();
-                if let Some(parent) = path.parent() {
-                    let _ = std::fs::create_dir_all(parent);
-                }
-                let toml = toml::to_string_pretty(&cfg)?;
-                std::fs::write(&path, toml)?;
+                core_types::config::save_config_atomic(&path, &cfg)?;
                 if json {
                     println!(
                         "{}",
@@ -226,10 +589,101 @@ async fn main() -> Result<()> {
                 }
             }
         },
+        Commands::Saved { ref sub } => match sub {
+            SavedCmd::Add { name, query, mode } => {
+                // Reject a query that would fail every time it is run.
+                parse_query_arg(query)?;
+                let mut cfg = load_or_create_config(None)?;
+                cfg.add_saved_search(SavedSearch {
+                    name: name.clone(),
+                    query: query.clone(),
+                    mode: (*mode).into(),
+                });
+                core_types::config::save_config_atomic(&default_config_path(), &cfg)?;
+                println!("{} {name}", style("Saved:").green());
+            }
+            SavedCmd::List { json } => {
+                let cfg = load_or_create_config(None)?;
+                output(cfg.saved_searches, *json, |saved| {
+                    if saved.is_empty() {
+                        println!("{}", style("No saved searches.").dim());
+                    }
+                    for s in saved {
+                        println!(
+                            "{}  {}  {}",
+                            style(&s.name).bold(),
+                            s.query,
+                            style(format!("({:?})", s.mode)).dim()
+                        );
+                    }
+                    Ok(())
+                })?;
+            }
+            SavedCmd::Remove { name } => {
+                let mut cfg = load_or_create_config(None)?;
+                if !cfg.remove_saved_search(name) {
+                    return Err(anyhow::anyhow!("no saved search named {name}"));
+                }
+                core_types::config::save_config_atomic(&default_config_path(), &cfg)?;
+                println!("{} {name}", style("Removed:").green());
+            }
+            SavedCmd::Run { name, limit, json } => {
+                let cfg = load_or_create_config(None)?;
+                let saved = cfg
+                    .saved_search(name)
+                    .ok_or_else(|| anyhow::anyhow!("no saved search named {name}"))?;
+                let req = saved_search_request(saved, *limit)?;
+                let mut resp = pipe(&cli).search(req).await?;
+                cli.path_style.apply_to_search(&mut resp);
+                output(resp, *json, print_search_response)?;
+            }
+        },
+        Commands::Jobs { sub } => match sub {
+            JobsCmd::Show { file, json } => {
+                output(inspect_job_file(&file)?, json, print_job_file)?;
+            }
+        },
     }
     Ok(())
 }
 
+fn print_job_file(report: &JobFileReport) -> Result<()> {
+    println!(
+        "{} {} ({:?}, {} jobs)",
+        style("Job file:").green(),
+        report.file.display(),
+        report.format,
+        report.jobs.len()
+    );
+    if !report.jobs.is_empty() {
+        println!("    {:>3}  {:>18}  {:>8}  PATH", "VOL", "FRN", "ATTEMPTS");
+    }
+    for entry in &report.jobs {
+        let job = &entry.job;
+        println!(
+            "    {:>3}  {:>#18x}  {:>8}  {}",
+            job.volume_id,
+            job.file_id,
+            job.attempts,
+            job.path.display()
+        );
+        for warning in &entry.warnings {
+            println!("         {}", style(format!("! {warning}")).yellow());
+        }
+    }
+    let warnings = report.warning_count();
+    if warnings > 0 {
+        println!("{}", style(format!("{warnings} warning(s)")).yellow());
+    }
+    Ok(())
+}
+
+/// The request `saved run` sends: the saved query and mode, `limit` hits.
+fn saved_search_request(saved: &SavedSearch, limit: u32) -> Result<SearchRequest> {
+    let req = SearchRequest::from_saved(saved).map_err(|e| query_error(&saved.query, e))?;
+    Ok(req.with_limit(limit))
+}
+
 #[cfg(windows)]
 fn pipe(cli: &Cli) -> PipeClient {
     cli.pipe
@@ -247,30 +701,180 @@ fn build_search_request(
     query: &str,
     limit: u32,
     offset: u32,
+    cursor: Option<String>,
     timeout_ms: Option<u64>,
     mode: ModeArg,
-) -> SearchRequest {
-    let term = QueryExpr::Term(TermExpr {
-        field: None,
-        value: query.to_string(),
-        modifier: TermModifier::Term,
-    });
-
-    SearchRequest {
+) -> Result<SearchRequest> {
+    Ok(SearchRequest {
         id: Uuid::new_v4(),
-        query: term,
+        query: parse_query_arg(query)?,
         limit,
         offset,
-        mode: match mode {
-            ModeArg::Auto => SearchMode::Auto,
-            ModeArg::Name => SearchMode::NameOnly,
-            ModeArg::Content => SearchMode::Content,
-            ModeArg::Hybrid => SearchMode::Hybrid,
-        },
+        mode: mode.into(),
         timeout: timeout_ms.map(std::time::Duration::from_millis),
+        cursor,
+        explain: false,
+        case_sensitive: false,
+        count_only: false,
+        fields: None,
+        include_system: false,
+        sort: None,
+        suggest: false,
+        group_by: None,
+        normalize_scores: false,
+    })
+}
+
+/// Read-search-print loop over one client. History persists next to the config file; Ctrl+C
+/// clears the line or cancels a running search, Ctrl+D leaves.
+async fn run_repl(cli: &Cli, limit: u32, mode: ModeArg) -> Result<()> {
+    use rustyline::error::ReadlineError;
+
+    let client = pipe(cli);
+    let mut editor = rustyline::DefaultEditor::new()?;
+    let history = default_config_path().with_file_name("repl_history.txt");
+    let _ = editor.load_history(&history);
+
+    let mut req = SearchRequest::default()
+        .with_limit(limit)
+        .with_mode(mode.into());
+    let mut has_query = false;
+    let mut hits: Vec<SearchHit> = Vec::new();
+    println!("{}", style("UltraSearch REPL (:help for commands)").dim());
+    loop {
+        let line = match editor.readline("ultrasearch> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(err) => return Err(err.into()),
+        };
+        let _ = editor.add_history_entry(line.as_str());
+        let command = match parse_line(&line) {
+            Ok(command) => command,
+            Err(err) => {
+                println!("{}", style(err).red());
+                continue;
+            }
+        };
+        match command {
+            ReplCommand::Empty => continue,
+            ReplCommand::Quit => break,
+            ReplCommand::Help => {
+                println!("{HELP}");
+                continue;
+            }
+            ReplCommand::Mode(mode) => {
+                req.mode = mode;
+                println!("{}", style(format!("mode: {mode:?}")).dim());
+            }
+            ReplCommand::Limit(limit) => {
+                req.limit = limit;
+                req.offset = 0;
+                println!("{}", style(format!("limit: {limit}")).dim());
+            }
+            ReplCommand::Open(n) => {
+                match hits.get(n - 1).and_then(|h| h.path.as_deref()) {
+                    Some(path) => open_path(path),
+                    None => println!("{}", style(format!("no hit {n} on this page")).red()),
+                }
+                continue;
+            }
+            ReplCommand::Search(text) => match parse_query_arg(&text) {
+                Ok(parsed) => {
+                    req.query = parsed;
+                    req.offset = 0;
+                    has_query = true;
+                }
+                Err(err) => {
+                    println!("{}", style(err).red());
+                    continue;
+                }
+            },
+            ReplCommand::NextPage => req.offset = req.offset.saturating_add(req.limit),
+            ReplCommand::PrevPage => req.offset = req.offset.saturating_sub(req.limit),
+        }
+        // Mode, limit and paging changes re-run the current query, if there is one.
+        if !has_query {
+            continue;
+        }
+        req.id = Uuid::new_v4();
+        let resp = tokio::select! {
+            resp = client.search(req.clone()) => resp,
+            _ = tokio::signal::ctrl_c() => {
+                println!("{}", style("cancelled").yellow());
+                continue;
+            }
+        };
+        match resp {
+            Ok(mut resp) => {
+                cli.path_style.apply_to_search(&mut resp);
+                print_search_response(&resp)?;
+                hits = resp.hits;
+            }
+            Err(err) => println!("{}", style(format!("search failed: {err:#}")).red()),
+        }
+    }
+    let _ = editor.save_history(&history);
+    Ok(())
+}
+
+/// Hand `path` to the platform's default application, refusing paths that no longer exist.
+fn open_path(path: &str) {
+    if let Err(err) = core_types::open_target::open_path(path) {
+        println!("{}", style(format!("{err:#}")).red());
     }
 }
 
+/// Parse a query argument, underlining the offending token on failure.
+fn parse_query_arg(query: &str) -> Result<QueryExpr> {
+    parse_query(query).map_err(|e| query_error(query, e))
+}
+
+fn query_error(query: &str, e: ParseError) -> anyhow::Error {
+    eprintln!("  {query}");
+    eprintln!("  {}", style(e.marker(query)).red().bold());
+    anyhow::anyhow!("invalid query: {}", e.message)
+}
+
+fn print_plan_response(resp: &PlanResponse) -> Result<()> {
+    println!(
+        "{} {:?} (estimated cost {})",
+        style("Plan:").green(),
+        resp.chosen_mode,
+        resp.estimated_cost
+    );
+    for reason in &resp.reasons {
+        println!("  - {reason}");
+    }
+    Ok(())
+}
+
+fn print_get_doc_response(resp: &GetDocResponse) -> Result<()> {
+    let Some(hit) = &resp.meta else {
+        println!("{}", style("Not indexed.").yellow());
+        return Ok(());
+    };
+    println!(
+        "{} {} ({})",
+        style("Key:").green(),
+        hit.key.to_base64(),
+        hit.key
+    );
+    println!("  Name: {}", hit.name.as_deref().unwrap_or("<unknown>"));
+    println!("  Path: {}", hit.path.as_deref().unwrap_or("<unknown>"));
+    if let Some(size) = hit.size {
+        println!("  Size: {size}");
+    }
+    if let Some(modified) = hit.modified {
+        println!("  Modified: {modified}");
+    }
+    if let Some(content) = &resp.content {
+        println!("{}", style("Content:").green());
+        println!("{content}");
+    }
+    Ok(())
+}
+
 fn print_status_response(resp: &StatusResponse) -> Result<()> {
     println!("{}", style("Service Status:").green());
     println!("  Scheduler: {}", resp.scheduler_state);
@@ -278,10 +882,15 @@ fn print_status_response(resp: &StatusResponse) -> Result<()> {
         "  Served By: {}",
         resp.served_by.as_deref().unwrap_or("unknown")
     );
+    println!("  Readiness: {:?}", resp.readiness);
 
     if let Some(metrics) = &resp.metrics {
         println!("{}", style("Metrics:").yellow());
         println!("    Queue Depth: {}", metrics.queue_depth.unwrap_or(0));
+        if let Some(depths) = &resp.queue_depth_by_category {
+            let split: Vec<String> = depths.iter().map(|(c, n)| format!("{c} {n}")).collect();
+            println!("      By Category: {}", split.join(", "));
+        }
         println!(
             "    Active Workers: {}",
             metrics.active_workers.unwrap_or(0)
@@ -299,23 +908,59 @@ fn print_status_response(resp: &StatusResponse) -> Result<()> {
         style(format!("Volumes: {}", resp.volumes.len())).yellow()
     );
     for v in &resp.volumes {
+        let space = match (v.free_bytes, v.total_bytes) {
+            (Some(free), Some(total)) => {
+                format!(" | Free {:.1} / {:.1} GiB", gib(free), gib(total))
+            }
+            _ => String::new(),
+        };
         println!(
-            "    Vol {:02}: Indexed {} | Pending {}",
+            "    Vol {:02}: Indexed {} | Pending {}{space}",
             v.volume, v.indexed_files, v.pending_files
         );
     }
     Ok(())
 }
 
+fn print_volumes(resp: &ListVolumesResponse) -> Result<()> {
+    println!(
+        "{}",
+        style(format!("Volumes: {}", resp.volumes.len())).green()
+    );
+    for entry in &resp.volumes {
+        let v = &entry.volume;
+        let letters = if v.drive_letters.is_empty() {
+            "-".to_string()
+        } else {
+            v.drive_letters.join(" ")
+        };
+        let setting = match (entry.indexed, entry.content_indexed) {
+            (false, _) => style("not indexed").dim(),
+            (true, false) => style("metadata only").yellow(),
+            (true, true) => style("metadata + content").green(),
+        };
+        println!(
+            "    Vol {:02}: {letters:<6} {setting}  {}",
+            v.id, v.guid_path
+        );
+    }
+    Ok(())
+}
+
+fn gib(bytes: u64) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0 * 1024.0)
+}
+
 fn print_search_response(resp: &SearchResponse) -> Result<()> {
     println!("{}", style("Hits:").green());
     for (i, hit) in resp.hits.iter().enumerate() {
         println!(
-            "{:3}. {:<40} {:<6} score={:.3} path={}",
+            "{:3}. {:<40} {:<6} score={:.3} key={} path={}",
             i + 1,
             hit.name.as_deref().unwrap_or("<unknown>"),
             hit.ext.as_deref().unwrap_or(""),
             hit.score,
+            hit.key.to_base64(),
             hit.path.as_deref().unwrap_or("")
         );
     }
@@ -330,6 +975,43 @@ fn print_search_response(resp: &SearchResponse) -> Result<()> {
         ))
         .dim()
     );
+    if resp.timed_out {
+        println!(
+            "{}",
+            style("Timed out: results are partial; retry with a larger --timeout-ms").yellow()
+        );
+    }
+    if let Some(reason) = &resp.degraded {
+        println!("{}", style(format!("Degraded: {reason}")).yellow());
+    }
+    if !resp.suggestions.is_empty() {
+        println!(
+            "{}",
+            style(format!("Did you mean: {}?", resp.suggestions.join(", "))).yellow()
+        );
+    }
+    if let Some(cursor) = &resp.next_cursor {
+        println!("{}", style(format!("Next page: --cursor {cursor}")).dim());
+    }
+    if let Some(explanation) = &resp.explanation {
+        println!("{}", style("Explain:").green());
+        println!("{explanation}");
+    }
+    Ok(())
+}
+
+fn print_count_response(resp: &SearchResponse) -> Result<()> {
+    println!("{} {}", style("Matches:").green(), resp.total);
+    if resp.timed_out {
+        println!(
+            "{}",
+            style("Timed out: count is partial; retry with a larger --timeout-ms").yellow()
+        );
+    }
+    if let Some(explanation) = &resp.explanation {
+        println!("{}", style("Explain:").green());
+        println!("{explanation}");
+    }
     Ok(())
 }
 
@@ -357,6 +1039,21 @@ impl StubClient {
     async fn search(&self, req: SearchRequest) -> Result<SearchResponse> {
         stub_search(req).await
     }
+    async fn get_doc(&self, req: GetDocRequest) -> Result<GetDocResponse> {
+        Ok(GetDocResponse {
+            id: req.id,
+            meta: None,
+            content: None,
+        })
+    }
+    async fn plan(&self, req: PlanRequest) -> Result<PlanResponse> {
+        Ok(PlanResponse {
+            id: req.id,
+            chosen_mode: SearchMode::Hybrid,
+            reasons: vec!["stub".into()],
+            estimated_cost: 0,
+        })
+    }
     async fn reload_config(&self, _: ReloadConfigRequest) -> Result<ipc::ReloadConfigResponse> {
         Ok(ipc::ReloadConfigResponse {
             id: Uuid::new_v4(),
@@ -371,6 +1068,19 @@ impl StubClient {
             message: Some("stub".into()),
         })
     }
+    async fn reindex(&self, req: ReindexRequest) -> Result<ipc::ReindexResponse> {
+        Ok(ipc::ReindexResponse {
+            id: req.id,
+            accepted: true,
+            message: Some("stub".into()),
+        })
+    }
+    async fn list_volumes(&self, req: ListVolumesRequest) -> Result<ListVolumesResponse> {
+        Ok(ListVolumesResponse {
+            id: req.id,
+            volumes: Vec::new(),
+        })
+    }
 }
 
 #[cfg(not(windows))]
@@ -386,6 +1096,12 @@ async fn stub_search(req: SearchRequest) -> Result<SearchResponse> {
         truncated: false,
         took_ms: 0,
         served_by: Some("cli-linux-stub".into()),
+        next_cursor: None,
+        explanation: None,
+        timed_out: false,
+        degraded: None,
+        suggestions: Vec::new(),
+        groups: Vec::new(),
     })
 }
 
@@ -400,6 +1116,10 @@ async fn stub_status(req: StatusRequest) -> Result<StatusResponse> {
         volumes: vec![],
         last_index_commit_ts: None,
         scheduler_state: "stubbed".into(),
+        content_jobs_total: None,
+        content_jobs_remaining: None,
+        content_bytes_total: None,
+        content_bytes_remaining: None,
         metrics: Some(MetricsSnapshot {
             search_latency_ms_p50: None,
             search_latency_ms_p95: None,
@@ -409,7 +1129,12 @@ async fn stub_status(req: StatusRequest) -> Result<StatusResponse> {
             active_workers: Some(0),
             content_enqueued: Some(0),
             content_dropped: Some(0),
+            extract_p50_ms: None,
+            extract_p95_ms: None,
+            extract_by_kind: Vec::new(),
         }),
         served_by: Some("cli-linux-stub".into()),
+        readiness: ipc::Readiness::Ready,
+        queue_depth_by_category: None,
     })
 }
@@ -0,0 +1,113 @@
+//! `--path-style`: how file paths are written in `search`, `recent`, `saved run` and `get`
+//! output, in text and in JSON.
+//!
+//! Paths come back from the service as Windows paths. `native` prints them unchanged;
+//! `forward` swaps every `\` for `/`, which Windows still accepts and which tools that treat
+//! backslashes as escapes (shells, regexes, hand-rolled JSON) pass through untouched. JSON is
+//! always written by serde, so either style round-trips exactly.
+
+use clap::ValueEnum;
+use ipc::{GetDocResponse, SearchHit, SearchResponse};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum PathStyle {
+    /// Paths as indexed, e.g. `C:\Users\me\notes.txt`.
+    #[default]
+    Native,
+    /// `/` separators, e.g. `C:/Users/me/notes.txt`.
+    Forward,
+}
+
+impl PathStyle {
+    pub fn apply(self, path: &mut String) {
+        if self == PathStyle::Forward && path.contains('\\') {
+            *path = path.replace('\\', "/");
+        }
+    }
+
+    pub fn apply_to_hit(self, hit: &mut SearchHit) {
+        if let Some(path) = &mut hit.path {
+            self.apply(path);
+        }
+    }
+
+    /// Restyle every hit in `resp`, including the top hits of its groups.
+    pub fn apply_to_search(self, resp: &mut SearchResponse) {
+        let groups = resp.groups.iter_mut().flat_map(|g| g.top_hits.iter_mut());
+        for hit in resp.hits.iter_mut().chain(groups) {
+            self.apply_to_hit(hit);
+        }
+    }
+
+    pub fn apply_to_doc(self, resp: &mut GetDocResponse) {
+        if let Some(hit) = &mut resp.meta {
+            self.apply_to_hit(hit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core_types::DocKey;
+    use serde_json::Value;
+    use uuid::Uuid;
+
+    fn hit(path: &str) -> SearchHit {
+        SearchHit {
+            key: DocKey::from_parts(1, 42),
+            score: 1.0,
+            name: Some("b".into()),
+            path: Some(path.into()),
+            ext: None,
+            size: None,
+            modified: None,
+            snippet: None,
+        }
+    }
+
+    /// `value` after restyling, as `--json` prints it and a consumer parses it back.
+    fn json<T: serde::Serialize>(value: &T) -> Value {
+        serde_json::from_str(&serde_json::to_string_pretty(value).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn native_keeps_backslashes_and_forward_swaps_them() {
+        for (style, expected) in [
+            (PathStyle::Native, r"C:\a\b"),
+            (PathStyle::Forward, "C:/a/b"),
+        ] {
+            let mut search = SearchResponse {
+                id: Uuid::new_v4(),
+                hits: vec![hit(r"C:\a\b")],
+                total: 1,
+                truncated: false,
+                took_ms: 0,
+                served_by: None,
+                next_cursor: None,
+                explanation: None,
+                timed_out: false,
+                degraded: None,
+                suggestions: Vec::new(),
+                groups: Vec::new(),
+            };
+            style.apply_to_search(&mut search);
+            assert_eq!(json(&search)["hits"][0]["path"], expected, "{style:?}");
+
+            let mut doc = GetDocResponse {
+                id: Uuid::new_v4(),
+                meta: Some(hit(r"C:\a\b")),
+                content: Some(r"text mentioning C:\a\b".into()),
+            };
+            style.apply_to_doc(&mut doc);
+            let doc = json(&doc);
+            assert_eq!(doc["meta"]["path"], expected, "{style:?}");
+            // Only paths are restyled, not text that happens to contain one.
+            assert_eq!(doc["content"], r"text mentioning C:\a\b");
+        }
+
+        let mut unix = "/home/me/a".to_string();
+        PathStyle::Forward.apply(&mut unix);
+        assert_eq!(unix, "/home/me/a");
+    }
+}
@@ -0,0 +1,118 @@
+//! Line commands for `ultrasearch repl`, parsed apart from any terminal I/O.
+//!
+//! Anything not starting with `:` is a query. `j`/`k` page forward and back like a pager.
+
+use anyhow::{Result, anyhow, bail};
+use ipc::SearchMode;
+
+/// Help text printed by `:help`.
+pub const HELP: &str = "\
+  <query>          search, e.g. `report ext:pdf`
+  j / :next        next page
+  k / :prev        previous page
+  :open N          open hit N of the current page
+  :mode M          auto | name | content | hybrid
+  :limit N         hits per page
+  :help            this text
+  :q / :quit       leave (Ctrl+D also works)";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplCommand {
+    /// Run a new search from the first page.
+    Search(String),
+    NextPage,
+    PrevPage,
+    /// Open the hit at this 1-based position on the current page.
+    Open(usize),
+    Mode(SearchMode),
+    Limit(u32),
+    Help,
+    Quit,
+    /// Blank line; nothing to do.
+    Empty,
+}
+
+/// Parse one input line. Errors carry a message fit to print back to the user.
+pub fn parse_line(line: &str) -> Result<ReplCommand> {
+    let line = line.trim();
+    match line {
+        "" => return Ok(ReplCommand::Empty),
+        "j" => return Ok(ReplCommand::NextPage),
+        "k" => return Ok(ReplCommand::PrevPage),
+        _ => {}
+    }
+    let Some(command) = line.strip_prefix(':') else {
+        return Ok(ReplCommand::Search(line.to_string()));
+    };
+    let (name, arg) = match command.split_once(char::is_whitespace) {
+        Some((name, arg)) => (name, arg.trim()),
+        None => (command, ""),
+    };
+    let number = |what: &str| {
+        arg.parse::<u32>()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| anyhow!(":{name} needs a positive {what}, got `{arg}`"))
+    };
+    Ok(match name {
+        "next" | "n" => ReplCommand::NextPage,
+        "prev" | "p" => ReplCommand::PrevPage,
+        "open" | "o" => ReplCommand::Open(number("hit number")? as usize),
+        "limit" => ReplCommand::Limit(number("limit")?),
+        "mode" | "m" => ReplCommand::Mode(parse_mode(arg)?),
+        "help" | "h" | "?" => ReplCommand::Help,
+        "quit" | "q" | "exit" => ReplCommand::Quit,
+        _ => bail!("unknown command :{name} (try :help)"),
+    })
+}
+
+fn parse_mode(arg: &str) -> Result<SearchMode> {
+    Ok(match arg.to_ascii_lowercase().as_str() {
+        "auto" => SearchMode::Auto,
+        "name" | "names" => SearchMode::NameOnly,
+        "content" => SearchMode::Content,
+        "hybrid" => SearchMode::Hybrid,
+        _ => bail!(":mode takes auto, name, content or hybrid, got `{arg}`"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_parse_with_arguments() {
+        assert_eq!(parse_line(":open 3").unwrap(), ReplCommand::Open(3));
+        assert_eq!(
+            parse_line("  :limit   50 ").unwrap(),
+            ReplCommand::Limit(50)
+        );
+        assert_eq!(
+            parse_line(":mode content").unwrap(),
+            ReplCommand::Mode(SearchMode::Content)
+        );
+        assert_eq!(parse_line("j").unwrap(), ReplCommand::NextPage);
+        assert_eq!(parse_line(":prev").unwrap(), ReplCommand::PrevPage);
+        assert_eq!(parse_line(":q").unwrap(), ReplCommand::Quit);
+        assert_eq!(parse_line("   ").unwrap(), ReplCommand::Empty);
+    }
+
+    #[test]
+    fn other_lines_are_queries() {
+        assert_eq!(
+            parse_line(" report ext:pdf ").unwrap(),
+            ReplCommand::Search("report ext:pdf".into())
+        );
+        assert_eq!(parse_line("jk").unwrap(), ReplCommand::Search("jk".into()));
+    }
+
+    #[test]
+    fn bad_arguments_are_rejected() {
+        assert!(parse_line(":open").is_err());
+        assert!(parse_line(":open 0").is_err());
+        assert!(parse_line(":limit lots").is_err());
+        assert!(parse_line(":mode fast").is_err());
+        let err = parse_line(":frobnicate").unwrap_err();
+        assert!(err.to_string().contains(":help"), "{err}");
+    }
+}
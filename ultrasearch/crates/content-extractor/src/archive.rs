@@ -0,0 +1,409 @@
+//! Archive extractor: indexes text-like members of zip/tar/tar.gz files.
+//!
+//! Member text is concatenated under `== <entry name> ==` headers. The byte budget from
+//! `ExtractContext::max_bytes` applies to decompressed data across the whole archive, skipped
+//! members included, so a zip bomb can never inflate past it.
+
+use crate::{
+    ExtractContext, ExtractError, ExtractedContent, Extractor, enforce_limits_str,
+    is_probably_binary, is_text_ext, resolve_ext,
+};
+use core_types::DocKey;
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::time::Instant;
+
+/// Default cap on archive members inspected per file.
+pub const DEFAULT_MAX_ENTRIES: usize = 1024;
+
+/// Bytes decompressed between deadline checks.
+const READ_CHUNK: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+fn archive_kind(ctx: &ExtractContext) -> Option<ArchiveKind> {
+    match ctx.mime_hint {
+        Some("application/zip") => return Some(ArchiveKind::Zip),
+        Some("application/x-tar") => return Some(ArchiveKind::Tar),
+        // Gzip alone doesn't imply tar; let the extension decide.
+        Some("application/gzip") | None => {}
+        Some(_) => return None,
+    }
+    let lower = ctx.path.to_ascii_lowercase();
+    if lower.ends_with(".tar.gz") {
+        return Some(ArchiveKind::TarGz);
+    }
+    match resolve_ext(ctx)?.as_str() {
+        "zip" => Some(ArchiveKind::Zip),
+        "tar" => Some(ArchiveKind::Tar),
+        "tgz" => Some(ArchiveKind::TarGz),
+        _ => None,
+    }
+}
+
+/// Extracts text from small text-like members of zip and tar archives.
+pub struct ArchiveExtractor {
+    max_entries: usize,
+}
+
+impl ArchiveExtractor {
+    pub fn new() -> Self {
+        Self {
+            max_entries: DEFAULT_MAX_ENTRIES,
+        }
+    }
+
+    /// Override the number of members inspected before giving up (and flagging truncation).
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries.max(1);
+        self
+    }
+
+    fn extract_zip(&self, file: File, acc: &mut Accumulator) -> Result<(), ExtractError> {
+        let mut zip =
+            zip::ZipArchive::new(file).map_err(|e| ExtractError::Failed(e.to_string()))?;
+        if zip.len() > self.max_entries {
+            acc.truncated = true;
+        }
+        for i in 0..zip.len().min(self.max_entries) {
+            let encrypted = zip
+                .by_index_raw(i)
+                .map_err(|e| ExtractError::Failed(e.to_string()))?
+                .encrypted();
+            if encrypted {
+                return Err(ExtractError::Unsupported("encrypted archive".into()));
+            }
+            let entry = zip
+                .by_index(i)
+                .map_err(|e| ExtractError::Failed(e.to_string()))?;
+            if !entry.is_file() {
+                continue;
+            }
+            let name = entry.name().to_string();
+            let size = entry.size();
+            // A skipped zip member is never decompressed: the next one is found by offset.
+            if !acc.push_entry(&name, size, entry, false)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn extract_tar<R: Read>(&self, reader: R, acc: &mut Accumulator) -> Result<(), ExtractError> {
+        let mut tar = tar::Archive::new(reader);
+        let entries = tar
+            .entries()
+            .map_err(|e| ExtractError::Failed(e.to_string()))?;
+        for (idx, entry) in entries.enumerate() {
+            if idx >= self.max_entries {
+                acc.truncated = true;
+                break;
+            }
+            let entry = entry.map_err(|e| ExtractError::Failed(e.to_string()))?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let name = entry
+                .path()
+                .map_err(|e| ExtractError::Failed(e.to_string()))?
+                .to_string_lossy()
+                .into_owned();
+            let size = entry.size();
+            // A tar stream has to be read through a member to reach the next one.
+            if !acc.push_entry(&name, size, entry, true)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for ArchiveExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Extractor for ArchiveExtractor {
+    fn name(&self) -> &'static str {
+        "archive"
+    }
+
+    fn supports(&self, ctx: &ExtractContext) -> bool {
+        archive_kind(ctx).is_some()
+    }
+
+    fn extract(&self, ctx: &ExtractContext, key: DocKey) -> Result<ExtractedContent, ExtractError> {
+        let kind = archive_kind(ctx).ok_or_else(|| {
+            ExtractError::Unsupported(resolve_ext(ctx).unwrap_or_else(|| "unknown".into()))
+        })?;
+        let file =
+            File::open(Path::new(ctx.path)).map_err(|e| ExtractError::Failed(e.to_string()))?;
+
+        let mut acc = Accumulator::new(ctx.max_bytes, ctx.deadline);
+        match kind {
+            ArchiveKind::Zip => self.extract_zip(file, &mut acc)?,
+            ArchiveKind::Tar => self.extract_tar(file, &mut acc)?,
+            ArchiveKind::TarGz => self.extract_tar(GzDecoder::new(file), &mut acc)?,
+        }
+
+        let (text, truncated, used) = enforce_limits_str(&acc.text, ctx);
+        Ok(ExtractedContent {
+            key,
+            text,
+            lang: None,
+            truncated: truncated || acc.truncated,
+            content_lang: None,
+            bytes_processed: used,
+        })
+    }
+}
+
+/// Collects member text while charging every decompressed byte against one shared budget.
+struct Accumulator {
+    text: String,
+    remaining: usize,
+    truncated: bool,
+    /// Checked before each member and every [`READ_CHUNK`] bytes read.
+    deadline: Option<Instant>,
+}
+
+impl Accumulator {
+    fn new(max_bytes: usize, deadline: Option<Instant>) -> Self {
+        Self {
+            text: String::new(),
+            remaining: max_bytes,
+            truncated: false,
+            deadline,
+        }
+    }
+
+    /// Append one member of `size` bytes if it looks like text. A skipped member is charged
+    /// its size too, and read through when `drain_skipped` (streams where that is the only way
+    /// past it). Returns `false` once the budget is spent.
+    fn push_entry<R: Read>(
+        &mut self,
+        name: &str,
+        size: u64,
+        reader: R,
+        drain_skipped: bool,
+    ) -> Result<bool, ExtractError> {
+        self.check_deadline()?;
+        let ext = Path::new(name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        if !ext.as_deref().is_some_and(is_text_ext) {
+            if size > self.remaining as u64 {
+                self.remaining = 0;
+                self.truncated = true;
+                return Ok(false);
+            }
+            self.remaining -= size as usize;
+            if drain_skipped {
+                self.read_chunks(reader, size, |_| {})?;
+            }
+            return Ok(self.remaining > 0);
+        }
+
+        let mut data = Vec::new();
+        // Read one byte past the budget to learn whether the member was cut short.
+        self.read_chunks(reader, self.remaining as u64 + 1, |chunk| {
+            data.extend_from_slice(chunk)
+        })?;
+        if data.len() > self.remaining {
+            data.truncate(self.remaining);
+            self.truncated = true;
+        }
+        self.remaining -= data.len();
+
+        if !is_probably_binary(&data) {
+            self.text.push_str("== ");
+            self.text.push_str(name);
+            self.text.push_str(" ==\n");
+            self.text.push_str(&String::from_utf8_lossy(&data));
+            self.text.push('\n');
+        }
+
+        Ok(self.remaining > 0)
+    }
+
+    fn check_deadline(&self) -> Result<(), ExtractError> {
+        if self.deadline.is_some_and(|d| Instant::now() >= d) {
+            return Err(ExtractError::timeout());
+        }
+        Ok(())
+    }
+
+    /// Feed up to `limit` bytes of `reader` to `sink`, checking the deadline between chunks.
+    fn read_chunks<R: Read>(
+        &self,
+        reader: R,
+        limit: u64,
+        mut sink: impl FnMut(&[u8]),
+    ) -> Result<(), ExtractError> {
+        let mut reader = reader.take(limit);
+        let mut buf = vec![0u8; READ_CHUNK];
+        loop {
+            let n = match reader.read(&mut buf) {
+                Ok(0) => return Ok(()),
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(ExtractError::Failed(e.to_string())),
+            };
+            sink(&buf[..n]);
+            self.check_deadline()?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/two_texts.zip");
+
+    fn ctx(path: &str, max_bytes: usize) -> ExtractContext<'_> {
+        ExtractContext {
+            path,
+            max_bytes,
+            max_chars: 100_000,
+            ext_hint: None,
+            mime_hint: None,
+            deadline: None,
+        }
+    }
+
+    #[test]
+    fn supports_archive_extensions() {
+        let ex = ArchiveExtractor::new();
+        assert!(ex.supports(&ctx("a.zip", 10)));
+        assert!(ex.supports(&ctx("a.tar", 10)));
+        assert!(ex.supports(&ctx("a.TAR.GZ", 10)));
+        assert!(ex.supports(&ctx("a.tgz", 10)));
+        assert!(!ex.supports(&ctx("a.gz", 10)));
+        assert!(!ex.supports(&ctx("a.txt", 10)));
+    }
+
+    #[test]
+    fn zip_members_are_concatenated_with_headers() {
+        let out = ArchiveExtractor::new()
+            .extract(&ctx(FIXTURE, 64 * 1024), DocKey::from_parts(1, 1))
+            .unwrap();
+        assert!(out.text.contains("== alpha.txt =="));
+        assert!(out.text.contains("alpha apples"));
+        assert!(out.text.contains("== beta.txt =="));
+        assert!(out.text.contains("beta bananas"));
+        assert!(!out.truncated);
+    }
+
+    #[test]
+    fn byte_budget_spans_whole_archive() {
+        let out = ArchiveExtractor::new()
+            .extract(&ctx(FIXTURE, 8), DocKey::from_parts(1, 1))
+            .unwrap();
+        assert!(out.truncated);
+        assert!(out.bytes_processed <= 8);
+        assert!(!out.text.contains("beta"));
+    }
+
+    #[test]
+    fn entry_cap_sets_truncated() {
+        let out = ArchiveExtractor::new()
+            .with_max_entries(1)
+            .extract(&ctx(FIXTURE, 64 * 1024), DocKey::from_parts(1, 1))
+            .unwrap();
+        assert!(out.truncated);
+        assert!(out.text.contains("alpha"));
+        assert!(!out.text.contains("beta"));
+    }
+
+    /// A tar.gz holding `members` as `(name, body)`.
+    fn tar_gz(path: &Path, members: &[(&str, &[u8])]) {
+        let gz =
+            flate2::write::GzEncoder::new(File::create(path).unwrap(), flate2::Compression::fast());
+        let mut builder = tar::Builder::new(gz);
+        for (name, body) in members {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(body.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, *body).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn skipped_members_are_charged_against_the_budget() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bomb.tar.gz");
+        let filler = vec![0u8; 4 * 1024 * 1024];
+        tar_gz(
+            &path,
+            &[
+                ("first.txt", b"first words"),
+                ("filler.bin", &filler),
+                ("after.txt", b"after words"),
+            ],
+        );
+
+        let out = ArchiveExtractor::new()
+            .extract(
+                &ctx(path.to_str().unwrap(), 64 * 1024),
+                DocKey::from_parts(1, 3),
+            )
+            .unwrap();
+        assert!(out.truncated);
+        assert!(out.text.contains("first words"));
+        assert!(!out.text.contains("after words"));
+
+        // Within budget, the same member is skipped and the walk goes on.
+        let out = ArchiveExtractor::new()
+            .extract(
+                &ctx(path.to_str().unwrap(), 8 * 1024 * 1024),
+                DocKey::from_parts(1, 3),
+            )
+            .unwrap();
+        assert!(!out.truncated);
+        assert!(out.text.contains("after words"));
+    }
+
+    #[test]
+    fn deadline_stops_reading_inside_a_member() {
+        let member = vec![b'a'; 1024 * 1024];
+        let mut acc = Accumulator::new(member.len(), None);
+        let mut read = 0;
+        acc.read_chunks(&member[..], member.len() as u64, |c| read += c.len())
+            .unwrap();
+        assert_eq!(read, member.len());
+
+        // Expires after the member was entered: the first chunk is the last one read.
+        acc.deadline = Some(Instant::now());
+        let mut read = 0;
+        let err = acc
+            .read_chunks(&member[..], member.len() as u64, |c| read += c.len())
+            .unwrap_err();
+        assert!(err.is_timeout());
+        assert_eq!(read, READ_CHUNK);
+    }
+
+    #[test]
+    fn tar_gz_members_are_extracted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bundle.tar.gz");
+        tar_gz(&path, &[("gamma.txt", b"gamma grapes")]);
+
+        let out = ArchiveExtractor::new()
+            .extract(&ctx(path.to_str().unwrap(), 1024), DocKey::from_parts(1, 2))
+            .unwrap();
+        assert!(out.text.contains("== gamma.txt =="));
+        assert!(out.text.contains("gamma grapes"));
+    }
+}
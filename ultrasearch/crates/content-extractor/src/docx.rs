@@ -0,0 +1,228 @@
+//! Office Open XML extractor: text from `.docx`, `.xlsx` and `.pptx` without IFilter.
+//!
+//! The package is a zip; only the XML parts that carry document text are read (body, headers,
+//! footers and notes for Word, slides for PowerPoint, shared and inline strings for Excel).
+//! Media, embeddings and other binary parts are never decompressed. As with archives, the
+//! `ExtractContext::max_bytes` budget applies to decompressed XML across all parts.
+
+use crate::{
+    ExtractContext, ExtractError, ExtractedContent, Extractor, enforce_limits_str, resolve_ext,
+};
+use core_types::DocKey;
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OoxmlKind {
+    Word,
+    Excel,
+    PowerPoint,
+}
+
+fn ooxml_kind(ctx: &ExtractContext) -> Option<OoxmlKind> {
+    match resolve_ext(ctx)?.as_str() {
+        "docx" | "docm" | "dotx" | "dotm" => Some(OoxmlKind::Word),
+        "xlsx" | "xlsm" | "xltx" | "xltm" => Some(OoxmlKind::Excel),
+        "pptx" | "pptm" | "potx" | "potm" | "ppsx" => Some(OoxmlKind::PowerPoint),
+        _ => None,
+    }
+}
+
+/// Sort key for a text-bearing part, or `None` for parts that are skipped. Body parts come
+/// first; numbered parts (`slide2.xml`, `sheet10.xml`) sort numerically.
+fn part_order(kind: OoxmlKind, name: &str) -> Option<(u8, u32)> {
+    let numbered = |prefix: &str| -> Option<u32> {
+        let rest = name.strip_prefix(prefix)?.strip_suffix(".xml")?;
+        if rest.is_empty() {
+            Some(0)
+        } else {
+            rest.parse().ok()
+        }
+    };
+    match kind {
+        OoxmlKind::Word => match name {
+            "word/document.xml" => Some((0, 0)),
+            "word/footnotes.xml" => Some((3, 0)),
+            "word/endnotes.xml" => Some((4, 0)),
+            "word/comments.xml" => Some((5, 0)),
+            _ => numbered("word/header")
+                .map(|n| (1, n))
+                .or_else(|| numbered("word/footer").map(|n| (2, n))),
+        },
+        OoxmlKind::PowerPoint => numbered("ppt/slides/slide")
+            .map(|n| (0, n))
+            .or_else(|| numbered("ppt/notesSlides/notesSlide").map(|n| (1, n))),
+        OoxmlKind::Excel => match name {
+            "xl/sharedStrings.xml" => Some((0, 0)),
+            _ => numbered("xl/worksheets/sheet").map(|n| (1, n)),
+        },
+    }
+}
+
+/// Extractor for Word, Excel and PowerPoint OOXML packages.
+pub struct DocxExtractor;
+
+impl Extractor for DocxExtractor {
+    fn name(&self) -> &'static str {
+        "ooxml"
+    }
+
+    fn supports(&self, ctx: &ExtractContext) -> bool {
+        ooxml_kind(ctx).is_some()
+    }
+
+    fn extract(&self, ctx: &ExtractContext, key: DocKey) -> Result<ExtractedContent, ExtractError> {
+        let kind = ooxml_kind(ctx).ok_or_else(|| {
+            ExtractError::Unsupported(resolve_ext(ctx).unwrap_or_else(|| "unknown".into()))
+        })?;
+        let file =
+            File::open(Path::new(ctx.path)).map_err(|e| ExtractError::Failed(e.to_string()))?;
+        let mut zip =
+            zip::ZipArchive::new(file).map_err(|e| ExtractError::Failed(e.to_string()))?;
+
+        let mut parts: Vec<((u8, u32), String)> = zip
+            .file_names()
+            .filter_map(|name| part_order(kind, name).map(|order| (order, name.to_string())))
+            .collect();
+        parts.sort();
+        if parts.is_empty() {
+            return Err(ExtractError::Unsupported("no text parts in package".into()));
+        }
+
+        let mut text = String::new();
+        let mut remaining = ctx.max_bytes;
+        let mut truncated = false;
+        for (_, name) in parts {
+            if ctx.expired() {
+                return Err(ExtractError::timeout());
+            }
+            if remaining == 0 {
+                truncated = true;
+                break;
+            }
+            let part = zip
+                .by_name(&name)
+                .map_err(|e| ExtractError::Failed(e.to_string()))?;
+            if part.encrypted() {
+                return Err(ExtractError::Unsupported("encrypted document".into()));
+            }
+            let mut xml = Vec::new();
+            // Read one byte past the budget to learn whether the part was cut short.
+            part.take(remaining as u64 + 1)
+                .read_to_end(&mut xml)
+                .map_err(|e| ExtractError::Failed(e.to_string()))?;
+            if xml.len() > remaining {
+                xml.truncate(remaining);
+                truncated = true;
+            }
+            remaining -= xml.len();
+            // A part cut mid-element still yields the text before the cut.
+            xml_text(&xml, &mut text);
+        }
+
+        let (text, limited, used) = enforce_limits_str(text.trim(), ctx);
+        Ok(ExtractedContent {
+            key,
+            text,
+            lang: None,
+            truncated: truncated || limited,
+            content_lang: None,
+            bytes_processed: used,
+        })
+    }
+}
+
+/// Append the text runs of one OOXML part to `out`. Runs live in `t` elements in every
+/// format (`w:t`, `a:t`, spreadsheet `t`); paragraphs, string items and rows end a line.
+fn xml_text(xml: &[u8], out: &mut String) {
+    let mut reader = Reader::from_reader(xml);
+    let mut in_text = false;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => in_text = e.local_name().as_ref() == b"t",
+            Ok(Event::Empty(e)) => match e.local_name().as_ref() {
+                b"tab" => out.push('\t'),
+                b"br" | b"cr" => out.push('\n'),
+                _ => {}
+            },
+            Ok(Event::End(e)) => match e.local_name().as_ref() {
+                b"t" => in_text = false,
+                b"p" | b"si" | b"row" if !out.is_empty() && !out.ends_with('\n') => {
+                    out.push('\n');
+                }
+                _ => {}
+            },
+            Ok(Event::Text(t)) if in_text => {
+                if let Ok(s) = t.unescape() {
+                    out.push_str(&s);
+                }
+            }
+            Ok(Event::CData(c)) if in_text => out.push_str(&String::from_utf8_lossy(&c)),
+            Ok(Event::Eof) | Err(_) => break,
+            Ok(_) => {}
+        }
+    }
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tiny.docx");
+
+    fn ctx(path: &str, max_bytes: usize) -> ExtractContext<'_> {
+        ExtractContext {
+            path,
+            max_bytes,
+            max_chars: 100_000,
+            ext_hint: None,
+            mime_hint: None,
+            deadline: None,
+        }
+    }
+
+    #[test]
+    fn docx_body_text_is_extracted() {
+        let out = DocxExtractor
+            .extract(&ctx(FIXTURE, 64 * 1024), DocKey::from_parts(1, 1))
+            .unwrap();
+        assert_eq!(
+            out.text,
+            "Quarterly report\nRevenue grew for Alpha & Beta\tin Q3."
+        );
+        assert!(!out.truncated);
+    }
+
+    #[test]
+    fn byte_budget_truncates() {
+        let out = DocxExtractor
+            .extract(&ctx(FIXTURE, 300), DocKey::from_parts(1, 1))
+            .unwrap();
+        assert!(out.truncated);
+        assert!(out.text.starts_with("Quarterly report"), "{:?}", out.text);
+        assert!(!out.text.contains("Q3"));
+    }
+
+    #[test]
+    fn only_text_parts_are_read() {
+        assert!(DocxExtractor.supports(&ctx("deck.PPTX", 10)));
+        assert!(!DocxExtractor.supports(&ctx("legacy.doc", 10)));
+        let word = OoxmlKind::Word;
+        assert_eq!(part_order(word, "word/document.xml"), Some((0, 0)));
+        assert_eq!(part_order(word, "word/header2.xml"), Some((1, 2)));
+        assert_eq!(part_order(word, "word/media/image1.png"), None);
+        assert_eq!(part_order(word, "word/embeddings/sheet.xlsx"), None);
+        let slides = OoxmlKind::PowerPoint;
+        assert!(
+            part_order(slides, "ppt/slides/slide2.xml")
+                < part_order(slides, "ppt/slides/slide10.xml")
+        );
+        assert_eq!(part_order(slides, "ppt/slides/_rels/slide1.xml.rels"), None);
+    }
+}
@@ -0,0 +1,163 @@
+//! HTML extractor: indexes visible text instead of raw markup.
+//!
+//! Scripts, styles and other non-rendered subtrees are dropped, entities are decoded by the
+//! parser, and whitespace is collapsed. The document `<title>` (if any) leads the output so
+//! snippets open with it.
+
+use crate::{
+    ExtractContext, ExtractError, ExtractedContent, Extractor, enforce_limits_str, resolve_ext,
+};
+use core_types::DocKey;
+use scraper::{Html, Node, Selector};
+use std::fs;
+use std::path::Path;
+
+/// Subtrees whose text is never rendered.
+const SKIPPED: &[&str] = &[
+    "head", "script", "style", "noscript", "template", "svg", "math", "iframe", "object",
+];
+
+/// Elements that flow inline; everything else separates words.
+const INLINE: &[&str] = &[
+    "a", "abbr", "b", "bdi", "bdo", "cite", "code", "data", "dfn", "em", "font", "i", "kbd",
+    "mark", "q", "s", "samp", "small", "span", "strong", "sub", "sup", "time", "u", "var",
+];
+
+/// Extractor for `.html`/`.htm` (or anything sniffed as `text/html`).
+pub struct HtmlExtractor;
+
+impl Extractor for HtmlExtractor {
+    fn name(&self) -> &'static str {
+        "html"
+    }
+
+    fn supports(&self, ctx: &ExtractContext) -> bool {
+        match ctx.mime_hint {
+            Some(m) => m == "text/html",
+            None => resolve_ext(ctx).is_some_and(|ext| matches!(ext.as_str(), "html" | "htm")),
+        }
+    }
+
+    fn extract(&self, ctx: &ExtractContext, key: DocKey) -> Result<ExtractedContent, ExtractError> {
+        let path = Path::new(ctx.path);
+        let meta = fs::metadata(path).map_err(|e| ExtractError::Failed(e.to_string()))?;
+        let max_bytes = ctx.max_bytes as u64;
+        if meta.len() > max_bytes {
+            return Err(ExtractError::FileTooLarge {
+                bytes: meta.len(),
+                max_bytes,
+            });
+        }
+
+        let data = fs::read(path).map_err(|e| ExtractError::Failed(e.to_string()))?;
+        let text_raw = html_to_text(&String::from_utf8_lossy(&data));
+        let (text, truncated, used_bytes) = enforce_limits_str(&text_raw, ctx);
+
+        Ok(ExtractedContent {
+            key,
+            text,
+            lang: None,
+            truncated,
+            content_lang: None,
+            bytes_processed: used_bytes,
+        })
+    }
+}
+
+/// Render an HTML document to its visible text, title first.
+pub fn html_to_text(source: &str) -> String {
+    let doc = Html::parse_document(source);
+    let mut raw = String::with_capacity(source.len() / 2);
+
+    if let Ok(sel) = Selector::parse("title")
+        && let Some(title) = doc.select(&sel).next()
+    {
+        raw.extend(title.text());
+        raw.push('\n');
+    }
+
+    // Iterative walk: deeply nested input must not exhaust the stack. `true` marks the
+    // closing edge of an element.
+    let mut stack = vec![(doc.tree.root(), false)];
+    while let Some((node, closing)) = stack.pop() {
+        match node.value() {
+            Node::Text(t) if !closing => raw.push_str(t),
+            Node::Element(el) => {
+                let name = el.name();
+                if SKIPPED.contains(&name) {
+                    continue;
+                }
+                if !INLINE.contains(&name) {
+                    raw.push(' ');
+                }
+                if !closing {
+                    stack.push((node, true));
+                    stack.extend(node.children().rev().map(|c| (c, false)));
+                }
+            }
+            Node::Document | Node::Fragment if !closing => {
+                stack.extend(node.children().rev().map(|c| (c, false)));
+            }
+            _ => {}
+        }
+    }
+
+    raw.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx(path: &str) -> ExtractContext<'_> {
+        ExtractContext {
+            path,
+            max_bytes: 4096,
+            max_chars: 4096,
+            ext_hint: None,
+            mime_hint: None,
+            deadline: None,
+        }
+    }
+
+    #[test]
+    fn strips_tags_and_scripts() {
+        assert_eq!(
+            html_to_text("<p>Hello <b>world</b></p><script>x</script>"),
+            "Hello world"
+        );
+    }
+
+    #[test]
+    fn drops_styles_decodes_entities_and_separates_blocks() {
+        let html = "<html><head><style>p{color:red}</style></head>\
+                    <body><p>fish &amp; chips</p><div>tea&nbsp;time</div></body></html>";
+        assert_eq!(html_to_text(html), "fish & chips tea time");
+    }
+
+    #[test]
+    fn title_leads_output() {
+        let html = "<html><head><title>Quarterly Report</title></head><body>numbers</body></html>";
+        assert_eq!(html_to_text(html), "Quarterly Report numbers");
+    }
+
+    #[test]
+    fn supports_html_extensions() {
+        assert!(HtmlExtractor.supports(&ctx("index.html")));
+        assert!(HtmlExtractor.supports(&ctx("INDEX.HTM")));
+        assert!(!HtmlExtractor.supports(&ctx("notes.txt")));
+    }
+
+    #[test]
+    fn extract_honors_char_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("page.html");
+        std::fs::write(&path, "<p>abcdef</p>").unwrap();
+        let mut c = ctx(path.to_str().unwrap());
+        c.max_chars = 3;
+
+        let out = HtmlExtractor.extract(&c, DocKey::from_parts(1, 1)).unwrap();
+        assert_eq!(out.text, "abc");
+        assert!(out.truncated);
+    }
+}
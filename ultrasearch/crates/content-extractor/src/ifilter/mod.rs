@@ -4,23 +4,133 @@ use crate::{ExtractContext, ExtractError, ExtractedContent, Extractor, enforce_l
 use anyhow::Result;
 use core_types::DocKey;
 use std::ffi::c_void;
+use std::panic::{AssertUnwindSafe, catch_unwind};
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::{self, JoinHandle};
 use windows::Win32::Foundation::S_OK;
 use windows::Win32::Storage::IndexServer::{
     CHUNK_TEXT, FILTER_E_END_OF_CHUNKS, FILTER_E_NO_MORE_TEXT, IFilter, LoadIFilter, STAT_CHUNK,
 };
-use windows::Win32::System::Com::{CoInitialize, CoUninitialize};
+use windows::Win32::System::Com::{COINIT_APARTMENTTHREADED, CoInitializeEx, CoUninitialize};
 use windows::core::{HSTRING, Interface, PCWSTR, PWSTR};
 
-// TODO: Properly manage COM initialization. CoInitialize is thread-local.
-// A robust solution might use a dedicated STA thread pool for IFilters.
-// For this shim, we attempt to init and ignore if already inited (RPC_E_CHANGED_MODE).
+/// Number of STA threads in the shared IFilter pool.
+pub const DEFAULT_STA_THREADS: usize = 2;
 
-pub struct IFilterExtractor;
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Dedicated single-threaded-apartment workers for IFilter calls.
+///
+/// Each thread initializes COM once as STA and then serves jobs from a shared channel, so
+/// extraction never pays per-call init/uninit or trips over a caller thread that is already MTA.
+pub struct StaPool {
+    sender: Option<Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+    com_inits: Arc<AtomicUsize>,
+}
+
+impl StaPool {
+    pub fn new(threads: usize) -> Self {
+        let (tx, rx) = mpsc::channel::<Job>();
+        let rx = Arc::new(Mutex::new(rx));
+        let com_inits = Arc::new(AtomicUsize::new(0));
+        let workers = (0..threads.max(1))
+            .map(|i| {
+                let rx = Arc::clone(&rx);
+                let inits = Arc::clone(&com_inits);
+                thread::Builder::new()
+                    .name(format!("ifilter-sta-{i}"))
+                    .spawn(move || sta_worker(rx, inits))
+                    .expect("spawn IFilter STA worker")
+            })
+            .collect();
+        Self {
+            sender: Some(tx),
+            workers,
+            com_inits,
+        }
+    }
+
+    /// Process-wide pool shared by every `IFilterExtractor::new()`.
+    pub fn global() -> Arc<StaPool> {
+        static POOL: OnceLock<Arc<StaPool>> = OnceLock::new();
+        Arc::clone(POOL.get_or_init(|| Arc::new(StaPool::new(DEFAULT_STA_THREADS))))
+    }
+
+    /// Run `f` on an STA worker and block until it returns.
+    pub fn run<T, F>(&self, f: F) -> Result<T, ExtractError>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let (reply_tx, reply_rx) = mpsc::channel();
+        let job: Job = Box::new(move || {
+            let _ = reply_tx.send(f());
+        });
+        self.sender
+            .as_ref()
+            .ok_or_else(|| ExtractError::Failed("IFilter STA pool is shut down".into()))?
+            .send(job)
+            .map_err(|_| ExtractError::Failed("IFilter STA pool is shut down".into()))?;
+        reply_rx
+            .recv()
+            .map_err(|_| ExtractError::Failed("IFilter STA job panicked".into()))
+    }
+
+    /// Number of worker threads that successfully initialized COM.
+    pub fn com_inits(&self) -> usize {
+        self.com_inits.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for StaPool {
+    fn drop(&mut self) {
+        // Closing the channel ends each worker loop.
+        self.sender.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn sta_worker(rx: Arc<Mutex<Receiver<Job>>>, inits: Arc<AtomicUsize>) {
+    // SAFETY: called once at thread start; balanced by CoUninitialize below.
+    let initialized = unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) }.is_ok();
+    if initialized {
+        inits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    loop {
+        let job = match rx.lock() {
+            Ok(guard) => guard.recv(),
+            Err(_) => break,
+        };
+        let Ok(job) = job else { break };
+        // A panicking filter must not take the worker (and its apartment) down with it.
+        let _ = catch_unwind(AssertUnwindSafe(job));
+    }
+
+    if initialized {
+        // SAFETY: matches the successful CoInitializeEx on this thread.
+        unsafe { CoUninitialize() };
+    }
+}
+
+pub struct IFilterExtractor {
+    pool: Arc<StaPool>,
+}
 
 impl IFilterExtractor {
     pub fn new() -> Self {
-        Self
+        Self::with_pool(StaPool::global())
+    }
+
+    /// Use a specific STA pool instead of the shared one.
+    pub fn with_pool(pool: Arc<StaPool>) -> Self {
+        Self { pool }
     }
 }
 
@@ -45,132 +155,162 @@ impl Extractor for IFilterExtractor {
     }
 
     fn extract(&self, ctx: &ExtractContext, key: DocKey) -> Result<ExtractedContent, ExtractError> {
-        let path = Path::new(ctx.path);
-        let path_hstring = HSTRING::from(path.as_os_str());
-
-        unsafe {
-            // Attempt init; track whether we should uninitialize.
-            let coinit_hr = CoInitialize(None);
-            let should_uninit = coinit_hr.is_ok();
-            // Defer uninit? In a thread pool, we might init once per thread.
-            // Here we are likely in a rayon thread.
-            // Ideally we should use a scope guard or just assume the thread is initialized by the runtime wrapper.
-            // But rayon threads are generic.
-            // Let's defer uninit for correctness in this scope.
-            // Actually, excessive init/uninit is slow.
-
-            // Scope guard for CoUninitialize
-            struct CoGuard(bool);
-            impl Drop for CoGuard {
-                fn drop(&mut self) {
-                    if self.0 {
-                        unsafe {
-                            CoUninitialize();
-                        }
-                    }
-                }
-            }
-            let _guard = CoGuard(should_uninit);
-
-            let mut raw_filter: *mut c_void = std::ptr::null_mut();
-            LoadIFilter(
-                PCWSTR(path_hstring.as_ptr()),
-                None,
-                &mut raw_filter as *mut *mut _,
-            )
-            .map_err(|e| ExtractError::Failed(format!("LoadIFilter failed: {e}")))?;
-
-            if raw_filter.is_null() {
-                return Err(ExtractError::Failed(
-                    "LoadIFilter returned null filter".into(),
-                ));
-            }
+        let path = ctx.path.to_owned();
+        let (max_bytes, max_chars, deadline) = (ctx.max_bytes, ctx.max_chars, ctx.deadline);
+        self.pool.run(move || {
+            let ctx = ExtractContext {
+                path: &path,
+                max_bytes,
+                max_chars,
+                ext_hint: None,
+                mime_hint: None,
+                deadline,
+            };
+            // SAFETY: runs on an STA worker with COM initialized.
+            unsafe { extract_on_sta(&ctx, key) }
+        })?
+    }
+}
+
+/// Pull text chunks through the registered IFilter. Caller must be on an STA thread.
+unsafe fn extract_on_sta(
+    ctx: &ExtractContext,
+    key: DocKey,
+) -> Result<ExtractedContent, ExtractError> {
+    let path = Path::new(ctx.path);
+    let path_hstring = HSTRING::from(path.as_os_str());
+
+    unsafe {
+        let mut raw_filter: *mut c_void = std::ptr::null_mut();
+        LoadIFilter(
+            PCWSTR(path_hstring.as_ptr()),
+            None,
+            &mut raw_filter as *mut *mut _,
+        )
+        .map_err(|e| ExtractError::Failed(format!("LoadIFilter failed: {e}")))?;
+
+        if raw_filter.is_null() {
+            return Err(ExtractError::Failed(
+                "LoadIFilter returned null filter".into(),
+            ));
+        }
+
+        // SAFETY: LoadIFilter populated raw_filter on success.
+        let filter: IFilter = IFilter::from_raw(raw_filter.cast());
 
-            // SAFETY: LoadIFilter populated raw_filter on success.
-            let filter: IFilter = IFilter::from_raw(raw_filter.cast());
-
-            // Initialize filter (canonicalize whitespace and paragraphs, index attributes).
-            let init_flags: u32 =
-                (windows::Win32::Storage::IndexServer::IFILTER_INIT_CANON_PARAGRAPHS.0
-                    | windows::Win32::Storage::IndexServer::IFILTER_INIT_CANON_SPACES.0
-                    | windows::Win32::Storage::IndexServer::IFILTER_INIT_APPLY_INDEX_ATTRIBUTES.0
-                    | windows::Win32::Storage::IndexServer::IFILTER_INIT_INDEXING_ONLY.0
-                    | windows::Win32::Storage::IndexServer::IFILTER_INIT_SEARCH_LINKS.0)
-                    as u32;
-            let mut init_flags_out: u32 = 0;
-            let init_hr = filter.Init(init_flags, &[], &mut init_flags_out);
-            if init_hr != S_OK.0 {
+        // Initialize filter (canonicalize whitespace and paragraphs, index attributes).
+        let init_flags: u32 = (windows::Win32::Storage::IndexServer::IFILTER_INIT_CANON_PARAGRAPHS
+            .0
+            | windows::Win32::Storage::IndexServer::IFILTER_INIT_CANON_SPACES.0
+            | windows::Win32::Storage::IndexServer::IFILTER_INIT_APPLY_INDEX_ATTRIBUTES.0
+            | windows::Win32::Storage::IndexServer::IFILTER_INIT_INDEXING_ONLY.0
+            | windows::Win32::Storage::IndexServer::IFILTER_INIT_SEARCH_LINKS.0)
+            as u32;
+        let mut init_flags_out: u32 = 0;
+        let init_hr = filter.Init(init_flags, &[], &mut init_flags_out);
+        if init_hr != S_OK.0 {
+            return Err(ExtractError::Failed(format!(
+                "IFilter::Init failed with 0x{init_hr:08x}"
+            )));
+        }
+
+        // Extract text chunks
+        let mut text = String::new();
+        let mut truncated = false;
+        let mut bytes_processed = 0;
+
+        // Stat chunk
+        // STAT_CHUNK struct.
+        // IFilter::GetChunk(&mut stat)
+
+        // Loop chunks
+        // Reading text: IFilter::GetText(&mut buffer)
+        // We need a buffer.
+
+        loop {
+            let mut stat = STAT_CHUNK::default();
+            let hr = filter.GetChunk(&mut stat);
+            if hr == FILTER_E_END_OF_CHUNKS.0 {
+                break;
+            }
+            if hr != S_OK.0 {
                 return Err(ExtractError::Failed(format!(
-                    "IFilter::Init failed with 0x{init_hr:08x}"
+                    "GetChunk failed with 0x{hr:08x}"
                 )));
             }
 
-            // Extract text chunks
-            let mut text = String::new();
-            let mut truncated = false;
-            let mut bytes_processed = 0;
-
-            // Stat chunk
-            // STAT_CHUNK struct.
-            // IFilter::GetChunk(&mut stat)
-
-            // Loop chunks
-            // Reading text: IFilter::GetText(&mut buffer)
-            // We need a buffer.
+            if stat.flags.0 & CHUNK_TEXT.0 == CHUNK_TEXT.0 {
+                // Read text
+                loop {
+                    let mut buf = [0u16; 4096];
+                    let mut count = buf.len() as u32;
+                    let hr = filter.GetText(&mut count, PWSTR(buf.as_mut_ptr()));
 
-            loop {
-                let mut stat = STAT_CHUNK::default();
-                let hr = filter.GetChunk(&mut stat);
-                if hr == FILTER_E_END_OF_CHUNKS.0 {
-                    break;
-                }
-                if hr != S_OK.0 {
-                    return Err(ExtractError::Failed(format!(
-                        "GetChunk failed with 0x{hr:08x}"
-                    )));
-                }
+                    if hr == FILTER_E_NO_MORE_TEXT.0 {
+                        break;
+                    }
+                    if hr != S_OK.0 {
+                        break;
+                    }
+                    if count == 0 {
+                        break;
+                    }
 
-                if stat.flags.0 & CHUNK_TEXT.0 == CHUNK_TEXT.0 {
-                    // Read text
-                    loop {
-                        let mut buf = [0u16; 4096];
-                        let mut count = buf.len() as u32;
-                        let hr = filter.GetText(&mut count, PWSTR(buf.as_mut_ptr()));
-
-                        if hr == FILTER_E_NO_MORE_TEXT.0 {
-                            break;
-                        }
-                        if hr != S_OK.0 {
-                            break;
-                        }
-                        if count == 0 {
-                            break;
-                        }
-
-                        let chunk = String::from_utf16_lossy(&buf[..count as usize]);
-                        let (trimmed, was_trunc, used) = enforce_limits_str(&chunk, ctx);
-                        text.push_str(&trimmed);
-                        bytes_processed += used; // approximate bytes from the UTF-16 slice
-                        if was_trunc || text.len() >= ctx.max_chars {
-                            truncated = true;
-                            break;
-                        }
+                    let chunk = String::from_utf16_lossy(&buf[..count as usize]);
+                    let (trimmed, was_trunc, used) = enforce_limits_str(&chunk, ctx);
+                    text.push_str(&trimmed);
+                    bytes_processed += used; // approximate bytes from the UTF-16 slice
+                    if was_trunc || text.len() >= ctx.max_chars {
+                        truncated = true;
+                        break;
                     }
                 }
+            }
 
-                if truncated {
-                    break;
-                }
+            if truncated {
+                break;
             }
+        }
 
-            Ok(ExtractedContent {
-                key,
-                text,
-                lang: None,
-                truncated,
-                content_lang: None,
-                bytes_processed,
-            })
+        Ok(ExtractedContent {
+            key,
+            text,
+            lang: None,
+            truncated,
+            content_lang: None,
+            bytes_processed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sta_pool_reuses_initialized_thread() {
+        let pool = Arc::new(StaPool::new(1));
+        let extractor = IFilterExtractor::with_pool(Arc::clone(&pool));
+        let dir = tempfile::tempdir().unwrap();
+
+        let mut threads = Vec::new();
+        for name in ["one.txt", "two.txt"] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, b"hello from ifilter").unwrap();
+            let ctx = ExtractContext {
+                path: path.to_str().unwrap(),
+                max_bytes: 1024,
+                max_chars: 1024,
+                ext_hint: None,
+                mime_hint: None,
+                deadline: None,
+            };
+            // Filter availability varies by machine; only the threading behaviour matters here.
+            let _ = extractor.extract(&ctx, DocKey::from_parts(1, 1));
+            threads.push(pool.run(|| thread::current().id()).unwrap());
         }
+
+        assert_eq!(threads[0], threads[1]);
+        assert_eq!(pool.com_inits(), 1);
     }
 }
@@ -8,10 +8,18 @@ use anyhow::Result;
 use core_types::DocKey;
 use std::fs;
 use std::path::Path;
-use tracing::instrument;
+use std::sync::{Arc, Mutex, OnceLock, mpsc};
+use std::time::Instant;
+use tracing::{instrument, warn};
 
+#[cfg(feature = "archive-backend")]
+pub mod archive;
 pub mod component_manager;
+#[cfg(feature = "ooxml-backend")]
+pub mod docx;
+pub mod html;
 pub mod ifilter;
+#[cfg(feature = "ocr-backend")]
 pub mod ocr;
 pub mod plugins;
 
@@ -34,6 +42,16 @@ pub struct ExtractContext<'a> {
     pub max_chars: usize,
     pub ext_hint: Option<&'a str>,
     pub mime_hint: Option<&'a str>,
+    /// Wall-clock deadline for the whole extraction; past it [`ExtractorStack::extract`] gives
+    /// up with [`ExtractError::timeout`]. `None` waits indefinitely.
+    pub deadline: Option<Instant>,
+}
+
+impl ExtractContext<'_> {
+    /// True once `deadline` has passed; long-running backends poll this to stop early.
+    pub fn expired(&self) -> bool {
+        self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
 }
 
 /// Extraction error categories.
@@ -45,6 +63,20 @@ pub enum ExtractError {
     Failed(String),
     #[error("file too large (bytes={bytes}, max={max_bytes})")]
     FileTooLarge { bytes: u64, max_bytes: u64 },
+    /// `extract.allowed_exts` / `extract.denied_exts` keep this extension out of the index.
+    #[error("excluded by extract config: {0}")]
+    Excluded(String),
+}
+
+impl ExtractError {
+    /// The extraction ran past `ExtractContext::deadline` and was abandoned.
+    pub fn timeout() -> Self {
+        Self::Failed("timeout".into())
+    }
+
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, Self::Failed(msg) if msg == "timeout")
+    }
 }
 
 /// Trait implemented by concrete extractor backends.
@@ -56,7 +88,12 @@ pub trait Extractor {
 
 /// Ordered stack of extractors with first-win semantics.
 pub struct ExtractorStack {
-    backends: Vec<Box<dyn Extractor + Send + Sync>>,
+    /// Shared so a backend can outlive a timed-out `extract` on its pool thread.
+    backends: Vec<Arc<dyn Extractor + Send + Sync>>,
+    /// Lowercase extensions without the dot; when non-empty, only these are extracted.
+    allowed_exts: Vec<String>,
+    /// Lowercase extensions without the dot; never extracted, even if also allowed.
+    denied_exts: Vec<String>,
 }
 
 impl ExtractorStack {
@@ -67,7 +104,9 @@ impl ExtractorStack {
 
     /// Simple-only stack (no external dependencies).
     pub fn simple_only() -> Self {
-        Self::new(vec![Box::new(SimpleTextExtractor), Box::new(NoopExtractor)])
+        let mut backends = Self::lightweight_backends();
+        backends.push(Box::new(NoopExtractor));
+        Self::new(backends)
     }
 
     /// Build a stack optionally including Extractous when the feature is enabled.
@@ -75,21 +114,73 @@ impl ExtractorStack {
         if enable {
             #[cfg(feature = "extractous_backend")]
             {
-                return Self::new(vec![
-                    Box::new(SimpleTextExtractor),
-                    Box::new(ExtractousExtractor::new()),
-                    Box::new(NoopExtractor),
-                ]);
+                let mut backends = Self::lightweight_backends();
+                backends.push(Box::new(ExtractousExtractor::new()));
+                backends.push(Box::new(NoopExtractor));
+                return Self::new(backends);
             }
         }
         Self::simple_only()
     }
 
+    /// In-process extractors: SimpleText, Html, plus Archive and OOXML when `archive-backend`
+    /// and `ooxml-backend` are enabled.
+    fn lightweight_backends() -> Vec<Box<dyn Extractor + Send + Sync>> {
+        #[allow(unused_mut)]
+        let mut backends: Vec<Box<dyn Extractor + Send + Sync>> =
+            vec![Box::new(SimpleTextExtractor), Box::new(html::HtmlExtractor)];
+        #[cfg(feature = "archive-backend")]
+        backends.push(Box::new(archive::ArchiveExtractor::new()));
+        #[cfg(feature = "ooxml-backend")]
+        backends.push(Box::new(docx::DocxExtractor));
+        backends
+    }
+
+    /// Put Tesseract OCR in front of the stack for images and image-only PDFs, reading at most
+    /// `max_pages` pages per PDF (`extract.ocr_enabled` / `extract.ocr_max_pages`).
+    #[cfg(feature = "ocr-backend")]
+    pub fn with_ocr(mut self, max_pages: u32) -> Self {
+        let ocr = ocr::OcrExtractor::with_default_components().with_max_pages(max_pages);
+        self.backends.insert(0, Arc::new(ocr));
+        self
+    }
+
     pub fn new(backends: Vec<Box<dyn Extractor + Send + Sync>>) -> Self {
-        Self { backends }
+        Self {
+            backends: backends.into_iter().map(Arc::from).collect(),
+            allowed_exts: Vec::new(),
+            denied_exts: Vec::new(),
+        }
+    }
+
+    /// Restrict extraction by extension (`extract.allowed_exts` / `extract.denied_exts`). An
+    /// empty allowlist admits everything; the denylist wins over it. Entries match
+    /// case-insensitively, with or without a leading dot.
+    pub fn with_ext_filter(mut self, allowed: &[String], denied: &[String]) -> Self {
+        let normalize = |exts: &[String]| -> Vec<String> {
+            exts.iter()
+                .map(|e| e.trim().trim_start_matches('.').to_ascii_lowercase())
+                .filter(|e| !e.is_empty())
+                .collect()
+        };
+        self.allowed_exts = normalize(allowed);
+        self.denied_exts = normalize(denied);
+        self
     }
 
-    /// Run the first extractor that claims support.
+    /// Whether the extension filter lets a file with `ext` (lowercase, no dot) through.
+    pub fn admits(&self, ext: Option<&str>) -> bool {
+        match ext {
+            Some(ext) if self.denied_exts.iter().any(|d| d == ext) => false,
+            Some(ext) => self.allowed_exts.is_empty() || self.allowed_exts.iter().any(|a| a == ext),
+            None => self.allowed_exts.is_empty(),
+        }
+    }
+
+    /// Run the first extractor that claims support. With a `deadline`, the backend runs on a
+    /// shared, bounded set of threads; if it has not finished by then its result is dropped
+    /// and the call fails with [`ExtractError::timeout`]. Backends poll
+    /// [`ExtractContext::expired`] to free their thread soon after.
     #[instrument(skip(self, ctx))]
     pub fn extract(&self, key: DocKey, ctx: &ExtractContext) -> Result<ExtractedContent> {
         if self.backends.is_empty() {
@@ -97,9 +188,34 @@ impl ExtractorStack {
             return Err(anyhow::anyhow!(ExtractError::Unsupported(ext)));
         }
 
+        // Checked before sniffing, so an excluded file is never opened.
+        let ext = resolve_ext(ctx);
+        if !self.admits(ext.as_deref()) {
+            let ext = ext.unwrap_or_else(|| "unknown".to_string());
+            return Err(anyhow::anyhow!(ExtractError::Excluded(ext)));
+        }
+
+        // Fill in the MIME hint from magic bytes so backends can route on content, not name.
+        let sniffed;
+        let ctx = if ctx.mime_hint.is_none() {
+            sniffed = ExtractContext {
+                mime_hint: sniff_mime(Path::new(ctx.path)),
+                ..ctx.clone()
+            };
+            &sniffed
+        } else {
+            ctx
+        };
+
         for backend in &self.backends {
             if backend.supports(ctx) {
-                return backend.extract(ctx, key).map_err(|e| e.into());
+                let result = match ctx.deadline {
+                    Some(deadline) => {
+                        ExtractPool::shared().extract_until(Arc::clone(backend), ctx, key, deadline)
+                    }
+                    None => backend.extract(ctx, key),
+                };
+                return result.map_err(|e| e.into());
             }
         }
         let ext = resolve_ext(ctx).unwrap_or_else(|| "unknown".to_string());
@@ -107,6 +223,103 @@ impl ExtractorStack {
     }
 }
 
+/// An extraction queued on an [`ExtractPool`] thread.
+type PoolJob = Box<dyn FnOnce() + Send>;
+
+/// Fixed set of threads that run extractions with a deadline. A backend that ignores its
+/// deadline keeps its thread, but never more than the pool's: once every thread is stuck,
+/// later extractions wait in the queue, time out there, and are skipped when finally reached.
+struct ExtractPool {
+    jobs: mpsc::Sender<PoolJob>,
+}
+
+impl ExtractPool {
+    fn new(threads: usize) -> Self {
+        let (jobs, queue) = mpsc::channel::<PoolJob>();
+        let queue = Arc::new(Mutex::new(queue));
+        for i in 0..threads.max(1) {
+            let queue = Arc::clone(&queue);
+            let spawned = std::thread::Builder::new()
+                .name(format!("extract-{i}"))
+                .spawn(move || {
+                    loop {
+                        let job = match queue.lock() {
+                            Ok(queue) => queue.recv(),
+                            Err(_) => return,
+                        };
+                        let Ok(job) = job else { return };
+                        // A panicking backend is reported through its dropped sender.
+                        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(job));
+                    }
+                });
+            if let Err(e) = spawned {
+                warn!("failed to start extraction thread {i}: {e}");
+            }
+        }
+        Self { jobs }
+    }
+
+    /// The process-wide pool, one thread per core within 2..=8.
+    fn shared() -> &'static Self {
+        static POOL: OnceLock<ExtractPool> = OnceLock::new();
+        POOL.get_or_init(|| {
+            let threads = std::thread::available_parallelism().map_or(2, |n| n.get());
+            Self::new(threads.clamp(2, 8))
+        })
+    }
+
+    /// Run `backend` on a pool thread and wait for it until `deadline`.
+    fn extract_until(
+        &self,
+        backend: Arc<dyn Extractor + Send + Sync>,
+        ctx: &ExtractContext,
+        key: DocKey,
+        deadline: Instant,
+    ) -> Result<ExtractedContent, ExtractError> {
+        let path = ctx.path.to_owned();
+        let ext_hint = ctx.ext_hint.map(str::to_owned);
+        let mime_hint = ctx.mime_hint.map(str::to_owned);
+        let (max_bytes, max_chars) = (ctx.max_bytes, ctx.max_chars);
+        let (tx, rx) = mpsc::channel();
+        let name = backend.name();
+        let job: PoolJob = Box::new(move || {
+            // Waited in the queue past its deadline: the caller has already given up.
+            if Instant::now() >= deadline {
+                let _ = tx.send(Err(ExtractError::timeout()));
+                return;
+            }
+            let ctx = ExtractContext {
+                path: &path,
+                max_bytes,
+                max_chars,
+                ext_hint: ext_hint.as_deref(),
+                mime_hint: mime_hint.as_deref(),
+                deadline: Some(deadline),
+            };
+            // The receiver is gone once the caller timed out; nothing left to report to.
+            let _ = tx.send(backend.extract(&ctx, key));
+        });
+        self.jobs
+            .send(job)
+            .map_err(|_| ExtractError::Failed("extraction threads are gone".into()))?;
+
+        match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(result) => result,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                warn!(
+                    backend = name,
+                    path = ctx.path,
+                    "extraction timed out; abandoning it"
+                );
+                Err(ExtractError::timeout())
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                Err(ExtractError::Failed(format!("{name} extractor panicked")))
+            }
+        }
+    }
+}
+
 /// Minimal placeholder extractor that returns empty text; used until real
 /// Extractous/IFilter/OCR adapters are wired.
 pub struct NoopExtractor;
@@ -142,14 +355,10 @@ impl Extractor for SimpleTextExtractor {
     }
 
     fn supports(&self, ctx: &ExtractContext) -> bool {
-        if let Some(ext) = resolve_ext(ctx) {
-            matches!(
-                ext.as_str(),
-                "txt" | "log" | "md" | "json" | "jsonl" | "toml" | "rs" | "ts" | "tsx" | "csv"
-            )
-        } else {
-            false
+        if ctx.mime_hint.is_some_and(|m| !is_text_mime(m)) {
+            return false;
         }
+        resolve_ext(ctx).is_some_and(|ext| is_text_ext(&ext))
     }
 
     fn extract(&self, ctx: &ExtractContext, key: DocKey) -> Result<ExtractedContent, ExtractError> {
@@ -211,6 +420,35 @@ pub(crate) fn resolve_ext(ctx: &ExtractContext) -> Option<String> {
         .map(|s| s.to_ascii_lowercase())
 }
 
+/// Extensions the plain-text path can index verbatim.
+pub(crate) fn is_text_ext(ext: &str) -> bool {
+    matches!(
+        ext,
+        "txt" | "log" | "md" | "json" | "jsonl" | "toml" | "rs" | "ts" | "tsx" | "csv"
+    )
+}
+
+/// Bytes read from the head of a file for magic-number sniffing.
+const SNIFF_BYTES: usize = 8192;
+
+/// Detect a MIME type from the file's magic bytes. `None` when unreadable or unrecognised
+/// (plain text has no signature, so it always lands here).
+pub fn sniff_mime(path: &Path) -> Option<&'static str> {
+    use std::io::Read;
+    let mut head = Vec::with_capacity(SNIFF_BYTES);
+    fs::File::open(path)
+        .ok()?
+        .take(SNIFF_BYTES as u64)
+        .read_to_end(&mut head)
+        .ok()?;
+    infer::get(&head).map(|t| t.mime_type())
+}
+
+/// MIME types whose payload is readable as text.
+pub(crate) fn is_text_mime(mime: &str) -> bool {
+    mime.starts_with("text/") || matches!(mime, "application/json" | "application/xml")
+}
+
 /// Heuristic to detect likely-binary content: look for NULs or >5% control bytes in first 4 KiB.
 fn is_probably_binary(bytes: &[u8]) -> bool {
     let sample = &bytes[..bytes.len().min(4096)];
@@ -303,6 +541,7 @@ mod tests {
             max_chars: 1024,
             ext_hint: Some("txt"),
             mime_hint: None,
+            deadline: None,
         };
         let stack = ExtractorStack::new(vec![Box::new(NoopExtractor)]);
         let out = stack.extract(DocKey::from_parts(1, 42), &ctx).unwrap();
@@ -320,6 +559,7 @@ mod tests {
             max_chars: 3,
             ext_hint: None,
             mime_hint: None,
+            deadline: None,
         };
         let (trimmed, was_truncated, used) = enforce_limits_str(s, &ctx);
         assert_eq!(trimmed, "abc");
@@ -336,6 +576,7 @@ mod tests {
             max_chars: 10,
             ext_hint: None,
             mime_hint: None,
+            deadline: None,
         };
         let (trimmed, truncated, used) = enforce_limits_str(s, &ctx);
         assert_eq!(trimmed, "é");
@@ -352,6 +593,7 @@ mod tests {
             max_chars: 10,
             ext_hint: None,
             mime_hint: None,
+            deadline: None,
         };
         let (trimmed, truncated, used) = enforce_limits_str(s, &ctx);
         assert_eq!(trimmed, "01234");
@@ -382,6 +624,7 @@ mod tests {
             max_chars: 10,
             ext_hint: Some("txt"),
             mime_hint: None,
+            deadline: None,
         };
         let simple = SimpleTextExtractor;
         let out = simple.extract(&ctx, DocKey::from_parts(1, 1)).unwrap();
@@ -404,6 +647,7 @@ mod tests {
             max_chars: 20,
             ext_hint: Some("txt"),
             mime_hint: None,
+            deadline: None,
         };
         let simple = SimpleTextExtractor;
         let err = simple.extract(&ctx, DocKey::from_parts(1, 1)).unwrap_err();
@@ -424,6 +668,7 @@ mod tests {
             max_chars: 1024,
             ext_hint: None,
             mime_hint: None,
+            deadline: None,
         };
         let simple = SimpleTextExtractor;
         assert!(simple.supports(&ctx));
@@ -437,6 +682,7 @@ mod tests {
             max_chars: 10,
             ext_hint: Some("txt"),
             mime_hint: None,
+            deadline: None,
         };
         assert_eq!(resolve_ext(&ctx).as_deref(), Some("txt"));
     }
@@ -449,16 +695,348 @@ mod tests {
             max_chars: 10,
             ext_hint: None,
             mime_hint: None,
+            deadline: None,
         };
         let stack = ExtractorStack::new(vec![]);
         let err = stack.extract(DocKey::from_parts(1, 1), &ctx).unwrap_err();
         assert!(err.to_string().contains("unsupported"));
     }
 
+    fn extract_ext(stack: &ExtractorStack, path: &str) -> Result<ExtractedContent> {
+        let ctx = ExtractContext {
+            path,
+            max_bytes: 1024,
+            max_chars: 1024,
+            ext_hint: None,
+            mime_hint: None,
+            deadline: None,
+        };
+        stack.extract(DocKey::from_parts(1, 1), &ctx)
+    }
+
+    fn is_excluded(err: &anyhow::Error) -> bool {
+        matches!(
+            err.downcast_ref::<ExtractError>(),
+            Some(ExtractError::Excluded(_))
+        )
+    }
+
+    #[test]
+    fn denied_ext_is_skipped_even_when_supported() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.TXT");
+        fs::write(&path, "hello").unwrap();
+        let path = path.to_str().unwrap();
+
+        let stack = ExtractorStack::simple_only();
+        assert_eq!(extract_ext(&stack, path).unwrap().text, "hello");
+
+        let stack = ExtractorStack::simple_only()
+            .with_ext_filter(&["txt".to_string()], &[".Txt".to_string()]);
+        let err = extract_ext(&stack, path).unwrap_err();
+        assert!(is_excluded(&err), "{err:#}");
+    }
+
+    #[test]
+    fn allowlist_excludes_everything_else() {
+        let stack = ExtractorStack::new(vec![Box::new(NoopExtractor)])
+            .with_ext_filter(&["md".to_string()], &[]);
+        // Neither file exists: the filter answers before anything is read.
+        assert!(extract_ext(&stack, "/missing/readme.md").is_ok());
+        assert!(is_excluded(
+            &extract_ext(&stack, "/missing/notes.txt").unwrap_err()
+        ));
+        assert!(is_excluded(
+            &extract_ext(&stack, "/missing/Makefile").unwrap_err()
+        ));
+        assert!(stack.admits(Some("md")));
+        assert!(!stack.admits(None));
+    }
+
+    /// Stand-in for a rich-document backend that only claims PDFs by MIME.
+    struct PdfProbe;
+
+    impl Extractor for PdfProbe {
+        fn name(&self) -> &'static str {
+            "pdf-probe"
+        }
+
+        fn supports(&self, ctx: &ExtractContext) -> bool {
+            ctx.mime_hint == Some("application/pdf")
+        }
+
+        fn extract(
+            &self,
+            _ctx: &ExtractContext,
+            key: DocKey,
+        ) -> Result<ExtractedContent, ExtractError> {
+            Ok(ExtractedContent {
+                key,
+                text: "pdf-probe".into(),
+                lang: None,
+                truncated: false,
+                content_lang: None,
+                bytes_processed: 0,
+            })
+        }
+    }
+
+    /// Stand-in for a pathological file: spins until the deadline, then flags that it stopped.
+    struct SlowProbe(Arc<std::sync::atomic::AtomicBool>);
+
+    impl Extractor for SlowProbe {
+        fn name(&self) -> &'static str {
+            "slow-probe"
+        }
+
+        fn supports(&self, _ctx: &ExtractContext) -> bool {
+            true
+        }
+
+        fn extract(
+            &self,
+            ctx: &ExtractContext,
+            _key: DocKey,
+        ) -> Result<ExtractedContent, ExtractError> {
+            let give_up = Instant::now() + std::time::Duration::from_secs(30);
+            while !ctx.expired() && Instant::now() < give_up {
+                std::thread::sleep(std::time::Duration::from_millis(5));
+            }
+            self.0.store(true, std::sync::atomic::Ordering::SeqCst);
+            Err(ExtractError::Failed("slow-probe gave up".into()))
+        }
+    }
+
+    #[test]
+    fn slow_backend_is_abandoned_at_the_deadline() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::time::Duration;
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let stack = ExtractorStack::new(vec![Box::new(SlowProbe(Arc::clone(&stopped)))]);
+        let ctx = ExtractContext {
+            path: "pathological.xml",
+            max_bytes: 1024,
+            max_chars: 1024,
+            ext_hint: None,
+            mime_hint: None,
+            deadline: Some(Instant::now() + Duration::from_millis(50)),
+        };
+        let started = Instant::now();
+        let err = stack.extract(DocKey::from_parts(1, 7), &ctx).unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let err = err.downcast::<ExtractError>().unwrap();
+        assert!(err.is_timeout(), "{err}");
+
+        // The abandoned thread sees the same deadline and stops on its own.
+        while !stopped.load(Ordering::SeqCst) && started.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert!(stopped.load(Ordering::SeqCst));
+
+        let fast = ExtractorStack::new(vec![Box::new(NoopExtractor)]);
+        let ctx = ExtractContext {
+            deadline: Some(Instant::now() + Duration::from_secs(30)),
+            ..ctx
+        };
+        assert!(fast.extract(DocKey::from_parts(1, 8), &ctx).is_ok());
+    }
+
+    /// Blocks until released, then counts the extractions it ran.
+    struct GatedProbe {
+        gate: Mutex<mpsc::Receiver<()>>,
+        ran: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl Extractor for GatedProbe {
+        fn name(&self) -> &'static str {
+            "gated-probe"
+        }
+
+        fn supports(&self, _ctx: &ExtractContext) -> bool {
+            true
+        }
+
+        fn extract(
+            &self,
+            ctx: &ExtractContext,
+            key: DocKey,
+        ) -> Result<ExtractedContent, ExtractError> {
+            let _ = self.gate.lock().unwrap().recv();
+            self.ran.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            NoopExtractor.extract(ctx, key)
+        }
+    }
+
+    #[test]
+    fn stuck_pool_times_out_queued_extractions_and_skips_them() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+
+        let pool = ExtractPool::new(1);
+        let (release, gate) = mpsc::channel();
+        let ran = Arc::new(AtomicUsize::new(0));
+        let probe: Arc<dyn Extractor + Send + Sync> = Arc::new(GatedProbe {
+            gate: Mutex::new(gate),
+            ran: Arc::clone(&ran),
+        });
+        let ctx = |timeout| ExtractContext {
+            path: "stuck.xml",
+            max_bytes: 1024,
+            max_chars: 1024,
+            ext_hint: None,
+            mime_hint: None,
+            deadline: Some(Instant::now() + timeout),
+        };
+
+        // Holds the only thread past its deadline, as a backend ignoring it would.
+        let first = pool.extract_until(
+            Arc::clone(&probe),
+            &ctx(Duration::from_millis(20)),
+            DocKey::from_parts(1, 1),
+            Instant::now() + Duration::from_millis(20),
+        );
+        assert!(first.unwrap_err().is_timeout());
+        // Queued behind it: no new thread is started for it.
+        let queued = ctx(Duration::from_millis(20));
+        let second = pool.extract_until(
+            Arc::clone(&probe),
+            &queued,
+            DocKey::from_parts(1, 2),
+            queued.deadline.unwrap(),
+        );
+        assert!(second.unwrap_err().is_timeout());
+
+        release.send(()).unwrap();
+        let started = Instant::now();
+        while ran.load(Ordering::SeqCst) == 0 && started.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        // The thread is free again: the queued extraction was skipped, a new one runs.
+        release.send(()).unwrap();
+        let fresh = ctx(Duration::from_secs(30));
+        assert!(
+            pool.extract_until(
+                Arc::clone(&probe),
+                &fresh,
+                DocKey::from_parts(1, 3),
+                fresh.deadline.unwrap()
+            )
+            .is_ok()
+        );
+        assert_eq!(ran.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn png_header_is_sniffed_and_skipped_by_text_extractor() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("not-really.txt");
+        std::fs::write(&path, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+
+        let mime = sniff_mime(&path);
+        assert_eq!(mime, Some("image/png"));
+
+        let ctx = ExtractContext {
+            path: path.to_str().unwrap(),
+            max_bytes: 1024,
+            max_chars: 1024,
+            ext_hint: None,
+            mime_hint: mime,
+            deadline: None,
+        };
+        assert!(!SimpleTextExtractor.supports(&ctx));
+
+        let out = ExtractorStack::simple_only()
+            .extract(
+                DocKey::from_parts(1, 1),
+                &ExtractContext {
+                    mime_hint: None,
+                    ..ctx
+                },
+            )
+            .unwrap();
+        assert!(out.text.is_empty(), "noop should have handled the image");
+    }
+
+    #[test]
+    fn pdf_magic_routes_to_pdf_backend_despite_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.txt");
+        std::fs::write(&path, b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n").unwrap();
+
+        let ctx = ExtractContext {
+            path: path.to_str().unwrap(),
+            max_bytes: 1024,
+            max_chars: 1024,
+            ext_hint: None,
+            mime_hint: None,
+            deadline: None,
+        };
+        let stack = ExtractorStack::new(vec![
+            Box::new(SimpleTextExtractor),
+            Box::new(PdfProbe),
+            Box::new(NoopExtractor),
+        ]);
+        let out = stack.extract(DocKey::from_parts(1, 2), &ctx).unwrap();
+        assert_eq!(out.text, "pdf-probe");
+    }
+
+    #[test]
+    fn unsniffable_text_falls_back_to_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.md");
+        std::fs::write(&path, b"plain words").unwrap();
+        assert_eq!(sniff_mime(&path), None);
+
+        let ctx = ExtractContext {
+            path: path.to_str().unwrap(),
+            max_bytes: 1024,
+            max_chars: 1024,
+            ext_hint: None,
+            mime_hint: None,
+            deadline: None,
+        };
+        let out = ExtractorStack::simple_only()
+            .extract(DocKey::from_parts(1, 3), &ctx)
+            .unwrap();
+        assert_eq!(out.text, "plain words");
+    }
+
     #[test]
     fn with_extractous_disabled_uses_simple_only() {
         let stack = ExtractorStack::with_extractous_enabled(false);
-        assert_eq!(stack.backends.len(), 2);
+        // SimpleText, Html and Noop, plus one per optional in-process backend.
+        let expected = 3
+            + usize::from(cfg!(feature = "archive-backend"))
+            + usize::from(cfg!(feature = "ooxml-backend"));
+        assert_eq!(stack.backends.len(), expected);
+    }
+
+    #[test]
+    fn html_routes_to_html_extractor() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("page.html");
+        std::fs::write(&path, "<p>Hello <b>world</b></p><script>x</script>").unwrap();
+
+        let ctx = ExtractContext {
+            path: path.to_str().unwrap(),
+            max_bytes: 1024,
+            max_chars: 1024,
+            ext_hint: None,
+            mime_hint: None,
+            deadline: None,
+        };
+        let out = ExtractorStack::with_defaults()
+            .extract(DocKey::from_parts(1, 4), &ctx)
+            .unwrap();
+        assert_eq!(out.text, "Hello world");
+    }
+
+    #[cfg(feature = "archive-backend")]
+    #[test]
+    fn archive_backend_joins_default_stack() {
+        let stack = ExtractorStack::with_defaults();
+        assert!(stack.backends.iter().any(|b| b.name() == "archive"));
     }
 
     #[cfg(feature = "extractous_backend")]
@@ -487,6 +1065,7 @@ mod tests {
             max_chars: 1024,
             ext_hint: Some("docx"),
             mime_hint: None,
+            deadline: None,
         };
 
         let extractor = ExtractousExtractor::new();
@@ -522,6 +1101,7 @@ mod tests {
             max_chars: 2048,
             ext_hint: Some("txt"),
             mime_hint: None,
+            deadline: None,
         };
 
         let extractor = ExtractousExtractor::new();
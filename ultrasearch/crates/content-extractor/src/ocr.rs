@@ -1,25 +1,54 @@
+//! OCR extractor (`ocr-backend` feature): recognizes text in images and image-only PDFs with
+//! the Tesseract CLI.
+//!
+//! PDF pages are rasterized with `pdftoppm` first, up to `max_pages`. PDFs that carry fonts
+//! already have a text layer and are left to the other backends.
+
 use crate::component_manager::{Component, ComponentManager};
-use crate::{ExtractContext, ExtractError, ExtractedContent, Extractor, enforce_limits_str};
-use anyhow::Result;
+use crate::{
+    ExtractContext, ExtractError, ExtractedContent, Extractor, enforce_limits_str, resolve_ext,
+};
 use core_types::DocKey;
-use std::path::Path;
-use std::process::Command;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
 use tracing::warn;
 
+const IMAGE_EXTS: &[&str] = &["png", "jpg", "jpeg", "tif", "tiff", "bmp", "webp"];
+
+/// Tesseract language used when none is configured.
+const DEFAULT_LANG: &str = "eng";
+
+/// Rasterization resolution for PDF pages; Tesseract is tuned for ~300 DPI input.
+const PDF_DPI: &str = "300";
+
 pub struct OcrExtractor {
-    manager: ComponentManager,
+    manager: Option<ComponentManager>,
     tesseract_component: Component,
+    lang: String,
+    max_pages: u32,
 }
 
 impl OcrExtractor {
     pub fn new(manager: ComponentManager) -> Self {
+        Self::with_manager(Some(manager))
+    }
+
+    /// Look for a managed Tesseract in the default component directory, then on `PATH`.
+    pub fn with_default_components() -> Self {
+        Self::with_manager(ComponentManager::with_default_path().ok())
+    }
+
+    fn with_manager(manager: Option<ComponentManager>) -> Self {
         // Define the standard Tesseract component we expect
         // In a real app, this might come from config or a remote manifest
         let tesseract_component = Component {
             id: "tesseract".to_string(),
             version: "5.3.3".to_string(),
             // Placeholder URL - in production this would be a real release asset
-            url: "https://github.com/UB-Mannheim/tesseract/releases/download/v5.3.3/tesseract-ocr-w64-setup-v5.3.3.20231005.exe".to_string(), 
+            url: "https://github.com/UB-Mannheim/tesseract/releases/download/v5.3.3/tesseract-ocr-w64-setup-v5.3.3.20231005.exe".to_string(),
             // Placeholder hash
             sha256: "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855".to_string(),
             executable_name: if cfg!(windows) { "tesseract.exe" } else { "tesseract" }.to_string(),
@@ -28,22 +57,76 @@ impl OcrExtractor {
         Self {
             manager,
             tesseract_component,
+            lang: DEFAULT_LANG.to_string(),
+            max_pages: 10,
         }
     }
 
-    fn get_tesseract_path(&self) -> Option<std::path::PathBuf> {
+    /// Tesseract language pack(s), e.g. `eng` or `eng+deu`.
+    pub fn with_lang(mut self, lang: impl Into<String>) -> Self {
+        self.lang = lang.into();
+        self
+    }
+
+    /// OCR at most this many pages of a PDF (`extract.ocr_max_pages`).
+    pub fn with_max_pages(mut self, max_pages: u32) -> Self {
+        self.max_pages = max_pages.max(1);
+        self
+    }
+
+    fn get_tesseract_path(&self) -> Option<PathBuf> {
         // Check component manager first
-        if let Some(path) = self.manager.get_executable_path(&self.tesseract_component) {
+        if let Some(path) = self
+            .manager
+            .as_ref()
+            .and_then(|m| m.get_executable_path(&self.tesseract_component))
+        {
             return Some(path);
         }
 
         // Fallback to system path
-        // This simple check assumes 'tesseract' is in PATH
-        if which::which("tesseract").is_ok() {
-            return Some("tesseract".into());
-        }
+        which::which("tesseract").ok()
+    }
 
-        None
+    /// Recognize one image; the text goes to stdout.
+    fn recognize(
+        &self,
+        tesseract: &Path,
+        image: &Path,
+        ctx: &ExtractContext,
+    ) -> Result<String, ExtractError> {
+        let mut cmd = Command::new(tesseract);
+        cmd.arg(image).arg("stdout").arg("-l").arg(&self.lang);
+        let stdout = run_until_deadline(cmd, ctx)?;
+        Ok(String::from_utf8_lossy(&stdout).into_owned())
+    }
+
+    /// Rasterize the first `max_pages` pages of `pdf` into `out_dir` and return the page images
+    /// in page order.
+    fn render_pdf(
+        &self,
+        pdf: &Path,
+        out_dir: &Path,
+        ctx: &ExtractContext,
+    ) -> Result<Vec<PathBuf>, ExtractError> {
+        let pdftoppm = which::which("pdftoppm")
+            .map_err(|_| ExtractError::Unsupported("pdf ocr needs pdftoppm".into()))?;
+        let mut cmd = Command::new(pdftoppm);
+        cmd.args(["-r", PDF_DPI, "-f", "1", "-l"])
+            .arg(self.max_pages.to_string())
+            .arg("-png")
+            .arg(pdf)
+            .arg(out_dir.join("page"));
+        run_until_deadline(cmd, ctx)?;
+
+        let mut pages: Vec<PathBuf> = fs::read_dir(out_dir)
+            .map_err(|e| ExtractError::Failed(e.to_string()))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "png"))
+            .collect();
+        // `page-01.png`, `page-02.png`, ...: zero-padded, so name order is page order.
+        pages.sort();
+        Ok(pages)
     }
 }
 
@@ -53,49 +136,234 @@ impl Extractor for OcrExtractor {
     }
 
     fn supports(&self, ctx: &ExtractContext) -> bool {
-        // Check if extension is an image
-        if let Some(ext) = super::resolve_ext(ctx) {
-            match ext.as_str() {
-                "png" | "jpg" | "jpeg" | "tiff" | "bmp" | "webp" => true,
-                _ => false, // PDF OCR handled by Extractous usually, or specialized PDF pipeline
-            }
-        } else {
-            false
+        match resolve_ext(ctx).as_deref() {
+            Some(ext) if IMAGE_EXTS.contains(&ext) => true,
+            Some("pdf") => is_image_only_pdf(Path::new(ctx.path), ctx.max_bytes),
+            _ => false,
         }
     }
 
     fn extract(&self, ctx: &ExtractContext, key: DocKey) -> Result<ExtractedContent, ExtractError> {
+        let input_path = Path::new(ctx.path);
+        let meta = fs::metadata(input_path).map_err(|e| ExtractError::Failed(e.to_string()))?;
+        let max_bytes = ctx.max_bytes as u64;
+        if meta.len() > max_bytes {
+            return Err(ExtractError::FileTooLarge {
+                bytes: meta.len(),
+                max_bytes,
+            });
+        }
+
         let tesseract_bin = self
             .get_tesseract_path()
             .ok_or_else(|| ExtractError::Failed("tesseract binary not found".into()))?;
 
-        let input_path = Path::new(ctx.path);
+        let text_raw = if resolve_ext(ctx).as_deref() == Some("pdf") {
+            let pages_dir = std::env::temp_dir().join(format!(
+                "ultrasearch-ocr-{}-{:016x}",
+                std::process::id(),
+                key.0
+            ));
+            fs::create_dir_all(&pages_dir).map_err(|e| ExtractError::Failed(e.to_string()))?;
+            let recognized = self
+                .render_pdf(input_path, &pages_dir, ctx)
+                .and_then(|pages| {
+                    let mut text = String::new();
+                    for page in pages {
+                        // Keep what earlier pages produced once the char budget is spent.
+                        if text.chars().count() >= ctx.max_chars {
+                            break;
+                        }
+                        text.push_str(&self.recognize(&tesseract_bin, &page, ctx)?);
+                        text.push('\n');
+                    }
+                    Ok(text)
+                });
+            let _ = fs::remove_dir_all(&pages_dir);
+            recognized?
+        } else {
+            self.recognize(&tesseract_bin, input_path, ctx)?
+        };
 
-        // Run tesseract: tesseract <image> stdout
-        let output = Command::new(tesseract_bin)
-            .arg(input_path)
-            .arg("stdout") // Write to stdout
-            .arg("-l")
-            .arg("eng") // Default to English for now, config later
-            .output()
-            .map_err(|e| ExtractError::Failed(format!("failed to spawn tesseract: {e}")))?;
-
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            warn!("tesseract failed for {:?}: {}", input_path, stderr);
-            return Err(ExtractError::Failed("tesseract exited with error".into()));
+        if text_raw.trim().is_empty() {
+            return Err(ExtractError::Unsupported("no text recognized".into()));
         }
-
-        let text_raw = String::from_utf8_lossy(&output.stdout);
-        let (text, truncated, used_bytes) = enforce_limits_str(&text_raw, ctx);
+        let (text, truncated, used_bytes) = enforce_limits_str(text_raw.trim(), ctx);
 
         Ok(ExtractedContent {
             key,
             text,
-            lang: Some("eng".into()), // Assumption
+            lang: Some(self.lang.clone()),
             truncated,
-            content_lang: None,
+            content_lang: Some(iso_lang(&self.lang)),
             bytes_processed: used_bytes,
         })
     }
 }
+
+/// How much of a PDF [`is_image_only_pdf`] reads looking for a font.
+const FONT_SCAN_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Whether a PDF looks like scanned pages with no text layer. A byte scan, not a parse: a PDF
+/// whose first [`FONT_SCAN_BYTES`] (or `max_bytes`, if smaller) never name a `/Font` is taken as
+/// image-only. Fonts named only inside compressed object streams or past that prefix are
+/// missed, so such PDFs are OCRed as well.
+fn is_image_only_pdf(path: &Path, max_bytes: usize) -> bool {
+    const FONT: &[u8] = b"/Font";
+    let Ok(file) = fs::File::open(path) else {
+        return false;
+    };
+    let mut reader = file.take((max_bytes as u64).min(FONT_SCAN_BYTES));
+    let mut header = [0u8; 4];
+    if reader.read_exact(&mut header).is_err() || &header != b"%PDF" {
+        return false;
+    }
+    let mut buf = vec![0u8; 64 * 1024];
+    // Bytes carried over from the previous read, so a name split across two reads is seen.
+    let mut kept = 0;
+    loop {
+        let filled = match reader.read(&mut buf[kept..]) {
+            Ok(0) => return true,
+            Ok(n) => kept + n,
+            Err(_) => return false,
+        };
+        if buf[..filled].windows(FONT.len()).any(|w| w == FONT) {
+            return false;
+        }
+        kept = (FONT.len() - 1).min(filled);
+        buf.copy_within(filled - kept..filled, 0);
+    }
+}
+
+/// Two-letter code for a Tesseract language (`eng` -> `en`), matching the `content_lang`
+/// values other backends store. The first pack wins for multi-language settings.
+fn iso_lang(lang: &str) -> String {
+    let primary = lang.split('+').next().unwrap_or(lang);
+    match primary {
+        "eng" => "en",
+        "deu" => "de",
+        "fra" => "fr",
+        "spa" => "es",
+        "ita" => "it",
+        "por" => "pt",
+        "nld" => "nl",
+        "rus" => "ru",
+        "jpn" => "ja",
+        "chi_sim" | "chi_tra" => "zh",
+        other => other,
+    }
+    .to_string()
+}
+
+/// Run `cmd` to completion and return its stdout, killing it once `ctx.deadline` passes so a
+/// stuck OCR run does not outlive the extraction.
+fn run_until_deadline(mut cmd: Command, ctx: &ExtractContext) -> Result<Vec<u8>, ExtractError> {
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| ExtractError::Failed(format!("failed to spawn ocr tool: {e}")))?;
+
+    // Drain the pipes on their own threads so a chatty child never blocks on a full pipe.
+    let drain = |pipe: Option<Box<dyn Read + Send>>| {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buf);
+            }
+            buf
+        })
+    };
+    let stdout = drain(child.stdout.take().map(|p| Box::new(p) as _));
+    let stderr = drain(child.stderr.take().map(|p| Box::new(p) as _));
+
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if ctx.expired() => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(ExtractError::timeout());
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(20)),
+            Err(e) => return Err(ExtractError::Failed(e.to_string())),
+        }
+    };
+
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    if !status.success() {
+        warn!(
+            "ocr tool failed for {:?}: {}",
+            ctx.path,
+            String::from_utf8_lossy(&stderr)
+        );
+        return Err(ExtractError::Failed("ocr tool exited with error".into()));
+    }
+    Ok(stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/hello_world.png"
+    );
+
+    fn ctx(path: &str) -> ExtractContext<'_> {
+        ExtractContext {
+            path,
+            max_bytes: 1024 * 1024,
+            max_chars: 10_000,
+            ext_hint: None,
+            mime_hint: None,
+            deadline: None,
+        }
+    }
+
+    #[test]
+    #[ignore = "needs the tesseract CLI; run with --ignored where it is installed"]
+    fn recognizes_text_in_fixture_image() {
+        let ocr = OcrExtractor::with_manager(None);
+        assert!(ocr.get_tesseract_path().is_some(), "tesseract not found");
+        let ctx = ctx(FIXTURE);
+        assert!(ocr.supports(&ctx));
+        let out = ocr.extract(&ctx, DocKey::from_parts(1, 1)).unwrap();
+        assert!(
+            out.text.to_ascii_uppercase().contains("HELLO"),
+            "{:?}",
+            out.text
+        );
+        assert_eq!(out.content_lang.as_deref(), Some("en"));
+    }
+
+    #[test]
+    fn pdfs_with_fonts_are_left_to_text_backends() {
+        let dir = tempfile::tempdir().unwrap();
+        let text_pdf = dir.path().join("text.pdf");
+        fs::write(&text_pdf, b"%PDF-1.4\n<< /Type /Font /Subtype /Type1 >>\n").unwrap();
+        let scanned = dir.path().join("scan.pdf");
+        fs::write(
+            &scanned,
+            b"%PDF-1.4\n<< /Type /XObject /Subtype /Image >>\n",
+        )
+        .unwrap();
+
+        // A font name that straddles two reads of the scan (the 4-byte header is read first).
+        let split = dir.path().join("split.pdf");
+        let mut bytes = b"%PDF-1.4\n".to_vec();
+        bytes.resize(64 * 1024 + 2, b' ');
+        bytes.extend_from_slice(b"/Font >>\n");
+        fs::write(&split, bytes).unwrap();
+
+        let ocr = OcrExtractor::with_manager(None);
+        assert!(!ocr.supports(&ctx(text_pdf.to_str().unwrap())));
+        assert!(!ocr.supports(&ctx(split.to_str().unwrap())));
+        assert!(ocr.supports(&ctx(scanned.to_str().unwrap())));
+        assert!(!ocr.supports(&ctx("notes.txt")));
+        assert_eq!(iso_lang("deu+eng"), "de");
+    }
+}
@@ -0,0 +1,142 @@
+//! Code-friendly analysis for source files (`features.doc_type_analyzers`).
+//!
+//! Identifiers are split on punctuation, snake_case and camelCase boundaries, so
+//! `getUserName`, `get_user_name` and `HTTPServer` index as `get user name` / `http server`.
+
+use tantivy::tokenizer::{RemoveLongFilter, TextAnalyzer, Token, TokenStream, TokenizerManager};
+
+pub const CODE_ANALYZER: &str = "code_analyzer";
+
+/// Extensions whose text goes through [`CODE_ANALYZER`] in addition to the default analyzer.
+const CODE_EXTS: &[&str] = &[
+    "rs", "py", "ts", "tsx", "js", "jsx", "mjs", "go", "java", "kt", "swift", "c", "h", "cc",
+    "cpp", "hpp", "cs", "rb", "php", "scala", "lua", "sh", "ps1", "sql",
+];
+
+/// Whether files with this extension are analyzed as code.
+pub fn is_code_ext(ext: Option<&str>) -> bool {
+    ext.is_some_and(|ext| CODE_EXTS.iter().any(|c| c.eq_ignore_ascii_case(ext)))
+}
+
+pub fn register_code_analyzer(manager: &TokenizerManager) {
+    let analyzer = TextAnalyzer::builder(CodeTokenizer::default())
+        .filter(RemoveLongFilter::limit(255))
+        .build();
+    manager.register(CODE_ANALYZER, analyzer);
+}
+
+/// Splits alphanumeric runs at case changes and lowercases the parts.
+#[derive(Clone, Default)]
+pub struct CodeTokenizer {
+    token: Token,
+}
+
+pub struct CodeTokenStream<'a> {
+    text: &'a str,
+    spans: std::vec::IntoIter<(usize, usize)>,
+    token: &'a mut Token,
+}
+
+impl tantivy::tokenizer::Tokenizer for CodeTokenizer {
+    type TokenStream<'a> = CodeTokenStream<'a>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> CodeTokenStream<'a> {
+        self.token.reset();
+        CodeTokenStream {
+            text,
+            spans: split_words(text).into_iter(),
+            token: &mut self.token,
+        }
+    }
+}
+
+impl TokenStream for CodeTokenStream<'_> {
+    fn advance(&mut self) -> bool {
+        let Some((from, to)) = self.spans.next() else {
+            return false;
+        };
+        self.token.text.clear();
+        self.token
+            .text
+            .extend(self.text[from..to].chars().flat_map(char::to_lowercase));
+        self.token.offset_from = from;
+        self.token.offset_to = to;
+        self.token.position = self.token.position.wrapping_add(1);
+        true
+    }
+
+    fn token(&self) -> &Token {
+        self.token
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        self.token
+    }
+}
+
+/// Byte spans of the words in `text`. Digits stay attached to the word before them
+/// (`utf8`), and an acronym ends before the capital that starts the next word (`HTTPServer`).
+fn split_words(text: &str) -> Vec<(usize, usize)> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut spans = Vec::new();
+    let mut start: Option<usize> = None;
+    for (i, &(offset, c)) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if let Some(s) = start.take() {
+                spans.push((chars[s].0, offset));
+            }
+            continue;
+        }
+        let Some(s) = start else {
+            start = Some(i);
+            continue;
+        };
+        let prev = chars[i - 1].1;
+        let next_lower = chars.get(i + 1).is_some_and(|&(_, n)| n.is_lowercase());
+        let boundary = c.is_uppercase()
+            && (prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_lower));
+        if boundary {
+            spans.push((chars[s].0, offset));
+            start = Some(i);
+        }
+    }
+    if let Some(s) = start {
+        spans.push((chars[s].0, text.len()));
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(text: &str) -> Vec<String> {
+        let manager = TokenizerManager::default();
+        register_code_analyzer(&manager);
+        let mut analyzer = manager.get(CODE_ANALYZER).unwrap();
+        let mut stream = analyzer.token_stream(text);
+        let mut out = Vec::new();
+        while stream.advance() {
+            out.push(stream.token().text.clone());
+        }
+        out
+    }
+
+    #[test]
+    fn splits_identifiers_into_words() {
+        assert_eq!(tokens("getUserName"), ["get", "user", "name"]);
+        assert_eq!(tokens("get_user_name()"), ["get", "user", "name"]);
+        assert_eq!(
+            tokens("let srv = HTTPServer::new(utf8Decode);"),
+            ["let", "srv", "http", "server", "new", "utf8", "decode"]
+        );
+    }
+
+    #[test]
+    fn code_extensions_are_case_insensitive() {
+        assert!(is_code_ext(Some("rs")));
+        assert!(is_code_ext(Some("PY")));
+        assert!(!is_code_ext(Some("txt")));
+        assert!(!is_code_ext(None));
+    }
+}
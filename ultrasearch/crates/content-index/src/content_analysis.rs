@@ -0,0 +1,215 @@
+//! Configurable analysis of extracted text (`search.content_stemming`,
+//! `search.content_stopwords`, `search.content_max_token_len`).
+//!
+//! Every combination is registered under its own tokenizer name and the `content` field records
+//! the one it was built with, so a reopened index keeps analyzing documents and queries the way
+//! its docs were indexed. With both options off and the default token cap the field uses
+//! Tantivy's `default` analyzer, so indexes built before these options existed read as "no
+//! stemming, no stopwords, 40-byte cap".
+
+use core_types::config::{ContentStemming, SearchSection};
+use tantivy::tokenizer::{
+    Language, LowerCaser, RemoveLongFilter, SimpleTokenizer, Stemmer, StopWordFilter, TextAnalyzer,
+    TokenizerManager,
+};
+
+const CONTENT_STOP_EN: &str = "content_stop_en";
+const CONTENT_STEM_EN: &str = "content_stem_en";
+const CONTENT_STEM_STOP_EN: &str = "content_stem_stop_en";
+/// Stands in for `default` when a non-default token cap needs a name of its own.
+const CONTENT_PLAIN: &str = "content";
+/// Separates a base tokenizer name from a non-default token cap (`content_stem_en_max64`).
+const MAX_SUFFIX: &str = "_max";
+
+/// Token cap of Tantivy's `default` analyzer and of `search.content_max_token_len`.
+pub const DEFAULT_MAX_TOKEN_LEN: usize = 40;
+
+const COMBINATIONS: [(ContentStemming, bool); 4] = [
+    (ContentStemming::None, false),
+    (ContentStemming::None, true),
+    (ContentStemming::En, false),
+    (ContentStemming::En, true),
+];
+
+/// How the `content` field turns text into terms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentAnalysis {
+    pub stemming: ContentStemming,
+    pub stopwords: bool,
+    /// Tokens this many bytes or longer are dropped; 0 keeps every token.
+    pub max_token_len: usize,
+}
+
+impl ContentAnalysis {
+    pub fn from_search(search: &SearchSection) -> Self {
+        Self {
+            stemming: search.content_stemming,
+            stopwords: search.content_stopwords,
+            max_token_len: search.content_max_token_len,
+        }
+    }
+
+    /// Tokenizer name the `content` field is built with for these options.
+    pub fn tokenizer(&self) -> String {
+        let base = base_tokenizer(self.stemming, self.stopwords);
+        if self.max_token_len == DEFAULT_MAX_TOKEN_LEN {
+            return base.to_string();
+        }
+        let base = if base == "default" {
+            CONTENT_PLAIN
+        } else {
+            base
+        };
+        format!("{base}{MAX_SUFFIX}{}", self.max_token_len)
+    }
+}
+
+fn base_tokenizer(stemming: ContentStemming, stopwords: bool) -> &'static str {
+    match (stemming, stopwords) {
+        (ContentStemming::None, false) => "default",
+        (ContentStemming::None, true) => CONTENT_STOP_EN,
+        (ContentStemming::En, false) => CONTENT_STEM_EN,
+        (ContentStemming::En, true) => CONTENT_STEM_STOP_EN,
+    }
+}
+
+/// Inverse of [`ContentAnalysis::tokenizer`], or `None` for a tokenizer these options never
+/// produce.
+pub fn content_analysis(tokenizer: &str) -> Option<ContentAnalysis> {
+    let (base, max_token_len) = match tokenizer.rsplit_once(MAX_SUFFIX) {
+        Some((base, len)) if len.bytes().all(|b| b.is_ascii_digit()) => {
+            let base = if base == CONTENT_PLAIN {
+                "default"
+            } else {
+                base
+            };
+            (base, len.parse().ok()?)
+        }
+        _ => (tokenizer, DEFAULT_MAX_TOKEN_LEN),
+    };
+    COMBINATIONS
+        .into_iter()
+        .find(|&(stemming, stopwords)| base_tokenizer(stemming, stopwords) == base)
+        .map(|(stemming, stopwords)| ContentAnalysis {
+            stemming,
+            stopwords,
+            max_token_len,
+        })
+}
+
+/// Register the non-default combinations at the default token cap; `default` is built into
+/// every index. Other caps are registered per index with [`register_content_tokenizer`].
+pub fn register_content_analyzers(manager: &TokenizerManager) {
+    for (stemming, stopwords) in COMBINATIONS {
+        let options = ContentAnalysis {
+            stemming,
+            stopwords,
+            max_token_len: DEFAULT_MAX_TOKEN_LEN,
+        };
+        register_content_tokenizer(manager, &options.tokenizer());
+    }
+}
+
+/// Register the analyzer behind `tokenizer` if it is one of ours and not `default`.
+pub fn register_content_tokenizer(manager: &TokenizerManager, tokenizer: &str) {
+    if tokenizer == "default" {
+        return;
+    }
+    if let Some(options) = content_analysis(tokenizer) {
+        manager.register(tokenizer, analyzer(options));
+    }
+}
+
+fn analyzer(options: ContentAnalysis) -> TextAnalyzer {
+    let mut builder = TextAnalyzer::builder(SimpleTokenizer::default()).dynamic();
+    if options.max_token_len > 0 {
+        builder = builder.filter_dynamic(RemoveLongFilter::limit(options.max_token_len));
+    }
+    builder = builder.filter_dynamic(LowerCaser);
+    // Stopwords are removed before stemming, since the list holds unstemmed words.
+    if options.stopwords {
+        builder = builder.filter_dynamic(english_stopwords());
+    }
+    if options.stemming == ContentStemming::En {
+        builder = builder.filter_dynamic(Stemmer::new(Language::English));
+    }
+    builder.build()
+}
+
+fn english_stopwords() -> StopWordFilter {
+    StopWordFilter::new(Language::English).expect("English stopwords are built into tantivy")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(stemming: ContentStemming, stopwords: bool) -> ContentAnalysis {
+        ContentAnalysis {
+            stemming,
+            stopwords,
+            max_token_len: DEFAULT_MAX_TOKEN_LEN,
+        }
+    }
+
+    fn tokens(options: ContentAnalysis, text: &str) -> Vec<String> {
+        let mut analyzer = analyzer(options);
+        let mut out = Vec::new();
+        analyzer
+            .token_stream(text)
+            .process(&mut |t| out.push(t.text.clone()));
+        out
+    }
+
+    #[test]
+    fn options_shape_the_tokens() {
+        let text = "The Runners are running";
+        assert_eq!(
+            tokens(options(ContentStemming::None, false), text),
+            ["the", "runners", "are", "running"]
+        );
+        assert_eq!(
+            tokens(options(ContentStemming::None, true), text),
+            ["runners", "running"]
+        );
+        assert_eq!(
+            tokens(options(ContentStemming::En, true), text),
+            ["runner", "run"]
+        );
+    }
+
+    #[test]
+    fn token_cap_drops_long_tokens() {
+        let blob = "a".repeat(200);
+        let text = format!("hello {blob} world");
+        let capped = options(ContentStemming::None, false);
+        assert_eq!(tokens(capped, &text), ["hello", "world"]);
+
+        let uncapped = ContentAnalysis {
+            max_token_len: 0,
+            ..capped
+        };
+        assert_eq!(tokens(uncapped, &text), ["hello", blob.as_str(), "world"]);
+    }
+
+    #[test]
+    fn tokenizer_names_round_trip() {
+        for (stemming, stopwords) in COMBINATIONS {
+            for max_token_len in [DEFAULT_MAX_TOKEN_LEN, 0, 64] {
+                let options = ContentAnalysis {
+                    stemming,
+                    stopwords,
+                    max_token_len,
+                };
+                assert_eq!(content_analysis(&options.tokenizer()), Some(options));
+            }
+        }
+        assert_eq!(
+            options(ContentStemming::None, false).tokenizer(),
+            "default",
+            "pre-existing indexes keep reading as the default options"
+        );
+        assert_eq!(content_analysis("code_analyzer"), None);
+        assert_eq!(content_analysis("content_max"), None);
+    }
+}
@@ -0,0 +1,260 @@
+//! Size cap for the content index (`extract.content_index_max_bytes`).
+//!
+//! When the index directory outgrows its budget, docs are deleted in `ContentEviction` order
+//! with one range delete per pass, and the segments are merged so the space is released.
+
+use std::ops::Bound;
+use std::path::Path;
+
+use anyhow::Result;
+use core_types::config::ContentEviction;
+use tantivy::query::RangeQuery;
+use tantivy::{IndexWriter, Searcher, Term};
+
+use crate::{ContentIndex, WriterConfig, create_writer, open_reader};
+
+/// Share of the budget an eviction aims for, so the next batch does not trip the cap at once.
+const TARGET_FILL: f64 = 0.9;
+/// Docs differ in size, so the doc count per pass is an estimate; stop after this many.
+const MAX_PASSES: usize = 4;
+
+/// What one [`enforce_size_cap`] call did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EvictionReport {
+    pub evicted_docs: u64,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+/// Bytes the index files in `path` take on disk.
+pub fn index_size_bytes(path: &Path) -> std::io::Result<u64> {
+    let mut total = 0;
+    for entry in std::fs::read_dir(path)? {
+        let meta = entry?.metadata()?;
+        if meta.is_file() {
+            total += meta.len();
+        }
+    }
+    Ok(total)
+}
+
+/// Evict docs in `order` until the index stored at `path` fits `max_bytes`, compacting after
+/// each pass. Does nothing when the index is within budget or `max_bytes` is 0.
+pub fn enforce_size_cap(
+    idx: &ContentIndex,
+    path: &Path,
+    max_bytes: u64,
+    order: ContentEviction,
+    writer_cfg: &WriterConfig,
+) -> Result<EvictionReport> {
+    let bytes_before = index_size_bytes(path)?;
+    let mut report = EvictionReport {
+        evicted_docs: 0,
+        bytes_before,
+        bytes_after: bytes_before,
+    };
+    if max_bytes == 0 || bytes_before <= max_bytes {
+        return Ok(report);
+    }
+
+    let target = (max_bytes as f64 * TARGET_FILL) as u64;
+    let mut writer = create_writer(idx, writer_cfg)?;
+    let reader = open_reader(idx)?;
+    for _ in 0..MAX_PASSES {
+        reader.reload()?;
+        let searcher = reader.searcher();
+        let docs = searcher.num_docs();
+        if docs == 0 || report.bytes_after <= target {
+            break;
+        }
+        let share = 1.0 - target as f64 / report.bytes_after as f64;
+        let count = ((docs as f64 * share).ceil() as u64).clamp(1, docs);
+        let Some(query) = eviction_query(idx, &searcher, order, count as usize)? else {
+            break;
+        };
+        writer.delete_query(Box::new(query))?;
+        writer.commit()?;
+        compact(idx, &mut writer)?;
+
+        reader.reload()?;
+        report.evicted_docs += docs - reader.searcher().num_docs();
+        report.bytes_after = index_size_bytes(path)?;
+    }
+    writer.wait_merging_threads()?;
+    report.bytes_after = index_size_bytes(path)?;
+    Ok(report)
+}
+
+/// A range over the `count` docs first in `order`; ties at the cutoff are evicted together.
+fn eviction_query(
+    idx: &ContentIndex,
+    searcher: &Searcher,
+    order: ContentEviction,
+    count: usize,
+) -> Result<Option<RangeQuery>> {
+    let schema = idx.index.schema();
+    Ok(match order {
+        ContentEviction::OldestModified => {
+            let name = schema.get_field_name(idx.fields.modified);
+            let mut values = Vec::new();
+            for segment in searcher.segment_readers() {
+                let column = segment.fast_fields().i64(name)?;
+                values.extend(segment.doc_ids_alive().filter_map(|doc| column.first(doc)));
+            }
+            values.sort_unstable();
+            values.get(count - 1).map(|&cutoff| {
+                let term = Term::from_field_i64(idx.fields.modified, cutoff);
+                RangeQuery::new(Bound::Unbounded, Bound::Included(term))
+            })
+        }
+        ContentEviction::Largest => {
+            let name = schema.get_field_name(idx.fields.size);
+            let mut values = Vec::new();
+            for segment in searcher.segment_readers() {
+                let column = segment.fast_fields().u64(name)?;
+                values.extend(segment.doc_ids_alive().filter_map(|doc| column.first(doc)));
+            }
+            values.sort_unstable_by(|a, b| b.cmp(a));
+            values.get(count - 1).map(|&cutoff| {
+                let term = Term::from_field_u64(idx.fields.size, cutoff);
+                RangeQuery::new(Bound::Included(term), Bound::Unbounded)
+            })
+        }
+    })
+}
+
+/// Merge every segment so deleted docs are dropped, then remove the files they occupied.
+fn compact(idx: &ContentIndex, writer: &mut IndexWriter) -> Result<()> {
+    let segments = idx.index.searchable_segment_ids()?;
+    if !segments.is_empty() {
+        writer.merge(&segments).wait()?;
+    }
+    writer.garbage_collect_files().wait()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ContentDoc, add_content_doc, open_or_create};
+    use core_types::DocKey;
+    use tantivy::collector::DocSetCollector;
+    use tantivy::query::AllQuery;
+    use tantivy::schema::Value;
+
+    const DOCS: u64 = 400;
+
+    /// Distinct words per doc so the store does not compress the text away.
+    fn text(seed: u64) -> String {
+        (0..300)
+            .map(|i| format!("w{:x}", (seed * 7919 + i * 104_729) % 1_000_003))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn seeded(path: &Path) -> ContentIndex {
+        let idx = open_or_create(path).unwrap();
+        let mut writer = create_writer(&idx, &WriterConfig::default()).unwrap();
+        for i in 0..DOCS {
+            let doc = ContentDoc {
+                key: DocKey::from_parts(1, i),
+                volume: 1,
+                name: Some(format!("f{i}.txt")),
+                path: None,
+                ext: Some("txt".into()),
+                size: DOCS - i,
+                modified: i as i64,
+                content_lang: None,
+                content: text(i),
+            };
+            add_content_doc(&mut writer, &idx.fields, &doc).unwrap();
+        }
+        writer.commit().unwrap();
+        idx
+    }
+
+    fn remaining(idx: &ContentIndex, field: tantivy::schema::Field) -> Vec<i64> {
+        let reader = open_reader(idx).unwrap();
+        reader.reload().unwrap();
+        let searcher = reader.searcher();
+        let addrs = searcher.search(&AllQuery, &DocSetCollector).unwrap();
+        let mut values: Vec<i64> = addrs
+            .into_iter()
+            .map(|addr| {
+                let doc: tantivy::TantivyDocument = searcher.doc(addr).unwrap();
+                let value = doc.get_first(field).unwrap();
+                value
+                    .as_i64()
+                    .or_else(|| value.as_u64().map(|v| v as i64))
+                    .unwrap()
+            })
+            .collect();
+        values.sort_unstable();
+        values
+    }
+
+    #[test]
+    fn within_budget_is_left_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let idx = seeded(dir.path());
+        let size = index_size_bytes(dir.path()).unwrap();
+        let cfg = WriterConfig::default();
+        for cap in [0, size] {
+            let report =
+                enforce_size_cap(&idx, dir.path(), cap, ContentEviction::OldestModified, &cfg)
+                    .unwrap();
+            assert_eq!(report.evicted_docs, 0);
+        }
+        assert_eq!(remaining(&idx, idx.fields.modified).len() as u64, DOCS);
+    }
+
+    #[test]
+    fn over_budget_evicts_oldest_and_shrinks_after_compaction() {
+        let dir = tempfile::tempdir().unwrap();
+        let idx = seeded(dir.path());
+        let before = index_size_bytes(dir.path()).unwrap();
+        let cap = before / 2;
+
+        let report = enforce_size_cap(
+            &idx,
+            dir.path(),
+            cap,
+            ContentEviction::OldestModified,
+            &WriterConfig::default(),
+        )
+        .unwrap();
+        assert!(
+            report.evicted_docs > 0 && report.evicted_docs < DOCS,
+            "{report:?}"
+        );
+        assert_eq!(report.bytes_before, before);
+        assert!(report.bytes_after <= cap, "{report:?}");
+        assert_eq!(index_size_bytes(dir.path()).unwrap(), report.bytes_after);
+
+        // Exactly the newest docs survive.
+        let kept = remaining(&idx, idx.fields.modified);
+        assert_eq!(kept.len() as u64, DOCS - report.evicted_docs);
+        let oldest_kept = report.evicted_docs as i64;
+        assert_eq!(kept, (oldest_kept..DOCS as i64).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn largest_policy_evicts_biggest_files_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let idx = seeded(dir.path());
+        let cap = index_size_bytes(dir.path()).unwrap() / 2;
+
+        let report = enforce_size_cap(
+            &idx,
+            dir.path(),
+            cap,
+            ContentEviction::Largest,
+            &WriterConfig::default(),
+        )
+        .unwrap();
+        assert!(report.evicted_docs > 0);
+        let sizes = remaining(&idx, idx.fields.size);
+        let largest_kept = (DOCS - report.evicted_docs) as i64;
+        assert_eq!(sizes, (1..=largest_kept).collect::<Vec<_>>());
+    }
+}
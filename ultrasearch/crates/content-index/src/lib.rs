@@ -1,16 +1,40 @@
 //! Tantivy-based content index (full-text).
 //!
 //! Schema matches the plan: doc_key, volume, name/path/ext metadata, size,
-//! modified, optional content_lang, and the main `content` text field.
+//! modified, optional content_lang, and the main `content` text field. Source files can
+//! also be indexed into `content_code` with the code analyzer (`features.doc_type_analyzers`),
+//! and paths into `path_segments` one directory per token (`features.path_segments`).
+//! `content` itself can be stemmed, stripped of stopwords and capped in token length
+//! (`search.content_stemming`, `search.content_stopwords`, `search.content_max_token_len`);
+//! like the fields, that is fixed when the index is created.
 
+use std::ops::Bound;
 use std::path::Path;
 
-use anyhow::Result;
-use core_types::DocKey;
+use anyhow::{Context, Result};
+use content_analysis::ContentAnalysis;
+use core_types::config::SearchSection;
+use core_types::{
+    DocKey, IndexOpenOutcome, MIB, WriterBudget, WriterProfile, backup_corrupt_index_dir,
+};
 pub use tantivy::IndexWriter;
-use tantivy::{Index, IndexSettings, ReloadPolicy, schema::document::TantivyDocument, schema::*};
+use tantivy::directory::error::LockError;
+use tantivy::query::RangeQuery;
+use tantivy::{
+    Index, IndexSettings, ReloadPolicy, TantivyError, Term, schema::document::TantivyDocument,
+    schema::*,
+};
 
+pub mod code_analysis;
+pub mod content_analysis;
+pub mod eviction;
 pub mod log_analysis;
+pub mod path_analysis;
+
+/// Name of the code-analyzed copy of `content`.
+pub const CONTENT_CODE_FIELD: &str = "content_code";
+/// Name of the segment-tokenized copy of `path`.
+pub const PATH_SEGMENTS_FIELD: &str = "path_segments";
 
 /// Field handles for the content index schema.
 #[derive(Debug, Clone)]
@@ -24,9 +48,21 @@ pub struct ContentFields {
     pub modified: Field,
     pub content_lang: Field,
     pub content: Field,
+    /// Code-analyzed copy of `content` for source files; `None` when per-extension analysis
+    /// is off or the index predates the field.
+    pub content_code: Option<Field>,
+    /// `path` split into whole directory segments; `None` when `features.path_segments` is off
+    /// or the index predates the field.
+    pub path_segments: Option<Field>,
 }
 
 pub fn build_schema() -> (Schema, ContentFields) {
+    build_schema_with(&SearchSection::default())
+}
+
+/// Build the schema for the `[search]` options that shape it (`content_stemming`,
+/// `content_stopwords`, `content_max_token_len`).
+pub fn build_schema_with(search: &SearchSection) -> (Schema, ContentFields) {
     let mut builder = Schema::builder();
 
     let doc_key = builder.add_u64_field("doc_key", FAST | STORED);
@@ -38,8 +74,28 @@ pub fn build_schema() -> (Schema, ContentFields) {
     let modified = builder.add_i64_field("modified", FAST | STORED);
     let content_lang = builder.add_text_field("content_lang", STRING | STORED);
 
-    // Use default tokenizer for content, but allow overrides via per-field options later if needed.
-    let content = builder.add_text_field("content", TEXT);
+    // Stored so a doc can be fetched back by key; indexes built before this return no text.
+    let content_options = TextOptions::default()
+        .set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer(&ContentAnalysis::from_search(search).tokenizer())
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+        )
+        .set_stored();
+    let content = builder.add_text_field("content", content_options);
+    // Added last so older indexes keep their field ids; not stored since `content` already is.
+    let code_options = TextOptions::default().set_indexing_options(
+        TextFieldIndexing::default()
+            .set_tokenizer(code_analysis::CODE_ANALYZER)
+            .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+    );
+    let content_code = builder.add_text_field(CONTENT_CODE_FIELD, code_options);
+    let path_options = TextOptions::default().set_indexing_options(
+        TextFieldIndexing::default()
+            .set_tokenizer(path_analysis::PATH_SEGMENT_ANALYZER)
+            .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+    );
+    let path_segments = builder.add_text_field(PATH_SEGMENTS_FIELD, path_options);
 
     let fields = ContentFields {
         doc_key,
@@ -51,6 +107,8 @@ pub fn build_schema() -> (Schema, ContentFields) {
         modified,
         content_lang,
         content,
+        content_code: Some(content_code),
+        path_segments: Some(path_segments),
     };
 
     (builder.build(), fields)
@@ -62,12 +120,59 @@ pub struct ContentIndex {
     pub fields: ContentFields,
 }
 
+impl ContentIndex {
+    /// Turn per-extension analysis on or off for docs written and queries built with
+    /// `self.fields`. Off by default; stays off for indexes created before `content_code`.
+    pub fn set_doc_type_analyzers(&mut self, enabled: bool) {
+        self.fields.content_code = enabled
+            .then(|| self.index.schema().get_field(CONTENT_CODE_FIELD).ok())
+            .flatten();
+    }
+
+    /// Turn segment indexing of `path` on or off, like [`Self::set_doc_type_analyzers`].
+    pub fn set_path_segments(&mut self, enabled: bool) {
+        self.fields.path_segments = enabled
+            .then(|| self.index.schema().get_field(PATH_SEGMENTS_FIELD).ok())
+            .flatten();
+    }
+
+    /// Options the `content` field was built with; `None` if its analyzer is not one of ours.
+    pub fn content_analysis(&self) -> Option<ContentAnalysis> {
+        content_analysis::content_analysis(&content_tokenizer(&self.index.schema())?)
+    }
+}
+
+/// Tokenizer recorded on the schema's `content` field.
+fn content_tokenizer(schema: &Schema) -> Option<String> {
+    let field = schema.get_field("content").ok()?;
+    let FieldType::Str(opts) = schema.get_field_entry(field).field_type() else {
+        return None;
+    };
+    Some(opts.get_indexing_options()?.tokenizer().to_string())
+}
+
 fn setup_index(index: &Index) {
     log_analysis::register_log_analyzers(index.tokenizers());
+    code_analysis::register_code_analyzer(index.tokenizers());
+    content_analysis::register_content_analyzers(index.tokenizers());
+    // A non-default token cap has a name of its own; register the one this index uses.
+    if let Some(tokenizer) = content_tokenizer(&index.schema()) {
+        content_analysis::register_content_tokenizer(index.tokenizers(), &tokenizer);
+    }
+    path_analysis::register_path_analyzer(index.tokenizers());
 }
 
 pub fn open_or_create(path: &Path) -> Result<ContentIndex> {
-    let (schema, fields) = build_schema();
+    open_or_create_with(path, &SearchSection::default())
+}
+
+/// Like [`open_or_create`], creating with the `[search]` schema options. An existing index
+/// keeps the analyzer it was built with; compare [`ContentIndex::content_analysis`] to detect
+/// that a rebuild is needed.
+pub fn open_or_create_with(path: &Path, search: &SearchSection) -> Result<ContentIndex> {
+    let (schema, mut fields) = build_schema_with(search);
+    fields.content_code = None;
+    fields.path_segments = None;
     let index = if path.join("meta.json").exists() {
         Index::open_in_dir(path)?
     } else {
@@ -77,9 +182,63 @@ pub fn open_or_create(path: &Path) -> Result<ContentIndex> {
     Ok(ContentIndex { index, fields })
 }
 
+/// Like [`open_or_create`], but an existing index that fails to open is backed up and
+/// replaced by an empty one when `recreate_corrupt` is set, instead of returning the error.
+pub fn open_or_recreate(
+    path: &Path,
+    recreate_corrupt: bool,
+) -> Result<(ContentIndex, IndexOpenOutcome)> {
+    open_or_recreate_with(path, &SearchSection::default(), recreate_corrupt)
+}
+
+/// [`open_or_recreate`] creating with the `[search]` schema options, as in
+/// [`open_or_create_with`].
+pub fn open_or_recreate_with(
+    path: &Path,
+    search: &SearchSection,
+    recreate_corrupt: bool,
+) -> Result<(ContentIndex, IndexOpenOutcome)> {
+    if !path.join("meta.json").exists() {
+        return Ok((
+            open_or_create_with(path, search)?,
+            IndexOpenOutcome::Created,
+        ));
+    }
+    let err = match open_or_create_with(path, search) {
+        Ok(idx) => return Ok((idx, IndexOpenOutcome::Opened)),
+        Err(err) if !recreate_corrupt => {
+            return Err(err.context(format!(
+                "content index at {} is unreadable; set paths.recreate_corrupt_indexes to rebuild it",
+                path.display()
+            )));
+        }
+        Err(err) => err,
+    };
+    let backup = backup_corrupt_index_dir(path)
+        .with_context(|| format!("back up corrupt content index at {}", path.display()))?;
+    tracing::warn!(
+        error = %err,
+        backup = %backup.display(),
+        "content index at {} was unreadable; recreated it empty",
+        path.display()
+    );
+    std::fs::create_dir_all(path)?;
+    Ok((
+        open_or_create_with(path, search)?,
+        IndexOpenOutcome::Recreated { backup },
+    ))
+}
+
 /// Create an in-memory index for tests and benchmarks.
 pub fn create_in_ram() -> Result<ContentIndex> {
-    let (schema, fields) = build_schema();
+    create_in_ram_with(&SearchSection::default())
+}
+
+/// [`create_in_ram`] with the `[search]` schema options.
+pub fn create_in_ram_with(search: &SearchSection) -> Result<ContentIndex> {
+    let (schema, mut fields) = build_schema_with(search);
+    fields.content_code = None;
+    fields.path_segments = None;
     let dir = tantivy::directory::RamDirectory::create();
     let index = Index::create(dir, schema, IndexSettings::default())?;
     setup_index(&index);
@@ -101,11 +260,65 @@ impl Default for WriterConfig {
     }
 }
 
+impl WriterConfig {
+    /// Size an initial-build writer from this machine's free memory and cores.
+    pub fn auto() -> Self {
+        Self::auto_for(WriterProfile::InitialBuild)
+    }
+
+    /// Size a writer for `profile` from this machine's free memory and cores.
+    pub fn auto_for(profile: WriterProfile) -> Self {
+        let mut sys = sysinfo::System::new();
+        sys.refresh_memory();
+        let cpus = std::thread::available_parallelism().map_or(1, |n| n.get());
+        Self::for_resources(profile, sys.available_memory(), cpus)
+    }
+
+    /// Like the metadata writer's budgets, with a higher initial-build ceiling and a larger
+    /// steady-state share since extracted text makes content batches heavier.
+    pub fn for_resources(profile: WriterProfile, available_bytes: u64, cpus: usize) -> Self {
+        let budget = match profile {
+            WriterProfile::InitialBuild => WriterBudget {
+                divisor: 4,
+                floor: 64 * MIB,
+                ceiling: 2048 * MIB,
+                max_threads: 8,
+            },
+            WriterProfile::SteadyState => WriterBudget {
+                divisor: 8,
+                floor: 32 * MIB,
+                ceiling: 256 * MIB,
+                max_threads: 2,
+            },
+        };
+        let (heap_size_bytes, num_threads) = budget.size(available_bytes, cpus);
+        Self {
+            heap_size_bytes,
+            num_threads,
+        }
+    }
+}
+
+/// Returned by [`create_writer`] while another writer holds the index. Tantivy allows one
+/// `IndexWriter` per index directory, across processes: the index-worker holds it for a batch,
+/// the service only briefly to delete docs.
+#[derive(Debug, thiserror::Error)]
+#[error(
+    "the content index is already open for writing by another process \
+     (an index-worker batch or the service); only one writer may hold it at a time"
+)]
+pub struct WriterBusy;
+
+/// Open the index's single writer, failing at once with [`WriterBusy`] if it is taken.
 pub fn create_writer(idx: &ContentIndex, cfg: &WriterConfig) -> Result<IndexWriter> {
-    let writer = idx
+    match idx
         .index
-        .writer_with_num_threads(cfg.num_threads, cfg.heap_size_bytes)?;
-    Ok(writer)
+        .writer_with_num_threads(cfg.num_threads, cfg.heap_size_bytes)
+    {
+        Ok(writer) => Ok(writer),
+        Err(TantivyError::LockFailure(LockError::LockBusy, _)) => Err(WriterBusy.into()),
+        Err(e) => Err(e.into()),
+    }
 }
 
 pub fn open_reader(idx: &ContentIndex) -> Result<tantivy::IndexReader> {
@@ -139,6 +352,9 @@ pub fn to_document(doc: &ContentDoc, fields: &ContentFields) -> TantivyDocument
     }
     if let Some(path) = &doc.path {
         d.add_text(fields.path, path);
+        if let Some(segments) = fields.path_segments {
+            d.add_text(segments, path);
+        }
     }
     if let Some(ext) = &doc.ext {
         d.add_text(fields.ext, ext);
@@ -149,6 +365,11 @@ pub fn to_document(doc: &ContentDoc, fields: &ContentFields) -> TantivyDocument
         d.add_text(fields.content_lang, lang);
     }
     d.add_text(fields.content, &doc.content);
+    if let Some(code) = fields.content_code
+        && code_analysis::is_code_ext(doc.ext.as_deref())
+    {
+        d.add_text(code, &doc.content);
+    }
     d
 }
 
@@ -163,11 +384,49 @@ pub fn add_content_doc(
     Ok(())
 }
 
+/// Replace any existing document with the same key, then add `doc`.
+///
+/// `doc_key` is a fast field rather than an indexed term, so the delete uses a single-value
+/// range query. Re-indexing a doc is therefore idempotent once committed.
+pub fn upsert_content_doc(
+    writer: &mut IndexWriter,
+    fields: &ContentFields,
+    doc: &ContentDoc,
+) -> Result<()> {
+    let term = Term::from_field_u64(fields.doc_key, doc.key.0);
+    writer.delete_query(Box::new(RangeQuery::new(
+        Bound::Included(term.clone()),
+        Bound::Included(term),
+    )))?;
+    add_content_doc(writer, fields, doc)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use tantivy::schema::OwnedValue;
 
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    #[test]
+    fn auto_sizing_respects_clamps() {
+        let tiny = WriterConfig::for_resources(WriterProfile::InitialBuild, 128 * 1024 * 1024, 1);
+        assert_eq!((tiny.heap_size_bytes, tiny.num_threads), (64 * MIB, 1));
+
+        let big = WriterConfig::for_resources(WriterProfile::InitialBuild, 128 * GIB, 64);
+        assert_eq!((big.heap_size_bytes, big.num_threads), (2048 * MIB, 8));
+
+        let steady = WriterConfig::for_resources(WriterProfile::SteadyState, 128 * GIB, 64);
+        assert_eq!((steady.heap_size_bytes, steady.num_threads), (256 * MIB, 2));
+
+        // Zero reported memory (unsupported platform) still yields a usable writer.
+        let unknown = WriterConfig::for_resources(WriterProfile::SteadyState, 0, 0);
+        assert_eq!(
+            (unknown.heap_size_bytes, unknown.num_threads),
+            (32 * MIB, 1)
+        );
+    }
+
     #[test]
     fn schema_has_expected_fields() {
         let (schema, fields) = build_schema();
@@ -181,11 +440,128 @@ mod tests {
             fields.modified,
             fields.content_lang,
             fields.content,
+            fields.content_code.unwrap(),
+            fields.path_segments.unwrap(),
         ] {
             assert!(!schema.get_field_entry(f).name().is_empty());
         }
     }
 
+    #[test]
+    fn code_analyzer_finds_identifiers_by_their_words() {
+        use tantivy::collector::Count;
+        use tantivy::query::QueryParser;
+
+        let mut idx = create_in_ram().unwrap();
+        idx.set_doc_type_analyzers(true);
+        let code = idx.fields.content_code.expect("code field enabled");
+        let mut writer = create_writer(&idx, &WriterConfig::default()).unwrap();
+        for (frn, ext) in [(1, "rs"), (2, "txt")] {
+            let doc = ContentDoc {
+                key: DocKey::from_parts(1, frn),
+                volume: 1,
+                name: Some(format!("user.{ext}")),
+                path: None,
+                ext: Some(ext.into()),
+                size: 10,
+                modified: 0,
+                content_lang: None,
+                content: "let name = getUserName();".into(),
+            };
+            add_content_doc(&mut writer, &idx.fields, &doc).unwrap();
+        }
+        writer.commit().unwrap();
+
+        let searcher = open_reader(&idx).unwrap().searcher();
+        let count = |field, text| {
+            let query = QueryParser::for_index(&idx.index, vec![field])
+                .parse_query(text)
+                .unwrap();
+            searcher.search(&query, &Count).unwrap()
+        };
+        // Only the .rs doc goes through the code analyzer, as `get user name`.
+        assert_eq!(count(code, "getUserName"), 1);
+        assert_eq!(count(code, "\"get user name\""), 1);
+        assert_eq!(count(code, "user"), 1);
+        // Prose keeps the default analyzer, which sees one lowercased word.
+        assert_eq!(count(idx.fields.content, "getUserName"), 2);
+        assert_eq!(count(idx.fields.content, "user"), 0);
+    }
+
+    #[test]
+    fn overlong_tokens_are_not_indexed_when_capped() {
+        let blob = "x".repeat(200);
+        let doc = ContentDoc {
+            key: DocKey::from_parts(1, 1),
+            volume: 1,
+            name: Some("bundle.min.js".into()),
+            path: None,
+            ext: Some("js".into()),
+            size: 10,
+            modified: 0,
+            content_lang: None,
+            content: format!("var render = \"{blob}\";"),
+        };
+        let doc_freq = |search: &SearchSection, text: &str| {
+            let idx = create_in_ram_with(search).unwrap();
+            let mut writer = create_writer(&idx, &WriterConfig::default()).unwrap();
+            add_content_doc(&mut writer, &idx.fields, &doc).unwrap();
+            writer.commit().unwrap();
+            let searcher = open_reader(&idx).unwrap().searcher();
+            let term = Term::from_field_text(idx.fields.content, text);
+            searcher.doc_freq(&term).unwrap()
+        };
+
+        let capped = SearchSection::default();
+        assert_eq!(capped.content_max_token_len, 40);
+        assert_eq!(doc_freq(&capped, "render"), 1);
+        assert_eq!(doc_freq(&capped, &blob), 0);
+
+        let uncapped = SearchSection {
+            content_max_token_len: 0,
+            ..SearchSection::default()
+        };
+        assert_eq!(doc_freq(&uncapped, &blob), 1);
+    }
+
+    #[test]
+    fn custom_token_cap_survives_reopen() {
+        let root = tempfile::tempdir().unwrap();
+        let search = SearchSection {
+            content_max_token_len: 64,
+            ..SearchSection::default()
+        };
+        drop(open_or_create_with(root.path(), &search).unwrap());
+
+        // Reopened with default options, the index keeps (and can run) its own analyzer.
+        let idx = open_or_create(root.path()).unwrap();
+        let built = idx.content_analysis().unwrap();
+        assert_eq!(built.max_token_len, 64);
+        assert!(idx.index.tokenizer_for_field(idx.fields.content).is_ok());
+    }
+
+    #[test]
+    fn doc_type_analyzers_default_off() {
+        let (_, fields) = build_schema();
+        let doc = ContentDoc {
+            key: DocKey::from_parts(1, 2),
+            volume: 1,
+            name: None,
+            path: None,
+            ext: Some("rs".into()),
+            size: 0,
+            modified: 0,
+            content_lang: None,
+            content: "fn main() {}".into(),
+        };
+        let code = fields.content_code.unwrap();
+        assert_eq!(to_document(&doc, &fields).get_all(code).count(), 1);
+
+        let idx = create_in_ram().unwrap();
+        assert!(idx.fields.content_code.is_none());
+        assert_eq!(to_document(&doc, &idx.fields).get_all(code).count(), 0);
+    }
+
     #[test]
     fn to_document_sets_key_and_content() {
         let (_, fields) = build_schema();
@@ -208,6 +584,37 @@ mod tests {
         assert!(vals.next().is_none());
     }
 
+    #[test]
+    fn corrupt_index_recreated_only_when_allowed() {
+        let root = tempfile::tempdir().unwrap();
+        let path = root.path().join("content");
+        std::fs::create_dir_all(&path).unwrap();
+        drop(open_or_create(&path).unwrap());
+        std::fs::write(path.join("meta.json"), b"{\"segments\": [").unwrap();
+
+        assert!(open_or_recreate(&path, false).is_err());
+
+        let (idx, outcome) = open_or_recreate(&path, true).unwrap();
+        assert!(matches!(outcome, IndexOpenOutcome::Recreated { .. }));
+        assert_eq!(open_reader(&idx).unwrap().searcher().num_docs(), 0);
+    }
+
+    #[test]
+    fn second_writer_fails_fast_while_one_is_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let idx = open_or_create(dir.path()).unwrap();
+        let first = create_writer(&idx, &WriterConfig::default()).unwrap();
+
+        let Err(err) = create_writer(&idx, &WriterConfig::default()) else {
+            panic!("second writer acquired");
+        };
+        assert!(err.is::<WriterBusy>(), "{err:#}");
+        assert!(err.to_string().contains("already open for writing"));
+
+        drop(first);
+        create_writer(&idx, &WriterConfig::default()).unwrap();
+    }
+
     #[test]
     fn create_ram_index_works() {
         let idx = create_in_ram().unwrap();
@@ -237,3 +644,31 @@ fn add_content_doc_appends() {
     let searcher = reader.searcher();
     assert_eq!(searcher.num_docs(), 1);
 }
+
+#[test]
+fn upsert_content_doc_replaces_same_key() {
+    let idx = create_in_ram().expect("in ram");
+    let mut writer = create_writer(&idx, &WriterConfig::default()).unwrap();
+    let mut doc = ContentDoc {
+        key: DocKey::from_parts(1, 2),
+        volume: 1,
+        name: Some("foo.txt".into()),
+        path: Some(r"C:\foo.txt".into()),
+        ext: Some("txt".into()),
+        size: 10,
+        modified: 123,
+        content_lang: None,
+        content: "first".into(),
+    };
+    upsert_content_doc(&mut writer, &idx.fields, &doc).unwrap();
+    writer.commit().unwrap();
+    doc.content = "second".into();
+    upsert_content_doc(&mut writer, &idx.fields, &doc).unwrap();
+    doc.key = DocKey::from_parts(1, 3);
+    upsert_content_doc(&mut writer, &idx.fields, &doc).unwrap();
+    writer.commit().unwrap();
+
+    let reader = open_reader(&idx).unwrap();
+    reader.reload().unwrap();
+    assert_eq!(reader.searcher().num_docs(), 2);
+}
@@ -0,0 +1,19 @@
+//! Whole-segment analysis of `path` (`features.path_segments`).
+//!
+//! `C:\Users\me\Projects\x.txt` indexes as `c:`, `users`, `me`, `projects`, `x.txt`, so
+//! `path:Projects` matches that directory but not `Projects-Old` or `MyProjects`.
+
+use tantivy::tokenizer::{
+    LowerCaser, RegexTokenizer, RemoveLongFilter, TextAnalyzer, TokenizerManager,
+};
+
+pub const PATH_SEGMENT_ANALYZER: &str = "path_segment";
+
+pub fn register_path_analyzer(manager: &TokenizerManager) {
+    let segments = RegexTokenizer::new(r"[^\\/]+").expect("valid path segment pattern");
+    let analyzer = TextAnalyzer::builder(segments)
+        .filter(RemoveLongFilter::limit(255))
+        .filter(LowerCaser)
+        .build();
+    manager.register(PATH_SEGMENT_ANALYZER, analyzer);
+}
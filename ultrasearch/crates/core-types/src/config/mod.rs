@@ -1,5 +1,7 @@
 use std::{
+    ffi::OsString,
     fs,
+    io::Write,
     path::{Path, PathBuf},
 };
 
@@ -7,6 +9,7 @@ use anyhow::Result;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::sync::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 /// Global configuration root loaded from `.env` + `config/config.toml`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +34,16 @@ pub struct AppConfig {
     pub extract: ExtractSection,
     #[serde(default)]
     pub semantic: SemanticSection,
+    #[serde(default)]
+    pub volume_settings: VolumeSettingsSection,
+    #[serde(default)]
+    pub search: SearchSection,
+    #[serde(default)]
+    pub ipc: IpcSection,
+    /// Named queries recalled by `ultrasearch saved run <name>` and the UI. Kept last so the
+    /// `[[saved_searches]]` tables follow every other section in the file.
+    #[serde(default)]
+    pub saved_searches: Vec<SavedSearch>,
 }
 
 /// Load config, creating a default config file if none exists at the target path.
@@ -40,18 +53,63 @@ pub fn load_or_create_config(path: Option<&Path>) -> Result<AppConfig> {
         .unwrap_or_else(default_config_path);
 
     if !target.exists() {
-        if let Some(parent) = target.parent() {
-            fs::create_dir_all(parent)?;
-        }
         let mut cfg = AppConfig::default();
         apply_placeholders(&mut cfg);
-        let toml = toml::to_string_pretty(&cfg)?;
-        fs::write(&target, toml)?;
+        save_config_atomic(&target, &cfg)?;
     }
 
     load_config(path)
 }
 
+/// Write `cfg` to `path` so readers see the old file or the new one, never a torn write. The
+/// TOML goes to a synced sibling temp file that is renamed over `path`, while an advisory lock
+/// on `<path>.lock` serializes concurrent editors (service, CLI, onboarding UI).
+pub fn save_config_atomic(path: &Path, cfg: &AppConfig) -> Result<()> {
+    let toml = toml::to_string_pretty(cfg)?;
+    write_atomic(path, &toml, |_| Ok(()))
+}
+
+/// [`save_config_atomic`] with a hook between the synced temp write and the rename.
+fn write_atomic(
+    path: &Path,
+    contents: &str,
+    before_rename: impl FnOnce(&Path) -> Result<()>,
+) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let lock = fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(sibling(path, "lock"))?;
+    // Released when `lock` drops. A temp file left by a writer that crashed mid-write is
+    // simply truncated by the next one.
+    lock.lock()?;
+    let tmp = sibling(path, "tmp");
+    let result = (|| -> Result<()> {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        before_rename(&tmp)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// `config.toml` -> `config.toml.<suffix>` in the same directory, so a rename never crosses
+/// volumes.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(".");
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
 #[allow(clippy::derivable_impls)]
 impl Default for AppConfig {
     fn default() -> Self {
@@ -64,12 +122,36 @@ impl Default for AppConfig {
             paths: PathsSection::default(),
             extract: ExtractSection::default(),
             semantic: SemanticSection::default(),
+            volume_settings: VolumeSettingsSection::default(),
+            search: SearchSection::default(),
+            ipc: IpcSection::default(),
             volumes: Vec::new(),
             content_index_volumes: Vec::new(),
+            saved_searches: Vec::new(),
         }
     }
 }
 
+/// A query saved under a name, in the same syntax the search box and `ultrasearch search` take.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedSearch {
+    pub name: String,
+    pub query: String,
+    #[serde(default)]
+    pub mode: SavedSearchMode,
+}
+
+/// Search mode stored with a saved search.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SavedSearchMode {
+    #[default]
+    Auto,
+    Name,
+    Content,
+    Hybrid,
+}
+
 /// Common app-wide metadata.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppSection {
@@ -165,6 +247,10 @@ pub struct MetricsSection {
     pub request_latency_buckets: Vec<f64>,
     #[serde(default = "default_worker_failure_threshold")]
     pub worker_failure_threshold: u64,
+    /// Searches slower than this are logged at `warn` and counted in `slow_query_total`;
+    /// 0 disables.
+    #[serde(default = "default_slow_query_ms")]
+    pub slow_query_ms: u64,
 }
 
 impl Default for MetricsSection {
@@ -176,6 +262,7 @@ impl Default for MetricsSection {
             sample_interval_secs: default_sample_interval(),
             request_latency_buckets: default_latency_buckets(),
             worker_failure_threshold: default_worker_failure_threshold(),
+            slow_query_ms: default_slow_query_ms(),
         }
     }
 }
@@ -196,6 +283,10 @@ fn default_worker_failure_threshold() -> u64 {
     3
 }
 
+fn default_slow_query_ms() -> u64 {
+    1000
+}
+
 /// Feature flags toggling advanced modules.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FeaturesSection {
@@ -217,6 +308,10 @@ pub struct FeaturesSection {
     pub mem_opt_tuning: bool,
     #[serde(default)]
     pub auto_tuning: bool,
+    /// Index each path directory as a whole token so `path:Projects` matches `...\Projects\...`
+    /// but not `Projects-Old`.
+    #[serde(default)]
+    pub path_segments: bool,
 }
 
 #[allow(clippy::derivable_impls)]
@@ -232,6 +327,7 @@ impl Default for FeaturesSection {
             log_dataset_mode: false,
             mem_opt_tuning: false,
             auto_tuning: false,
+            path_segments: false,
         }
     }
 }
@@ -247,16 +343,95 @@ pub struct SchedulerSection {
     pub max_records_per_tick: u64,
     #[serde(default = "default_usn_chunk_bytes")]
     pub usn_chunk_bytes: u64,
-    #[serde(default = "default_cpu_soft")]
-    pub cpu_soft_limit_pct: u64,
-    #[serde(default = "default_cpu_hard")]
-    pub cpu_hard_limit_pct: u64,
+    /// How eagerly idle time goes to indexing; sets the CPU limits and per-tick batch sizes
+    /// below that are left unset.
+    #[serde(default)]
+    pub profile: IndexingProfile,
+    /// Metadata jobs wait while CPU use is at or above this; unset takes the profile's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_soft_limit_pct: Option<u64>,
+    /// Content jobs wait while CPU use is at or above this; unset takes the profile's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_hard_limit_pct: Option<u64>,
+    /// Metadata jobs taken per scheduler tick; unset takes the profile's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata_batch_files: Option<u64>,
+    /// Content jobs taken per scheduler tick; unset takes the profile's.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_batch_files: Option<u64>,
     #[serde(default = "default_disk_busy")]
     pub disk_busy_bytes_per_s: u64,
     #[serde(default = "default_content_batch")]
     pub content_batch_size: u64,
     #[serde(default)]
     pub power_save_mode: bool,
+    /// Retries a failed content job gets before it is dead-lettered.
+    #[serde(default = "default_max_job_retries")]
+    pub max_job_retries: u32,
+    /// Content indexing of a volume pauses while it has less free space than this; 0 disables.
+    #[serde(default = "default_min_free_disk_bytes")]
+    pub min_free_disk_bytes: u64,
+    /// Resident memory of the service past which writers are committed and caches trimmed;
+    /// 0 disables the check.
+    #[serde(default)]
+    pub memory_ceiling_bytes: u64,
+    /// Content jobs waiting for extraction; past this the `content_queue_policy` applies.
+    #[serde(default = "default_content_queue_capacity")]
+    pub content_queue_capacity: u64,
+    #[serde(default)]
+    pub content_queue_policy: ContentQueuePolicy,
+    /// With `features.auto_tuning`, the lowest the tuner may scale the CPU limits, disk-busy
+    /// threshold and batch sizes, in percent of their configured values.
+    #[serde(default = "default_auto_tuning_min_pct")]
+    pub auto_tuning_min_pct: u64,
+    /// The highest the tuner may scale them, in percent.
+    #[serde(default = "default_auto_tuning_max_pct")]
+    pub auto_tuning_max_pct: u64,
+}
+
+/// Named presets for the scheduler's CPU limits and batch sizes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexingProfile {
+    /// Index only on a quiet machine, a little at a time.
+    Conservative,
+    #[default]
+    Balanced,
+    /// Keep indexing under moderate load, in large batches; for machines with headroom.
+    Aggressive,
+}
+
+impl IndexingProfile {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Conservative => "conservative",
+            Self::Balanced => "balanced",
+            Self::Aggressive => "aggressive",
+        }
+    }
+}
+
+/// What happens to a new content job while the content queue is full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentQueuePolicy {
+    /// The producer waits for room; nothing is dropped.
+    Block,
+    /// The longest-waiting job is dropped to make room.
+    DropOldest,
+    /// The new job is dropped.
+    #[default]
+    DropNew,
+}
+
+impl ContentQueuePolicy {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Block => "block",
+            Self::DropOldest => "drop_oldest",
+            Self::DropNew => "drop_new",
+        }
+    }
 }
 
 impl Default for SchedulerSection {
@@ -266,11 +441,21 @@ impl Default for SchedulerSection {
             idle_deep_seconds: default_idle_deep(),
             max_records_per_tick: default_max_records_per_tick(),
             usn_chunk_bytes: default_usn_chunk_bytes(),
-            cpu_soft_limit_pct: default_cpu_soft(),
-            cpu_hard_limit_pct: default_cpu_hard(),
+            profile: IndexingProfile::default(),
+            cpu_soft_limit_pct: None,
+            cpu_hard_limit_pct: None,
+            metadata_batch_files: None,
+            content_batch_files: None,
             disk_busy_bytes_per_s: default_disk_busy(),
             content_batch_size: default_content_batch(),
             power_save_mode: true, // Default to enabled
+            max_job_retries: default_max_job_retries(),
+            min_free_disk_bytes: default_min_free_disk_bytes(),
+            memory_ceiling_bytes: 0,
+            content_queue_capacity: default_content_queue_capacity(),
+            content_queue_policy: ContentQueuePolicy::default(),
+            auto_tuning_min_pct: default_auto_tuning_min_pct(),
+            auto_tuning_max_pct: default_auto_tuning_max_pct(),
         }
     }
 }
@@ -287,18 +472,27 @@ fn default_max_records_per_tick() -> u64 {
 fn default_usn_chunk_bytes() -> u64 {
     1_048_576
 }
-fn default_cpu_soft() -> u64 {
-    50
-}
-fn default_cpu_hard() -> u64 {
-    80
-}
 fn default_disk_busy() -> u64 {
     10 * 1024 * 1024
 }
 fn default_content_batch() -> u64 {
     1000
 }
+fn default_max_job_retries() -> u32 {
+    3
+}
+fn default_content_queue_capacity() -> u64 {
+    100_000
+}
+fn default_auto_tuning_min_pct() -> u64 {
+    50
+}
+fn default_auto_tuning_max_pct() -> u64 {
+    200
+}
+fn default_min_free_disk_bytes() -> u64 {
+    1024 * 1024 * 1024
+}
 
 /// Index and state paths.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -311,6 +505,10 @@ pub struct PathsSection {
     pub state_dir: String,
     #[serde(default = "default_jobs_dir")]
     pub jobs_dir: String,
+    /// Back up and recreate an index that fails to open instead of refusing to start; the
+    /// startup scan then repopulates it.
+    #[serde(default)]
+    pub recreate_corrupt_indexes: bool,
 }
 
 impl Default for PathsSection {
@@ -320,6 +518,7 @@ impl Default for PathsSection {
             content_index: default_content_index_path(),
             state_dir: default_state_dir(),
             jobs_dir: default_jobs_dir(),
+            recreate_corrupt_indexes: false,
         }
     }
 }
@@ -348,6 +547,34 @@ pub struct ExtractSection {
     pub ocr_enabled: bool,
     #[serde(default = "default_ocr_max_pages")]
     pub ocr_max_pages: u64,
+    /// Wall-clock limit per file; slower extractions are abandoned and the file is skipped.
+    /// 0 disables.
+    #[serde(default = "default_extract_timeout_secs")]
+    pub timeout_secs: u64,
+    /// On-disk budget for the content index; above it, docs are evicted in `content_eviction`
+    /// order and the index is compacted. 0 disables the cap.
+    #[serde(default)]
+    pub content_index_max_bytes: u64,
+    #[serde(default)]
+    pub content_eviction: ContentEviction,
+    /// Extensions (no dot) to extract; when non-empty, every other file is indexed by name only.
+    #[serde(default)]
+    pub allowed_exts: Vec<String>,
+    /// Extensions (no dot) never extracted; wins over `allowed_exts`.
+    #[serde(default)]
+    pub denied_exts: Vec<String>,
+}
+
+/// Which content docs a `content_index_max_bytes` cap evicts first. Evicted files stay
+/// searchable by name; their text is indexed again when they change.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentEviction {
+    /// Files with the oldest `modified` time.
+    #[default]
+    OldestModified,
+    /// The largest files.
+    Largest,
 }
 
 impl Default for ExtractSection {
@@ -357,6 +584,11 @@ impl Default for ExtractSection {
             max_chars_per_file: default_max_chars(),
             ocr_enabled: false,
             ocr_max_pages: default_ocr_max_pages(),
+            timeout_secs: default_extract_timeout_secs(),
+            content_index_max_bytes: 0,
+            content_eviction: ContentEviction::default(),
+            allowed_exts: Vec::new(),
+            denied_exts: Vec::new(),
         }
     }
 }
@@ -370,6 +602,207 @@ fn default_max_chars() -> u64 {
 fn default_ocr_max_pages() -> u64 {
     10
 }
+fn default_extract_timeout_secs() -> u64 {
+    60
+}
+
+/// How enumeration treats special NTFS entries.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolumeSettingsSection {
+    /// Index junctions, symlinks and other reparse points (off: they can loop or double-index).
+    #[serde(default)]
+    pub index_reparse_points: bool,
+    /// Keep metadata for offline/cloud placeholders. Their content is never extracted.
+    #[serde(default = "default_index_offline_files")]
+    pub index_offline_files: bool,
+    /// Include/exclude globs per volume, each applied only to the volume mounted at its `mount`.
+    #[serde(default)]
+    pub paths: Vec<VolumePaths>,
+    /// Delete a volume's docs when a config reload drops it from `volumes`.
+    #[serde(default)]
+    pub purge_removed_volumes: bool,
+}
+
+impl Default for VolumeSettingsSection {
+    fn default() -> Self {
+        Self {
+            index_reparse_points: false,
+            index_offline_files: default_index_offline_files(),
+            paths: Vec::new(),
+            purge_removed_volumes: false,
+        }
+    }
+}
+
+/// Path globs for one volume (`[[volume_settings.paths]]`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VolumePaths {
+    /// Mount of the volume the globs apply to, formatted like `volumes` (`C:\`).
+    pub mount: String,
+    /// Globs to index; when non-empty only matching paths are kept.
+    #[serde(default)]
+    pub include_paths: Vec<String>,
+    /// Globs to skip; wins over `include_paths`.
+    #[serde(default)]
+    pub exclude_paths: Vec<String>,
+}
+
+fn default_index_offline_files() -> bool {
+    true
+}
+
+/// How the metadata index analyzes file names. Baked into the index schema: changing it
+/// requires rebuilding the metadata index.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NameAnalyzer {
+    /// Split into words, keep case and accents (`Resumé` only matches `Resumé`).
+    Exact,
+    /// Split into words and lowercase.
+    #[default]
+    Lowercase,
+    /// Lowercase and fold accents to ASCII (`resumé` matches `resume`).
+    AsciiFolded,
+}
+
+/// Stemming applied to the content index's `content` field. Baked into the index schema like
+/// [`NameAnalyzer`]: changing it requires rebuilding the content index.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentStemming {
+    /// Index words as written (lowercased).
+    #[default]
+    None,
+    /// English Snowball stemmer: `running` and `runs` both index as `run`.
+    En,
+}
+
+/// Query-side behaviour of search.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchSection {
+    #[serde(default)]
+    pub name_analyzer: NameAnalyzer,
+    /// Stemming of extracted text. Schema change of the content index.
+    #[serde(default)]
+    pub content_stemming: ContentStemming,
+    /// Drop English stopwords (`the`, `and`, ...) from extracted text. Schema change, like
+    /// `content_stemming`.
+    #[serde(default)]
+    pub content_stopwords: bool,
+    /// Drop tokens of this many bytes or more from extracted text (minified code, base64,
+    /// hashes); 0 keeps every token. Schema change, like `content_stemming`.
+    #[serde(default = "default_content_max_token_len")]
+    pub content_max_token_len: usize,
+    /// Index name n-grams so substring queries (`port` -> `report.txt`) work. Off by default:
+    /// it grows the metadata index several-fold. Schema change, like `name_analyzer`.
+    #[serde(default)]
+    pub name_ngrams: bool,
+    #[serde(default = "default_ngram_min")]
+    pub ngram_min: usize,
+    #[serde(default = "default_ngram_max")]
+    pub ngram_max: usize,
+    /// Paths left out of every result unless a request sets `include_system`. Matched word by
+    /// word against the indexed path, so a bare directory name covers every volume.
+    #[serde(default = "default_excluded_paths")]
+    pub excluded_paths: Vec<String>,
+    /// Fields an unqualified term searches, each optionally boosted (`"name^3"`). Empty keeps
+    /// each index's built-in set: name and path for metadata, name and content for content.
+    #[serde(default)]
+    pub default_fields: Vec<String>,
+    /// Most hits one search returns; a larger `limit` is clamped and the response marked
+    /// `truncated`.
+    #[serde(default = "default_max_limit")]
+    pub max_limit: u32,
+    /// Re-run the most recent UI searches once at startup so the first real one hits warm
+    /// readers and caches.
+    #[serde(default)]
+    pub preload_history: bool,
+    /// How many history entries `preload_history` re-runs, newest first.
+    #[serde(default = "default_preload_queries")]
+    pub preload_queries: usize,
+    /// Content hit snippets kept per (document, query terms) so paging and re-renders don't
+    /// rebuild them; 0 disables the cache.
+    #[serde(default = "default_snippet_cache_size")]
+    pub snippet_cache_size: usize,
+}
+
+impl Default for SearchSection {
+    fn default() -> Self {
+        Self {
+            name_analyzer: NameAnalyzer::default(),
+            content_stemming: ContentStemming::default(),
+            content_stopwords: false,
+            content_max_token_len: default_content_max_token_len(),
+            name_ngrams: false,
+            ngram_min: default_ngram_min(),
+            ngram_max: default_ngram_max(),
+            excluded_paths: default_excluded_paths(),
+            default_fields: Vec::new(),
+            max_limit: default_max_limit(),
+            preload_history: false,
+            preload_queries: default_preload_queries(),
+            snippet_cache_size: default_snippet_cache_size(),
+        }
+    }
+}
+
+impl SearchSection {
+    /// `default_fields` as `(field, boost)` pairs; a bare field name has boost 1.
+    pub fn default_field_boosts(&self) -> Result<Vec<(&str, f32)>> {
+        self.default_fields
+            .iter()
+            .map(|spec| {
+                let (name, boost) = match spec.split_once('^') {
+                    Some((name, boost)) => (name.trim(), boost.trim().parse::<f32>().ok()),
+                    None => (spec.trim(), Some(1.0)),
+                };
+                if !DEFAULT_FIELD_NAMES.contains(&name) {
+                    anyhow::bail!(
+                        "search.default_fields: unknown field `{name}` (expected one of {})",
+                        DEFAULT_FIELD_NAMES.join(", ")
+                    );
+                }
+                match boost {
+                    Some(boost) if boost.is_finite() && boost > 0.0 => Ok((name, boost)),
+                    _ => anyhow::bail!("search.default_fields: `{spec}` needs a positive boost"),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Text fields an unqualified term can search.
+const DEFAULT_FIELD_NAMES: &[&str] = &["name", "path", "ext", "content"];
+
+fn default_content_max_token_len() -> usize {
+    40
+}
+fn default_max_limit() -> u32 {
+    10_000
+}
+fn default_preload_queries() -> usize {
+    20
+}
+fn default_snippet_cache_size() -> usize {
+    1024
+}
+
+fn default_ngram_min() -> usize {
+    3
+}
+fn default_ngram_max() -> usize {
+    5
+}
+fn default_excluded_paths() -> Vec<String> {
+    [
+        "$Recycle.Bin",
+        "System Volume Information",
+        r"Windows\Temp",
+        r"AppData\Local\Temp",
+    ]
+    .map(String::from)
+    .to_vec()
+}
 
 /// Semantic search configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -399,13 +832,58 @@ fn default_semantic_index_dir() -> String {
     "{data_dir}/index/semantic".into()
 }
 
+/// Named-pipe / socket server limits.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpcSection {
+    /// Largest request or response frame accepted, checked against the length prefix before
+    /// the body is allocated. Longer frames close the connection.
+    #[serde(default = "default_max_frame_bytes")]
+    pub max_frame_bytes: usize,
+    /// Who may connect to the named pipe. Ignored by the Unix socket transport.
+    #[serde(default)]
+    pub pipe_access: PipeAccess,
+}
+
+impl Default for IpcSection {
+    fn default() -> Self {
+        Self {
+            max_frame_bytes: default_max_frame_bytes(),
+            pipe_access: PipeAccess::default(),
+        }
+    }
+}
+
+/// Access granted by the named pipe's security descriptor. SYSTEM and administrators always
+/// have full control.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipeAccess {
+    /// Interactively logged-on users may connect, read and write, so an unelevated UI can
+    /// reach a service running as LocalSystem.
+    #[default]
+    Interactive,
+    /// Only SYSTEM and administrators; the UI and CLI must run elevated.
+    AdminsOnly,
+}
+
+/// Same as `ipc::framing::DEFAULT_MAX_FRAME_BYTES` (16 MiB).
+fn default_max_frame_bytes() -> usize {
+    16 * 1024 * 1024
+}
+
 static CONFIG: Lazy<RwLock<AppConfig>> = Lazy::new(|| RwLock::new(AppConfig::default()));
+static GENERATION: AtomicU64 = AtomicU64::new(0);
 
 /// Get a clone of the currently loaded configuration.
 pub fn get_current_config() -> AppConfig {
     CONFIG.read().expect("config lock poisoned").clone()
 }
 
+/// Bumped every time a config file is (re)loaded, so long-running tasks can notice reloads.
+pub fn config_generation() -> u64 {
+    GENERATION.load(Ordering::Acquire)
+}
+
 /// Load configuration from .env and a TOML file (default: `config/config.toml`).
 ///
 /// Returns a clone of the current configuration.
@@ -426,13 +904,11 @@ pub fn reload_config(path: Option<&Path>) -> Result<AppConfig> {
         .map_err(|_| anyhow::anyhow!("config lock poisoned"))?;
 
     if target.exists() {
-        let raw = fs::read_to_string(&target)?;
         // Load into a temporary to validate
-        let mut file_cfg: AppConfig = toml::from_str(&raw)?;
-        apply_placeholders(&mut file_cfg);
-        file_cfg.validate()?;
+        let file_cfg = check_config_file(&target)?;
 
         *lock = file_cfg.clone();
+        GENERATION.fetch_add(1, Ordering::Release);
         Ok(file_cfg)
     } else {
         // If no file, return what we have (defaults or previous)
@@ -440,6 +916,20 @@ pub fn reload_config(path: Option<&Path>) -> Result<AppConfig> {
     }
 }
 
+/// Parse config TOML, expand placeholders and validate it, as a load would.
+pub fn parse_config(raw: &str) -> Result<AppConfig> {
+    let mut cfg: AppConfig = toml::from_str(raw)?;
+    apply_placeholders(&mut cfg);
+    cfg.validate()?;
+    Ok(cfg)
+}
+
+/// [`parse_config`] on a file, leaving the loaded global config untouched.
+pub fn check_config_file(path: &Path) -> Result<AppConfig> {
+    let raw = fs::read_to_string(path)?;
+    parse_config(&raw)
+}
+
 impl AppConfig {
     /// Validate configuration constraints.
     pub fn validate(&self) -> Result<()> {
@@ -454,8 +944,88 @@ impl AppConfig {
                 "Feature 'semantic_search' requires a valid model configuration"
             ));
         }
+        if self.search.name_ngrams
+            && (self.search.ngram_min == 0 || self.search.ngram_min > self.search.ngram_max)
+        {
+            return Err(anyhow::anyhow!(
+                "search.ngram_min must be between 1 and search.ngram_max"
+            ));
+        }
+        self.search.default_field_boosts()?;
+        if self.search.max_limit == 0 {
+            return Err(anyhow::anyhow!("search.max_limit must be at least 1"));
+        }
+        if self.scheduler.content_queue_capacity == 0 {
+            return Err(anyhow::anyhow!(
+                "scheduler.content_queue_capacity must be at least 1"
+            ));
+        }
+        let (tune_min, tune_max) = (
+            self.scheduler.auto_tuning_min_pct,
+            self.scheduler.auto_tuning_max_pct,
+        );
+        if tune_min == 0 || tune_min > 100 || tune_max < 100 {
+            return Err(anyhow::anyhow!(
+                "scheduler.auto_tuning_min_pct must be between 1 and 100 and \
+                 scheduler.auto_tuning_max_pct at least 100 (got {tune_min} and {tune_max})"
+            ));
+        }
+        if self.ipc.max_frame_bytes == 0 || self.ipc.max_frame_bytes > u32::MAX as usize {
+            return Err(anyhow::anyhow!(
+                "ipc.max_frame_bytes must be between 1 and {}",
+                u32::MAX
+            ));
+        }
+        for (i, saved) in self.saved_searches.iter().enumerate() {
+            if saved.name.trim().is_empty() {
+                return Err(anyhow::anyhow!("saved_searches entries need a name"));
+            }
+            if self.saved_searches[..i]
+                .iter()
+                .any(|s| s.name == saved.name)
+            {
+                return Err(anyhow::anyhow!(
+                    "saved search {:?} is defined more than once",
+                    saved.name
+                ));
+            }
+        }
         Ok(())
     }
+
+    /// `<data_dir>/ui/history.json`, where the UI keeps recent queries; `None` while the data
+    /// dir is still an unexpanded default.
+    pub fn search_history_path(&self) -> Option<PathBuf> {
+        let data_dir = &self.app.data_dir;
+        if data_dir.contains('%') || data_dir.contains('$') {
+            return None;
+        }
+        Some(PathBuf::from(data_dir).join("ui").join("history.json"))
+    }
+
+    /// The saved search called `name`.
+    pub fn saved_search(&self, name: &str) -> Option<&SavedSearch> {
+        self.saved_searches.iter().find(|s| s.name == name)
+    }
+
+    /// Save `search`, replacing any existing one with the same name in place.
+    pub fn add_saved_search(&mut self, search: SavedSearch) {
+        match self
+            .saved_searches
+            .iter_mut()
+            .find(|s| s.name == search.name)
+        {
+            Some(existing) => *existing = search,
+            None => self.saved_searches.push(search),
+        }
+    }
+
+    /// Forget the saved search called `name`; `false` if there was none.
+    pub fn remove_saved_search(&mut self, name: &str) -> bool {
+        let before = self.saved_searches.len();
+        self.saved_searches.retain(|s| s.name != name);
+        self.saved_searches.len() != before
+    }
 }
 
 /// Replace `{data_dir}` placeholder tokens with the configured data_dir,
@@ -580,8 +1150,10 @@ mod tests {
         base.paths = override_cfg.paths;
         base.extract = override_cfg.extract;
         base.semantic = override_cfg.semantic;
+        base.ipc = override_cfg.ipc;
         base.volumes = override_cfg.volumes;
         base.content_index_volumes = override_cfg.content_index_volumes;
+        base.saved_searches = override_cfg.saved_searches;
         base
     }
 
@@ -593,6 +1165,72 @@ mod tests {
         assert!(cfg.validate().is_err());
     }
 
+    #[test]
+    fn parse_config_reports_the_failed_constraint() {
+        let err = parse_config("[features]\ndelta_index = true\n").unwrap_err();
+        assert!(
+            err.to_string().contains("requires 'multi_tier_index'"),
+            "{err}"
+        );
+        let cfg =
+            parse_config("[features]\ndelta_index = true\nmulti_tier_index = true\n").unwrap();
+        assert!(cfg.features.delta_index);
+        assert!(parse_config("[features\n").is_err());
+    }
+
+    #[test]
+    fn interrupted_save_keeps_previous_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config").join("config.toml");
+        let mut cfg = AppConfig {
+            volumes: vec!["C:\\".into()],
+            ..Default::default()
+        };
+        save_config_atomic(&path, &cfg).unwrap();
+
+        cfg.volumes = vec!["D:\\".into()];
+        let toml = toml::to_string_pretty(&cfg).unwrap();
+        let err = write_atomic(&path, &toml, |_| Err(anyhow::anyhow!("simulated crash")));
+        assert!(err.unwrap_err().to_string().contains("simulated crash"));
+        assert_eq!(check_config_file(&path).unwrap().volumes, ["C:\\"]);
+        assert!(!sibling(&path, "tmp").exists(), "temp file discarded");
+
+        save_config_atomic(&path, &cfg).unwrap();
+        assert_eq!(check_config_file(&path).unwrap().volumes, ["D:\\"]);
+    }
+
+    #[test]
+    fn saved_searches_add_replace_list_and_remove() {
+        let mut cfg = AppConfig::default();
+        let saved = |name: &str, query: &str, mode| SavedSearch {
+            name: name.into(),
+            query: query.into(),
+            mode,
+        };
+        cfg.add_saved_search(saved("pdfs", "ext:pdf", SavedSearchMode::Name));
+        cfg.add_saved_search(saved("notes", "meeting notes", SavedSearchMode::Auto));
+        cfg.add_saved_search(saved("pdfs", "ext:pdf size:>1M", SavedSearchMode::Hybrid));
+        let names: Vec<&str> = cfg.saved_searches.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["pdfs", "notes"]);
+        assert_eq!(cfg.saved_search("pdfs").unwrap().query, "ext:pdf size:>1M");
+        assert!(cfg.validate().is_ok());
+
+        let reloaded = parse_config(&toml::to_string_pretty(&cfg).unwrap()).unwrap();
+        assert_eq!(reloaded.saved_searches, cfg.saved_searches);
+        assert_eq!(
+            reloaded.saved_search("pdfs").unwrap().mode,
+            SavedSearchMode::Hybrid
+        );
+
+        assert!(cfg.remove_saved_search("pdfs"));
+        assert!(!cfg.remove_saved_search("pdfs"));
+        assert!(cfg.saved_search("pdfs").is_none());
+
+        cfg.saved_searches
+            .push(saved("notes", "other", SavedSearchMode::Auto));
+        assert!(cfg.validate().is_err());
+    }
+
     #[test]
     fn validation_accepts_valid_combo() {
         let mut cfg = AppConfig::default();
@@ -642,8 +1280,39 @@ mod tests {
         assert_eq!(cfg.scheduler.idle_deep_seconds, 60);
         assert_eq!(cfg.scheduler.max_records_per_tick, 10_000);
         assert_eq!(cfg.scheduler.usn_chunk_bytes, 1_024 * 1_024);
-        assert_eq!(cfg.scheduler.cpu_soft_limit_pct, 50);
-        assert_eq!(cfg.scheduler.cpu_hard_limit_pct, 80);
+        assert_eq!(cfg.scheduler.profile, IndexingProfile::Balanced);
+        assert_eq!(cfg.scheduler.cpu_soft_limit_pct, None);
+        assert_eq!(cfg.scheduler.cpu_hard_limit_pct, None);
+        assert_eq!(cfg.scheduler.min_free_disk_bytes, 1024 * 1024 * 1024);
+        assert_eq!(cfg.scheduler.content_queue_capacity, 100_000);
+        assert_eq!(
+            cfg.scheduler.content_queue_policy,
+            ContentQueuePolicy::DropNew
+        );
+        let cfg: AppConfig =
+            toml::from_str("[scheduler]\ncontent_queue_policy = \"drop_oldest\"").unwrap();
+        assert_eq!(
+            cfg.scheduler.content_queue_policy,
+            ContentQueuePolicy::DropOldest
+        );
+        let cfg: AppConfig =
+            toml::from_str("[scheduler]\nprofile = \"aggressive\"\ncpu_hard_limit_pct = 90")
+                .unwrap();
+        assert_eq!(cfg.scheduler.profile, IndexingProfile::Aggressive);
+        assert_eq!(cfg.scheduler.cpu_hard_limit_pct, Some(90));
+        assert_eq!(cfg.scheduler.content_batch_files, None);
+
+        let mut cfg = AppConfig::default();
+        assert_eq!(
+            (
+                cfg.scheduler.auto_tuning_min_pct,
+                cfg.scheduler.auto_tuning_max_pct
+            ),
+            (50, 200)
+        );
+        cfg.scheduler.auto_tuning_max_pct = 80;
+        let err = cfg.validate().unwrap_err();
+        assert!(err.to_string().contains("auto_tuning_max_pct"), "{err}");
     }
 
     #[test]
@@ -656,4 +1325,68 @@ mod tests {
         let cfg: AppConfig = toml::from_str(toml_str).unwrap();
         assert_eq!(cfg.extract.max_chars_per_file, 12_345);
     }
+
+    #[test]
+    fn validation_rejects_inverted_ngram_bounds() {
+        let mut cfg = AppConfig::default();
+        cfg.search.ngram_min = 6;
+        assert!(cfg.validate().is_ok(), "bounds only matter when enabled");
+        cfg.search.name_ngrams = true;
+        assert!(cfg.validate().is_err());
+        cfg.search.ngram_min = 2;
+        assert!(cfg.validate().is_ok());
+    }
+
+    #[test]
+    fn name_analyzer_parses_snake_case() {
+        assert_eq!(
+            AppConfig::default().search.name_analyzer,
+            NameAnalyzer::Lowercase
+        );
+        let cfg: AppConfig = toml::from_str("[search]\nname_analyzer = \"ascii_folded\"").unwrap();
+        assert_eq!(cfg.search.name_analyzer, NameAnalyzer::AsciiFolded);
+    }
+
+    #[test]
+    fn content_analysis_defaults_off() {
+        let search = AppConfig::default().search;
+        assert_eq!(search.content_stemming, ContentStemming::None);
+        assert!(!search.content_stopwords);
+        let cfg =
+            parse_config("[search]\ncontent_stemming = \"en\"\ncontent_stopwords = true").unwrap();
+        assert_eq!(cfg.search.content_stemming, ContentStemming::En);
+        assert!(cfg.search.content_stopwords);
+        assert!(parse_config("[search]\ncontent_stemming = \"fr\"").is_err());
+    }
+
+    #[test]
+    fn ipc_frame_limit_defaults_to_16_mib() {
+        assert_eq!(AppConfig::default().ipc.max_frame_bytes, 16 * 1024 * 1024);
+        let cfg = parse_config("[ipc]\nmax_frame_bytes = 65536").unwrap();
+        assert_eq!(cfg.ipc.max_frame_bytes, 65536);
+        assert!(parse_config("[ipc]\nmax_frame_bytes = 0").is_err());
+    }
+
+    #[test]
+    fn pipe_access_defaults_to_interactive_users() {
+        assert_eq!(
+            AppConfig::default().ipc.pipe_access,
+            PipeAccess::Interactive
+        );
+        let cfg = parse_config("[ipc]\npipe_access = \"admins_only\"").unwrap();
+        assert_eq!(cfg.ipc.pipe_access, PipeAccess::AdminsOnly);
+        assert!(parse_config("[ipc]\npipe_access = \"everyone\"").is_err());
+    }
+
+    #[test]
+    fn default_fields_parse_boosts() {
+        let cfg = parse_config("[search]\ndefault_fields = [\"name^3\", \"content\"]").unwrap();
+        assert_eq!(
+            cfg.search.default_field_boosts().unwrap(),
+            [("name", 3.0), ("content", 1.0)]
+        );
+        assert!(parse_config("[search]\ndefault_fields = [\"title\"]").is_err());
+        assert!(parse_config("[search]\ndefault_fields = [\"name^0\"]").is_err());
+        assert!(parse_config("[search]\ndefault_fields = [\"name^x\"]").is_err());
+    }
 }
@@ -0,0 +1,100 @@
+//! Files the index worker leaves in the jobs directory for the service.
+//!
+//! Both sides keep their own job spec type; these are generic over it so the on-disk shape is
+//! defined once.
+
+use crate::FileKind;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Worker-maintained retry and dead-letter lists in the jobs dir.
+pub const FAILED_FILE: &str = "failed.json";
+
+/// Where a batch's [`ExtractSample`]s go: `<job file>.stats`.
+pub fn stats_path(job_file: &Path) -> PathBuf {
+    job_file.with_extension("stats")
+}
+
+/// Where a batch's [`BatchMarker`] goes: `<job file>.progress`.
+pub fn progress_path(job_file: &Path) -> PathBuf {
+    job_file.with_extension("progress")
+}
+
+/// How many leading jobs of a batch are committed to the index, rewritten after each commit.
+/// A batch whose worker died is resumed from here.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct BatchMarker {
+    pub committed: usize,
+}
+
+/// Extraction cost of one job, fed into the service metrics.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractSample {
+    pub kind: FileKind,
+    pub duration_ms: f64,
+    pub bytes: u64,
+}
+
+/// The parts of a job spec failure bookkeeping needs.
+pub trait RetryJob {
+    /// The doc the job indexes, as `(volume_id, file_id)`.
+    fn doc(&self) -> (u16, u64);
+    /// Failed attempts so far.
+    fn attempts_mut(&mut self) -> &mut u32;
+}
+
+/// Whether a failed job is worth another attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// The file was busy (locked, being written, volume not ready); retry later.
+    Transient,
+    /// Retrying cannot help (unsupported format, too large, access denied, timed out).
+    Permanent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedJob<J> {
+    pub job: J,
+    pub error: String,
+}
+
+/// Contents of [`FAILED_FILE`]: jobs the service should requeue, and jobs given up on.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FailedJobs<J> {
+    pub version: u32,
+    #[serde(default = "Vec::new")]
+    pub retry: Vec<FailedJob<J>>,
+    #[serde(default = "Vec::new")]
+    pub dead_letter: Vec<FailedJob<J>>,
+}
+
+impl<J> Default for FailedJobs<J> {
+    fn default() -> Self {
+        Self {
+            version: 1,
+            retry: Vec::new(),
+            dead_letter: Vec::new(),
+        }
+    }
+}
+
+impl<J: RetryJob> FailedJobs<J> {
+    /// Account for one more failed attempt. A transient failure may happen `max_retries + 1`
+    /// times in total before the job is dead-lettered; a permanent one dead-letters it at once.
+    /// Re-recording the same doc replaces its earlier entry.
+    pub fn record(&mut self, mut job: J, error: String, kind: FailureKind, max_retries: u32) {
+        let key = job.doc();
+        self.retry.retain(|f| f.job.doc() != key);
+        self.dead_letter.retain(|f| f.job.doc() != key);
+
+        let attempts = job.attempts_mut();
+        *attempts = attempts.saturating_add(1);
+        let over_budget = *attempts > max_retries;
+        let entry = FailedJob { job, error };
+        if kind == FailureKind::Permanent || over_budget {
+            self.dead_letter.push(entry);
+        } else {
+            self.retry.push(entry);
+        }
+    }
+}
@@ -4,7 +4,9 @@
 //! serialization-friendly for rkyv/bincode and IPC payloads.
 
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 pub type VolumeId = u16;
 pub type FileId = u64;
@@ -61,6 +63,75 @@ impl FromStr for DocKey {
     }
 }
 
+/// URL-safe alphabet so encoded keys survive shells, URLs and CSV cells unquoted.
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+/// Eight bytes encode to eleven unpadded base64 characters.
+const BASE64_KEY_LEN: usize = 11;
+
+impl DocKey {
+    /// Compact form: the 8 big-endian bytes as unpadded URL-safe base64 (11 chars).
+    pub fn to_base64(self) -> String {
+        let bytes = self.0.to_be_bytes();
+        let mut out = String::with_capacity(BASE64_KEY_LEN);
+        for chunk in bytes.chunks(3) {
+            let mut group = [0u8; 3];
+            group[..chunk.len()].copy_from_slice(chunk);
+            let n = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+            for i in 0..=chunk.len() {
+                let sextet = (n >> (18 - 6 * i)) & 0x3f;
+                out.push(BASE64_ALPHABET[sextet as usize] as char);
+            }
+        }
+        out
+    }
+
+    /// Inverse of [`DocKey::to_base64`]; padding is tolerated, the length must decode to 8 bytes.
+    pub fn from_base64(s: &str) -> Result<Self, &'static str> {
+        let s = s.trim_end_matches('=');
+        if s.len() != BASE64_KEY_LEN {
+            return Err("base64 key must decode to 8 bytes");
+        }
+        let mut acc: u128 = 0;
+        for c in s.bytes() {
+            let sextet = BASE64_ALPHABET
+                .iter()
+                .position(|&a| a == c)
+                .ok_or("invalid base64 character")?;
+            acc = (acc << 6) | sextet as u128;
+        }
+        // 11 sextets carry 66 bits; the last two are padding and must be zero.
+        if acc & 0b11 != 0 {
+            return Err("non-canonical base64 key");
+        }
+        Ok(DocKey((acc >> 2) as u64))
+    }
+
+    /// Parse comma-separated keys, each in either the Display or base64 form.
+    /// Blank entries are skipped so trailing commas are harmless.
+    pub fn parse_many(s: &str) -> Result<Vec<Self>, &'static str> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                if part.contains(':') {
+                    part.parse()
+                } else {
+                    Self::from_base64(part)
+                }
+            })
+            .collect()
+    }
+
+    /// Join keys as comma-separated base64, the compact form [`DocKey::parse_many`] accepts.
+    pub fn join_many(keys: &[Self]) -> String {
+        keys.iter()
+            .map(|k| k.to_base64())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
 bitflags::bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
     pub struct FileFlags: u32 {
@@ -103,9 +174,7 @@ impl FileMeta {
         modified: Timestamp,
         flags: FileFlags,
     ) -> Self {
-        let ext = name
-            .rsplit_once('.')
-            .map(|(_, ext)| ext.to_ascii_lowercase());
+        let ext = file_ext(&name);
         Self {
             key,
             volume,
@@ -121,7 +190,172 @@ impl FileMeta {
     }
 }
 
-/// Per-volume configuration snapshot (kept simple for now).
+/// Multi-part extensions kept whole, so `archive.tar.gz` has extension `tar.gz`, not `gz`.
+const COMPOUND_EXTS: &[&str] = &["tar.gz", "tar.bz2", "tar.xz", "tar.zst"];
+
+/// Lowercase extension of a file name, without the dot. Compound extensions stay whole
+/// (`archive.tar.gz` -> `tar.gz`); dotfiles (`.gitignore`) and names ending in a dot (`file.`)
+/// have none.
+pub fn file_ext(name: &str) -> Option<String> {
+    let lower = name.to_ascii_lowercase();
+    // Leading dots start a dotfile's name rather than an extension.
+    let body = lower.trim_start_matches('.');
+    if let Some(compound) = COMPOUND_EXTS.iter().find(|ext| {
+        body.strip_suffix(*ext)
+            .and_then(|stem| stem.strip_suffix('.'))
+            .is_some_and(|stem| !stem.is_empty())
+    }) {
+        return Some((*compound).to_string());
+    }
+    let (stem, ext) = body.rsplit_once('.')?;
+    (!stem.is_empty() && !ext.is_empty()).then(|| ext.to_string())
+}
+
+/// Workload an index writer is sized for; each index crate maps it to a [`WriterBudget`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WriterProfile {
+    /// Full scans and rebuilds: a large share of free memory and most cores.
+    InitialBuild,
+    /// Incremental batches next to a running service: a small heap and few threads.
+    SteadyState,
+}
+
+pub const MIB: usize = 1024 * 1024;
+/// Tantivy rejects writers with less than 15 MB of heap per indexing thread.
+pub const MIN_HEAP_PER_THREAD: usize = 15_000_000;
+
+/// How one index sizes its writer for a [`WriterProfile`]: the heap takes `1 / divisor` of free
+/// memory clamped to `floor..=ceiling`, with one thread per core up to `max_threads`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WriterBudget {
+    pub divisor: u64,
+    pub floor: usize,
+    pub ceiling: usize,
+    pub max_threads: usize,
+}
+
+impl WriterBudget {
+    /// `(heap_size_bytes, num_threads)` given `available_bytes` of free memory and `cpus`
+    /// cores. Threads are capped so none drops below [`MIN_HEAP_PER_THREAD`].
+    pub fn size(&self, available_bytes: u64, cpus: usize) -> (usize, usize) {
+        let share = usize::try_from(available_bytes / self.divisor).unwrap_or(usize::MAX);
+        let heap_size_bytes = share.clamp(self.floor, self.ceiling);
+        let num_threads = cpus
+            .clamp(1, self.max_threads)
+            .min(heap_size_bytes / MIN_HEAP_PER_THREAD)
+            .max(1);
+        (heap_size_bytes, num_threads)
+    }
+}
+
+/// How an index crate's `open_or_recreate` obtained its index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndexOpenOutcome {
+    /// An existing index opened cleanly.
+    Opened,
+    /// No index existed, so an empty one was created.
+    Created,
+    /// The existing index could not be opened; it was moved to `backup` and replaced by an
+    /// empty index that needs a rescan to repopulate.
+    Recreated { backup: PathBuf },
+}
+
+/// Move an unreadable index directory aside as `<dir>.corrupt-<unix secs>` and return the
+/// backup path. The original path no longer exists afterwards.
+pub fn backup_corrupt_index_dir(dir: &Path) -> std::io::Result<PathBuf> {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let name = dir
+        .file_name()
+        .map_or_else(|| "index".into(), |n| n.to_string_lossy().into_owned());
+    let mut backup = dir.with_file_name(format!("{name}.corrupt-{secs}"));
+    let mut n = 1;
+    while backup.exists() {
+        backup = dir.with_file_name(format!("{name}.corrupt-{secs}-{n}"));
+        n += 1;
+    }
+    std::fs::rename(dir, &backup)?;
+    Ok(backup)
+}
+
+/// Coarse file category, derived from the extension; used to bucket extraction metrics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileKind {
+    Text,
+    Pdf,
+    Office,
+    Html,
+    Image,
+    Archive,
+    Other,
+}
+
+impl FileKind {
+    pub const ALL: [FileKind; 7] = [
+        FileKind::Text,
+        FileKind::Pdf,
+        FileKind::Office,
+        FileKind::Html,
+        FileKind::Image,
+        FileKind::Archive,
+        FileKind::Other,
+    ];
+
+    /// Classify a lowercase extension (without the dot).
+    pub fn from_ext(ext: Option<&str>) -> Self {
+        let ext = ext.unwrap_or_default();
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.extensions().contains(&ext))
+            .unwrap_or(FileKind::Other)
+    }
+
+    /// The lowercase extensions classified as this kind; empty for `Other`, which is every
+    /// extension not listed elsewhere.
+    pub fn extensions(self) -> &'static [&'static str] {
+        match self {
+            FileKind::Text => &[
+                "txt", "log", "md", "json", "jsonl", "toml", "rs", "ts", "tsx", "csv", "xml",
+                "yaml", "yml", "ini",
+            ],
+            FileKind::Pdf => &["pdf"],
+            FileKind::Office => &[
+                "doc", "docx", "xls", "xlsx", "ppt", "pptx", "odt", "ods", "odp", "rtf",
+            ],
+            FileKind::Html => &["htm", "html", "xhtml", "mht"],
+            FileKind::Image => &["png", "jpg", "jpeg", "gif", "bmp", "tif", "tiff", "webp"],
+            FileKind::Archive => &[
+                "zip", "7z", "rar", "tar", "gz", "tgz", "tar.gz", "tar.bz2", "tar.xz", "tar.zst",
+            ],
+            FileKind::Other => &[],
+        }
+    }
+
+    /// The kind whose [`as_str`](Self::as_str) label is `name`, ignoring case.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str().eq_ignore_ascii_case(name))
+    }
+
+    /// Stable label, matching the serde name (used for metric labels).
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FileKind::Text => "text",
+            FileKind::Pdf => "pdf",
+            FileKind::Office => "office",
+            FileKind::Html => "html",
+            FileKind::Image => "image",
+            FileKind::Archive => "archive",
+            FileKind::Other => "other",
+        }
+    }
+}
+
+/// How one discovered volume is indexed, resolved from the config by its mount.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VolumeSettings {
     pub volume: VolumeId,
@@ -131,7 +365,7 @@ pub struct VolumeSettings {
 }
 
 /// Basic descriptor for a discovered NTFS volume.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct VolumeDescriptor {
     pub id: VolumeId,
     /// NT-style volume GUID path, e.g. `\\\\?\\Volume{...}\\`
@@ -141,17 +375,63 @@ pub struct VolumeDescriptor {
 }
 
 pub mod config;
+pub mod jobs;
+pub mod open_target;
 
 impl FileFlags {
     pub fn is_dir(self) -> bool {
         self.contains(Self::IS_DIR)
     }
+
+    pub fn is_reparse(self) -> bool {
+        self.contains(Self::REPARSE)
+    }
+
+    pub fn is_offline(self) -> bool {
+        self.contains(Self::OFFLINE)
+    }
+
+    /// The flag a query names in `flags:<name>`, case-insensitively.
+    pub fn from_query_name(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "dir" | "directory" | "folder" => Self::IS_DIR,
+            "hidden" => Self::HIDDEN,
+            "system" => Self::SYSTEM,
+            "archive" => Self::ARCHIVE,
+            "reparse" | "link" => Self::REPARSE,
+            "offline" => Self::OFFLINE,
+            "temp" | "temporary" => Self::TEMPORARY,
+            _ => return None,
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn writer_budget_keeps_tantivy_per_thread_minimum() {
+        const GIB: u64 = 1024 * 1024 * 1024;
+        let budget = WriterBudget {
+            divisor: 4,
+            floor: 32 * MIB,
+            ceiling: 1024 * MIB,
+            max_threads: 8,
+        };
+        for available in [0, 100 * 1024 * 1024, GIB, 16 * GIB] {
+            for cpus in [0, 1, 3, 16, 128] {
+                let (heap, threads) = budget.size(available, cpus);
+                assert!(threads >= 1);
+                assert!(
+                    heap / threads >= MIN_HEAP_PER_THREAD,
+                    "{available} {cpus}: {heap} / {threads}"
+                );
+            }
+        }
+        assert_eq!(budget.size(2 * GIB, 4), (512 * MIB, 4));
+    }
+
     #[test]
     fn doc_key_round_trips() {
         let dk = DocKey::from_parts(42, 0x1234_5678_9abc);
@@ -160,6 +440,49 @@ mod tests {
         assert_eq!(f, 0x1234_5678_9abc);
     }
 
+    #[test]
+    fn base64_round_trips_random_and_edge_keys() {
+        // xorshift64 keeps the test deterministic without a rand dependency.
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        let mut keys = vec![DocKey(0), DocKey(u64::MAX), DocKey::from_parts(3, 0x4d2)];
+        for _ in 0..256 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            keys.push(DocKey(state));
+        }
+        for key in &keys {
+            let encoded = key.to_base64();
+            assert_eq!(encoded.len(), 11);
+            assert_eq!(DocKey::from_base64(&encoded), Ok(*key));
+        }
+        assert_eq!(DocKey(0).to_base64(), "AAAAAAAAAAA");
+        assert_eq!(DocKey(u64::MAX).to_base64(), "__________8");
+        assert_eq!(DocKey::parse_many(&DocKey::join_many(&keys)), Ok(keys));
+    }
+
+    #[test]
+    fn base64_rejects_bad_input() {
+        assert!(DocKey::from_base64("").is_err());
+        assert!(DocKey::from_base64("AAAAAAAAAA").is_err());
+        assert!(DocKey::from_base64("AAAAAAAAAAAA").is_err());
+        assert!(DocKey::from_base64("AAAAAAAAAA*").is_err());
+        assert!(DocKey::from_base64("AAAAAAAAAA+").is_err());
+        // Low padding bits set: a second spelling of the same key.
+        assert!(DocKey::from_base64("AAAAAAAAAAB").is_err());
+        assert_eq!(DocKey::from_base64("AAAAAAAAAAA="), Ok(DocKey(0)));
+    }
+
+    #[test]
+    fn parse_many_mixes_forms_and_skips_blanks() {
+        let a = DocKey::from_parts(3, 0x4d2);
+        let b = DocKey::from_parts(7, 1);
+        let input = format!("{a}, {} ,", b.to_base64());
+        assert_eq!(DocKey::parse_many(&input), Ok(vec![a, b]));
+        assert_eq!(DocKey::parse_many(""), Ok(vec![]));
+        assert!(DocKey::parse_many("3:0x1,nope").is_err());
+    }
+
     #[test]
     fn file_meta_ext_derives_lowercase() {
         let key = DocKey::from_parts(1, 2);
@@ -177,6 +500,32 @@ mod tests {
         assert_eq!(fm.ext.as_deref(), Some("pdf"));
     }
 
+    #[test]
+    fn file_ext_keeps_compound_extensions_and_skips_dotfiles() {
+        assert_eq!(file_ext("archive.tar.gz").as_deref(), Some("tar.gz"));
+        assert_eq!(file_ext("Backup.TAR.BZ2").as_deref(), Some("tar.bz2"));
+        assert_eq!(file_ext("notes.v2.txt").as_deref(), Some("txt"));
+        assert_eq!(file_ext(".gitignore"), None);
+        assert_eq!(file_ext(".config.json").as_deref(), Some("json"));
+        assert_eq!(file_ext("file."), None);
+        assert_eq!(file_ext("README"), None);
+        assert_eq!(
+            FileKind::from_ext(file_ext("a.tar.gz").as_deref()),
+            FileKind::Archive
+        );
+    }
+
+    #[test]
+    fn file_kind_from_ext() {
+        assert_eq!(FileKind::from_ext(Some("docx")), FileKind::Office);
+        assert_eq!(FileKind::from_ext(Some("pdf")), FileKind::Pdf);
+        assert_eq!(FileKind::from_ext(Some("rs")), FileKind::Text);
+        assert_eq!(FileKind::from_ext(Some("exe")), FileKind::Other);
+        assert_eq!(FileKind::from_ext(None), FileKind::Other);
+        assert_eq!(FileKind::from_name("Image"), Some(FileKind::Image));
+        assert_eq!(FileKind::from_name("images"), None);
+    }
+
     #[test]
     fn doc_key_display_is_stable() {
         let dk = DocKey::from_parts(7, 0xabc);
@@ -0,0 +1,191 @@
+//! Turning an indexed path into something safe to hand to the platform opener.
+//!
+//! Paths come out of the index as stored strings: they may be relative to nothing, point at a
+//! file deleted since it was indexed, or go through a link whose target is gone. The UI and CLI
+//! open actions resolve them here first, so a stale hit fails with a clear message instead of
+//! spawning `explorer`/`open`/`xdg-open` on something else.
+
+use anyhow::{Result, anyhow, bail};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Resolve `path` to the absolute, link-free path of an existing file or directory.
+///
+/// The entry itself is checked without following links first, so a dangling link is reported
+/// as such rather than as a missing file, and never opened.
+pub fn resolve_open_target(path: &str) -> Result<PathBuf> {
+    if path.trim().is_empty() {
+        bail!("no path to open");
+    }
+    let raw = Path::new(path);
+    let entry = fs::symlink_metadata(raw).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => anyhow!("{path} no longer exists"),
+        _ => anyhow!("cannot open {path}: {e}"),
+    })?;
+    let resolved = fs::canonicalize(raw).map_err(|e| {
+        if entry.file_type().is_symlink() {
+            anyhow!("{path} is a link whose target no longer exists")
+        } else {
+            anyhow!("cannot open {path}: {e}")
+        }
+    })?;
+    Ok(strip_verbatim(resolved))
+}
+
+/// Drop the `\\?\` prefix Windows' `canonicalize` adds, which Explorer and most applications
+/// do not understand: `\\?\C:\x` becomes `C:\x` and `\\?\UNC\srv\share` becomes `\\srv\share`.
+/// Other paths are returned unchanged.
+pub fn strip_verbatim(path: PathBuf) -> PathBuf {
+    let Some(text) = path.to_str() else {
+        return path;
+    };
+    if let Some(rest) = text.strip_prefix(r"\\?\UNC\") {
+        return PathBuf::from(format!(r"\\{rest}"));
+    }
+    match text.strip_prefix(r"\\?\") {
+        Some(rest) if rest.as_bytes().get(1) == Some(&b':') => PathBuf::from(rest),
+        _ => path,
+    }
+}
+
+/// `target` in double quotes for Explorer, which splits unquoted arguments at commas and
+/// does not follow the C runtime's quoting rules. Windows paths cannot contain `"`.
+pub fn quote_for_explorer(target: &Path) -> String {
+    format!("\"{}\"", target.display())
+}
+
+/// Command opening a resolved `target` with its default application. No shell is involved,
+/// and a resolved path is absolute, so it cannot be mistaken for an option.
+pub fn open_command(target: &Path) -> Command {
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        let mut cmd = Command::new("explorer");
+        cmd.raw_arg(quote_for_explorer(target));
+        cmd
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let mut cmd = Command::new("open");
+        cmd.arg(target);
+        cmd
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        let mut cmd = Command::new("xdg-open");
+        cmd.arg(target);
+        cmd
+    }
+}
+
+/// Command showing a resolved `target` in the file manager: selected where the platform
+/// supports it, otherwise by opening its parent directory.
+pub fn reveal_command(target: &Path) -> Command {
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        let mut cmd = Command::new("explorer");
+        cmd.raw_arg(format!("/select,{}", quote_for_explorer(target)));
+        cmd
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let mut cmd = Command::new("open");
+        cmd.arg("-R").arg(target);
+        cmd
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        let mut cmd = Command::new("xdg-open");
+        cmd.arg(target.parent().unwrap_or(target));
+        cmd
+    }
+}
+
+/// Resolve `path` and open it with its default application.
+pub fn open_path(path: &str) -> Result<()> {
+    let target = resolve_open_target(path)?;
+    open_command(&target)
+        .spawn()
+        .map_err(|e| anyhow!("could not open {}: {e}", target.display()))?;
+    Ok(())
+}
+
+/// Resolve `path` and show it in the file manager.
+pub fn reveal_path(path: &str) -> Result<()> {
+    let target = resolve_open_target(path)?;
+    reveal_command(&target)
+        .spawn()
+        .map_err(|e| anyhow!("could not show {}: {e}", target.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_to_a_normalized_absolute_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        fs::create_dir(root.join("sub")).unwrap();
+        fs::write(root.join("a & b, c.txt"), b"x").unwrap();
+
+        let messy = root.join("sub").join("..").join(".").join("a & b, c.txt");
+        let resolved = resolve_open_target(messy.to_str().unwrap()).unwrap();
+        assert_eq!(resolved, strip_verbatim(root.join("a & b, c.txt")));
+        assert!(resolved.is_absolute());
+    }
+
+    #[test]
+    fn missing_paths_are_rejected_before_spawning() {
+        let dir = tempfile::tempdir().unwrap();
+        let gone = dir.path().join("deleted.txt");
+        let err = open_path(gone.to_str().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("no longer exists"), "{err}");
+        assert!(resolve_open_target("  ").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dangling_links_are_not_followed() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("target.txt");
+        let link = dir.path().join("link.txt");
+        fs::write(&target, b"x").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        let resolved = resolve_open_target(link.to_str().unwrap()).unwrap();
+        assert_eq!(resolved, fs::canonicalize(&target).unwrap());
+
+        fs::remove_file(&target).unwrap();
+        let err = resolve_open_target(link.to_str().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("link whose target"), "{err}");
+    }
+
+    #[test]
+    fn verbatim_prefixes_are_stripped() {
+        let strip = |p: &str| strip_verbatim(PathBuf::from(p));
+        assert_eq!(
+            strip(r"\\?\C:\Users\a.txt"),
+            PathBuf::from(r"C:\Users\a.txt")
+        );
+        assert_eq!(
+            strip(r"\\?\UNC\server\share\a.txt"),
+            PathBuf::from(r"\\server\share\a.txt")
+        );
+        // Volume GUID paths have no drive-letter form; keep them verbatim.
+        let guid = r"\\?\Volume{1234}\a.txt";
+        assert_eq!(strip(guid), PathBuf::from(guid));
+        assert_eq!(strip("/home/a.txt"), PathBuf::from("/home/a.txt"));
+    }
+
+    #[test]
+    fn explorer_arguments_are_quoted() {
+        assert_eq!(
+            quote_for_explorer(Path::new(r"C:\a,b & c.txt")),
+            r#""C:\a,b & c.txt""#
+        );
+    }
+}
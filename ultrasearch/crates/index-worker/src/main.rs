@@ -5,14 +5,23 @@
 //! - JSON job file (`--job-file`) containing an array of jobs
 //! - Optional Extractous backend toggle via flag or ULTRASEARCH_ENABLE_EXTRACTOUS
 //! - Preview or JSON output for debugging
-//! - Writes extracted docs into the content index (creates if missing)
+//! - Writes extracted docs into the content index (creates if missing), upserting by doc key
+//! - Resumes an interrupted `--job-file` batch from its progress marker
+
+mod progress;
+mod retry;
 
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use content_extractor::{ExtractContext, ExtractorStack};
 use content_index::{ContentIndex, IndexWriter, WriterConfig};
-use core_types::DocKey;
+use core_types::config::{ContentStemming, SearchSection};
+use core_types::jobs::{ExtractSample, stats_path};
+use core_types::{DocKey, FileKind, file_ext};
 use dotenvy::dotenv;
+use progress::BatchProgress;
+use retry::FailedLog;
+use scheduler::JobCategory;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::{env, fs};
@@ -54,9 +63,58 @@ struct Args {
     /// Optional JSON job file (array of jobs). When set, --path is ignored.
     #[arg(long)]
     job_file: Option<PathBuf>,
+    /// Abandon a file whose extraction runs longer than this and skip it (0 = no limit).
+    #[arg(long, default_value = "60000")]
+    extract_timeout_ms: u64,
     /// Commit after at most N docs (0 = commit once at end).
     #[arg(long, default_value = "0")]
     commit_every: usize,
+    /// Retries allowed per job before it is dead-lettered in `failed.json`.
+    #[arg(long, default_value = "3")]
+    max_retries: u32,
+    /// Also index source files with the code analyzer (`features.doc_type_analyzers`).
+    #[arg(long, default_value = "false")]
+    doc_type_analyzers: bool,
+    /// Also index paths one directory per token (`features.path_segments`).
+    #[arg(long, default_value = "false")]
+    path_segments: bool,
+    /// Stemming of `content` when this run creates the index (`search.content_stemming`).
+    #[arg(long, value_enum, default_value_t = StemmingArg::None)]
+    content_stemming: StemmingArg,
+    /// Drop English stopwords from `content` when this run creates the index
+    /// (`search.content_stopwords`).
+    #[arg(long, default_value = "false")]
+    content_stopwords: bool,
+    /// Drop `content` tokens of this many bytes or more when this run creates the index
+    /// (`search.content_max_token_len`; 0 = no cap).
+    #[arg(long, default_value = "40")]
+    content_max_token_len: usize,
+    /// OCR images and image-only PDFs, up to this many pages per PDF (`extract.ocr_enabled`;
+    /// 0 = off, requires feature ocr-backend).
+    #[arg(long, default_value = "0")]
+    ocr_max_pages: u32,
+    /// Only extract these extensions, comma-separated (`extract.allowed_exts`; empty = all).
+    #[arg(long, value_delimiter = ',')]
+    allowed_exts: Vec<String>,
+    /// Never extract these extensions, comma-separated (`extract.denied_exts`).
+    #[arg(long, value_delimiter = ',')]
+    denied_exts: Vec<String>,
+}
+
+/// `search.content_stemming` on the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum StemmingArg {
+    None,
+    En,
+}
+
+impl From<StemmingArg> for ContentStemming {
+    fn from(arg: StemmingArg) -> Self {
+        match arg {
+            StemmingArg::None => ContentStemming::None,
+            StemmingArg::En => ContentStemming::En,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -70,6 +128,11 @@ struct JobSpec {
     max_chars: Option<usize>,
     #[serde(default)]
     file_size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    priority: Option<JobCategory>,
+    /// Failed attempts so far.
+    #[serde(default)]
+    attempts: u32,
 }
 
 #[derive(Debug, Serialize)]
@@ -132,25 +195,47 @@ fn main() -> Result<()> {
         args.enable_extractous = false;
     }
 
-    let stack = ExtractorStack::with_extractous_enabled(args.enable_extractous);
+    let stack = ExtractorStack::with_extractous_enabled(args.enable_extractous)
+        .with_ext_filter(&args.allowed_exts, &args.denied_exts);
+    #[cfg(feature = "ocr-backend")]
+    let stack = if args.ocr_max_pages > 0 {
+        stack.with_ocr(args.ocr_max_pages)
+    } else {
+        stack
+    };
+    #[cfg(not(feature = "ocr-backend"))]
+    if args.ocr_max_pages > 0 {
+        warn!("OCR requested but this worker was built without the ocr-backend feature");
+    }
 
     // Open index writer once for the run.
-    let index: ContentIndex = content_index::open_or_create(&args.index_dir)?;
+    let search = SearchSection {
+        content_stemming: args.content_stemming.into(),
+        content_stopwords: args.content_stopwords,
+        content_max_token_len: args.content_max_token_len,
+        ..SearchSection::default()
+    };
+    let mut index: ContentIndex = content_index::open_or_create_with(&args.index_dir, &search)?;
+    index.set_doc_type_analyzers(args.doc_type_analyzers);
+    index.set_path_segments(args.path_segments);
     let mut writer: IndexWriter = content_index::create_writer(&index, &WriterConfig::default())?;
-    let mut pending = 0usize;
 
     if let Some(job_file) = args.job_file.clone() {
         let jobs = load_jobs(&job_file)?;
-        for job in jobs {
-            if let Err(err) = process_job(&stack, &index, &mut writer, job, &args) {
-                warn!("job failed: {err}");
-            }
-            pending += 1;
-            if args.commit_every > 0 && pending >= args.commit_every {
-                writer.commit()?;
-                pending = 0;
-            }
-        }
+        let jobs_dir = job_file.parent().unwrap_or(std::path::Path::new("."));
+        let mut progress = BatchProgress::for_job_file(&job_file);
+        let mut failed = FailedLog::open(jobs_dir, args.max_retries)?;
+        let samples = run_batch(
+            &stack,
+            &index,
+            &mut writer,
+            &jobs,
+            &args,
+            &mut progress,
+            &mut failed,
+        )?;
+        progress.finish()?;
+        write_stats(&job_file, &samples);
     } else {
         let path = args
             .path
@@ -170,17 +255,81 @@ fn main() -> Result<()> {
             max_bytes: Some(args.max_bytes),
             max_chars: Some(args.max_chars),
             file_size: fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+            priority: None,
+            attempts: 0,
         };
 
         process_job(&stack, &index, &mut writer, single, &args)?;
-        pending += 1;
+        writer.commit()?;
+    }
+
+    Ok(())
+}
+
+/// Index `jobs`, skipping the ones `progress` already saw committed. After every commit the
+/// failures so far are flushed to `failed.json`, then progress is recorded. Returns the
+/// extraction samples of the jobs indexed in this run.
+fn run_batch(
+    stack: &ExtractorStack,
+    index: &ContentIndex,
+    writer: &mut IndexWriter,
+    jobs: &[JobSpec],
+    args: &Args,
+    progress: &mut BatchProgress,
+    failed: &mut FailedLog,
+) -> Result<Vec<ExtractSample>> {
+    let mut samples = Vec::new();
+    let start = progress.committed();
+    if start >= jobs.len() {
+        if start > jobs.len() {
+            warn!(
+                "progress marker ({start}) exceeds batch size ({}); nothing to resume",
+                jobs.len()
+            );
+        }
+        return Ok(samples);
+    }
+    if start > 0 {
+        info!("resuming batch after {start} committed jobs");
     }
 
+    let mut pending = 0usize;
+    for (i, job) in jobs.iter().enumerate().skip(start) {
+        match process_job(stack, index, writer, job.clone(), args) {
+            Ok(sample) => samples.push(sample),
+            // Configured out, not failed: nothing to retry or inspect later.
+            Err(err) if retry::is_excluded(&err) => info!("skipping {:?}: {err}", job.path),
+            Err(err) => {
+                let kind = retry::classify(&err);
+                warn!("job failed ({kind:?}): {err}");
+                failed.record(job.clone(), format!("{err:#}"), kind);
+            }
+        }
+        pending += 1;
+        if args.commit_every > 0 && pending >= args.commit_every {
+            writer.commit()?;
+            failed.flush()?;
+            progress.record(i + 1)?;
+            pending = 0;
+        }
+    }
     if pending > 0 {
         writer.commit()?;
+        failed.flush()?;
+        progress.record(jobs.len())?;
     }
+    Ok(samples)
+}
 
-    Ok(())
+/// Best-effort: metrics are not worth failing a committed batch over.
+fn write_stats(job_file: &std::path::Path, samples: &[ExtractSample]) {
+    let path = stats_path(job_file);
+    let result = serde_json::to_vec(samples)
+        .map_err(anyhow::Error::from)
+        .and_then(|bytes| fs::write(&path, bytes).map_err(anyhow::Error::from));
+    if let Err(e) = result {
+        warn!("failed to write extraction stats {}: {e}", path.display());
+    }
 }
 
 #[cfg(feature = "extractous_backend")]
@@ -246,7 +395,7 @@ fn process_job(
     writer: &mut IndexWriter,
     job: JobSpec,
     args: &Args,
-) -> Result<()> {
+) -> Result<ExtractSample> {
     let doc_key = DocKey::from_parts(job.volume_id, job.file_id);
 
     // Choose per-job limits if present, otherwise fall back to CLI defaults.
@@ -255,9 +404,9 @@ fn process_job(
 
     let ext_owned = job
         .path
-        .extension()
+        .file_name()
         .and_then(|s| s.to_str())
-        .map(|s| s.to_ascii_lowercase());
+        .and_then(file_ext);
 
     let ctx = ExtractContext {
         path: job
@@ -268,9 +417,13 @@ fn process_job(
         max_chars,
         ext_hint: ext_owned.as_deref(),
         mime_hint: None,
+        deadline: (args.extract_timeout_ms > 0).then(|| {
+            std::time::Instant::now() + std::time::Duration::from_millis(args.extract_timeout_ms)
+        }),
     };
 
-    let meta = fs::metadata(&job.path)
+    let meta = open_shared(&job.path)
+        .and_then(|f| f.metadata())
         .with_context(|| format!("file missing or unreadable: {}", job.path.display()))?;
 
     info!(
@@ -278,7 +431,12 @@ fn process_job(
         job.path, job.volume_id, job.file_id, args.enable_extractous, max_bytes, max_chars
     );
 
-    match stack.extract(doc_key, &ctx) {
+    let kind = FileKind::from_ext(ext_owned.as_deref());
+    let started = std::time::Instant::now();
+    let extracted = stack.extract(doc_key, &ctx);
+    let elapsed = started.elapsed();
+
+    match extracted {
         Ok(out) => {
             let lang = out.lang.clone();
             let truncated = out.truncated;
@@ -292,8 +450,7 @@ fn process_job(
 
             // Index the document.
             let content_doc = to_content_doc(&job, &meta, out)?;
-            let tdoc = content_index::to_document(&content_doc, &index.fields);
-            writer.add_document(tdoc)?;
+            content_index::upsert_content_doc(writer, &index.fields, &content_doc)?;
 
             // Output for debugging.
             if args.json {
@@ -315,12 +472,33 @@ fn process_job(
                     .collect::<String>();
                 println!("{preview}");
             }
-            Ok(())
+            Ok(ExtractSample {
+                kind,
+                duration_ms: elapsed.as_secs_f64() * 1000.0,
+                bytes: bytes_processed as u64,
+            })
         }
         Err(err) => Err(err),
     }
 }
 
+/// Open `path` without blocking other writers or deleters, with backup semantics so the
+/// service's backup privilege applies. Sharing violations still surface as errors for
+/// `retry::classify`.
+fn open_shared(path: &std::path::Path) -> std::io::Result<fs::File> {
+    let mut opts = fs::OpenOptions::new();
+    opts.read(true);
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+        const FILE_SHARE_READ_WRITE_DELETE: u32 = 0x1 | 0x2 | 0x4;
+        const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x0200_0000;
+        opts.share_mode(FILE_SHARE_READ_WRITE_DELETE)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS);
+    }
+    opts.open(path)
+}
+
 fn to_content_doc(
     job: &JobSpec,
     meta: &std::fs::Metadata,
@@ -339,11 +517,7 @@ fn to_content_doc(
         .and_then(|s| s.to_str())
         .map(|s| s.to_string());
 
-    let ext = job
-        .path
-        .extension()
-        .and_then(|s| s.to_str())
-        .map(|s| s.to_ascii_lowercase());
+    let ext = name.as_deref().and_then(file_ext);
 
     Ok(content_index::ContentDoc {
         key: out.key,
@@ -357,3 +531,193 @@ fn to_content_doc(
         content: out.text,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(dir: &std::path::Path, file_id: u64, text: &str) -> JobSpec {
+        let path = dir.join(format!("doc{file_id}.txt"));
+        fs::write(&path, text).unwrap();
+        JobSpec {
+            volume_id: 1,
+            file_id,
+            path,
+            max_bytes: None,
+            max_chars: None,
+            file_size: text.len() as u64,
+            priority: None,
+            attempts: 0,
+        }
+    }
+
+    fn num_docs(index: &ContentIndex) -> u64 {
+        let reader = content_index::open_reader(index).unwrap();
+        reader.reload().unwrap();
+        reader.searcher().num_docs()
+    }
+
+    fn setup(dir: &std::path::Path) -> (Args, ExtractorStack, ContentIndex) {
+        let index_dir = dir.join("index");
+        fs::create_dir_all(&index_dir).unwrap();
+        let args = Args::parse_from([
+            "index-worker",
+            "--index-dir",
+            index_dir.to_str().unwrap(),
+            "--commit-every",
+            "1",
+            "--max-retries",
+            "1",
+        ]);
+        let index = content_index::open_or_create(&index_dir).unwrap();
+        (args, ExtractorStack::simple_only(), index)
+    }
+
+    #[test]
+    fn rerun_after_crash_does_not_duplicate_committed_docs() {
+        let dir = tempfile::tempdir().unwrap();
+        let job_file = dir.path().join("job_test.json");
+        let (args, stack, index) = setup(dir.path());
+        let jobs = vec![job(dir.path(), 1, "alpha"), job(dir.path(), 2, "beta")];
+        let mut failed = FailedLog::open(dir.path(), args.max_retries).unwrap();
+
+        // First run commits job 1, then "crashes" before job 2.
+        {
+            let mut writer =
+                content_index::create_writer(&index, &WriterConfig::default()).unwrap();
+            let mut progress = BatchProgress::for_job_file(&job_file);
+            let first = &jobs[..1];
+            run_batch(
+                &stack,
+                &index,
+                &mut writer,
+                first,
+                &args,
+                &mut progress,
+                &mut failed,
+            )
+            .unwrap();
+        }
+        assert_eq!(num_docs(&index), 1);
+
+        // Restart: the marker skips job 1.
+        let mut writer = content_index::create_writer(&index, &WriterConfig::default()).unwrap();
+        let mut progress = BatchProgress::for_job_file(&job_file);
+        assert_eq!(progress.committed(), 1);
+        let samples = run_batch(
+            &stack,
+            &index,
+            &mut writer,
+            &jobs,
+            &args,
+            &mut progress,
+            &mut failed,
+        )
+        .unwrap();
+        assert_eq!(num_docs(&index), 2);
+        assert_eq!(samples.len(), 1, "only the resumed job is sampled");
+        assert_eq!(samples[0].kind, FileKind::Text);
+        assert_eq!(samples[0].bytes, 4);
+
+        // Crash between commit and marker update: the stale marker re-indexes everything, but
+        // upserts keep one doc per key.
+        fs::remove_file(job_file.with_extension("progress")).unwrap();
+        let mut stale = BatchProgress::for_job_file(&job_file);
+        run_batch(
+            &stack,
+            &index,
+            &mut writer,
+            &jobs,
+            &args,
+            &mut stale,
+            &mut failed,
+        )
+        .unwrap();
+        assert_eq!(num_docs(&index), 2);
+
+        progress.finish().unwrap();
+        assert!(!job_file.with_extension("progress").exists());
+        assert!(!dir.path().join(retry::FAILED_FILE).exists());
+    }
+
+    #[test]
+    fn failing_job_is_retried_then_dead_lettered() {
+        let dir = tempfile::tempdir().unwrap();
+        let (args, stack, index) = setup(dir.path());
+        let mut missing = job(dir.path(), 9, "gone");
+        fs::remove_file(&missing.path).unwrap();
+        let mut writer = content_index::create_writer(&index, &WriterConfig::default()).unwrap();
+
+        let read_failed = || -> retry::FailedJobs {
+            serde_json::from_slice(&fs::read(dir.path().join(retry::FAILED_FILE)).unwrap()).unwrap()
+        };
+
+        for expected_attempts in 1..=2 {
+            let job_file = dir.path().join(format!("job_{expected_attempts}.json"));
+            let mut progress = BatchProgress::for_job_file(&job_file);
+            let mut failed = FailedLog::open(dir.path(), args.max_retries).unwrap();
+            let batch = [missing.clone()];
+            run_batch(
+                &stack,
+                &index,
+                &mut writer,
+                &batch,
+                &args,
+                &mut progress,
+                &mut failed,
+            )
+            .unwrap();
+            let on_disk = read_failed();
+            let entry = on_disk
+                .retry
+                .iter()
+                .chain(&on_disk.dead_letter)
+                .next()
+                .unwrap();
+            assert_eq!(entry.job.attempts, expected_attempts);
+            assert!(entry.error.contains("file missing"), "{}", entry.error);
+            missing = entry.job.clone();
+        }
+
+        // `--max-retries 1`: the second failure exhausts the budget.
+        let on_disk = read_failed();
+        assert!(on_disk.retry.is_empty());
+        assert_eq!(on_disk.dead_letter.len(), 1);
+    }
+
+    #[test]
+    fn unsupported_file_is_dead_lettered_without_retries() {
+        let dir = tempfile::tempdir().unwrap();
+        let (args, _, index) = setup(dir.path());
+        // No backends: every format is unsupported.
+        let stack = ExtractorStack::new(Vec::new());
+        let mut writer = content_index::create_writer(&index, &WriterConfig::default()).unwrap();
+        let path = dir.path().join("blob.bin");
+        fs::write(&path, [0u8, 159, 146, 150]).unwrap();
+        let blob = JobSpec {
+            path,
+            ..job(dir.path(), 4, "")
+        };
+
+        let mut progress = BatchProgress::for_job_file(&dir.path().join("job_1.json"));
+        let mut failed = FailedLog::open(dir.path(), args.max_retries).unwrap();
+        run_batch(
+            &stack,
+            &index,
+            &mut writer,
+            &[blob],
+            &args,
+            &mut progress,
+            &mut failed,
+        )
+        .unwrap();
+
+        let on_disk: retry::FailedJobs =
+            serde_json::from_slice(&fs::read(dir.path().join(retry::FAILED_FILE)).unwrap())
+                .unwrap();
+        assert!(on_disk.retry.is_empty());
+        assert_eq!(on_disk.dead_letter.len(), 1);
+        assert_eq!(on_disk.dead_letter[0].job.attempts, 1);
+        assert!(on_disk.dead_letter[0].error.contains("unsupported"));
+    }
+}
@@ -0,0 +1,90 @@
+//! Durable progress marker for a job batch.
+//!
+//! After every commit the worker records how many leading jobs of the batch are in the index,
+//! in `<job file>.progress` beside the job file (i.e. in `jobs_dir`). A restarted worker skips
+//! those jobs, and the service requeues only the rest when a worker dies. The marker is replaced (tmp + rename) only after the index commit returns, so a
+//! crash in between merely re-indexes committed docs, which the delete-by-key upsert absorbs.
+
+use anyhow::{Context, Result};
+use core_types::jobs::{BatchMarker, progress_path};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+#[derive(Debug)]
+pub struct BatchProgress {
+    path: PathBuf,
+    committed: usize,
+}
+
+impl BatchProgress {
+    /// Progress for `job_file`, resuming from an existing marker when one is present.
+    pub fn for_job_file(job_file: &Path) -> Self {
+        let path = progress_path(job_file);
+        let committed = match fs::read(&path) {
+            Ok(bytes) => match serde_json::from_slice::<BatchMarker>(&bytes) {
+                Ok(m) => m.committed,
+                Err(e) => {
+                    warn!("ignoring corrupt progress marker {}: {e}", path.display());
+                    0
+                }
+            },
+            Err(_) => 0,
+        };
+        Self { path, committed }
+    }
+
+    /// Number of leading jobs already committed.
+    pub fn committed(&self) -> usize {
+        self.committed
+    }
+
+    /// Persist that the first `committed` jobs are in the index. Call only after a commit.
+    pub fn record(&mut self, committed: usize) -> Result<()> {
+        self.committed = committed;
+        let path = &self.path;
+        let tmp = path.with_extension("progress.tmp");
+        fs::write(&tmp, serde_json::to_vec(&BatchMarker { committed })?)
+            .with_context(|| format!("write progress marker {}", tmp.display()))?;
+        fs::rename(&tmp, path)
+            .with_context(|| format!("replace progress marker {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Drop the marker once the whole batch is committed.
+    pub fn finish(self) -> Result<()> {
+        if self.path.exists() {
+            fs::remove_file(&self.path)
+                .with_context(|| format!("remove progress marker {}", self.path.display()))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn marker_round_trips_and_finishes() {
+        let dir = tempfile::tempdir().unwrap();
+        let job_file = dir.path().join("job_1.json");
+
+        let mut progress = BatchProgress::for_job_file(&job_file);
+        assert_eq!(progress.committed(), 0);
+        progress.record(3).unwrap();
+
+        let resumed = BatchProgress::for_job_file(&job_file);
+        assert_eq!(resumed.committed(), 3);
+        resumed.finish().unwrap();
+        assert!(!job_file.with_extension("progress").exists());
+    }
+
+    #[test]
+    fn corrupt_marker_starts_over() {
+        let dir = tempfile::tempdir().unwrap();
+        let job_file = dir.path().join("job_2.json");
+        fs::write(job_file.with_extension("progress"), b"not json").unwrap();
+        assert_eq!(BatchProgress::for_job_file(&job_file).committed(), 0);
+    }
+}
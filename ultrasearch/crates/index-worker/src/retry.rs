@@ -0,0 +1,252 @@
+//! Failed-job bookkeeping in `<jobs_dir>/failed.json`.
+//!
+//! Each failure bumps the job's `attempts`. Jobs still under the retry cap go to `retry` for
+//! the scheduler to requeue; jobs over it go to `dead_letter` and stay there for inspection.
+//! Permanent failures (unsupported formats, access denied, extraction timeouts) skip the retry
+//! list entirely.
+
+use crate::JobSpec;
+use anyhow::{Context, Result};
+use content_extractor::ExtractError;
+pub use core_types::jobs::{FAILED_FILE, FailureKind};
+use core_types::jobs::{FailedJobs as FailedList, RetryJob};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// [`FAILED_FILE`] as the worker's [`JobSpec`]s.
+pub type FailedJobs = FailedList<JobSpec>;
+
+impl RetryJob for JobSpec {
+    fn doc(&self) -> (u16, u64) {
+        (self.volume_id, self.file_id)
+    }
+
+    fn attempts_mut(&mut self) -> &mut u32 {
+        &mut self.attempts
+    }
+}
+
+/// Classify a Win32 error code. Unknown codes are treated as transient so they keep the
+/// retry budget as their only limit.
+pub fn classify_win32(code: i32) -> FailureKind {
+    match code {
+        // ERROR_ACCESS_DENIED, ERROR_NOT_SUPPORTED, ERROR_INVALID_NAME,
+        // ERROR_CANT_ACCESS_FILE, ERROR_FILE_TOO_LARGE
+        5 | 50 | 123 | 1920 | 223 => FailureKind::Permanent,
+        // ERROR_SHARING_VIOLATION, ERROR_LOCK_VIOLATION, ERROR_NOT_READY, ERROR_NETWORK_BUSY,
+        // ERROR_USER_MAPPED_FILE, and everything else.
+        _ => FailureKind::Transient,
+    }
+}
+
+/// Classify an I/O error from opening or reading the job's file.
+pub fn classify_io(err: &io::Error) -> FailureKind {
+    if cfg!(windows)
+        && let Some(code) = err.raw_os_error()
+    {
+        return classify_win32(code);
+    }
+    match err.kind() {
+        io::ErrorKind::PermissionDenied
+        | io::ErrorKind::Unsupported
+        | io::ErrorKind::InvalidFilename
+        | io::ErrorKind::FileTooLarge => FailureKind::Permanent,
+        _ => FailureKind::Transient,
+    }
+}
+
+/// Classify a `process_job` error by the first I/O or extractor error in its chain.
+pub fn classify(err: &anyhow::Error) -> FailureKind {
+    for cause in err.chain() {
+        if let Some(io_err) = cause.downcast_ref::<io::Error>() {
+            return classify_io(io_err);
+        }
+        if let Some(extract_err) = cause.downcast_ref::<ExtractError>() {
+            return match extract_err {
+                ExtractError::Unsupported(_)
+                | ExtractError::FileTooLarge { .. }
+                | ExtractError::Excluded(_) => FailureKind::Permanent,
+                // The same file would stall the next worker too.
+                ExtractError::Failed(_) if extract_err.is_timeout() => FailureKind::Permanent,
+                ExtractError::Failed(_) => FailureKind::Transient,
+            };
+        }
+    }
+    FailureKind::Transient
+}
+
+/// Whether `err` comes from the `extract.allowed_exts` / `extract.denied_exts` filter.
+pub fn is_excluded(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<ExtractError>(),
+            Some(ExtractError::Excluded(_))
+        )
+    })
+}
+
+/// `failed.json` for one jobs directory, flushed alongside batch progress.
+#[derive(Debug)]
+pub struct FailedLog {
+    path: PathBuf,
+    jobs: FailedJobs,
+    max_retries: u32,
+    dirty: bool,
+}
+
+impl FailedLog {
+    pub fn open(jobs_dir: &Path, max_retries: u32) -> Result<Self> {
+        let path = jobs_dir.join(FAILED_FILE);
+        let jobs = match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .with_context(|| format!("parse {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => FailedJobs::default(),
+            Err(e) => return Err(e).with_context(|| format!("read {}", path.display())),
+        };
+        Ok(Self {
+            path,
+            jobs,
+            max_retries,
+            dirty: false,
+        })
+    }
+
+    pub fn record(&mut self, job: JobSpec, error: String, kind: FailureKind) {
+        self.jobs.record(job, error, kind, self.max_retries);
+        self.dirty = true;
+    }
+
+    /// Write pending changes (tmp + rename).
+    pub fn flush(&mut self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(&self.jobs)?)
+            .with_context(|| format!("write {}", tmp.display()))?;
+        fs::rename(&tmp, &self.path).with_context(|| format!("replace {}", self.path.display()))?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(file_id: u64) -> JobSpec {
+        JobSpec {
+            volume_id: 1,
+            file_id,
+            path: PathBuf::from(format!("C:\\locked{file_id}.docx")),
+            max_bytes: None,
+            max_chars: None,
+            file_size: 0,
+            priority: None,
+            attempts: 0,
+        }
+    }
+
+    #[test]
+    fn failures_count_attempts_until_dead_letter() {
+        let mut failed = FailedJobs::default();
+        let mut current = job(7);
+        for attempt in 1..=3 {
+            failed.record(
+                current.clone(),
+                "sharing violation".into(),
+                FailureKind::Transient,
+                3,
+            );
+            assert_eq!(failed.retry.len(), 1);
+            assert!(failed.dead_letter.is_empty());
+            current = failed.retry[0].job.clone();
+            assert_eq!(current.attempts, attempt);
+        }
+
+        failed.record(
+            current,
+            "sharing violation".into(),
+            FailureKind::Transient,
+            3,
+        );
+        assert!(failed.retry.is_empty());
+        assert_eq!(failed.dead_letter.len(), 1);
+        assert_eq!(failed.dead_letter[0].job.attempts, 4);
+        assert_eq!(failed.dead_letter[0].error, "sharing violation");
+    }
+
+    #[test]
+    fn zero_retries_dead_letters_immediately() {
+        let mut failed = FailedJobs::default();
+        failed.record(job(1), "boom".into(), FailureKind::Transient, 0);
+        assert!(failed.retry.is_empty());
+        assert_eq!(failed.dead_letter.len(), 1);
+    }
+
+    #[test]
+    fn permanent_failure_skips_retries() {
+        let mut failed = FailedJobs::default();
+        failed.record(job(1), "unsupported".into(), FailureKind::Permanent, 3);
+        assert!(failed.retry.is_empty());
+        assert_eq!(failed.dead_letter[0].job.attempts, 1);
+    }
+
+    #[test]
+    fn win32_codes_map_to_failure_kind() {
+        use FailureKind::*;
+        for (code, expected) in [
+            (32, Transient),   // ERROR_SHARING_VIOLATION
+            (33, Transient),   // ERROR_LOCK_VIOLATION
+            (21, Transient),   // ERROR_NOT_READY
+            (54, Transient),   // ERROR_NETWORK_BUSY
+            (1224, Transient), // ERROR_USER_MAPPED_FILE
+            (2, Transient),    // ERROR_FILE_NOT_FOUND (mid atomic save)
+            (5, Permanent),    // ERROR_ACCESS_DENIED
+            (50, Permanent),   // ERROR_NOT_SUPPORTED
+            (123, Permanent),  // ERROR_INVALID_NAME
+            (1920, Permanent), // ERROR_CANT_ACCESS_FILE
+        ] {
+            assert_eq!(classify_win32(code), expected, "code {code}");
+        }
+    }
+
+    #[test]
+    fn error_chain_classification() {
+        let unsupported = anyhow::anyhow!(ExtractError::Unsupported("bin".into()));
+        assert_eq!(classify(&unsupported), FailureKind::Permanent);
+        let timed_out = anyhow::anyhow!(ExtractError::timeout());
+        assert_eq!(classify(&timed_out), FailureKind::Permanent);
+        let failed = anyhow::anyhow!(ExtractError::Failed("corrupt".into()));
+        assert_eq!(classify(&failed), FailureKind::Transient);
+        let excluded = anyhow::anyhow!(ExtractError::Excluded("exe".into()));
+        assert!(is_excluded(&excluded));
+        assert!(!is_excluded(&unsupported));
+
+        let denied = anyhow::Error::new(io::Error::from(io::ErrorKind::PermissionDenied))
+            .context("file missing or unreadable: C:\\secret.txt");
+        assert_eq!(classify(&denied), FailureKind::Permanent);
+
+        let busy = anyhow::Error::new(io::Error::from(io::ErrorKind::ResourceBusy));
+        assert_eq!(classify(&busy), FailureKind::Transient);
+        assert_eq!(classify(&anyhow::anyhow!("boom")), FailureKind::Transient);
+    }
+
+    #[test]
+    fn log_merges_with_existing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = FailedLog::open(dir.path(), 3).unwrap();
+        log.record(job(1), "first".into(), FailureKind::Transient);
+        log.flush().unwrap();
+
+        let mut log = FailedLog::open(dir.path(), 3).unwrap();
+        log.record(job(2), "second".into(), FailureKind::Transient);
+        log.flush().unwrap();
+
+        let raw = fs::read(dir.path().join(FAILED_FILE)).unwrap();
+        let on_disk: FailedJobs = serde_json::from_slice(&raw).unwrap();
+        let ids: Vec<u64> = on_disk.retry.iter().map(|f| f.job.file_id).collect();
+        assert_eq!(ids, vec![1, 2]);
+    }
+}
@@ -1,4 +1,4 @@
-#![cfg(target_os = "windows")]
+#![cfg(any(target_os = "windows", all(unix, feature = "uds-transport")))]
 
 mod named_pipe_client;
 
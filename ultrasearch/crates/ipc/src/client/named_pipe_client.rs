@@ -1,20 +1,23 @@
-#![cfg(target_os = "windows")]
+#![cfg(any(target_os = "windows", all(unix, feature = "uds-transport")))]
 
 use crate::{
-    ReloadConfigRequest, ReloadConfigResponse, RescanRequest, RescanResponse, SearchRequest,
-    SearchResponse, StatusRequest, StatusResponse, framing,
+    ChildrenRequest, ChildrenResponse, ClientError, CompleteRequest, CompleteResponse,
+    ErrorResponse, GetDocRequest, GetDocResponse, ListVolumesRequest, ListVolumesResponse,
+    PlanRequest, PlanResponse, PreviewRequest, PreviewResponse, ReindexRequest, ReindexResponse,
+    ReloadConfigRequest, ReloadConfigResponse, Request, RescanRequest, RescanResponse, ScanRequest,
+    ScanResponse, SearchRequest, SearchResponse, StatusRequest, StatusResponse, framing,
 };
-use anyhow::{Result, bail};
-use serde::{Serialize, de::DeserializeOwned};
+use serde::de::DeserializeOwned;
 use std::sync::OnceLock;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::windows::named_pipe::ClientOptions;
+use tokio::io::AsyncWriteExt;
 use tokio::time::{Duration, sleep};
 use tracing::warn;
 
+#[cfg(windows)]
 const DEFAULT_PIPE_NAME: &str = r#"\\.\pipe\ultrasearch"#;
-const MAX_MESSAGE_BYTES: usize = 256 * 1024;
+#[cfg(not(windows))]
+const DEFAULT_PIPE_NAME: &str = "/tmp/ultrasearch.sock";
 const DEFAULT_TIMEOUT_MS: u64 = 750;
 const DEFAULT_RETRIES: u32 = 5;
 const DEFAULT_BACKOFF_MS: u64 = 100;
@@ -31,13 +34,30 @@ pub fn reconnect_success_count() -> usize {
     reconnect_counter().load(Ordering::Relaxed)
 }
 
-/// Named-pipe IPC client for UltraSearch.
+/// Open a connection to the service: a named pipe on Windows, a Unix domain socket elsewhere.
+#[cfg(windows)]
+async fn connect(name: &str) -> std::io::Result<tokio::net::windows::named_pipe::NamedPipeClient> {
+    tokio::net::windows::named_pipe::ClientOptions::new().open(name)
+}
+
+#[cfg(not(windows))]
+async fn connect(path: &str) -> std::io::Result<tokio::net::UnixStream> {
+    tokio::net::UnixStream::connect(path).await
+}
+
+/// Named-pipe IPC client for UltraSearch; with `uds-transport` off Windows, `pipe_name` is a
+/// socket path.
+///
+/// Every request returns a [`ClientError`]. A request the service rejects fails without
+/// retrying, as [`ClientError::Server`]; other failures are retried with backoff and the last
+/// one is returned.
 #[derive(Debug, Clone)]
 pub struct PipeClient {
     pipe_name: String,
     request_timeout: Duration,
     retries: u32,
     backoff: Duration,
+    max_frame_bytes: usize,
 }
 
 impl Default for PipeClient {
@@ -47,6 +67,7 @@ impl Default for PipeClient {
             request_timeout: Duration::from_millis(DEFAULT_TIMEOUT_MS),
             retries: DEFAULT_RETRIES,
             backoff: Duration::from_millis(DEFAULT_BACKOFF_MS),
+            max_frame_bytes: framing::DEFAULT_MAX_FRAME_BYTES,
         }
     }
 }
@@ -58,6 +79,7 @@ impl PipeClient {
             request_timeout: Duration::from_millis(DEFAULT_TIMEOUT_MS),
             retries: DEFAULT_RETRIES,
             backoff: Duration::from_millis(DEFAULT_BACKOFF_MS),
+            max_frame_bytes: framing::DEFAULT_MAX_FRAME_BYTES,
         }
     }
 
@@ -76,108 +98,132 @@ impl PipeClient {
         self
     }
 
-    pub async fn status(&self, req: StatusRequest) -> Result<StatusResponse> {
-        self.request(&req).await
+    /// Largest response frame accepted; match the service's `ipc.max_frame_bytes`.
+    pub fn with_max_frame_bytes(mut self, max: usize) -> Self {
+        self.max_frame_bytes = max;
+        self
+    }
+
+    pub async fn status(&self, req: StatusRequest) -> Result<StatusResponse, ClientError> {
+        self.request(req.into()).await
+    }
+
+    pub async fn search(&self, req: SearchRequest) -> Result<SearchResponse, ClientError> {
+        self.request(req.into()).await
+    }
+
+    pub async fn get_doc(&self, req: GetDocRequest) -> Result<GetDocResponse, ClientError> {
+        self.request(req.into()).await
+    }
+
+    pub async fn plan(&self, req: PlanRequest) -> Result<PlanResponse, ClientError> {
+        self.request(req.into()).await
     }
 
-    pub async fn search(&self, req: SearchRequest) -> Result<SearchResponse> {
-        self.request(&req).await
+    pub async fn preview(&self, req: PreviewRequest) -> Result<PreviewResponse, ClientError> {
+        self.request(req.into()).await
     }
 
-    pub async fn reload_config(&self, req: ReloadConfigRequest) -> Result<ReloadConfigResponse> {
-        self.request(&req).await
+    pub async fn complete(&self, req: CompleteRequest) -> Result<CompleteResponse, ClientError> {
+        self.request(req.into()).await
     }
 
-    pub async fn rescan(&self, req: RescanRequest) -> Result<RescanResponse> {
-        self.request(&req).await
+    pub async fn children(&self, req: ChildrenRequest) -> Result<ChildrenResponse, ClientError> {
+        self.request(req.into()).await
     }
 
-    async fn request<Req, Resp>(&self, req: &Req) -> Result<Resp>
+    pub async fn scan(&self, req: ScanRequest) -> Result<ScanResponse, ClientError> {
+        self.request(req.into()).await
+    }
+
+    pub async fn reload_config(
+        &self,
+        req: ReloadConfigRequest,
+    ) -> Result<ReloadConfigResponse, ClientError> {
+        self.request(req.into()).await
+    }
+
+    pub async fn rescan(&self, req: RescanRequest) -> Result<RescanResponse, ClientError> {
+        self.request(req.into()).await
+    }
+
+    pub async fn reindex(&self, req: ReindexRequest) -> Result<ReindexResponse, ClientError> {
+        self.request(req.into()).await
+    }
+
+    pub async fn list_volumes(
+        &self,
+        req: ListVolumesRequest,
+    ) -> Result<ListVolumesResponse, ClientError> {
+        self.request(req.into()).await
+    }
+
+    async fn request<Resp>(&self, req: Request) -> Result<Resp, ClientError>
     where
-        Req: Serialize,
         Resp: DeserializeOwned,
     {
-        // Serialize payload
-        let payload = bincode::serialize(req)?;
+        // Serialize payload, tagged with the request kind
+        let payload = bincode::serialize(&req).map_err(|e| ClientError::Protocol(e.to_string()))?;
         // Frame it (adds length header)
-        let framed = framing::encode_frame(&payload)?;
+        let framed =
+            framing::encode_frame(&payload).map_err(|e| ClientError::Protocol(e.to_string()))?;
 
         let mut attempt = 0;
-        let mut last_err: Option<anyhow::Error> = None;
+        let mut last_err: Option<ClientError> = None;
 
         while attempt <= self.retries {
             let was_retry = attempt > 0;
             let frame = framed.clone();
             let fut = async move {
-                // Return anyhow::Result to simplify error handling.
                 // Connect (new pipe each attempt)
-                let mut conn = match ClientOptions::new().open(&self.pipe_name) {
-                    Ok(c) => c,
-                    Err(e) => return Err(anyhow::Error::new(e)),
-                };
+                let mut conn = connect(&self.pipe_name)
+                    .await
+                    .map_err(ClientError::connect_failed)?;
 
                 // Write the framed request
-                conn.write_all(&frame).await?;
+                conn.write_all(&frame).await.map_err(ClientError::Io)?;
 
-                // Read response header
-                let mut len_buf = [0u8; 4];
-                conn.read_exact(&mut len_buf).await?;
-                let resp_len = u32::from_le_bytes(len_buf) as usize;
+                // Read the response frame; the length is checked before the body is allocated.
+                let buf = match framing::read_frame(&mut conn, self.max_frame_bytes).await {
+                    Ok(Some(buf)) => buf,
+                    Ok(None) => {
+                        return Err(ClientError::Protocol(
+                            "connection closed before a response".into(),
+                        ));
+                    }
+                    Err(framing::FrameError::Io(e)) => return Err(ClientError::Io(e)),
+                    Err(e) => return Err(ClientError::Protocol(e.to_string())),
+                };
 
-                if resp_len == 0 || resp_len > MAX_MESSAGE_BYTES {
-                    bail!("invalid response length {}", resp_len);
+                if let Some(err) = ErrorResponse::from_payload(&buf) {
+                    return Err(ClientError::Server(err));
                 }
-
-                // Read response body
-                let mut buf = vec![0u8; resp_len];
-                conn.read_exact(&mut buf).await?;
-
-                // Deserialize directly from the body buffer
-                // (framing::decode_frame expects [header + body], but we already consumed header.
-                // Since we trust the stream logic here, we can skip using decode_frame logic for the buffer check
-                // and just deserialize the body.)
-                let resp: Resp = bincode::deserialize(&buf)?;
-                Ok(resp)
+                bincode::deserialize::<Resp>(&buf).map_err(|e| ClientError::Protocol(e.to_string()))
             };
 
-            match tokio::time::timeout(self.request_timeout, fut).await {
+            let err = match tokio::time::timeout(self.request_timeout, fut).await {
                 Ok(Ok(resp)) => {
                     if was_retry {
                         reconnect_counter().fetch_add(1, Ordering::Relaxed);
                     }
                     return Ok(resp);
                 }
-                Ok(Err(e)) => {
-                    // Common reconnect cases: pipe missing (service down) or busy.
-                    if let Some(code) = e
-                        .downcast_ref::<std::io::Error>()
-                        .and_then(|ioe| ioe.raw_os_error())
-                    {
-                        if code == 2 || code == 231 {
-                            // 2 = ERROR_FILE_NOT_FOUND (service not up yet)
-                            // 231 = ERROR_PIPE_BUSY (connecting during service restart)
-                            warn!(
-                                "pipe request attempt {}: service unavailable/busy (os err {})",
-                                attempt + 1,
-                                code
-                            );
-                            last_err = Some(e);
-                            // Continue loop with backoff.
-                        } else {
-                            warn!("pipe request attempt {} failed: {e:?}", attempt + 1);
-                            last_err = Some(e);
-                        }
-                    } else {
-                        warn!("pipe request attempt {} failed: {e:?}", attempt + 1);
-                        last_err = Some(e);
-                    }
-                }
-                Err(e) => {
-                    warn!("pipe request attempt {} timed out: {e:?}", attempt + 1);
-                    let err: anyhow::Error = e.into();
-                    last_err = Some(err);
-                }
+                Ok(Err(e)) => e,
+                Err(_) => ClientError::Timeout(self.request_timeout),
+            };
+            // The service answered; asking again would get the same answer.
+            if !err.is_retryable() {
+                return Err(err);
+            }
+            match &err {
+                // Common reconnect cases: pipe missing (service down) or busy (restarting).
+                ClientError::NotConnected(_) => warn!(
+                    "pipe request attempt {}: service unavailable/busy ({err})",
+                    attempt + 1
+                ),
+                _ => warn!("pipe request attempt {} failed: {err}", attempt + 1),
             }
+            last_err = Some(err);
 
             attempt += 1;
             if attempt <= self.retries {
@@ -186,12 +232,86 @@ impl PipeClient {
             }
         }
 
-        Err(last_err.unwrap_or_else(|| {
-            anyhow::anyhow!(
-                "request failed after {} attempts to {}",
-                self.retries + 1,
-                self.pipe_name
-            )
-        }))
+        // The loop runs at least once, so there is always a last error.
+        Err(last_err.expect("at least one attempt"))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::ErrorCode;
+    use std::path::Path;
+    use tokio::net::UnixListener;
+
+    fn client(socket: &Path) -> PipeClient {
+        PipeClient::new(socket.to_string_lossy())
+            .with_request_timeout(Duration::from_millis(200))
+            .with_retries(0)
+    }
+
+    /// Accept one connection, read its request, then answer with `reply` or hold it open.
+    fn serve_once(socket: &Path, reply: Option<Vec<u8>>) {
+        let listener = UnixListener::bind(socket).unwrap();
+        tokio::spawn(async move {
+            let (mut conn, _) = listener.accept().await.unwrap();
+            let _ = framing::read_frame(&mut conn, framing::DEFAULT_MAX_FRAME_BYTES).await;
+            match reply {
+                Some(payload) => {
+                    let frame = framing::encode_frame(&payload).unwrap();
+                    conn.write_all(&frame).await.unwrap();
+                }
+                None => sleep(Duration::from_secs(5)).await,
+            }
+        });
+    }
+
+    fn status() -> StatusRequest {
+        StatusRequest {
+            id: uuid::Uuid::new_v4(),
+        }
+    }
+
+    #[tokio::test]
+    async fn missing_service_is_not_connected() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = client(&dir.path().join("none.sock"))
+            .status(status())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ClientError::NotConnected(_)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn silent_service_times_out() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("silent.sock");
+        serve_once(&socket, None);
+        let err = client(&socket).status(status()).await.unwrap_err();
+        assert!(matches!(err, ClientError::Timeout(_)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn unreadable_response_is_a_protocol_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("garbled.sock");
+        serve_once(&socket, Some(b"junk".to_vec()));
+        let err = client(&socket).status(status()).await.unwrap_err();
+        assert!(matches!(err, ClientError::Protocol(_)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn rejected_request_is_a_server_error_and_not_retried() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("reject.sock");
+        let rejection = ErrorResponse::new(uuid::Uuid::nil(), ErrorCode::Unsupported, "too new");
+        serve_once(&socket, Some(rejection.to_payload()));
+        // A retry would connect to a listener that never accepts again and time out.
+        let err = client(&socket)
+            .with_retries(3)
+            .status(status())
+            .await
+            .unwrap_err();
+        assert_eq!(err.server_code(), Some(ErrorCode::Unsupported), "{err:?}");
     }
 }
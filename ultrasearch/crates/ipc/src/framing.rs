@@ -1,7 +1,54 @@
 //! Simple length-prefixed framing helpers for pipe/stream transport.
 use anyhow::{Result, bail};
+use tokio::io::{AsyncRead, AsyncReadExt};
 
-pub const MAX_FRAME: usize = 256 * 1024;
+/// Default for `ipc.max_frame_bytes`.
+pub const DEFAULT_MAX_FRAME_BYTES: usize = 16 * 1024 * 1024;
+
+/// Largest frame [`encode_frame`] and [`decode_frame`] accept.
+pub const MAX_FRAME: usize = DEFAULT_MAX_FRAME_BYTES;
+
+/// Why a frame could not be read from a stream.
+#[derive(Debug, thiserror::Error)]
+pub enum FrameError {
+    /// The length prefix claims more than the configured limit; the body was not read.
+    #[error("frame of {len} bytes exceeds the {max} byte limit")]
+    TooLarge { len: usize, max: usize },
+    #[error("empty frame")]
+    Empty,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Reject a length prefix of zero or over `max` before anything is allocated for the body.
+pub fn check_frame_len(len: usize, max: usize) -> Result<(), FrameError> {
+    if len == 0 {
+        return Err(FrameError::Empty);
+    }
+    if len > max {
+        return Err(FrameError::TooLarge { len, max });
+    }
+    Ok(())
+}
+
+/// Read one length-prefixed frame of at most `max` bytes. `Ok(None)` means the peer closed the
+/// stream before sending another header.
+pub async fn read_frame<R>(reader: &mut R, max: usize) -> Result<Option<Vec<u8>>, FrameError>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut header = [0u8; 4];
+    match reader.read_exact(&mut header).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    }
+    let len = u32::from_le_bytes(header) as usize;
+    check_frame_len(len, max)?;
+    let mut buf = vec![0u8; len];
+    reader.read_exact(&mut buf).await?;
+    Ok(Some(buf))
+}
 
 /// Encode a payload with a little-endian u32 length prefix.
 pub fn encode_frame(payload: &[u8]) -> Result<Vec<u8>> {
@@ -91,4 +138,37 @@ mod tests {
         let res = decode_frame(&[0, 0, 0]);
         assert!(res.is_err());
     }
+
+    fn read_blocking(bytes: &[u8], max: usize) -> std::result::Result<Option<Vec<u8>>, FrameError> {
+        let mut reader = bytes;
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(read_frame(&mut reader, max))
+    }
+
+    #[test]
+    fn read_frame_stops_at_clean_eof() {
+        let framed = encode_frame(b"ping").unwrap();
+        assert_eq!(read_blocking(&framed, 16).unwrap().unwrap(), b"ping");
+        assert!(read_blocking(&[], 16).unwrap().is_none());
+        assert!(matches!(read_blocking(&[0; 4], 16), Err(FrameError::Empty)));
+    }
+
+    #[test]
+    fn oversized_prefix_is_rejected_without_reading_the_body() {
+        // The header claims 4 GiB with no body behind it: a reader that allocated first would
+        // try to reserve the whole length, then fail with EOF rather than TooLarge.
+        let header = u32::MAX.to_le_bytes();
+        match read_blocking(&header, 1024) {
+            Err(FrameError::TooLarge { len, max }) => {
+                assert_eq!(len, u32::MAX as usize);
+                assert_eq!(max, 1024);
+            }
+            other => panic!("expected TooLarge, got {other:?}"),
+        }
+        let mut at_limit = 1024u32.to_le_bytes().to_vec();
+        at_limit.extend_from_slice(&[7; 1024]);
+        assert_eq!(read_blocking(&at_limit, 1024).unwrap().unwrap().len(), 1024);
+    }
 }
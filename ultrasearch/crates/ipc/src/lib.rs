@@ -5,7 +5,8 @@
 //! query AST, requests, and responses in a way that matches the architecture
 //! plan without pulling in search/index dependencies.
 
-use core_types::DocKey;
+use core_types::config::{SavedSearch, SavedSearchMode};
+use core_types::{DocKey, FileId, FileKind, VolumeDescriptor, VolumeId};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use uuid::Uuid;
@@ -88,13 +89,18 @@ pub enum TermModifier {
     Phrase,
     Prefix,
     Fuzzy(u8), // max edit distance
+    /// Infix match against the `name_ngram` field; behaves like `Term` when it is absent.
+    Substring,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct TermExpr {
     pub field: Option<FieldKind>, // None => default (name + content)
     pub value: String,
     pub modifier: TermModifier,
+    /// Score multiplier for matches of this term (`name:report^3`); `None` means 1.0.
+    #[serde(default)]
+    pub boost: Option<f32>,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -119,7 +125,7 @@ pub struct RangeExpr {
     pub value: RangeValue,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum QueryExpr {
     Term(TermExpr),
     Range(RangeExpr),
@@ -143,9 +149,21 @@ pub enum SearchMode {
     Hybrid,   // meta + content merge
 }
 
-#[cfg(windows)]
+impl From<SavedSearchMode> for SearchMode {
+    fn from(mode: SavedSearchMode) -> Self {
+        match mode {
+            SavedSearchMode::Auto => SearchMode::Auto,
+            SavedSearchMode::Name => SearchMode::NameOnly,
+            SavedSearchMode::Content => SearchMode::Content,
+            SavedSearchMode::Hybrid => SearchMode::Hybrid,
+        }
+    }
+}
+
+#[cfg(any(windows, all(unix, feature = "uds-transport")))]
 pub mod client;
 pub mod framing;
+pub mod query;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchRequest {
@@ -160,6 +178,52 @@ pub struct SearchRequest {
     pub timeout: Option<Duration>,
     #[serde(default)]
     pub offset: u32,
+    /// Opaque resume token from a previous `SearchResponse::next_cursor`. Takes precedence over
+    /// `offset` when set.
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Ask the service to describe how it planned and ran the query.
+    #[serde(default)]
+    pub explain: bool,
+    /// Match file names case-sensitively, whatever the index's `name_analyzer`.
+    #[serde(default)]
+    pub case_sensitive: bool,
+    /// Only count matches: the response carries `total` and no hits, so no stored fields
+    /// are read. `limit`, `offset` and `cursor` are ignored.
+    #[serde(default)]
+    pub count_only: bool,
+    /// Optional hit fields (`Name`, `Path`, `Ext`, `Size`, `Modified`) to return; the rest stay
+    /// `None`. `key` and `score` are always set. `None` returns every field.
+    #[serde(default)]
+    pub fields: Option<Vec<FieldKind>>,
+    /// Also return docs under `search.excluded_paths` (recycle bin, temp directories, ...).
+    #[serde(default)]
+    pub include_system: bool,
+    /// Order hits by a field value instead of score. Sorted pages carry no `next_cursor`;
+    /// page them with `offset`.
+    #[serde(default)]
+    pub sort: Option<SortBy>,
+    /// When a name search finds nothing, fill `SearchResponse::suggestions` with spelling
+    /// corrections. Off by default: it scans the name dictionary.
+    #[serde(default)]
+    pub suggest: bool,
+    /// Also bucket name-search matches into `SearchResponse::groups`: `Path` groups by parent
+    /// directory, `Ext` by extension, `Kind` by file kind (`image`, `office`, ...). Other
+    /// fields produce no groups.
+    #[serde(default)]
+    pub group_by: Option<FieldKind>,
+    /// Divide every hit's score by the best one in the response, so scores fall in `0..=1`
+    /// with the top hit at 1. Off: raw BM25 scores, which are unbounded.
+    #[serde(default)]
+    pub normalize_scores: bool,
+}
+
+/// Field ordering for a `SearchRequest`. `Modified`, `Created` and `Size` are sortable; any
+/// other field falls back to score order.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SortBy {
+    pub field: FieldKind,
+    pub descending: bool,
 }
 
 fn default_limit() -> u32 {
@@ -175,6 +239,16 @@ impl Default for SearchRequest {
             mode: SearchMode::Auto,
             timeout: None,
             offset: 0,
+            cursor: None,
+            explain: false,
+            case_sensitive: false,
+            count_only: false,
+            fields: None,
+            include_system: false,
+            sort: None,
+            suggest: false,
+            group_by: None,
+            normalize_scores: false,
         }
     }
 }
@@ -188,6 +262,14 @@ impl SearchRequest {
         }
     }
 
+    /// A fresh request for a saved search: its query text parsed as typed, with its mode.
+    pub fn from_saved(saved: &SavedSearch) -> Result<Self, query::ParseError> {
+        let mut req =
+            Self::with_query(query::parse_query(&saved.query)?).with_mode(saved.mode.into());
+        req.id = Uuid::new_v4();
+        Ok(req)
+    }
+
     /// Set a timeout in milliseconds.
     pub fn with_timeout_ms(mut self, ms: u64) -> Self {
         self.timeout = Some(Duration::from_millis(ms));
@@ -206,11 +288,71 @@ impl SearchRequest {
         self
     }
 
+    /// Resume after a cursor returned by a previous page.
+    pub fn with_cursor(mut self, cursor: impl Into<String>) -> Self {
+        self.cursor = Some(cursor.into());
+        self
+    }
+
     /// Override the search mode.
     pub fn with_mode(mut self, mode: SearchMode) -> Self {
         self.mode = mode;
         self
     }
+
+    /// Request an `explanation` in the response.
+    pub fn with_explain(mut self) -> Self {
+        self.explain = true;
+        self
+    }
+
+    /// Match file names case-sensitively.
+    pub fn with_case_sensitive(mut self) -> Self {
+        self.case_sensitive = true;
+        self
+    }
+
+    /// Return only the number of matches.
+    pub fn with_count_only(mut self) -> Self {
+        self.count_only = true;
+        self
+    }
+
+    /// Return only these hit fields.
+    pub fn with_fields(mut self, fields: impl Into<Vec<FieldKind>>) -> Self {
+        self.fields = Some(fields.into());
+        self
+    }
+
+    /// Skip the configured path exclusions.
+    pub fn with_include_system(mut self) -> Self {
+        self.include_system = true;
+        self
+    }
+
+    /// Order hits by `field` instead of score.
+    pub fn with_sort(mut self, field: FieldKind, descending: bool) -> Self {
+        self.sort = Some(SortBy { field, descending });
+        self
+    }
+
+    /// Ask for "did you mean" corrections if the search comes back empty.
+    pub fn with_suggestions(mut self) -> Self {
+        self.suggest = true;
+        self
+    }
+
+    /// Group matches by `field` (see [`SearchRequest::group_by`]).
+    pub fn with_group_by(mut self, field: FieldKind) -> Self {
+        self.group_by = Some(field);
+        self
+    }
+
+    /// Scale hit scores to `0..=1` relative to the top hit.
+    pub fn with_normalized_scores(mut self) -> Self {
+        self.normalize_scores = true;
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -225,6 +367,14 @@ pub struct SearchHit {
     pub snippet: Option<String>,
 }
 
+impl SearchHit {
+    /// `(volume, frn)` identity of the hit. FRNs are only unique within a volume, so anything
+    /// that dedups or tracks a selection across result sets must key on both parts.
+    pub fn stable_id(&self) -> (VolumeId, FileId) {
+        self.key.into_parts()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResponse {
     pub id: Uuid,
@@ -234,6 +384,158 @@ pub struct SearchResponse {
     pub took_ms: u32,
     #[serde(default)]
     pub served_by: Option<String>,
+    /// Cursor for the next page; `None` when this page is the last.
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+    /// Planned mode, executed queries and top-hit scoring; set only for `explain` requests.
+    #[serde(default)]
+    pub explanation: Option<String>,
+    /// The request's `timeout` expired mid-search; `hits` are the best of what was scanned.
+    #[serde(default)]
+    pub timed_out: bool,
+    /// Why the search ran in a narrower mode than requested, e.g. a `Content` search answered
+    /// from file names because there is no content index. `None` when it ran as asked.
+    #[serde(default)]
+    pub degraded: Option<String>,
+    /// Up to three indexed name terms close to the query's words, for a `suggest` request that
+    /// found nothing in name mode.
+    #[serde(default)]
+    pub suggestions: Vec<String>,
+    /// Matches bucketed by the request's `group_by`, largest group first; empty unless asked.
+    #[serde(default)]
+    pub groups: Vec<ResultGroup>,
+}
+
+/// Matches sharing one `group_by` value, e.g. every hit in one directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResultGroup {
+    /// The shared value: a directory path for `Path`, an extension for `Ext`, a file kind
+    /// label for `Kind`.
+    pub key: String,
+    /// Matches in the group, including those not in `top_hits`.
+    pub count: u64,
+    /// Best-scoring matches of the group.
+    pub top_hits: Vec<SearchHit>,
+}
+
+/// Fetch one indexed document by the `DocKey` a previous search returned.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetDocRequest {
+    pub id: Uuid,
+    pub key: DocKey,
+    /// Also return the extracted text from the content index.
+    pub include_content: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetDocResponse {
+    pub id: Uuid,
+    /// `None` when the key is not in the metadata index.
+    pub meta: Option<SearchHit>,
+    /// `None` unless requested and the document has stored content.
+    pub content: Option<String>,
+}
+
+/// Dry-run the planner: which mode an `Auto` search of `query` would run under. Nothing is
+/// executed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanRequest {
+    pub id: Uuid,
+    pub query: QueryExpr,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanResponse {
+    pub id: Uuid,
+    /// `NameOnly`, `Content` or `Hybrid`; never `Auto`.
+    pub chosen_mode: SearchMode,
+    /// The heuristics that fired, in the order the planner applied them.
+    pub reasons: Vec<String>,
+    /// Relative, unitless cost; only meaningful compared with other plans.
+    pub estimated_cost: u32,
+}
+
+/// Extract a file's text on demand for the preview pane. The service resolves `key` to a path
+/// through the metadata index and runs its extractor stack, so the UI needs no extractors.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewRequest {
+    pub id: Uuid,
+    pub key: DocKey,
+    /// Cap on the returned text, in UTF-8 bytes.
+    pub max_bytes: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreviewResponse {
+    pub id: Uuid,
+    /// `None` for unknown keys and for binary or unsupported files.
+    pub text: Option<String>,
+    /// The file had more text than `max_bytes`.
+    pub truncated: bool,
+    /// Category from the file's extension; lets the UI label files it gets no text for.
+    pub kind: FileKind,
+}
+
+/// As-you-type completions for the search box: indexed terms of `field` that start with the
+/// last word of `prefix`. Only `Name` and `Ext` are completed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompleteRequest {
+    pub id: Uuid,
+    pub prefix: String,
+    pub field: FieldKind,
+    /// Most completions to return; the service caps it.
+    pub limit: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompleteResponse {
+    pub id: Uuid,
+    /// Most frequent first, ties in lexical order.
+    pub completions: Vec<String>,
+}
+
+/// List the direct children of a directory for tree-style drill-down. `parent` is the
+/// directory's `DocKey` as returned in a previous hit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChildrenRequest {
+    pub id: Uuid,
+    pub parent: DocKey,
+    /// Most children to return; the service caps it.
+    pub limit: u32,
+    /// Only subdirectories, for a folder tree.
+    pub dirs_only: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChildrenResponse {
+    pub id: Uuid,
+    /// Ordered by name, case-insensitively.
+    pub hits: Vec<SearchHit>,
+    /// All children of `parent`, including those past `limit`.
+    pub total: u64,
+}
+
+/// Every indexed document in `DocKey` order, one page at a time, for export and backup tools.
+/// No query runs and nothing is scored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanRequest {
+    pub id: Uuid,
+    /// Resume strictly after this key, normally the previous page's `next`; `None` starts from
+    /// the lowest key.
+    pub after: Option<DocKey>,
+    /// Most docs to return; the service caps it.
+    pub limit: u32,
+    /// Only docs on this volume, e.g. to export one drive.
+    pub volume: Option<VolumeId>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanResponse {
+    pub id: Uuid,
+    /// In ascending `DocKey` order.
+    pub hits: Vec<SearchHit>,
+    /// `after` for the next page; `None` once the scan has reached the end.
+    pub next: Option<DocKey>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -265,6 +567,70 @@ pub struct RescanResponse {
     pub message: Option<String>,
 }
 
+/// The NTFS volumes the service sees and how its config treats them, so clients do not have to
+/// enumerate disks themselves.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListVolumesRequest {
+    pub id: Uuid,
+    /// Also list discovered volumes that `volumes` leaves out, e.g. to pick new ones to index.
+    pub include_unindexed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListVolumesResponse {
+    pub id: Uuid,
+    /// In volume id order.
+    pub volumes: Vec<VolumeEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VolumeEntry {
+    pub volume: VolumeDescriptor,
+    /// Metadata is indexed: listed in `volumes`, or that list is empty.
+    pub indexed: bool,
+    /// Content is extracted too: indexed and listed in `content_index_volumes`, or that list
+    /// is empty.
+    pub content_indexed: bool,
+}
+
+/// Which indexes a reindex drops and rebuilds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ReindexScope {
+    /// Metadata from a fresh MFT enumeration, then content by re-extraction.
+    #[default]
+    All,
+    MetaOnly,
+    ContentOnly,
+}
+
+impl ReindexScope {
+    pub fn includes_meta(self) -> bool {
+        matches!(self, Self::All | Self::MetaOnly)
+    }
+
+    pub fn includes_content(self) -> bool {
+        matches!(self, Self::All | Self::ContentOnly)
+    }
+}
+
+/// Drop and rebuild indexes. The service answers once the rebuild has started; progress shows
+/// up in `StatusResponse::scheduler_state`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReindexRequest {
+    pub id: Uuid,
+    /// Mount such as `C:\`; `None` rebuilds every configured volume.
+    pub volume: Option<String>,
+    pub scope: ReindexScope,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReindexResponse {
+    pub id: Uuid,
+    /// False when another reindex is still running or the rebuild could not start.
+    pub accepted: bool,
+    pub message: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VolumeStatus {
     pub volume: u16,
@@ -272,8 +638,29 @@ pub struct VolumeStatus {
     pub indexed_bytes: u64,
     pub pending_files: u64,
     pub pending_bytes: u64,
+    /// Journal position the index has committed changes up to; `None` until a commit on this
+    /// volume carried one.
     pub last_usn: Option<u64>,
     pub journal_id: Option<u64>,
+    /// Free and total bytes of the volume's disk, from the scheduler's periodic sample.
+    #[serde(default)]
+    pub free_bytes: Option<u64>,
+    #[serde(default)]
+    pub total_bytes: Option<u64>,
+}
+
+/// Whether the service can answer searches; a responding pipe alone does not mean it can.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Readiness {
+    /// Indexes are still being opened.
+    #[default]
+    Starting,
+    /// Indexes are open but the initial scan has not finished, so results may be incomplete.
+    Indexing,
+    /// Indexes are open and the initial scan has completed.
+    Ready,
+    /// An index failed to open; searches may fail or miss content.
+    Degraded,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -288,6 +675,11 @@ pub struct StatusResponse {
     pub content_bytes_remaining: Option<u64>,
     pub metrics: Option<MetricsSnapshot>,
     pub served_by: Option<String>,
+    #[serde(default)]
+    pub readiness: Readiness,
+    /// `MetricsSnapshot::queue_depth` split into `critical`, `metadata` and `content`.
+    #[serde(default)]
+    pub queue_depth_by_category: Option<[(String, u64); 3]>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -302,6 +694,193 @@ pub struct MetricsSnapshot {
     pub content_enqueued: Option<u64>,
     /// Total content jobs dropped due to backpressure or missing scheduler (best-effort).
     pub content_dropped: Option<u64>,
+    /// Extraction latency across all kinds over the recent sample window.
+    pub extract_p50_ms: Option<f64>,
+    pub extract_p95_ms: Option<f64>,
+    /// Per-kind extraction cost; only kinds that have been extracted are listed.
+    pub extract_by_kind: Vec<ExtractKindStats>,
+}
+
+/// Extraction cost for one file kind: totals since startup, percentiles over recent samples.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExtractKindStats {
+    pub kind: FileKind,
+    pub files: u64,
+    pub bytes: u64,
+    pub p50_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+}
+
+/// A request frame's payload. The variant index goes on the wire first, so the service reads
+/// the kind before the body; new kinds are appended and existing ones never reordered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Request {
+    Status(StatusRequest),
+    Search(SearchRequest),
+    ReloadConfig(ReloadConfigRequest),
+    Rescan(RescanRequest),
+    Reindex(ReindexRequest),
+    GetDoc(GetDocRequest),
+    Plan(PlanRequest),
+    Preview(PreviewRequest),
+    Complete(CompleteRequest),
+    Children(ChildrenRequest),
+    ListVolumes(ListVolumesRequest),
+    Scan(ScanRequest),
+}
+
+macro_rules! request_kinds {
+    ($($variant:ident($req:ty)),* $(,)?) => {
+        impl Request {
+            /// How many kinds this build knows; a payload's kind tag below this names one.
+            pub const KINDS: u32 = [$(stringify!($variant)),*].len() as u32;
+
+            /// The id the response will echo.
+            pub fn id(&self) -> Uuid {
+                match self {
+                    $(Self::$variant(req) => req.id,)*
+                }
+            }
+        }
+
+        $(
+            impl From<$req> for Request {
+                fn from(req: $req) -> Self {
+                    Self::$variant(req)
+                }
+            }
+        )*
+    };
+}
+
+request_kinds! {
+    Status(StatusRequest),
+    Search(SearchRequest),
+    ReloadConfig(ReloadConfigRequest),
+    Rescan(RescanRequest),
+    Reindex(ReindexRequest),
+    GetDoc(GetDocRequest),
+    Plan(PlanRequest),
+    Preview(PreviewRequest),
+    Complete(CompleteRequest),
+    Children(ChildrenRequest),
+    ListVolumes(ListVolumesRequest),
+    Scan(ScanRequest),
+}
+
+/// Prefix of a response frame that carries an [`ErrorResponse`]. Every other response starts
+/// with the bincode encoding of its `id`, a `u64` length of 16, so the two can't be confused.
+pub const ERROR_FRAME_TAG: &[u8; 4] = b"ERR!";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ErrorCode {
+    /// The payload is not a readable request: too short to hold an id, garbled, or a known kind
+    /// whose body does not decode.
+    BadRequest,
+    /// The payload carries a request id but no request this service knows, e.g. a kind added
+    /// by a newer client.
+    Unsupported,
+    /// The request was understood but the service failed while answering it.
+    Internal,
+    /// The request was understood but its query can't run, e.g. a range with reversed bounds.
+    InvalidQuery,
+}
+
+/// Sent in place of the expected response when a request can't be answered, so the client
+/// fails fast instead of timing out. `id` is nil when the request's id could not be read.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, thiserror::Error)]
+#[error("service rejected the request ({code:?}): {message}")]
+pub struct ErrorResponse {
+    pub id: Uuid,
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl ErrorResponse {
+    pub fn new(id: Uuid, code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            id,
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// The tagged response payload.
+    pub fn to_payload(&self) -> Vec<u8> {
+        let mut payload = ERROR_FRAME_TAG.to_vec();
+        payload.extend(bincode::serialize(self).unwrap_or_default());
+        payload
+    }
+
+    /// The error carried by a response payload; `None` for any other response.
+    pub fn from_payload(payload: &[u8]) -> Option<Self> {
+        let body = payload.strip_prefix(ERROR_FRAME_TAG)?;
+        bincode::deserialize(body).ok()
+    }
+}
+
+/// Why a [`client::PipeClient`] request failed, so callers can tell a stopped service from a
+/// slow one or from a request the service turned down.
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    /// No response within the request timeout.
+    #[error("request timed out after {} ms", .0.as_millis())]
+    Timeout(Duration),
+    /// Nothing is listening on the pipe: the service is not running, or is restarting.
+    #[error("service is not running: {0}")]
+    NotConnected(std::io::Error),
+    /// The connection failed while the request was in flight.
+    #[error("pipe I/O failed: {0}")]
+    Io(std::io::Error),
+    /// The request could not be encoded, or the response could not be read as the expected
+    /// type.
+    #[error("protocol error: {0}")]
+    Protocol(String),
+    /// The service answered with an [`ErrorResponse`].
+    #[error(transparent)]
+    Server(#[from] ErrorResponse),
+}
+
+impl ClientError {
+    /// Classify a failure to open the connection. A missing pipe or socket, a refused
+    /// connection and a busy pipe (Windows error 231) all mean the service isn't accepting
+    /// requests right now.
+    pub fn connect_failed(err: std::io::Error) -> Self {
+        use std::io::ErrorKind;
+        const ERROR_PIPE_BUSY: i32 = 231;
+        match err.kind() {
+            ErrorKind::NotFound | ErrorKind::ConnectionRefused => Self::NotConnected(err),
+            _ if err.raw_os_error() == Some(ERROR_PIPE_BUSY) => Self::NotConnected(err),
+            _ => Self::Io(err),
+        }
+    }
+
+    /// Whether asking again may help. A service that rejected the request would reject it
+    /// again; everything else can be a restart in progress.
+    pub fn is_retryable(&self) -> bool {
+        !matches!(self, Self::Server(_))
+    }
+
+    /// The service's error code, for [`ClientError::Server`].
+    pub fn server_code(&self) -> Option<ErrorCode> {
+        match self {
+            Self::Server(err) => Some(err.code),
+            _ => None,
+        }
+    }
+}
+
+/// The id of a [`Request`] payload, read past its kind tag, so it is found even for a kind this
+/// build does not know. `None` when the payload is too short or garbled to hold one.
+pub fn request_id(payload: &[u8]) -> Option<Uuid> {
+    bincode::deserialize::<(u32, Uuid)>(payload)
+        .ok()
+        .map(|(_, id)| id)
+}
+
+/// Whether a payload's kind tag names a [`Request`] this build knows, readable or not.
+pub fn is_known_kind(payload: &[u8]) -> bool {
+    bincode::deserialize::<u32>(payload).is_ok_and(|tag| tag < Request::KINDS)
 }
 
 #[cfg(test)]
@@ -328,6 +907,26 @@ mod tests {
         assert!(matches!(req.query, QueryExpr::And(ref items) if items.is_empty()));
     }
 
+    #[test]
+    fn saved_search_builds_a_parsed_request() {
+        let saved = SavedSearch {
+            name: "big pdfs".into(),
+            query: "report ext:pdf".into(),
+            mode: SavedSearchMode::Name,
+        };
+        let req = SearchRequest::from_saved(&saved).unwrap();
+        assert_ne!(req.id, Uuid::nil());
+        assert_eq!(req.mode, SearchMode::NameOnly);
+        assert_eq!(req.limit, SearchRequest::default().limit);
+        assert_eq!(req.query, query::parse_query("report ext:pdf").unwrap());
+
+        let broken = SavedSearch {
+            query: "(unclosed".into(),
+            ..saved
+        };
+        assert!(SearchRequest::from_saved(&broken).is_err());
+    }
+
     #[test]
     fn bincode_roundtrip_query() {
         let q = QueryExpr::And(vec![
@@ -335,6 +934,7 @@ mod tests {
                 field: Some(FieldKind::Name),
                 value: "report".into(),
                 modifier: TermModifier::Prefix,
+                boost: Some(3.0),
             }),
             QueryExpr::Range(RangeExpr {
                 field: FieldKind::Modified,
@@ -348,15 +948,26 @@ mod tests {
 
         let req = SearchRequest {
             id: Uuid::new_v4(),
-            query: q,
+            query: q.clone(),
             limit: 20,
             mode: SearchMode::Hybrid,
             timeout: None,
             offset: 0,
+            cursor: None,
+            explain: false,
+            case_sensitive: false,
+            count_only: false,
+            fields: None,
+            include_system: false,
+            sort: None,
+            suggest: false,
+            group_by: None,
+            normalize_scores: false,
         };
 
         let bytes = ser(&req);
         let back: SearchRequest = de(&bytes);
+        assert_eq!(back.query, q);
         assert_eq!(back.limit, 20);
         assert!(matches!(back.mode, SearchMode::Hybrid));
         assert_eq!(back.timeout, None);
@@ -371,16 +982,290 @@ mod tests {
                 field: None,
                 value: "foo".into(),
                 modifier: TermModifier::Term,
+                boost: None,
             }),
             limit: 5,
             mode: SearchMode::Auto,
             timeout: Some(Duration::from_millis(250)),
             offset: 7,
+            cursor: Some("3f800000.0000000000000001".into()),
+            explain: false,
+            case_sensitive: false,
+            count_only: false,
+            fields: None,
+            include_system: false,
+            sort: None,
+            suggest: false,
+            group_by: None,
+            normalize_scores: false,
         };
         let bytes = ser(&req);
         let back: SearchRequest = de(&bytes);
         assert_eq!(back.timeout, Some(Duration::from_millis(250)));
         assert_eq!(back.offset, 7);
+        assert_eq!(back.cursor.as_deref(), Some("3f800000.0000000000000001"));
+    }
+
+    #[test]
+    fn reindex_request_roundtrips() {
+        let req = ReindexRequest {
+            id: Uuid::new_v4(),
+            volume: Some("C:\\".into()),
+            scope: ReindexScope::ContentOnly,
+        };
+        let bytes = ser(&req);
+        let back: ReindexRequest = de(&bytes);
+        assert_eq!(back.id, req.id);
+        assert_eq!(back.volume.as_deref(), Some("C:\\"));
+        assert_eq!(back.scope, ReindexScope::ContentOnly);
+
+        assert!(ReindexScope::All.includes_meta() && ReindexScope::All.includes_content());
+        assert!(!ReindexScope::MetaOnly.includes_content());
+        assert!(!ReindexScope::ContentOnly.includes_meta());
+    }
+
+    #[test]
+    fn plan_request_roundtrips() {
+        let req = PlanRequest {
+            id: Uuid::new_v4(),
+            query: QueryExpr::Term(TermExpr {
+                field: Some(FieldKind::Ext),
+                value: "pdf".into(),
+                modifier: TermModifier::Term,
+                boost: None,
+            }),
+        };
+        let bytes = ser(&req);
+        let back: PlanRequest = de(&bytes);
+        assert_eq!(back.id, req.id);
+        assert_eq!(back.query, req.query);
+
+        let resp = PlanResponse {
+            id: req.id,
+            chosen_mode: SearchMode::NameOnly,
+            reasons: vec!["metadata fields only: ext:pdf".into()],
+            estimated_cost: 1,
+        };
+        let back: PlanResponse = de(&ser(&resp));
+        assert_eq!(back.chosen_mode, SearchMode::NameOnly);
+        assert_eq!(back.reasons, resp.reasons);
+    }
+
+    #[test]
+    fn preview_request_roundtrips() {
+        let req = PreviewRequest {
+            id: Uuid::new_v4(),
+            key: DocKey::from_parts(2, 0x1234),
+            max_bytes: 4096,
+        };
+        let bytes = ser(&req);
+        let back: PreviewRequest = de(&bytes);
+        assert_eq!((back.id, back.key, back.max_bytes), (req.id, req.key, 4096));
+
+        let resp = PreviewResponse {
+            id: req.id,
+            text: None,
+            truncated: false,
+            kind: FileKind::Image,
+        };
+        let back: PreviewResponse = de(&ser(&resp));
+        assert_eq!((back.text, back.kind), (None, FileKind::Image));
+    }
+
+    #[test]
+    fn complete_request_roundtrips() {
+        let req = CompleteRequest {
+            id: Uuid::new_v4(),
+            prefix: "rep".into(),
+            field: FieldKind::Name,
+            limit: 10,
+        };
+        let back: CompleteRequest = de(&ser(&req));
+        assert_eq!(
+            (back.id, back.prefix.as_str(), back.field, back.limit),
+            (req.id, "rep", FieldKind::Name, 10)
+        );
+
+        let resp = CompleteResponse {
+            id: req.id,
+            completions: vec!["report".into(), "repository".into()],
+        };
+        let back: CompleteResponse = de(&ser(&resp));
+        assert_eq!(back.completions, resp.completions);
+    }
+
+    #[test]
+    fn children_request_roundtrips() {
+        let req = ChildrenRequest {
+            id: Uuid::new_v4(),
+            parent: DocKey::from_parts(3, 5),
+            limit: 200,
+            dirs_only: false,
+        };
+        let back: ChildrenRequest = de(&ser(&req));
+        assert_eq!(
+            (back.id, back.parent, back.limit, back.dirs_only),
+            (req.id, req.parent, 200, false)
+        );
+
+        let resp = ChildrenResponse {
+            id: req.id,
+            hits: vec![],
+            total: 0,
+        };
+        let back: ChildrenResponse = de(&ser(&resp));
+        assert_eq!((back.id, back.total), (req.id, 0));
+    }
+
+    #[test]
+    fn scan_request_roundtrips() {
+        let req = ScanRequest {
+            id: Uuid::new_v4(),
+            after: Some(DocKey::from_parts(2, 77)),
+            limit: 500,
+            volume: None,
+        };
+        let back: ScanRequest = de(&ser(&req));
+        assert_eq!(
+            (back.id, back.after, back.limit, back.volume),
+            (req.id, req.after, 500, None)
+        );
+
+        let resp = ScanResponse {
+            id: req.id,
+            hits: vec![],
+            next: Some(DocKey::from_parts(2, 78)),
+        };
+        let back: ScanResponse = de(&ser(&resp));
+        assert_eq!((back.id, back.next), (req.id, resp.next));
+    }
+
+    #[test]
+    fn list_volumes_request_roundtrips() {
+        let req = ListVolumesRequest {
+            id: Uuid::new_v4(),
+            include_unindexed: true,
+        };
+        let bytes = ser(&req);
+        let back: ListVolumesRequest = de(&bytes);
+        assert_eq!((back.id, back.include_unindexed), (req.id, true));
+
+        let resp = ListVolumesResponse {
+            id: req.id,
+            volumes: vec![VolumeEntry {
+                volume: VolumeDescriptor {
+                    id: 1,
+                    guid_path: r"\\?\Volume{1}\".into(),
+                    drive_letters: vec!["C:".into()],
+                },
+                indexed: true,
+                content_indexed: false,
+            }],
+        };
+        let back: ListVolumesResponse = de(&ser(&resp));
+        assert_eq!(back.volumes, resp.volumes);
+    }
+
+    #[test]
+    fn requests_are_told_apart_by_their_kind_tag() {
+        let id = Uuid::new_v4();
+        let key = DocKey::from_parts(3, 5);
+        // Same shape as a `PreviewRequest` for `key`, and a `ListVolumesRequest` is all but a
+        // `StatusRequest`; the tag keeps each apart.
+        let children = Request::from(ChildrenRequest {
+            id,
+            parent: key,
+            limit: 200,
+            dirs_only: false,
+        });
+        let preview = Request::from(PreviewRequest {
+            id,
+            key,
+            max_bytes: 200,
+        });
+        let back: Request = de(&ser(&children));
+        assert!(matches!(back, Request::Children(ref r) if r.parent == key && r.limit == 200));
+        assert!(matches!(de::<Request>(&ser(&preview)), Request::Preview(_)));
+        assert!(matches!(
+            de::<Request>(&ser(&Request::from(StatusRequest { id }))),
+            Request::Status(_)
+        ));
+        assert_eq!(back.id(), id);
+        assert_eq!(request_id(&ser(&back)), Some(id));
+
+        // A kind this build does not know still yields its id.
+        let newer = ser(&(u32::MAX, id));
+        assert!(bincode::deserialize::<Request>(&newer).is_err());
+        assert_eq!(request_id(&newer), Some(id));
+    }
+
+    #[test]
+    fn error_responses_are_tagged_apart_from_responses() {
+        let err = ErrorResponse::new(Uuid::new_v4(), ErrorCode::Unsupported, "unknown request");
+        assert_eq!(
+            ErrorResponse::from_payload(&err.to_payload()),
+            Some(err.clone())
+        );
+
+        let status = ser(&Request::from(StatusRequest { id: err.id }));
+        assert_eq!(ErrorResponse::from_payload(&status), None);
+        assert_eq!(request_id(&status), Some(err.id));
+        assert_eq!(request_id(b"junk"), None);
+        assert!(is_known_kind(&status));
+        assert!(!is_known_kind(&ser(&(Request::KINDS, err.id))));
+    }
+
+    #[test]
+    fn connect_failures_are_classified() {
+        use std::io::{Error, ErrorKind};
+        let classify = |err: Error| ClientError::connect_failed(err);
+        for kind in [ErrorKind::NotFound, ErrorKind::ConnectionRefused] {
+            assert!(matches!(
+                classify(Error::from(kind)),
+                ClientError::NotConnected(_)
+            ));
+        }
+        assert!(matches!(
+            classify(Error::from_raw_os_error(231)),
+            ClientError::NotConnected(_)
+        ));
+        assert!(matches!(
+            classify(Error::from(ErrorKind::PermissionDenied)),
+            ClientError::Io(_)
+        ));
+
+        let rejected = ClientError::from(ErrorResponse::new(
+            Uuid::nil(),
+            ErrorCode::BadRequest,
+            "garbled",
+        ));
+        assert_eq!(rejected.server_code(), Some(ErrorCode::BadRequest));
+        assert!(!rejected.is_retryable());
+        assert!(ClientError::Timeout(Duration::from_millis(5)).is_retryable());
+    }
+
+    #[test]
+    fn status_queue_depth_by_category_roundtrips() {
+        let resp = StatusResponse {
+            id: Uuid::new_v4(),
+            volumes: vec![],
+            last_index_commit_ts: None,
+            scheduler_state: "idle".into(),
+            content_jobs_total: None,
+            content_jobs_remaining: None,
+            content_bytes_total: None,
+            content_bytes_remaining: None,
+            metrics: None,
+            served_by: None,
+            readiness: Readiness::Ready,
+            queue_depth_by_category: Some([
+                ("critical".into(), 1),
+                ("metadata".into(), 0),
+                ("content".into(), 12),
+            ]),
+        };
+        let back: StatusResponse = de(&ser(&resp));
+        assert_eq!(back.queue_depth_by_category, resp.queue_depth_by_category);
     }
 
     #[test]
@@ -393,11 +1278,15 @@ mod tests {
             pending_bytes: 512,
             last_usn: Some(42),
             journal_id: Some(7),
+            free_bytes: Some(4096),
+            total_bytes: Some(8192),
         };
         let encoded = ser(&v);
         let decoded: VolumeStatus = de(&encoded);
         assert_eq!(decoded.last_usn, Some(42));
         assert_eq!(decoded.journal_id, Some(7));
+        assert_eq!(decoded.free_bytes, Some(4096));
+        assert_eq!(decoded.total_bytes, Some(8192));
         assert_eq!(decoded.indexed_bytes, 1024);
         assert_eq!(decoded.pending_bytes, 512);
     }
@@ -413,9 +1302,20 @@ mod tests {
             active_workers: Some(2),
             content_enqueued: Some(9),
             content_dropped: Some(1),
+            extract_p50_ms: Some(4.0),
+            extract_p95_ms: Some(250.0),
+            extract_by_kind: vec![ExtractKindStats {
+                kind: FileKind::Pdf,
+                files: 3,
+                bytes: 4096,
+                p50_ms: Some(20.0),
+                p95_ms: Some(250.0),
+            }],
         };
         let bytes = ser(&m);
         let back: MetricsSnapshot = de(&bytes);
+        assert_eq!(back.extract_p95_ms, Some(250.0));
+        assert_eq!(back.extract_by_kind, m.extract_by_kind);
         assert_eq!(back.queue_depth, Some(5));
         assert_eq!(back.active_workers, Some(2));
         assert_eq!(back.content_enqueued, Some(9));
@@ -439,6 +1339,7 @@ mod tests {
             field: Some(FieldKind::Name),
             value: "foo".into(),
             modifier: TermModifier::Prefix,
+            boost: None,
         });
         let req = SearchRequest::with_query(q.clone())
             .with_timeout_ms(500)
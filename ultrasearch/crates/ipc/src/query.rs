@@ -0,0 +1,730 @@
+//! Text query language shared by the CLI and UI.
+//!
+//! ```text
+//! report ext:pdf                 implicit AND
+//! (draft OR final) -tmp          grouping, OR, NOT (`-` or `NOT`)
+//! name:"annual report"           phrase; `rep*` prefix, `repot~1` fuzzy, `*port*` substring
+//! name:report^3 "q3 plan"^2      boost a term's score
+//! size:>10M  modified:2024-01-01..2024-06-30  size:1K..4K
+//! flags:dir  NOT flags:hidden    attribute flags (dir, hidden, system, archive, ...)
+//! ```
+//!
+//! Field names are case-insensitive. Sizes take `K`/`M`/`G`/`T` suffixes (binary units) and
+//! timestamps are unix seconds or `YYYY-MM-DD` (UTC). Errors carry the byte span of the offending
+//! input so callers can underline it.
+
+use crate::{FieldKind, QueryExpr, RangeExpr, RangeOp, RangeValue, TermExpr, TermModifier};
+use core_types::FileFlags;
+use std::ops::Range;
+
+/// Why a query failed to parse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A `(` without its `)`, or a stray `)`.
+    UnbalancedParen,
+    /// `field:` with a field name the language does not know.
+    UnknownField,
+    /// A malformed or inverted range/number on a numeric field.
+    BadRange,
+    /// A `"` without its closing quote.
+    UnterminatedQuote,
+    /// An operator or field prefix with nothing to apply to (`a OR`, `NOT`, `name:`).
+    MissingOperand,
+    /// A `^` boost that is not a positive number, or a boost on a range.
+    BadBoost,
+}
+
+/// Parse failure pointing at `span`, a byte range into the input.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("{message} (at {}..{})", span.start, span.end)]
+pub struct ParseError {
+    pub message: String,
+    pub span: Range<usize>,
+    pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+    fn new(kind: ParseErrorKind, span: Range<usize>, message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            span,
+            kind,
+        }
+    }
+
+    /// A line of spaces and carets that underlines the span when printed below `input`.
+    pub fn marker(&self, input: &str) -> String {
+        let start = input[..self.span.start.min(input.len())].chars().count();
+        let width = input
+            .get(self.span.clone())
+            .map_or(1, |s| s.chars().count().max(1));
+        format!("{}{}", " ".repeat(start), "^".repeat(width))
+    }
+}
+
+/// Parse `input` into a query. Empty input (or `()`) yields an empty `And`, which matches
+/// nothing useful; callers decide whether to send it.
+pub fn parse_query(input: &str) -> Result<QueryExpr, ParseError> {
+    let tokens = lex(input)?;
+    let mut parser = Parser {
+        tokens: &tokens,
+        pos: 0,
+    };
+    let expr = parser.or()?;
+    match parser.peek() {
+        None => Ok(expr),
+        Some(tok) => Err(ParseError::new(
+            ParseErrorKind::UnbalancedParen,
+            tok.span.clone(),
+            "unmatched ')'",
+        )),
+    }
+}
+
+/// True when `input` has nothing to search for: whitespace, or only parentheses, operators and
+/// punctuation (`()`, `-`, `""`). Such input parses to an empty or letterless query, which
+/// callers should treat as no query at all rather than send.
+pub fn is_blank(input: &str) -> bool {
+    match parse_query(input) {
+        Ok(expr) => is_empty_expr(&expr),
+        Err(_) => !input.chars().any(char::is_alphanumeric),
+    }
+}
+
+fn is_empty_expr(expr: &QueryExpr) -> bool {
+    match expr {
+        QueryExpr::Term(term) => !term.value.chars().any(char::is_alphanumeric),
+        QueryExpr::Range(_) => false,
+        QueryExpr::Not(inner) => is_empty_expr(inner),
+        QueryExpr::And(parts) | QueryExpr::Or(parts) => parts.iter().all(is_empty_expr),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokKind {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Atom {
+        field: Option<FieldKind>,
+        value: String,
+        quoted: bool,
+        /// Span of the value alone, without `field:`, quotes or boost.
+        value_span: Range<usize>,
+        boost: Option<f32>,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokKind,
+    span: Range<usize>,
+}
+
+fn field_by_name(name: &str) -> Option<FieldKind> {
+    Some(match name.to_ascii_lowercase().as_str() {
+        "name" => FieldKind::Name,
+        "path" => FieldKind::Path,
+        "ext" => FieldKind::Ext,
+        "content" => FieldKind::Content,
+        "size" => FieldKind::Size,
+        "modified" => FieldKind::Modified,
+        "created" => FieldKind::Created,
+        "flags" => FieldKind::Flags,
+        "volume" => FieldKind::Volume,
+        "kind" => FieldKind::Kind,
+        _ => return None,
+    })
+}
+
+fn is_break(c: char) -> bool {
+    c.is_whitespace() || c == '(' || c == ')'
+}
+
+fn parse_boost(text: &str) -> Option<f32> {
+    text.parse::<f32>()
+        .ok()
+        .filter(|b| b.is_finite() && *b > 0.0)
+}
+
+fn lex(input: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while let Some(c) = input[i..].chars().next() {
+        let start = i;
+        if c.is_whitespace() {
+            i += c.len_utf8();
+            continue;
+        }
+        let simple = match c {
+            '(' => Some(TokKind::Open),
+            ')' => Some(TokKind::Close),
+            '-' if input[i + 1..].starts_with(|n: char| !is_break(n)) => Some(TokKind::Not),
+            _ => None,
+        };
+        if let Some(kind) = simple {
+            i += 1;
+            tokens.push(Token {
+                kind,
+                span: start..i,
+            });
+            continue;
+        }
+
+        // `field:` prefix; a single letter is a drive (`C:\`), not a field.
+        let mut field = None;
+        let name_len = input[i..]
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(input.len() - i);
+        if name_len >= 2 && input[i + name_len..].starts_with(':') {
+            let name = &input[i..i + name_len];
+            field = Some(field_by_name(name).ok_or_else(|| {
+                ParseError::new(
+                    ParseErrorKind::UnknownField,
+                    i..i + name_len,
+                    format!("unknown field '{name}'"),
+                )
+            })?);
+            i += name_len + 1;
+        }
+
+        let (mut value_span, quoted) = if input[i..].starts_with('"') {
+            let body = i + 1;
+            let Some(len) = input[body..].find('"') else {
+                return Err(ParseError::new(
+                    ParseErrorKind::UnterminatedQuote,
+                    i..input.len(),
+                    "missing closing '\"'",
+                ));
+            };
+            i = body + len + 1;
+            (body..body + len, true)
+        } else {
+            let len = input[i..].find(is_break).unwrap_or(input.len() - i);
+            i += len;
+            (i - len..i, false)
+        };
+        // `^N` boost: after a phrase it must be a number; on a bare word it is only a boost
+        // when it parses, since `^` also appears in file names.
+        let mut boost = None;
+        if quoted && input[i..].starts_with('^') {
+            let len = input[i..].find(is_break).unwrap_or(input.len() - i);
+            let text = &input[i + 1..i + len];
+            boost = Some(parse_boost(text).ok_or_else(|| {
+                ParseError::new(
+                    ParseErrorKind::BadBoost,
+                    i..i + len,
+                    format!("boost '{text}' is not a positive number"),
+                )
+            })?);
+            i += len;
+        } else if !quoted
+            && let Some((stem, text)) = input[value_span.clone()].rsplit_once('^')
+            && let Some(b) = parse_boost(text)
+        {
+            boost = Some(b);
+            value_span.end = value_span.start + stem.len();
+        }
+        let value = &input[value_span.clone()];
+        let kind = match (field, quoted, value) {
+            (None, false, "AND") => TokKind::And,
+            (None, false, "OR") => TokKind::Or,
+            (None, false, "NOT") => TokKind::Not,
+            _ => TokKind::Atom {
+                field,
+                value: value.to_string(),
+                quoted,
+                value_span,
+                boost,
+            },
+        };
+        tokens.push(Token {
+            kind,
+            span: start..i,
+        });
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn missing_operand(tok: &Token) -> ParseError {
+        ParseError::new(
+            ParseErrorKind::MissingOperand,
+            tok.span.clone(),
+            "operator has nothing to apply to",
+        )
+    }
+
+    fn or(&mut self) -> Result<QueryExpr, ParseError> {
+        let mut parts = vec![self.and()?];
+        while let Some(tok) = self.peek().filter(|t| t.kind == TokKind::Or) {
+            let tok = tok.clone();
+            self.pos += 1;
+            let next = self.and()?;
+            if parts.last().is_some_and(is_empty) || is_empty(&next) {
+                return Err(Self::missing_operand(&tok));
+            }
+            parts.push(next);
+        }
+        Ok(collapse(parts, QueryExpr::Or))
+    }
+
+    fn and(&mut self) -> Result<QueryExpr, ParseError> {
+        let mut parts = Vec::new();
+        while let Some(tok) = self.peek().cloned() {
+            match tok.kind {
+                TokKind::Close | TokKind::Or => break,
+                TokKind::And => {
+                    self.pos += 1;
+                    if parts.is_empty()
+                        || self
+                            .peek()
+                            .is_none_or(|t| matches!(t.kind, TokKind::Close | TokKind::Or))
+                    {
+                        return Err(Self::missing_operand(&tok));
+                    }
+                }
+                _ => parts.push(self.unary()?),
+            }
+        }
+        Ok(collapse(parts, QueryExpr::And))
+    }
+
+    fn unary(&mut self) -> Result<QueryExpr, ParseError> {
+        let tok = self.tokens[self.pos].clone();
+        self.pos += 1;
+        match tok.kind {
+            TokKind::Not => match self.peek() {
+                Some(next) if !matches!(next.kind, TokKind::Close | TokKind::Or | TokKind::And) => {
+                    Ok(QueryExpr::Not(Box::new(self.unary()?)))
+                }
+                _ => Err(Self::missing_operand(&tok)),
+            },
+            TokKind::Open => {
+                let inner = self.or()?;
+                match self.peek() {
+                    Some(t) if t.kind == TokKind::Close => {
+                        self.pos += 1;
+                        Ok(inner)
+                    }
+                    _ => Err(ParseError::new(
+                        ParseErrorKind::UnbalancedParen,
+                        tok.span,
+                        "missing closing ')'",
+                    )),
+                }
+            }
+            TokKind::Atom {
+                field,
+                value,
+                quoted,
+                value_span,
+                boost,
+            } => atom(field, &value, quoted, boost, tok.span, value_span),
+            TokKind::Close | TokKind::And | TokKind::Or => {
+                unreachable!("and() stops before {:?}", tok.kind)
+            }
+        }
+    }
+}
+
+fn is_empty(expr: &QueryExpr) -> bool {
+    matches!(expr, QueryExpr::And(parts) if parts.is_empty())
+}
+
+fn collapse(mut parts: Vec<QueryExpr>, wrap: fn(Vec<QueryExpr>) -> QueryExpr) -> QueryExpr {
+    if parts.len() == 1 {
+        parts.pop().expect("one part")
+    } else {
+        wrap(parts)
+    }
+}
+
+fn atom(
+    field: Option<FieldKind>,
+    value: &str,
+    quoted: bool,
+    boost: Option<f32>,
+    span: Range<usize>,
+    value_span: Range<usize>,
+) -> Result<QueryExpr, ParseError> {
+    let missing = || {
+        ParseError::new(
+            ParseErrorKind::MissingOperand,
+            span.clone(),
+            "expected a value",
+        )
+    };
+    if value.is_empty() {
+        return Err(missing());
+    }
+    if let Some(
+        field @ (FieldKind::Size
+        | FieldKind::Modified
+        | FieldKind::Created
+        | FieldKind::Volume
+        | FieldKind::Flags),
+    ) = field
+    {
+        if boost.is_some() {
+            return Err(ParseError::new(
+                ParseErrorKind::BadBoost,
+                span,
+                "ranges filter and cannot be boosted",
+            ));
+        }
+        // `flags:hidden` names a flag; a number still compares the raw bits.
+        if field == FieldKind::Flags && !quoted && value.starts_with(|c: char| c.is_alphabetic()) {
+            if FileFlags::from_query_name(value).is_none() {
+                return Err(ParseError::new(
+                    ParseErrorKind::BadRange,
+                    value_span,
+                    format!(
+                        "unknown flag '{value}' (expected dir, hidden, system, archive, reparse, offline or temp)"
+                    ),
+                ));
+            }
+            return Ok(QueryExpr::Term(TermExpr {
+                field: Some(field),
+                value: value.to_ascii_lowercase(),
+                modifier: TermModifier::Term,
+                boost: None,
+            }));
+        }
+        return range(field, value, value_span);
+    }
+
+    let (value, modifier) = if quoted {
+        (value, TermModifier::Phrase)
+    } else if let Some(inner) = value.strip_prefix('*').and_then(|v| v.strip_suffix('*')) {
+        (inner, TermModifier::Substring)
+    } else if let Some(stem) = value.strip_suffix('*') {
+        (stem, TermModifier::Prefix)
+    } else if let Some((stem, distance)) = value.rsplit_once('~') {
+        match distance {
+            "" => (stem, TermModifier::Fuzzy(1)),
+            d => match d.parse::<u8>() {
+                Ok(d) => (stem, TermModifier::Fuzzy(d)),
+                Err(_) => (value, TermModifier::Term),
+            },
+        }
+    } else {
+        (value, TermModifier::Term)
+    };
+    if value.is_empty() {
+        return Err(missing());
+    }
+    Ok(QueryExpr::Term(TermExpr {
+        field,
+        value: value.to_string(),
+        modifier,
+        boost,
+    }))
+}
+
+/// `>v`, `>=v`, `<v`, `<=v`, `lo..hi`, `lo..`, `..hi` or a bare value. A bare date covers the
+/// whole day.
+fn range(field: FieldKind, value: &str, span: Range<usize>) -> Result<QueryExpr, ParseError> {
+    let bad = |message: String| ParseError::new(ParseErrorKind::BadRange, span.clone(), message);
+    let signed = matches!(field, FieldKind::Modified | FieldKind::Created);
+    // (start, last) of the interval one literal denotes.
+    let number = |text: &str| -> Result<(i128, i128), ParseError> {
+        let parsed = if signed {
+            parse_time(text)
+        } else if field == FieldKind::Size {
+            parse_size(text).map(|v| (v, v))
+        } else {
+            text.parse::<u64>().ok().map(|v| (v as i128, v as i128))
+        };
+        parsed.ok_or_else(|| bad(format!("invalid {field:?} value '{text}'")))
+    };
+
+    let (op, lo, hi) = if let Some((lo, hi)) = value.split_once("..") {
+        match (lo, hi) {
+            ("", "") => return Err(bad("range needs at least one bound".into())),
+            ("", hi) => (RangeOp::Le, number(hi)?.1, None),
+            (lo, "") => (RangeOp::Ge, number(lo)?.0, None),
+            (lo, hi) => {
+                let (lo, hi) = (number(lo)?.0, number(hi)?.1);
+                if lo > hi {
+                    return Err(bad(format!("range start {lo} is after its end {hi}")));
+                }
+                (RangeOp::Between, lo, Some(hi))
+            }
+        }
+    } else if let Some(v) = value.strip_prefix(">=") {
+        (RangeOp::Ge, number(v)?.0, None)
+    } else if let Some(v) = value.strip_prefix("<=") {
+        (RangeOp::Le, number(v)?.1, None)
+    } else if let Some(v) = value.strip_prefix('>') {
+        (RangeOp::Gt, number(v)?.1, None)
+    } else if let Some(v) = value.strip_prefix('<') {
+        (RangeOp::Lt, number(v)?.0, None)
+    } else {
+        let (lo, hi) = number(value.strip_prefix('=').unwrap_or(value))?;
+        (RangeOp::Between, lo, Some(hi))
+    };
+
+    let value = if signed {
+        RangeValue::I64 {
+            lo: lo as i64,
+            hi: hi.map(|h| h as i64),
+        }
+    } else {
+        RangeValue::U64 {
+            lo: lo as u64,
+            hi: hi.map(|h| h as u64),
+        }
+    };
+    Ok(QueryExpr::Range(RangeExpr { field, op, value }))
+}
+
+/// Bytes, with an optional binary `K`/`M`/`G`/`T` suffix (`B` after it is allowed).
+fn parse_size(text: &str) -> Option<i128> {
+    let upper = text.to_ascii_uppercase();
+    let digits = upper.strip_suffix('B').unwrap_or(&upper);
+    let (digits, shift) = match digits.chars().last()? {
+        'K' => (&digits[..digits.len() - 1], 10),
+        'M' => (&digits[..digits.len() - 1], 20),
+        'G' => (&digits[..digits.len() - 1], 30),
+        'T' => (&digits[..digits.len() - 1], 40),
+        _ => (digits, 0),
+    };
+    let n = digits.parse::<u64>().ok()?;
+    n.checked_mul(1 << shift).map(i128::from)
+}
+
+/// Unix seconds, or a `YYYY-MM-DD` day as its first and last second (UTC).
+fn parse_time(text: &str) -> Option<(i128, i128)> {
+    if let Ok(secs) = text.parse::<i64>() {
+        return Some((secs as i128, secs as i128));
+    }
+    let mut parts = text.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    let days_in_month = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        _ => return None,
+    };
+    if day == 0 || day > days_in_month {
+        return None;
+    }
+    let start = days_from_civil(year, month, day) as i128 * 86_400;
+    Some((start, start + 86_399))
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date (Howard Hinnant's algorithm).
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(field: Option<FieldKind>, value: &str, modifier: TermModifier) -> QueryExpr {
+        QueryExpr::Term(TermExpr {
+            field,
+            value: value.into(),
+            modifier,
+            boost: None,
+        })
+    }
+
+    fn err(input: &str) -> (ParseErrorKind, &str) {
+        let e = parse_query(input).expect_err(input);
+        (e.kind, &input[e.span])
+    }
+
+    #[test]
+    fn parses_operators_fields_and_modifiers() {
+        let q =
+            parse_query(r#"(draft OR name:"final cut") -tmp ext:pdf rep* repot~2 *port*"#).unwrap();
+        let expected = QueryExpr::And(vec![
+            QueryExpr::Or(vec![
+                term(None, "draft", TermModifier::Term),
+                term(Some(FieldKind::Name), "final cut", TermModifier::Phrase),
+            ]),
+            QueryExpr::Not(Box::new(term(None, "tmp", TermModifier::Term))),
+            term(Some(FieldKind::Ext), "pdf", TermModifier::Term),
+            term(None, "rep", TermModifier::Prefix),
+            term(None, "repot", TermModifier::Fuzzy(2)),
+            term(None, "port", TermModifier::Substring),
+        ]);
+        assert_eq!(q, expected);
+        assert_eq!(
+            parse_query("a AND NOT b").unwrap(),
+            parse_query("a -b").unwrap()
+        );
+        assert_eq!(
+            parse_query(r"C:\Users").unwrap(),
+            term(None, r"C:\Users", TermModifier::Term)
+        );
+        assert_eq!(parse_query("  ").unwrap(), QueryExpr::default());
+        assert_eq!(parse_query("()").unwrap(), QueryExpr::default());
+    }
+
+    #[test]
+    fn blank_input_has_nothing_to_search() {
+        for input in [
+            "", "   ", "\t\n", "()", "( ( ) )", "\"\"", "-", "(", "... !!",
+        ] {
+            assert!(is_blank(input), "{input:?}");
+        }
+        for input in [" report ", "(a)", "-tmp", "size:>1M", "ü", "a OR ()"] {
+            assert!(!is_blank(input), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn parses_flag_names() {
+        assert_eq!(
+            parse_query("flags:Hidden").unwrap(),
+            term(Some(FieldKind::Flags), "hidden", TermModifier::Term)
+        );
+        assert_eq!(
+            parse_query("NOT flags:dir").unwrap(),
+            QueryExpr::Not(Box::new(term(
+                Some(FieldKind::Flags),
+                "dir",
+                TermModifier::Term
+            )))
+        );
+        assert!(matches!(
+            parse_query("flags:2").unwrap(),
+            QueryExpr::Range(_)
+        ));
+        assert_eq!(err("flags:shiny"), (ParseErrorKind::BadRange, "shiny"));
+    }
+
+    #[test]
+    fn parses_boosts() {
+        let boosted = |field, value: &str, modifier, boost| {
+            QueryExpr::Term(TermExpr {
+                field,
+                value: value.into(),
+                modifier,
+                boost: Some(boost),
+            })
+        };
+        assert_eq!(
+            parse_query(r#"name:report^3 "q3 plan"^0.5 rep*^2"#).unwrap(),
+            QueryExpr::And(vec![
+                boosted(Some(FieldKind::Name), "report", TermModifier::Term, 3.0),
+                boosted(None, "q3 plan", TermModifier::Phrase, 0.5),
+                boosted(None, "rep", TermModifier::Prefix, 2.0),
+            ])
+        );
+        // Not a number after `^`: part of the name.
+        assert_eq!(
+            parse_query("a^b").unwrap(),
+            term(None, "a^b", TermModifier::Term)
+        );
+        assert_eq!(
+            parse_query("x^0").unwrap(),
+            term(None, "x^0", TermModifier::Term)
+        );
+    }
+
+    #[test]
+    fn parses_sizes_and_dates() {
+        let range = |input| match parse_query(input).unwrap() {
+            QueryExpr::Range(r) => (r.field, r.op, r.value),
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(
+            range("size:>10M"),
+            (
+                FieldKind::Size,
+                RangeOp::Gt,
+                RangeValue::U64 {
+                    lo: 10 << 20,
+                    hi: None
+                }
+            )
+        );
+        assert_eq!(
+            range("SIZE:1k..4KB"),
+            (
+                FieldKind::Size,
+                RangeOp::Between,
+                RangeValue::U64 {
+                    lo: 1024,
+                    hi: Some(4096)
+                }
+            )
+        );
+        // 2024-01-01T00:00:00Z .. 2024-01-31T23:59:59Z
+        assert_eq!(
+            range("modified:2024-01-01..2024-01-31"),
+            (
+                FieldKind::Modified,
+                RangeOp::Between,
+                RangeValue::I64 {
+                    lo: 1_704_067_200,
+                    hi: Some(1_706_745_599)
+                }
+            )
+        );
+        assert_eq!(
+            range("created:<=1700000000"),
+            (
+                FieldKind::Created,
+                RangeOp::Le,
+                RangeValue::I64 {
+                    lo: 1_700_000_000,
+                    hi: None
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn errors_carry_kind_and_span() {
+        assert_eq!(err("(a OR b"), (ParseErrorKind::UnbalancedParen, "("));
+        assert_eq!(err("a) b"), (ParseErrorKind::UnbalancedParen, ")"));
+        assert_eq!(
+            err("x colour:red"),
+            (ParseErrorKind::UnknownField, "colour")
+        );
+        assert_eq!(err("size:>lots"), (ParseErrorKind::BadRange, ">lots"));
+        assert_eq!(err("size:4K..1K"), (ParseErrorKind::BadRange, "4K..1K"));
+        assert_eq!(
+            err("modified:2024-02-30"),
+            (ParseErrorKind::BadRange, "2024-02-30")
+        );
+        assert_eq!(
+            err(r#"a "open phrase"#),
+            (ParseErrorKind::UnterminatedQuote, r#""open phrase"#)
+        );
+        assert_eq!(err("a OR"), (ParseErrorKind::MissingOperand, "OR"));
+        assert_eq!(err("name: x"), (ParseErrorKind::MissingOperand, "name:"));
+        assert_eq!(err(r#""a b"^big"#), (ParseErrorKind::BadBoost, "^big"));
+        assert_eq!(err("size:>1M^2"), (ParseErrorKind::BadBoost, "size:>1M^2"));
+
+        let e = parse_query("a size:big").unwrap_err();
+        assert_eq!(e.span, 7..10);
+        assert_eq!(e.marker("a size:big"), "       ^^^");
+    }
+}
@@ -3,7 +3,7 @@
 use anyhow::{Context, Result};
 use console::style;
 use dotenvy::{dotenv, from_path};
-use ipc::{StatusRequest, client::PipeClient};
+use ipc::{Readiness, StatusRequest, client::PipeClient};
 #[cfg(windows)]
 use std::os::windows::process::CommandExt;
 use std::{
@@ -174,22 +174,42 @@ fn wait_for_ipc_ready(service: &mut ChildGuard) -> Result<()> {
                 anyhow::bail!("service exited early with status {:?}", status.code());
             }
 
+            // A responding pipe is not enough: wait until indexes are open and scanned.
             let res = rt.block_on(client.status(StatusRequest { id: Uuid::new_v4() }));
             match res {
-                Ok(_) => {
+                Ok(resp) if resp.readiness == Readiness::Ready => {
                     println!("{}", style("IPC ready").green());
                     return Ok(());
                 }
-                Err(err) if attempts < 120 => {
+                Ok(resp) if resp.readiness == Readiness::Degraded => {
+                    // Waiting will not help; let the UI surface the service's status.
+                    println!(
+                        "{}",
+                        style("service degraded: an index failed to open").yellow()
+                    );
+                    return Ok(());
+                }
+                Ok(resp) if attempts >= 120 => {
+                    println!();
+                    println!(
+                        "{}",
+                        style(format!(
+                            "service still {:?} after retries; launching UI anyway",
+                            resp.readiness
+                        ))
+                        .yellow()
+                    );
+                    return Ok(());
+                }
+                Err(err) if attempts >= 120 => {
+                    println!();
+                    anyhow::bail!("IPC not ready after retries: {err}");
+                }
+                Ok(_) | Err(_) => {
                     attempts += 1;
                     print!(".");
                     let _ = std::io::stdout().flush();
                     sleep(Duration::from_millis(500));
-                    continue;
-                }
-                Err(err) => {
-                    println!();
-                    anyhow::bail!("IPC not ready after retries: {err}");
                 }
             }
         }
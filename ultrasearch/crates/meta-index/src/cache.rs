@@ -131,6 +131,36 @@ impl MetadataCache {
         Some(path_arc)
     }
 
+    /// Drop the cached paths of `key` and of everything beneath it, e.g. after a directory
+    /// rename. Items stay cached; only the joined paths are rebuilt on the next lookup.
+    pub fn invalidate_subtree(&mut self, key: DocKey) {
+        let stale: Vec<DocKey> = self
+            .path_cache
+            .iter()
+            .map(|(k, _)| *k)
+            .filter(|&k| self.is_within(k, key))
+            .collect();
+        for k in stale {
+            self.path_cache.pop(&k);
+        }
+    }
+
+    /// Whether `ancestor` is `key` or one of its cached parents.
+    fn is_within(&self, key: DocKey, ancestor: DocKey) -> bool {
+        let mut current = key;
+        // Bounded by the item count so a corrupt parent cycle cannot spin forever.
+        for _ in 0..=self.slots.len() {
+            if current == ancestor {
+                return true;
+            }
+            match self.get(current).and_then(|item| item.parent) {
+                Some(parent) if parent != current => current = parent,
+                _ => return false,
+            }
+        }
+        false
+    }
+
     pub fn resolve_name(&self, item: &CachedItem) -> &str {
         self.interner.resolve(&item.name)
     }
@@ -227,4 +257,28 @@ mod tests {
         // Verify dir is now in cache
         assert!(cache.get(dir_key).is_some());
     }
+
+    #[test]
+    fn invalidate_subtree_rebuilds_paths_below_a_renamed_dir() {
+        let mut cache = MetadataCache::new(10);
+        let root_key = DocKey::from_parts(1, 1);
+        let dir_key = DocKey::from_parts(1, 2);
+        let file_key = DocKey::from_parts(1, 3);
+        let other_key = DocKey::from_parts(1, 4);
+
+        cache.put(&make_meta(root_key, None, "C:"));
+        cache.put(&make_meta(dir_key, Some(root_key), "Users"));
+        cache.put(&make_meta(file_key, Some(dir_key), "test.txt"));
+        cache.put(&make_meta(other_key, Some(root_key), "other.txt"));
+        cache.resolve_path(file_key, |_| None).unwrap();
+        cache.resolve_path(other_key, |_| None).unwrap();
+
+        cache.put(&make_meta(dir_key, Some(root_key), "People"));
+        cache.invalidate_subtree(dir_key);
+        assert!(cache.path_cache.peek(&file_key).is_none());
+        assert!(cache.path_cache.peek(&other_key).is_some());
+
+        let path = cache.resolve_path(file_key, |_| None).unwrap();
+        assert!(path.contains("People"), "{path}");
+    }
 }
@@ -0,0 +1,289 @@
+//! In-memory "delta" tier for bursty metadata changes (`features.delta_index`).
+//!
+//! Every commit of the on-disk meta index flushes a new segment, and a steady trickle of USN
+//! changes committed one tick at a time keeps the merger rewriting segments far larger than the
+//! change. The delta tier absorbs those updates instead: they are committed to a small RAM index
+//! that searches read alongside the main one, and [`DeltaIndex::merge_into`] folds everything
+//! accumulated into the main index with a single commit.
+//!
+//! A key held by the delta shadows the main index's doc with that key, and keys removed while
+//! changes sit in the delta are remembered until the merge deletes them from the main index, so
+//! a search that unions both tiers sees each file once, in its newest form.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use anyhow::Result;
+use core_types::{DocKey, FileMeta as CoreFileMeta};
+use tantivy::directory::RamDirectory;
+use tantivy::{Index, IndexReader, IndexSettings, IndexWriter, ReloadPolicy, Searcher};
+
+use crate::{MetaIndex, WriterConfig, create_writer, delete_file_meta, upsert_file_meta};
+
+/// Writer heap of the delta tier; it only ever holds one merge interval's worth of changes.
+const DELTA_HEAP_BYTES: usize = 16 * 1024 * 1024;
+
+/// RAM index holding metadata changes not yet merged into the main index.
+pub struct DeltaIndex {
+    meta: MetaIndex,
+    reader: IndexReader,
+    state: Mutex<DeltaState>,
+    /// Completed [`merge_into`](Self::merge_into) calls.
+    merges: AtomicU64,
+    /// Commits to the delta since it was created; bumps whenever its contents change.
+    version: AtomicU64,
+}
+
+struct DeltaState {
+    writer: IndexWriter,
+    /// Newest version of every file upserted since the last merge.
+    pending: HashMap<DocKey, CoreFileMeta>,
+    /// Keys deleted since the last merge, still present in the main index.
+    removed: HashSet<DocKey>,
+}
+
+/// A consistent view of the delta: its searcher, the main-index keys it hides, and the merge
+/// count at the time it was taken.
+pub struct DeltaSnapshot {
+    pub searcher: Searcher,
+    /// Keys whose main-index docs are stale: upserted or removed in the delta.
+    pub shadowed: Arc<HashSet<DocKey>>,
+    pub merges: u64,
+}
+
+impl DeltaSnapshot {
+    /// True when the delta neither holds nor hides anything.
+    pub fn is_empty(&self) -> bool {
+        self.shadowed.is_empty()
+    }
+}
+
+impl DeltaIndex {
+    /// Create an empty delta with the same schema, tokenizers and field options as `main`, so
+    /// queries built for the main index run unchanged against it.
+    pub fn for_main(main: &MetaIndex) -> Result<Self> {
+        let index = Index::create(
+            RamDirectory::create(),
+            main.index.schema(),
+            IndexSettings::default(),
+        )?;
+        let mut meta = MetaIndex::from_index(index)?;
+        meta.fields = main.fields.clone();
+        let writer = meta.index.writer_with_num_threads(1, DELTA_HEAP_BYTES)?;
+        let reader = meta
+            .index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        Ok(Self {
+            meta,
+            reader,
+            state: Mutex::new(DeltaState {
+                writer,
+                pending: HashMap::new(),
+                removed: HashSet::new(),
+            }),
+            merges: AtomicU64::new(0),
+            version: AtomicU64::new(0),
+        })
+    }
+
+    pub fn meta(&self) -> &MetaIndex {
+        &self.meta
+    }
+
+    fn lock(&self) -> MutexGuard<'_, DeltaState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Delete `removed` and replace-or-add `upserts`, then commit so the change is searchable
+    /// as soon as this returns.
+    pub fn apply(
+        &self,
+        removed: impl IntoIterator<Item = DocKey>,
+        upserts: impl IntoIterator<Item = CoreFileMeta>,
+    ) -> Result<()> {
+        let mut state = self.lock();
+        let state = &mut *state;
+        for key in removed {
+            delete_file_meta(&mut state.writer, &self.meta.fields, key)?;
+            state.pending.remove(&key);
+            state.removed.insert(key);
+        }
+        for meta in upserts {
+            upsert_file_meta(&mut state.writer, &self.meta.fields, &meta)?;
+            state.removed.remove(&meta.key);
+            state.pending.insert(meta.key, meta);
+        }
+        state.writer.commit()?;
+        self.reader.reload()?;
+        self.version.fetch_add(1, Ordering::Release);
+        Ok(())
+    }
+
+    /// Changes waiting for the next merge (upserted plus removed keys).
+    pub fn len(&self) -> usize {
+        let state = self.lock();
+        state.pending.len() + state.removed.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Completed merges; the main index must be reloaded whenever this changes.
+    pub fn merges(&self) -> u64 {
+        self.merges.load(Ordering::Acquire)
+    }
+
+    /// Bumps on every commit to the delta, including the one that empties it after a merge.
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+
+    pub fn snapshot(&self) -> DeltaSnapshot {
+        let state = self.lock();
+        DeltaSnapshot {
+            searcher: self.reader.searcher(),
+            shadowed: Arc::new(
+                state
+                    .pending
+                    .keys()
+                    .chain(state.removed.iter())
+                    .copied()
+                    .collect(),
+            ),
+            merges: self.merges(),
+        }
+    }
+
+    /// Write every pending change into `main` with one commit, then empty the delta. Returns
+    /// how many changes were merged.
+    ///
+    /// Updates wait while the merge runs. The merge count is bumped after the main commit and
+    /// before the delta is cleared, so a reader that reloads the main index whenever
+    /// [`merges`](Self::merges) moves never sees a change in neither tier.
+    pub fn merge_into(&self, main: &MetaIndex, cfg: &WriterConfig) -> Result<usize> {
+        let mut state = self.lock();
+        let merged = state.pending.len() + state.removed.len();
+        if merged == 0 {
+            return Ok(0);
+        }
+        let mut writer = create_writer(main, cfg)?;
+        for key in &state.removed {
+            delete_file_meta(&mut writer, &main.fields, *key)?;
+        }
+        for meta in state.pending.values() {
+            upsert_file_meta(&mut writer, &main.fields, meta)?;
+        }
+        writer.commit()?;
+        drop(writer);
+        self.merges.fetch_add(1, Ordering::Release);
+
+        state.writer.delete_all_documents()?;
+        state.writer.commit()?;
+        state.pending.clear();
+        state.removed.clear();
+        self.reader.reload()?;
+        self.version.fetch_add(1, Ordering::Release);
+        Ok(merged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{create_in_ram, open_reader};
+    use core_types::FileFlags;
+    use tantivy::collector::{Count, TopDocs};
+    use tantivy::query::{Query, QueryParser};
+
+    fn file(frn: u64, name: &str) -> CoreFileMeta {
+        CoreFileMeta::new(
+            DocKey::from_parts(1, frn),
+            1,
+            None,
+            name.to_string(),
+            Some(format!("C:\\docs\\{name}")),
+            1,
+            0,
+            0,
+            FileFlags::empty(),
+        )
+    }
+
+    fn name_query(meta: &MetaIndex, text: &str) -> Box<dyn Query> {
+        QueryParser::for_index(&meta.index, vec![meta.fields.name])
+            .parse_query(text)
+            .unwrap()
+    }
+
+    fn count(searcher: &Searcher, meta: &MetaIndex, text: &str) -> usize {
+        searcher.search(&name_query(meta, text), &Count).unwrap()
+    }
+
+    #[test]
+    fn delta_docs_are_searchable_immediately() {
+        let main = create_in_ram().unwrap();
+        let delta = DeltaIndex::for_main(&main).unwrap();
+        delta.apply([], [file(7, "budget.xlsx")]).unwrap();
+
+        let snap = delta.snapshot();
+        assert_eq!(count(&snap.searcher, delta.meta(), "budget"), 1);
+        assert!(snap.shadowed.contains(&DocKey::from_parts(1, 7)));
+
+        // Upserting the same key again replaces the doc rather than adding a second one.
+        delta.apply([], [file(7, "forecast.xlsx")]).unwrap();
+        let snap = delta.snapshot();
+        assert_eq!(count(&snap.searcher, delta.meta(), "budget"), 0);
+        assert_eq!(count(&snap.searcher, delta.meta(), "forecast"), 1);
+        assert_eq!(delta.len(), 1);
+    }
+
+    #[test]
+    fn merge_moves_docs_into_main_exactly_once() {
+        let main = create_in_ram().unwrap();
+        let mut writer = create_writer(&main, &WriterConfig::default()).unwrap();
+        upsert_file_meta(&mut writer, &main.fields, &file(1, "draft.txt")).unwrap();
+        upsert_file_meta(&mut writer, &main.fields, &file(2, "old.txt")).unwrap();
+        writer.commit().unwrap();
+        drop(writer);
+
+        let delta = DeltaIndex::for_main(&main).unwrap();
+        // A rename of an indexed file, a brand-new file, and a deletion.
+        delta
+            .apply(
+                [DocKey::from_parts(1, 2)],
+                [file(1, "final.txt"), file(3, "new.txt")],
+            )
+            .unwrap();
+        delta.apply([], [file(1, "final.txt")]).unwrap();
+
+        assert_eq!(
+            delta.merge_into(&main, &WriterConfig::default()).unwrap(),
+            3
+        );
+        assert_eq!(delta.merges(), 1);
+        assert!(delta.is_empty());
+        assert_eq!(delta.snapshot().searcher.num_docs(), 0);
+
+        let searcher = open_reader(&main).unwrap().searcher();
+        assert_eq!(searcher.num_docs(), 2);
+        assert_eq!(count(&searcher, &main, "final"), 1);
+        assert_eq!(count(&searcher, &main, "new"), 1);
+        assert_eq!(count(&searcher, &main, "draft"), 0);
+        assert_eq!(count(&searcher, &main, "old"), 0);
+        let top = searcher
+            .search(&name_query(&main, "final"), &TopDocs::with_limit(5))
+            .unwrap();
+        assert_eq!(top.len(), 1);
+
+        // Nothing pending: a second merge is a no-op.
+        assert_eq!(
+            delta.merge_into(&main, &WriterConfig::default()).unwrap(),
+            0
+        );
+        assert_eq!(delta.merges(), 1);
+    }
+}
@@ -5,19 +5,29 @@
 //! a schema builder and a thin wrapper to open/create the index; the service
 //! will wire the actual writer/reader later.
 
+use std::ops::Bound;
 use std::path::Path;
 
-use anyhow::Result;
-use core_types::{DocKey, FileMeta as CoreFileMeta};
+use anyhow::{Context, Result};
+use core_types::config::{NameAnalyzer, SearchSection};
+use core_types::{
+    DocKey, FileMeta as CoreFileMeta, IndexOpenOutcome, MIB, WriterBudget, WriterProfile,
+    backup_corrupt_index_dir,
+};
+use tantivy::query::RangeQuery;
+use tantivy::tokenizer::{
+    AsciiFoldingFilter, LowerCaser, NgramTokenizer, RegexTokenizer, RemoveLongFilter,
+    SimpleTokenizer, TextAnalyzer,
+};
 use tantivy::{Index, IndexWriter, schema::document::TantivyDocument, schema::*};
 
 #[cfg(test)]
 use tantivy::{IndexSettings, ReloadPolicy};
 
 pub mod cache;
+pub mod delta;
 pub mod fst;
 pub mod state;
-pub mod tiers;
 
 /// Fields used in the metadata index.
 #[derive(Debug, Clone)]
@@ -25,38 +35,201 @@ pub struct MetaFields {
     pub doc_key: Field,
     pub volume: Field,
     pub name: Field,
+    /// Case-preserving copy of `name` for case-sensitive queries; `None` in indexes built
+    /// before it existed.
+    pub name_cased: Option<Field>,
+    /// N-grams of `name` for substring queries; only present when `search.name_ngrams` was on
+    /// when the index was built.
+    pub name_ngram: Option<Field>,
     pub path: Field,
+    /// `path` split into whole directory segments (`features.path_segments`); `None` when the
+    /// flag is off or the index predates the field.
+    pub path_segments: Option<Field>,
     pub ext: Field,
     pub size: Field,
     pub created: Field,
     pub modified: Field,
     pub flags: Field,
+    /// `DocKey` of the containing directory, for listing a directory's children; `None` in
+    /// indexes built before it existed.
+    pub parent: Option<Field>,
+}
+
+impl MetaFields {
+    /// Resolve the fields of an existing schema (which may predate newer optional fields).
+    pub fn from_schema(schema: &Schema) -> Result<Self> {
+        Ok(Self {
+            doc_key: schema.get_field("doc_key")?,
+            volume: schema.get_field("volume")?,
+            name: schema.get_field("name")?,
+            name_cased: schema.get_field("name_cased").ok(),
+            name_ngram: schema.get_field("name_ngram").ok(),
+            path: schema.get_field("path")?,
+            // Off until `MetaIndex::set_path_segments` turns it on.
+            path_segments: None,
+            ext: schema.get_field("ext")?,
+            size: schema.get_field("size")?,
+            created: schema.get_field("created")?,
+            modified: schema.get_field("modified")?,
+            flags: schema.get_field("flags")?,
+            parent: schema.get_field("parent").ok(),
+        })
+    }
+}
+
+/// Tokenizer names registered by [`register_tokenizers`]. `Lowercase` is Tantivy's built-in
+/// `default` tokenizer, so indexes built before the choice existed read as `Lowercase`.
+const NAME_EXACT_TOKENIZER: &str = "name_exact";
+const NAME_FOLDED_TOKENIZER: &str = "name_folded";
+const NAME_CASED_TOKENIZER: &str = NAME_EXACT_TOKENIZER;
+
+/// Matches the token length cap of Tantivy's `default` tokenizer.
+const MAX_TOKEN_LEN: usize = 40;
+
+/// Name of the segment-tokenized copy of `path`.
+pub const PATH_SEGMENTS_FIELD: &str = "path_segments";
+/// One lowercased token per `\`- or `/`-separated path segment, spaces and dots included.
+const PATH_SEGMENT_TOKENIZER: &str = "path_segment";
+
+fn path_segment_analyzer() -> TextAnalyzer {
+    let segments = RegexTokenizer::new(r"[^\\/]+").expect("valid path segment pattern");
+    TextAnalyzer::builder(segments)
+        .filter(RemoveLongFilter::limit(255))
+        .filter(LowerCaser)
+        .build()
+}
+
+fn name_tokenizer(analyzer: NameAnalyzer) -> &'static str {
+    match analyzer {
+        NameAnalyzer::Exact => NAME_EXACT_TOKENIZER,
+        NameAnalyzer::Lowercase => "default",
+        NameAnalyzer::AsciiFolded => NAME_FOLDED_TOKENIZER,
+    }
+}
+
+/// N-gram tokenizers are named `name_ngram[_folded]_{min}_{max}` so a reopened index can
+/// rebuild the exact analyzer its schema refers to.
+const NAME_NGRAM_PREFIX: &str = "name_ngram";
+
+fn ngram_tokenizer_name(folded: bool, min: usize, max: usize) -> String {
+    let fold = if folded { "_folded" } else { "" };
+    format!("{NAME_NGRAM_PREFIX}{fold}_{min}_{max}")
+}
+
+/// Inverse of [`ngram_tokenizer_name`]: `(folded, min, max)`.
+fn parse_ngram_tokenizer(name: &str) -> Option<(bool, usize, usize)> {
+    let rest = name.strip_prefix(NAME_NGRAM_PREFIX)?;
+    let (folded, rest) = match rest.strip_prefix("_folded") {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+    };
+    let (min, max) = rest.strip_prefix('_')?.split_once('_')?;
+    Some((folded, min.parse().ok()?, max.parse().ok()?))
+}
+
+fn ngram_analyzer(folded: bool, min: usize, max: usize) -> tantivy::Result<TextAnalyzer> {
+    let grams = NgramTokenizer::all_ngrams(min, max)?;
+    Ok(if folded {
+        TextAnalyzer::builder(grams)
+            .filter(LowerCaser)
+            .filter(AsciiFoldingFilter)
+            .build()
+    } else {
+        TextAnalyzer::builder(grams).filter(LowerCaser).build()
+    })
+}
+
+/// Register the custom name tokenizers; required on every `Index` handle before use.
+pub fn register_tokenizers(index: &Index) {
+    let tokenizers = index.tokenizers();
+    tokenizers.register(
+        NAME_EXACT_TOKENIZER,
+        TextAnalyzer::builder(SimpleTokenizer::default())
+            .filter(RemoveLongFilter::limit(MAX_TOKEN_LEN))
+            .build(),
+    );
+    tokenizers.register(
+        NAME_FOLDED_TOKENIZER,
+        TextAnalyzer::builder(SimpleTokenizer::default())
+            .filter(RemoveLongFilter::limit(MAX_TOKEN_LEN))
+            .filter(LowerCaser)
+            .filter(AsciiFoldingFilter)
+            .build(),
+    );
+    tokenizers.register(PATH_SEGMENT_TOKENIZER, path_segment_analyzer());
+    for (_, entry) in index.schema().fields() {
+        let FieldType::Str(opts) = entry.field_type() else {
+            continue;
+        };
+        let Some(tokenizer) = opts.get_indexing_options().map(|o| o.tokenizer()) else {
+            continue;
+        };
+        if let Some((folded, min, max)) = parse_ngram_tokenizer(tokenizer)
+            && let Ok(analyzer) = ngram_analyzer(folded, min, max)
+        {
+            tokenizers.register(tokenizer, analyzer);
+        }
+    }
+}
+
+fn text_field(tokenizer: &str) -> TextOptions {
+    TextOptions::default().set_indexing_options(
+        TextFieldIndexing::default()
+            .set_tokenizer(tokenizer)
+            .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+    )
 }
 
 /// Build the Tantivy schema and return both `Schema` and typed field handles.
 pub fn build_schema() -> (Schema, MetaFields) {
+    build_schema_with(&SearchSection::default())
+}
+
+/// Build the schema for the `[search]` options that shape it (`name_analyzer`, `name_ngrams`).
+pub fn build_schema_with(search: &SearchSection) -> (Schema, MetaFields) {
+    let analyzer = search.name_analyzer;
     let mut builder = Schema::builder();
 
     let doc_key = builder.add_u64_field("doc_key", FAST | STORED);
     let volume = builder.add_u64_field("volume", FAST | STORED);
-    let name = builder.add_text_field("name", TEXT | STORED);
+    let name = builder.add_text_field("name", text_field(name_tokenizer(analyzer)) | STORED);
     let path = builder.add_text_field("path", TEXT | STORED);
     let ext = builder.add_text_field("ext", STRING | FAST);
     let size = builder.add_u64_field("size", FAST | STORED);
     let created = builder.add_i64_field("created", FAST | STORED);
     let modified = builder.add_i64_field("modified", FAST | STORED);
     let flags = builder.add_u64_field("flags", FAST | STORED);
+    // Stored too, so tier compaction (which re-adds stored docs) keeps it.
+    let parent = builder.add_u64_field("parent", INDEXED | STORED);
+    let name_cased = builder.add_text_field("name_cased", text_field(NAME_CASED_TOKENIZER));
+    let name_ngram = search.name_ngrams.then(|| {
+        let folded = analyzer == NameAnalyzer::AsciiFolded;
+        let tokenizer = ngram_tokenizer_name(folded, search.ngram_min, search.ngram_max);
+        let indexing = TextFieldIndexing::default()
+            .set_tokenizer(&tokenizer)
+            .set_index_option(IndexRecordOption::WithFreqs);
+        builder.add_text_field(
+            "name_ngram",
+            TextOptions::default().set_indexing_options(indexing),
+        )
+    });
+    let path_segments =
+        builder.add_text_field(PATH_SEGMENTS_FIELD, text_field(PATH_SEGMENT_TOKENIZER));
 
     let fields = MetaFields {
         doc_key,
         volume,
         name,
+        name_cased: Some(name_cased),
+        name_ngram,
         path,
+        path_segments: Some(path_segments),
         ext,
         size,
         created,
         modified,
         flags,
+        parent: Some(parent),
     };
 
     (builder.build(), fields)
@@ -74,6 +247,7 @@ pub struct MetaDoc {
     pub created: i64,
     pub modified: i64,
     pub flags: u64,
+    pub parent: Option<DocKey>,
 }
 
 impl From<&CoreFileMeta> for MetaDoc {
@@ -88,6 +262,7 @@ impl From<&CoreFileMeta> for MetaDoc {
             created: f.created,
             modified: f.modified,
             flags: f.flags.bits() as u64,
+            parent: f.parent,
         }
     }
 }
@@ -115,6 +290,30 @@ pub fn add_file_meta_batch(
     add_batch(writer, fields, metas.into_iter().map(|m| MetaDoc::from(&m)))
 }
 
+/// Delete the document stored under `key`, if any.
+///
+/// `doc_key` is a fast field rather than an indexed term, so the delete uses a single-value
+/// range query.
+pub fn delete_file_meta(writer: &mut IndexWriter, fields: &MetaFields, key: DocKey) -> Result<()> {
+    let term = Term::from_field_u64(fields.doc_key, key.0);
+    writer.delete_query(Box::new(RangeQuery::new(
+        Bound::Included(term.clone()),
+        Bound::Included(term),
+    )))?;
+    Ok(())
+}
+
+/// Replace any existing document with the same key, then add `meta`.
+pub fn upsert_file_meta(
+    writer: &mut IndexWriter,
+    fields: &MetaFields,
+    meta: &CoreFileMeta,
+) -> Result<()> {
+    delete_file_meta(writer, fields, meta.key)?;
+    writer.add_document(to_document(&MetaDoc::from(meta), fields))?;
+    Ok(())
+}
+
 /// Convenience handle bundling an index with its field set.
 #[derive(Debug)]
 pub struct MetaIndex {
@@ -122,19 +321,108 @@ pub struct MetaIndex {
     pub fields: MetaFields,
 }
 
+impl MetaIndex {
+    /// Wrap an index whose tokenizers are not yet registered.
+    pub fn from_index(index: Index) -> Result<Self> {
+        register_tokenizers(&index);
+        let fields = MetaFields::from_schema(&index.schema())?;
+        Ok(Self { index, fields })
+    }
+
+    /// The analyzer the `name` field was built with; `None` if it is not one we know.
+    pub fn name_analyzer(&self) -> Option<NameAnalyzer> {
+        let entry = self
+            .index
+            .schema()
+            .get_field_entry(self.fields.name)
+            .clone();
+        let FieldType::Str(opts) = entry.field_type() else {
+            return None;
+        };
+        match opts.get_indexing_options()?.tokenizer() {
+            NAME_EXACT_TOKENIZER => Some(NameAnalyzer::Exact),
+            "default" => Some(NameAnalyzer::Lowercase),
+            NAME_FOLDED_TOKENIZER => Some(NameAnalyzer::AsciiFolded),
+            _ => None,
+        }
+    }
+
+    /// Turn segment indexing of `path` on or off for docs written and queries built with
+    /// `self.fields`. Stays off for indexes created before `path_segments` existed.
+    pub fn set_path_segments(&mut self, enabled: bool) {
+        self.fields.path_segments = enabled
+            .then(|| self.index.schema().get_field(PATH_SEGMENTS_FIELD).ok())
+            .flatten();
+    }
+}
+
 /// Open an existing index if it exists; otherwise create a fresh one.
 ///
 /// This keeps the caller’s path semantics simple and mirror Tantivy’s typical
 /// “open or create” ergonomics without forcing the caller to probe the
 /// directory manually.
 pub fn open_or_create_index(path: &Path) -> Result<MetaIndex> {
-    let (schema, fields) = build_schema();
+    open_or_create_index_with(path, &SearchSection::default())
+}
+
+/// Like [`open_or_create_index`], creating with the `[search]` schema options. An existing
+/// index keeps the schema it was built with; compare [`MetaIndex::name_analyzer`] and
+/// `fields.name_ngram` to detect that a rebuild is needed.
+pub fn open_or_create_index_with(path: &Path, search: &SearchSection) -> Result<MetaIndex> {
     let index = if path.join("meta.json").exists() {
         Index::open_in_dir(path)?
     } else {
-        Index::create_in_dir(path, schema)?
+        Index::create_in_dir(path, build_schema_with(search).0)?
     };
-    Ok(MetaIndex { index, fields })
+    MetaIndex::from_index(index)
+}
+
+/// Create an in-memory index for tests and benchmarks.
+pub fn create_in_ram() -> Result<MetaIndex> {
+    create_in_ram_with(&SearchSection::default())
+}
+
+/// [`create_in_ram`] with the `[search]` schema options.
+pub fn create_in_ram_with(search: &SearchSection) -> Result<MetaIndex> {
+    MetaIndex::from_index(Index::create_in_ram(build_schema_with(search).0))
+}
+
+/// Like [`open_or_create_index_with`], but treats an index that fails to open as corrupt.
+/// With `recreate_corrupt` the directory is backed up and replaced by an empty index;
+/// otherwise the open error is returned and nothing on disk changes.
+pub fn open_or_recreate_index_with(
+    path: &Path,
+    search: &SearchSection,
+    recreate_corrupt: bool,
+) -> Result<(MetaIndex, IndexOpenOutcome)> {
+    if !path.join("meta.json").exists() {
+        let meta = open_or_create_index_with(path, search)?;
+        return Ok((meta, IndexOpenOutcome::Created));
+    }
+    let err = match Index::open_in_dir(path)
+        .map_err(anyhow::Error::from)
+        .and_then(MetaIndex::from_index)
+    {
+        Ok(meta) => return Ok((meta, IndexOpenOutcome::Opened)),
+        Err(err) if !recreate_corrupt => {
+            return Err(err.context(format!(
+                "meta index at {} is unreadable; set paths.recreate_corrupt_indexes to rebuild it",
+                path.display()
+            )));
+        }
+        Err(err) => err,
+    };
+    let backup = backup_corrupt_index_dir(path)
+        .with_context(|| format!("back up corrupt meta index at {}", path.display()))?;
+    tracing::warn!(
+        error = %err,
+        backup = %backup.display(),
+        "meta index at {} was unreadable; recreated it empty",
+        path.display()
+    );
+    std::fs::create_dir_all(path)?;
+    let meta = open_or_create_index_with(path, search)?;
+    Ok((meta, IndexOpenOutcome::Recreated { backup }))
 }
 
 /// Writer configuration used during initial builds and batch updates.
@@ -155,6 +443,45 @@ impl Default for WriterConfig {
     }
 }
 
+impl WriterConfig {
+    /// Size an initial-build writer from this machine's free memory and cores.
+    pub fn auto() -> Self {
+        Self::auto_for(WriterProfile::InitialBuild)
+    }
+
+    /// Size a writer for `profile` from this machine's free memory and cores.
+    pub fn auto_for(profile: WriterProfile) -> Self {
+        let mut sys = sysinfo::System::new();
+        sys.refresh_memory();
+        let cpus = std::thread::available_parallelism().map_or(1, |n| n.get());
+        Self::for_resources(profile, sys.available_memory(), cpus)
+    }
+
+    /// Sizing behind [`WriterConfig::auto_for`]: the metadata index's [`WriterBudget`] for
+    /// `profile` applied to `available_bytes` and `cpus`.
+    pub fn for_resources(profile: WriterProfile, available_bytes: u64, cpus: usize) -> Self {
+        let budget = match profile {
+            WriterProfile::InitialBuild => WriterBudget {
+                divisor: 4,
+                floor: 64 * MIB,
+                ceiling: 1024 * MIB,
+                max_threads: 8,
+            },
+            WriterProfile::SteadyState => WriterBudget {
+                divisor: 16,
+                floor: 32 * MIB,
+                ceiling: 128 * MIB,
+                max_threads: 2,
+            },
+        };
+        let (heap_size_bytes, num_threads) = budget.size(available_bytes, cpus);
+        Self {
+            heap_size_bytes,
+            num_threads,
+        }
+    }
+}
+
 /// Create an `IndexWriter` with the provided configuration.
 pub fn create_writer(meta: &MetaIndex, cfg: &WriterConfig) -> Result<IndexWriter> {
     meta.index
@@ -174,8 +501,17 @@ pub fn to_document(doc: &MetaDoc, fields: &MetaFields) -> TantivyDocument {
     d.add_u64(fields.doc_key, doc.key.0);
     d.add_u64(fields.volume, doc.volume as u64);
     d.add_text(fields.name, &doc.name);
+    if let Some(name_cased) = fields.name_cased {
+        d.add_text(name_cased, &doc.name);
+    }
+    if let Some(name_ngram) = fields.name_ngram {
+        d.add_text(name_ngram, &doc.name);
+    }
     if let Some(path) = &doc.path {
         d.add_text(fields.path, path);
+        if let Some(segments) = fields.path_segments {
+            d.add_text(segments, path);
+        }
     }
     if let Some(ext) = &doc.ext {
         d.add_text(fields.ext, ext);
@@ -184,9 +520,61 @@ pub fn to_document(doc: &MetaDoc, fields: &MetaFields) -> TantivyDocument {
     d.add_i64(fields.created, doc.created);
     d.add_i64(fields.modified, doc.modified);
     d.add_u64(fields.flags, doc.flags);
+    if let (Some(field), Some(parent)) = (fields.parent, doc.parent) {
+        d.add_u64(field, parent.0);
+    }
     d
 }
 
+/// Inverse of [`to_document`] for a stored doc; `None` when a stored field it needs is missing.
+pub fn doc_to_meta(doc: &TantivyDocument, fields: &MetaFields) -> Option<MetaDoc> {
+    let mut key = None;
+    let mut name = None;
+    let mut path = None;
+    let mut ext = None;
+    let mut size = None;
+    let mut created = None;
+    let mut modified = None;
+    let mut flags = None;
+    let mut volume = None;
+    let mut parent = None;
+
+    for (field, value) in doc.iter_fields_and_values() {
+        match field {
+            f if f == fields.doc_key => key = value.as_u64().map(DocKey),
+            f if f == fields.volume => volume = value.as_u64().map(|v| v as u16),
+            f if f == fields.name => name = value.as_str().map(|s| s.to_string()),
+            f if f == fields.path => path = value.as_str().map(|s| s.to_string()),
+            f if f == fields.ext => ext = value.as_str().map(|s| s.to_string()),
+            f if f == fields.size => size = value.as_u64(),
+            f if f == fields.created => created = value.as_i64(),
+            f if f == fields.modified => modified = value.as_i64(),
+            f if f == fields.flags => flags = value.as_u64(),
+            f if Some(f) == fields.parent => parent = value.as_u64().map(DocKey),
+            _ => {}
+        }
+    }
+
+    if let (Some(k), Some(v), Some(n), Some(s), Some(c), Some(m), Some(f)) =
+        (key, volume, name, size, created, modified, flags)
+    {
+        Some(MetaDoc {
+            key: k,
+            volume: v,
+            name: n,
+            path,
+            ext,
+            size: s,
+            created: c,
+            modified: m,
+            flags: f,
+            parent,
+        })
+    } else {
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -205,6 +593,7 @@ mod tests {
             created: 100,
             modified: 200,
             flags: 0b1010,
+            parent: Some(DocKey::from_parts(9, 5)),
         };
 
         let tdoc = to_document(&doc, &fields);
@@ -215,6 +604,10 @@ mod tests {
         assert_eq!(get(fields.created).as_i64().unwrap(), doc.created);
         assert_eq!(get(fields.modified).as_i64().unwrap(), doc.modified);
         assert_eq!(get(fields.flags).as_u64().unwrap(), doc.flags);
+        assert_eq!(
+            get(fields.parent.unwrap()).as_u64(),
+            doc.parent.map(|p| p.0)
+        );
     }
 
     #[test]
@@ -222,6 +615,7 @@ mod tests {
         let dir = RamDirectory::create();
         let (schema, fields) = build_schema();
         let index = Index::create(dir, schema, IndexSettings::default())?;
+        register_tokenizers(&index);
         let mut writer = index.writer_with_num_threads(1, 50_000_000)?;
 
         let docs = vec![
@@ -235,6 +629,7 @@ mod tests {
                 created: 1_700_000_000,
                 modified: 1_700_000_100,
                 flags: 0,
+                parent: None,
             },
             MetaDoc {
                 key: DocKey::from_parts(2, 20),
@@ -246,6 +641,7 @@ mod tests {
                 created: 1_700_000_200,
                 modified: 1_700_000_300,
                 flags: 0,
+                parent: None,
             },
         ];
 
@@ -267,4 +663,241 @@ mod tests {
         assert!(doc_key == docs[0].key.0 || doc_key == docs[1].key.0);
         Ok(())
     }
+
+    fn analyzer(name_analyzer: NameAnalyzer) -> SearchSection {
+        SearchSection {
+            name_analyzer,
+            ..SearchSection::default()
+        }
+    }
+
+    fn name_hits(analyzer: NameAnalyzer, names: &[&str], query: &str) -> Vec<String> {
+        hits_in(&self::analyzer(analyzer), names, |meta| {
+            let parser =
+                tantivy::query::QueryParser::for_index(&meta.index, vec![meta.fields.name]);
+            parser.parse_query(query).unwrap()
+        })
+    }
+
+    fn term_hits(search: &SearchSection, names: &[&str], field: Field, text: &str) -> Vec<String> {
+        hits_in(search, names, |_| {
+            Box::new(tantivy::query::TermQuery::new(
+                Term::from_field_text(field, text),
+                IndexRecordOption::Basic,
+            ))
+        })
+    }
+
+    fn hits_in(
+        search: &SearchSection,
+        names: &[&str],
+        query: impl Fn(&MetaIndex) -> Box<dyn tantivy::query::Query>,
+    ) -> Vec<String> {
+        let dir = tempfile::tempdir().unwrap();
+        let meta = open_or_create_index_with(dir.path(), search).unwrap();
+        let mut writer = meta.index.writer_with_num_threads(1, 15_000_000).unwrap();
+        for (i, name) in names.iter().enumerate() {
+            let doc = MetaDoc {
+                key: DocKey::from_parts(1, i as u64 + 1),
+                volume: 1,
+                name: name.to_string(),
+                path: None,
+                ext: None,
+                size: 0,
+                created: 0,
+                modified: 0,
+                flags: 0,
+                parent: None,
+            };
+            writer
+                .add_document(to_document(&doc, &meta.fields))
+                .unwrap();
+        }
+        writer.commit().unwrap();
+
+        let searcher = open_reader(&meta).unwrap().searcher();
+        let top = searcher
+            .search(
+                query(&meta).as_ref(),
+                &tantivy::collector::TopDocs::with_limit(10),
+            )
+            .unwrap();
+        let mut out: Vec<String> = top
+            .into_iter()
+            .map(|(_, addr)| {
+                let doc: TantivyDocument = searcher.doc(addr).unwrap();
+                let name = doc.get_first(meta.fields.name).unwrap();
+                name.as_str().unwrap().to_string()
+            })
+            .collect();
+        out.sort();
+        out
+    }
+
+    #[test]
+    fn ascii_folding_matches_accented_and_plain_names() {
+        let names = ["resumé.docx", "Resume.pdf", "report.txt"];
+        let folded = NameAnalyzer::AsciiFolded;
+        assert_eq!(
+            name_hits(folded, &names, "resume"),
+            ["Resume.pdf", "resumé.docx"]
+        );
+        assert_eq!(
+            name_hits(folded, &names, "RÉSUMÉ"),
+            ["Resume.pdf", "resumé.docx"]
+        );
+
+        assert_eq!(
+            name_hits(NameAnalyzer::Lowercase, &names, "resume"),
+            ["Resume.pdf"]
+        );
+    }
+
+    #[test]
+    fn exact_analyzer_is_case_sensitive() {
+        let names = ["Resume.pdf", "resume.txt"];
+        assert_eq!(
+            name_hits(NameAnalyzer::Exact, &names, "Resume"),
+            ["Resume.pdf"]
+        );
+    }
+
+    #[test]
+    fn reopened_index_reports_its_build_analyzer() {
+        let dir = tempfile::tempdir().unwrap();
+        let built =
+            open_or_create_index_with(dir.path(), &analyzer(NameAnalyzer::AsciiFolded)).unwrap();
+        assert_eq!(built.name_analyzer(), Some(NameAnalyzer::AsciiFolded));
+        drop(built);
+
+        // The stored schema wins over the requested analyzer.
+        let reopened =
+            open_or_create_index_with(dir.path(), &analyzer(NameAnalyzer::Exact)).unwrap();
+        assert_eq!(reopened.name_analyzer(), Some(NameAnalyzer::AsciiFolded));
+        assert!(reopened.fields.name_cased.is_some());
+    }
+
+    #[test]
+    fn ngram_field_matches_infix_substrings() {
+        let search = SearchSection {
+            name_ngrams: true,
+            ..SearchSection::default()
+        };
+        let (_, fields) = build_schema_with(&search);
+        let names = ["report.txt", "Passport.pdf", "notes.md"];
+        assert_eq!(
+            term_hits(&search, &names, fields.name_ngram.unwrap(), "port"),
+            ["Passport.pdf", "report.txt"]
+        );
+        assert!(term_hits(&search, &names, fields.name, "port").is_empty());
+    }
+
+    #[test]
+    fn ngram_tokenizer_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let search = SearchSection {
+            name_ngrams: true,
+            ngram_min: 2,
+            ngram_max: 4,
+            ..analyzer(NameAnalyzer::AsciiFolded)
+        };
+        drop(open_or_create_index_with(dir.path(), &search).unwrap());
+
+        let reopened = open_or_create_index_with(dir.path(), &SearchSection::default()).unwrap();
+        let field = reopened.fields.name_ngram.expect("ngram field kept");
+        let tokenizer = match reopened.index.schema().get_field_entry(field).field_type() {
+            FieldType::Str(opts) => opts.get_indexing_options().unwrap().tokenizer().to_string(),
+            _ => unreachable!(),
+        };
+        assert_eq!(parse_ngram_tokenizer(&tokenizer), Some((true, 2, 4)));
+        assert!(reopened.index.tokenizers().get(&tokenizer).is_some());
+    }
+
+    #[test]
+    fn truncated_meta_json_is_refused_or_recreated() {
+        let root = tempfile::tempdir().unwrap();
+        let path = root.path().join("meta");
+        std::fs::create_dir_all(&path).unwrap();
+        let search = SearchSection::default();
+        let (meta, outcome) = open_or_recreate_index_with(&path, &search, false).unwrap();
+        assert_eq!(outcome, IndexOpenOutcome::Created);
+        let doc = MetaDoc {
+            key: DocKey::from_parts(1, 1),
+            volume: 1,
+            name: "kept.txt".into(),
+            path: None,
+            ext: None,
+            size: 0,
+            created: 0,
+            modified: 0,
+            flags: 0,
+            parent: None,
+        };
+        let mut writer = meta.index.writer_with_num_threads(1, 15_000_000).unwrap();
+        writer
+            .add_document(to_document(&doc, &meta.fields))
+            .unwrap();
+        writer.commit().unwrap();
+        drop((writer, meta));
+
+        let meta_json = path.join("meta.json");
+        let full = std::fs::read(&meta_json).unwrap();
+        std::fs::write(&meta_json, &full[..full.len() / 2]).unwrap();
+
+        // Safe default: report the corruption and leave the directory alone.
+        assert!(open_or_recreate_index_with(&path, &search, false).is_err());
+        assert_eq!(std::fs::read(&meta_json).unwrap().len(), full.len() / 2);
+
+        let (meta, outcome) = open_or_recreate_index_with(&path, &search, true).unwrap();
+        let IndexOpenOutcome::Recreated { backup } = outcome else {
+            panic!("expected recreate, got {outcome:?}");
+        };
+        assert!(backup.join("meta.json").exists());
+        let reader = meta.index.reader().unwrap();
+        assert_eq!(reader.searcher().num_docs(), 0);
+
+        let (_, outcome) = open_or_recreate_index_with(&path, &search, true).unwrap();
+        assert_eq!(outcome, IndexOpenOutcome::Opened);
+    }
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    #[test]
+    fn auto_sizing_respects_clamps() {
+        let tiny = WriterConfig::for_resources(WriterProfile::InitialBuild, 128 * 1024 * 1024, 1);
+        assert_eq!((tiny.heap_size_bytes, tiny.num_threads), (64 * MIB, 1));
+
+        let mid = WriterConfig::for_resources(WriterProfile::InitialBuild, 2 * GIB, 4);
+        assert_eq!((mid.heap_size_bytes, mid.num_threads), (512 * MIB, 4));
+
+        let big = WriterConfig::for_resources(WriterProfile::InitialBuild, 128 * GIB, 64);
+        assert_eq!((big.heap_size_bytes, big.num_threads), (1024 * MIB, 8));
+
+        let steady = WriterConfig::for_resources(WriterProfile::SteadyState, 128 * GIB, 64);
+        assert_eq!((steady.heap_size_bytes, steady.num_threads), (128 * MIB, 2));
+
+        // The live probe goes through the same budgets.
+        let live = WriterConfig::auto();
+        assert!(live.heap_size_bytes >= 64 * MIB && live.num_threads >= 1);
+    }
+
+    #[test]
+    fn legacy_schema_without_cased_name_opens() {
+        let mut builder = Schema::builder();
+        builder.add_u64_field("doc_key", FAST | STORED);
+        builder.add_u64_field("volume", FAST | STORED);
+        builder.add_text_field("name", TEXT | STORED);
+        builder.add_text_field("path", TEXT | STORED);
+        builder.add_text_field("ext", STRING | FAST);
+        builder.add_u64_field("size", FAST | STORED);
+        builder.add_i64_field("created", FAST | STORED);
+        builder.add_i64_field("modified", FAST | STORED);
+        builder.add_u64_field("flags", FAST | STORED);
+        let index = Index::create_in_ram(builder.build());
+
+        let meta = MetaIndex::from_index(index).unwrap();
+        assert!(meta.fields.name_cased.is_none());
+        assert!(meta.fields.parent.is_none());
+        assert_eq!(meta.name_analyzer(), Some(NameAnalyzer::Lowercase));
+    }
 }
@@ -6,7 +6,7 @@
 //! testable scaffold that mirrors the implementation plan without yet wiring
 //! Win32 calls.
 
-use core_types::{DocKey, FileMeta, VolumeId};
+use core_types::{DocKey, FileFlags, FileMeta, VolumeId};
 use thiserror::Error;
 
 pub type Usn = u64;
@@ -56,6 +56,38 @@ pub struct JournalCursor {
     pub journal_id: u64,
 }
 
+/// Journal bounds as reported by `FSCTL_QUERY_USN_JOURNAL`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JournalInfo {
+    pub journal_id: u64,
+    /// Oldest USN still present; records below it have been purged.
+    pub first_usn: Usn,
+    /// USN the next record will be written at.
+    pub next_usn: Usn,
+}
+
+/// Validate a saved cursor against the live journal and decide where tailing resumes.
+///
+/// A cursor with `journal_id == 0` has never seen this journal and starts at its head. A
+/// different journal id (journal deleted/recreated) or a `last_usn` the journal has already
+/// purged means events were lost, reported as [`NtfsError::JournalWrapped`].
+pub fn resume_cursor(
+    volume: VolumeId,
+    cursor: JournalCursor,
+    journal: JournalInfo,
+) -> Result<JournalCursor, NtfsError> {
+    if cursor.journal_id == 0 {
+        return Ok(JournalCursor {
+            last_usn: journal.next_usn,
+            journal_id: journal.journal_id,
+        });
+    }
+    if cursor.journal_id != journal.journal_id || cursor.last_usn < journal.first_usn {
+        return Err(NtfsError::JournalWrapped { volume });
+    }
+    Ok(cursor)
+}
+
 /// Errors that can surface while interacting with NTFS / USN APIs.
 #[derive(Debug, Error)]
 pub enum NtfsError {
@@ -65,6 +97,13 @@ pub enum NtfsError {
     Journal(String),
     #[error("usn gap detected")]
     GapDetected,
+    #[error("usn journal on volume {volume} wrapped or was reset; full rescan required")]
+    JournalWrapped { volume: VolumeId },
+    /// The volume has no active journal (never created, disabled, or being deleted). A rescan
+    /// cannot help until one exists; a journal created later has a new id and reports
+    /// [`NtfsError::JournalWrapped`] then.
+    #[error("usn journal on volume {volume} is not active")]
+    JournalInactive { volume: VolumeId },
     #[error("mft enumeration failed: {0}")]
     Mft(String),
     #[error("operation not supported on this platform")]
@@ -90,6 +129,27 @@ pub trait NtfsWatcher {
 }
 
 /// Discover NTFS volumes available on the machine.
+/// Map Win32 `FILE_ATTRIBUTE_*` bits onto [`FileFlags`].
+pub fn flags_from_attributes(attrs: u32) -> FileFlags {
+    const MAPPING: &[(u32, FileFlags)] = &[
+        (0x0000_0010, FileFlags::IS_DIR),    // FILE_ATTRIBUTE_DIRECTORY
+        (0x0000_0002, FileFlags::HIDDEN),    // FILE_ATTRIBUTE_HIDDEN
+        (0x0000_0004, FileFlags::SYSTEM),    // FILE_ATTRIBUTE_SYSTEM
+        (0x0000_0020, FileFlags::ARCHIVE),   // FILE_ATTRIBUTE_ARCHIVE
+        (0x0000_0400, FileFlags::REPARSE),   // FILE_ATTRIBUTE_REPARSE_POINT
+        (0x0000_0100, FileFlags::TEMPORARY), // FILE_ATTRIBUTE_TEMPORARY
+        // FILE_ATTRIBUTE_OFFLINE and the cloud-file recall attributes all mean "reading
+        // the data may pull it from remote storage".
+        (0x0000_1000, FileFlags::OFFLINE),
+        (0x0004_0000, FileFlags::OFFLINE), // FILE_ATTRIBUTE_RECALL_ON_OPEN
+        (0x0040_0000, FileFlags::OFFLINE), // FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS
+    ];
+    MAPPING
+        .iter()
+        .filter(|(bit, _)| attrs & bit != 0)
+        .fold(FileFlags::empty(), |acc, (_, flag)| acc | *flag)
+}
+
 #[cfg(windows)]
 pub fn discover_volumes() -> Result<Vec<VolumeInfo>, NtfsError> {
     use std::collections::HashMap;
@@ -227,7 +287,6 @@ pub fn open_volume_handle(
 /// On Windows this uses usn-journal-rs to iterate the MFT and resolve paths.
 #[cfg(windows)]
 pub fn enumerate_mft(volume: &VolumeInfo) -> Result<Vec<FileMeta>, NtfsError> {
-    use core_types::FileFlags;
     use std::path::Path;
     use usn_journal_rs::mft::Mft;
     use usn_journal_rs::path::PathResolver;
@@ -249,7 +308,7 @@ pub fn enumerate_mft(volume: &VolumeInfo) -> Result<Vec<FileMeta>, NtfsError> {
         let entry = entry.map_err(|e| NtfsError::Mft(format!("mft read: {e}")))?;
         let frn = entry.fid;
         let parent_frn = entry.parent_fid;
-        let is_dir = entry.is_dir();
+        let flags = flags_from_attributes(entry.file_attributes);
 
         let path = resolver
             .resolve_path(&entry)
@@ -269,12 +328,6 @@ pub fn enumerate_mft(volume: &VolumeInfo) -> Result<Vec<FileMeta>, NtfsError> {
 
         let key = DocKey::from_parts(volume.id, frn);
         let parent = Some(DocKey::from_parts(volume.id, parent_frn));
-        let flags = if is_dir {
-            FileFlags::IS_DIR
-        } else {
-            FileFlags::empty()
-        };
-
         out.push(FileMeta::new(
             key, volume.id, parent, name, path, size, 0, 0, flags,
         ));
@@ -288,13 +341,73 @@ pub fn enumerate_mft(_volume: &VolumeInfo) -> Result<Vec<FileMeta>, NtfsError> {
     Err(NtfsError::NotSupported)
 }
 
+/// Query the live journal bounds for a volume (Windows only).
+#[cfg(windows)]
+pub fn query_journal(volume: &VolumeInfo) -> Result<JournalInfo, NtfsError> {
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::{
+        ERROR_JOURNAL_DELETE_IN_PROGRESS, ERROR_JOURNAL_NOT_ACTIVE, HANDLE,
+    };
+    use windows::Win32::System::IO::DeviceIoControl;
+    use windows::Win32::System::Ioctl::{FSCTL_QUERY_USN_JOURNAL, USN_JOURNAL_DATA_V0};
+
+    let handle = open_volume_handle(volume)?;
+    let mut data = USN_JOURNAL_DATA_V0::default();
+    let mut returned = 0u32;
+    // SAFETY: the handle is open for the duration of the call and `data` is sized correctly.
+    let res = unsafe {
+        DeviceIoControl(
+            HANDLE(handle.as_raw_handle() as isize),
+            FSCTL_QUERY_USN_JOURNAL,
+            None,
+            0,
+            Some(&mut data as *mut _ as *mut _),
+            std::mem::size_of::<USN_JOURNAL_DATA_V0>() as u32,
+            Some(&mut returned),
+            None,
+        )
+    };
+    if let Err(e) = res {
+        let code = e.code();
+        if code == ERROR_JOURNAL_NOT_ACTIVE.to_hresult()
+            || code == ERROR_JOURNAL_DELETE_IN_PROGRESS.to_hresult()
+        {
+            return Err(NtfsError::JournalInactive { volume: volume.id });
+        }
+        return Err(NtfsError::Journal(format!(
+            "FSCTL_QUERY_USN_JOURNAL failed for {}: {e}",
+            volume.guid_path
+        )));
+    }
+
+    Ok(JournalInfo {
+        journal_id: data.UsnJournalID,
+        first_usn: data.FirstUsn.max(0) as Usn,
+        next_usn: data.NextUsn.max(0) as Usn,
+    })
+}
+
 /// Tail the USN journal for a volume and emit file events from the given cursor.
+///
+/// Returns [`NtfsError::JournalWrapped`] when the cursor can no longer be resumed and
+/// [`NtfsError::JournalInactive`] when the volume has no journal to tail.
+#[cfg(windows)]
+pub fn tail_usn(
+    volume: &VolumeInfo,
+    cursor: JournalCursor,
+) -> Result<(Vec<FileEvent>, JournalCursor), NtfsError> {
+    let journal = query_journal(volume)?;
+    let cursor = resume_cursor(volume.id, cursor, journal)?;
+    // TODO: read records from `cursor.last_usn` and translate them into events.
+    Ok((Vec::new(), cursor))
+}
+
+#[cfg(not(windows))]
 pub fn tail_usn(
     _volume: &VolumeInfo,
-    _cursor: JournalCursor,
+    cursor: JournalCursor,
 ) -> Result<(Vec<FileEvent>, JournalCursor), NtfsError> {
-    // TODO: connect to USN journal, read deltas, and return next cursor.
-    Ok((Vec::new(), _cursor))
+    Ok((Vec::new(), cursor))
 }
 
 /// Simple in-memory watcher useful for tests and higher-level components.
@@ -331,7 +444,6 @@ impl NtfsWatcher for InMemoryWatcher {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use core_types::FileFlags;
 
     #[test]
     fn doc_key_round_trip() {
@@ -341,6 +453,22 @@ mod tests {
         assert_eq!(frn, 1_234_567_890);
     }
 
+    #[test]
+    fn attributes_map_to_flags() {
+        assert_eq!(flags_from_attributes(0x80), FileFlags::empty()); // NORMAL
+        assert_eq!(flags_from_attributes(0x10), FileFlags::IS_DIR);
+        assert_eq!(
+            flags_from_attributes(0x10 | 0x400),
+            FileFlags::IS_DIR | FileFlags::REPARSE
+        );
+        // OneDrive online-only placeholder: reparse point that recalls on data access.
+        assert_eq!(
+            flags_from_attributes(0x400 | 0x40_0000),
+            FileFlags::REPARSE | FileFlags::OFFLINE
+        );
+        assert_eq!(flags_from_attributes(0x1000), FileFlags::OFFLINE);
+    }
+
     #[test]
     fn reader_config_defaults_are_sane() {
         let cfg = ReaderConfig::default();
@@ -387,4 +515,55 @@ mod tests {
         assert_eq!(evs.len(), events.len());
         assert_eq!(cur.last_usn, 0);
     }
+
+    const JOURNAL: JournalInfo = JournalInfo {
+        journal_id: 0xABCD,
+        first_usn: 1_000,
+        next_usn: 5_000,
+    };
+
+    #[test]
+    fn fresh_cursor_starts_at_journal_head() {
+        let cur = resume_cursor(
+            1,
+            JournalCursor {
+                last_usn: 0,
+                journal_id: 0,
+            },
+            JOURNAL,
+        )
+        .unwrap();
+        assert_eq!(cur.journal_id, JOURNAL.journal_id);
+        assert_eq!(cur.last_usn, JOURNAL.next_usn);
+    }
+
+    #[test]
+    fn matching_cursor_resumes_in_place() {
+        let saved = JournalCursor {
+            last_usn: 2_500,
+            journal_id: JOURNAL.journal_id,
+        };
+        let cur = resume_cursor(1, saved, JOURNAL).unwrap();
+        assert_eq!(cur.last_usn, 2_500);
+    }
+
+    #[test]
+    fn journal_id_mismatch_reports_wrap() {
+        let saved = JournalCursor {
+            last_usn: 2_500,
+            journal_id: 0x1234,
+        };
+        let err = resume_cursor(7, saved, JOURNAL).unwrap_err();
+        assert!(matches!(err, NtfsError::JournalWrapped { volume: 7 }));
+    }
+
+    #[test]
+    fn purged_usn_reports_wrap() {
+        let saved = JournalCursor {
+            last_usn: 999,
+            journal_id: JOURNAL.journal_id,
+        };
+        let err = resume_cursor(3, saved, JOURNAL).unwrap_err();
+        assert!(matches!(err, NtfsError::JournalWrapped { volume: 3 }));
+    }
 }
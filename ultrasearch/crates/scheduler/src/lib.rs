@@ -7,10 +7,13 @@ pub mod metrics;
 pub mod policy;
 
 pub use idle::{IdleSample, IdleState, IdleTracker};
-pub use metrics::{SystemLoad, SystemLoadSampler};
+pub use metrics::{DiskSpace, DiskSpaceSampler, SystemLoad, SystemLoadSampler};
 pub use policy::adaptive::AdaptivePolicy;
+pub use policy::tuning::{AutoTuner, TuningSample};
 
 use core_types::DocKey;
+use core_types::config::{IndexingProfile, SchedulerSection};
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 
@@ -28,13 +31,36 @@ pub struct QueuedJob {
     pub est_bytes: u64,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum JobCategory {
     Critical, // deletes/renames/attr updates
     Metadata, // MFT/USN rebuilds, small batches
     Content,  // heavy extraction/index writes
 }
 
+impl JobCategory {
+    /// Every category, highest priority first.
+    pub const ALL: [JobCategory; 3] = [Self::Critical, Self::Metadata, Self::Content];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Critical => "critical",
+            Self::Metadata => "metadata",
+            Self::Content => "content",
+        }
+    }
+}
+
+/// Label each of `(critical, metadata, content)` with its category name, in
+/// [`JobCategory::ALL`] order.
+pub fn depth_by_category(
+    (critical, metadata, content): (usize, usize, usize),
+) -> [(String, u64); 3] {
+    let depths = [critical, metadata, content];
+    JobCategory::ALL.map(|c| (c.as_str().to_string(), depths[c as usize] as u64))
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Budget {
     pub max_files: usize,
@@ -78,6 +104,11 @@ impl JobQueues {
     pub fn counts(&self) -> (usize, usize, usize) {
         (self.critical.len(), self.metadata.len(), self.content.len())
     }
+
+    /// [`counts`](Self::counts) labeled by category, for status reporting.
+    pub fn depth_by_category(&self) -> [(String, u64); 3] {
+        depth_by_category(self.counts())
+    }
 }
 
 /// Select jobs given idle state, system load, and simple budgets.
@@ -158,6 +189,62 @@ pub fn allow_content_jobs(idle: IdleState, load: SystemLoad, config: &SchedulerC
         && !load.disk_busy
 }
 
+/// Whether content jobs may write index data for a volume with `space` left. Volumes whose
+/// space is unknown are allowed; a `min_free_bytes` of 0 disables the floor.
+pub fn volume_has_room(space: Option<DiskSpace>, min_free_bytes: u64) -> bool {
+    space.is_none_or(|s| s.free_bytes >= min_free_bytes)
+}
+
+/// CPU limits and per-tick batch sizes that decide how eagerly idle time goes to indexing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SchedulerProfile {
+    pub cpu_metadata_max: f32,
+    pub cpu_content_max: f32,
+    pub metadata_batch: usize,
+    pub content_batch: usize,
+}
+
+impl SchedulerProfile {
+    pub fn named(profile: IndexingProfile) -> Self {
+        let (cpu_metadata_max, cpu_content_max, metadata_batch, content_batch) = match profile {
+            IndexingProfile::Conservative => (40.0, 20.0, 64, 16),
+            IndexingProfile::Balanced => (60.0, 40.0, 256, 64),
+            IndexingProfile::Aggressive => (85.0, 75.0, 1024, 256),
+        };
+        Self {
+            cpu_metadata_max,
+            cpu_content_max,
+            metadata_batch,
+            content_batch,
+        }
+    }
+
+    /// `section.profile`, with the limits the section sets explicitly taking precedence.
+    pub fn from_section(section: &SchedulerSection) -> Self {
+        let named = Self::named(section.profile);
+        Self {
+            cpu_metadata_max: section
+                .cpu_soft_limit_pct
+                .map_or(named.cpu_metadata_max, |pct| pct as f32),
+            cpu_content_max: section
+                .cpu_hard_limit_pct
+                .map_or(named.cpu_content_max, |pct| pct as f32),
+            metadata_batch: section
+                .metadata_batch_files
+                .map_or(named.metadata_batch, |n| n as usize),
+            content_batch: section
+                .content_batch_files
+                .map_or(named.content_batch, |n| n as usize),
+        }
+    }
+}
+
+impl Default for SchedulerProfile {
+    fn default() -> Self {
+        Self::named(IndexingProfile::default())
+    }
+}
+
 /// Static policy inputs used across scheduler beads.
 #[derive(Debug, Clone)]
 pub struct SchedulerConfig {
@@ -174,20 +261,31 @@ pub struct SchedulerConfig {
     pub power_save_mode: bool,
 }
 
+impl SchedulerConfig {
+    /// Take the CPU limits and batch sizes from `profile`.
+    pub fn apply_profile(&mut self, profile: SchedulerProfile) {
+        self.cpu_metadata_max = profile.cpu_metadata_max;
+        self.cpu_content_max = profile.cpu_content_max;
+        self.metadata_budget.max_files = profile.metadata_batch;
+        self.content_budget.max_files = profile.content_batch;
+    }
+}
+
 impl Default for SchedulerConfig {
     fn default() -> Self {
+        let profile = SchedulerProfile::default();
         Self {
             warm_idle: Duration::from_secs(15),
             deep_idle: Duration::from_secs(60),
-            cpu_metadata_max: 60.0,
-            cpu_content_max: 40.0,
+            cpu_metadata_max: profile.cpu_metadata_max,
+            cpu_content_max: profile.cpu_content_max,
             disk_busy_threshold_bps: 10 * 1024 * 1024, // placeholder: 10 MiB/s
             metadata_budget: Budget {
-                max_files: 256,
+                max_files: profile.metadata_batch,
                 max_bytes: 64 * 1024 * 1024,
             },
             content_budget: Budget {
-                max_files: 64,
+                max_files: profile.content_batch,
                 max_bytes: 512 * 1024 * 1024,
             },
             content_spawn_backlog: 200,
@@ -325,6 +423,31 @@ mod tests {
         assert_eq!(queues.len(), 1); // second job remains due to budget
     }
 
+    #[test]
+    fn depth_by_category_splits_the_total() {
+        let mut queues = JobQueues::default();
+        queues.push(
+            JobCategory::Critical,
+            Job::Delete(DocKey::from_parts(1, 1)),
+            1,
+        );
+        for frn in 2..5 {
+            queues.push(
+                JobCategory::Content,
+                Job::ContentIndex(DocKey::from_parts(1, frn)),
+                10,
+            );
+        }
+
+        let depths = queues.depth_by_category();
+        let labeled: Vec<(&str, u64)> = depths.iter().map(|(c, n)| (c.as_str(), *n)).collect();
+        assert_eq!(labeled, [("critical", 1), ("metadata", 0), ("content", 3)]);
+        assert_eq!(
+            depths.iter().map(|(_, n)| n).sum::<u64>(),
+            queues.len() as u64
+        );
+    }
+
     #[test]
     fn critical_jobs_run_even_when_busy() {
         let mut queues = JobQueues::default();
@@ -353,6 +476,66 @@ mod tests {
         assert!(selected.iter().any(|j| matches!(j, Job::Delete(_))));
     }
 
+    #[test]
+    fn aggressive_profile_indexes_content_under_more_load() {
+        let config = |profile| {
+            let mut cfg = SchedulerConfig::default();
+            cfg.apply_profile(SchedulerProfile::named(profile));
+            cfg
+        };
+        let content_taken = |cfg: &SchedulerConfig| {
+            let mut queues = JobQueues::default();
+            for i in 0..300 {
+                queues.push(
+                    JobCategory::Content,
+                    Job::ContentIndex(DocKey::from_parts(1, i)),
+                    1,
+                );
+            }
+            let load = SystemLoad {
+                cpu_percent: 50.0,
+                ..load_ok()
+            };
+            select_jobs(&mut queues, IdleState::DeepIdle, load, cfg).len()
+        };
+
+        assert_eq!(content_taken(&config(IndexingProfile::Conservative)), 0);
+        assert_eq!(content_taken(&config(IndexingProfile::Balanced)), 0);
+        assert_eq!(content_taken(&config(IndexingProfile::Aggressive)), 256);
+    }
+
+    #[test]
+    fn explicit_limits_override_the_profile() {
+        let section = SchedulerSection {
+            profile: IndexingProfile::Aggressive,
+            cpu_hard_limit_pct: Some(30),
+            ..SchedulerSection::default()
+        };
+        let profile = SchedulerProfile::from_section(&section);
+        assert_eq!(profile.cpu_content_max, 30.0);
+        assert_eq!(
+            profile.cpu_metadata_max,
+            SchedulerProfile::named(IndexingProfile::Aggressive).cpu_metadata_max
+        );
+        assert_eq!(profile.content_batch, 256);
+    }
+
+    #[test]
+    fn content_paused_below_free_space_floor() {
+        let space = |free_bytes| {
+            Some(DiskSpace {
+                free_bytes,
+                total_bytes: 10_000,
+            })
+        };
+        assert!(volume_has_room(space(5_000), 1_000));
+        assert!(volume_has_room(space(1_000), 1_000));
+        assert!(!volume_has_room(space(999), 1_000));
+        // Unknown space and a disabled floor never block.
+        assert!(volume_has_room(None, 1_000));
+        assert!(volume_has_room(space(0), 0));
+    }
+
     #[test]
     fn spawn_content_worker_honors_backlog_and_cooldown() {
         let cfg = SchedulerConfig {
@@ -1,3 +1,5 @@
 mod collect;
+mod space;
 
 pub use collect::{SystemLoad, SystemLoadSampler};
+pub use space::{DiskSpace, DiskSpaceSampler};
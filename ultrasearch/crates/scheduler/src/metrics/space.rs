@@ -0,0 +1,103 @@
+//! Free-space sampling for the disks holding indexed volumes.
+
+use std::path::Path;
+use sysinfo::{DiskRefreshKind, Disks};
+
+/// Free and total bytes of one disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskSpace {
+    pub free_bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// Reads free space of mounted disks through sysinfo.
+pub struct DiskSpaceSampler {
+    disks: Disks,
+}
+
+impl Default for DiskSpaceSampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DiskSpaceSampler {
+    pub fn new() -> Self {
+        Self {
+            disks: Disks::new_with_refreshed_list_specifics(storage_only()),
+        }
+    }
+
+    /// Re-read free space, picking up disks mounted since the last refresh.
+    pub fn refresh(&mut self) {
+        self.disks.refresh_specifics(true, storage_only());
+    }
+
+    /// Space of the disk holding `path`, if any mounted disk contains it.
+    pub fn space_for(&self, path: &Path) -> Option<DiskSpace> {
+        longest_mount(
+            self.disks.list().iter().map(|d| {
+                (
+                    d.mount_point(),
+                    DiskSpace {
+                        free_bytes: d.available_space(),
+                        total_bytes: d.total_space(),
+                    },
+                )
+            }),
+            path,
+        )
+    }
+}
+
+fn storage_only() -> DiskRefreshKind {
+    DiskRefreshKind::nothing().with_storage()
+}
+
+/// The most specific mount containing `path`, so a disk mounted at `/data` wins over `/`.
+fn longest_mount<'a>(
+    mounts: impl IntoIterator<Item = (&'a Path, DiskSpace)>,
+    path: &Path,
+) -> Option<DiskSpace> {
+    mounts
+        .into_iter()
+        .filter(|(mount, _)| path.starts_with(mount))
+        .max_by_key(|(mount, _)| mount.as_os_str().len())
+        .map(|(_, space)| space)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn space(free_bytes: u64) -> DiskSpace {
+        DiskSpace {
+            free_bytes,
+            total_bytes: 100,
+        }
+    }
+
+    #[test]
+    fn most_specific_mount_wins() {
+        let mounts = [(Path::new("/"), space(1)), (Path::new("/data"), space(2))];
+        assert_eq!(
+            longest_mount(mounts, Path::new("/data/docs/a.txt")),
+            Some(space(2))
+        );
+        assert_eq!(
+            longest_mount(mounts, Path::new("/home/a.txt")),
+            Some(space(1))
+        );
+        assert_eq!(longest_mount(mounts, Path::new("relative")), None);
+    }
+
+    #[test]
+    fn sampler_reports_space_for_existing_paths() {
+        let mut sampler = DiskSpaceSampler::new();
+        sampler.refresh();
+        let cwd = std::env::current_dir().unwrap();
+        if let Some(s) = sampler.space_for(&cwd) {
+            assert!(s.free_bytes <= s.total_bytes);
+        }
+    }
+}
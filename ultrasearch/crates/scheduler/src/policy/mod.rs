@@ -1 +1,2 @@
 pub mod adaptive;
+pub mod tuning;
@@ -0,0 +1,217 @@
+//! Feedback tuning of how eagerly idle time goes to indexing (`features.auto_tuning`).
+//!
+//! One factor scales the configured CPU limits, disk-busy threshold and batch sizes. The
+//! scheduler reports every tick that ran a content batch: how many jobs it finished, how long
+//! that took, whether the user came back while it ran, and whether work is still queued. After
+//! [`TUNING_WINDOW`] such ticks the factor steps down if the user was interrupted often, and up
+//! if they never were while work kept waiting. It never leaves the configured bounds.
+
+use crate::SchedulerConfig;
+use std::time::Duration;
+
+/// Indexing ticks observed before the factor may move.
+pub const TUNING_WINDOW: u32 = 10;
+/// Share of a window's ticks that may be interrupted before the factor steps down.
+const BACKOFF_RATE: f32 = 0.2;
+const STEP_DOWN: f32 = 0.75;
+const STEP_UP: f32 = 1.1;
+/// CPU limits are never tuned past these, whatever the factor.
+const CPU_PCT_MIN: f32 = 5.0;
+const CPU_PCT_MAX: f32 = 95.0;
+
+/// What one indexing tick achieved.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TuningSample {
+    /// The user was active at some point while the batch ran.
+    pub interrupted: bool,
+    pub jobs_done: usize,
+    pub elapsed: Duration,
+    /// Content jobs were still queued afterwards.
+    pub backlog: bool,
+}
+
+/// A change of the factor, for logging.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TuningStep {
+    pub from: f32,
+    pub to: f32,
+    /// Interrupted ticks / observed ticks in the window that caused the step.
+    pub interruption_rate: f32,
+    pub jobs_per_sec: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct AutoTuner {
+    scale: f32,
+    min_scale: f32,
+    max_scale: f32,
+    ticks: u32,
+    interrupted: u32,
+    backlogged: u32,
+    jobs: usize,
+    busy: Duration,
+}
+
+impl AutoTuner {
+    /// Start at the configured values (factor 1.0), tuning within `[min_scale, max_scale]`.
+    pub fn new(min_scale: f32, max_scale: f32) -> Self {
+        let mut tuner = Self {
+            scale: 1.0,
+            min_scale,
+            max_scale,
+            ticks: 0,
+            interrupted: 0,
+            backlogged: 0,
+            jobs: 0,
+            busy: Duration::ZERO,
+        };
+        tuner.set_bounds(min_scale, max_scale);
+        tuner
+    }
+
+    /// Current factor applied to the configured thresholds.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Change the bounds (after a config reload), pulling the factor inside them.
+    pub fn set_bounds(&mut self, min_scale: f32, max_scale: f32) {
+        self.min_scale = min_scale.min(max_scale);
+        self.max_scale = max_scale.max(min_scale);
+        self.scale = self.scale.clamp(self.min_scale, self.max_scale);
+    }
+
+    /// Record one indexing tick; returns the step taken if this closed a window that moved
+    /// the factor.
+    pub fn observe(&mut self, sample: TuningSample) -> Option<TuningStep> {
+        self.ticks += 1;
+        self.interrupted += u32::from(sample.interrupted);
+        self.backlogged += u32::from(sample.backlog);
+        self.jobs += sample.jobs_done;
+        self.busy += sample.elapsed;
+        if self.ticks < TUNING_WINDOW {
+            return None;
+        }
+
+        let interruption_rate = self.interrupted as f32 / self.ticks as f32;
+        let jobs_per_sec = match self.busy.as_secs_f32() {
+            secs if secs > 0.0 => self.jobs as f32 / secs,
+            _ => 0.0,
+        };
+        let target = if interruption_rate > BACKOFF_RATE {
+            self.scale * STEP_DOWN
+        } else if self.interrupted == 0 && self.backlogged > 0 {
+            self.scale * STEP_UP
+        } else {
+            self.scale
+        };
+        let from = self.scale;
+        self.scale = target.clamp(self.min_scale, self.max_scale);
+        self.ticks = 0;
+        self.interrupted = 0;
+        self.backlogged = 0;
+        self.jobs = 0;
+        self.busy = Duration::ZERO;
+
+        (self.scale != from).then_some(TuningStep {
+            from,
+            to: self.scale,
+            interruption_rate,
+            jobs_per_sec,
+        })
+    }
+
+    /// Scale the CPU limits, disk-busy threshold and batch sizes of `config`, which holds the
+    /// configured values.
+    pub fn apply(&self, config: &mut SchedulerConfig) {
+        let scale = self.scale;
+        let count = |n: usize| ((n as f32 * scale).round() as usize).max(1);
+        config.cpu_metadata_max = (config.cpu_metadata_max * scale).clamp(CPU_PCT_MIN, CPU_PCT_MAX);
+        config.cpu_content_max = (config.cpu_content_max * scale).clamp(CPU_PCT_MIN, CPU_PCT_MAX);
+        config.disk_busy_threshold_bps =
+            (config.disk_busy_threshold_bps as f64 * scale as f64) as u64;
+        config.content_batch_size = count(config.content_batch_size);
+        config.metadata_budget.max_files = count(config.metadata_budget.max_files);
+        config.content_budget.max_files = count(config.content_budget.max_files);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(interrupted: bool) -> TuningSample {
+        TuningSample {
+            interrupted,
+            jobs_done: 50,
+            elapsed: Duration::from_secs(2),
+            backlog: true,
+        }
+    }
+
+    fn run(tuner: &mut AutoTuner, interrupted: bool, windows: u32) {
+        for _ in 0..windows * TUNING_WINDOW {
+            tuner.observe(tick(interrupted));
+        }
+    }
+
+    #[test]
+    fn constant_interruptions_lower_aggressiveness_to_the_floor() {
+        let base = SchedulerConfig::default();
+        let mut tuner = AutoTuner::new(0.5, 2.0);
+
+        for _ in 0..TUNING_WINDOW - 1 {
+            assert_eq!(tuner.observe(tick(true)), None);
+        }
+        let step = tuner
+            .observe(tick(true))
+            .expect("a full window moves the factor");
+        assert!(step.to < step.from);
+        assert_eq!(step.interruption_rate, 1.0);
+        assert_eq!(step.jobs_per_sec, 25.0);
+
+        run(&mut tuner, true, 20);
+        assert_eq!(tuner.scale(), 0.5);
+        let mut tuned = base.clone();
+        tuner.apply(&mut tuned);
+        assert!(tuned.cpu_content_max < base.cpu_content_max);
+        assert!(tuned.disk_busy_threshold_bps < base.disk_busy_threshold_bps);
+        assert!(tuned.content_batch_size < base.content_batch_size);
+    }
+
+    #[test]
+    fn no_interruptions_raise_aggressiveness_to_the_ceiling() {
+        let base = SchedulerConfig::default();
+        let mut tuner = AutoTuner::new(0.5, 2.0);
+
+        run(&mut tuner, false, 1);
+        assert!(tuner.scale() > 1.0);
+        run(&mut tuner, false, 50);
+        assert_eq!(tuner.scale(), 2.0);
+        let mut tuned = base.clone();
+        tuner.apply(&mut tuned);
+        assert!(tuned.cpu_content_max > base.cpu_content_max);
+        assert!(tuned.cpu_content_max <= CPU_PCT_MAX);
+        assert!(tuned.content_batch_size > base.content_batch_size);
+    }
+
+    #[test]
+    fn idle_queue_and_occasional_interruptions_hold_steady() {
+        let mut tuner = AutoTuner::new(0.5, 2.0);
+        for i in 0..TUNING_WINDOW {
+            tuner.observe(TuningSample {
+                backlog: false,
+                ..tick(i == 0)
+            });
+        }
+        assert_eq!(tuner.scale(), 1.0);
+
+        for _ in 0..TUNING_WINDOW {
+            tuner.observe(TuningSample {
+                backlog: false,
+                ..tick(false)
+            });
+        }
+        assert_eq!(tuner.scale(), 1.0, "nothing waiting, nothing to gain");
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    path::Path,
+    path::{Path, PathBuf},
     sync::Arc,
     time::{SystemTime, UNIX_EPOCH},
 };
@@ -7,7 +7,7 @@ use std::{
 use anyhow::Result;
 use core_types::config::AppConfig;
 use ipc::VolumeStatus;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
 
 #[derive(Debug, Clone, Default)]
 pub struct BootstrapOptions {
@@ -15,7 +15,8 @@ pub struct BootstrapOptions {
     pub initial_metas: Option<Vec<core_types::FileMeta>>,
     /// Skip initial ingest entirely (used for tests that want a blank service).
     pub skip_initial_ingest: bool,
-    /// Override IPC pipe name (default is \\\\.\\pipe\\ultrasearch).
+    /// Override IPC pipe name (default is \\\\.\\pipe\\ultrasearch); with `uds-transport` off
+    /// Windows, the socket path (default `/tmp/ultrasearch.sock`).
     pub pipe_name: Option<String>,
     /// Force scheduler to run content jobs even if idle/load gates are active (tests).
     pub force_content_jobs: bool,
@@ -23,14 +24,16 @@ pub struct BootstrapOptions {
 
 use crate::{
     init_tracing_with_config,
-    meta_ingest::ingest_with_paths,
+    instance_lock::InstanceLock,
+    meta_ingest::{IngestContext, ingest_with_paths},
     metrics::{init_metrics_from_config, set_global_metrics},
     priority::apply_background_priorities,
     scanner::{scan_volumes, watch_changes},
-    scheduler_runtime::SchedulerRuntime,
+    scheduler_runtime::{SHUTDOWN_GRACE, SchedulerRuntime},
     search_handler::set_search_handler,
     status_provider::{
-        init_basic_status_provider, update_status_last_commit, update_status_volumes,
+        IndexState, init_basic_status_provider, mark_initial_scan_complete,
+        update_status_index_states, update_status_last_commit, update_status_volumes,
     },
 };
 
@@ -48,6 +51,15 @@ pub fn run_app_with_options(
 
     let _guard = init_tracing_with_config(&cfg.logging)?;
 
+    // Before any index or the pipe is opened: a second instance must not race the first.
+    let _instance = match InstanceLock::acquire(Path::new(&cfg.paths.state_dir)) {
+        Ok(lock) => lock,
+        Err(e) => {
+            tracing::error!("{e:#}");
+            return Err(e);
+        }
+    };
+
     // Initialize Tokio runtime
     let rt = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
@@ -62,23 +74,10 @@ pub fn run_app_with_options(
         set_global_metrics(metrics);
     }
 
-    let mut pending_jobs = Vec::new();
-
     let mut cfg_owned = cfg.clone();
     super::ensure_default_volumes(&mut cfg_owned)?;
     ensure_data_paths_exist(&cfg_owned)?;
 
-    match opts.initial_metas {
-        Some(metas) => ingest_seed_metadata(&cfg_owned, metas, &mut pending_jobs)?,
-        None if opts.skip_initial_ingest => {
-            tracing::info!("skip_initial_ingest=true; leaving indices empty");
-        }
-        None => {
-            let jobs = scan_volumes(&cfg_owned)?;
-            pending_jobs.extend(jobs);
-        }
-    }
-
     // Start scheduler loop
     // We need to clone cfg for the scheduler (or pass reference if new() takes ref).
     // SchedulerRuntime::new takes &AppConfig.
@@ -86,67 +85,75 @@ pub fn run_app_with_options(
     if opts.force_content_jobs {
         scheduler.force_allow_content();
     }
-    if !pending_jobs.is_empty() {
-        tracing::info!(
-            "Seeding {} content jobs into scheduler queue",
-            pending_jobs.len()
-        );
-        scheduler.submit_content_jobs(pending_jobs);
-    }
-    rt.spawn(scheduler.run_loop());
-
-    // Start change watcher (USN or noop on unsupported platforms) after scheduler channel exists.
-    let cfg_clone = cfg_owned.clone();
-    rt.spawn(async move {
-        if let Err(e) = watch_changes(cfg_clone).await {
-            tracing::warn!("change watcher exited: {e}");
+    let (scheduler_stop, stop_rx) = oneshot::channel::<()>();
+    let scheduler_task = rt.spawn(scheduler.run_until(
+        async {
+            let _ = stop_rx.await;
+        },
+        SHUTDOWN_GRACE,
+    ));
+
+    // Installed before the change watcher starts so every rename goes through it.
+    let delta = if cfg_owned.features.delta_index {
+        let meta_path = PathBuf::from(&cfg_owned.paths.meta_index);
+        match crate::delta_tier::install(&meta_path) {
+            Ok(delta) => {
+                rt.spawn(crate::delta_tier::run_merger(Arc::clone(&delta), meta_path));
+                Some(delta)
+            }
+            Err(e) => {
+                tracing::warn!("delta index unavailable, writing changes directly: {e:#}");
+                None
+            }
         }
-    });
+    } else {
+        None
+    };
 
     // Try to install unified search handler.
     // We pass both meta and content index paths.
     let meta_path = Path::new(&cfg_owned.paths.meta_index);
     let content_path = Path::new(&cfg_owned.paths.content_index);
 
-    let mut attempts = 0;
-    loop {
-        match crate::search_handler::UnifiedSearchHandler::try_new(meta_path, content_path) {
-            Ok(handler) => {
-                set_search_handler(Box::new(handler));
-                break;
-            }
-            Err(e) => {
-                // Check if error string contains "corruption" or "corrupted" or similar tantivy errors.
-                // Tantivy errors are opaque via anyhow, so string check is a heuristic.
-                let msg = e.to_string().to_lowercase();
-                let is_corruption =
-                    msg.contains("corrupt") || msg.contains("format") || msg.contains("lock");
-
-                if is_corruption && attempts < 1 {
-                    tracing::warn!(
-                        "Index corruption detected ({}), attempting recovery...",
-                        msg
-                    );
-                    // Rename broken index if it exists
-                    if meta_path.exists() {
-                        let broken = meta_path.with_extension("broken");
-                        let _ = std::fs::rename(meta_path, &broken);
-                        tracing::info!("Renamed corrupt meta index to {:?}", broken);
-                    }
-                    // Content index might be fine, but let's be safe and rename it too if opening failed generally?
-                    // UnifiedSearchHandler tries both. If meta fails, we fail.
-                    // If content fails, we log warning but return handler (in try_new implementation).
-                    // So if try_new returns Err, it's likely meta-index issue or critical content issue.
-                    // Let's wipe both if we can't determine source easily, or just meta.
-                    // For simplicity in this resilience task, we wipe meta.
-                    attempts += 1;
-                    continue;
-                }
-
-                tracing::warn!("unified search handler not initialized: {}", e);
-                break;
+    // Corrupt indexes are backed up, recreated and queued for a reindex inside the open itself
+    // when `paths.recreate_corrupt_indexes` is set; otherwise the service stays up, degraded.
+    match crate::search_handler::UnifiedSearchHandler::try_new(meta_path, content_path) {
+        Ok(handler) => {
+            let handler = match &delta {
+                Some(delta) => handler.with_delta(Arc::clone(delta)),
+                None => handler,
+            };
+            let content = if handler.has_content_index() {
+                IndexState::Open
+            } else {
+                IndexState::Failed
+            };
+            update_status_index_states(IndexState::Open, content);
+            if cfg_owned.search.preload_history {
+                crate::warmup::preload_from_history(&handler, &cfg_owned);
             }
+            set_search_handler(Box::new(handler));
         }
+        Err(e) => {
+            tracing::warn!("unified search handler not initialized: {:#}", e);
+            update_status_index_states(IndexState::Failed, IndexState::Pending);
+        }
+    }
+
+    let ceiling = cfg_owned.scheduler.memory_ceiling_bytes;
+    if ceiling > 0 {
+        let mut guard = crate::memory::MemoryGuard::process(ceiling);
+        if let Some(delta) = &delta {
+            let (delta, meta_path) = (Arc::clone(delta), meta_path.to_path_buf());
+            guard = guard.on_pressure("delta merge", move || {
+                crate::delta_tier::merge_now(&delta, &meta_path).map(drop)
+            });
+        }
+        let guard = guard.on_pressure("search caches", || {
+            crate::search_handler::trim_caches();
+            Ok(())
+        });
+        rt.spawn(crate::memory::run_guard(Arc::new(guard)));
     }
 
     #[cfg(target_os = "windows")]
@@ -158,6 +165,36 @@ pub fn run_app_with_options(
         }
     }
 
+    #[cfg(all(unix, feature = "uds-transport"))]
+    {
+        let socket = opts
+            .pipe_name
+            .as_deref()
+            .unwrap_or(crate::ipc::DEFAULT_SOCKET_PATH);
+        if let Err(e) = rt.block_on(crate::ipc::start_uds_server(Path::new(socket))) {
+            tracing::error!("failed to start IPC server: {:#}", e);
+        }
+    }
+
+    // The initial scan runs behind the open indexes and the pipe, so status reports `Indexing`
+    // until it is done; the change watcher then resumes from the cursors it saved.
+    let cfg_clone = cfg_owned.clone();
+    let initial = match opts.initial_metas {
+        Some(metas) => InitialScan::Seed(metas),
+        None if opts.skip_initial_ingest => InitialScan::Skip,
+        None => InitialScan::Volumes,
+    };
+    rt.spawn(async move {
+        run_initial_scan(cfg_clone.clone(), initial).await;
+        // Drop results cached mid-scan and pick up its commits before reporting `Ready`.
+        crate::search_handler::trim_caches();
+        mark_initial_scan_complete();
+        // USN or noop on unsupported platforms.
+        if let Err(e) = watch_changes(cfg_clone).await {
+            tracing::warn!("change watcher exited: {e}");
+        }
+    });
+
     tracing::info!("UltraSearch service started. Waiting for shutdown signal...");
 
     // Block until shutdown signal
@@ -168,10 +205,68 @@ pub fn run_app_with_options(
 
     let _ = shutdown_rx.blocking_recv();
 
-    tracing::info!("Shutdown signal received. Exiting.");
+    tracing::info!("Shutdown signal received. Draining scheduler.");
+    let _ = scheduler_stop.send(());
+    if let Err(e) = rt.block_on(scheduler_task) {
+        tracing::warn!("scheduler task ended abnormally: {e}");
+    }
+    if let Some(delta) = &delta {
+        match crate::delta_tier::merge_now(delta, meta_path) {
+            Ok(merged) => tracing::info!("merged {merged} pending delta changes"),
+            Err(e) => tracing::warn!("pending delta changes lost at shutdown: {e:#}"),
+        }
+    }
+    tracing::info!("Scheduler drained. Exiting.");
     Ok(())
 }
 
+/// Where the index contents come from at startup.
+enum InitialScan {
+    /// Seed the meta index with these entries instead of discovering volumes.
+    Seed(Vec<core_types::FileMeta>),
+    /// Leave the indexes as they are.
+    Skip,
+    /// Scan the configured volumes.
+    Volumes,
+}
+
+/// Ingest the startup metadata and queue its content jobs; failures are logged, leaving the
+/// service up with whatever the indexes already hold.
+async fn run_initial_scan(cfg: AppConfig, initial: InitialScan) {
+    let scanned = tokio::task::spawn_blocking(move || {
+        let mut pending_jobs = Vec::new();
+        match initial {
+            InitialScan::Seed(metas) => ingest_seed_metadata(&cfg, metas, &mut pending_jobs)?,
+            InitialScan::Skip => {
+                tracing::info!("skip_initial_ingest=true; leaving indices empty");
+            }
+            InitialScan::Volumes => pending_jobs = scan_volumes(&cfg)?,
+        }
+        anyhow::Ok(pending_jobs)
+    })
+    .await;
+    let pending_jobs = match scanned {
+        Ok(Ok(jobs)) => jobs,
+        Ok(Err(e)) => {
+            tracing::error!("initial scan failed: {e:#}");
+            return;
+        }
+        Err(e) => {
+            tracing::error!("initial scan task failed: {e}");
+            return;
+        }
+    };
+    if !pending_jobs.is_empty() {
+        tracing::info!(
+            "Seeding {} content jobs into scheduler queue",
+            pending_jobs.len()
+        );
+        for job in pending_jobs {
+            crate::scheduler_runtime::enqueue_content_job(job);
+        }
+    }
+}
+
 /// Make sure all configured data paths exist so worker processes don’t fail with ENOENT.
 fn ensure_data_paths_exist(cfg: &AppConfig) -> Result<()> {
     use std::fs;
@@ -203,7 +298,13 @@ fn ingest_seed_metadata(
         return Ok(());
     }
 
-    ingest_with_paths(&cfg.paths, metas.clone(), None)?;
+    // Seed metadata comes from no journal, so there is no cursor to report.
+    ingest_with_paths(
+        &cfg.paths,
+        metas.clone(),
+        Some(meta_index::WriterConfig::auto()),
+        &IngestContext::default(),
+    )?;
 
     let mut by_vol: std::collections::HashMap<core_types::VolumeId, (u64, u64)> =
         std::collections::HashMap::new();
@@ -223,6 +324,8 @@ fn ingest_seed_metadata(
             pending_bytes: 0,
             last_usn: None,
             journal_id: None,
+            free_bytes: None,
+            total_bytes: None,
         });
     }
 
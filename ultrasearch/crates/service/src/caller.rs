@@ -0,0 +1,153 @@
+//! The IPC client a request is being handled for.
+//!
+//! The service reads files with its own account, which can open files the client cannot.
+//! Requests that hand back file contents (previews, stored content) first ask the caller set
+//! here whether it may read the file itself. Work not done for an IPC client (no caller set)
+//! is the service's own and is not checked.
+
+use std::cell::RefCell;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Whether the client behind a connection may read a given file.
+pub trait CallerAccess: Send + Sync {
+    fn can_read(&self, path: &Path) -> bool;
+}
+
+/// A client whose identity could not be established; it may read nothing.
+pub struct NoAccess;
+
+impl CallerAccess for NoAccess {
+    fn can_read(&self, _path: &Path) -> bool {
+        false
+    }
+}
+
+thread_local! {
+    static CALLER: RefCell<Option<Arc<dyn CallerAccess>>> = const { RefCell::new(None) };
+}
+
+/// Run `f` on behalf of `caller`, restoring the previous caller afterwards.
+pub fn with_caller<R>(caller: Option<Arc<dyn CallerAccess>>, f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<Arc<dyn CallerAccess>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            CALLER.with(|c| *c.borrow_mut() = previous);
+        }
+    }
+    let _restore = Restore(CALLER.with(|c| c.replace(caller)));
+    f()
+}
+
+/// Whether the current caller may read `path`; true outside of any request.
+pub fn caller_can_read(path: &Path) -> bool {
+    let caller = CALLER.with(|c| c.borrow().clone());
+    caller.is_none_or(|c| c.can_read(path))
+}
+
+/// The client of a named pipe instance, checked by impersonating it while opening the file.
+#[cfg(windows)]
+pub struct PipeClient {
+    /// Raw handle of the pipe instance; valid while its connection is being served.
+    pipe: isize,
+}
+
+#[cfg(windows)]
+impl PipeClient {
+    /// `pipe` must outlive every request handled with this caller.
+    pub fn new(pipe: &tokio::net::windows::named_pipe::NamedPipeServer) -> Self {
+        use std::os::windows::io::AsRawHandle;
+        Self {
+            pipe: pipe.as_raw_handle() as isize,
+        }
+    }
+}
+
+#[cfg(windows)]
+impl CallerAccess for PipeClient {
+    fn can_read(&self, path: &Path) -> bool {
+        use windows::Win32::Foundation::HANDLE;
+        use windows::Win32::Security::RevertToSelf;
+        use windows::Win32::System::Pipes::ImpersonateNamedPipeClient;
+
+        // SAFETY: the pipe handle is open while its connection is served.
+        if unsafe { ImpersonateNamedPipeClient(HANDLE(self.pipe)) }.is_err() {
+            return false;
+        }
+        let readable = std::fs::File::open(path).is_ok();
+        // SAFETY: ends the impersonation begun above on this thread.
+        if let Err(e) = unsafe { RevertToSelf() } {
+            // Carrying on would serve later requests with the client's token.
+            panic!("RevertToSelf failed after impersonating a pipe client: {e}");
+        }
+        readable
+    }
+}
+
+/// The peer of a Unix socket, checked against the file's owner, group and mode bits.
+#[cfg(unix)]
+pub struct PeerCred {
+    pub uid: u32,
+    pub gid: u32,
+}
+
+#[cfg(unix)]
+impl CallerAccess for PeerCred {
+    fn can_read(&self, path: &Path) -> bool {
+        use std::os::unix::fs::MetadataExt;
+        let Ok(meta) = std::fs::metadata(path) else {
+            return false;
+        };
+        let mode = meta.mode();
+        if self.uid == 0 {
+            return true;
+        }
+        if meta.uid() == self.uid {
+            mode & 0o400 != 0
+        } else if meta.gid() == self.gid {
+            mode & 0o040 != 0
+        } else {
+            mode & 0o004 != 0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caller_applies_only_inside_with_caller() {
+        let path = Path::new("Cargo.toml");
+        assert!(caller_can_read(path));
+        let denied = with_caller(Some(Arc::new(NoAccess)), || caller_can_read(path));
+        assert!(!denied);
+        assert!(caller_can_read(path));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn peer_cred_follows_mode_bits() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("private.txt");
+        std::fs::write(&path, "secret").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        let meta = std::fs::metadata(&path).unwrap();
+
+        let owner = PeerCred {
+            uid: meta.uid(),
+            gid: meta.gid(),
+        };
+        let stranger = PeerCred {
+            uid: meta.uid().wrapping_add(1).max(1),
+            gid: meta.gid().wrapping_add(1),
+        };
+        assert!(owner.can_read(&path));
+        assert!(!stranger.can_read(&path));
+
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(stranger.can_read(&path));
+    }
+}
@@ -0,0 +1,453 @@
+//! Live config reload: diff the running config against the file on disk and apply what can
+//! change without a restart.
+//!
+//! - Added volumes are discovered and scanned; the USN watcher picks them up on its next tick.
+//! - Removed volumes drop out of the watcher, and their docs are purged when
+//!   `volume_settings.purge_removed_volumes` is set.
+//! - Scheduler thresholds are picked up by `SchedulerRuntime` on its next tick.
+
+use crate::scanner;
+use crate::scheduler_runtime::enqueue_content_job;
+use crate::status_provider::{status_snapshot, update_status_volumes};
+use anyhow::Result;
+use core_types::config::{AppConfig, PathsSection, SchedulerSection, get_current_config};
+use ntfs_watcher::discover_volumes;
+use serde::Serialize;
+use std::fmt::Display;
+use std::path::Path;
+
+/// Sections that are only read at startup.
+const RESTART_SECTIONS: &[&str] = &["app", "logging", "metrics", "paths"];
+
+/// Sections baked into the index schema; they only apply to a rebuilt index.
+const REINDEX_SECTIONS: &[&str] = &["search"];
+
+/// What changed between two configs.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ConfigDiff {
+    pub added_volumes: Vec<String>,
+    pub removed_volumes: Vec<String>,
+    /// Human-readable `field: old -> new` entries for the scheduler section.
+    pub scheduler: Vec<String>,
+    /// Other top-level sections whose contents differ.
+    pub sections: Vec<&'static str>,
+}
+
+impl ConfigDiff {
+    pub fn between(old: &AppConfig, new: &AppConfig) -> Self {
+        let added_volumes = new
+            .volumes
+            .iter()
+            .filter(|v| !old.volumes.contains(v))
+            .cloned()
+            .collect();
+        let removed_volumes = old
+            .volumes
+            .iter()
+            .filter(|v| !new.volumes.contains(v))
+            .cloned()
+            .collect();
+
+        let mut sections = Vec::new();
+        let mut section = |name: &'static str, changed: bool| {
+            if changed {
+                sections.push(name);
+            }
+        };
+        section("app", differs(&old.app, &new.app));
+        section("logging", differs(&old.logging, &new.logging));
+        section("metrics", differs(&old.metrics, &new.metrics));
+        section("paths", differs(&old.paths, &new.paths));
+        section("features", differs(&old.features, &new.features));
+        section("extract", differs(&old.extract, &new.extract));
+        section("semantic", differs(&old.semantic, &new.semantic));
+        section("search", differs(&old.search, &new.search));
+        section(
+            "volume_settings",
+            differs(&old.volume_settings, &new.volume_settings),
+        );
+        section(
+            "content_index_volumes",
+            old.content_index_volumes != new.content_index_volumes,
+        );
+
+        Self {
+            added_volumes,
+            removed_volumes,
+            scheduler: scheduler_changes(&old.scheduler, &new.scheduler),
+            sections,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added_volumes.is_empty()
+            && self.removed_volumes.is_empty()
+            && self.scheduler.is_empty()
+            && self.sections.is_empty()
+    }
+
+    /// One-line summary for `ReloadConfigResponse::message`.
+    pub fn summary(&self) -> String {
+        if self.is_empty() {
+            return "no changes".into();
+        }
+        let mut parts = Vec::new();
+        if !self.added_volumes.is_empty() {
+            parts.push(format!(
+                "volumes added: {} (scanning)",
+                self.added_volumes.join(", ")
+            ));
+        }
+        if !self.removed_volumes.is_empty() {
+            parts.push(format!(
+                "volumes removed: {}",
+                self.removed_volumes.join(", ")
+            ));
+        }
+        if !self.scheduler.is_empty() {
+            parts.push(format!("scheduler: {}", self.scheduler.join(", ")));
+        }
+        let (reindex, rest): (Vec<&str>, Vec<&str>) = self
+            .sections
+            .iter()
+            .partition(|s| REINDEX_SECTIONS.contains(s));
+        let (restart, live): (Vec<&str>, Vec<&str>) =
+            rest.into_iter().partition(|s| RESTART_SECTIONS.contains(s));
+        if !live.is_empty() {
+            parts.push(format!("updated: {}", live.join(", ")));
+        }
+        if !restart.is_empty() {
+            parts.push(format!("restart required: {}", restart.join(", ")));
+        }
+        if !reindex.is_empty() {
+            parts.push(format!(
+                "reindex required: {} (rebuild the metadata index)",
+                reindex.join(", ")
+            ));
+        }
+        parts.join("; ")
+    }
+}
+
+fn differs<T: Serialize>(a: &T, b: &T) -> bool {
+    serde_json::to_value(a).ok() != serde_json::to_value(b).ok()
+}
+
+fn scheduler_changes(old: &SchedulerSection, new: &SchedulerSection) -> Vec<String> {
+    fn field<T: PartialEq + Display>(out: &mut Vec<String>, name: &str, a: T, b: T) {
+        if a != b {
+            out.push(format!("{name} {a} -> {b}"));
+        }
+    }
+    let mut out = Vec::new();
+    field(
+        &mut out,
+        "idle_warm_seconds",
+        old.idle_warm_seconds,
+        new.idle_warm_seconds,
+    );
+    field(
+        &mut out,
+        "idle_deep_seconds",
+        old.idle_deep_seconds,
+        new.idle_deep_seconds,
+    );
+    field(
+        &mut out,
+        "max_records_per_tick",
+        old.max_records_per_tick,
+        new.max_records_per_tick,
+    );
+    field(
+        &mut out,
+        "usn_chunk_bytes",
+        old.usn_chunk_bytes,
+        new.usn_chunk_bytes,
+    );
+    // Unset limits come from the profile.
+    fn limit(value: Option<u64>) -> String {
+        value.map_or_else(|| "profile".to_string(), |v| v.to_string())
+    }
+    field(
+        &mut out,
+        "profile",
+        old.profile.as_str(),
+        new.profile.as_str(),
+    );
+    field(
+        &mut out,
+        "cpu_soft_limit_pct",
+        limit(old.cpu_soft_limit_pct),
+        limit(new.cpu_soft_limit_pct),
+    );
+    field(
+        &mut out,
+        "cpu_hard_limit_pct",
+        limit(old.cpu_hard_limit_pct),
+        limit(new.cpu_hard_limit_pct),
+    );
+    field(
+        &mut out,
+        "metadata_batch_files",
+        limit(old.metadata_batch_files),
+        limit(new.metadata_batch_files),
+    );
+    field(
+        &mut out,
+        "content_batch_files",
+        limit(old.content_batch_files),
+        limit(new.content_batch_files),
+    );
+    field(
+        &mut out,
+        "disk_busy_bytes_per_s",
+        old.disk_busy_bytes_per_s,
+        new.disk_busy_bytes_per_s,
+    );
+    field(
+        &mut out,
+        "content_batch_size",
+        old.content_batch_size,
+        new.content_batch_size,
+    );
+    field(
+        &mut out,
+        "power_save_mode",
+        old.power_save_mode,
+        new.power_save_mode,
+    );
+    field(
+        &mut out,
+        "min_free_disk_bytes",
+        old.min_free_disk_bytes,
+        new.min_free_disk_bytes,
+    );
+    field(
+        &mut out,
+        "content_queue_capacity",
+        old.content_queue_capacity,
+        new.content_queue_capacity,
+    );
+    field(
+        &mut out,
+        "content_queue_policy",
+        old.content_queue_policy.as_str(),
+        new.content_queue_policy.as_str(),
+    );
+    field(
+        &mut out,
+        "auto_tuning_min_pct",
+        old.auto_tuning_min_pct,
+        new.auto_tuning_min_pct,
+    );
+    field(
+        &mut out,
+        "auto_tuning_max_pct",
+        old.auto_tuning_max_pct,
+        new.auto_tuning_max_pct,
+    );
+    out
+}
+
+/// Reload the config file, apply the differences, and describe them.
+pub fn reload_and_apply(path: Option<&Path>) -> Result<String> {
+    let old = get_current_config();
+    let new = core_types::config::reload_config(path)?;
+    let diff = ConfigDiff::between(&old, &new);
+    let mut summary = diff.summary();
+    if let Some(note) = apply(&diff, &new) {
+        summary.push_str("; ");
+        summary.push_str(&note);
+    }
+    tracing::info!(%summary, "config reloaded");
+    Ok(summary)
+}
+
+/// Act on volume changes and pass the new config to the search handler. Scheduler and watcher
+/// settings are read live from the config cache, so they need nothing here. Returns a note when
+/// removed volumes could not be handled.
+fn apply(diff: &ConfigDiff, new: &AppConfig) -> Option<String> {
+    crate::search_handler::apply_config(new);
+
+    if !diff.added_volumes.is_empty() {
+        let cfg = new.clone();
+        let added = diff.added_volumes.clone();
+        // Scans can take minutes; the IPC caller only waits for the summary.
+        std::thread::spawn(move || {
+            for job in scanner::scan_added_volumes(&cfg, &added) {
+                enqueue_content_job(job);
+            }
+        });
+    }
+
+    if diff.removed_volumes.is_empty() {
+        return None;
+    }
+    let volumes = match discover_volumes() {
+        Ok(v) => v,
+        Err(e) => return Some(format!("could not resolve removed volumes: {e}")),
+    };
+    let removed = scanner::volumes_for_mounts(&volumes, &diff.removed_volumes);
+    let mut status = status_snapshot().volumes;
+    status.retain(|s| !removed.iter().any(|v| v.id == s.volume));
+    update_status_volumes(status);
+
+    if !new.volume_settings.purge_removed_volumes {
+        return None;
+    }
+    let failures: Vec<String> = removed
+        .iter()
+        .filter_map(|vol| {
+            purge_volume_docs(&new.paths, vol.id)
+                .err()
+                .map(|e| format!("{}: {e}", vol.id))
+        })
+        .collect();
+    (!failures.is_empty()).then(|| format!("purge failed for {}", failures.join(", ")))
+}
+
+/// Delete every metadata and content doc belonging to `volume`.
+pub fn purge_volume_docs(paths: &PathsSection, volume: u16) -> Result<()> {
+    crate::reindex::drop_docs(paths, Some(&[volume]), ipc::ReindexScope::All)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core_types::{DocKey, FileFlags, FileMeta};
+
+    fn cfg_with_volumes(volumes: &[&str]) -> AppConfig {
+        AppConfig {
+            volumes: volumes.iter().map(|v| v.to_string()).collect(),
+            ..AppConfig::default()
+        }
+    }
+
+    #[test]
+    fn identical_configs_have_no_changes() {
+        let cfg = cfg_with_volumes(&["C:\\"]);
+        let diff = ConfigDiff::between(&cfg, &cfg.clone());
+        assert!(diff.is_empty());
+        assert_eq!(diff.summary(), "no changes");
+    }
+
+    #[test]
+    fn volume_changes_are_split_into_added_and_removed() {
+        let old = cfg_with_volumes(&["C:\\", "D:\\"]);
+        let new = cfg_with_volumes(&["D:\\", "E:\\"]);
+        let diff = ConfigDiff::between(&old, &new);
+        assert_eq!(diff.added_volumes, vec!["E:\\".to_string()]);
+        assert_eq!(diff.removed_volumes, vec!["C:\\".to_string()]);
+        assert!(diff.scheduler.is_empty());
+        assert!(diff.sections.is_empty());
+    }
+
+    #[test]
+    fn scheduler_threshold_changes_are_listed() {
+        let old = AppConfig::default();
+        let mut new = old.clone();
+        new.scheduler.idle_warm_seconds = 30;
+        new.scheduler.content_batch_size = 50;
+        let diff = ConfigDiff::between(&old, &new);
+        assert_eq!(
+            diff.scheduler,
+            vec![
+                "idle_warm_seconds 15 -> 30".to_string(),
+                "content_batch_size 1000 -> 50".to_string(),
+            ]
+        );
+
+        let mut tuned = old.clone();
+        tuned.scheduler.auto_tuning_min_pct = 40;
+        tuned.scheduler.auto_tuning_max_pct = 160;
+        assert_eq!(
+            ConfigDiff::between(&old, &tuned).scheduler,
+            vec![
+                format!(
+                    "auto_tuning_min_pct {} -> 40",
+                    old.scheduler.auto_tuning_min_pct
+                ),
+                format!(
+                    "auto_tuning_max_pct {} -> 160",
+                    old.scheduler.auto_tuning_max_pct
+                ),
+            ]
+        );
+        assert_eq!(
+            diff.summary(),
+            "scheduler: idle_warm_seconds 15 -> 30, content_batch_size 1000 -> 50"
+        );
+    }
+
+    #[test]
+    fn content_queue_and_free_space_changes_are_listed() {
+        let old = AppConfig::default();
+        let mut new = old.clone();
+        new.scheduler.min_free_disk_bytes = 0;
+        new.scheduler.content_queue_capacity = 10;
+        new.scheduler.content_queue_policy = core_types::config::ContentQueuePolicy::Block;
+        let diff = ConfigDiff::between(&old, &new);
+        assert_eq!(
+            diff.scheduler,
+            vec![
+                format!(
+                    "min_free_disk_bytes {} -> 0",
+                    old.scheduler.min_free_disk_bytes
+                ),
+                format!(
+                    "content_queue_capacity {} -> 10",
+                    old.scheduler.content_queue_capacity
+                ),
+                "content_queue_policy drop_new -> block".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn summary_flags_sections_needing_restart() {
+        let old = cfg_with_volumes(&["C:\\"]);
+        let mut new = cfg_with_volumes(&["C:\\", "D:\\"]);
+        new.paths.meta_index = "elsewhere".into();
+        new.extract.max_chars_per_file = 10;
+        new.search.name_analyzer = core_types::config::NameAnalyzer::AsciiFolded;
+        let diff = ConfigDiff::between(&old, &new);
+        assert_eq!(diff.sections, vec!["paths", "extract", "search"]);
+        assert_eq!(
+            diff.summary(),
+            "volumes added: D:\\ (scanning); updated: extract; restart required: paths; \
+             reindex required: search (rebuild the metadata index)"
+        );
+    }
+
+    #[test]
+    fn purge_removes_only_the_target_volume() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = PathsSection {
+            meta_index: dir.path().join("meta").to_string_lossy().into_owned(),
+            content_index: dir.path().join("content").to_string_lossy().into_owned(),
+            ..PathsSection::default()
+        };
+        std::fs::create_dir_all(&paths.meta_index).unwrap();
+        std::fs::create_dir_all(&paths.content_index).unwrap();
+        let metas = [(1, 10), (2, 20), (2, 21)].map(|(vol, frn)| {
+            FileMeta::new(
+                DocKey::from_parts(vol, frn),
+                vol,
+                None,
+                format!("f{frn}.txt"),
+                None,
+                1,
+                0,
+                0,
+                FileFlags::empty(),
+            )
+        });
+        crate::meta_ingest::ingest_with_paths(&paths, metas, None, &Default::default()).unwrap();
+
+        purge_volume_docs(&paths, 2).unwrap();
+
+        let meta = meta_index::open_or_create_index(Path::new(&paths.meta_index)).unwrap();
+        let searcher = meta_index::open_reader(&meta).unwrap().searcher();
+        assert_eq!(searcher.num_docs(), 1);
+    }
+}
@@ -0,0 +1,77 @@
+//! Service side of the metadata delta tier (`features.delta_index`).
+//!
+//! With the flag on, change-journal renames are committed to a RAM [`DeltaIndex`] instead of
+//! the on-disk meta index, and a background task folds the delta into it every
+//! [`MERGE_INTERVAL`], or sooner once [`MERGE_THRESHOLD`] changes are waiting, with one commit.
+//! Name searches union both tiers (`UnifiedSearchHandler::with_delta`), so a change is
+//! searchable as soon as the delta commits it. Whatever is still in the delta at shutdown is
+//! merged before the service exits.
+
+use crate::meta_ingest::open_meta_index;
+use anyhow::Result;
+use core_types::WriterProfile;
+use meta_index::WriterConfig;
+use meta_index::delta::DeltaIndex;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// Longest a change waits in the delta before it is merged.
+pub const MERGE_INTERVAL: Duration = Duration::from_secs(30);
+/// Pending changes that trigger a merge before the interval is up.
+pub const MERGE_THRESHOLD: usize = 5_000;
+/// How often the merger checks the delta.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+static DELTA: OnceLock<Arc<DeltaIndex>> = OnceLock::new();
+
+/// Create the delta for the meta index at `index_path` and route change ingest to it. Later
+/// calls return the delta installed first.
+pub fn install(index_path: &Path) -> Result<Arc<DeltaIndex>> {
+    if let Some(delta) = DELTA.get() {
+        return Ok(Arc::clone(delta));
+    }
+    let main = open_meta_index(index_path)?;
+    let delta = Arc::new(DeltaIndex::for_main(&main)?);
+    Ok(Arc::clone(DELTA.get_or_init(|| delta)))
+}
+
+/// The installed delta, if `features.delta_index` is on.
+pub fn current() -> Option<Arc<DeltaIndex>> {
+    DELTA.get().cloned()
+}
+
+/// Merge `delta` into the meta index at `index_path` now; returns how many changes moved.
+pub fn merge_now(delta: &DeltaIndex, index_path: &Path) -> Result<usize> {
+    if delta.is_empty() {
+        return Ok(0);
+    }
+    let main = open_meta_index(index_path)?;
+    delta.merge_into(&main, &WriterConfig::auto_for(WriterProfile::SteadyState))
+}
+
+/// Merge on [`MERGE_INTERVAL`] / [`MERGE_THRESHOLD`] for as long as the runtime runs. A failed
+/// merge (say, another writer holds the index) leaves the changes in the delta for the next
+/// attempt.
+pub async fn run_merger(delta: Arc<DeltaIndex>, index_path: PathBuf) {
+    let mut since_merge = Instant::now();
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let waiting = delta.len();
+        if waiting == 0 {
+            since_merge = Instant::now();
+            continue;
+        }
+        if waiting < MERGE_THRESHOLD && since_merge.elapsed() < MERGE_INTERVAL {
+            continue;
+        }
+        let (task_delta, path) = (Arc::clone(&delta), index_path.clone());
+        match tokio::task::spawn_blocking(move || merge_now(&task_delta, &path)).await {
+            Ok(Ok(merged)) => debug!(merged, "merged delta tier into the meta index"),
+            Ok(Err(err)) => warn!(error = %err, waiting, "delta merge failed; will retry"),
+            Err(err) => warn!("delta merge task failed: {err}"),
+        }
+        since_merge = Instant::now();
+    }
+}
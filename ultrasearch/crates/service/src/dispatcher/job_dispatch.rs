@@ -1,9 +1,16 @@
 use anyhow::{Context, Result};
-use core_types::config::AppConfig;
+use core_types::config::{AppConfig, ContentStemming};
+use core_types::jobs::{
+    BatchMarker, ExtractSample, FAILED_FILE, FailedJobs, FailureKind, RetryJob, progress_path,
+    stats_path,
+};
+use scheduler::JobCategory;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
 use tokio::task;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JobSpec {
@@ -16,18 +23,85 @@ pub struct JobSpec {
     pub max_chars: Option<usize>,
     #[serde(default)]
     pub file_size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<JobCategory>,
+    /// Failed attempts so far (maintained by the worker).
+    #[serde(default)]
+    pub attempts: u32,
+}
+
+impl RetryJob for JobSpec {
+    fn doc(&self) -> (u16, u64) {
+        (self.volume_id, self.file_id)
+    }
+
+    fn attempts_mut(&mut self) -> &mut u32 {
+        &mut self.attempts
+    }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 struct JobBatch {
     version: u32,
     jobs: Vec<JobSpec>,
 }
 
+/// Jobs still queued when the service shut down, re-queued on the next start.
+const PENDING_FILE: &str = "pending.json";
+
+static CONTENT_WRITER: Mutex<()> = Mutex::new(());
+
+/// The service's turn at the content index writer. A worker batch holds it for the life of
+/// the worker process, and the service's own deletes wait for it, so the two never contend
+/// for Tantivy's writer lock.
+pub fn content_writer_lease() -> MutexGuard<'static, ()> {
+    CONTENT_WRITER.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Feed a finished batch's extraction samples into the service metrics and drop the file.
+fn ingest_extract_stats(job_file: &Path) -> usize {
+    let path = stats_path(job_file);
+    let Ok(bytes) = std::fs::read(&path) else {
+        return 0;
+    };
+    let _ = std::fs::remove_file(&path);
+    match serde_json::from_slice::<Vec<ExtractSample>>(&bytes) {
+        Ok(samples) => {
+            for s in &samples {
+                crate::metrics::record_extraction_global(
+                    s.kind,
+                    Duration::from_secs_f64(s.duration_ms.max(0.0) / 1000.0),
+                    s.bytes,
+                );
+            }
+            samples.len()
+        }
+        Err(e) => {
+            warn!(
+                "ignoring malformed extraction stats {}: {e}",
+                path.display()
+            );
+            0
+        }
+    }
+}
+
 pub struct JobDispatcher {
     worker_path: PathBuf,
     jobs_dir: PathBuf,
     index_dir: PathBuf,
+    max_retries: u32,
+    doc_type_analyzers: bool,
+    path_segments: bool,
+    content_stemming: ContentStemming,
+    content_stopwords: bool,
+    content_max_token_len: usize,
+    extract_timeout_ms: u64,
+    /// `extract.ocr_max_pages` when `extract.ocr_enabled`, else 0 (no OCR).
+    ocr_max_pages: u64,
+    /// `extract.allowed_exts` / `extract.denied_exts`, comma-joined for the worker.
+    allowed_exts: String,
+    denied_exts: String,
 }
 
 impl JobDispatcher {
@@ -50,7 +124,168 @@ impl JobDispatcher {
             worker_path,
             jobs_dir: PathBuf::from(&cfg.paths.jobs_dir),
             index_dir: PathBuf::from(&cfg.paths.content_index),
+            max_retries: cfg.scheduler.max_job_retries,
+            doc_type_analyzers: cfg.features.doc_type_analyzers,
+            path_segments: cfg.features.path_segments,
+            content_stemming: cfg.search.content_stemming,
+            content_stopwords: cfg.search.content_stopwords,
+            content_max_token_len: cfg.search.content_max_token_len,
+            extract_timeout_ms: cfg.extract.timeout_secs.saturating_mul(1000),
+            ocr_max_pages: if cfg.extract.ocr_enabled {
+                cfg.extract.ocr_max_pages
+            } else {
+                0
+            },
+            allowed_exts: cfg.extract.allowed_exts.join(","),
+            denied_exts: cfg.extract.denied_exts.join(","),
+        }
+    }
+
+    /// Take the jobs the worker marked for retry, leaving dead-lettered ones in place.
+    pub fn take_retries(&self) -> Result<Vec<JobSpec>> {
+        let path = self.jobs_dir.join(FAILED_FILE);
+        let bytes = match std::fs::read(&path) {
+            Ok(b) => b,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).with_context(|| format!("read {}", path.display())),
+        };
+        let mut failed: FailedJobs<JobSpec> =
+            serde_json::from_slice(&bytes).with_context(|| format!("parse {}", path.display()))?;
+        if failed.retry.is_empty() {
+            return Ok(Vec::new());
+        }
+        let retries = std::mem::take(&mut failed.retry);
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&failed)?)?;
+        std::fs::rename(&tmp, &path)?;
+
+        for f in &retries {
+            info!(
+                "requeueing {:?} (attempt {}): {}",
+                f.job.path,
+                f.job.attempts + 1,
+                f.error
+            );
+        }
+        Ok(retries.into_iter().map(|f| f.job).collect())
+    }
+
+    /// Hand the uncommitted tail of the batch in `job_file` to the retry list, as if each of
+    /// those jobs had failed transiently with `error`, and remove the batch's files. The bumped
+    /// attempt counts dead-letter jobs that keep killing their worker. Returns how many jobs
+    /// were requeued.
+    pub fn requeue_batch(&self, job_file: &Path, error: &str) -> Result<usize> {
+        let bytes =
+            std::fs::read(job_file).with_context(|| format!("read {}", job_file.display()))?;
+        let batch: JobBatch = serde_json::from_slice(&bytes)
+            .with_context(|| format!("parse {}", job_file.display()))?;
+        let marker = progress_path(job_file);
+        let committed = std::fs::read(&marker)
+            .ok()
+            .and_then(|b| serde_json::from_slice::<BatchMarker>(&b).ok())
+            .unwrap_or_default()
+            .committed;
+
+        let remaining: Vec<JobSpec> = batch.jobs.into_iter().skip(committed).collect();
+        let requeued = remaining.len();
+        if requeued > 0 {
+            let path = self.jobs_dir.join(FAILED_FILE);
+            let mut failed: FailedJobs<JobSpec> = match std::fs::read(&path) {
+                Ok(b) => serde_json::from_slice(&b)
+                    .with_context(|| format!("parse {}", path.display()))?,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => FailedJobs::default(),
+                Err(e) => return Err(e).with_context(|| format!("read {}", path.display())),
+            };
+            for job in remaining {
+                failed.record(
+                    job,
+                    error.to_string(),
+                    FailureKind::Transient,
+                    self.max_retries,
+                );
+            }
+            let tmp = path.with_extension("json.tmp");
+            std::fs::write(&tmp, serde_json::to_vec_pretty(&failed)?)?;
+            std::fs::rename(&tmp, &path)?;
+        }
+        warn!(
+            "requeueing {requeued} uncommitted jobs of {} (after {committed} committed): {error}",
+            job_file.display()
+        );
+
+        for path in [job_file.to_path_buf(), marker, stats_path(job_file)] {
+            match std::fs::remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    warn!("failed to remove {}: {e}", path.display());
+                }
+                _ => {}
+            }
+        }
+        Ok(requeued)
+    }
+
+    /// Requeue batches a previous run left behind (the service stopped or crashed while their
+    /// worker ran), then take them off the retry list like any other retries.
+    pub fn take_interrupted(&self) -> Result<Vec<JobSpec>> {
+        let entries = match std::fs::read_dir(&self.jobs_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("read {}", self.jobs_dir.display()));
+            }
+        };
+        for entry in entries {
+            let path = entry?.path();
+            let is_batch = path.extension().is_some_and(|e| e == "json")
+                && path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with("job_"));
+            if is_batch
+                && let Err(e) = self.requeue_batch(&path, "batch interrupted by a service restart")
+            {
+                warn!("failed to requeue {}: {e:?}", path.display());
+            }
+        }
+        self.take_retries()
+    }
+
+    /// Write jobs that never started to `pending.json`; an empty queue removes the file.
+    pub fn save_pending(&self, jobs: Vec<JobSpec>) -> Result<()> {
+        let path = self.jobs_dir.join(PENDING_FILE);
+        if jobs.is_empty() {
+            return match std::fs::remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    Err(e).with_context(|| format!("remove {}", path.display()))
+                }
+                _ => Ok(()),
+            };
         }
+        std::fs::create_dir_all(&self.jobs_dir)?;
+        let batch = JobBatch { version: 1, jobs };
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&batch)?)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// Jobs left in `pending.json` by the previous shutdown; the file is consumed.
+    pub fn take_pending(&self) -> Result<Vec<JobSpec>> {
+        let path = self.jobs_dir.join(PENDING_FILE);
+        let bytes = match std::fs::read(&path) {
+            Ok(b) => b,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).with_context(|| format!("read {}", path.display())),
+        };
+        std::fs::remove_file(&path).with_context(|| format!("remove {}", path.display()))?;
+        let batch: JobBatch =
+            serde_json::from_slice(&bytes).with_context(|| format!("parse {}", path.display()))?;
+        Ok(batch.jobs)
+    }
+
+    #[cfg(test)]
+    pub(crate) fn set_worker_path(&mut self, path: PathBuf) {
+        self.worker_path = path;
     }
 
     pub async fn spawn_batch(&self, jobs: Vec<JobSpec>) -> Result<()> {
@@ -84,8 +319,28 @@ impl JobDispatcher {
         let job_file_for_spawn = job_file_path.clone();
         let index_dir_for_spawn = self.index_dir.clone();
         let index_dir_for_log = index_dir_for_spawn.clone();
+        let max_retries = self.max_retries.to_string();
+        let doc_type_analyzers = self.doc_type_analyzers.then_some("--doc-type-analyzers");
+        let path_segments = self.path_segments.then_some("--path-segments");
+        let content_stemming =
+            (self.content_stemming == ContentStemming::En).then_some(["--content-stemming", "en"]);
+        let content_stopwords = self.content_stopwords.then_some("--content-stopwords");
+        let content_max_token_len = self.content_max_token_len.to_string();
+        let extract_timeout_ms = self.extract_timeout_ms.to_string();
+        let ocr_max_pages = (self.ocr_max_pages > 0).then(|| {
+            [
+                "--ocr-max-pages".to_string(),
+                self.ocr_max_pages.to_string(),
+            ]
+        });
+        let allowed_exts = (!self.allowed_exts.is_empty())
+            .then(|| ["--allowed-exts".to_string(), self.allowed_exts.clone()]);
+        let denied_exts = (!self.denied_exts.is_empty())
+            .then(|| ["--denied-exts".to_string(), self.denied_exts.clone()]);
 
         let status = task::spawn_blocking(move || -> anyhow::Result<std::process::ExitStatus> {
+            // Released when the worker exits, on any path out of this closure.
+            let _lease = content_writer_lease();
             if !worker_path.exists() {
                 error!("worker binary missing at {}", worker_path.display());
                 anyhow::bail!("worker binary missing at {}", worker_path.display());
@@ -104,6 +359,19 @@ impl JobDispatcher {
                     .arg(&job_file_for_spawn)
                     .arg("--index-dir")
                     .arg(&index_dir_for_spawn)
+                    .arg("--max-retries")
+                    .arg(&max_retries)
+                    .arg("--extract-timeout-ms")
+                    .arg(&extract_timeout_ms)
+                    .args(doc_type_analyzers)
+                    .args(path_segments)
+                    .args(content_stemming.iter().flatten())
+                    .args(content_stopwords)
+                    .arg("--content-max-token-len")
+                    .arg(&content_max_token_len)
+                    .args(ocr_max_pages.iter().flatten())
+                    .args(allowed_exts.iter().flatten())
+                    .args(denied_exts.iter().flatten())
                     .creation_flags(CREATE_NO_WINDOW)
                     .spawn()
                     .context("failed to spawn worker process")?;
@@ -124,14 +392,36 @@ impl JobDispatcher {
                     .arg("--job-file")
                     .arg(&job_file_for_spawn)
                     .arg("--index-dir")
-                    .arg(&index_dir)
+                    .arg(&index_dir_for_spawn)
+                    .arg("--max-retries")
+                    .arg(&max_retries)
+                    .arg("--extract-timeout-ms")
+                    .arg(&extract_timeout_ms)
+                    .args(doc_type_analyzers)
+                    .args(path_segments)
+                    .args(content_stemming.iter().flatten())
+                    .args(content_stopwords)
+                    .arg("--content-max-token-len")
+                    .arg(&content_max_token_len)
+                    .args(ocr_max_pages.iter().flatten())
+                    .args(allowed_exts.iter().flatten())
+                    .args(denied_exts.iter().flatten())
                     .spawn()
                     .context("failed to spawn worker process")?
                     .wait()?;
                 Ok(status)
             }
         })
-        .await??;
+        .await?;
+        let status = match status {
+            Ok(status) => status,
+            Err(e) => {
+                self.requeue_batch(&job_file_path, &format!("{e:#}"))?;
+                return Err(e);
+            }
+        };
+
+        ingest_extract_stats(&job_file_path);
 
         if status.success() {
             info!(
@@ -147,6 +437,7 @@ impl JobDispatcher {
                 job_file_path.display(),
                 index_dir_for_log.display()
             );
+            self.requeue_batch(&job_file_path, &format!("worker exited with {status}"))?;
         }
 
         Ok(())
@@ -184,3 +475,150 @@ fn attach_background_job_object(handle: std::os::windows::io::BorrowedHandle<'_>
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_stats_are_consumed() {
+        let dir = tempfile::tempdir().unwrap();
+        let job_file = dir.path().join("job_1.json");
+        assert_eq!(ingest_extract_stats(&job_file), 0);
+
+        let stats = r#"[{"kind": "pdf", "duration_ms": 120.5, "bytes": 2048},
+                        {"kind": "text", "duration_ms": 0.4, "bytes": 12}]"#;
+        std::fs::write(stats_path(&job_file), stats).unwrap();
+        assert_eq!(ingest_extract_stats(&job_file), 2);
+        assert!(!stats_path(&job_file).exists());
+    }
+
+    #[test]
+    fn take_retries_drains_retry_list_and_keeps_dead_letters() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cfg = AppConfig::default();
+        cfg.paths.jobs_dir = dir.path().to_string_lossy().into_owned();
+        let dispatcher = JobDispatcher::new(&cfg);
+        assert!(dispatcher.take_retries().unwrap().is_empty());
+
+        let failed = r#"{
+            "version": 1,
+            "retry": [{"job": {"volume_id": 1, "file_id": 2, "path": "C:/locked.docx",
+                               "priority": "content", "attempts": 1},
+                       "error": "sharing violation"}],
+            "dead_letter": [{"job": {"volume_id": 1, "file_id": 3, "path": "C:/bad.pdf",
+                                     "attempts": 4},
+                             "error": "corrupt"}]
+        }"#;
+        std::fs::write(dir.path().join(FAILED_FILE), failed).unwrap();
+
+        let retries = dispatcher.take_retries().unwrap();
+        assert_eq!(retries.len(), 1);
+        assert_eq!(retries[0].file_id, 2);
+        assert_eq!(retries[0].attempts, 1);
+        assert_eq!(retries[0].priority, Some(JobCategory::Content));
+        assert!(dispatcher.take_retries().unwrap().is_empty());
+
+        let left: FailedJobs<JobSpec> =
+            serde_json::from_slice(&std::fs::read(dir.path().join(FAILED_FILE)).unwrap()).unwrap();
+        assert_eq!(left.dead_letter.len(), 1);
+        assert_eq!(left.dead_letter[0].job.file_id, 3);
+    }
+
+    fn job(file_id: u64) -> JobSpec {
+        JobSpec {
+            volume_id: 1,
+            file_id,
+            path: PathBuf::from(format!("C:/doc{file_id}.txt")),
+            max_bytes: None,
+            max_chars: None,
+            file_size: 0,
+            priority: None,
+            attempts: 0,
+        }
+    }
+
+    fn ids(jobs: &[JobSpec]) -> Vec<u64> {
+        jobs.iter().map(|j| j.file_id).collect()
+    }
+
+    fn batch_files(dir: &Path) -> usize {
+        std::fs::read_dir(dir)
+            .unwrap()
+            .filter(|e| {
+                let name = e.as_ref().unwrap().file_name();
+                name.to_string_lossy().starts_with("job_")
+            })
+            .count()
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn killed_batch_resumes_after_its_committed_jobs() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let jobs_dir = dir.path().join("jobs");
+        let index_dir = dir.path().join("content");
+        std::fs::create_dir_all(&index_dir).unwrap();
+        let script = |name: &str, body: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+            path
+        };
+        // Commits two jobs, then dies before finishing the batch.
+        let crashing = script(
+            "crash.sh",
+            r#"printf '{"committed":2}' > "${2%.json}.progress"; kill -9 $$"#,
+        );
+        let finishing = script("finish.sh", r#"cp "$2" "$4/committed.json""#);
+
+        let mut cfg = AppConfig::default();
+        cfg.paths.jobs_dir = jobs_dir.to_string_lossy().into_owned();
+        cfg.paths.content_index = index_dir.to_string_lossy().into_owned();
+        let mut dispatcher = JobDispatcher::new(&cfg);
+        dispatcher.set_worker_path(crashing);
+
+        dispatcher
+            .spawn_batch((1..=5).map(job).collect())
+            .await
+            .unwrap();
+        let retries = dispatcher.take_retries().unwrap();
+        assert_eq!(ids(&retries), [3, 4, 5]);
+        assert!(retries.iter().all(|j| j.attempts == 1));
+        assert_eq!(batch_files(&jobs_dir), 0);
+
+        dispatcher.set_worker_path(finishing);
+        dispatcher.spawn_batch(retries).await.unwrap();
+        let committed: JobBatch =
+            serde_json::from_slice(&std::fs::read(index_dir.join("committed.json")).unwrap())
+                .unwrap();
+        assert_eq!(ids(&committed.jobs), [3, 4, 5]);
+        assert_eq!(batch_files(&jobs_dir), 0);
+        assert!(dispatcher.take_retries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn batches_left_by_a_previous_run_are_requeued_from_their_marker() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cfg = AppConfig::default();
+        cfg.paths.jobs_dir = dir.path().to_string_lossy().into_owned();
+        let dispatcher = JobDispatcher::new(&cfg);
+        assert!(dispatcher.take_interrupted().unwrap().is_empty());
+
+        let job_file = dir.path().join("job_left.json");
+        let batch = JobBatch {
+            version: 1,
+            jobs: (1..=4).map(job).collect(),
+        };
+        std::fs::write(&job_file, serde_json::to_vec(&batch).unwrap()).unwrap();
+        std::fs::write(progress_path(&job_file), br#"{"committed":1}"#).unwrap();
+        dispatcher.save_pending(vec![job(9)]).unwrap();
+
+        assert_eq!(ids(&dispatcher.take_interrupted().unwrap()), [2, 3, 4]);
+        assert!(!job_file.exists());
+        assert!(!progress_path(&job_file).exists());
+        assert!(dispatcher.take_interrupted().unwrap().is_empty());
+        assert_eq!(ids(&dispatcher.take_pending().unwrap()), [9]);
+    }
+}
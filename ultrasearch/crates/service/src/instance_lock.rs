@@ -0,0 +1,106 @@
+//! Single-instance guard: one service per state directory.
+//!
+//! Two services on the same data (the installed service plus a `--console` run, or a double
+//! launch) would race for the pipe and open two Tantivy writers on one index. `run_app` takes
+//! an exclusive lock on `<state_dir>/service.lock` before anything else and refuses to start
+//! while another process holds it. The OS releases the lock however the holder exits, so a
+//! leftover file never blocks a restart.
+
+use anyhow::{Context, Result, bail};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::{Read, Write};
+use std::path::Path;
+
+pub const LOCK_FILE: &str = "service.lock";
+
+/// Held for the life of the service; dropping it releases the lock.
+#[derive(Debug)]
+pub struct InstanceLock {
+    _file: File,
+}
+
+impl InstanceLock {
+    /// Lock `<state_dir>/service.lock`, or fail naming the running instance.
+    pub fn acquire(state_dir: &Path) -> Result<Self> {
+        fs::create_dir_all(state_dir)
+            .with_context(|| format!("create state dir {}", state_dir.display()))?;
+        let path = state_dir.join(LOCK_FILE);
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)
+            .with_context(|| format!("open {}", path.display()))?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                // Windows locks are mandatory, so the holder's pid can't always be read.
+                let mut holder = String::new();
+                let pid = match file.read_to_string(&mut holder) {
+                    Ok(_) if !holder.trim().is_empty() => format!(" (pid {})", holder.trim()),
+                    _ => String::new(),
+                };
+                bail!(
+                    "another UltraSearch service is already running{pid}; it holds {}",
+                    path.display()
+                );
+            }
+            Err(TryLockError::Error(e)) => {
+                return Err(e).with_context(|| format!("lock {}", path.display()));
+            }
+        }
+        file.set_len(0)?;
+        write!(file, "{}", std::process::id())?;
+        Ok(Self { _file: file })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bootstrap::{BootstrapOptions, run_app_with_options};
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn second_lock_fails_until_the_first_is_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = InstanceLock::acquire(dir.path()).unwrap();
+        let err = InstanceLock::acquire(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("already running"), "{err}");
+
+        drop(first);
+        InstanceLock::acquire(dir.path()).unwrap();
+    }
+
+    #[test]
+    fn run_app_refuses_to_start_while_the_lock_is_held() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_dir = dir.path().join("data");
+        let mut cfg = core_types::config::AppConfig::default();
+        cfg.app.data_dir = data_dir.to_string_lossy().to_string();
+        cfg.logging.file = data_dir.join("searchd.log").to_string_lossy().to_string();
+        for (path, sub) in [
+            (&mut cfg.paths.meta_index, "meta"),
+            (&mut cfg.paths.content_index, "content"),
+            (&mut cfg.paths.state_dir, "state"),
+            (&mut cfg.paths.jobs_dir, "jobs"),
+        ] {
+            *path = data_dir.join(sub).to_string_lossy().to_string();
+        }
+        cfg.metrics.enabled = false;
+
+        let _running = InstanceLock::acquire(Path::new(&cfg.paths.state_dir)).unwrap();
+        let (_tx, rx) = mpsc::channel(1);
+        let opts = BootstrapOptions {
+            skip_initial_ingest: true,
+            ..BootstrapOptions::default()
+        };
+        let err = run_app_with_options(&cfg, rx, opts).unwrap_err();
+        assert!(err.to_string().contains("already running"), "{err}");
+        assert!(
+            !Path::new(&cfg.paths.meta_index).exists(),
+            "the second instance must stop before touching the indexes"
+        );
+    }
+}
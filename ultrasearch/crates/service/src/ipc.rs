@@ -1,39 +1,45 @@
-#![cfg(target_os = "windows")]
+#![cfg(any(target_os = "windows", all(unix, feature = "uds-transport")))]
+//! IPC server: a named pipe on Windows, or a Unix domain socket with the `uds-transport`
+//! feature. Both carry the same length-prefixed bincode frames and share one dispatcher.
 
-use std::env;
+use std::sync::Arc;
 use std::time::Instant;
 
+use crate::caller::{CallerAccess, with_caller};
 use crate::metrics::{global_metrics_snapshot, record_ipc_request};
-use crate::search_handler::search;
-use crate::status::make_status_response;
+use crate::search_handler::{children, complete, get_doc, plan, preview, scan, search};
+use crate::status::{make_status_response, served_by};
 use crate::status_provider::status_snapshot;
+#[cfg(windows)]
+use crate::windows::pipe_security::create_secure_pipe;
 use anyhow::Result;
 use ipc::{
-    MetricsSnapshot, ReloadConfigRequest, ReloadConfigResponse, RescanRequest, RescanResponse,
-    SearchRequest, StatusRequest, framing,
+    ErrorCode, ErrorResponse, MetricsSnapshot, ReloadConfigResponse, Request, RescanResponse,
+    framing,
 };
 #[cfg(test)]
-use ipc::{SearchResponse, StatusResponse};
-use std::io::Cursor;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::windows::named_pipe::NamedPipeServer;
+use ipc::{SearchRequest, SearchResponse, StatusRequest, StatusResponse};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 use tokio::task::JoinHandle;
 use uuid::Uuid;
 
+#[cfg(windows)]
 const DEFAULT_PIPE_NAME: &str = r#"\\.\pipe\ultrasearch"#;
-const MAX_MESSAGE_BYTES: usize = 256 * 1024;
+/// Socket path used when `BootstrapOptions::pipe_name` is not set; matches `PipeClient`.
+#[cfg(all(unix, feature = "uds-transport"))]
+pub const DEFAULT_SOCKET_PATH: &str = "/tmp/ultrasearch.sock";
 
 /// Start a Tokio named-pipe server that spawns a task per connection.
+#[cfg(windows)]
 pub async fn start_pipe_server(pipe_name: Option<&str>) -> Result<JoinHandle<()>> {
     let name = pipe_name.unwrap_or(DEFAULT_PIPE_NAME).to_string();
 
     let handle = tokio::spawn(async move {
         let mut first = true;
         loop {
-            // Use raw Win32 API to create pipe with Security Descriptor
-            // SDDL: D:(A;;GA;;;SY)(A;;GA;;;BA)(A;;GRGW;;;AU)
-            // SY=System, BA=Admins, AU=Authenticated Users (Read/Write)
-            let server = match unsafe { create_secure_pipe(&name, first) } {
+            // Read per instance, so after a reload `ipc.pipe_access` applies to new instances.
+            let access = core_types::config::get_current_config().ipc.pipe_access;
+            let server = match create_secure_pipe(&name, first, access) {
                 Ok(s) => s,
                 Err(e) => {
                     // Likely another instance owns the pipe. Back off and retry, but escalate to warning after a few tries.
@@ -50,8 +56,9 @@ pub async fn start_pipe_server(pipe_name: Option<&str>) -> Result<JoinHandle<()>
                 continue;
             }
 
+            let caller: Arc<dyn CallerAccess> = Arc::new(crate::caller::PipeClient::new(&server));
             tokio::spawn(async move {
-                if let Err(e) = handle_connection(server).await {
+                if let Err(e) = handle_connection(server, Some(caller)).await {
                     tracing::warn!("pipe connection error: {e:?}");
                 }
             });
@@ -61,117 +68,91 @@ pub async fn start_pipe_server(pipe_name: Option<&str>) -> Result<JoinHandle<()>
     Ok(handle)
 }
 
-unsafe fn create_secure_pipe(name: &str, first: bool) -> Result<NamedPipeServer> {
-    use std::ffi::OsStr;
-    use std::os::windows::ffi::OsStrExt;
-    use windows::Win32::Foundation::{HLOCAL, INVALID_HANDLE_VALUE, LocalFree};
-    use windows::Win32::Security::{
-        Authorization::ConvertStringSecurityDescriptorToSecurityDescriptorW, PSECURITY_DESCRIPTOR,
-        SECURITY_ATTRIBUTES,
-    };
-    use windows::Win32::Storage::FileSystem::{
-        FILE_FLAG_FIRST_PIPE_INSTANCE, FILE_FLAG_OVERLAPPED, FILE_FLAGS_AND_ATTRIBUTES,
-        PIPE_ACCESS_DUPLEX,
-    };
-    use windows::Win32::System::Pipes::{
-        CreateNamedPipeW, NAMED_PIPE_MODE, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS,
-        PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
-    };
-    use windows::core::PCWSTR;
-
-    // D:(A;;GA;;;SY)(A;;GA;;;BA)(A;;GRGW;;;AU)
-    let sddl = "D:(A;;GA;;;SY)(A;;GA;;;BA)(A;;GRGW;;;AU)\0";
-    let sddl_wide: Vec<u16> = sddl.encode_utf16().collect();
-
-    let mut sd: PSECURITY_DESCRIPTOR = PSECURITY_DESCRIPTOR::default();
-
-    unsafe {
-        ConvertStringSecurityDescriptorToSecurityDescriptorW(
-            PCWSTR(sddl_wide.as_ptr()),
-            1, // SDDL_REVISION_1
-            &mut sd,
-            None,
-        )?;
+/// Listen on the Unix domain socket at `path` (replacing a stale socket file) and spawn a
+/// task per connection.
+#[cfg(all(unix, feature = "uds-transport"))]
+pub async fn start_uds_server(path: &std::path::Path) -> Result<JoinHandle<()>> {
+    use anyhow::Context;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
     }
-
-    // Ensure we free the SD
-    struct SdGuard(PSECURITY_DESCRIPTOR);
-    impl Drop for SdGuard {
-        fn drop(&mut self) {
-            // sd.0 is *mut c_void. HLOCAL wraps *mut c_void.
-            unsafe {
-                let _ = LocalFree(HLOCAL(self.0.0));
-            }
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(e).with_context(|| format!("remove stale socket {}", path.display()));
         }
+        _ => {}
     }
-    let _guard = SdGuard(sd);
-
-    let sa = SECURITY_ATTRIBUTES {
-        nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
-        lpSecurityDescriptor: sd.0 as *mut _,
-        bInheritHandle: windows::Win32::Foundation::FALSE,
-    };
-
-    let mut name_wide: Vec<u16> = OsStr::new(name).encode_wide().collect();
-    name_wide.push(0);
-
-    let mut open_mode = PIPE_ACCESS_DUPLEX.0 | FILE_FLAG_OVERLAPPED.0;
-    if first {
-        open_mode |= FILE_FLAG_FIRST_PIPE_INSTANCE.0;
-    }
+    let listener =
+        tokio::net::UnixListener::bind(path).with_context(|| format!("bind {}", path.display()))?;
 
-    let handle = unsafe {
-        CreateNamedPipeW(
-            PCWSTR(name_wide.as_ptr()),
-            FILE_FLAGS_AND_ATTRIBUTES(open_mode),
-            NAMED_PIPE_MODE(
-                PIPE_TYPE_BYTE.0
-                    | PIPE_READMODE_BYTE.0
-                    | PIPE_WAIT.0
-                    | PIPE_REJECT_REMOTE_CLIENTS.0,
-            ),
-            PIPE_UNLIMITED_INSTANCES,
-            65536,
-            65536,
-            0,
-            Some(&sa),
-        )
-    };
-
-    if handle == INVALID_HANDLE_VALUE {
-        return Err(anyhow::Error::from(windows::core::Error::from_win32()));
-    }
+    let handle = tokio::spawn(async move {
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    tracing::error!("unix socket accept failed: {}", e);
+                    continue;
+                }
+            };
+            // Without the peer's credentials, file contents are withheld from it.
+            let caller: Arc<dyn CallerAccess> = match stream.peer_cred() {
+                Ok(cred) => Arc::new(crate::caller::PeerCred {
+                    uid: cred.uid(),
+                    gid: cred.gid(),
+                }),
+                Err(e) => {
+                    tracing::warn!("unix socket peer credentials unavailable: {e}");
+                    Arc::new(crate::caller::NoAccess)
+                }
+            };
+            tokio::spawn(async move {
+                if let Err(e) = handle_connection(stream, Some(caller)).await {
+                    tracing::warn!("socket connection error: {e:?}");
+                }
+            });
+        }
+    });
 
-    // Wrap in Tokio
-    let server = unsafe { NamedPipeServer::from_raw_handle(handle.0 as *mut _) }?;
-    Ok(server)
+    Ok(handle)
 }
 
-async fn handle_connection(mut conn: NamedPipeServer) -> Result<()> {
+/// Serve requests on `conn` until it closes. File contents go back only where `caller` may
+/// read the file; `None` serves the service's own, unchecked.
+async fn handle_connection<S>(mut conn: S, caller: Option<Arc<dyn CallerAccess>>) -> Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    // Read per connection so a reloaded limit applies to the next client.
+    let max_frame = core_types::config::get_current_config().ipc.max_frame_bytes;
     loop {
-        // decode frame
-        let mut len_prefix = [0u8; 4];
-        // If read returns 0, client disconnected (or EOF).
-        if conn.read_exact(&mut len_prefix).await.is_err() {
-            break;
-        }
-        let frame_len = u32::from_le_bytes(len_prefix) as usize;
-        if frame_len == 0 || frame_len > MAX_MESSAGE_BYTES {
-            tracing::warn!("invalid frame size {frame_len}");
-            break;
-        }
-        let mut buf = vec![0u8; frame_len];
-        conn.read_exact(&mut buf).await?;
-
-        // framing::decode_frame expects [header + body].
-        // We have read them separately.
-        // We can reconstruct or just parse the body if we trust it.
-        // Since we are the server, we trust our read logic.
-        // Dispatch expects the RAW payload (no frame).
-        // But wait, `buf` IS the payload.
-        // framing::decode_frame also checks length.
+        // The length prefix is checked before the body is allocated; a bad one ends the
+        // connection, since the stream can't be resynchronized after it.
+        let buf = match framing::read_frame(&mut conn, max_frame).await {
+            Ok(Some(buf)) => buf,
+            Ok(None) => break,
+            Err(framing::FrameError::Io(e)) => return Err(e.into()),
+            Err(e) => {
+                tracing::warn!("closing IPC connection: {e}");
+                let err = ErrorResponse::new(Uuid::nil(), ErrorCode::BadRequest, e.to_string());
+                let framed = framing::encode_frame(&err.to_payload()).unwrap_or_default();
+                let _ = conn.write_all(&framed).await;
+                break;
+            }
+        };
 
-        let response = dispatch(&buf);
+        // Searches are CPU-bound; keep them off the runtime so other pipes stay responsive.
+        let id = ipc::request_id(&buf).unwrap_or_default();
+        let caller = caller.clone();
+        let response =
+            match tokio::task::spawn_blocking(move || with_caller(caller, || dispatch(&buf))).await
+            {
+                Ok(response) => response,
+                Err(e) => {
+                    tracing::error!("IPC handler failed: {e}");
+                    ErrorResponse::new(id, ErrorCode::Internal, "the service failed to answer")
+                        .to_payload()
+                }
+            };
         let framed = framing::encode_frame(&response).unwrap_or_default();
         // framed includes length prefix.
         conn.write_all(&framed).await?;
@@ -179,15 +160,16 @@ async fn handle_connection(mut conn: NamedPipeServer) -> Result<()> {
     Ok(())
 }
 
-fn dispatch(payload: &[u8]) -> Vec<u8> {
-    fn deserialize_exact<T: serde::de::DeserializeOwned>(payload: &[u8]) -> Option<T> {
-        let mut cursor = Cursor::new(payload);
-        match bincode::deserialize_from::<_, T>(&mut cursor) {
-            Ok(v) if cursor.position() as usize == payload.len() => Some(v),
-            _ => None,
-        }
-    }
+/// `resp` encoded, or an `Internal` error for `id` if it can't be.
+fn encode_response<T: serde::Serialize>(id: Uuid, resp: &T) -> Vec<u8> {
+    bincode::serialize(resp).unwrap_or_else(|e| {
+        tracing::error!("failed to encode IPC response: {e}");
+        ErrorResponse::new(id, ErrorCode::Internal, "the response could not be encoded")
+            .to_payload()
+    })
+}
 
+fn dispatch(payload: &[u8]) -> Vec<u8> {
     // Fast-path: ping echo when payload is prefixed with "PING" + UUID.
     if payload.len() >= 20
         && payload.starts_with(b"PING")
@@ -196,125 +178,182 @@ fn dispatch(payload: &[u8]) -> Vec<u8> {
         return id.as_bytes().to_vec();
     }
 
-    // Try StatusRequest first.
-    if let Some(req) = deserialize_exact::<StatusRequest>(payload) {
-        let started = Instant::now();
-        let snap = status_snapshot();
-        let empty_metrics =
-            snap.metrics.or(
-                global_metrics_snapshot(Some(0), Some(0), Some(0), Some(0)).or(Some(
-                    MetricsSnapshot {
-                        search_latency_ms_p50: None,
-                        search_latency_ms_p95: None,
-                        worker_cpu_pct: None,
-                        worker_mem_bytes: None,
-                        queue_depth: Some(0),
-                        active_workers: Some(0),
-                        content_enqueued: Some(0),
-                        content_dropped: Some(0),
-                    },
-                )),
+    let req = match bincode::deserialize::<Request>(payload) {
+        Ok(req) => req,
+        Err(_) => return reject(payload),
+    };
+    let started = Instant::now();
+    let encoded = match req {
+        Request::Status(req) => {
+            let snap = status_snapshot();
+            let empty_metrics =
+                snap.metrics.or(
+                    global_metrics_snapshot(Some(0), Some(0), Some(0), Some(0)).or(Some(
+                        MetricsSnapshot {
+                            search_latency_ms_p50: None,
+                            search_latency_ms_p95: None,
+                            worker_cpu_pct: None,
+                            worker_mem_bytes: None,
+                            queue_depth: Some(0),
+                            active_workers: Some(0),
+                            content_enqueued: Some(0),
+                            content_dropped: Some(0),
+                            extract_p50_ms: None,
+                            extract_p95_ms: None,
+                            extract_by_kind: Vec::new(),
+                        },
+                    )),
+                );
+            let resp = make_status_response(
+                req.id,
+                snap.volumes,
+                snap.scheduler_state,
+                empty_metrics,
+                snap.last_index_commit_ts,
+                snap.content_jobs_total,
+                snap.content_jobs_remaining,
+                snap.content_bytes_total,
+                snap.content_bytes_remaining,
+                snap.readiness,
+                snap.queue_depth_by_category,
             );
-        let resp = make_status_response(
-            req.id,
-            snap.volumes,
-            snap.scheduler_state,
-            empty_metrics,
-            snap.last_index_commit_ts,
-            snap.content_jobs_total,
-            snap.content_jobs_remaining,
-            snap.content_bytes_total,
-            snap.content_bytes_remaining,
-        );
-        let encoded = bincode::serialize(&resp).unwrap_or_default();
-        record_ipc_request(started.elapsed());
-        return encoded;
-    }
-
-    // Handle ReloadConfigRequest
-    if let Some(req) = deserialize_exact::<ReloadConfigRequest>(payload) {
-        let started = Instant::now();
-        let result = core_types::config::reload_config(None);
-        let (success, message) = match result {
-            Ok(_) => (true, None),
-            Err(e) => (false, Some(e.to_string())),
-        };
-        let resp = ReloadConfigResponse {
-            id: req.id,
-            success,
-            message,
-        };
-        let encoded = bincode::serialize(&resp).unwrap_or_default();
-        record_ipc_request(started.elapsed());
-        return encoded;
-    }
-
-    // Handle RescanRequest
-    if let Some(req) = deserialize_exact::<RescanRequest>(payload) {
-        let started = Instant::now();
-        let cfg = core_types::config::get_current_config();
-        let res = crate::scanner::scan_volumes(&cfg).map(|jobs| {
-            let mut submitted = 0usize;
-            for job in jobs {
-                if crate::scheduler_runtime::enqueue_content_job(job) {
-                    submitted += 1;
+            encode_response(resp.id, &resp)
+        }
+        Request::ReloadConfig(req) => {
+            let (success, message) = match crate::config_reload::reload_and_apply(None) {
+                Ok(summary) => (true, Some(summary)),
+                Err(e) => (false, Some(e.to_string())),
+            };
+            let resp = ReloadConfigResponse {
+                id: req.id,
+                success,
+                message,
+            };
+            encode_response(resp.id, &resp)
+        }
+        Request::Rescan(req) => {
+            let cfg = core_types::config::get_current_config();
+            let res = crate::scanner::scan_volumes(&cfg).map(|jobs| {
+                let mut submitted = 0usize;
+                for job in jobs {
+                    if crate::scheduler_runtime::enqueue_content_job(job) {
+                        submitted += 1;
+                    }
                 }
-            }
-            submitted
-        });
-
-        let (success, message) = match res {
-            Ok(count) => (true, Some(format!("Submitted {} jobs", count))),
-            Err(e) => (false, Some(e.to_string())),
-        };
+                submitted
+            });
 
-        let resp = RescanResponse {
-            id: req.id,
-            success,
-            message,
-        };
-        let encoded = bincode::serialize(&resp).unwrap_or_default();
-        record_ipc_request(started.elapsed());
-        return encoded;
-    }
+            let (success, message) = match res {
+                Ok(count) => (true, Some(format!("Submitted {} jobs", count))),
+                Err(e) => (false, Some(e.to_string())),
+            };
 
-    // Fallback: dispatch SearchRequest.
-    if let Some(req) = deserialize_exact::<SearchRequest>(payload) {
-        let start = Instant::now();
-        let req_clone = req.clone();
-        let mut resp = search(req);
-        // Ensure the echoed id always matches the request for protocol stability.
-        // search(req) should propagate id, but we enforce it defensively.
-        // Use the id already in resp if set, otherwise fallback to request id.
-        if resp.id.is_nil() {
-            resp.id = req_clone.id;
+            let resp = RescanResponse {
+                id: req.id,
+                success,
+                message,
+            };
+            encode_response(resp.id, &resp)
         }
-        let elapsed = start.elapsed();
-        let took = elapsed.as_millis().min(u32::MAX as u128) as u32;
-        if resp.took_ms == 0 {
-            resp.took_ms = took;
+        // Acks once the rebuild thread has started.
+        Request::Reindex(req) => {
+            let resp = crate::reindex::start_reindex(req);
+            encode_response(resp.id, &resp)
         }
-        if resp.served_by.is_none() {
-            resp.served_by = Some(host_label());
+        // A fresh discovery checked against the live config.
+        Request::ListVolumes(req) => {
+            let resp = crate::scanner::list_volumes(req);
+            encode_response(resp.id, &resp)
         }
-        let encoded = bincode::serialize(&resp).unwrap_or_default();
-        record_ipc_request(elapsed);
-        return encoded;
-    }
-    // If payload decodes as a UUID prefix, echo it back.
-    Vec::new()
+        Request::GetDoc(req) => {
+            let resp = get_doc(req);
+            encode_response(resp.id, &resp)
+        }
+        // Runs the planner heuristics only.
+        Request::Plan(req) => {
+            let resp = plan(req);
+            encode_response(resp.id, &resp)
+        }
+        Request::Preview(req) => {
+            let resp = preview(req);
+            encode_response(resp.id, &resp)
+        }
+        Request::Children(req) => {
+            let resp = children(req);
+            encode_response(resp.id, &resp)
+        }
+        // A dictionary scan, no query is executed.
+        Request::Complete(req) => {
+            let resp = complete(req);
+            encode_response(resp.id, &resp)
+        }
+        Request::Scan(req) => {
+            let resp = scan(req);
+            encode_response(resp.id, &resp)
+        }
+        // A query that can't be built is answered with an error, not an empty page.
+        Request::Search(req) => {
+            let req_id = req.id;
+            match search(req) {
+                Ok(mut resp) => {
+                    // Ensure the echoed id always matches the request for protocol stability.
+                    // search(req) should propagate id, but we enforce it defensively.
+                    // Use the id already in resp if set, otherwise fallback to request id.
+                    if resp.id.is_nil() {
+                        resp.id = req_id;
+                    }
+                    let took = started.elapsed().as_millis().min(u32::MAX as u128) as u32;
+                    if resp.took_ms == 0 {
+                        resp.took_ms = took;
+                    }
+                    if resp.served_by.is_none() {
+                        resp.served_by = Some(served_by());
+                    }
+                    encode_response(resp.id, &resp)
+                }
+                Err(err) => {
+                    let err =
+                        ErrorResponse::new(req_id, ErrorCode::InvalidQuery, format!("{err:#}"));
+                    tracing::warn!("rejected search: {}", err.message);
+                    err.to_payload()
+                }
+            }
+        }
+    };
+    record_ipc_request(started.elapsed());
+    encoded
 }
 
-fn host_label() -> String {
-    env::var("COMPUTERNAME")
-        .or_else(|_| env::var("HOSTNAME"))
-        .unwrap_or_else(|_| "service".into())
+/// The error for a payload that decodes as no request. One that carries a request id under a
+/// kind tag this build does not know is a newer request kind; anything else is malformed.
+fn reject(payload: &[u8]) -> Vec<u8> {
+    let err = match ipc::request_id(payload) {
+        Some(id) if ipc::is_known_kind(payload) => {
+            ErrorResponse::new(id, ErrorCode::BadRequest, "malformed request body")
+        }
+        Some(id) => ErrorResponse::new(
+            id,
+            ErrorCode::Unsupported,
+            "unknown request kind; the service may be older than this client",
+        ),
+        None => ErrorResponse::new(
+            Uuid::nil(),
+            ErrorCode::BadRequest,
+            "payload is not an UltraSearch request",
+        ),
+    };
+    tracing::warn!("rejected IPC request: {}", err.message);
+    err.to_payload()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn payload(req: impl Into<Request>) -> Vec<u8> {
+        bincode::serialize(&req.into()).unwrap()
+    }
+
     #[tokio::test]
     async fn echoes_uuid_prefix() {
         let id = Uuid::new_v4();
@@ -327,7 +366,7 @@ mod tests {
     #[test]
     fn status_request_roundtrip() {
         let req = StatusRequest { id: Uuid::new_v4() };
-        let resp_bytes = dispatch(&bincode::serialize(&req).unwrap());
+        let resp_bytes = dispatch(&payload(req.clone()));
         let resp: StatusResponse = bincode::deserialize(&resp_bytes).unwrap();
         assert_eq!(resp.id, req.id);
         assert!(resp.volumes.is_empty());
@@ -343,16 +382,85 @@ mod tests {
                 field: None,
                 value: "x".into(),
                 modifier: ipc::TermModifier::Term,
+                boost: None,
             }),
             limit: 1,
             mode: ipc::SearchMode::Auto,
             timeout: None,
             offset: 0,
+            cursor: None,
+            explain: false,
+            case_sensitive: false,
+            count_only: false,
+            fields: None,
+            include_system: false,
+            sort: None,
+            suggest: false,
+            group_by: None,
+            normalize_scores: false,
         };
-        let resp_bytes = dispatch(&bincode::serialize(&req).unwrap());
+        let resp_bytes = dispatch(&payload(req.clone()));
         let resp: SearchResponse = bincode::deserialize(&resp_bytes).unwrap();
         assert_eq!(resp.id, req.id);
         assert!(resp.hits.is_empty());
         assert_eq!(resp.total, 0);
     }
+    #[test]
+    fn unknown_request_kind_is_unsupported() {
+        // A kind tag past the known ones, as a newer client might send.
+        let id = Uuid::new_v4();
+        let mut payload = bincode::serialize(&(99u32, id)).unwrap();
+        payload.extend_from_slice(&[0xFF; 3]);
+        let err = ErrorResponse::from_payload(&dispatch(&payload)).unwrap();
+        assert_eq!((err.id, err.code), (id, ErrorCode::Unsupported));
+    }
+
+    #[test]
+    fn known_kind_with_truncated_body_is_a_bad_request() {
+        let req = SearchRequest {
+            id: Uuid::new_v4(),
+            ..Default::default()
+        };
+        let full = payload(req.clone());
+        let err = ErrorResponse::from_payload(&dispatch(&full[..full.len() - 4])).unwrap();
+        assert_eq!((err.id, err.code), (req.id, ErrorCode::BadRequest));
+    }
+
+    #[test]
+    fn undecodable_payload_is_a_bad_request() {
+        let err = ErrorResponse::from_payload(&dispatch(b"not a request")).unwrap();
+        assert_eq!((err.id, err.code), (Uuid::nil(), ErrorCode::BadRequest));
+    }
+
+    #[tokio::test]
+    async fn connection_survives_rejected_requests() {
+        use tokio::io::AsyncReadExt;
+
+        let (mut client, server) = tokio::io::duplex(64 * 1024);
+        let served = tokio::spawn(handle_connection(server, None));
+        async fn round_trip(client: &mut tokio::io::DuplexStream, payload: &[u8]) -> Vec<u8> {
+            client
+                .write_all(&framing::encode_frame(payload).unwrap())
+                .await
+                .unwrap();
+            let mut len = [0u8; 4];
+            client.read_exact(&mut len).await.unwrap();
+            let mut body = vec![0u8; u32::from_le_bytes(len) as usize];
+            client.read_exact(&mut body).await.unwrap();
+            body
+        }
+
+        let rejected = round_trip(&mut client, b"not a request").await;
+        assert_eq!(
+            ErrorResponse::from_payload(&rejected).map(|e| e.code),
+            Some(ErrorCode::BadRequest)
+        );
+        let req = StatusRequest { id: Uuid::new_v4() };
+        let answered = round_trip(&mut client, &payload(req.clone())).await;
+        let resp: StatusResponse = bincode::deserialize(&answered).unwrap();
+        assert_eq!(resp.id, req.id);
+
+        drop(client);
+        served.await.unwrap().unwrap();
+    }
 }
@@ -1,18 +1,30 @@
 //! Service support library: tracing/logging bootstrap and metrics helpers.
 
 pub mod bootstrap;
+pub mod caller;
+pub mod config_reload;
+pub mod delta_tier;
 pub mod dispatcher;
+pub mod instance_lock;
 mod logging;
 pub mod memory;
 pub mod meta_ingest;
 pub mod metrics;
+pub mod paging;
+pub mod path_filter;
 pub mod planner;
 pub mod priority;
+pub mod query_cache;
+pub mod query_log;
+pub mod reindex;
 pub mod scanner;
 pub mod scheduler_runtime;
 pub mod search_handler;
+pub mod snippet_cache;
 pub mod status;
 pub mod status_provider;
+pub mod suggest;
+pub mod warmup;
 
 #[cfg(windows)]
 pub mod windows;
@@ -20,7 +32,10 @@ pub mod windows;
 pub mod ipc; // I forgot to add this!
 
 pub use logging::{init_tracing, init_tracing_with_config};
-pub use meta_ingest::{ingest_file_meta_batch, ingest_with_paths};
+pub use meta_ingest::{
+    IngestContext, MemoryBudget, apply_renames, handle_rename, ingest_file_meta_batch,
+    ingest_file_meta_batch_with, ingest_with_paths,
+};
 pub use metrics::{
     ServiceMetrics, ServiceMetricsSnapshot, init_metrics_from_config, scrape_metrics,
 };
@@ -30,12 +45,12 @@ pub use search_handler::{
     SearchHandler, StubSearchHandler, UnifiedSearchHandler, search, set_search_handler,
 };
 pub use status_provider::{
-    BasicStatusProvider, init_basic_status_provider, set_status_provider, status_snapshot,
+    BasicStatusProvider, IndexState, ReadinessTracker, init_basic_status_provider,
+    set_status_provider, status_snapshot,
 };
 
 use core_types::config::AppConfig;
 use ntfs_watcher::discover_volumes;
-use std::fs;
 use std::path::PathBuf;
 use std::process::Command;
 
@@ -67,12 +82,8 @@ pub fn ensure_default_volumes(cfg: &mut AppConfig) -> anyhow::Result<()> {
 
 fn persist_config(cfg: &AppConfig) {
     let path = core_types::config::default_config_path();
-    if let Some(parent) = path.parent() {
-        let _ = fs::create_dir_all(parent);
-    }
-    if let Ok(toml) = toml::to_string_pretty(cfg) {
-        let _ = fs::write(PathBuf::from(path.clone()), toml);
-        ensure_config_acl_writable(&PathBuf::from(path));
+    if core_types::config::save_config_atomic(&path, cfg).is_ok() {
+        ensure_config_acl_writable(&path);
     }
 }
 
@@ -107,7 +118,7 @@ pub fn ensure_config_acl_writable(path: &PathBuf) {
 mod e2e_windows_tests {
     use crate::bootstrap::{BootstrapOptions, run_app_with_options};
     use ::ipc::{
-        QueryExpr, SearchMode, SearchRequest, StatusRequest, TermExpr, TermModifier,
+        QueryExpr, Readiness, SearchMode, SearchRequest, StatusRequest, TermExpr, TermModifier,
         client::PipeClient,
     };
     use anyhow::Result;
@@ -207,7 +218,11 @@ mod e2e_windows_tests {
         sleep(Duration::from_millis(150)).await;
         for _ in 0..20 {
             let req: StatusRequest = StatusRequest { id: Uuid::new_v4() };
-            if client.status(req).await.is_ok() {
+            if client
+                .status(req)
+                .await
+                .is_ok_and(|status| status.readiness == Readiness::Ready)
+            {
                 ready = true;
                 break;
             }
@@ -222,11 +237,22 @@ mod e2e_windows_tests {
                 field: None,
                 value: "hello".into(),
                 modifier: TermModifier::Term,
+                boost: None,
             }),
             limit: 10,
             mode: SearchMode::NameOnly,
             timeout: Some(Duration::from_secs(2)),
             offset: 0,
+            cursor: None,
+            explain: false,
+            case_sensitive: false,
+            count_only: false,
+            fields: None,
+            include_system: false,
+            sort: None,
+            suggest: false,
+            group_by: None,
+            normalize_scores: false,
         };
         let resp = client.search(search_req).await?;
         assert!(
@@ -246,11 +272,22 @@ mod e2e_windows_tests {
                         field: None,
                         value: "ultrasearch".into(),
                         modifier: TermModifier::Term,
+                        boost: None,
                     }),
                     limit: 10,
                     mode: SearchMode::Content,
                     timeout: Some(Duration::from_secs(2)),
                     offset: 0,
+                    cursor: None,
+                    explain: false,
+                    case_sensitive: false,
+                    count_only: false,
+                    fields: None,
+                    include_system: false,
+                    sort: None,
+                    suggest: false,
+                    group_by: None,
+                    normalize_scores: false,
                 };
                 let resp = client.search(content_req).await?;
                 if resp.total > 0 {
@@ -349,7 +386,11 @@ mod e2e_windows_tests {
         sleep(Duration::from_millis(150)).await;
         for _ in 0..20 {
             let req: StatusRequest = StatusRequest { id: Uuid::new_v4() };
-            if client.status(req).await.is_ok() {
+            if client
+                .status(req)
+                .await
+                .is_ok_and(|status| status.readiness == Readiness::Ready)
+            {
                 ready = true;
                 break;
             }
@@ -363,11 +404,22 @@ mod e2e_windows_tests {
                 field: None,
                 value: "lorem".into(),
                 modifier: TermModifier::Term,
+                boost: None,
             }),
             limit: 5,
             mode: SearchMode::Content,
             timeout: Some(Duration::from_secs(2)),
             offset: 0,
+            cursor: None,
+            explain: false,
+            case_sensitive: false,
+            count_only: false,
+            fields: None,
+            include_system: false,
+            sort: None,
+            suggest: false,
+            group_by: None,
+            normalize_scores: false,
         };
         let resp = client.search(search_req).await?;
         assert!(
@@ -444,7 +496,11 @@ mod e2e_windows_tests {
         sleep(Duration::from_millis(150)).await;
         for _ in 0..20 {
             let req: StatusRequest = StatusRequest { id: Uuid::new_v4() };
-            if client.status(req).await.is_ok() {
+            if client
+                .status(req)
+                .await
+                .is_ok_and(|status| status.readiness == Readiness::Ready)
+            {
                 ready = true;
                 break;
             }
@@ -455,12 +511,24 @@ mod e2e_windows_tests {
             "IPC server did not become ready in time (malformed test)"
         );
 
-        // Send malformed frame (length=0)
-        {
+        // Send malformed frames: length=0, then a length prefix far over `ipc.max_frame_bytes`
+        // with no body. The server must answer with a BadRequest error and drop the connection
+        // instead of waiting to fill it.
+        for len in [0u32, u32::MAX] {
+            use tokio::io::AsyncReadExt;
             use tokio::net::windows::named_pipe::ClientOptions;
             let mut conn = ClientOptions::new().open(&pipe_name)?;
-            conn.write_all(&0u32.to_le_bytes()).await?;
-            let _ = conn.shutdown().await;
+            conn.write_all(&len.to_le_bytes()).await?;
+            let mut rest = Vec::new();
+            let closed =
+                tokio::time::timeout(Duration::from_secs(5), conn.read_to_end(&mut rest)).await;
+            assert!(
+                closed.is_ok(),
+                "server kept the connection open after a {len}-byte prefix"
+            );
+            let (body, _) = ::ipc::framing::decode_frame(&rest)?;
+            let err = ::ipc::ErrorResponse::from_payload(&body).expect("error response");
+            assert_eq!(err.code, ::ipc::ErrorCode::BadRequest);
         }
 
         // Server should still respond to a valid request on a fresh connection.
@@ -470,11 +538,22 @@ mod e2e_windows_tests {
                 field: None,
                 value: "alive".into(),
                 modifier: TermModifier::Term,
+                boost: None,
             }),
             limit: 5,
             mode: SearchMode::NameOnly,
             timeout: Some(Duration::from_secs(2)),
             offset: 0,
+            cursor: None,
+            explain: false,
+            case_sensitive: false,
+            count_only: false,
+            fields: None,
+            include_system: false,
+            sort: None,
+            suggest: false,
+            group_by: None,
+            normalize_scores: false,
         };
         let resp = client.search(search_req).await?;
         assert!(
@@ -487,3 +566,100 @@ mod e2e_windows_tests {
         Ok(())
     }
 }
+
+#[cfg(all(test, unix, feature = "uds-transport"))]
+mod uds_tests {
+    use crate::bootstrap::{BootstrapOptions, run_app_with_options};
+    use ::ipc::{
+        QueryExpr, Readiness, SearchMode, SearchRequest, StatusRequest, TermExpr, TermModifier,
+        client::PipeClient,
+    };
+    use anyhow::Result;
+    use core_types::{DocKey, FileFlags, FileMeta};
+    use tempfile::tempdir;
+    use tokio::sync::mpsc;
+    use tokio::time::{Duration, sleep};
+    use uuid::Uuid;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn search_over_unix_socket() -> Result<()> {
+        let temp = tempdir()?;
+        let data_dir = temp.path().join("data");
+        let mut cfg = core_types::config::AppConfig::default();
+        cfg.app.data_dir = data_dir.to_string_lossy().to_string();
+        cfg.logging.file = data_dir.join("searchd.log").to_string_lossy().to_string();
+        for (path, dir) in [
+            (&mut cfg.paths.meta_index, "meta"),
+            (&mut cfg.paths.content_index, "content"),
+            (&mut cfg.paths.state_dir, "state"),
+            (&mut cfg.paths.jobs_dir, "jobs"),
+        ] {
+            *path = data_dir.join(dir).to_string_lossy().to_string();
+        }
+        cfg.metrics.enabled = false;
+
+        let meta = FileMeta::new(
+            DocKey::from_parts(1, 1),
+            1,
+            None,
+            "hello.txt".into(),
+            Some("/docs/hello.txt".into()),
+            5,
+            0,
+            0,
+            FileFlags::empty(),
+        );
+        let socket = temp.path().join("ultrasearch.sock");
+        let opts = BootstrapOptions {
+            initial_metas: Some(vec![meta]),
+            skip_initial_ingest: true,
+            pipe_name: Some(socket.to_string_lossy().to_string()),
+            force_content_jobs: false,
+        };
+
+        let (shutdown_tx, shutdown_rx) = mpsc::channel(1);
+        let handle = std::thread::spawn(move || run_app_with_options(&cfg, shutdown_rx, opts));
+
+        let client = PipeClient::new(socket.to_string_lossy())
+            .with_request_timeout(Duration::from_millis(500));
+        let mut seen = Vec::new();
+        for _ in 0..25 {
+            if let Ok(status) = client.status(StatusRequest { id: Uuid::new_v4() }).await
+                && seen.last() != Some(&status.readiness)
+            {
+                seen.push(status.readiness);
+            }
+            if seen.last() == Some(&Readiness::Ready) {
+                break;
+            }
+            sleep(Duration::from_millis(200)).await;
+        }
+        // The indexes are open before the socket answers; only the scan can still be running.
+        assert!(
+            matches!(
+                seen.as_slice(),
+                [Readiness::Ready] | [Readiness::Indexing, Readiness::Ready]
+            ),
+            "readiness went {seen:?}"
+        );
+
+        let req = SearchRequest {
+            id: Uuid::new_v4(),
+            ..SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                field: None,
+                value: "hello".into(),
+                modifier: TermModifier::Term,
+                boost: None,
+            }))
+        }
+        .with_mode(SearchMode::NameOnly);
+        let resp = client.search(req.clone()).await?;
+        assert_eq!(resp.id, req.id);
+        assert_eq!(resp.total, 1);
+        assert_eq!(resp.hits[0].name.as_deref(), Some("hello.txt"));
+
+        let _ = shutdown_tx.send(()).await;
+        handle.join().expect("service thread panicked")?;
+        Ok(())
+    }
+}
@@ -1,2 +1,147 @@
+//! Process allocator and memory ceiling (`scheduler.memory_ceiling_bytes`).
+//!
+//! A big build can leave the service holding far more than it needs: writer heaps full of
+//! uncommitted docs, the delta tier, cached responses and snippets, searchers pinning merged-away
+//! segments. [`MemoryGuard`] samples the process's resident set every [`CHECK_INTERVAL`] and,
+//! while it is over the ceiling, runs the registered trims (commit writers, clear caches) and
+//! logs what that gave back.
+
+use anyhow::Result;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tracing::{info, warn};
+
 #[global_allocator]
 static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
+
+/// How often the guard samples the process's memory.
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+type Trim = Box<dyn Fn() -> Result<()> + Send + Sync>;
+
+/// Runs trims whenever the process's resident memory is above a ceiling.
+pub struct MemoryGuard {
+    ceiling_bytes: u64,
+    rss: Box<dyn Fn() -> u64 + Send + Sync>,
+    trims: Vec<(&'static str, Trim)>,
+    /// Checks that found the process over the ceiling.
+    trimmed: AtomicU64,
+}
+
+impl MemoryGuard {
+    /// Guard measuring this process's resident set; a ceiling of 0 never trims.
+    pub fn process(ceiling_bytes: u64) -> Self {
+        let sys = Mutex::new(sysinfo::System::new());
+        let pid = sysinfo::get_current_pid().ok();
+        Self::with_probe(ceiling_bytes, move || {
+            let Some(pid) = pid else { return 0 };
+            let mut sys = sys.lock();
+            sys.refresh_processes(sysinfo::ProcessesToUpdate::Some(&[pid]), true);
+            sys.process(pid).map_or(0, |p| p.memory())
+        })
+    }
+
+    /// Guard reading resident bytes from `rss`, e.g. a simulated usage signal.
+    pub fn with_probe(ceiling_bytes: u64, rss: impl Fn() -> u64 + Send + Sync + 'static) -> Self {
+        Self {
+            ceiling_bytes,
+            rss: Box::new(rss),
+            trims: Vec::new(),
+            trimmed: AtomicU64::new(0),
+        }
+    }
+
+    /// Run `trim` whenever the ceiling is exceeded, after the trims registered before it.
+    pub fn on_pressure(
+        mut self,
+        name: &'static str,
+        trim: impl Fn() -> Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        self.trims.push((name, Box::new(trim)));
+        self
+    }
+
+    pub fn ceiling_bytes(&self) -> u64 {
+        self.ceiling_bytes
+    }
+
+    /// Checks so far that ran the trims.
+    pub fn trimmed(&self) -> u64 {
+        self.trimmed.load(Ordering::Relaxed)
+    }
+
+    /// Sample memory once and trim if it is over the ceiling; returns whether it trimmed. A
+    /// failing trim is logged and the rest still run.
+    pub fn check(&self) -> bool {
+        if self.ceiling_bytes == 0 {
+            return false;
+        }
+        let rss = (self.rss)();
+        if rss <= self.ceiling_bytes {
+            return false;
+        }
+        warn!(
+            rss,
+            ceiling = self.ceiling_bytes,
+            "memory over ceiling; committing writers and trimming caches"
+        );
+        for (name, trim) in &self.trims {
+            if let Err(err) = trim() {
+                warn!(trim = *name, error = %err, "memory trim failed");
+            }
+        }
+        self.trimmed.fetch_add(1, Ordering::Relaxed);
+        info!(before = rss, after = (self.rss)(), "memory trim finished");
+        true
+    }
+}
+
+/// Check `guard` every [`CHECK_INTERVAL`] for as long as the runtime runs.
+pub async fn run_guard(guard: Arc<MemoryGuard>) {
+    loop {
+        tokio::time::sleep(CHECK_INTERVAL).await;
+        let guard = Arc::clone(&guard);
+        if let Err(err) = tokio::task::spawn_blocking(move || guard.check()).await {
+            warn!("memory check task failed: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crossing_the_ceiling_runs_every_trim() {
+        let rss = Arc::new(AtomicU64::new(400));
+        let trims = Arc::new(AtomicU64::new(0));
+        let probe = Arc::clone(&rss);
+        let (first, second) = (Arc::clone(&trims), Arc::clone(&trims));
+        let guard = MemoryGuard::with_probe(500, move || probe.load(Ordering::Relaxed))
+            .on_pressure("failing", || anyhow::bail!("writer busy"))
+            .on_pressure("caches", move || {
+                first.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            })
+            .on_pressure("writers", move || {
+                second.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            });
+
+        assert!(!guard.check());
+        rss.store(500, Ordering::Relaxed);
+        assert!(!guard.check(), "at the ceiling is still within it");
+        assert_eq!(trims.load(Ordering::Relaxed), 0);
+
+        rss.store(501, Ordering::Relaxed);
+        assert!(guard.check());
+        // The failing trim does not stop the others.
+        assert_eq!(trims.load(Ordering::Relaxed), 2);
+        assert_eq!(guard.trimmed(), 1);
+
+        let off = MemoryGuard::with_probe(0, || u64::MAX).on_pressure("never", || unreachable!());
+        assert!(!off.check());
+    }
+}
@@ -1,27 +1,375 @@
+use crate::reindex::repopulate_if_recreated;
+use crate::status_provider::update_status_volume_cursor;
 use anyhow::Result;
-use core_types::FileMeta;
-use core_types::config::PathsSection;
-use meta_index::{WriterConfig, add_file_meta_batch, create_writer, open_or_create_index};
+use core_types::config::{PathsSection, get_current_config};
+use core_types::{DocKey, FileMeta, VolumeId, WriterProfile};
+use ipc::ReindexScope;
+use meta_index::cache::MetadataCache;
+use meta_index::delta::DeltaIndex;
+use meta_index::{
+    MetaFields, MetaIndex, WriterConfig, add_file_meta_batch, create_writer, delete_file_meta,
+    open_or_recreate_index_with, upsert_file_meta,
+};
+use ntfs_watcher::{FileEvent, JournalCursor};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{debug, warn};
 
-/// Ingest a batch of `FileMeta` records into the metadata index and commit.
+static ANALYZER_MISMATCH_WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Parent-linked paths of entries seen by change ingest; renames evict what they invalidate.
+static PATH_CACHE: Lazy<Mutex<MetadataCache>> =
+    Lazy::new(|| Mutex::new(MetadataCache::new(10_000)));
+
+/// Open (or create) the metadata index with the configured `[search]` schema options. An
+/// existing index keeps the schema it was built with; a mismatch is logged since only a
+/// rebuild applies it. An unreadable index is recreated only if
+/// `paths.recreate_corrupt_indexes` is set, and then repopulated by rescanning the volumes.
+pub fn open_meta_index(index_path: &Path) -> Result<MetaIndex> {
+    let cfg = get_current_config();
+    let search = cfg.search;
+    let wanted = search.name_analyzer;
+    let (mut meta, outcome) =
+        open_or_recreate_index_with(index_path, &search, cfg.paths.recreate_corrupt_indexes)?;
+    repopulate_if_recreated(&outcome, ReindexScope::MetaOnly);
+    meta.set_path_segments(cfg.features.path_segments);
+    let built = meta.name_analyzer();
+    let ngrams_differ = meta.fields.name_ngram.is_some() != search.name_ngrams;
+    if (built != Some(wanted) || ngrams_differ)
+        && !ANALYZER_MISMATCH_WARNED.swap(true, Ordering::Relaxed)
+    {
+        warn!(
+            ?built,
+            ?wanted,
+            name_ngrams = search.name_ngrams,
+            "meta index at {} was built with a different name_analyzer or name_ngrams; \
+             rebuild it (delete the directory and rescan) to apply the configured one",
+            index_path.display()
+        );
+    }
+    Ok(meta)
+}
+
+/// How many docs [`ingest_file_meta_batch_with`] adds per commit. Each commit flushes the
+/// writer's buffered docs, so when available memory falls below `low_water_bytes` batches
+/// shrink in proportion and the heap is released sooner.
+pub struct MemoryBudget {
+    /// Docs per commit while memory is plentiful.
+    pub max_batch: usize,
+    /// Docs per commit under the heaviest pressure.
+    pub min_batch: usize,
+    /// Available memory below which batches start shrinking.
+    pub low_water_bytes: u64,
+    available: Box<dyn Fn() -> u64 + Send + Sync>,
+}
+
+impl Default for MemoryBudget {
+    fn default() -> Self {
+        Self::system()
+    }
+}
+
+impl MemoryBudget {
+    /// Budget that probes this machine's available memory before each batch.
+    pub fn system() -> Self {
+        let sys = Mutex::new(sysinfo::System::new());
+        Self::with_probe(move || {
+            let mut sys = sys.lock();
+            sys.refresh_memory();
+            sys.available_memory()
+        })
+    }
+
+    /// Budget reading available bytes from `available`, e.g. a simulated pressure signal.
+    pub fn with_probe(available: impl Fn() -> u64 + Send + Sync + 'static) -> Self {
+        Self {
+            max_batch: 100_000,
+            min_batch: 1_000,
+            low_water_bytes: 512 * 1024 * 1024,
+            available: Box::new(available),
+        }
+    }
+
+    /// Docs to add before the next commit, from the memory available right now.
+    pub fn batch_size(&self) -> usize {
+        let max = self.max_batch.max(1);
+        let available = (self.available)();
+        if available >= self.low_water_bytes {
+            return max;
+        }
+        let scaled = max as u128 * available as u128 / self.low_water_bytes as u128;
+        let batch = (scaled as usize).max(self.min_batch).min(max).max(1);
+        debug!(available, batch, "shrinking meta ingest batch");
+        batch
+    }
+}
+
+/// Journal position, per volume, that the metas being ingested were observed at. Once their
+/// batch is committed, [`mark_committed`](Self::mark_committed) reports it as the volume's
+/// `last_usn` / `journal_id` in status, so status says what the index holds rather than how
+/// far the watcher has read.
+#[derive(Debug, Clone, Default)]
+pub struct IngestContext {
+    cursors: HashMap<VolumeId, JournalCursor>,
+}
+
+impl IngestContext {
+    /// Context for changes read from `volume` up to `cursor`.
+    pub fn at(volume: VolumeId, cursor: JournalCursor) -> Self {
+        let mut ctx = Self::default();
+        ctx.set_cursor(volume, cursor);
+        ctx
+    }
+
+    pub fn set_cursor(&mut self, volume: VolumeId, cursor: JournalCursor) {
+        self.cursors.insert(volume, cursor);
+    }
+
+    pub fn cursor(&self, volume: VolumeId) -> Option<JournalCursor> {
+        self.cursors.get(&volume).copied()
+    }
+
+    /// Publish the carried cursors to status; call only after the batch they describe has
+    /// been committed.
+    pub fn mark_committed(&self) {
+        for (&volume, cursor) in &self.cursors {
+            update_status_volume_cursor(volume, cursor.last_usn, cursor.journal_id);
+        }
+    }
+}
+
+/// Ingest a batch of `FileMeta` records into the metadata index, committing in chunks sized by
+/// the system [`MemoryBudget`].
 pub fn ingest_file_meta_batch(
     index_path: &Path,
     metas: impl IntoIterator<Item = FileMeta>,
     writer_cfg: Option<WriterConfig>,
 ) -> Result<()> {
-    let meta = open_or_create_index(index_path)?;
-    let mut writer = create_writer(&meta, &writer_cfg.unwrap_or_default())?;
-    add_file_meta_batch(&mut writer, &meta.fields, metas)?;
-    writer.commit()?;
+    ingest_file_meta_batch_with(
+        index_path,
+        metas,
+        writer_cfg,
+        &MemoryBudget::system(),
+        |_| {},
+    )
+}
+
+/// Like [`ingest_file_meta_batch`] with an explicit budget. `on_commit` runs after each commit
+/// with the number of docs it flushed; an empty input still commits once.
+pub fn ingest_file_meta_batch_with(
+    index_path: &Path,
+    metas: impl IntoIterator<Item = FileMeta>,
+    writer_cfg: Option<WriterConfig>,
+    budget: &MemoryBudget,
+    mut on_commit: impl FnMut(usize),
+) -> Result<()> {
+    let meta = open_meta_index(index_path)?;
+    let cfg = writer_cfg.unwrap_or_else(|| WriterConfig::auto_for(WriterProfile::SteadyState));
+    let mut writer = create_writer(&meta, &cfg)?;
+    let mut metas = metas.into_iter().peekable();
+    loop {
+        let chunk: Vec<FileMeta> = metas.by_ref().take(budget.batch_size()).collect();
+        let len = chunk.len();
+        add_file_meta_batch(&mut writer, &meta.fields, chunk)?;
+        writer.commit()?;
+        on_commit(len);
+        if metas.peek().is_none() {
+            return Ok(());
+        }
+    }
+}
+
+/// Apply a rename in place: the doc stored under `from` is replaced by `to`. NTFS keeps the
+/// file reference across renames, so the `DocKey` (and with it the content-index entry) is
+/// unchanged; only name, path and extension are rewritten. Cached paths of the entry and of
+/// everything beneath it are dropped, since a renamed directory moves its whole subtree.
+pub fn handle_rename(
+    writer: &mut tantivy::IndexWriter,
+    fields: &MetaFields,
+    cache: &mut MetadataCache,
+    from: DocKey,
+    to: &FileMeta,
+) -> Result<()> {
+    if from != to.key {
+        delete_file_meta(writer, fields, from)?;
+    }
+    upsert_file_meta(writer, fields, to)?;
+    forget_renamed(cache, from, to);
     Ok(())
 }
 
-/// Convenience for ingesting using configured paths.
+/// Drop the cached paths a rename of `from` to `to` invalidates and cache the new entry.
+fn forget_renamed(cache: &mut MetadataCache, from: DocKey, to: &FileMeta) {
+    if from != to.key {
+        cache.remove(from);
+    }
+    cache.invalidate_subtree(to.key);
+    cache.put(to);
+}
+
+fn renamed_entries(events: &[FileEvent]) -> Vec<(DocKey, &FileMeta)> {
+    events
+        .iter()
+        .filter_map(|ev| match ev {
+            FileEvent::Renamed { from, to } => Some((*from, to)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Apply the `Renamed` events among `events` to the metadata index and commit; returns how
+/// many were applied. Other events are left to the content pipeline.
+pub fn apply_renames(index_path: &Path, events: &[FileEvent]) -> Result<usize> {
+    let renames = renamed_entries(events);
+    if renames.is_empty() {
+        return Ok(0);
+    }
+    let meta = open_meta_index(index_path)?;
+    let mut writer = create_writer(&meta, &WriterConfig::auto_for(WriterProfile::SteadyState))?;
+    let mut cache = PATH_CACHE.lock();
+    for (from, to) in &renames {
+        handle_rename(&mut writer, &meta.fields, &mut cache, *from, to)?;
+    }
+    writer.commit()?;
+    Ok(renames.len())
+}
+
+/// [`apply_renames`] for `features.delta_index`: the renames are committed to `delta`, which
+/// searches read immediately, and reach the on-disk index with its next merge.
+pub fn apply_renames_to_delta(delta: &DeltaIndex, events: &[FileEvent]) -> Result<usize> {
+    let renames = renamed_entries(events);
+    if renames.is_empty() {
+        return Ok(0);
+    }
+    let removed = renames
+        .iter()
+        .filter(|(from, to)| *from != to.key)
+        .map(|(from, _)| *from);
+    delta.apply(removed, renames.iter().map(|(_, to)| (*to).clone()))?;
+    let mut cache = PATH_CACHE.lock();
+    for (from, to) in &renames {
+        forget_renamed(&mut cache, *from, to);
+    }
+    Ok(renames.len())
+}
+
+/// Convenience for ingesting using configured paths. Once every chunk is committed, `ctx`'s
+/// journal cursors are reported as committed.
 pub fn ingest_with_paths(
     paths: &PathsSection,
     metas: impl IntoIterator<Item = FileMeta>,
     writer_cfg: Option<WriterConfig>,
+    ctx: &IngestContext,
 ) -> Result<()> {
-    ingest_file_meta_batch(Path::new(&paths.meta_index), metas, writer_cfg)
+    ingest_file_meta_batch(Path::new(&paths.meta_index), metas, writer_cfg)?;
+    ctx.mark_committed();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core_types::{DocKey, FileFlags};
+    use tempfile::tempdir;
+
+    fn metas(n: u64) -> impl Iterator<Item = FileMeta> {
+        (1..=n).map(|frn| {
+            FileMeta::new(
+                DocKey::from_parts(1, frn),
+                1,
+                None,
+                format!("file_{frn}.txt"),
+                None,
+                0,
+                0,
+                0,
+                FileFlags::empty(),
+            )
+        })
+    }
+
+    fn commits_for(available: u64) -> (Vec<usize>, u64) {
+        let dir = tempdir().unwrap();
+        let budget = MemoryBudget {
+            max_batch: 100,
+            min_batch: 5,
+            low_water_bytes: 1000,
+            ..MemoryBudget::with_probe(move || available)
+        };
+        let mut commits = Vec::new();
+        ingest_file_meta_batch_with(dir.path(), metas(250), None, &budget, |n| commits.push(n))
+            .unwrap();
+        let meta = open_meta_index(dir.path()).unwrap();
+        let docs = meta_index::open_reader(&meta)
+            .unwrap()
+            .searcher()
+            .num_docs();
+        (commits, docs)
+    }
+
+    #[test]
+    fn low_memory_commits_in_smaller_batches() {
+        assert_eq!(commits_for(u64::MAX), (vec![100, 100, 50], 250));
+
+        // 10% of the low-water mark: batches shrink to a tenth.
+        let (commits, docs) = commits_for(100);
+        assert_eq!(commits.len(), 25);
+        assert!(commits.iter().all(|&n| n == 10), "{commits:?}");
+        assert_eq!(docs, 250);
+
+        // Batches never drop below the floor.
+        let (commits, _) = commits_for(0);
+        assert_eq!(commits.len(), 50);
+    }
+
+    #[test]
+    fn committing_a_batch_reports_its_journal_cursor() {
+        use crate::status_provider::{init_basic_status_provider, status_snapshot};
+
+        // No other test reports this volume.
+        const VOL: VolumeId = 41;
+        let _ = init_basic_status_provider();
+        let reported = || {
+            status_snapshot()
+                .volumes
+                .into_iter()
+                .find(|v| v.volume == VOL)
+                .and_then(|v| v.last_usn.zip(v.journal_id))
+        };
+        let at = |last_usn| {
+            IngestContext::at(
+                VOL,
+                JournalCursor {
+                    last_usn,
+                    journal_id: 9,
+                },
+            )
+        };
+        let dir = tempdir().unwrap();
+        let paths = PathsSection {
+            meta_index: dir.path().join("meta").to_string_lossy().to_string(),
+            ..PathsSection::default()
+        };
+        std::fs::create_dir_all(&paths.meta_index).unwrap();
+
+        assert_eq!(reported(), None);
+        ingest_with_paths(&paths, metas(3), None, &at(500)).unwrap();
+        assert_eq!(reported(), Some((500, 9)));
+
+        // A batch that never commits leaves the reported cursor where it was.
+        let not_a_dir = dir.path().join("file");
+        std::fs::write(&not_a_dir, "x").unwrap();
+        let broken = PathsSection {
+            meta_index: not_a_dir.to_string_lossy().to_string(),
+            ..PathsSection::default()
+        };
+        ingest_with_paths(&broken, metas(1), None, &at(900)).unwrap_err();
+        assert_eq!(reported(), Some((500, 9)));
+
+        ingest_with_paths(&paths, metas(1), None, &at(800)).unwrap();
+        assert_eq!(reported(), Some((800, 9)));
+    }
 }
@@ -1,14 +1,25 @@
 #![allow(dead_code)]
 
 use anyhow::Result;
+use core_types::FileKind;
 use core_types::config::MetricsSection;
-use ipc::MetricsSnapshot;
+use ipc::{ExtractKindStats, MetricsSnapshot};
 use once_cell::sync::Lazy;
-use prometheus::{Encoder, Histogram, HistogramOpts, IntCounter, Registry, TextEncoder, opts};
-use std::sync::{Arc, OnceLock};
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec,
+    Registry, TextEncoder, opts,
+};
+use scheduler::depth_by_category;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::Duration;
 use tracing::warn;
 
+/// Recent extraction durations kept per kind for percentile snapshots.
+const EXTRACT_WINDOW: usize = 1024;
+
+const EXTRACT_BUCKETS: [f64; 10] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 5.0, 30.0];
+
 /// Shared metrics handle for the service.
 pub struct ServiceMetrics {
     pub registry: Registry,
@@ -16,6 +27,14 @@ pub struct ServiceMetrics {
     pub request_latency: Histogram,
     pub worker_failures: IntCounter,
     pub worker_failure_threshold: u64,
+    pub slow_queries: IntCounter,
+    pub extract_duration: HistogramVec,
+    pub extract_bytes: IntCounterVec,
+    /// Jobs waiting per scheduler queue, labeled by `category`.
+    pub queue_depth: IntGaugeVec,
+    pub content_enqueued: IntCounter,
+    pub content_dropped: IntCounter,
+    extract_window: Mutex<HashMap<FileKind, VecDeque<f64>>>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -27,6 +46,18 @@ pub struct ServiceMetricsSnapshot {
     pub active_workers: Option<u32>,
     pub content_enqueued: Option<u64>,
     pub content_dropped: Option<u64>,
+    pub extract_p50_ms: Option<f64>,
+    pub extract_p95_ms: Option<f64>,
+    pub extract_by_kind: Vec<ExtractKindStats>,
+}
+
+/// Nearest-rank percentile of an ascending slice.
+fn percentile(sorted: &[f64], pct: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
 }
 
 impl ServiceMetrics {
@@ -43,10 +74,52 @@ impl ServiceMetrics {
         let request_latency = Histogram::with_opts(hist_opts)?;
         let worker_failures =
             IntCounter::with_opts(opts!("worker_failures_total", "Index worker failures"))?;
+        let slow_queries = IntCounter::with_opts(opts!(
+            "slow_query_total",
+            "Searches slower than metrics.slow_query_ms"
+        ))?;
 
         registry.register(Box::new(requests_total.clone()))?;
         registry.register(Box::new(request_latency.clone()))?;
+        let extract_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "extract_duration_seconds",
+                "Content extraction time per file, by file kind",
+            )
+            .buckets(EXTRACT_BUCKETS.to_vec()),
+            &["kind"],
+        )?;
+        let extract_bytes = IntCounterVec::new(
+            opts!(
+                "extract_bytes_total",
+                "Bytes processed by extraction, by file kind"
+            ),
+            &["kind"],
+        )?;
+
+        let queue_depth = IntGaugeVec::new(
+            opts!(
+                "queue_depth",
+                "Jobs waiting in the scheduler, by queue category"
+            ),
+            &["category"],
+        )?;
+        let content_enqueued = IntCounter::with_opts(opts!(
+            "content_jobs_enqueued_total",
+            "Content jobs accepted by the scheduler"
+        ))?;
+        let content_dropped = IntCounter::with_opts(opts!(
+            "content_jobs_dropped_total",
+            "Content jobs dropped by backpressure or a stopped scheduler"
+        ))?;
+
         registry.register(Box::new(worker_failures.clone()))?;
+        registry.register(Box::new(slow_queries.clone()))?;
+        registry.register(Box::new(extract_duration.clone()))?;
+        registry.register(Box::new(extract_bytes.clone()))?;
+        registry.register(Box::new(queue_depth.clone()))?;
+        registry.register(Box::new(content_enqueued.clone()))?;
+        registry.register(Box::new(content_dropped.clone()))?;
 
         Ok(Self {
             registry,
@@ -54,9 +127,63 @@ impl ServiceMetrics {
             request_latency,
             worker_failures,
             worker_failure_threshold: cfg.worker_failure_threshold,
+            slow_queries,
+            extract_duration,
+            extract_bytes,
+            queue_depth,
+            content_enqueued,
+            content_dropped,
+            extract_window: Mutex::new(HashMap::new()),
         })
     }
 
+    /// Record one file's extraction time and processed bytes.
+    pub fn record_extraction(&self, kind: FileKind, duration: Duration, bytes: u64) {
+        self.extract_duration
+            .with_label_values(&[kind.as_str()])
+            .observe(duration.as_secs_f64());
+        self.extract_bytes
+            .with_label_values(&[kind.as_str()])
+            .inc_by(bytes);
+        if let Ok(mut window) = self.extract_window.lock() {
+            let samples = window.entry(kind).or_default();
+            if samples.len() == EXTRACT_WINDOW {
+                samples.pop_front();
+            }
+            samples.push_back(duration.as_secs_f64() * 1000.0);
+        }
+    }
+
+    /// Overall `(p50, p95)` in ms plus per-kind stats, for kinds seen so far.
+    pub fn extract_summary(&self) -> (Option<f64>, Option<f64>, Vec<ExtractKindStats>) {
+        let Ok(window) = self.extract_window.lock() else {
+            return (None, None, Vec::new());
+        };
+        let mut all = Vec::new();
+        let mut by_kind = Vec::new();
+        for kind in FileKind::ALL {
+            let Some(samples) = window.get(&kind) else {
+                continue;
+            };
+            let mut sorted: Vec<f64> = samples.iter().copied().collect();
+            sorted.sort_by(f64::total_cmp);
+            let label = [kind.as_str()];
+            by_kind.push(ExtractKindStats {
+                kind,
+                files: self
+                    .extract_duration
+                    .with_label_values(&label)
+                    .get_sample_count(),
+                bytes: self.extract_bytes.with_label_values(&label).get(),
+                p50_ms: percentile(&sorted, 50.0),
+                p95_ms: percentile(&sorted, 95.0),
+            });
+            all.extend(sorted);
+        }
+        all.sort_by(f64::total_cmp);
+        (percentile(&all, 50.0), percentile(&all, 95.0), by_kind)
+    }
+
     /// Record a successful request with latency (seconds).
     pub fn record_request(&self, latency_secs: f64) {
         self.requests_total.inc();
@@ -87,6 +214,26 @@ impl ServiceMetrics {
         self.worker_failures.reset();
     }
 
+    /// Set the `queue_depth` gauge of each category.
+    pub fn set_queue_depths(&self, critical: usize, metadata: usize, content: usize) {
+        for (category, depth) in depth_by_category((critical, metadata, content)) {
+            self.queue_depth
+                .with_label_values(&[category.as_str()])
+                .set(depth as i64);
+        }
+    }
+
+    /// Bring the content job counters up to the scheduler's running totals. The scheduler
+    /// keeps its own totals, so the counters only ever advance by the difference.
+    pub fn set_content_totals(&self, enqueued: u64, dropped: u64) {
+        for (counter, total) in [
+            (&self.content_enqueued, enqueued),
+            (&self.content_dropped, dropped),
+        ] {
+            counter.inc_by(total.saturating_sub(counter.get()));
+        }
+    }
+
     pub fn snapshot_with_queue_state(
         &self,
         queue_depth: Option<u64>,
@@ -94,6 +241,7 @@ impl ServiceMetrics {
         content_enqueued: Option<u64>,
         content_dropped: Option<u64>,
     ) -> ServiceMetricsSnapshot {
+        let (extract_p50_ms, extract_p95_ms, extract_by_kind) = self.extract_summary();
         ServiceMetricsSnapshot {
             search_latency_ms_p50: None,
             search_latency_ms_p95: None,
@@ -102,21 +250,16 @@ impl ServiceMetrics {
             active_workers,
             content_enqueued,
             content_dropped,
+            extract_p50_ms,
+            extract_p95_ms,
+            extract_by_kind,
         }
     }
 
     /// Render a lightweight metrics snapshot for status reporting.
     /// Note: Prometheus crate does not expose quantiles; we return None for p50/p95 for now.
     pub fn snapshot(&self) -> ServiceMetricsSnapshot {
-        ServiceMetricsSnapshot {
-            search_latency_ms_p50: None,
-            search_latency_ms_p95: None,
-            worker_failures: self.worker_failures.get(),
-            queue_depth: None,
-            active_workers: None,
-            content_enqueued: None,
-            content_dropped: None,
-        }
+        self.snapshot_with_queue_state(None, None, None, None)
     }
 }
 
@@ -168,15 +311,40 @@ pub fn global_metrics_snapshot(
             active_workers: snap.active_workers,
             content_enqueued: snap.content_enqueued,
             content_dropped: snap.content_dropped,
+            extract_p50_ms: snap.extract_p50_ms,
+            extract_p95_ms: snap.extract_p95_ms,
+            extract_by_kind: snap.extract_by_kind,
         }
     })
 }
 
+/// Record one extraction against the global metrics handle (no-op if uninitialized).
+pub fn record_extraction_global(kind: FileKind, duration: Duration, bytes: u64) {
+    let _ = with_global_metrics(|m| m.record_extraction(kind, duration, bytes));
+}
+
 /// Record a single IPC request duration against the global metrics handle (no-op if uninitialized).
 pub fn record_ipc_request(duration: Duration) {
     let _ = with_global_metrics(|m| m.record_request_duration(duration));
 }
 
+/// Publish scheduler queue depths and content job totals to the global handle (no-op if uninitialized).
+pub fn record_queue_state_global(
+    (critical, metadata, content): (usize, usize, usize),
+    enqueued: u64,
+    dropped: u64,
+) {
+    let _ = with_global_metrics(|m| {
+        m.set_queue_depths(critical, metadata, content);
+        m.set_content_totals(enqueued, dropped);
+    });
+}
+
+/// Count one search over `metrics.slow_query_ms` against the global handle (no-op if uninitialized).
+pub fn record_slow_query_global() {
+    let _ = with_global_metrics(|m| m.slow_queries.inc());
+}
+
 /// Record a worker failure and return true if the failure threshold was met; no-op if metrics unset.
 pub fn record_worker_failure_global() -> Option<bool> {
     with_global_metrics(|m| m.record_worker_failure())
@@ -219,6 +387,62 @@ mod tests {
         assert_eq!(snap.content_dropped, Some(1));
     }
 
+    #[test]
+    fn extraction_percentiles_by_kind() {
+        let metrics = ServiceMetrics::new(&MetricsSection::default()).unwrap();
+        assert_eq!(metrics.snapshot().extract_p95_ms, None);
+
+        for ms in 1..=100 {
+            metrics.record_extraction(FileKind::Pdf, Duration::from_millis(ms), 1000);
+        }
+        metrics.record_extraction(FileKind::Text, Duration::from_millis(2), 10);
+
+        let snap = metrics.snapshot();
+        assert_eq!(snap.extract_by_kind.len(), 2);
+        let pdf = &snap.extract_by_kind[1];
+        assert_eq!(pdf.kind, FileKind::Pdf);
+        assert_eq!(pdf.files, 100);
+        assert_eq!(pdf.bytes, 100_000);
+        assert_eq!(pdf.p50_ms, Some(50.0));
+        assert_eq!(pdf.p95_ms, Some(95.0));
+        let text = &snap.extract_by_kind[0];
+        assert_eq!((text.kind, text.p95_ms), (FileKind::Text, Some(2.0)));
+        // Overall percentiles pool all 101 samples.
+        assert_eq!(snap.extract_p50_ms, Some(50.0));
+        assert_eq!(snap.extract_p95_ms, Some(95.0));
+
+        let scraped = String::from_utf8(scrape_metrics(&metrics).unwrap()).unwrap();
+        assert!(scraped.contains("extract_duration_seconds_count{kind=\"pdf\"} 100"));
+    }
+
+    #[test]
+    fn queue_state_is_exported_with_category_labels() {
+        let metrics = ServiceMetrics::new(&MetricsSection::default()).unwrap();
+        metrics.set_queue_depths(1, 0, 42);
+        metrics.set_content_totals(7, 2);
+        // Totals never move the counters backwards.
+        metrics.set_content_totals(9, 1);
+
+        let scraped = String::from_utf8(scrape_metrics(&metrics).unwrap()).unwrap();
+        for line in [
+            "queue_depth{category=\"critical\"} 1",
+            "queue_depth{category=\"metadata\"} 0",
+            "queue_depth{category=\"content\"} 42",
+            "# TYPE content_jobs_enqueued_total counter",
+            "content_jobs_enqueued_total 9",
+            "content_jobs_dropped_total 2",
+        ] {
+            assert!(
+                scraped.lines().any(|l| l == line),
+                "missing {line:?} in\n{scraped}"
+            );
+        }
+
+        metrics.set_queue_depths(0, 0, 5);
+        let scraped = String::from_utf8(scrape_metrics(&metrics).unwrap()).unwrap();
+        assert!(scraped.contains("queue_depth{category=\"content\"} 5"));
+    }
+
     #[test]
     fn reset_worker_failures_resets_counter() {
         let metrics = ServiceMetrics::new(&MetricsSection {
@@ -0,0 +1,698 @@
+//! Cursor-based paging for search results.
+//!
+//! Results are ordered by `(score desc, doc_key asc)`. A cursor captures the last hit a client
+//! saw so the next page can resume strictly after it instead of re-collecting and skipping
+//! `offset` documents from the top.
+
+use anyhow::{Result, anyhow};
+use core_types::DocKey;
+use std::cmp::{Ordering, Reverse};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
+use std::time::Instant;
+use tantivy::collector::{Collector, Count, SegmentCollector, TopDocs};
+use tantivy::columnar::Column;
+use tantivy::query::{Query, Weight};
+use tantivy::{
+    DocAddress, DocId, DocSet, Order, Score, Searcher, SegmentOrdinal, SegmentReader, TERMINATED,
+};
+
+/// Name of the `u64` fast field holding the `DocKey` in both meta and content indices.
+pub const DOC_KEY_FIELD: &str = "doc_key";
+
+/// Position of the last hit returned to a client.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageCursor {
+    pub score: Score,
+    pub key: DocKey,
+}
+
+impl PageCursor {
+    pub fn new(score: Score, key: DocKey) -> Self {
+        Self { score, key }
+    }
+
+    /// Opaque wire form: `<score bits hex>.<doc key hex>`.
+    pub fn encode(&self) -> String {
+        format!("{:08x}.{:016x}", self.score.to_bits(), self.key.0)
+    }
+
+    pub fn decode(s: &str) -> Result<Self> {
+        let (score, key) = s
+            .split_once('.')
+            .ok_or_else(|| anyhow!("malformed cursor: {s}"))?;
+        let score = u32::from_str_radix(score, 16).map_err(|e| anyhow!("bad cursor score: {e}"))?;
+        let key = u64::from_str_radix(key, 16).map_err(|e| anyhow!("bad cursor key: {e}"))?;
+        Ok(Self::new(Score::from_bits(score), DocKey(key)))
+    }
+
+    /// True when a hit with `(score, key)` sorts strictly after this cursor.
+    pub fn is_before(&self, score: Score, key: DocKey) -> bool {
+        compare_ranked(self.score, self.key, score, key) == Ordering::Less
+    }
+}
+
+/// Total ordering used for result pages: higher score first, then lower `DocKey`.
+pub fn compare_ranked(a_score: Score, a_key: DocKey, b_score: Score, b_key: DocKey) -> Ordering {
+    b_score
+        .partial_cmp(&a_score)
+        .unwrap_or(Ordering::Equal)
+        .then_with(|| a_key.0.cmp(&b_key.0))
+}
+
+/// Sort key fed to Tantivy's top-k collector. Docs at or before the cursor are demoted below
+/// every eligible doc so they only surface when the page would otherwise be short.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+struct RankKey {
+    eligible: bool,
+    score: Score,
+    key: Reverse<u64>,
+}
+
+/// Docs a segment scan scores between deadline checks.
+const DEADLINE_CHECK_EVERY: u32 = 256;
+
+/// Wraps a collector so the search stops once `deadline` passes and the expiry is recorded.
+///
+/// The deadline is checked before each segment, after its scorer is built (where fuzzy and
+/// regex queries walk the term dictionary), before each collected doc and every
+/// [`DEADLINE_CHECK_EVERY`] docs scored, so a query matching nothing still stops in time.
+pub struct DeadlineCollector<C> {
+    inner: C,
+    deadline: Option<Instant>,
+    expired: Arc<AtomicBool>,
+}
+
+impl<C> DeadlineCollector<C> {
+    /// `None` never expires.
+    pub fn new(inner: C, deadline: Option<Instant>) -> Self {
+        Self {
+            inner,
+            deadline,
+            expired: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Shared flag set once any segment saw the deadline pass.
+    pub fn expired_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.expired)
+    }
+}
+
+impl<C: Collector> Collector for DeadlineCollector<C> {
+    type Fruit = C::Fruit;
+    type Child = DeadlineSegmentCollector<C::Child>;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> tantivy::Result<Self::Child> {
+        Ok(DeadlineSegmentCollector {
+            inner: self.inner.for_segment(segment_local_id, segment)?,
+            deadline: self.deadline,
+            expired: Arc::clone(&self.expired),
+            stopped: false,
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        self.inner.requires_scoring()
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<<Self::Child as SegmentCollector>::Fruit>,
+    ) -> tantivy::Result<Self::Fruit> {
+        self.inner.merge_fruits(segment_fruits)
+    }
+
+    fn collect_segment(
+        &self,
+        weight: &dyn Weight,
+        segment_ord: u32,
+        reader: &SegmentReader,
+    ) -> tantivy::Result<<Self::Child as SegmentCollector>::Fruit> {
+        let mut child = self.for_segment(segment_ord, reader)?;
+        if child.expire_if_due() {
+            return Ok(child.harvest());
+        }
+        let mut scorer = weight.scorer(reader, 1.0)?;
+        let alive = reader.alive_bitset();
+        let scoring = self.requires_scoring();
+        let mut doc = scorer.doc();
+        let mut scanned = 0u32;
+        while doc != TERMINATED {
+            if scanned.is_multiple_of(DEADLINE_CHECK_EVERY) && child.expire_if_due() {
+                break;
+            }
+            scanned = scanned.wrapping_add(1);
+            if alive.is_none_or(|alive| alive.is_alive(doc)) {
+                let score = if scoring { scorer.score() } else { 0.0 };
+                child.collect(doc, score);
+                if child.stopped {
+                    break;
+                }
+            }
+            doc = scorer.advance();
+        }
+        Ok(child.harvest())
+    }
+}
+
+pub struct DeadlineSegmentCollector<S> {
+    inner: S,
+    deadline: Option<Instant>,
+    expired: Arc<AtomicBool>,
+    stopped: bool,
+}
+
+impl<S> DeadlineSegmentCollector<S> {
+    /// Whether collection has stopped, recording the expiry the first time the deadline is seen
+    /// to have passed.
+    fn expire_if_due(&mut self) -> bool {
+        if !self.stopped
+            && self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            self.stopped = true;
+            self.expired.store(true, AtomicOrdering::Relaxed);
+        }
+        self.stopped
+    }
+}
+
+impl<S: SegmentCollector> SegmentCollector for DeadlineSegmentCollector<S> {
+    type Fruit = S::Fruit;
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        if !self.expire_if_due() {
+            self.inner.collect(doc, score);
+        }
+    }
+
+    fn harvest(self) -> Self::Fruit {
+        self.inner.harvest()
+    }
+}
+
+/// Wraps a collector so docs whose `DocKey` is in `keys` are skipped as if they had not matched.
+///
+/// Main-index docs that have a newer copy (or a deletion) in the delta tier are hidden this way,
+/// so pages and totals that union both tiers count each file once.
+pub struct HideKeys<C> {
+    inner: C,
+    keys: Option<Arc<HashSet<DocKey>>>,
+}
+
+impl<C> HideKeys<C> {
+    /// `None` or an empty set hides nothing and skips the per-doc key lookup.
+    pub fn new(inner: C, keys: Option<Arc<HashSet<DocKey>>>) -> Self {
+        Self {
+            inner,
+            keys: keys.filter(|k| !k.is_empty()),
+        }
+    }
+}
+
+impl<C: Collector> Collector for HideKeys<C> {
+    type Fruit = C::Fruit;
+    type Child = HideKeysSegmentCollector<C::Child>;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> tantivy::Result<Self::Child> {
+        let hidden = match &self.keys {
+            Some(keys) => Some((Arc::clone(keys), segment.fast_fields().u64(DOC_KEY_FIELD)?)),
+            None => None,
+        };
+        Ok(HideKeysSegmentCollector {
+            inner: self.inner.for_segment(segment_local_id, segment)?,
+            hidden,
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        self.inner.requires_scoring()
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<<Self::Child as SegmentCollector>::Fruit>,
+    ) -> tantivy::Result<Self::Fruit> {
+        self.inner.merge_fruits(segment_fruits)
+    }
+}
+
+pub struct HideKeysSegmentCollector<S> {
+    inner: S,
+    hidden: Option<(Arc<HashSet<DocKey>>, Column<u64>)>,
+}
+
+impl<S: SegmentCollector> SegmentCollector for HideKeysSegmentCollector<S> {
+    type Fruit = S::Fruit;
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        if let Some((keys, column)) = &self.hidden
+            && column
+                .first(doc)
+                .is_some_and(|key| keys.contains(&DocKey(key)))
+        {
+            return;
+        }
+        self.inner.collect(doc, score);
+    }
+
+    fn harvest(self) -> Self::Fruit {
+        self.inner.harvest()
+    }
+}
+
+/// One collected page: hits in rank order, the query's total match count, and the cursor to
+/// resume from if more hits remain.
+pub struct Page {
+    pub hits: Vec<(Score, DocKey, DocAddress)>,
+    pub total: usize,
+    pub next_cursor: Option<PageCursor>,
+    /// The deadline passed; `hits` and `total` only cover docs scanned before it, and no
+    /// cursor is offered since unscanned docs could still outrank them.
+    pub timed_out: bool,
+}
+
+/// Collect up to `limit` hits for `query`, starting after `cursor` when given, otherwise after
+/// skipping `offset` ranked hits. Collection stops at `deadline`, if any; docs keyed in
+/// `hidden` are left out of hits and total.
+pub fn collect_page(
+    searcher: &Searcher,
+    query: &dyn Query,
+    limit: usize,
+    offset: usize,
+    cursor: Option<PageCursor>,
+    deadline: Option<Instant>,
+    hidden: Option<Arc<HashSet<DocKey>>>,
+) -> Result<Page> {
+    let limit = limit.max(1);
+    let offset = if cursor.is_some() {
+        0
+    } else {
+        bounded_offset(searcher, offset)
+    };
+    // One extra hit tells us whether another page exists.
+    let top_k = limit.saturating_add(offset).saturating_add(1);
+
+    let collector = TopDocs::with_limit(top_k).tweak_score(move |segment: &SegmentReader| {
+        let keys = segment.fast_fields().u64(DOC_KEY_FIELD).ok();
+        move |doc: DocId, score: Score| {
+            let key = keys.as_ref().and_then(|c| c.first(doc)).unwrap_or_default();
+            RankKey {
+                eligible: cursor.is_none_or(|c| c.is_before(score, DocKey(key))),
+                score,
+                key: Reverse(key),
+            }
+        }
+    });
+
+    let collector = DeadlineCollector::new(HideKeys::new((collector, Count), hidden), deadline);
+    let expired = collector.expired_flag();
+    let (ranked, total) = searcher.search(query, &collector)?;
+    let timed_out = expired.load(AtomicOrdering::Relaxed);
+    let mut hits: Vec<_> = ranked
+        .into_iter()
+        .filter(|(rank, _)| rank.eligible)
+        .skip(offset)
+        .map(|(rank, addr)| (rank.score, DocKey(rank.key.0), addr))
+        .collect();
+
+    let next_cursor = if hits.len() > limit && !timed_out {
+        hits.truncate(limit);
+        hits.last()
+            .map(|(score, key, _)| PageCursor::new(*score, *key))
+    } else {
+        hits.truncate(limit);
+        None
+    };
+
+    Ok(Page {
+        hits,
+        total,
+        next_cursor,
+        timed_out,
+    })
+}
+
+/// `offset`, capped at the number of docs: the collector sizes its buffer by `offset + limit`,
+/// and skipping more docs than exist gives the same empty page.
+fn bounded_offset(searcher: &Searcher, offset: usize) -> usize {
+    offset.min(usize::try_from(searcher.num_docs()).unwrap_or(usize::MAX))
+}
+
+/// Fast-field type of a column results can be sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortValue {
+    I64,
+    U64,
+}
+
+/// Collect up to `limit` hits for `query` ordered by the fast field `field` instead of score,
+/// after skipping `offset`. Hits carry a score of 1.0 and no cursor is offered: field order
+/// pages by `offset` only. Docs keyed in `hidden` are left out, as in [`collect_page`].
+#[allow(clippy::too_many_arguments)]
+pub fn collect_sorted_page(
+    searcher: &Searcher,
+    query: &dyn Query,
+    field: &str,
+    value: SortValue,
+    descending: bool,
+    limit: usize,
+    offset: usize,
+    deadline: Option<Instant>,
+    hidden: Option<Arc<HashSet<DocKey>>>,
+) -> Result<Page> {
+    let order = if descending { Order::Desc } else { Order::Asc };
+    let top = TopDocs::with_limit(limit.max(1)).and_offset(bounded_offset(searcher, offset));
+    let (addrs, total, timed_out) = match value {
+        SortValue::I64 => run_sorted(
+            searcher,
+            query,
+            top.order_by_fast_field::<i64>(field, order),
+            deadline,
+            hidden,
+        )?,
+        SortValue::U64 => run_sorted(
+            searcher,
+            query,
+            top.order_by_fast_field::<u64>(field, order),
+            deadline,
+            hidden,
+        )?,
+    };
+    let hits = addrs
+        .into_iter()
+        .map(|addr| {
+            let key = searcher
+                .segment_reader(addr.segment_ord)
+                .fast_fields()
+                .u64(DOC_KEY_FIELD)
+                .ok()
+                .and_then(|c| c.first(addr.doc_id))
+                .unwrap_or_default();
+            (1.0, DocKey(key), addr)
+        })
+        .collect();
+
+    Ok(Page {
+        hits,
+        total,
+        next_cursor: None,
+        timed_out,
+    })
+}
+
+fn run_sorted<C, T>(
+    searcher: &Searcher,
+    query: &dyn Query,
+    collector: C,
+    deadline: Option<Instant>,
+    hidden: Option<Arc<HashSet<DocKey>>>,
+) -> Result<(Vec<DocAddress>, usize, bool)>
+where
+    C: Collector<Fruit = Vec<(T, DocAddress)>>,
+{
+    let collector = DeadlineCollector::new(HideKeys::new((collector, Count), hidden), deadline);
+    let expired = collector.expired_flag();
+    let (sorted, total) = searcher.search(query, &collector)?;
+    let addrs = sorted.into_iter().map(|(_, addr)| addr).collect();
+    Ok((addrs, total, expired.load(AtomicOrdering::Relaxed)))
+}
+
+/// Count the matches of `query` without ranking or loading any document, leaving out docs
+/// keyed in `hidden`. The flag is set when `deadline` passed and the count only covers docs
+/// scanned before it.
+pub fn count_matches(
+    searcher: &Searcher,
+    query: &dyn Query,
+    deadline: Option<Instant>,
+    hidden: Option<Arc<HashSet<DocKey>>>,
+) -> Result<(usize, bool)> {
+    let collector = DeadlineCollector::new(HideKeys::new(Count, hidden), deadline);
+    let expired = collector.expired_flag();
+    let total = searcher.search(query, &collector)?;
+    Ok((total, expired.load(AtomicOrdering::Relaxed)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_roundtrips() {
+        let c = PageCursor::new(1.25, DocKey::from_parts(3, 42));
+        assert_eq!(PageCursor::decode(&c.encode()).unwrap(), c);
+    }
+
+    #[test]
+    fn cursor_rejects_garbage() {
+        assert!(PageCursor::decode("nope").is_err());
+        assert!(PageCursor::decode("zz.00").is_err());
+    }
+
+    #[test]
+    fn ordering_breaks_ties_by_doc_key() {
+        let c = PageCursor::new(1.0, DocKey(10));
+        assert!(c.is_before(0.5, DocKey(1)));
+        assert!(c.is_before(1.0, DocKey(11)));
+        assert!(!c.is_before(1.0, DocKey(10)));
+        assert!(!c.is_before(1.0, DocKey(9)));
+        assert!(!c.is_before(2.0, DocKey(99)));
+    }
+
+    /// Counts docs, sleeping on each one to stand in for a pathological query.
+    struct SlowCount;
+
+    struct SlowCountSegment(usize);
+
+    impl Collector for SlowCount {
+        type Fruit = usize;
+        type Child = SlowCountSegment;
+
+        fn for_segment(
+            &self,
+            _: SegmentOrdinal,
+            _: &SegmentReader,
+        ) -> tantivy::Result<Self::Child> {
+            Ok(SlowCountSegment(0))
+        }
+
+        fn requires_scoring(&self) -> bool {
+            false
+        }
+
+        fn merge_fruits(&self, fruits: Vec<usize>) -> tantivy::Result<usize> {
+            Ok(fruits.into_iter().sum())
+        }
+    }
+
+    impl SegmentCollector for SlowCountSegment {
+        type Fruit = usize;
+
+        fn collect(&mut self, _: DocId, _: Score) {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            self.0 += 1;
+        }
+
+        fn harvest(self) -> usize {
+            self.0
+        }
+    }
+
+    #[test]
+    fn deadline_collector_returns_partial_results_in_time() {
+        use std::time::Duration;
+        use tantivy::schema::{FAST, Schema};
+        use tantivy::{Index, TantivyDocument};
+
+        let mut builder = Schema::builder();
+        let key = builder.add_u64_field(DOC_KEY_FIELD, FAST);
+        let index = Index::create_in_ram(builder.build());
+        let mut writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        for i in 0..200u64 {
+            let mut doc = TantivyDocument::default();
+            doc.add_u64(key, i);
+            writer.add_document(doc).unwrap();
+        }
+        writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+
+        // Unbounded, this collector needs ~1s.
+        let started = Instant::now();
+        let collector =
+            DeadlineCollector::new(SlowCount, Some(started + Duration::from_millis(30)));
+        let expired = collector.expired_flag();
+        let counted = searcher
+            .search(&tantivy::query::AllQuery, &collector)
+            .unwrap();
+
+        assert!(expired.load(AtomicOrdering::Relaxed));
+        assert!(counted > 0 && counted < 200, "counted {counted}");
+        assert!(started.elapsed() < Duration::from_millis(500));
+
+        let unbounded = DeadlineCollector::new(Count, None);
+        assert_eq!(
+            searcher
+                .search(&tantivy::query::AllQuery, &unbounded)
+                .unwrap(),
+            200
+        );
+        assert!(!unbounded.expired_flag().load(AtomicOrdering::Relaxed));
+    }
+
+    #[test]
+    fn collect_page_flags_timeouts_and_drops_cursor() {
+        use tantivy::schema::{FAST, Schema};
+        use tantivy::{Index, TantivyDocument};
+
+        let mut builder = Schema::builder();
+        let key = builder.add_u64_field(DOC_KEY_FIELD, FAST);
+        let index = Index::create_in_ram(builder.build());
+        let mut writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        for i in 0..10u64 {
+            let mut doc = TantivyDocument::default();
+            doc.add_u64(key, i);
+            writer.add_document(doc).unwrap();
+        }
+        writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+        let query = tantivy::query::AllQuery;
+
+        let page = collect_page(&searcher, &query, 3, 0, None, None, None).unwrap();
+        assert!(!page.timed_out);
+        assert!(page.next_cursor.is_some());
+
+        let page = collect_page(&searcher, &query, 3, 0, None, Some(Instant::now()), None).unwrap();
+        assert!(page.timed_out);
+        assert!(page.next_cursor.is_none());
+        assert!(page.hits.len() <= 3);
+    }
+
+    #[test]
+    fn deadline_stops_a_query_that_collects_nothing() {
+        use tantivy::query::{FuzzyTermQuery, RegexQuery};
+        use tantivy::schema::{FAST, Schema, TEXT};
+        use tantivy::{Index, TantivyDocument, Term};
+
+        let mut builder = Schema::builder();
+        let key = builder.add_u64_field(DOC_KEY_FIELD, FAST);
+        let name = builder.add_text_field("name", TEXT);
+        let index = Index::create_in_ram(builder.build());
+        let mut writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        for segment in 0..2u64 {
+            for i in 0..2_000u64 {
+                let mut doc = TantivyDocument::default();
+                doc.add_u64(key, segment * 2_000 + i);
+                doc.add_text(name, format!("file{i}a{segment}"));
+                writer.add_document(doc).unwrap();
+            }
+            writer.commit().unwrap();
+        }
+        let searcher = index.reader().unwrap().searcher();
+
+        // Both walk the whole term dictionary yet match no doc, so nothing ever reaches the
+        // collector: only the scan itself can notice the deadline.
+        let regex = RegexQuery::from_pattern("(.*1)*.*9.*9.*9.*z", name).unwrap();
+        let fuzzy = FuzzyTermQuery::new(Term::from_field_text(name, "zzzzzzzq"), 2, true);
+        for query in [&regex as &dyn Query, &fuzzy] {
+            let (total, expired) = count_matches(&searcher, query, None, None).unwrap();
+            assert_eq!((total, expired), (0, false));
+
+            let page =
+                collect_page(&searcher, query, 10, 0, None, Some(Instant::now()), None).unwrap();
+            assert!(page.timed_out);
+            assert!(page.hits.is_empty());
+            let (_, expired) = count_matches(&searcher, query, Some(Instant::now()), None).unwrap();
+            assert!(expired);
+        }
+    }
+
+    #[test]
+    fn hidden_keys_are_left_out_of_pages_and_totals() {
+        use tantivy::schema::{FAST, Schema};
+        use tantivy::{Index, TantivyDocument};
+
+        let mut builder = Schema::builder();
+        let key = builder.add_u64_field(DOC_KEY_FIELD, FAST);
+        let index = Index::create_in_ram(builder.build());
+        let mut writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        for i in 0..10u64 {
+            let mut doc = TantivyDocument::default();
+            doc.add_u64(key, i);
+            writer.add_document(doc).unwrap();
+        }
+        writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+        let query = tantivy::query::AllQuery;
+        let hidden = Some(Arc::new(HashSet::from([DocKey(0), DocKey(3)])));
+
+        let page = collect_page(&searcher, &query, 20, 0, None, None, hidden.clone()).unwrap();
+        assert_eq!(page.total, 8);
+        let keys: Vec<u64> = page.hits.iter().map(|(_, k, _)| k.0).collect();
+        assert_eq!(keys, [1, 2, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(count_matches(&searcher, &query, None, hidden).unwrap().0, 8);
+        assert_eq!(count_matches(&searcher, &query, None, None).unwrap().0, 10);
+    }
+
+    #[test]
+    fn sorted_page_orders_by_fast_field() {
+        use tantivy::schema::{FAST, Schema};
+        use tantivy::{Index, TantivyDocument};
+
+        let mut builder = Schema::builder();
+        let key = builder.add_u64_field(DOC_KEY_FIELD, FAST);
+        let modified = builder.add_i64_field("modified", FAST);
+        let index = Index::create_in_ram(builder.build());
+        let mut writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        for (k, ts) in [(1u64, 300i64), (2, 100), (3, 500), (4, 200)] {
+            let mut doc = TantivyDocument::default();
+            doc.add_u64(key, k);
+            doc.add_i64(modified, ts);
+            writer.add_document(doc).unwrap();
+        }
+        writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+        let query = tantivy::query::AllQuery;
+        let keys = |page: Page| page.hits.iter().map(|(_, k, _)| k.0).collect::<Vec<_>>();
+
+        let page = collect_sorted_page(
+            &searcher,
+            &query,
+            "modified",
+            SortValue::I64,
+            true,
+            2,
+            0,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(page.total, 4);
+        assert!(page.next_cursor.is_none());
+        assert_eq!(keys(page), vec![3, 1]);
+
+        let page = collect_sorted_page(
+            &searcher,
+            &query,
+            "modified",
+            SortValue::I64,
+            false,
+            2,
+            1,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(keys(page), vec![4, 1]);
+    }
+}
@@ -0,0 +1,138 @@
+//! Include/exclude path globbing applied before metadata is ingested.
+//!
+//! Patterns use glob syntax with either separator (`C:\Windows\**`, `**/node_modules/**`,
+//! `*.tmp`). A pattern without wildcards names a directory and also covers its subtree.
+//! Precedence: an exclude match always wins; a non-empty include list narrows everything else
+//! to paths matching at least one include.
+
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+#[derive(Debug, Clone, Default)]
+pub struct PathFilter {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+impl PathFilter {
+    /// Build a filter using the platform's case rules (case-insensitive on Windows).
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        Self::with_case(include, exclude, cfg!(windows))
+    }
+
+    /// Build a filter with explicit case sensitivity.
+    pub fn with_case(
+        include: &[String],
+        exclude: &[String],
+        case_insensitive: bool,
+    ) -> Result<Self> {
+        let include = if include.is_empty() {
+            None
+        } else {
+            Some(build_set(include, case_insensitive)?)
+        };
+        Ok(Self {
+            include,
+            exclude: build_set(exclude, case_insensitive)?,
+        })
+    }
+
+    /// True when both lists are empty and every path passes.
+    pub fn is_empty(&self) -> bool {
+        self.include.is_none() && self.exclude.is_empty()
+    }
+
+    /// Whether `path` should be indexed.
+    pub fn allows(&self, path: &str) -> bool {
+        let path = normalize(path);
+        if self.exclude.is_match(path.as_str()) {
+            return false;
+        }
+        self.include
+            .as_ref()
+            .is_none_or(|set| set.is_match(path.as_str()))
+    }
+}
+
+fn build_set(patterns: &[String], case_insensitive: bool) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for raw in patterns {
+        let pattern = normalize(raw);
+        let pattern = pattern.trim_end_matches('/');
+        if pattern.is_empty() {
+            continue;
+        }
+        let mut add = |p: &str| -> Result<()> {
+            let glob = GlobBuilder::new(p)
+                .case_insensitive(case_insensitive)
+                .build()
+                .with_context(|| format!("invalid path pattern {raw:?}"))?;
+            builder.add(glob);
+            Ok(())
+        };
+        add(pattern)?;
+        if !pattern.contains(['*', '?', '[', '{']) {
+            add(&format!("{pattern}/**"))?;
+        }
+    }
+    builder.build().context("build path pattern set")
+}
+
+/// Use `/` throughout so Windows paths and patterns compare uniformly (globs treat `\` as an
+/// escape).
+fn normalize(s: &str) -> String {
+    s.replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(include: &[&str], exclude: &[&str]) -> PathFilter {
+        let owned = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        PathFilter::with_case(&owned(include), &owned(exclude), true).unwrap()
+    }
+
+    #[test]
+    fn empty_filter_allows_everything() {
+        let f = filter(&[], &[]);
+        assert!(f.is_empty());
+        assert!(f.allows(r"C:\anything\at\all.txt"));
+    }
+
+    #[test]
+    fn excluded_subtrees_are_filtered() {
+        let f = filter(&[], &[r"C:\Windows\**", r"**\node_modules\**", "*.tmp"]);
+        assert!(!f.allows(r"C:\Windows\System32\kernel32.dll"));
+        assert!(!f.allows(r"c:\windows\notepad.exe"));
+        assert!(!f.allows(r"D:\src\app\node_modules\left-pad\index.js"));
+        assert!(!f.allows(r"C:\Users\me\Downloads\setup.TMP"));
+        assert!(f.allows(r"C:\Users\me\notes.txt"));
+        assert!(f.allows(r"C:\WindowsApps\readme.txt"));
+    }
+
+    #[test]
+    fn include_narrows_and_exclude_wins() {
+        let f = filter(
+            &[r"C:\Users", r"D:\Projects\**"],
+            &[r"C:\Users\*\AppData\**", "*.tmp"],
+        );
+        assert!(f.allows(r"C:\Users\me\notes.txt"));
+        assert!(f.allows(r"D:\Projects\ultra\src\main.rs"));
+        assert!(!f.allows(r"C:\Program Files\app.exe"));
+        assert!(!f.allows(r"C:\Users\me\AppData\Local\cache.db"));
+        assert!(!f.allows(r"D:\Projects\ultra\build.tmp"));
+    }
+
+    #[test]
+    fn case_sensitive_mode_respects_case() {
+        let f = PathFilter::with_case(&[], &["/home/*/Cache/**".to_string()], false).unwrap();
+        assert!(!f.allows("/home/me/Cache/blob"));
+        assert!(f.allows("/home/me/cache/blob"));
+    }
+
+    #[test]
+    fn invalid_pattern_is_an_error() {
+        assert!(PathFilter::new(&[], &["C:/[unclosed".to_string()]).is_err());
+    }
+}
@@ -1,4 +1,23 @@
-use ipc::QueryExpr;
+use anyhow::{Result, bail};
+use content_index::ContentFields;
+use core_types::config::SearchSection;
+use core_types::{FileFlags, FileKind};
+use ipc::{
+    FieldKind, QueryExpr, RangeExpr, RangeOp, RangeValue, SearchMode, TermExpr, TermModifier,
+};
+use meta_index::MetaFields;
+use std::ops::Bound;
+use tantivy::query::{
+    AllQuery, BooleanQuery, BoostQuery, ConstScorer, EnableScoring, Explanation, FuzzyTermQuery,
+    Occur, PhraseQuery, Query, QueryParser, RangeQuery, Scorer, TermQuery, Weight,
+};
+use tantivy::schema::{Field, IndexRecordOption, Value};
+use tantivy::{
+    DocId, DocSet, Index, Score, SegmentReader, TERMINATED, TantivyDocument, TantivyError, Term,
+};
+
+/// Longest term (in chars) still eligible for fuzzy matching.
+pub const MAX_FUZZY_TERM_CHARS: usize = 32;
 
 /// Optimizes a raw query AST for execution.
 pub struct QueryPlanner;
@@ -10,6 +29,89 @@ impl QueryPlanner {
         Self::flatten(Self::push_down_not(expr))
     }
 
+    /// Optimize and adapt the query to the search mode it will run under.
+    pub fn plan(expr: QueryExpr, mode: SearchMode) -> QueryExpr {
+        Self::plan_with_case(expr, mode, false)
+    }
+
+    /// Like [`Self::plan`]; a case-sensitive plan has no fuzzy terms, since edit-distance
+    /// matching runs on the case-folded `name` terms.
+    pub fn plan_with_case(expr: QueryExpr, mode: SearchMode, case_sensitive: bool) -> QueryExpr {
+        Self::restrict_fuzzy(Self::optimize(expr), mode, case_sensitive)
+    }
+
+    /// Pick the mode an `Auto` search of the (optimized) `expr` runs under, from the query alone.
+    /// Field-qualified metadata clauses stay on the metadata index, content-only or long free
+    /// text goes to the content index, and anything ambiguous consults both.
+    pub fn choose_mode(expr: &QueryExpr) -> ModePlan {
+        let mut leaves = Leaves::default();
+        leaves.visit(expr, false);
+        let list = |labels: &[String]| labels.join(", ");
+
+        let mut reasons = Vec::new();
+        let mode = if !leaves.either.is_empty() {
+            reasons.push(format!(
+                "unqualified terms may match names or content: {}",
+                list(&leaves.either)
+            ));
+            SearchMode::Hybrid
+        } else if !leaves.content.is_empty() && !leaves.meta.is_empty() {
+            reasons.push(format!(
+                "metadata filters ({}) mixed with content terms ({})",
+                list(&leaves.meta),
+                list(&leaves.content)
+            ));
+            SearchMode::Hybrid
+        } else if !leaves.content.is_empty() {
+            reasons.push(format!("content terms only: {}", list(&leaves.content)));
+            SearchMode::Content
+        } else if !leaves.meta.is_empty() {
+            reasons.push(format!("metadata fields only: {}", list(&leaves.meta)));
+            SearchMode::NameOnly
+        } else {
+            reasons.push("no positive clauses; the metadata index answers alone".to_string());
+            SearchMode::NameOnly
+        };
+        if leaves.negated > 0 {
+            reasons.push(format!(
+                "{} negated clause(s) filter results but do not pick the index",
+                leaves.negated
+            ));
+        }
+        let per_index = match mode {
+            SearchMode::NameOnly => 1,
+            SearchMode::Content => CONTENT_COST_FACTOR,
+            _ => 1 + CONTENT_COST_FACTOR,
+        };
+        ModePlan {
+            mode,
+            reasons,
+            estimated_cost: leaves.cost.max(1).saturating_mul(per_index),
+        }
+    }
+
+    /// Fuzzy matching is only worth its cost against the small `name` dictionary; elsewhere, or
+    /// for long multi-word values, fall back to an exact term.
+    fn restrict_fuzzy(expr: QueryExpr, mode: SearchMode, case_sensitive: bool) -> QueryExpr {
+        let recurse = |e| Self::restrict_fuzzy(e, mode, case_sensitive);
+        match expr {
+            QueryExpr::Term(mut t) => {
+                if matches!(t.modifier, TermModifier::Fuzzy(_)) {
+                    let short = t.value.trim().chars().count() <= MAX_FUZZY_TERM_CHARS
+                        && t.value.split_whitespace().count() <= 1;
+                    if case_sensitive || !matches!(mode, SearchMode::NameOnly) || !short {
+                        t.modifier = TermModifier::Term;
+                    }
+                }
+                QueryExpr::Term(t)
+            }
+            QueryExpr::Not(inner) => QueryExpr::Not(Box::new(recurse(*inner))),
+            QueryExpr::And(subs) => QueryExpr::And(subs.into_iter().map(recurse).collect()),
+            QueryExpr::Or(subs) => QueryExpr::Or(subs.into_iter().map(recurse).collect()),
+            leaf => leaf,
+        }
+    }
+
     /// Distribute NOTs: `Not(And([A, B]))` -> `Or([Not(A), Not(B)])` (De Morgan's).
     /// This canonicalizes negations to be closer to leaves.
     fn push_down_not(expr: QueryExpr) -> QueryExpr {
@@ -87,16 +189,671 @@ impl QueryPlanner {
     }
 }
 
+/// Unqualified text with at least this many words reads as document text, not a file name.
+pub const LONG_TEXT_WORDS: usize = 4;
+
+/// Content postings dwarf the name dictionary; scales `estimated_cost` per content lookup.
+const CONTENT_COST_FACTOR: u32 = 4;
+
+/// The mode [`QueryPlanner::choose_mode`] picked for an `Auto` search, and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModePlan {
+    /// `NameOnly`, `Content` or `Hybrid`.
+    pub mode: SearchMode,
+    pub reasons: Vec<String>,
+    /// Leaf costs (exact 1, range/phrase 2, prefix 3, substring 4, fuzzy 8) times the number
+    /// of index lookups, with content lookups weighted by `CONTENT_COST_FACTOR`.
+    pub estimated_cost: u32,
+}
+
+/// Positive leaves of a query, by which index can answer them.
+#[derive(Default)]
+struct Leaves {
+    meta: Vec<String>,
+    content: Vec<String>,
+    /// Short unqualified terms: a file name or a word inside a document.
+    either: Vec<String>,
+    negated: usize,
+    cost: u32,
+}
+
+impl Leaves {
+    fn visit(&mut self, expr: &QueryExpr, negated: bool) {
+        match expr {
+            QueryExpr::Not(inner) => self.visit(inner, !negated),
+            QueryExpr::And(subs) | QueryExpr::Or(subs) => {
+                subs.iter().for_each(|sub| self.visit(sub, negated))
+            }
+            QueryExpr::Range(r) => {
+                self.cost += 2;
+                if negated {
+                    self.negated += 1;
+                } else {
+                    self.meta.push(format!("{} range", field_label(r.field)));
+                }
+            }
+            QueryExpr::Term(t) => {
+                self.cost += match t.modifier {
+                    TermModifier::Term => 1,
+                    TermModifier::Phrase => 2,
+                    TermModifier::Prefix => 3,
+                    TermModifier::Substring => 4,
+                    TermModifier::Fuzzy(_) => 8,
+                };
+                if negated {
+                    self.negated += 1;
+                    return;
+                }
+                match t.field {
+                    Some(FieldKind::Content) => self.content.push(format!("content:{}", t.value)),
+                    Some(field) => self
+                        .meta
+                        .push(format!("{}:{}", field_label(field), t.value)),
+                    None if t.value.split_whitespace().count() >= LONG_TEXT_WORDS => self
+                        .content
+                        .push(format!("long free text \"{}\"", t.value.trim())),
+                    None => self.either.push(t.value.clone()),
+                }
+            }
+        }
+    }
+}
+
+fn field_label(field: FieldKind) -> String {
+    format!("{field:?}").to_lowercase()
+}
+
+/// The Tantivy fields one index exposes to the query language. Fields an index lacks are
+/// `None`; terms and ranges on them match nothing there.
+#[derive(Debug, Clone)]
+pub struct QueryFields {
+    pub name: Field,
+    pub name_cased: Option<Field>,
+    pub name_ngram: Option<Field>,
+    pub path: Field,
+    /// Segment-tokenized copy of `path` (`features.path_segments`).
+    pub path_segments: Option<Field>,
+    pub ext: Field,
+    pub content: Option<Field>,
+    /// Code-analyzed copy of `content` (`features.doc_type_analyzers`).
+    pub content_code: Option<Field>,
+    pub size: Field,
+    pub modified: Field,
+    pub created: Option<Field>,
+    pub volume: Field,
+    pub flags: Option<Field>,
+    /// What an unqualified term (`field: None`) searches, with the boost for each field.
+    pub default_fields: Vec<(FieldKind, f32)>,
+}
+
+impl QueryFields {
+    /// Replace the index's built-in default fields with `search.default_fields`, unless that
+    /// list is empty.
+    pub fn with_default_fields(mut self, fields: &[(FieldKind, f32)]) -> Self {
+        if !fields.is_empty() {
+            self.default_fields = fields.to_vec();
+        }
+        self
+    }
+}
+
+/// `search.default_fields` as query fields; empty when unset or invalid (config validation
+/// reports the invalid case on load).
+pub fn configured_default_fields(search: &SearchSection) -> Vec<(FieldKind, f32)> {
+    let Ok(specs) = search.default_field_boosts() else {
+        return Vec::new();
+    };
+    specs
+        .into_iter()
+        .filter_map(|(name, boost)| {
+            let field = match name {
+                "name" => FieldKind::Name,
+                "path" => FieldKind::Path,
+                "ext" => FieldKind::Ext,
+                "content" => FieldKind::Content,
+                _ => return None,
+            };
+            Some((field, boost))
+        })
+        .collect()
+}
+
+impl From<&MetaFields> for QueryFields {
+    fn from(f: &MetaFields) -> Self {
+        Self {
+            name: f.name,
+            name_cased: f.name_cased,
+            name_ngram: f.name_ngram,
+            path: f.path,
+            path_segments: f.path_segments,
+            ext: f.ext,
+            content: None,
+            content_code: None,
+            size: f.size,
+            modified: f.modified,
+            created: Some(f.created),
+            volume: f.volume,
+            flags: Some(f.flags),
+            default_fields: vec![(FieldKind::Name, 1.0), (FieldKind::Path, 1.0)],
+        }
+    }
+}
+
+impl From<&ContentFields> for QueryFields {
+    fn from(f: &ContentFields) -> Self {
+        Self {
+            name: f.name,
+            name_cased: None,
+            name_ngram: None,
+            path: f.path,
+            path_segments: f.path_segments,
+            ext: f.ext,
+            content: Some(f.content),
+            content_code: f.content_code,
+            size: f.size,
+            modified: f.modified,
+            created: None,
+            volume: f.volume,
+            flags: None,
+            default_fields: vec![(FieldKind::Name, 1.0), (FieldKind::Content, 1.0)],
+        }
+    }
+}
+
+/// Translate a (planned) query AST into a Tantivy query over one index.
+///
+/// `index` supplies the field analyzers so hand-built terms match what was indexed. Fails only
+/// for malformed ranges (a `Between` without `hi`, or `lo > hi`).
+pub fn to_tantivy(
+    expr: &QueryExpr,
+    fields: &QueryFields,
+    index: &Index,
+    case_sensitive: bool,
+) -> Result<Box<dyn Query>> {
+    let build = |q| to_tantivy(q, fields, index, case_sensitive);
+    Ok(match expr {
+        QueryExpr::Term(t) => term_query(t, fields, index, case_sensitive),
+        QueryExpr::Range(r) => range_query(r, fields)?,
+        // A query of only MustNot clauses matches nothing, so exclude from every document.
+        QueryExpr::Not(inner) => Box::new(BooleanQuery::new(vec![
+            (Occur::Must, Box::new(AllQuery) as Box<dyn Query>),
+            (Occur::MustNot, build(inner)?),
+        ])),
+        // An empty query (no clauses at all) matches every document.
+        QueryExpr::And(items) if items.is_empty() => Box::new(AllQuery),
+        QueryExpr::And(items) => Box::new(BooleanQuery::new(
+            items
+                .iter()
+                .map(|q| Ok((Occur::Must, build(q)?)))
+                .collect::<Result<Vec<_>>>()?,
+        )),
+        QueryExpr::Or(items) => Box::new(BooleanQuery::new(
+            items
+                .iter()
+                .map(|q| Ok((Occur::Should, build(q)?)))
+                .collect::<Result<Vec<_>>>()?,
+        )),
+    })
+}
+
+/// Drop docs whose path has any of `excluded` as whole consecutive directory segments,
+/// compared case-insensitively on any volume: `$Recycle.Bin` excludes `C:\$Recycle.Bin\...`
+/// but not `C:\docs\recycle_bin_notes.txt`. Indexes with the `path_segments` field match it
+/// directly; others narrow by the path's words and check the stored path.
+pub fn exclude_paths(
+    query: Box<dyn Query>,
+    fields: &QueryFields,
+    index: &Index,
+    excluded: &[String],
+) -> Box<dyn Query> {
+    let mut clauses = vec![(Occur::Must, query)];
+    for path in excluded {
+        let segments = path_segments(path);
+        if segments.is_empty() {
+            continue;
+        }
+        let clause: Box<dyn Query> = match fields.path_segments {
+            Some(field) => {
+                let terms: Vec<Term> = segments
+                    .iter()
+                    .map(|s| Term::from_field_text(field, s))
+                    .collect();
+                phrase_or_term(terms)
+            }
+            None => {
+                let words: Vec<Term> = analyzed_tokens(index, fields.path, path)
+                    .iter()
+                    .map(|t| Term::from_field_text(fields.path, t))
+                    .collect();
+                if words.is_empty() {
+                    continue;
+                }
+                Box::new(PathSegmentsQuery {
+                    candidates: phrase_or_term(words),
+                    path: fields.path,
+                    segments,
+                })
+            }
+        };
+        clauses.push((Occur::MustNot, clause));
+    }
+    if clauses.len() == 1 {
+        return clauses.pop().expect("one clause").1;
+    }
+    Box::new(BooleanQuery::new(clauses))
+}
+
+fn phrase_or_term(mut terms: Vec<Term>) -> Box<dyn Query> {
+    if terms.len() == 1 {
+        let term = terms.pop().expect("one term");
+        return Box::new(TermQuery::new(term, IndexRecordOption::Basic));
+    }
+    Box::new(PhraseQuery::new(terms))
+}
+
+/// `path` split on either separator into lowercased segments, as the `path_segments` field
+/// indexes it.
+fn path_segments(path: &str) -> Vec<String> {
+    path.split(['\\', '/'])
+        .filter(|s| !s.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Docs of `candidates` whose stored `path` holds `segments` consecutively. `candidates` keeps
+/// the doc store reads to paths that contain the segments' words at all.
+#[derive(Debug)]
+struct PathSegmentsQuery {
+    candidates: Box<dyn Query>,
+    path: Field,
+    segments: Vec<String>,
+}
+
+impl Clone for PathSegmentsQuery {
+    fn clone(&self) -> Self {
+        Self {
+            candidates: self.candidates.box_clone(),
+            path: self.path,
+            segments: self.segments.clone(),
+        }
+    }
+}
+
+impl Query for PathSegmentsQuery {
+    fn weight(&self, scoring: EnableScoring<'_>) -> tantivy::Result<Box<dyn Weight>> {
+        let candidates = self
+            .candidates
+            .weight(EnableScoring::disabled_from_schema(scoring.schema()))?;
+        Ok(Box::new(PathSegmentsWeight {
+            candidates,
+            path: self.path,
+            segments: self.segments.clone(),
+        }))
+    }
+}
+
+struct PathSegmentsWeight {
+    candidates: Box<dyn Weight>,
+    path: Field,
+    segments: Vec<String>,
+}
+
+impl Weight for PathSegmentsWeight {
+    fn scorer(&self, reader: &SegmentReader, boost: Score) -> tantivy::Result<Box<dyn Scorer>> {
+        let mut candidates = self.candidates.scorer(reader, boost)?;
+        let store = reader.get_store_reader(1)?;
+        let mut docs = Vec::new();
+        let mut doc = candidates.doc();
+        while doc != TERMINATED {
+            let stored: TantivyDocument = store.get(doc)?;
+            let path = stored.get_first(self.path).and_then(|v| v.as_str());
+            if path.is_some_and(|p| {
+                path_segments(p)
+                    .windows(self.segments.len())
+                    .any(|w| w == self.segments)
+            }) {
+                docs.push(doc);
+            }
+            doc = candidates.advance();
+        }
+        Ok(Box::new(ConstScorer::new(DocList::new(docs), boost)))
+    }
+
+    fn explain(&self, reader: &SegmentReader, doc: DocId) -> tantivy::Result<Explanation> {
+        if self.scorer(reader, 1.0)?.seek(doc) != doc {
+            return Err(TantivyError::InvalidArgument(format!(
+                "document #({doc}) does not match"
+            )));
+        }
+        Ok(Explanation::new("path segments", 1.0))
+    }
+}
+
+/// An ascending list of doc ids as a [`DocSet`].
+struct DocList {
+    docs: Vec<DocId>,
+    at: usize,
+}
+
+impl DocList {
+    fn new(docs: Vec<DocId>) -> Self {
+        Self { docs, at: 0 }
+    }
+}
+
+impl DocSet for DocList {
+    fn advance(&mut self) -> DocId {
+        self.at = (self.at + 1).min(self.docs.len());
+        self.doc()
+    }
+
+    fn doc(&self) -> DocId {
+        self.docs.get(self.at).copied().unwrap_or(TERMINATED)
+    }
+
+    fn size_hint(&self) -> u32 {
+        (self.docs.len() - self.at) as u32
+    }
+}
+
+fn match_nothing() -> Box<dyn Query> {
+    Box::new(BooleanQuery::new(vec![]))
+}
+
+fn term_query(
+    term: &TermExpr,
+    fields: &QueryFields,
+    index: &Index,
+    case_sensitive: bool,
+) -> Box<dyn Query> {
+    let value = term.value.trim();
+    if value.is_empty() {
+        return match_nothing();
+    }
+    // Indexes built before `name_cased` existed can only match names case-insensitively.
+    let name = match (case_sensitive, fields.name_cased) {
+        (true, Some(cased)) => cased,
+        _ => fields.name,
+    };
+    let qualified;
+    let targets = match term.field {
+        Some(f) => {
+            qualified = [(f, 1.0)];
+            &qualified[..]
+        }
+        None => &fields.default_fields[..],
+    };
+    // Substrings shorter than the smallest gram produce no query and fall through to `Term`.
+    let mut substring = match (term.modifier, fields.name_ngram) {
+        (TermModifier::Substring, Some(ngram)) => substring_query(index, ngram, value),
+        _ => None,
+    };
+
+    let mut clauses = Vec::new();
+    for &(field, boost) in targets {
+        let query = match field {
+            FieldKind::Ext => exact_term(fields.ext, value),
+            FieldKind::Name => match (term.modifier, substring.take()) {
+                (_, Some(q)) => q,
+                (TermModifier::Fuzzy(distance), _) => fuzzy_query(index, name, value, distance),
+                (TermModifier::Prefix, _) => exact_term(name, &single_token(index, name, value)),
+                _ => parsed(index, name, value),
+            },
+            FieldKind::Path => match (term.modifier, fields.path_segments) {
+                // Unqualified terms keep word matching; `path:` asks for directories.
+                (TermModifier::Term | TermModifier::Phrase, Some(segments))
+                    if term.field.is_some() =>
+                {
+                    segment_query(index, segments, value)
+                }
+                (TermModifier::Prefix, _) => {
+                    exact_term(fields.path, &single_token(index, fields.path, value))
+                }
+                _ => parsed(index, fields.path, value),
+            },
+            FieldKind::Content => match (fields.content, term.modifier) {
+                (None, _) => continue,
+                (Some(c), TermModifier::Fuzzy(distance)) => fuzzy_query(index, c, value, distance),
+                (Some(c), TermModifier::Prefix) => exact_term(c, &single_token(index, c, value)),
+                (Some(c), _) => {
+                    // Source files are also indexed word by word: `getUserName` -> `get user name`.
+                    if let Some(code) = fields.content_code {
+                        clauses.push((Occur::Should, boosted(parsed(index, code, value), boost)));
+                    }
+                    parsed(index, c, value)
+                }
+            },
+            FieldKind::Flags => match (fields.flags, FileFlags::from_query_name(value)) {
+                (Some(f), Some(flag)) => flags_query(f, flag),
+                _ => continue,
+            },
+            FieldKind::Kind => match FileKind::from_name(value) {
+                Some(kind) => kind_query(fields.ext, kind),
+                None => continue,
+            },
+            _ => continue,
+        };
+        clauses.push((Occur::Should, boosted(query, boost)));
+    }
+    boosted(
+        Box::new(BooleanQuery::new(clauses)),
+        term.boost.unwrap_or(1.0),
+    )
+}
+
+fn boosted(query: Box<dyn Query>, boost: f32) -> Box<dyn Query> {
+    if boost == 1.0 {
+        query
+    } else {
+        Box::new(BoostQuery::new(query, boost))
+    }
+}
+
+/// Docs whose extension [`FileKind::from_ext`] classifies as `kind`: any of its extensions,
+/// or for `Other`, none of the listed ones.
+fn kind_query(ext: Field, kind: FileKind) -> Box<dyn Query> {
+    let exts = |kind: FileKind, occur: Occur| {
+        kind.extensions()
+            .iter()
+            .map(move |e| (occur, exact_term(ext, e)))
+    };
+    if kind != FileKind::Other {
+        return Box::new(BooleanQuery::new(exts(kind, Occur::Should).collect()));
+    }
+    let mut clauses: Vec<(Occur, Box<dyn Query>)> = vec![(Occur::Must, Box::new(AllQuery))];
+    for known in FileKind::ALL {
+        clauses.extend(exts(known, Occur::MustNot));
+    }
+    Box::new(BooleanQuery::new(clauses))
+}
+
+fn exact_term(field: Field, text: &str) -> Box<dyn Query> {
+    Box::new(TermQuery::new(
+        Term::from_field_text(field, text),
+        IndexRecordOption::WithFreqs,
+    ))
+}
+
+/// Match whole path segments: `Projects` finds `...\Projects\...`, and `me\Projects` needs the
+/// two directories adjacent. Not parsed, since paths are full of query syntax (`\`, `:`).
+fn segment_query(index: &Index, field: Field, value: &str) -> Box<dyn Query> {
+    let terms: Vec<Term> = analyzed_tokens(index, field, value)
+        .iter()
+        .map(|t| Term::from_field_text(field, t))
+        .collect();
+    match terms.len() {
+        0 => match_nothing(),
+        1 => Box::new(TermQuery::new(
+            terms[0].clone(),
+            IndexRecordOption::WithFreqs,
+        )),
+        _ => Box::new(PhraseQuery::new(terms)),
+    }
+}
+
+/// Parse `value` against one field, all words required; unparsable input matches nothing.
+fn parsed(index: &Index, field: Field, value: &str) -> Box<dyn Query> {
+    let mut parser = QueryParser::for_index(index, vec![field]);
+    parser.set_conjunction_by_default();
+    parser
+        .parse_query(value)
+        .unwrap_or_else(|_| match_nothing())
+}
+
+/// Ranges run over fast fields: sizes, volumes and flags are u64, timestamps i64. Values of
+/// the other width are clamped into range. `Gt`/`Ge`/`Lt`/`Le` compare against `lo`; `Between`
+/// is inclusive on both ends.
+fn range_query(range: &RangeExpr, fields: &QueryFields) -> Result<Box<dyn Query>> {
+    let (field, signed) = match range.field {
+        FieldKind::Size => (Some(fields.size), false),
+        FieldKind::Volume => (Some(fields.volume), false),
+        FieldKind::Flags => (fields.flags, false),
+        FieldKind::Modified => (Some(fields.modified), true),
+        FieldKind::Created => (fields.created, true),
+        _ => (None, false),
+    };
+    let Some(field) = field else {
+        return Ok(match_nothing());
+    };
+    let (lo, hi) = match range.value {
+        RangeValue::U64 { lo, hi } => (lo as i128, hi.map(i128::from)),
+        RangeValue::I64 { lo, hi } => (lo as i128, hi.map(i128::from)),
+    };
+    let term = |v: i128| {
+        if signed {
+            Term::from_field_i64(field, v.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+        } else {
+            Term::from_field_u64(field, v.clamp(0, u64::MAX as i128) as u64)
+        }
+    };
+    let (lower, upper) = match range.op {
+        RangeOp::Gt => (Bound::Excluded(term(lo)), Bound::Unbounded),
+        RangeOp::Ge => (Bound::Included(term(lo)), Bound::Unbounded),
+        RangeOp::Lt => (Bound::Unbounded, Bound::Excluded(term(lo))),
+        RangeOp::Le => (Bound::Unbounded, Bound::Included(term(lo))),
+        RangeOp::Between => {
+            let Some(hi) = hi else {
+                bail!(
+                    "{:?} range uses Between but has no upper bound (hi)",
+                    range.field
+                );
+            };
+            if lo > hi {
+                bail!("{:?} range has lo {lo} greater than hi {hi}", range.field);
+            }
+            (Bound::Included(term(lo)), Bound::Included(term(hi)))
+        }
+    };
+    Ok(Box::new(RangeQuery::new(lower, upper)))
+}
+
+/// Docs whose `flags` value has every bit of `flag` set. Fast fields only answer ranges, so
+/// the matching values (all within the few known bits) are merged into runs and OR-ed.
+pub(crate) fn flags_query(field: Field, flag: FileFlags) -> Box<dyn Query> {
+    let bits = u64::from(flag.bits());
+    let all = u64::from(FileFlags::all().bits());
+    let mut runs: Vec<(u64, u64)> = Vec::new();
+    for v in (0..=all).filter(|v| v & bits == bits) {
+        match runs.last_mut() {
+            Some((_, hi)) if *hi + 1 == v => *hi = v,
+            _ => runs.push((v, v)),
+        }
+    }
+    let clauses = runs
+        .into_iter()
+        .map(|(lo, hi)| {
+            let range: Box<dyn Query> = Box::new(RangeQuery::new(
+                Bound::Included(Term::from_field_u64(field, lo)),
+                Bound::Included(Term::from_field_u64(field, hi)),
+            ));
+            (Occur::Should, range)
+        })
+        .collect();
+    Box::new(BooleanQuery::new(clauses))
+}
+
+/// Upper bound on Levenshtein distance; larger automata blow up on big term dictionaries.
+const MAX_FUZZY_DISTANCE: u8 = 2;
+
+/// Clamp the requested edit distance by term length so short terms don't match everything.
+pub(crate) fn fuzzy_distance(token: &str, requested: u8) -> u8 {
+    let cap = match token.chars().count() {
+        0..=2 => 0,
+        3..=5 => 1,
+        _ => MAX_FUZZY_DISTANCE,
+    };
+    requested.min(cap)
+}
+
+/// Run `value` through `field`'s analyzer so hand-built terms match what the index stored
+/// (lowercased, accent-folded, or verbatim, depending on the field).
+pub(crate) fn analyzed_tokens(index: &Index, field: Field, value: &str) -> Vec<String> {
+    let Ok(mut analyzer) = index.tokenizer_for_field(field) else {
+        return value.split_whitespace().map(str::to_lowercase).collect();
+    };
+    let mut tokens = Vec::new();
+    analyzer
+        .token_stream(value)
+        .process(&mut |t| tokens.push(t.text.clone()));
+    tokens
+}
+
+/// `value` as a single indexed term: analyzed when that yields one token, verbatim otherwise.
+fn single_token(index: &Index, field: Field, value: &str) -> String {
+    match analyzed_tokens(index, field, value).as_slice() {
+        [one] => one.clone(),
+        _ => value.to_string(),
+    }
+}
+
+/// Match names containing `value` using the n-gram field: every longest gram must be present.
+/// `None` when `value` is shorter than the smallest indexed gram.
+fn substring_query(index: &Index, field: Field, value: &str) -> Option<Box<dyn Query>> {
+    let mut grams = analyzed_tokens(index, field, value);
+    let longest = grams.iter().map(|g| g.chars().count()).max()?;
+    grams.retain(|g| g.chars().count() == longest);
+    grams.sort();
+    grams.dedup();
+    let clauses = grams
+        .into_iter()
+        .map(|gram| {
+            let term = Term::from_field_text(field, &gram);
+            let q: Box<dyn Query> = Box::new(TermQuery::new(term, IndexRecordOption::WithFreqs));
+            (Occur::Must, q)
+        })
+        .collect();
+    Some(Box::new(BooleanQuery::new(clauses)))
+}
+
+/// Build a fuzzy match for each analyzed token; all tokens must match.
+fn fuzzy_query(index: &Index, field: Field, value: &str, distance: u8) -> Box<dyn Query> {
+    let clauses = analyzed_tokens(index, field, value)
+        .into_iter()
+        .map(|token| {
+            let dist = fuzzy_distance(&token, distance);
+            let term = Term::from_field_text(field, &token);
+            let q: Box<dyn Query> = if dist == 0 {
+                Box::new(TermQuery::new(term, IndexRecordOption::WithFreqs))
+            } else {
+                Box::new(FuzzyTermQuery::new(term, dist, true))
+            };
+            (Occur::Must, q)
+        })
+        .collect();
+    Box::new(BooleanQuery::new(clauses))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ipc::{TermExpr, TermModifier};
+    use ipc::TermExpr;
 
     fn term(val: &str) -> QueryExpr {
         QueryExpr::Term(TermExpr {
             field: None,
             value: val.into(),
             modifier: TermModifier::Term,
+            boost: None,
         })
     }
 
@@ -128,4 +885,565 @@ mod tests {
             assert!(matches!(subs[1], QueryExpr::Not(_)));
         }
     }
+
+    fn fuzzy(val: &str, distance: u8) -> QueryExpr {
+        QueryExpr::Term(TermExpr {
+            field: None,
+            value: val.into(),
+            modifier: TermModifier::Fuzzy(distance),
+            boost: None,
+        })
+    }
+
+    fn modifier(expr: &QueryExpr) -> TermModifier {
+        match expr {
+            QueryExpr::Term(t) => t.modifier,
+            other => panic!("expected term, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn fuzzy_kept_in_name_mode() {
+        let planned = QueryPlanner::plan(fuzzy("repot", 1), SearchMode::NameOnly);
+        assert_eq!(modifier(&planned), TermModifier::Fuzzy(1));
+    }
+
+    #[test]
+    fn fuzzy_downgraded_outside_name_mode() {
+        for mode in [SearchMode::Auto, SearchMode::Content, SearchMode::Hybrid] {
+            let planned = QueryPlanner::plan(fuzzy("repot", 1), mode);
+            assert_eq!(modifier(&planned), TermModifier::Term, "mode {mode:?}");
+        }
+    }
+
+    #[test]
+    fn fuzzy_downgraded_for_long_or_multiword_terms() {
+        let long = "x".repeat(MAX_FUZZY_TERM_CHARS + 1);
+        let planned = QueryPlanner::plan(fuzzy(&long, 2), SearchMode::NameOnly);
+        assert_eq!(modifier(&planned), TermModifier::Term);
+        let planned = QueryPlanner::plan(fuzzy("annual repot", 1), SearchMode::NameOnly);
+        assert_eq!(modifier(&planned), TermModifier::Term);
+    }
+
+    #[test]
+    fn case_sensitive_plan_drops_fuzzy() {
+        let planned = QueryPlanner::plan_with_case(fuzzy("Repot", 1), SearchMode::NameOnly, true);
+        assert_eq!(modifier(&planned), TermModifier::Term);
+    }
+
+    #[test]
+    fn fuzzy_distance_is_capped() {
+        assert_eq!(fuzzy_distance("report", 9), MAX_FUZZY_DISTANCE);
+        assert_eq!(fuzzy_distance("repot", 2), 1);
+        assert_eq!(fuzzy_distance("ab", 2), 0);
+    }
+
+    fn meta_index() -> (Index, QueryFields) {
+        let (schema, fields) = meta_index::build_schema();
+        let index = Index::create_in_ram(schema);
+        meta_index::register_tokenizers(&index);
+        (index, QueryFields::from(&fields))
+    }
+
+    fn content_index() -> (Index, QueryFields) {
+        let idx = content_index::create_in_ram().unwrap();
+        let fields = QueryFields::from(&idx.fields);
+        (idx.index, fields)
+    }
+
+    fn field_term(field: FieldKind, val: &str, modifier: TermModifier) -> QueryExpr {
+        QueryExpr::Term(TermExpr {
+            field: Some(field),
+            value: val.into(),
+            modifier,
+            boost: None,
+        })
+    }
+
+    fn size_range(op: RangeOp, lo: u64, hi: Option<u64>) -> QueryExpr {
+        QueryExpr::Range(RangeExpr {
+            field: FieldKind::Size,
+            op,
+            value: RangeValue::U64 { lo, hi },
+        })
+    }
+
+    fn clauses(query: &dyn Query) -> &[(Occur, Box<dyn Query>)] {
+        query
+            .downcast_ref::<BooleanQuery>()
+            .expect("boolean query")
+            .clauses()
+    }
+
+    fn occurs(query: &dyn Query) -> Vec<Occur> {
+        clauses(query).iter().map(|(occur, _)| *occur).collect()
+    }
+
+    #[test]
+    fn not_wraps_must_not() {
+        let (index, fields) = meta_index();
+        let q = to_tantivy(&QueryExpr::Not(Box::new(term("a"))), &fields, &index, false).unwrap();
+        assert_eq!(occurs(q.as_ref()), [Occur::Must, Occur::MustNot]);
+    }
+
+    #[test]
+    fn and_or_map_to_must_and_should() {
+        let (index, fields) = meta_index();
+        let and = to_tantivy(
+            &QueryExpr::And(vec![term("a"), term("b")]),
+            &fields,
+            &index,
+            false,
+        )
+        .unwrap();
+        assert_eq!(occurs(and.as_ref()), [Occur::Must, Occur::Must]);
+        let or = to_tantivy(
+            &QueryExpr::Or(vec![term("a"), term("b"), term("c")]),
+            &fields,
+            &index,
+            false,
+        )
+        .unwrap();
+        assert_eq!(occurs(or.as_ref()), [Occur::Should; 3]);
+    }
+
+    #[test]
+    fn unqualified_term_uses_index_default_fields() {
+        let (index, fields) = meta_index();
+        let q = to_tantivy(&term("report"), &fields, &index, false).unwrap();
+        assert_eq!(clauses(q.as_ref()).len(), 2, "name + path");
+
+        let (index, fields) = content_index();
+        let q = to_tantivy(&term("report"), &fields, &index, false).unwrap();
+        assert_eq!(clauses(q.as_ref()).len(), 2, "name + content");
+    }
+
+    #[test]
+    fn configured_default_fields_replace_the_index_set() {
+        let search = SearchSection {
+            default_fields: vec!["name".into()],
+            ..SearchSection::default()
+        };
+        let configured = configured_default_fields(&search);
+        assert_eq!(configured, [(FieldKind::Name, 1.0)]);
+
+        let (index, fields) = content_index();
+        let fields = fields.with_default_fields(&configured);
+        let q = to_tantivy(&term("report"), &fields, &index, false).unwrap();
+        let [(Occur::Should, clause)] = clauses(q.as_ref()) else {
+            panic!("expected a single name clause");
+        };
+        let mut searched = Vec::new();
+        clause.query_terms(&mut |t, _| searched.push(t.field()));
+        assert_eq!(searched, [fields.name]);
+
+        let boosted = configured_default_fields(&SearchSection {
+            default_fields: vec!["name^3".into(), "path".into()],
+            ..SearchSection::default()
+        });
+        assert_eq!(boosted, [(FieldKind::Name, 3.0), (FieldKind::Path, 1.0)]);
+        let q = to_tantivy(
+            &term("report"),
+            &fields.with_default_fields(&boosted),
+            &index,
+            false,
+        )
+        .unwrap();
+        assert!(
+            clauses(q.as_ref())[0]
+                .1
+                .downcast_ref::<BoostQuery>()
+                .is_some()
+        );
+        assert!(
+            clauses(q.as_ref())[1]
+                .1
+                .downcast_ref::<BoostQuery>()
+                .is_none()
+        );
+    }
+
+    /// Index `report.txt` (the word only in its name) and `notes.txt` (only in its content)
+    /// and return the doc ranked first for `expr`: 0 for the name match, 1 for the content one.
+    fn top_of_name_vs_content(expr: &QueryExpr, default_fields: &[(FieldKind, f32)]) -> u32 {
+        use content_index::{ContentDoc, WriterConfig};
+        use core_types::DocKey;
+        use tantivy::collector::TopDocs;
+
+        let idx = content_index::create_in_ram().unwrap();
+        // One indexing thread, so both docs land in one segment.
+        let single = WriterConfig {
+            num_threads: 1,
+            ..WriterConfig::default()
+        };
+        let mut writer = content_index::create_writer(&idx, &single).unwrap();
+        for (frn, name, text) in [
+            (1, "report.txt", "quarterly numbers"),
+            (2, "notes.txt", "draft of the report"),
+        ] {
+            let doc = ContentDoc {
+                key: DocKey::from_parts(1, frn),
+                volume: 1,
+                name: Some(name.into()),
+                path: None,
+                ext: Some("txt".into()),
+                size: 0,
+                modified: 0,
+                content_lang: None,
+                content: text.into(),
+            };
+            content_index::add_content_doc(&mut writer, &idx.fields, &doc).unwrap();
+        }
+        writer.commit().unwrap();
+        let searcher = content_index::open_reader(&idx).unwrap().searcher();
+        let fields = QueryFields::from(&idx.fields).with_default_fields(default_fields);
+        let q = to_tantivy(expr, &fields, &idx.index, false).unwrap();
+        let hits = searcher
+            .search(q.as_ref(), &TopDocs::with_limit(2))
+            .unwrap();
+        assert_eq!(hits.len(), 2);
+        // One segment, so doc ids follow insertion order.
+        hits[0].1.doc_id
+    }
+
+    #[test]
+    fn default_field_boosts_order_hits() {
+        let report = term("report");
+        let name_first = [(FieldKind::Name, 10.0), (FieldKind::Content, 1.0)];
+        let content_first = [(FieldKind::Name, 1.0), (FieldKind::Content, 10.0)];
+        assert_eq!(top_of_name_vs_content(&report, &name_first), 0);
+        assert_eq!(top_of_name_vs_content(&report, &content_first), 1);
+    }
+
+    #[test]
+    fn term_boosts_order_hits() {
+        let top = |query: &str| {
+            let expr = QueryPlanner::optimize(ipc::query::parse_query(query).unwrap());
+            top_of_name_vs_content(&expr, &[])
+        };
+        assert_eq!(top("name:report^3 OR content:report"), 0);
+        assert_eq!(top("name:report OR content:report^3"), 1);
+
+        let (index, fields) = meta_index();
+        let boosted = QueryExpr::Term(TermExpr {
+            field: None,
+            value: "report".into(),
+            modifier: TermModifier::Term,
+            boost: Some(2.0),
+        });
+        let q = to_tantivy(&boosted, &fields, &index, false).unwrap();
+        assert!(q.downcast_ref::<BoostQuery>().is_some());
+        let q = to_tantivy(&term("report"), &fields, &index, false).unwrap();
+        assert!(q.downcast_ref::<BoostQuery>().is_none());
+    }
+
+    #[test]
+    fn content_terms_also_search_code_analyzed_field() {
+        use content_index::{ContentDoc, WriterConfig};
+        use core_types::DocKey;
+        use tantivy::collector::DocSetCollector;
+
+        let mut idx = content_index::create_in_ram().unwrap();
+        idx.set_doc_type_analyzers(true);
+        let mut writer = content_index::create_writer(&idx, &WriterConfig::default()).unwrap();
+        for (frn, ext, text) in [
+            (1, "rs", "let name = getUserName();"),
+            (2, "md", "call getUserName to fetch it"),
+            (3, "txt", "the user name field"),
+        ] {
+            let doc = ContentDoc {
+                key: DocKey::from_parts(1, frn),
+                volume: 1,
+                name: None,
+                path: None,
+                ext: Some(ext.into()),
+                size: 0,
+                modified: 0,
+                content_lang: None,
+                content: text.into(),
+            };
+            content_index::add_content_doc(&mut writer, &idx.fields, &doc).unwrap();
+        }
+        writer.commit().unwrap();
+        let searcher = content_index::open_reader(&idx).unwrap().searcher();
+        let fields = QueryFields::from(&idx.fields);
+        let hits = |value: &str| {
+            let expr = field_term(FieldKind::Content, value, TermModifier::Term);
+            let q = to_tantivy(&expr, &fields, &idx.index, false).unwrap();
+            searcher.search(q.as_ref(), &DocSetCollector).unwrap().len()
+        };
+
+        assert_eq!(hits("getUserName"), 2, "exact identifier in code and prose");
+        // Only the source file is split into `get user name`.
+        assert_eq!(hits("getUser"), 1);
+        assert_eq!(
+            hits("user name"),
+            2,
+            "prose words plus the split identifier"
+        );
+    }
+
+    #[test]
+    fn path_terms_match_whole_segments_when_enabled() {
+        use core_types::{DocKey, FileFlags, FileMeta};
+        use tantivy::collector::DocSetCollector;
+        use tantivy::schema::{TantivyDocument, Value};
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut meta = meta_index::open_or_create_index(dir.path()).unwrap();
+        let hits = |meta: &meta_index::MetaIndex, value: &str| {
+            let searcher = meta_index::open_reader(meta).unwrap().searcher();
+            let expr = field_term(FieldKind::Path, value, TermModifier::Term);
+            let q = to_tantivy(&expr, &QueryFields::from(&meta.fields), &meta.index, false);
+            let mut names: Vec<String> = searcher
+                .search(q.unwrap().as_ref(), &DocSetCollector)
+                .unwrap()
+                .into_iter()
+                .map(|addr| {
+                    let doc: TantivyDocument = searcher.doc(addr).unwrap();
+                    let name = doc.get_first(meta.fields.name).unwrap();
+                    name.as_str().unwrap().to_string()
+                })
+                .collect();
+            names.sort();
+            names
+        };
+        let write = |meta: &meta_index::MetaIndex, frn: u64, path: &str| {
+            let name = path.rsplit('\\').next().unwrap().to_string();
+            let file = FileMeta::new(
+                DocKey::from_parts(1, frn),
+                1,
+                None,
+                name,
+                Some(path.into()),
+                0,
+                0,
+                0,
+                FileFlags::empty(),
+            );
+            let cfg = meta_index::WriterConfig::default();
+            let mut writer = meta_index::create_writer(meta, &cfg).unwrap();
+            meta_index::add_file_meta_batch(&mut writer, &meta.fields, [file]).unwrap();
+            writer.commit().unwrap();
+        };
+
+        write(&meta, 1, r"C:\Users\me\Projects-Old\old.txt");
+        assert_eq!(hits(&meta, "Projects"), ["old.txt"], "word match while off");
+
+        meta.set_path_segments(true);
+        write(&meta, 2, r"C:\Users\me\Projects\x.txt");
+        write(&meta, 3, r"C:\Users\me\Projects-Archive\y.txt");
+        write(&meta, 4, r"D:\MyProjects\z.txt");
+        assert_eq!(hits(&meta, "Projects"), ["x.txt"]);
+        assert_eq!(hits(&meta, "projects-archive"), ["y.txt"]);
+        assert_eq!(hits(&meta, r"me\Projects"), ["x.txt"]);
+        assert!(
+            hits(&meta, r"Users\Projects").is_empty(),
+            "segments must be adjacent"
+        );
+    }
+
+    #[test]
+    fn kind_terms_match_the_extensions_of_that_kind() {
+        use core_types::{DocKey, FileMeta};
+        use tantivy::collector::Count;
+
+        let meta = meta_index::create_in_ram().unwrap();
+        let files = ["a.png", "b.JPG", "c.docx", "d.exe", "e"].map(|name| name.to_string());
+        let mut writer =
+            meta_index::create_writer(&meta, &meta_index::WriterConfig::default()).unwrap();
+        let metas = files.iter().enumerate().map(|(i, name)| {
+            FileMeta::new(
+                DocKey::from_parts(1, i as u64),
+                1,
+                None,
+                name.clone(),
+                None,
+                0,
+                0,
+                0,
+                FileFlags::empty(),
+            )
+        });
+        meta_index::add_file_meta_batch(&mut writer, &meta.fields, metas).unwrap();
+        writer.commit().unwrap();
+
+        let searcher = meta_index::open_reader(&meta).unwrap().searcher();
+        let fields = QueryFields::from(&meta.fields);
+        let count = |kind: &str| {
+            let expr = field_term(FieldKind::Kind, kind, TermModifier::Term);
+            let q = to_tantivy(&expr, &fields, &meta.index, false).unwrap();
+            searcher.search(q.as_ref(), &Count).unwrap()
+        };
+        assert_eq!(count("image"), 2);
+        assert_eq!(count("Office"), 1);
+        assert_eq!(count("archive"), 0);
+        // `other` is everything no kind lists, including files without an extension.
+        assert_eq!(count("other"), 2);
+        assert_eq!(count("spreadsheet"), 0, "unknown kinds match nothing");
+    }
+
+    #[test]
+    fn term_modifiers_pick_query_types() {
+        let (index, fields) = meta_index();
+        let single = |expr: QueryExpr| {
+            let q = to_tantivy(&expr, &fields, &index, false).unwrap();
+            let [(Occur::Should, inner)] = clauses(q.as_ref()) else {
+                panic!("expected one should clause: {q:?}");
+            };
+            format!("{inner:?}")
+        };
+        let ext = single(field_term(FieldKind::Ext, "pdf", TermModifier::Term));
+        assert!(ext.starts_with("TermQuery"), "{ext}");
+        let prefix = single(field_term(FieldKind::Name, "Rep", TermModifier::Prefix));
+        assert!(
+            prefix.contains("TermQuery") && prefix.contains("\"rep\""),
+            "{prefix}"
+        );
+        let fuzzy = single(field_term(FieldKind::Name, "repot", TermModifier::Fuzzy(1)));
+        assert!(fuzzy.contains("FuzzyTermQuery"), "{fuzzy}");
+    }
+
+    #[test]
+    fn case_sensitive_terms_target_cased_name() {
+        let (index, fields) = meta_index();
+        let q = to_tantivy(
+            &field_term(FieldKind::Name, "Report", TermModifier::Prefix),
+            &fields,
+            &index,
+            true,
+        )
+        .unwrap();
+        let [(_, inner)] = clauses(q.as_ref()) else {
+            panic!("{q:?}");
+        };
+        let term = inner.downcast_ref::<TermQuery>().unwrap().term();
+        assert_eq!(Some(term.field()), fields.name_cased);
+        assert_eq!(term.value().as_str(), Some("Report"));
+    }
+
+    #[test]
+    fn missing_fields_match_nothing() {
+        let (index, fields) = content_index();
+        let content_only = field_term(FieldKind::Content, "x", TermModifier::Term);
+        let (meta, meta_fields) = meta_index();
+        assert!(
+            clauses(
+                to_tantivy(&content_only, &meta_fields, &meta, false)
+                    .unwrap()
+                    .as_ref()
+            )
+            .is_empty()
+        );
+        let created = QueryExpr::Range(RangeExpr {
+            field: FieldKind::Created,
+            op: RangeOp::Ge,
+            value: RangeValue::I64 { lo: 0, hi: None },
+        });
+        assert!(
+            clauses(
+                to_tantivy(&created, &fields, &index, false)
+                    .unwrap()
+                    .as_ref()
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn size_range_is_u64_fast_field_range() {
+        let (index, fields) = meta_index();
+        let q = to_tantivy(&size_range(RangeOp::Ge, 10, None), &fields, &index, false).unwrap();
+        let range = q.downcast_ref::<RangeQuery>().expect("range query");
+        assert_eq!(range.field(), fields.size);
+        assert_eq!(range.value_type(), tantivy::schema::Type::U64);
+
+        let q = QueryExpr::Range(RangeExpr {
+            field: FieldKind::Modified,
+            op: RangeOp::Lt,
+            value: RangeValue::U64 { lo: 5, hi: None },
+        });
+        let q = to_tantivy(&q, &fields, &index, false).unwrap();
+        let range = q.downcast_ref::<RangeQuery>().expect("range query");
+        assert_eq!(range.field(), fields.modified);
+        assert_eq!(range.value_type(), tantivy::schema::Type::I64);
+    }
+
+    #[test]
+    fn between_requires_ordered_bounds() {
+        let (index, fields) = meta_index();
+        let err = to_tantivy(
+            &size_range(RangeOp::Between, 10, None),
+            &fields,
+            &index,
+            false,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("no upper bound"), "{err}");
+        let nested = QueryExpr::And(vec![term("a"), size_range(RangeOp::Between, 20, Some(10))]);
+        let err = to_tantivy(&nested, &fields, &index, false)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("greater than hi"), "{err}");
+    }
+
+    #[test]
+    fn range_ops_select_expected_sizes() {
+        let (schema, meta_fields) = meta_index::build_schema();
+        let index = Index::create_in_ram(schema);
+        meta_index::register_tokenizers(&index);
+        let mut writer = index.writer_with_num_threads(1, 15_000_000).unwrap();
+        for size in [5u64, 10, 20, 30] {
+            writer
+                .add_document(tantivy::doc!(meta_fields.size => size))
+                .unwrap();
+        }
+        writer.commit().unwrap();
+        let searcher = index.reader().unwrap().searcher();
+        let fields = QueryFields::from(&meta_fields);
+        let count = |expr: QueryExpr| {
+            let q = to_tantivy(&expr, &fields, &index, false).unwrap();
+            searcher
+                .search(q.as_ref(), &tantivy::collector::Count)
+                .unwrap()
+        };
+
+        assert_eq!(count(size_range(RangeOp::Gt, 10, None)), 2);
+        assert_eq!(count(size_range(RangeOp::Ge, 10, None)), 3);
+        assert_eq!(count(size_range(RangeOp::Lt, 10, None)), 1);
+        assert_eq!(count(size_range(RangeOp::Le, 10, None)), 2);
+        assert_eq!(count(size_range(RangeOp::Between, 10, Some(20))), 2);
+    }
+
+    #[test]
+    fn auto_mode_follows_the_fields_a_query_uses() {
+        let plan = |input: &str| {
+            let expr = ipc::query::parse_query(input).unwrap();
+            QueryPlanner::choose_mode(&QueryPlanner::optimize(expr))
+        };
+
+        let ext = plan("ext:pdf");
+        assert_eq!(ext.mode, SearchMode::NameOnly, "{:?}", ext.reasons);
+        assert!(ext.reasons[0].contains("ext:pdf"), "{:?}", ext.reasons);
+
+        let phrase = plan("\"quarterly revenue forecast for the board\"");
+        assert!(
+            matches!(phrase.mode, SearchMode::Content | SearchMode::Hybrid),
+            "{phrase:?}"
+        );
+        assert!(phrase.estimated_cost > ext.estimated_cost);
+        let words = plan("quarterly revenue forecast for the board");
+        assert!(
+            matches!(words.mode, SearchMode::Content | SearchMode::Hybrid),
+            "{words:?}"
+        );
+
+        assert_eq!(plan("content:invoice").mode, SearchMode::Content);
+        assert_eq!(plan("report").mode, SearchMode::Hybrid);
+        assert_eq!(plan("ext:pdf content:invoice").mode, SearchMode::Hybrid);
+        let negated = plan("ext:pdf -report");
+        assert_eq!(negated.mode, SearchMode::NameOnly);
+        assert!(negated.reasons.iter().any(|r| r.contains("1 negated")));
+    }
 }
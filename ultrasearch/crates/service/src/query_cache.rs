@@ -0,0 +1,266 @@
+//! Short-lived cache of search responses.
+//!
+//! UI debounce and client retries send the same request several times in a row. Responses are
+//! cached under a canonical form of the request, so `a AND b` and `b AND a` share an entry, and
+//! stamped with the searcher generations they were computed against: once a reader reloads
+//! after a commit, older entries no longer match and are dropped on lookup.
+
+use ipc::{QueryExpr, SearchRequest, SearchResponse};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Entries kept before the least recently used one is evicted.
+pub const DEFAULT_CAPACITY: usize = 256;
+/// How long a response may be served from cache, even if no commit happened.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(5);
+
+/// Searcher generation ids of the meta and content readers (0 when there is no content index).
+pub type Generation = (u64, u64);
+
+/// Canonical form of every request field that affects the response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey {
+    hash: u64,
+    bytes: Vec<u8>,
+}
+
+impl CacheKey {
+    pub fn for_request(req: &SearchRequest) -> Self {
+        let canonical = (
+            canonicalize(&req.query),
+            req.mode,
+            req.limit,
+            req.offset,
+            &req.cursor,
+            req.explain,
+            req.case_sensitive,
+            req.count_only,
+            &req.fields,
+            req.include_system,
+            req.sort,
+            req.suggest,
+            req.group_by,
+            req.normalize_scores,
+        );
+        let bytes = bincode::serialize(&canonical).expect("search request serializes");
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        Self {
+            hash: hasher.finish(),
+            bytes,
+        }
+    }
+}
+
+/// Recursively sort `And`/`Or` operands so operand order does not change the key.
+pub fn canonicalize(expr: &QueryExpr) -> QueryExpr {
+    let sorted = |parts: &[QueryExpr]| {
+        let mut parts: Vec<(Vec<u8>, QueryExpr)> = parts
+            .iter()
+            .map(canonicalize)
+            .map(|p| (bincode::serialize(&p).expect("query serializes"), p))
+            .collect();
+        parts.sort_by(|a, b| a.0.cmp(&b.0));
+        parts.into_iter().map(|(_, p)| p).collect()
+    };
+    match expr {
+        QueryExpr::And(parts) => QueryExpr::And(sorted(parts)),
+        QueryExpr::Or(parts) => QueryExpr::Or(sorted(parts)),
+        QueryExpr::Not(inner) => QueryExpr::Not(Box::new(canonicalize(inner))),
+        QueryExpr::Term(_) | QueryExpr::Range(_) => expr.clone(),
+    }
+}
+
+struct Entry {
+    key: CacheKey,
+    generation: Generation,
+    stored_at: Instant,
+    last_used: u64,
+    response: SearchResponse,
+}
+
+#[derive(Default)]
+struct Entries {
+    map: HashMap<u64, Entry>,
+    clock: u64,
+}
+
+/// LRU cache of `SearchResponse`s with a TTL and generation-based invalidation.
+pub struct QueryCache {
+    capacity: usize,
+    ttl: Duration,
+    entries: Mutex<Entries>,
+    hits: AtomicU64,
+}
+
+impl Default for QueryCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY, DEFAULT_TTL)
+    }
+}
+
+impl QueryCache {
+    pub fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            capacity,
+            ttl,
+            entries: Mutex::new(Entries::default()),
+            hits: AtomicU64::new(0),
+        }
+    }
+
+    /// The cached response for `key` if it is fresh and was computed at `generation`.
+    pub fn get(&self, key: &CacheKey, generation: Generation) -> Option<SearchResponse> {
+        let mut entries = self.entries.lock();
+        entries.clock += 1;
+        let clock = entries.clock;
+        let entry = entries.map.get_mut(&key.hash)?;
+        if entry.key != *key {
+            return None;
+        }
+        if entry.generation != generation || entry.stored_at.elapsed() > self.ttl {
+            entries.map.remove(&key.hash);
+            return None;
+        }
+        entry.last_used = clock;
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(entry.response.clone())
+    }
+
+    pub fn insert(&self, key: CacheKey, generation: Generation, response: SearchResponse) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock();
+        entries.clock += 1;
+        let clock = entries.clock;
+        if entries.map.len() >= self.capacity && !entries.map.contains_key(&key.hash) {
+            let ttl = self.ttl;
+            entries
+                .map
+                .retain(|_, e| e.generation == generation && e.stored_at.elapsed() <= ttl);
+            if entries.map.len() >= self.capacity
+                && let Some(oldest) = entries
+                    .map
+                    .iter()
+                    .min_by_key(|(_, e)| e.last_used)
+                    .map(|(h, _)| *h)
+            {
+                entries.map.remove(&oldest);
+            }
+        }
+        entries.map.insert(
+            key.hash,
+            Entry {
+                key,
+                generation,
+                stored_at: Instant::now(),
+                last_used: clock,
+                response,
+            },
+        );
+    }
+
+    /// Lookups served from cache since creation.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().map.len()
+    }
+
+    /// Drop every cached response, e.g. to give memory back under pressure.
+    pub fn clear(&self) {
+        self.entries.lock().map.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ipc::{FieldKind, TermExpr, TermModifier};
+    use uuid::Uuid;
+
+    fn term(value: &str) -> QueryExpr {
+        QueryExpr::Term(TermExpr {
+            field: Some(FieldKind::Name),
+            value: value.into(),
+            modifier: TermModifier::Term,
+            boost: None,
+        })
+    }
+
+    fn response(total: u64) -> SearchResponse {
+        SearchResponse {
+            id: Uuid::nil(),
+            hits: Vec::new(),
+            total,
+            truncated: false,
+            took_ms: 0,
+            served_by: None,
+            next_cursor: None,
+            explanation: None,
+            timed_out: false,
+            degraded: None,
+            suggestions: Vec::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn operand_order_does_not_change_the_key() {
+        let a = SearchRequest::with_query(QueryExpr::And(vec![
+            term("a"),
+            QueryExpr::Or(vec![term("b"), term("c")]),
+        ]));
+        let b = SearchRequest::with_query(QueryExpr::And(vec![
+            QueryExpr::Or(vec![term("c"), term("b")]),
+            term("a"),
+        ]));
+        assert_eq!(CacheKey::for_request(&a), CacheKey::for_request(&b));
+
+        let paged = a.clone().with_limit(7);
+        assert_ne!(CacheKey::for_request(&a), CacheKey::for_request(&paged));
+    }
+
+    #[test]
+    fn stale_generation_and_ttl_miss() {
+        let key = CacheKey::for_request(&SearchRequest::with_query(term("a")));
+        let cache = QueryCache::new(4, Duration::from_secs(60));
+        cache.insert(key.clone(), (1, 0), response(3));
+        assert_eq!(cache.get(&key, (1, 0)).map(|r| r.total), Some(3));
+        assert!(cache.get(&key, (2, 0)).is_none());
+        assert!(cache.is_empty());
+
+        let expiring = QueryCache::new(4, Duration::ZERO);
+        expiring.insert(key.clone(), (1, 0), response(3));
+        std::thread::sleep(Duration::from_millis(2));
+        assert!(expiring.get(&key, (1, 0)).is_none());
+    }
+
+    #[test]
+    fn full_cache_evicts_least_recently_used() {
+        let keys: Vec<CacheKey> = ["a", "b", "c"]
+            .iter()
+            .map(|v| CacheKey::for_request(&SearchRequest::with_query(term(v))))
+            .collect();
+        let cache = QueryCache::new(2, Duration::from_secs(60));
+        cache.insert(keys[0].clone(), (1, 0), response(0));
+        cache.insert(keys[1].clone(), (1, 0), response(1));
+        assert!(cache.get(&keys[0], (1, 0)).is_some());
+        cache.insert(keys[2].clone(), (1, 0), response(2));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&keys[1], (1, 0)).is_none());
+        assert!(cache.get(&keys[0], (1, 0)).is_some());
+        assert!(cache.get(&keys[2], (1, 0)).is_some());
+    }
+}
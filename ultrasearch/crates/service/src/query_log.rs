@@ -0,0 +1,293 @@
+//! Query dataset log (`features.log_dataset_mode`).
+//!
+//! With the flag on, every executed search appends one JSON line to
+//! `<data_dir>/query_log.jsonl`: the planned query's shape, the mode it ran in, how many docs
+//! matched and how long it took. The records are meant for offline analysis and tuning, so
+//! they carry nothing identifying: term text is replaced by its length and an HMAC-SHA256 keyed
+//! with a random per-install salt (`query_log.salt`, created next to the log), so repeats of a
+//! query stay recognisable across restarts while its words can't be recovered by hashing
+//! guesses. No paths, names or contents of hits are written. Size ranges and date ranges are
+//! kept as they are.
+//!
+//! Once the file passes [`MAX_LOG_BYTES`] it is renamed to `query_log.1.jsonl`, replacing the
+//! previous one, and a new file is started.
+
+use hmac::{Hmac, Mac};
+use ipc::{FieldKind, QueryExpr, RangeExpr, SearchMode, TermModifier};
+use parking_lot::Mutex;
+use serde::Serialize;
+use sha2::Sha256;
+use std::fmt::Write as _;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+pub const QUERY_LOG_FILE: &str = "query_log.jsonl";
+/// Key for the term hashes, kept next to the log.
+pub const QUERY_LOG_SALT_FILE: &str = "query_log.salt";
+const SALT_LEN: usize = 32;
+/// Size at which the log is rotated.
+pub const MAX_LOG_BYTES: u64 = 16 * 1024 * 1024;
+
+/// A query with its term text replaced by length and hash.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoggedExpr {
+    Term {
+        field: Option<FieldKind>,
+        modifier: TermModifier,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        boost: Option<f32>,
+        chars: usize,
+        /// HMAC-SHA256 of the lowercased term under the install's salt, in hex.
+        hash: String,
+    },
+    Range(RangeExpr),
+    Not(Box<LoggedExpr>),
+    And(Vec<LoggedExpr>),
+    Or(Vec<LoggedExpr>),
+}
+
+impl LoggedExpr {
+    pub fn anonymize(expr: &QueryExpr, salt: &[u8]) -> Self {
+        let all = |parts: &[QueryExpr]| parts.iter().map(|p| Self::anonymize(p, salt)).collect();
+        match expr {
+            QueryExpr::Term(term) => {
+                let mut mac =
+                    Hmac::<Sha256>::new_from_slice(salt).expect("HMAC takes keys of any length");
+                mac.update(term.value.to_lowercase().as_bytes());
+                let hash = mac.finalize().into_bytes().iter().fold(
+                    String::with_capacity(2 * SALT_LEN),
+                    |mut hex, b| {
+                        let _ = write!(hex, "{b:02x}");
+                        hex
+                    },
+                );
+                LoggedExpr::Term {
+                    field: term.field,
+                    modifier: term.modifier,
+                    boost: term.boost,
+                    chars: term.value.chars().count(),
+                    hash,
+                }
+            }
+            QueryExpr::Range(range) => LoggedExpr::Range(range.clone()),
+            QueryExpr::Not(inner) => LoggedExpr::Not(Box::new(Self::anonymize(inner, salt))),
+            QueryExpr::And(parts) => LoggedExpr::And(all(parts)),
+            QueryExpr::Or(parts) => LoggedExpr::Or(all(parts)),
+        }
+    }
+}
+
+/// One line of the log.
+#[derive(Debug, Serialize)]
+pub struct QueryRecord {
+    /// Unix seconds.
+    pub ts: u64,
+    pub query: LoggedExpr,
+    pub mode: SearchMode,
+    pub hit_count: u64,
+    pub latency_ms: u64,
+}
+
+impl QueryRecord {
+    pub fn new(
+        query: &QueryExpr,
+        mode: SearchMode,
+        hit_count: u64,
+        latency: Duration,
+        salt: &[u8],
+    ) -> Self {
+        Self {
+            ts: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            query: LoggedExpr::anonymize(query, salt),
+            mode,
+            hit_count,
+            latency_ms: latency.as_millis() as u64,
+        }
+    }
+}
+
+struct Active {
+    file: File,
+    size: u64,
+    salt: [u8; SALT_LEN],
+}
+
+/// Appender for the query log; opens the file on first use.
+pub struct QueryLog {
+    path: PathBuf,
+    max_bytes: u64,
+    active: Mutex<Option<Active>>,
+    warned: AtomicBool,
+}
+
+impl QueryLog {
+    pub fn new(path: PathBuf, max_bytes: u64) -> Self {
+        Self {
+            path,
+            max_bytes,
+            active: Mutex::new(None),
+            warned: AtomicBool::new(false),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a record of one search. Failures are logged once and otherwise ignored; a search
+    /// never fails over its log line.
+    pub fn append(&self, query: &QueryExpr, mode: SearchMode, hit_count: u64, latency: Duration) {
+        if let Err(err) = self.try_append(query, mode, hit_count, latency)
+            && !self.warned.swap(true, Ordering::Relaxed)
+        {
+            warn!(path = %self.path.display(), error = %err, "failed to write query log");
+        }
+    }
+
+    fn try_append(
+        &self,
+        query: &QueryExpr,
+        mode: SearchMode,
+        hit_count: u64,
+        latency: Duration,
+    ) -> io::Result<()> {
+        let mut active = self.active.lock();
+        if active.is_none() {
+            *active = Some(self.open()?);
+        }
+        let salt = active.as_ref().expect("opened above").salt;
+        let record = QueryRecord::new(query, mode, hit_count, latency, &salt);
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+        let full = active
+            .as_ref()
+            .is_some_and(|a| a.size > 0 && a.size + line.len() as u64 > self.max_bytes);
+        if full {
+            *active = None;
+            fs::rename(&self.path, self.path.with_extension("1.jsonl"))?;
+            *active = Some(self.open()?);
+        }
+        let active = active.as_mut().expect("opened above");
+        active.file.write_all(&line)?;
+        active.size += line.len() as u64;
+        Ok(())
+    }
+
+    fn open(&self) -> io::Result<Active> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let size = file.metadata()?.len();
+        let salt = load_or_create_salt(&self.path.with_file_name(QUERY_LOG_SALT_FILE))?;
+        Ok(Active { file, size, salt })
+    }
+}
+
+/// The install's hash key at `path`, generated on first use.
+fn load_or_create_salt(path: &Path) -> io::Result<[u8; SALT_LEN]> {
+    match fs::read(path) {
+        Ok(bytes) => {
+            return bytes.try_into().map_err(|_| {
+                io::Error::new(
+                    ErrorKind::InvalidData,
+                    "query log salt has the wrong length",
+                )
+            });
+        }
+        Err(err) if err.kind() != ErrorKind::NotFound => return Err(err),
+        Err(_) => {}
+    }
+    let mut salt = [0u8; SALT_LEN];
+    salt[..16].copy_from_slice(uuid::Uuid::new_v4().as_bytes());
+    salt[16..].copy_from_slice(uuid::Uuid::new_v4().as_bytes());
+    match OpenOptions::new().write(true).create_new(true).open(path) {
+        Ok(mut file) => {
+            file.write_all(&salt)?;
+            Ok(salt)
+        }
+        // Another writer created it first; use theirs.
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => load_or_create_salt(path),
+        Err(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ipc::TermExpr;
+
+    fn term(value: &str) -> QueryExpr {
+        QueryExpr::Term(TermExpr {
+            field: Some(FieldKind::Path),
+            value: value.into(),
+            modifier: TermModifier::Term,
+            boost: None,
+        })
+    }
+
+    #[test]
+    fn records_hide_term_text_and_the_log_rotates() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = QueryLog::new(dir.path().join(QUERY_LOG_FILE), 400);
+        let salt = [7u8; SALT_LEN];
+        let query = QueryExpr::And(vec![term("C:\\Users\\alice\\secret"), term("Secret")]);
+        let record = QueryRecord::new(
+            &query,
+            SearchMode::NameOnly,
+            3,
+            Duration::from_millis(12),
+            &salt,
+        );
+
+        let line = serde_json::to_string(&record).unwrap();
+        assert!(!line.to_lowercase().contains("secret"), "{line}");
+        assert!(!line.contains("alice"), "{line}");
+        let parsed: serde_json::Value = serde_json::from_str(&line).unwrap();
+        let parts = parsed["query"]["and"].as_array().unwrap();
+        assert_eq!(parts[0]["term"]["chars"], 21);
+        assert_ne!(parts[0]["term"]["hash"], parts[1]["term"]["hash"]);
+        let hash = |value: &str, salt: &[u8]| {
+            let LoggedExpr::Term { hash, .. } = LoggedExpr::anonymize(&term(value), salt) else {
+                unreachable!()
+            };
+            hash
+        };
+        // The hash ignores case, so the same word typed differently is still one query.
+        assert_eq!(parts[1]["term"]["hash"], hash("secret", &salt));
+        assert_eq!(hash("secret", &salt).len(), 64);
+        assert_ne!(hash("secret", &salt), hash("secret", &[8u8; SALT_LEN]));
+
+        for _ in 0..5 {
+            log.append(&query, SearchMode::NameOnly, 3, Duration::from_millis(12));
+        }
+        let rotated = dir.path().join("query_log.1.jsonl");
+        assert!(rotated.exists());
+        let current = fs::read_to_string(log.path()).unwrap();
+        assert!(current.len() as u64 <= 400);
+        let total = current.lines().count() + fs::read_to_string(&rotated).unwrap().lines().count();
+        assert!((2..=5).contains(&total), "{total}");
+    }
+
+    #[test]
+    fn salt_is_created_once_and_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(QUERY_LOG_SALT_FILE);
+        let salt = load_or_create_salt(&path).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), salt);
+        assert_eq!(load_or_create_salt(&path).unwrap(), salt);
+        let other = tempfile::tempdir().unwrap();
+        let elsewhere = load_or_create_salt(&other.path().join(QUERY_LOG_SALT_FILE)).unwrap();
+        assert_ne!(elsewhere, salt, "each install gets its own key");
+    }
+}
@@ -0,0 +1,327 @@
+//! Forced index rebuilds requested over IPC (`ultrasearch reindex`).
+//!
+//! A reindex drops the selected docs, re-enumerates the volumes' MFTs and queues content
+//! extraction again. Only one runs at a time, on its own thread; while it runs, status
+//! responses report its phase in `scheduler_state`.
+
+use crate::dispatcher::job_dispatch::content_writer_lease;
+use crate::scanner::{reindex_targets, rescan_volumes};
+use crate::scheduler_runtime::enqueue_content_job;
+use anyhow::Result;
+use core_types::config::{AppConfig, PathsSection, get_current_config};
+use core_types::{IndexOpenOutcome, WriterProfile};
+use ipc::{ReindexRequest, ReindexResponse, ReindexScope};
+use std::ops::Bound;
+use std::path::Path;
+use std::sync::Mutex;
+use tantivy::query::RangeQuery;
+use tantivy::schema::Field;
+use tantivy::{IndexWriter, Term};
+use uuid::Uuid;
+
+/// Label and current phase of the running reindex.
+static ACTIVE: Mutex<Option<String>> = Mutex::new(None);
+
+/// What the running reindex is doing, e.g. `reindexing meta+content of C:\ (scanning)`.
+pub fn active_reindex() -> Option<String> {
+    ACTIVE.lock().ok()?.clone()
+}
+
+/// Start rebuilding what `req` selects in the background. Rejected while another reindex runs.
+pub fn start_reindex(req: ReindexRequest) -> ReindexResponse {
+    let cfg = get_current_config();
+    start_with(req, move |req, phase| rebuild(&cfg, req, phase))
+}
+
+/// Refill an index its open found unreadable and replaced with an empty one. `scope` names
+/// the index that was opened; other outcomes need nothing. Returns whether a reindex started.
+pub fn repopulate_if_recreated(outcome: &IndexOpenOutcome, scope: ReindexScope) -> bool {
+    let Some(req) = repopulation(outcome, scope) else {
+        return false;
+    };
+    let resp = start_reindex(req);
+    let message = resp.message.unwrap_or_default();
+    if resp.accepted {
+        tracing::warn!("index recreated empty; {message} to repopulate it");
+    } else {
+        tracing::warn!("index recreated empty but could not be repopulated: {message}");
+    }
+    resp.accepted
+}
+
+/// The reindex that repopulates a recreated index: for the meta index a rescan of every
+/// volume, for the content index a rescan that queues every file's extraction again.
+fn repopulation(outcome: &IndexOpenOutcome, scope: ReindexScope) -> Option<ReindexRequest> {
+    let IndexOpenOutcome::Recreated { backup } = outcome else {
+        return None;
+    };
+    tracing::warn!("unreadable index moved to {}", backup.display());
+    Some(ReindexRequest {
+        id: Uuid::new_v4(),
+        volume: None,
+        scope,
+    })
+}
+
+fn start_with<F>(req: ReindexRequest, job: F) -> ReindexResponse
+where
+    F: FnOnce(&ReindexRequest, &dyn Fn(&str)) -> Result<String> + Send + 'static,
+{
+    let label = describe(&req);
+    {
+        let Ok(mut active) = ACTIVE.lock() else {
+            return rejected(&req, "reindex state is poisoned".into());
+        };
+        if let Some(running) = active.as_ref() {
+            return rejected(&req, format!("already {running}"));
+        }
+        *active = Some(format!("{label} (starting)"));
+    }
+
+    let id = req.id;
+    let thread_label = label.clone();
+    let spawned = std::thread::Builder::new()
+        .name("reindex".into())
+        .spawn(move || {
+            let phase = |p: &str| set_active(Some(format!("{thread_label} ({p})")));
+            match job(&req, &phase) {
+                Ok(summary) => {
+                    tracing::info!(request_id = %req.id, "{thread_label} done: {summary}")
+                }
+                Err(err) => tracing::warn!(request_id = %req.id, "{thread_label} failed: {err:#}"),
+            }
+            set_active(None);
+        });
+    if let Err(err) = spawned {
+        set_active(None);
+        return ReindexResponse {
+            id,
+            accepted: false,
+            message: Some(format!("failed to start reindex thread: {err}")),
+        };
+    }
+    ReindexResponse {
+        id,
+        accepted: true,
+        message: Some(format!("started {label}")),
+    }
+}
+
+fn rebuild(cfg: &AppConfig, req: &ReindexRequest, phase: &dyn Fn(&str)) -> Result<String> {
+    phase("discovering volumes");
+    let volumes = reindex_targets(cfg, req.volume.as_deref())?;
+
+    phase("dropping docs");
+    // A full rebuild also clears docs of volumes that are no longer configured.
+    let ids: Vec<u16> = volumes.iter().map(|v| v.id).collect();
+    let selected = req.volume.is_some().then_some(ids.as_slice());
+    drop_docs(&cfg.paths, selected, req.scope)?;
+
+    phase("scanning");
+    let jobs = rescan_volumes(cfg, &volumes, req.scope.includes_meta());
+
+    let queued = if req.scope.includes_content() {
+        phase("queueing content jobs");
+        jobs.into_iter()
+            .map(enqueue_content_job)
+            .filter(|queued| *queued)
+            .count()
+    } else {
+        0
+    };
+    Ok(format!(
+        "rescanned {} volume(s), queued {queued} content job(s)",
+        volumes.len()
+    ))
+}
+
+/// Delete the docs of `volumes` (every doc when `None`) from the indexes `scope` covers.
+pub fn drop_docs(paths: &PathsSection, volumes: Option<&[u16]>, scope: ReindexScope) -> Result<()> {
+    let steady = WriterProfile::SteadyState;
+    if scope.includes_meta() {
+        let meta = meta_index::open_or_create_index(Path::new(&paths.meta_index))?;
+        let mut writer =
+            meta_index::create_writer(&meta, &meta_index::WriterConfig::auto_for(steady))?;
+        delete_volumes(&mut writer, meta.fields.volume, volumes)?;
+        writer.commit()?;
+    }
+    if scope.includes_content() {
+        // Waits for a running worker batch, which holds the content writer until it exits.
+        let _lease = content_writer_lease();
+        let search = get_current_config().search;
+        let content = content_index::open_or_create_with(Path::new(&paths.content_index), &search)?;
+        let mut writer =
+            content_index::create_writer(&content, &content_index::WriterConfig::auto_for(steady))?;
+        delete_volumes(&mut writer, content.fields.volume, volumes)?;
+        writer.commit()?;
+    }
+    Ok(())
+}
+
+fn delete_volumes(writer: &mut IndexWriter, field: Field, volumes: Option<&[u16]>) -> Result<()> {
+    match volumes {
+        None => {
+            writer.delete_all_documents()?;
+        }
+        Some(ids) => {
+            for &volume in ids {
+                let term = Term::from_field_u64(field, volume as u64);
+                let query = RangeQuery::new(Bound::Included(term.clone()), Bound::Included(term));
+                writer.delete_query(Box::new(query))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn describe(req: &ReindexRequest) -> String {
+    let what = match req.scope {
+        ReindexScope::All => "meta+content",
+        ReindexScope::MetaOnly => "meta",
+        ReindexScope::ContentOnly => "content",
+    };
+    let volume = req.volume.as_deref().unwrap_or("all volumes");
+    format!("reindexing {what} of {volume}")
+}
+
+fn rejected(req: &ReindexRequest, message: String) -> ReindexResponse {
+    ReindexResponse {
+        id: req.id,
+        accepted: false,
+        message: Some(message),
+    }
+}
+
+fn set_active(value: Option<String>) {
+    if let Ok(mut active) = ACTIVE.lock() {
+        *active = value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::status_provider::{BasicStatusProvider, StatusProvider};
+    use std::sync::mpsc;
+    use std::time::{Duration, Instant};
+    use uuid::Uuid;
+
+    fn request(scope: ReindexScope) -> ReindexRequest {
+        ReindexRequest {
+            id: Uuid::new_v4(),
+            volume: Some("C:\\".into()),
+            scope,
+        }
+    }
+
+    #[test]
+    fn concurrent_reindex_is_rejected_and_shown_in_scheduler_state() {
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let (phase_tx, phase_rx) = mpsc::channel::<()>();
+        let first = start_with(request(ReindexScope::ContentOnly), move |_, phase| {
+            phase("scanning");
+            phase_tx.send(()).unwrap();
+            release_rx.recv().unwrap();
+            Ok("done".into())
+        });
+        assert!(first.accepted, "{:?}", first.message);
+        phase_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+
+        let state = BasicStatusProvider::new().snapshot().scheduler_state;
+        assert!(
+            state.starts_with("reindexing content of C:\\ (scanning)"),
+            "{state}"
+        );
+
+        let second = start_with(request(ReindexScope::All), |_, _| Ok(String::new()));
+        assert!(!second.accepted);
+        assert!(second.message.unwrap().contains("already reindexing"));
+
+        release_tx.send(()).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while active_reindex().is_some() {
+            assert!(Instant::now() < deadline, "reindex never finished");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert!(
+            !BasicStatusProvider::new()
+                .snapshot()
+                .scheduler_state
+                .starts_with("reindexing")
+        );
+
+        // The slot frees up for the next request once the job finished.
+        let third = start_with(request(ReindexScope::MetaOnly), |_, _| Ok(String::new()));
+        assert!(third.accepted);
+        while active_reindex().is_some() {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn only_a_recreated_index_is_repopulated() {
+        assert!(repopulation(&IndexOpenOutcome::Opened, ReindexScope::MetaOnly).is_none());
+        assert!(repopulation(&IndexOpenOutcome::Created, ReindexScope::MetaOnly).is_none());
+
+        let recreated = IndexOpenOutcome::Recreated {
+            backup: "content.corrupt-1".into(),
+        };
+        let req = repopulation(&recreated, ReindexScope::ContentOnly).unwrap();
+        // Every volume, and only the index that was lost: a content rebuild requeues
+        // extraction without ingesting metadata a second time.
+        assert_eq!(req.volume, None);
+        assert_eq!(req.scope, ReindexScope::ContentOnly);
+        assert_eq!(
+            repopulation(&recreated, ReindexScope::MetaOnly)
+                .unwrap()
+                .scope,
+            ReindexScope::MetaOnly
+        );
+    }
+
+    #[test]
+    fn drop_docs_respects_scope_and_volume() {
+        use crate::meta_ingest::ingest_file_meta_batch;
+        use core_types::{DocKey, FileFlags, FileMeta};
+
+        let dir = tempfile::tempdir().unwrap();
+        let paths = PathsSection {
+            meta_index: dir.path().join("meta").to_string_lossy().into_owned(),
+            content_index: dir.path().join("content").to_string_lossy().into_owned(),
+            ..PathsSection::default()
+        };
+        std::fs::create_dir_all(&paths.meta_index).unwrap();
+        std::fs::create_dir_all(&paths.content_index).unwrap();
+        let metas = [(1, 10), (2, 20), (2, 21)].map(|(vol, frn)| {
+            FileMeta::new(
+                DocKey::from_parts(vol, frn),
+                vol,
+                None,
+                format!("f{frn}.txt"),
+                None,
+                0,
+                0,
+                0,
+                FileFlags::empty(),
+            )
+        });
+        ingest_file_meta_batch(Path::new(&paths.meta_index), metas, None).unwrap();
+        let meta_docs = || {
+            let meta = meta_index::open_or_create_index(Path::new(&paths.meta_index)).unwrap();
+            meta_index::open_reader(&meta)
+                .unwrap()
+                .searcher()
+                .num_docs()
+        };
+
+        // Content-only leaves metadata alone.
+        drop_docs(&paths, Some(&[2]), ReindexScope::ContentOnly).unwrap();
+        assert_eq!(meta_docs(), 3);
+
+        drop_docs(&paths, Some(&[2]), ReindexScope::MetaOnly).unwrap();
+        assert_eq!(meta_docs(), 1);
+
+        drop_docs(&paths, None, ReindexScope::All).unwrap();
+        assert_eq!(meta_docs(), 0);
+    }
+}
@@ -1,27 +1,45 @@
 use crate::dispatcher::job_dispatch::JobSpec;
-use crate::meta_ingest::ingest_with_paths;
-use crate::scheduler_runtime::{content_job_from_meta, enqueue_content_job};
-use crate::status_provider::{update_status_last_commit, update_status_volumes};
+use crate::meta_ingest::{IngestContext, apply_renames, apply_renames_to_delta, ingest_with_paths};
+use crate::path_filter::PathFilter;
+use crate::scheduler_runtime::{
+    EntryDisposition, content_job_from_meta, enqueue_content_job, entry_disposition,
+};
+use crate::status_provider::{status_snapshot, update_status_last_commit, update_status_volumes};
 use anyhow::Result;
-use core_types::FileMeta;
-use core_types::config::AppConfig;
-use ipc::VolumeStatus;
+use core_types::config::{AppConfig, config_generation, get_current_config};
+use core_types::{DocKey, FileMeta, VolumeDescriptor, VolumeId, VolumeSettings};
+use ipc::{ListVolumesRequest, ListVolumesResponse, VolumeEntry, VolumeStatus};
+use meta_index::state::VolumeState;
 #[cfg(any())]
 use meta_index::{open_or_create_index, open_reader};
 use ntfs_watcher::{
     FileEvent, JournalCursor, NtfsError, VolumeInfo, discover_volumes, enumerate_mft, tail_usn,
 };
-#[cfg(any())]
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 #[cfg(any())]
 use std::fs;
-#[cfg(any())]
-use std::path::Path;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 #[cfg(any())]
 use tantivy::DocAddress;
 use tokio::time::{Duration, interval};
 
+/// How long the change watcher leaves a volume without an active USN journal alone before
+/// checking again.
+const INACTIVE_JOURNAL_RETRY: Duration = Duration::from_secs(300);
+
+/// Drive roots (`C:\`) of the volumes scanned so far, for the scheduler's free-space sampling.
+static VOLUME_ROOTS: Mutex<BTreeMap<u16, PathBuf>> = Mutex::new(BTreeMap::new());
+
+/// Root of each scanned volume that has a drive letter.
+pub fn volume_roots() -> Vec<(u16, PathBuf)> {
+    VOLUME_ROOTS
+        .lock()
+        .map(|roots| roots.iter().map(|(id, root)| (*id, root.clone())).collect())
+        .unwrap_or_default()
+}
+
 pub fn scan_volumes(cfg: &AppConfig) -> Result<Vec<JobSpec>> {
     tracing::info!("Starting volume scan...");
     let all_volumes = match discover_volumes() {
@@ -69,72 +87,329 @@ pub fn scan_volumes(cfg: &AppConfig) -> Result<Vec<JobSpec>> {
     let mut status = Vec::with_capacity(volumes.len());
 
     for volume in volumes {
-        tracing::info!(guid = %volume.guid_path, letters = ?volume.drive_letters, "enumerating MFT for volume");
-        match enumerate_mft(&volume) {
-            Ok(metas) => {
-                if metas.is_empty() {
-                    tracing::info!(guid = %volume.guid_path, "no entries found during MFT enumeration");
-                    continue;
+        if let Some((content_jobs, vol_status)) = scan_volume(&volume, cfg, true) {
+            jobs.extend(content_jobs);
+            status.push(vol_status);
+        }
+    }
+
+    if !status.is_empty() {
+        update_status_volumes(status);
+    }
+
+    Ok(jobs)
+}
+
+/// Scan only `mounts` (e.g. volumes added by a config reload), keeping the status of volumes
+/// that are already indexed.
+pub fn scan_added_volumes(cfg: &AppConfig, mounts: &[String]) -> Vec<JobSpec> {
+    let all_volumes = match discover_volumes() {
+        Ok(v) => v,
+        Err(err) => {
+            tracing::warn!(error = %err, "failed to discover added volumes");
+            return Vec::new();
+        }
+    };
+
+    let mut jobs = Vec::new();
+    let mut status = status_snapshot().volumes;
+    for volume in volumes_for_mounts(&all_volumes, mounts) {
+        if let Some((content_jobs, vol_status)) = scan_volume(volume, cfg, true) {
+            jobs.extend(content_jobs);
+            status.retain(|s| s.volume != vol_status.volume);
+            status.push(vol_status);
+        }
+    }
+    update_status_volumes(status);
+    jobs
+}
+
+/// Volumes with a drive letter among `mounts` (formatted like `C:\`).
+pub(crate) fn volumes_for_mounts<'a>(
+    volumes: &'a [VolumeInfo],
+    mounts: &[String],
+) -> Vec<&'a VolumeInfo> {
+    volumes
+        .iter()
+        .filter(|v| mounts.iter().any(|m| mounted_at(v, m)))
+        .collect()
+}
+
+fn mounted_at(volume: &VolumeInfo, mount: &str) -> bool {
+    volume
+        .drive_letters
+        .iter()
+        .any(|l| mount == format!("{l}:\\"))
+}
+
+/// How `cfg` indexes each of `volumes`: content per `content_index_volumes`, and the globs of
+/// every `[[volume_settings.paths]]` entry for one of the volume's mounts.
+pub(crate) fn volume_settings(cfg: &AppConfig, volumes: &[VolumeInfo]) -> Vec<VolumeSettings> {
+    let content = ContentVolumes::from_config(cfg, volumes);
+    volumes
+        .iter()
+        .map(|v| {
+            let paths: Vec<_> = cfg
+                .volume_settings
+                .paths
+                .iter()
+                .filter(|p| mounted_at(v, &p.mount))
+                .collect();
+            VolumeSettings {
+                volume: v.id,
+                include_paths: paths.iter().flat_map(|p| p.include_paths.clone()).collect(),
+                exclude_paths: paths.iter().flat_map(|p| p.exclude_paths.clone()).collect(),
+                content_indexing: content.allows_volume(v.id),
+            }
+        })
+        .collect()
+}
+
+/// Each volume's include/exclude globs, from its [`VolumeSettings`]. Volumes without any keep
+/// every path.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct VolumeFilters(HashMap<VolumeId, PathFilter>);
+
+impl VolumeFilters {
+    pub(crate) fn from_config(cfg: &AppConfig, volumes: &[VolumeInfo]) -> Self {
+        Self::new(&volume_settings(cfg, volumes))
+    }
+
+    /// An invalid pattern disables its volume's filtering rather than its indexing.
+    pub(crate) fn new(settings: &[VolumeSettings]) -> Self {
+        let filters = settings
+            .iter()
+            .filter_map(|s| match PathFilter::new(&s.include_paths, &s.exclude_paths) {
+                Ok(filter) => (!filter.is_empty()).then_some((s.volume, filter)),
+                Err(e) => {
+                    tracing::error!(volume = s.volume, error = %e, "ignoring include/exclude paths");
+                    None
                 }
+            })
+            .collect();
+        Self(filters)
+    }
 
-                let (content_jobs, content_bytes) = build_content_jobs(&metas, cfg);
+    /// Whether `meta` passes its volume's globs. Entries without a resolved path cannot be
+    /// matched and are kept.
+    pub(crate) fn allows(&self, meta: &FileMeta) -> bool {
+        match (self.0.get(&meta.volume), meta.path.as_deref()) {
+            (Some(filter), Some(path)) => filter.allows(path),
+            _ => true,
+        }
+    }
+}
+
+/// Volumes whose files get content jobs: those with a drive letter in `content_index_volumes`,
+/// or every volume when that list is empty. Metadata is indexed on all of them either way.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ContentVolumes(Option<HashSet<VolumeId>>);
+
+impl ContentVolumes {
+    pub(crate) fn from_config(cfg: &AppConfig, volumes: &[VolumeInfo]) -> Self {
+        if cfg.content_index_volumes.is_empty() {
+            return Self(None);
+        }
+        let ids = volumes_for_mounts(volumes, &cfg.content_index_volumes)
+            .into_iter()
+            .map(|v| v.id)
+            .collect();
+        Self(Some(ids))
+    }
+
+    pub(crate) fn allows_volume(&self, volume: VolumeId) -> bool {
+        self.0.as_ref().is_none_or(|ids| ids.contains(&volume))
+    }
+
+    /// Whether the volume `key` lives on is content-indexed.
+    pub(crate) fn allows(&self, key: DocKey) -> bool {
+        self.allows_volume(key.volume())
+    }
+}
+
+/// Discovered volumes with how `cfg` indexes them, sorted by id. Volumes outside `volumes` are
+/// only listed with `include_unindexed`; an empty `volumes` list indexes every volume.
+pub fn volume_entries(
+    cfg: &AppConfig,
+    discovered: &[VolumeInfo],
+    include_unindexed: bool,
+) -> Vec<VolumeEntry> {
+    let selected: Option<HashSet<VolumeId>> = (!cfg.volumes.is_empty()).then(|| {
+        volumes_for_mounts(discovered, &cfg.volumes)
+            .into_iter()
+            .map(|v| v.id)
+            .collect()
+    });
+    let content = ContentVolumes::from_config(cfg, discovered);
+    let mut entries: Vec<VolumeEntry> = discovered
+        .iter()
+        .filter_map(|v| {
+            let indexed = selected.as_ref().is_none_or(|ids| ids.contains(&v.id));
+            (indexed || include_unindexed).then(|| VolumeEntry {
+                volume: VolumeDescriptor {
+                    id: v.id,
+                    guid_path: v.guid_path.clone(),
+                    drive_letters: v.drive_letters.iter().map(|l| format!("{l}:")).collect(),
+                },
+                indexed,
+                content_indexed: indexed && content.allows_volume(v.id),
+            })
+        })
+        .collect();
+    entries.sort_by_key(|e| e.volume.id);
+    entries
+}
+
+/// Answer a `ListVolumesRequest` from a fresh discovery and the live config.
+pub fn list_volumes(req: ListVolumesRequest) -> ListVolumesResponse {
+    let discovered = match discover_volumes() {
+        Ok(v) => v,
+        Err(NtfsError::NotSupported) => Vec::new(),
+        Err(err) => {
+            tracing::warn!(error = %err, "failed to discover volumes for listing");
+            Vec::new()
+        }
+    };
+    ListVolumesResponse {
+        id: req.id,
+        volumes: volume_entries(&get_current_config(), &discovered, req.include_unindexed),
+    }
+}
+
+/// Volumes a reindex of `mount` covers; every configured volume (or every discovered one when
+/// none are configured) for `None`.
+pub fn reindex_targets(cfg: &AppConfig, mount: Option<&str>) -> Result<Vec<VolumeInfo>> {
+    let all_volumes = discover_volumes()?;
+    let selected = match mount {
+        Some(mount) => volumes_for_mounts(&all_volumes, &[mount.to_string()]),
+        None if cfg.volumes.is_empty() => all_volumes.iter().collect(),
+        None => volumes_for_mounts(&all_volumes, &cfg.volumes),
+    };
+    if selected.is_empty() {
+        anyhow::bail!(
+            "no NTFS volume matches {}",
+            mount.unwrap_or("the configuration")
+        );
+    }
+    Ok(selected.into_iter().cloned().collect())
+}
+
+/// Re-enumerate `volumes` for a reindex and return their content jobs. Metadata is only
+/// re-ingested with `ingest_meta`, so a content-only rebuild does not duplicate it.
+pub fn rescan_volumes(cfg: &AppConfig, volumes: &[VolumeInfo], ingest_meta: bool) -> Vec<JobSpec> {
+    let mut jobs = Vec::new();
+    let mut status = status_snapshot().volumes;
+    for volume in volumes {
+        if let Some((content_jobs, vol_status)) = scan_volume(volume, cfg, ingest_meta) {
+            jobs.extend(content_jobs);
+            status.retain(|s| s.volume != vol_status.volume);
+            status.push(vol_status);
+        }
+    }
+    update_status_volumes(status);
+    jobs
+}
+
+/// Enumerate one volume's MFT, ingest its metadata (unless `ingest_meta` is off), and return
+/// the content jobs it produced.
+fn scan_volume(
+    volume: &VolumeInfo,
+    cfg: &AppConfig,
+    ingest_meta: bool,
+) -> Option<(Vec<JobSpec>, VolumeStatus)> {
+    tracing::info!(guid = %volume.guid_path, letters = ?volume.drive_letters, "enumerating MFT for volume");
+    if let (Some(letter), Ok(mut roots)) = (volume.drive_letters.first(), VOLUME_ROOTS.lock()) {
+        roots.insert(volume.id, PathBuf::from(format!("{letter}:\\")));
+    }
+    let content = ContentVolumes::from_config(cfg, std::slice::from_ref(volume));
+    if !content.allows_volume(volume.id) {
+        tracing::info!(guid = %volume.guid_path, "not in content_index_volumes; indexing metadata only");
+    }
+    // Taken before enumerating: whatever changes after it is tailed from this cursor later.
+    let ctx = journal_head(volume)
+        .map(|cursor| IngestContext::at(volume.id, cursor))
+        .unwrap_or_default();
+    match enumerate_mft(volume) {
+        Ok(mut metas) => {
+            let before = metas.len();
+            let filters = VolumeFilters::from_config(cfg, std::slice::from_ref(volume));
+            let (content_jobs, content_bytes) = plan_scan(&mut metas, cfg, &filters, &content);
+            if metas.len() < before {
+                tracing::info!(
+                    guid = %volume.guid_path,
+                    skipped = before - metas.len(),
+                    "skipped excluded, reparse and offline entries"
+                );
+            }
+            if metas.is_empty() {
+                tracing::info!(guid = %volume.guid_path, "no entries found during MFT enumeration");
+                return None;
+            }
 
-                let count = metas.len() as u64;
-                let total_bytes: u64 = metas.iter().map(|m| m.size).sum();
+            let count = metas.len() as u64;
+            let total_bytes: u64 = metas.iter().map(|m| m.size).sum();
+            let mut committed = None;
+            if ingest_meta {
                 tracing::info!(guid = %volume.guid_path, files = count, "ingesting metadata batch into meta-index");
-                match ingest_with_paths(&cfg.paths, metas, None) {
-                    Ok(_) => tracing::info!("Successfully ingested {} files.", count),
+                match ingest_with_paths(
+                    &cfg.paths,
+                    metas,
+                    Some(meta_index::WriterConfig::auto()),
+                    &ctx,
+                ) {
+                    Ok(_) => {
+                        tracing::info!("Successfully ingested {} files.", count);
+                        committed = ctx.cursor(volume.id);
+                    }
                     Err(e) => tracing::error!("Failed to ingest files: {}", e),
                 }
+            }
 
-                let pending_files = content_jobs.len() as u64;
-                jobs.extend(content_jobs);
+            let pending_files = content_jobs.len() as u64;
+            update_status_last_commit(Some(unix_timestamp_secs()));
 
-                status.push(VolumeStatus {
+            Some((
+                content_jobs,
+                VolumeStatus {
                     volume: volume.id,
                     indexed_files: count,
                     indexed_bytes: total_bytes,
                     pending_files,
                     pending_bytes: content_bytes,
-                    last_usn: None,
-                    journal_id: None,
-                });
-
-                update_status_last_commit(Some(unix_timestamp_secs()));
-            }
-            Err(err) => {
-                let msg = err.to_string();
-                if msg.contains("Access is denied") || msg.contains("privilege") {
-                    tracing::error!(
-                        guid = %volume.guid_path,
-                        "CRITICAL: Failed to enumerate MFT due to permissions. Please run the application as Administrator."
-                    );
-                } else {
-                    tracing::warn!(
-                        guid = %volume.guid_path,
-                        error = %err,
-                        "failed to enumerate MFT; skipping volume"
-                    );
-                }
+                    last_usn: committed.map(|c| c.last_usn),
+                    journal_id: committed.map(|c| c.journal_id),
+                    free_bytes: None,
+                    total_bytes: None,
+                },
+            ))
+        }
+        Err(err) => {
+            let msg = err.to_string();
+            if msg.contains("Access is denied") || msg.contains("privilege") {
+                tracing::error!(
+                    guid = %volume.guid_path,
+                    "CRITICAL: Failed to enumerate MFT due to permissions. Please run the application as Administrator."
+                );
+            } else {
+                tracing::warn!(
+                    guid = %volume.guid_path,
+                    error = %err,
+                    "failed to enumerate MFT; skipping volume"
+                );
             }
+            None
         }
     }
-
-    if !status.is_empty() {
-        update_status_volumes(status);
-    }
-
-    Ok(jobs)
 }
 
 /// Spawn a background task that tails the USN journal (where available) and enqueues content jobs.
-pub async fn watch_changes(cfg: AppConfig) -> Result<()> {
-    let volumes = match discover_volumes() {
+pub async fn watch_changes(mut cfg: AppConfig) -> Result<()> {
+    let all_volumes = match discover_volumes() {
         Ok(v) if v.is_empty() => {
             tracing::info!("change watcher: no NTFS volumes discovered");
             return Ok(());
         }
-        Ok(v) => filter_volumes(cfg.clone(), v),
+        Ok(v) => v,
         Err(NtfsError::NotSupported) => {
             tracing::info!("change watcher: USN not supported; falling back to polling.");
             return Ok(());
@@ -145,39 +420,83 @@ pub async fn watch_changes(cfg: AppConfig) -> Result<()> {
         }
     };
 
+    let mut volumes = filter_volumes(cfg.clone(), all_volumes.clone());
     if volumes.is_empty() {
         tracing::info!("change watcher: no volumes matched configuration");
         update_status_volumes(Vec::new());
-        return Ok(());
     }
 
-    // Initialize cursors per volume (start at 0).
+    // Resume from the last persisted cursor per volume (fresh volumes start at 0).
+    let mut filters = VolumeFilters::from_config(&cfg, &volumes);
+    let mut content = ContentVolumes::from_config(&cfg, &volumes);
     let mut cursors = volumes
         .iter()
-        .map(|v| {
-            (
-                v.id,
-                JournalCursor {
-                    last_usn: 0,
-                    journal_id: 0,
-                },
-            )
-        })
+        .map(|v| (v.id, load_cursor(&cfg, v)))
         .collect::<std::collections::HashMap<_, _>>();
+    let mut generation = config_generation();
+    // Volumes whose journal is off, and when to look at them again.
+    let mut inactive: HashMap<VolumeId, Instant> = HashMap::new();
 
     let mut ticker = interval(Duration::from_secs(5));
     loop {
         ticker.tick().await;
+
+        // A config reload may have added/removed volumes or changed filters.
+        if config_generation() != generation {
+            generation = config_generation();
+            cfg = get_current_config();
+            volumes = filter_volumes(cfg.clone(), all_volumes.clone());
+            filters = VolumeFilters::from_config(&cfg, &volumes);
+            content = ContentVolumes::from_config(&cfg, &volumes);
+            cursors.retain(|id, _| volumes.iter().any(|v| v.id == *id));
+            for vol in &volumes {
+                cursors
+                    .entry(vol.id)
+                    .or_insert_with(|| load_cursor(&cfg, vol));
+            }
+            tracing::info!(
+                volumes = volumes.len(),
+                "change watcher: applied reloaded config"
+            );
+        }
+
         for vol in volumes.iter() {
+            if inactive
+                .get(&vol.id)
+                .is_some_and(|retry_at| Instant::now() < *retry_at)
+            {
+                continue;
+            }
             let cursor = *cursors.get(&vol.id).unwrap_or(&JournalCursor {
                 last_usn: 0,
                 journal_id: 0,
             });
 
-            match tail_usn(vol, cursor) {
+            let tailed = tail_usn(vol, cursor);
+            if !matches!(tailed, Err(NtfsError::JournalInactive { .. }))
+                && inactive.remove(&vol.id).is_some()
+            {
+                tracing::info!(volume = vol.id, "USN journal is active again");
+            }
+            match tailed {
                 Ok((events, next)) => {
+                    // Everything up to `next` is in the index unless applying it failed.
+                    let mut committed = true;
                     if !events.is_empty() {
-                        let jobs = events_to_jobs(&events, &cfg);
+                        // Renames keep their DocKey, so the meta doc is rewritten in place.
+                        let applied = match crate::delta_tier::current() {
+                            Some(delta) => apply_renames_to_delta(&delta, &events),
+                            None => apply_renames(Path::new(&cfg.paths.meta_index), &events),
+                        };
+                        if let Err(err) = applied {
+                            committed = false;
+                            tracing::warn!(
+                                volume = vol.id,
+                                error = %err,
+                                "change watcher: failed to apply renames"
+                            );
+                        }
+                        let jobs = events_to_jobs(&events, &cfg, &filters, &content);
                         let mut dropped = 0;
                         for job in jobs {
                             if !enqueue_content_job(job) {
@@ -193,11 +512,63 @@ pub async fn watch_changes(cfg: AppConfig) -> Result<()> {
                             dropped
                         );
                     }
+                    if committed {
+                        IngestContext::at(vol.id, next).mark_committed();
+                    }
+                    if next.last_usn != cursor.last_usn || next.journal_id != cursor.journal_id {
+                        save_cursor(&cfg, vol, next, false);
+                    }
                     cursors.insert(vol.id, next);
                 }
                 Err(NtfsError::GapDetected) => {
                     tracing::warn!("USN gap detected on volume {}; consider rescan", vol.id);
                 }
+                Err(NtfsError::JournalWrapped { volume }) => {
+                    tracing::warn!(volume, "USN journal wrapped or reset; re-enumerating MFT");
+                    // Taken before the rescan so changes made while it runs are tailed after.
+                    let head = journal_head(vol);
+                    let (vol_clone, cfg_clone) = (vol.clone(), cfg.clone());
+                    match tokio::task::spawn_blocking(move || {
+                        scan_volume(&vol_clone, &cfg_clone, true)
+                    })
+                    .await
+                    {
+                        Ok(Some((jobs, _))) => {
+                            let dropped = jobs
+                                .into_iter()
+                                .map(enqueue_content_job)
+                                .filter(|ok| !ok)
+                                .count();
+                            if dropped > 0 {
+                                tracing::warn!(volume, dropped, "rescan dropped content jobs");
+                            }
+                        }
+                        Ok(None) => {}
+                        Err(join_err) => {
+                            tracing::warn!(volume, "rescan task panicked: {join_err}");
+                        }
+                    }
+                    // Without a readable head, forget the stale journal; the next tick adopts
+                    // the current one from wherever it is then.
+                    let fresh = head.unwrap_or(JournalCursor {
+                        last_usn: 0,
+                        journal_id: 0,
+                    });
+                    save_cursor(&cfg, vol, fresh, true);
+                    cursors.insert(vol.id, fresh);
+                }
+                Err(NtfsError::JournalInactive { volume }) => {
+                    if inactive
+                        .insert(volume, Instant::now() + INACTIVE_JOURNAL_RETRY)
+                        .is_none()
+                    {
+                        tracing::warn!(
+                            volume,
+                            "USN journal is not active; changes on this volume are not tracked \
+                             until it is enabled"
+                        );
+                    }
+                }
                 Err(err) => {
                     tracing::warn!(volume = vol.id, error = %err, "tail_usn failed");
                 }
@@ -206,6 +577,69 @@ pub async fn watch_changes(cfg: AppConfig) -> Result<()> {
     }
 }
 
+/// File holding the last-good USN cursor for a volume, keyed by its GUID so ids can shift.
+fn cursor_state_path(cfg: &AppConfig, vol: &VolumeInfo) -> PathBuf {
+    let tag: String = vol
+        .guid_path
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .collect();
+    let tag = if tag.is_empty() {
+        format!("vol-{}", vol.id)
+    } else {
+        tag
+    };
+    PathBuf::from(&cfg.paths.state_dir).join(format!("{tag}.state"))
+}
+
+fn load_cursor(cfg: &AppConfig, vol: &VolumeInfo) -> JournalCursor {
+    let state = VolumeState::load(&cursor_state_path(cfg, vol)).unwrap_or_else(|e| {
+        tracing::warn!(volume = vol.id, error = %e, "failed to load USN cursor; starting fresh");
+        VolumeState::default()
+    });
+    JournalCursor {
+        last_usn: state.last_usn,
+        journal_id: state.journal_id,
+    }
+}
+
+fn save_cursor(cfg: &AppConfig, vol: &VolumeInfo, cursor: JournalCursor, rescanned: bool) {
+    let path = cursor_state_path(cfg, vol);
+    let result = (|| -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut state = VolumeState::load(&path).unwrap_or_default();
+        state.last_usn = cursor.last_usn;
+        state.journal_id = cursor.journal_id;
+        if rescanned {
+            state.last_mft_scan_generation += 1;
+        }
+        state.save(&path)
+    })();
+    if let Err(e) = result {
+        tracing::warn!(volume = vol.id, error = %e, "failed to persist USN cursor");
+    }
+}
+
+/// Where the volume's journal will write next, if it can be read.
+fn journal_head(volume: &VolumeInfo) -> Option<JournalCursor> {
+    #[cfg(windows)]
+    {
+        ntfs_watcher::query_journal(volume)
+            .ok()
+            .map(|journal| JournalCursor {
+                last_usn: journal.next_usn,
+                journal_id: journal.journal_id,
+            })
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = volume;
+        None
+    }
+}
+
 fn unix_timestamp_secs() -> i64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -213,10 +647,30 @@ fn unix_timestamp_secs() -> i64 {
         .unwrap_or(0)
 }
 
-fn build_content_jobs(metas: &[FileMeta], cfg: &AppConfig) -> (Vec<JobSpec>, u64) {
+/// Drop skipped and excluded entries from `metas` (the rest is ingested as metadata) and build
+/// the content jobs for those on content-indexed volumes.
+fn plan_scan(
+    metas: &mut Vec<FileMeta>,
+    cfg: &AppConfig,
+    filters: &VolumeFilters,
+    content: &ContentVolumes,
+) -> (Vec<JobSpec>, u64) {
+    metas.retain(|m| {
+        entry_disposition(m.flags, &cfg.volume_settings) != EntryDisposition::Skip
+            && filters.allows(m)
+    });
+    build_content_jobs(metas, cfg, content)
+}
+
+fn build_content_jobs(
+    metas: &[FileMeta],
+    cfg: &AppConfig,
+    content: &ContentVolumes,
+) -> (Vec<JobSpec>, u64) {
     let mut total_bytes = 0u64;
     let jobs = metas
         .iter()
+        .filter(|meta| content.allows(meta.key))
         .filter_map(|meta| {
             if let Some(job) = content_job_from_meta(meta, &cfg.extract) {
                 total_bytes = total_bytes.saturating_add(meta.size);
@@ -246,20 +700,31 @@ fn filter_volumes(cfg: AppConfig, all_volumes: Vec<VolumeInfo>) -> Vec<VolumeInf
     }
 }
 
-fn events_to_jobs(events: &[FileEvent], cfg: &AppConfig) -> Vec<JobSpec> {
+fn events_to_jobs(
+    events: &[FileEvent],
+    cfg: &AppConfig,
+    filters: &VolumeFilters,
+    content: &ContentVolumes,
+) -> Vec<JobSpec> {
     let mut out = Vec::new();
+    let indexable = |meta: &FileMeta| {
+        entry_disposition(meta.flags, &cfg.volume_settings) == EntryDisposition::Full
+            && filters.allows(meta)
+            && content.allows(meta.key)
+    };
     for ev in events {
         match ev {
-            FileEvent::Created(meta) => {
+            FileEvent::Created(meta) if indexable(meta) => {
                 if let Some(job) = content_job_from_meta(meta, &cfg.extract) {
                     out.push(job);
                 }
             }
-            FileEvent::Renamed { to, .. } => {
+            FileEvent::Renamed { to, .. } if indexable(to) => {
                 if let Some(job) = content_job_from_meta(to, &cfg.extract) {
                     out.push(job);
                 }
             }
+            FileEvent::Created(_) | FileEvent::Renamed { .. } => {}
             FileEvent::Modified { .. } | FileEvent::AttributesChanged { .. } => {
                 // Lacking path/size here; could trigger a lightweight stat in future.
             }
@@ -332,7 +797,7 @@ fn detect_changed_files(
                 doc_id,
             };
             let doc = searcher.doc(addr)?;
-            if let Some(meta_doc) = meta_index::tiers::doc_to_meta(&doc, &meta.fields)
+            if let Some(meta_doc) = meta_index::doc_to_meta(&doc, &meta.fields)
                 && let Some(path) = &meta_doc.path
             {
                 let meta_fs = match fs::metadata(path) {
@@ -374,3 +839,128 @@ fn detect_changed_files(
 
     Ok(changed)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core_types::FileFlags;
+
+    fn volume(id: VolumeId, letter: char) -> VolumeInfo {
+        VolumeInfo {
+            id,
+            guid_path: format!("\\\\?\\Volume{{{id}}}\\"),
+            drive_letters: vec![letter],
+        }
+    }
+
+    fn file(volume: VolumeId, frn: u64, path: &str) -> FileMeta {
+        FileMeta::new(
+            DocKey::from_parts(volume, frn),
+            volume,
+            None,
+            path.rsplit('\\').next().unwrap().to_string(),
+            Some(path.into()),
+            10,
+            0,
+            0,
+            FileFlags::empty(),
+        )
+    }
+
+    #[test]
+    fn non_content_volumes_get_metadata_but_no_content_jobs() {
+        let mut cfg = AppConfig {
+            volumes: vec!["C:\\".into(), "D:\\".into()],
+            content_index_volumes: vec!["C:\\".into()],
+            ..Default::default()
+        };
+        let volumes = [volume(1, 'C'), volume(2, 'D')];
+        let content = ContentVolumes::from_config(&cfg, &volumes);
+
+        let mut metas = vec![file(1, 1, r"C:\notes.txt"), file(2, 1, r"D:\notes.txt")];
+        let (jobs, bytes) = plan_scan(&mut metas, &cfg, &VolumeFilters::default(), &content);
+        assert_eq!(metas.len(), 2, "both files are still ingested as metadata");
+        assert_eq!(jobs.iter().map(|j| j.volume_id).collect::<Vec<_>>(), [1]);
+        assert_eq!(bytes, 10);
+
+        let events = [
+            FileEvent::Created(file(1, 2, r"C:\new.txt")),
+            FileEvent::Created(file(2, 2, r"D:\new.txt")),
+            FileEvent::Renamed {
+                from: DocKey::from_parts(2, 3),
+                to: file(2, 3, r"D:\moved.txt"),
+            },
+        ];
+        let jobs = events_to_jobs(&events, &cfg, &VolumeFilters::default(), &content);
+        assert_eq!(jobs.iter().map(|j| j.volume_id).collect::<Vec<_>>(), [1]);
+
+        // An empty list keeps content indexing on every volume.
+        cfg.content_index_volumes.clear();
+        let content = ContentVolumes::from_config(&cfg, &volumes);
+        assert_eq!(
+            events_to_jobs(&events, &cfg, &VolumeFilters::default(), &content).len(),
+            3
+        );
+    }
+
+    #[test]
+    fn path_globs_apply_only_to_their_volume() {
+        let cfg = AppConfig {
+            volumes: vec!["C:\\".into(), "D:\\".into()],
+            volume_settings: core_types::config::VolumeSettingsSection {
+                paths: vec![core_types::config::VolumePaths {
+                    mount: "C:\\".into(),
+                    exclude_paths: vec!["**/*.tmp".into()],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let volumes = [volume(1, 'C'), volume(2, 'D')];
+        let settings = volume_settings(&cfg, &volumes);
+        assert_eq!(settings[0].exclude_paths, ["**/*.tmp"]);
+        assert!(settings[1].exclude_paths.is_empty());
+
+        let filters = VolumeFilters::new(&settings);
+        let content = ContentVolumes::from_config(&cfg, &volumes);
+        let mut metas = vec![file(1, 1, r"C:\x.tmp"), file(2, 1, r"D:\x.tmp")];
+        plan_scan(&mut metas, &cfg, &filters, &content);
+        assert_eq!(metas.iter().map(|m| m.volume).collect::<Vec<_>>(), [2]);
+    }
+
+    #[test]
+    fn volume_entries_report_configured_volumes_and_content_flags() {
+        let mut cfg = AppConfig {
+            volumes: vec!["C:\\".into(), "D:\\".into()],
+            content_index_volumes: vec!["D:\\".into()],
+            ..Default::default()
+        };
+        let discovered = [volume(3, 'E'), volume(2, 'D'), volume(1, 'C')];
+
+        let entries = volume_entries(&cfg, &discovered, false);
+        let summary: Vec<_> = entries
+            .iter()
+            .map(|e| {
+                (
+                    e.volume.id,
+                    e.volume.drive_letters[0].as_str(),
+                    e.content_indexed,
+                )
+            })
+            .collect();
+        assert_eq!(summary, [(1, "C:", false), (2, "D:", true)]);
+        assert!(entries.iter().all(|e| e.indexed));
+
+        let all = volume_entries(&cfg, &discovered, true);
+        assert_eq!(all.len(), 3);
+        assert!(!all[2].indexed && !all[2].content_indexed);
+
+        // Empty lists index every volume, content included.
+        cfg.volumes.clear();
+        cfg.content_index_volumes.clear();
+        let entries = volume_entries(&cfg, &discovered, false);
+        assert_eq!(entries.len(), 3);
+        assert!(entries.iter().all(|e| e.indexed && e.content_indexed));
+    }
+}
@@ -1,21 +1,24 @@
 use crate::dispatcher::job_dispatch::{JobDispatcher, JobSpec};
-use crate::scanner;
+use crate::metrics::record_queue_state_global;
+use crate::scanner::volume_roots;
 use crate::status_provider::{
     increment_content_plan, update_content_remaining, update_status_metrics,
-    update_status_queue_state, update_status_scheduler_state,
+    update_status_queue_by_category, update_status_queue_state, update_status_scheduler_state,
+    update_status_volume_space,
 };
-use core_types::FileMeta;
-use core_types::config::{AppConfig, ExtractSection};
+use core_types::config::{AppConfig, ContentQueuePolicy, ExtractSection, VolumeSettingsSection};
+use core_types::{FileFlags, FileMeta, WriterProfile};
 use scheduler::{
-    SchedulerConfig, allow_content_jobs, idle::IdleTracker, metrics::SystemLoadSampler,
+    AutoTuner, DiskSpace, DiskSpaceSampler, JobCategory, SchedulerConfig, SchedulerProfile,
+    TuningSample, allow_content_jobs, idle::IdleTracker, metrics::SystemLoadSampler,
+    volume_has_room,
 };
-use std::collections::VecDeque;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::future::Future;
 use std::path::PathBuf;
-use std::sync::OnceLock;
 use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
-use std::time::Duration;
-use tokio::sync::mpsc;
-use tokio::task;
+use std::sync::{Condvar, Mutex, OnceLock};
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Default)]
 struct SchedulerLiveState {
@@ -27,11 +30,144 @@ struct SchedulerLiveState {
     enqueued_content: AtomicUsize,
 }
 
+impl SchedulerLiveState {
+    /// Mirror the queue counts and content totals into the Prometheus gauges and counters.
+    fn publish_metrics(&self) {
+        record_queue_state_global(
+            (
+                self.critical.load(Ordering::Relaxed),
+                self.metadata.load(Ordering::Relaxed),
+                self.content.load(Ordering::Relaxed),
+            ),
+            self.enqueued_content.load(Ordering::Relaxed) as u64,
+            self.dropped_content.load(Ordering::Relaxed) as u64,
+        );
+    }
+}
+
 static LIVE_STATE: OnceLock<SchedulerLiveState> = OnceLock::new();
-static JOB_SENDER: OnceLock<mpsc::UnboundedSender<JobSpec>> = OnceLock::new();
+static INTAKE: OnceLock<ContentIntake> = OnceLock::new();
 static RUNTIME_ACTIVE: AtomicBool = AtomicBool::new(false);
 
-const MAX_CONTENT_QUEUE: usize = 100_000;
+/// Longest a blocked producer sleeps before re-checking for room.
+const BLOCK_POLL: Duration = Duration::from_millis(100);
+/// How often free space of the indexed volumes is re-read.
+const DISK_SPACE_INTERVAL: Duration = Duration::from_secs(30);
+/// How long shutdown waits for an in-flight batch before leaving it to the worker.
+pub const SHUTDOWN_GRACE: Duration = Duration::from_secs(30);
+
+/// Bounded hand-off from producers (scanner, USN watcher, reindex) to the scheduler loop.
+/// When full, `scheduler.content_queue_policy` decides between waiting and dropping.
+struct ContentIntake {
+    state: Mutex<IntakeState>,
+    room: Condvar,
+}
+
+struct IntakeState {
+    jobs: VecDeque<JobSpec>,
+    capacity: usize,
+    policy: ContentQueuePolicy,
+    /// Cleared when the scheduler stops, releasing producers blocked on a full intake.
+    open: bool,
+}
+
+impl ContentIntake {
+    fn new(capacity: usize, policy: ContentQueuePolicy) -> Self {
+        Self {
+            state: Mutex::new(IntakeState {
+                jobs: VecDeque::new(),
+                capacity: capacity.max(1),
+                policy,
+                open: true,
+            }),
+            room: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, IntakeState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn configure(&self, capacity: usize, policy: ContentQueuePolicy) {
+        let mut state = self.lock();
+        state.capacity = capacity.max(1);
+        state.policy = policy;
+        state.open = true;
+        self.room.notify_all();
+    }
+
+    fn close(&self) {
+        self.lock().open = false;
+        self.room.notify_all();
+    }
+
+    fn policy(&self) -> ContentQueuePolicy {
+        self.lock().policy
+    }
+
+    fn len(&self) -> usize {
+        self.lock().jobs.len()
+    }
+
+    /// Queue `job`, applying the policy while full. Returns `false` if `job` itself was dropped.
+    fn push(&self, job: JobSpec, live: &SchedulerLiveState) -> bool {
+        let mut state = self.lock();
+        while state.jobs.len() >= state.capacity {
+            match state.policy {
+                ContentQueuePolicy::DropNew => {
+                    live.dropped_content.fetch_add(1, Ordering::Relaxed);
+                    tracing::warn!(
+                        capacity = state.capacity,
+                        "content queue full; dropping job for {:?}",
+                        job.path
+                    );
+                    return false;
+                }
+                ContentQueuePolicy::DropOldest => {
+                    if let Some(oldest) = state.jobs.pop_front() {
+                        live.dropped_content.fetch_add(1, Ordering::Relaxed);
+                        tracing::warn!(
+                            capacity = state.capacity,
+                            "content queue full; dropping oldest job for {:?}",
+                            oldest.path
+                        );
+                    }
+                }
+                ContentQueuePolicy::Block => {
+                    // Nobody will make room once the scheduler stopped.
+                    if !state.open {
+                        return false;
+                    }
+                    state = self
+                        .room
+                        .wait_timeout(state, BLOCK_POLL)
+                        .unwrap_or_else(|e| e.into_inner())
+                        .0;
+                }
+            }
+        }
+        state.jobs.push_back(job);
+        true
+    }
+
+    /// Up to `max` jobs in arrival order; wakes blocked producers.
+    fn take(&self, max: usize) -> Vec<JobSpec> {
+        let mut state = self.lock();
+        let n = max.min(state.jobs.len());
+        let jobs = state.jobs.drain(..n).collect();
+        self.room.notify_all();
+        jobs
+    }
+}
+
+fn intake() -> &'static ContentIntake {
+    INTAKE.get_or_init(|| {
+        ContentIntake::new(
+            core_types::config::SchedulerSection::default().content_queue_capacity as usize,
+            ContentQueuePolicy::default(),
+        )
+    })
+}
 
 /// Runtime wrapper that drives a simple scheduling loop and dispatches content batches.
 pub struct SchedulerRuntime {
@@ -39,71 +175,220 @@ pub struct SchedulerRuntime {
     idle: IdleTracker,
     load: SystemLoadSampler,
     content_jobs: VecDeque<JobSpec>,
-    job_rx: mpsc::UnboundedReceiver<JobSpec>,
+    queue_capacity: usize,
+    queue_policy: ContentQueuePolicy,
     dispatcher: JobDispatcher,
     live: &'static SchedulerLiveState,
-    current_volumes: Vec<String>,
     force_allow_content: bool,
+    disk_space: DiskSpaceSampler,
+    last_space_sample: Option<Instant>,
+    volume_space: HashMap<u16, DiskSpace>,
+    min_free_disk_bytes: u64,
+    /// Volumes below `min_free_disk_bytes`; their content jobs stay queued.
+    low_disk: BTreeSet<u16>,
+    /// Scales `config`'s thresholds from indexing feedback while `features.auto_tuning` is on.
+    tuner: Option<AutoTuner>,
 }
 
 impl SchedulerRuntime {
     pub fn new(app_cfg: &AppConfig) -> Self {
-        let config = SchedulerConfig {
+        let mut config = SchedulerConfig {
             warm_idle: Duration::from_secs(app_cfg.scheduler.idle_warm_seconds),
             deep_idle: Duration::from_secs(app_cfg.scheduler.idle_deep_seconds),
-            cpu_metadata_max: app_cfg.scheduler.cpu_soft_limit_pct as f32,
-            cpu_content_max: app_cfg.scheduler.cpu_hard_limit_pct as f32,
             disk_busy_threshold_bps: app_cfg.scheduler.disk_busy_bytes_per_s,
             content_batch_size: app_cfg.scheduler.content_batch_size as usize,
             power_save_mode: app_cfg.scheduler.power_save_mode,
             ..SchedulerConfig::default()
         };
+        config.apply_profile(SchedulerProfile::from_section(&app_cfg.scheduler));
 
         let live = LIVE_STATE.get_or_init(SchedulerLiveState::default);
-        let (tx, rx) = mpsc::unbounded_channel();
-        // Keep a global sender so producers (scanner/USN) can enqueue work from anywhere.
-        let _ = JOB_SENDER.get_or_init(|| tx.clone());
+        let queue_capacity = app_cfg.scheduler.content_queue_capacity.max(1) as usize;
+        let queue_policy = app_cfg.scheduler.content_queue_policy;
+        // Producers (scanner/USN) enqueue through the global intake from anywhere.
+        intake().configure(queue_capacity, queue_policy);
 
         RUNTIME_ACTIVE.store(true, Ordering::Relaxed);
 
-        Self {
+        let mut runtime = Self {
             idle: IdleTracker::new(config.warm_idle, config.deep_idle),
             load: SystemLoadSampler::new(config.disk_busy_threshold_bps),
             content_jobs: VecDeque::new(),
-            job_rx: rx,
+            queue_capacity,
+            queue_policy,
             dispatcher: JobDispatcher::new(app_cfg),
             config,
             live,
-            current_volumes: app_cfg.volumes.clone(),
             force_allow_content: false,
+            disk_space: DiskSpaceSampler::new(),
+            last_space_sample: None,
+            volume_space: HashMap::new(),
+            min_free_disk_bytes: app_cfg.scheduler.min_free_disk_bytes,
+            low_disk: BTreeSet::new(),
+            tuner: None,
+        };
+        runtime.apply_tuning(app_cfg);
+        // Jobs the previous shutdown never started.
+        match runtime.dispatcher.take_pending() {
+            Ok(jobs) => runtime.submit_content_jobs(jobs),
+            Err(e) => tracing::warn!("failed to read pending content jobs: {e:?}"),
         }
+        // Batches whose worker was still running when the previous run ended.
+        match runtime.dispatcher.take_interrupted() {
+            Ok(jobs) => runtime.submit_content_jobs(jobs),
+            Err(e) => tracing::warn!("failed to requeue interrupted content batches: {e:?}"),
+        }
+        runtime
     }
 
+    /// Apply scheduler thresholds from the live config. Volume changes are handled by
+    /// `config_reload`.
     fn update_config(&mut self, app_cfg: &AppConfig) {
-        // Check for volume changes
-        if self.current_volumes != app_cfg.volumes {
-            tracing::info!("Volume configuration changed, triggering rescan...");
-            self.current_volumes = app_cfg.volumes.clone();
-            let cfg_clone = app_cfg.clone();
-
-            // Spawn blocking task to rescan
-            task::spawn_blocking(move || match scanner::scan_volumes(&cfg_clone) {
-                Ok(new_jobs) => {
-                    for job in new_jobs {
-                        enqueue_content_job(job);
-                    }
-                }
-                Err(e) => tracing::error!("Failed to rescan volumes after config update: {}", e),
-            });
+        let sched = &app_cfg.scheduler;
+        let warm_idle = Duration::from_secs(sched.idle_warm_seconds);
+        let deep_idle = Duration::from_secs(sched.idle_deep_seconds);
+        if (warm_idle, deep_idle) != (self.config.warm_idle, self.config.deep_idle)
+            && deep_idle >= warm_idle
+        {
+            self.idle = IdleTracker::new(warm_idle, deep_idle);
+            self.config.warm_idle = warm_idle;
+            self.config.deep_idle = deep_idle;
+        }
+        if sched.disk_busy_bytes_per_s != self.config.disk_busy_threshold_bps {
+            self.load.set_disk_threshold(sched.disk_busy_bytes_per_s);
+            self.config.disk_busy_threshold_bps = sched.disk_busy_bytes_per_s;
+        }
+        self.config
+            .apply_profile(SchedulerProfile::from_section(sched));
+        self.config.content_batch_size = sched.content_batch_size as usize;
+        self.config.power_save_mode = sched.power_save_mode;
+        let capacity = sched.content_queue_capacity.max(1) as usize;
+        if (capacity, sched.content_queue_policy) != (self.queue_capacity, self.queue_policy) {
+            self.queue_capacity = capacity;
+            self.queue_policy = sched.content_queue_policy;
+            intake().configure(capacity, self.queue_policy);
         }
+        if sched.min_free_disk_bytes != self.min_free_disk_bytes {
+            self.min_free_disk_bytes = sched.min_free_disk_bytes;
+            self.refresh_low_disk();
+        }
+    }
 
-        self.config.warm_idle = Duration::from_secs(app_cfg.scheduler.idle_warm_seconds);
-        self.config.deep_idle = Duration::from_secs(app_cfg.scheduler.idle_deep_seconds);
-        self.config.cpu_metadata_max = app_cfg.scheduler.cpu_soft_limit_pct as f32;
-        self.config.cpu_content_max = app_cfg.scheduler.cpu_hard_limit_pct as f32;
-        self.config.disk_busy_threshold_bps = app_cfg.scheduler.disk_busy_bytes_per_s;
-        self.config.content_batch_size = app_cfg.scheduler.content_batch_size as usize;
-        self.config.power_save_mode = app_cfg.scheduler.power_save_mode;
+    /// Scale the configured thresholds in `self.config` by the tuner's factor, starting or
+    /// dropping the tuner as `features.auto_tuning` turns on or off. Runs after every
+    /// [`update_config`](Self::update_config), which restores the configured values.
+    fn apply_tuning(&mut self, app_cfg: &AppConfig) {
+        if !app_cfg.features.auto_tuning {
+            if self.tuner.take().is_some() {
+                tracing::info!("auto-tuning off; scheduler thresholds back to configured values");
+            }
+            return;
+        }
+        let sched = &app_cfg.scheduler;
+        let (min_scale, max_scale) = (
+            sched.auto_tuning_min_pct as f32 / 100.0,
+            sched.auto_tuning_max_pct as f32 / 100.0,
+        );
+        let tuner = self
+            .tuner
+            .get_or_insert_with(|| AutoTuner::new(min_scale, max_scale));
+        tuner.set_bounds(min_scale, max_scale);
+        tuner.apply(&mut self.config);
+        self.load
+            .set_disk_threshold(self.config.disk_busy_threshold_bps);
+    }
+
+    /// Feed one content batch's outcome to the tuner and log any adjustment. The batch counts
+    /// as interrupted if the user's last input came after it started.
+    fn record_batch(&mut self, jobs_done: usize, started: Instant) {
+        let Some(tuner) = self.tuner.as_mut() else {
+            return;
+        };
+        let elapsed = started.elapsed();
+        let sample = TuningSample {
+            interrupted: self.idle.sample().idle_for < elapsed,
+            jobs_done,
+            elapsed,
+            backlog: !self.content_jobs.is_empty(),
+        };
+        if let Some(step) = tuner.observe(sample) {
+            tracing::info!(
+                from = step.from,
+                to = step.to,
+                interruption_rate = step.interruption_rate,
+                jobs_per_sec = step.jobs_per_sec,
+                "auto-tuning: {} indexing; thresholds now at {:.0}% of configured",
+                if step.to < step.from {
+                    "backing off"
+                } else {
+                    "stepping up"
+                },
+                step.to * 100.0
+            );
+        }
+    }
+
+    /// Re-read free space of the scanned volumes every `DISK_SPACE_INTERVAL`.
+    fn sample_disk_space(&mut self) {
+        if self
+            .last_space_sample
+            .is_some_and(|at| at.elapsed() < DISK_SPACE_INTERVAL)
+        {
+            return;
+        }
+        self.last_space_sample = Some(Instant::now());
+        self.disk_space.refresh();
+        for (volume, root) in volume_roots() {
+            if let Some(space) = self.disk_space.space_for(&root) {
+                self.volume_space.insert(volume, space);
+            }
+        }
+        self.refresh_low_disk();
+    }
+
+    fn refresh_low_disk(&mut self) {
+        let floor = self.min_free_disk_bytes;
+        let low: BTreeSet<u16> = self
+            .volume_space
+            .iter()
+            .filter(|(_, space)| !volume_has_room(Some(**space), floor))
+            .map(|(volume, _)| *volume)
+            .collect();
+        for volume in low.difference(&self.low_disk) {
+            tracing::warn!(
+                volume,
+                free_bytes = self.volume_space[volume].free_bytes,
+                min_free_bytes = floor,
+                "volume is low on disk space; pausing its content indexing"
+            );
+        }
+        for volume in self.low_disk.difference(&low) {
+            tracing::info!(
+                volume,
+                "volume has free space again; resuming content indexing"
+            );
+        }
+        self.low_disk = low;
+    }
+
+    /// Take up to `batch_size` jobs in queue order, skipping volumes low on disk space. Skipped
+    /// jobs keep their place at the head of the queue.
+    fn take_batch(&mut self, batch_size: usize) -> Vec<JobSpec> {
+        let mut batch = Vec::with_capacity(batch_size);
+        let mut held = Vec::new();
+        while batch.len() < batch_size
+            && let Some(job) = self.content_jobs.pop_front()
+        {
+            if self.low_disk.contains(&job.volume_id) {
+                held.push(job);
+            } else {
+                batch.push(job);
+            }
+        }
+        for job in held.into_iter().rev() {
+            self.content_jobs.push_front(job);
+        }
+        batch
     }
 
     /// Submit a content indexing job (path + doc ids).
@@ -133,23 +418,70 @@ impl SchedulerRuntime {
         // Metadata/critical queues not implemented yet; keep zero.
         self.live.critical.store(0, Ordering::Relaxed);
         self.live.metadata.store(0, Ordering::Relaxed);
+        self.live.publish_metrics();
+    }
+
+    pub async fn run_loop(self) {
+        self.run_until(std::future::pending(), SHUTDOWN_GRACE).await
     }
 
-    pub async fn run_loop(mut self) {
+    /// Run the loop until `shutdown` resolves. New jobs are then refused, a batch already
+    /// handed to a worker gets up to `grace` to finish (the worker commits its own writer),
+    /// and jobs that never started are saved to be re-queued on the next start.
+    pub async fn run_until(mut self, shutdown: impl Future<Output = ()>, grace: Duration) {
+        tokio::pin!(shutdown);
         let mut interval = tokio::time::interval(Duration::from_secs(1));
         loop {
-            interval.tick().await;
-            self.tick().await;
+            tokio::select! {
+                _ = &mut shutdown => break,
+                _ = interval.tick() => {}
+            }
+            let tick = self.tick();
+            tokio::pin!(tick);
+            tokio::select! {
+                _ = &mut tick => {}
+                _ = &mut shutdown => {
+                    RUNTIME_ACTIVE.store(false, Ordering::Relaxed);
+                    tracing::info!("shutdown requested; waiting for the in-flight batch");
+                    if tokio::time::timeout(grace, &mut tick).await.is_err() {
+                        tracing::warn!(
+                            "in-flight batch still running after {grace:?}; leaving it to the worker"
+                        );
+                    }
+                    break;
+                }
+            }
         }
+        self.finish_shutdown();
+    }
+
+    fn finish_shutdown(&mut self) {
+        RUNTIME_ACTIVE.store(false, Ordering::Relaxed);
+        intake().close();
+        for job in intake().take(usize::MAX) {
+            self.content_jobs.push_back(job);
+        }
+        let pending: Vec<JobSpec> = self.content_jobs.drain(..).collect();
+        let count = pending.len();
+        match self.dispatcher.save_pending(pending) {
+            Ok(()) => tracing::info!(count, "scheduler stopped; saved unstarted content jobs"),
+            Err(e) => tracing::error!("failed to save {count} pending content jobs: {e:?}"),
+        }
+        self.update_live_counts();
     }
 
     pub async fn tick(&mut self) {
         // Reload config dynamically (from memory cache updated by IPC)
         let app_cfg = core_types::config::get_current_config();
         self.update_config(&app_cfg);
+        self.apply_tuning(&app_cfg);
+        self.sample_disk_space();
+        update_status_volume_space(&self.volume_space);
 
-        // Drain any newly submitted content jobs.
-        while let Ok(job) = self.job_rx.try_recv() {
+        // Move newly submitted content jobs over as far as the queue has room; the rest wait
+        // in the intake, where the queue policy applies.
+        let room = self.queue_capacity.saturating_sub(self.content_jobs.len());
+        for job in intake().take(room) {
             self.push_job(job);
         }
         self.update_live_counts();
@@ -162,16 +494,25 @@ impl SchedulerRuntime {
         let workers = self.live.active_workers.load(Ordering::Relaxed);
         let dropped = self.live.dropped_content.load(Ordering::Relaxed);
         let enqueued = self.live.enqueued_content.load(Ordering::Relaxed);
-        update_status_scheduler_state(format!(
+        let mut state = format!(
             "idle={:?} cpu={:.1}% mem={:.1}% queue(content)={} dropped={} enqueued={}",
             idle_sample.state, load.cpu_percent, load.mem_used_percent, ct, dropped, enqueued
-        ));
+        );
+        if !self.low_disk.is_empty() {
+            state.push_str(&format!(" low_disk={:?}", self.low_disk));
+        }
+        update_status_scheduler_state(state);
         update_status_queue_state(
             Some(ct as u64),
             Some(workers),
             Some(self.live.enqueued_content.load(Ordering::Relaxed) as u64),
             Some(self.live.dropped_content.load(Ordering::Relaxed) as u64),
         );
+        update_status_queue_by_category((
+            self.live.critical.load(Ordering::Relaxed),
+            self.live.metadata.load(Ordering::Relaxed),
+            self.live.content.load(Ordering::Relaxed),
+        ));
         update_content_remaining(ct as u64, workers);
         update_status_metrics(None);
 
@@ -180,13 +521,13 @@ impl SchedulerRuntime {
             self.force_allow_content || allow_content_jobs(idle_sample.state, load, &self.config);
 
         // If backlog is large, override load/idle gates to prevent permanent stalls.
-        let backlog = self.content_jobs.len();
-        if backlog >= (MAX_CONTENT_QUEUE / 2) {
+        let backlog = self.content_jobs.len() + intake().len();
+        if backlog >= (self.queue_capacity / 2).max(1) {
             allow_content = true;
             tracing::warn!(
-                "Backlog high ({} jobs, max {}); overriding load gates to drain queue",
+                "Backlog high ({} jobs, queue capacity {}); overriding load gates to drain queue",
                 backlog,
-                MAX_CONTENT_QUEUE
+                self.queue_capacity
             );
         }
 
@@ -197,34 +538,62 @@ impl SchedulerRuntime {
                 .min(self.content_jobs.len())
                 .max(1);
 
-            let mut batch = Vec::with_capacity(batch_size);
-            for _ in 0..batch_size {
-                if let Some(job) = self.content_jobs.pop_front() {
-                    batch.push(job);
-                }
+            let batch = self.take_batch(batch_size);
+            if batch.is_empty() {
+                return;
             }
 
             self.update_live_counts();
             self.live.active_workers.fetch_add(1, Ordering::Relaxed);
 
+            let started = Instant::now();
+            let jobs = batch.len();
             if let Err(e) = self.dispatcher.spawn_batch(batch).await {
                 tracing::error!("failed to dispatch batch: {e:?}");
             }
+            // Transient failures (e.g. locked files) go to the back of the queue.
+            match self.dispatcher.take_retries() {
+                Ok(retries) => {
+                    for job in retries {
+                        self.push_job(job);
+                    }
+                }
+                Err(e) => tracing::warn!("failed to read worker retries: {e:?}"),
+            }
+            enforce_content_cap(&app_cfg).await;
+            self.record_batch(jobs, started);
 
             self.live.active_workers.fetch_sub(1, Ordering::Relaxed);
         }
     }
 
+    /// Queue a job the scheduler itself produced (seeds, retries, pending jobs). The queue
+    /// policy applies, except that `block` admits the job: the loop cannot wait on itself.
     fn push_job(&mut self, job: JobSpec) {
-        if self.content_jobs.len() >= MAX_CONTENT_QUEUE {
-            self.live.dropped_content.fetch_add(1, Ordering::Relaxed);
-            tracing::warn!(
-                queue_len = self.content_jobs.len(),
-                max = MAX_CONTENT_QUEUE,
-                "content queue full; dropping job for {:?}",
-                job.path
-            );
-            return;
+        if self.content_jobs.len() >= self.queue_capacity {
+            match self.queue_policy {
+                ContentQueuePolicy::DropNew => {
+                    self.live.dropped_content.fetch_add(1, Ordering::Relaxed);
+                    tracing::warn!(
+                        queue_len = self.content_jobs.len(),
+                        max = self.queue_capacity,
+                        "content queue full; dropping job for {:?}",
+                        job.path
+                    );
+                    return;
+                }
+                ContentQueuePolicy::DropOldest => {
+                    if let Some(oldest) = self.content_jobs.pop_front() {
+                        self.live.dropped_content.fetch_add(1, Ordering::Relaxed);
+                        tracing::warn!(
+                            max = self.queue_capacity,
+                            "content queue full; dropping oldest job for {:?}",
+                            oldest.path
+                        );
+                    }
+                }
+                ContentQueuePolicy::Block => {}
+            }
         }
         let size_hint = job.file_size;
         self.content_jobs.push_back(job);
@@ -234,8 +603,37 @@ impl SchedulerRuntime {
     }
 }
 
+/// Apply `extract.content_index_max_bytes` once the worker released the index writer.
+async fn enforce_content_cap(app_cfg: &AppConfig) {
+    let max_bytes = app_cfg.extract.content_index_max_bytes;
+    if max_bytes == 0 {
+        return;
+    }
+    let path = PathBuf::from(&app_cfg.paths.content_index);
+    let order = app_cfg.extract.content_eviction;
+    let search = app_cfg.search.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let idx = content_index::open_or_create_with(&path, &search)?;
+        let writer_cfg = content_index::WriterConfig::auto_for(WriterProfile::SteadyState);
+        content_index::eviction::enforce_size_cap(&idx, &path, max_bytes, order, &writer_cfg)
+    })
+    .await;
+    match result {
+        Ok(Ok(report)) if report.evicted_docs > 0 => tracing::info!(
+            evicted = report.evicted_docs,
+            bytes_before = report.bytes_before,
+            bytes_after = report.bytes_after,
+            max_bytes,
+            "content index over its size cap; evicted docs ({order:?})"
+        ),
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => tracing::warn!("content index size cap failed: {e:?}"),
+        Err(e) => tracing::warn!("content index size cap task failed: {e:?}"),
+    }
+}
+
 /// Enqueue a content indexing job for the scheduler loop.
-/// Returns `false` if the scheduler has not been initialized yet.
+/// Returns `false` if the scheduler is not running or a full queue dropped the job.
 pub fn enqueue_content_job(job: JobSpec) -> bool {
     if !RUNTIME_ACTIVE.load(Ordering::Relaxed) {
         tracing::warn!("scheduler not initialized; dropping content job");
@@ -244,22 +642,15 @@ pub fn enqueue_content_job(job: JobSpec) -> bool {
         return false;
     }
 
-    match JOB_SENDER.get() {
-        Some(tx) => {
-            if tx.send(job).is_ok() {
-                true
-            } else {
-                let live = LIVE_STATE.get_or_init(SchedulerLiveState::default);
-                live.dropped_content.fetch_add(1, Ordering::Relaxed);
-                false
-            }
-        }
-        None => {
-            tracing::warn!("scheduler not initialized; dropping content job");
-            let live = LIVE_STATE.get_or_init(SchedulerLiveState::default);
-            live.dropped_content.fetch_add(1, Ordering::Relaxed);
-            false
-        }
+    let live = LIVE_STATE.get_or_init(SchedulerLiveState::default);
+    let intake = intake();
+    // A blocking push must not stall a runtime worker thread unannounced.
+    let on_multi_thread = tokio::runtime::Handle::try_current()
+        .is_ok_and(|h| h.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread);
+    if intake.policy() == ContentQueuePolicy::Block && on_multi_thread {
+        tokio::task::block_in_place(|| intake.push(job, live))
+    } else {
+        intake.push(job, live)
     }
 }
 
@@ -275,17 +666,51 @@ pub fn set_live_queue_counts(critical: usize, metadata: usize, content: usize) {
     live.critical.store(critical, Ordering::Relaxed);
     live.metadata.store(metadata, Ordering::Relaxed);
     live.content.store(content, Ordering::Relaxed);
+    live.publish_metrics();
 }
 
 impl Drop for SchedulerRuntime {
     fn drop(&mut self) {
         RUNTIME_ACTIVE.store(false, Ordering::Relaxed);
+        intake().close();
+    }
+}
+
+/// What enumeration does with an entry, based on its NTFS flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryDisposition {
+    /// Drop the entry entirely.
+    Skip,
+    /// Index metadata only; the file is never opened.
+    MetadataOnly,
+    /// Index metadata and queue content extraction.
+    Full,
+}
+
+/// Decide how to treat an entry. Reparse points are skipped unless enabled (junction loops and
+/// double-indexing); offline/cloud placeholders never get content jobs since opening them
+/// would trigger a download.
+pub fn entry_disposition(flags: FileFlags, settings: &VolumeSettingsSection) -> EntryDisposition {
+    if flags.is_reparse() && !settings.index_reparse_points {
+        return EntryDisposition::Skip;
+    }
+    if flags.is_offline() {
+        return if settings.index_offline_files {
+            EntryDisposition::MetadataOnly
+        } else {
+            EntryDisposition::Skip
+        };
+    }
+    if flags.is_dir() {
+        EntryDisposition::MetadataOnly
+    } else {
+        EntryDisposition::Full
     }
 }
 
 /// Convert a `FileMeta` into a `JobSpec` if it looks indexable.
 pub fn content_job_from_meta(meta: &FileMeta, extract: &ExtractSection) -> Option<JobSpec> {
-    if meta.flags.is_dir() {
+    if meta.flags.is_dir() || meta.flags.is_offline() {
         return None;
     }
     let path_str = meta.path.as_ref()?;
@@ -307,6 +732,8 @@ pub fn content_job_from_meta(meta: &FileMeta, extract: &ExtractSection) -> Optio
         max_bytes: Some(to_usize(extract.max_bytes_per_file)),
         max_chars: Some(to_usize(extract.max_chars_per_file)),
         file_size: meta.size,
+        priority: Some(JobCategory::Content),
+        attempts: 0,
     })
 }
 
@@ -323,6 +750,7 @@ pub fn live_counters() -> (usize, usize) {
 mod tests {
     use super::*;
     use crate::status_provider::init_basic_status_provider;
+    use core_types::config::IndexingProfile;
 
     fn dummy_job() -> JobSpec {
         JobSpec {
@@ -332,9 +760,315 @@ mod tests {
             max_bytes: None,
             max_chars: None,
             file_size: 0,
+            priority: None,
+            attempts: 0,
+        }
+    }
+
+    #[test]
+    fn update_config_applies_scheduler_thresholds_in_place() {
+        let _ = init_basic_status_provider();
+        let mut cfg = AppConfig::default();
+        let mut rt = SchedulerRuntime::new(&cfg);
+
+        cfg.scheduler.idle_warm_seconds = 30;
+        cfg.scheduler.idle_deep_seconds = 120;
+        cfg.scheduler.disk_busy_bytes_per_s = 42;
+        cfg.scheduler.content_batch_size = 7;
+        cfg.scheduler.cpu_hard_limit_pct = Some(95);
+        rt.update_config(&cfg);
+
+        assert_eq!(rt.config.warm_idle, Duration::from_secs(30));
+        assert_eq!(rt.config.deep_idle, Duration::from_secs(120));
+        assert_eq!(rt.load.disk_threshold(), 42);
+        assert_eq!(rt.config.content_batch_size, 7);
+        assert_eq!(rt.config.cpu_content_max, 95.0);
+
+        cfg.scheduler.cpu_hard_limit_pct = None;
+        cfg.scheduler.profile = IndexingProfile::Aggressive;
+        rt.update_config(&cfg);
+        assert_eq!(rt.config.cpu_content_max, 75.0);
+        assert_eq!(rt.config.content_budget.max_files, 256);
+    }
+
+    #[test]
+    fn auto_tuning_scales_configured_thresholds_until_turned_off() {
+        let _ = init_basic_status_provider();
+        let mut cfg = AppConfig::default();
+        cfg.features.auto_tuning = true;
+        cfg.scheduler.content_batch_size = 100;
+        let mut rt = SchedulerRuntime::new(&cfg);
+        assert_eq!(rt.config.content_batch_size, 100);
+
+        let tuner = rt.tuner.as_mut().unwrap();
+        for _ in 0..scheduler::policy::tuning::TUNING_WINDOW {
+            tuner.observe(TuningSample {
+                interrupted: true,
+                jobs_done: 1,
+                elapsed: Duration::from_secs(1),
+                backlog: true,
+            });
+        }
+        // Every tick restores the configured values before scaling them, so the factor
+        // never compounds.
+        for _ in 0..3 {
+            rt.update_config(&cfg);
+            rt.apply_tuning(&cfg);
+        }
+        assert_eq!(rt.config.content_batch_size, 75);
+        assert_eq!(
+            rt.load.disk_threshold(),
+            cfg.scheduler.disk_busy_bytes_per_s * 3 / 4
+        );
+
+        cfg.features.auto_tuning = false;
+        rt.update_config(&cfg);
+        rt.apply_tuning(&cfg);
+        assert!(rt.tuner.is_none());
+        assert_eq!(rt.config.content_batch_size, 100);
+        assert_eq!(
+            rt.load.disk_threshold(),
+            cfg.scheduler.disk_busy_bytes_per_s
+        );
+    }
+
+    #[test]
+    fn low_disk_volumes_keep_their_content_jobs_queued() {
+        let _ = init_basic_status_provider();
+        let mut cfg = AppConfig::default();
+        cfg.scheduler.min_free_disk_bytes = 1_000;
+        let mut rt = SchedulerRuntime::new(&cfg);
+        let job = |volume_id, file_id| JobSpec {
+            volume_id,
+            file_id,
+            ..dummy_job()
+        };
+        for (volume, file) in [(1, 1), (2, 2), (1, 3), (2, 4)] {
+            rt.content_jobs.push_back(job(volume, file));
+        }
+        let space = |free_bytes| DiskSpace {
+            free_bytes,
+            total_bytes: 10_000,
+        };
+        rt.volume_space.insert(1, space(500));
+        rt.volume_space.insert(2, space(5_000));
+        rt.refresh_low_disk();
+        assert_eq!(rt.low_disk, BTreeSet::from([1]));
+
+        let ids = |jobs: &[JobSpec]| jobs.iter().map(|j| j.file_id).collect::<Vec<_>>();
+        assert_eq!(ids(&rt.take_batch(10)), [2, 4]);
+        let queued: Vec<_> = rt.content_jobs.iter().cloned().collect();
+        assert_eq!(ids(&queued), [1, 3]);
+
+        // Lowering the floor below the free space releases the volume.
+        cfg.scheduler.min_free_disk_bytes = 100;
+        rt.update_config(&cfg);
+        assert!(rt.low_disk.is_empty());
+        assert_eq!(ids(&rt.take_batch(1)), [1]);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn shutdown_mid_batch_finishes_the_started_batch_and_saves_the_rest() {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = init_basic_status_provider();
+        let dir = tempfile::tempdir().unwrap();
+        let index_dir = dir.path().join("content");
+        std::fs::create_dir_all(&index_dir).unwrap();
+        // Stand-in worker: takes a while, then "commits" the batch it was given.
+        let worker = dir.path().join("worker.sh");
+        std::fs::write(
+            &worker,
+            "#!/bin/sh\nsleep 0.5\ncp \"$2\" \"$4/committed.json\"\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&worker, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut cfg = AppConfig::default();
+        cfg.paths.jobs_dir = dir.path().join("jobs").to_string_lossy().into_owned();
+        cfg.paths.content_index = index_dir.to_string_lossy().into_owned();
+        let mut rt = SchedulerRuntime::new(&cfg);
+        rt.dispatcher.set_worker_path(worker);
+        rt.force_allow_content();
+        rt.submit_content_job(dummy_job());
+
+        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+        let run = tokio::spawn(rt.run_until(
+            async {
+                let _ = stop_rx.await;
+            },
+            Duration::from_secs(10),
+        ));
+        // Signal as soon as the batch has been handed to the worker.
+        let jobs_dir = PathBuf::from(&cfg.paths.jobs_dir);
+        let started = Instant::now();
+        while !std::fs::read_dir(&jobs_dir).is_ok_and(|mut d| d.next().is_some()) {
+            assert!(
+                started.elapsed() < Duration::from_secs(5),
+                "batch never started"
+            );
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        stop_tx.send(()).unwrap();
+        run.await.unwrap();
+
+        let committed = std::fs::read_to_string(index_dir.join("committed.json")).unwrap();
+        assert!(committed.contains("dummy"), "{committed}");
+
+        // The started job is not queued again; an unstarted one survives the restart.
+        let mut rt = SchedulerRuntime::new(&cfg);
+        assert!(rt.content_jobs.is_empty());
+        rt.submit_content_job(JobSpec {
+            file_id: 2,
+            ..dummy_job()
+        });
+        rt.finish_shutdown();
+        let rt = SchedulerRuntime::new(&cfg);
+        let queued: Vec<u64> = rt.content_jobs.iter().map(|j| j.file_id).collect();
+        assert_eq!(queued, [2]);
+    }
+
+    fn intake_ids(intake: &ContentIntake) -> Vec<u64> {
+        intake.lock().jobs.iter().map(|j| j.file_id).collect()
+    }
+
+    fn full_intake(policy: ContentQueuePolicy) -> (ContentIntake, SchedulerLiveState) {
+        let intake = ContentIntake::new(2, policy);
+        let live = SchedulerLiveState::default();
+        for file_id in [1, 2] {
+            assert!(intake.push(
+                JobSpec {
+                    file_id,
+                    ..dummy_job()
+                },
+                &live
+            ));
+        }
+        (intake, live)
+    }
+
+    #[test]
+    fn drop_new_policy_keeps_the_queued_jobs() {
+        let (intake, live) = full_intake(ContentQueuePolicy::DropNew);
+        let job = JobSpec {
+            file_id: 3,
+            ..dummy_job()
+        };
+        assert!(!intake.push(job, &live));
+        assert_eq!(intake_ids(&intake), [1, 2]);
+        assert_eq!(live.dropped_content.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn drop_oldest_policy_makes_room_for_the_new_job() {
+        let (intake, live) = full_intake(ContentQueuePolicy::DropOldest);
+        let job = JobSpec {
+            file_id: 3,
+            ..dummy_job()
+        };
+        assert!(intake.push(job, &live));
+        assert_eq!(intake_ids(&intake), [2, 3]);
+        assert_eq!(live.dropped_content.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn block_policy_waits_for_room_and_drops_nothing() {
+        let (intake, live) = full_intake(ContentQueuePolicy::Block);
+        std::thread::scope(|scope| {
+            let producer = scope.spawn(|| {
+                let job = JobSpec {
+                    file_id: 3,
+                    ..dummy_job()
+                };
+                intake.push(job, &live)
+            });
+            std::thread::sleep(Duration::from_millis(50));
+            assert!(!producer.is_finished(), "push should wait while full");
+            assert_eq!(intake.take(1)[0].file_id, 1);
+            assert!(producer.join().unwrap());
+        });
+        assert_eq!(intake_ids(&intake), [2, 3]);
+        assert_eq!(live.dropped_content.load(Ordering::Relaxed), 0);
+
+        // A closed intake releases a waiting producer without counting a drop.
+        intake.close();
+        assert!(!intake.push(dummy_job(), &live));
+        assert_eq!(live.dropped_content.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn disposition_defaults_skip_reparse_and_keep_offline_names() {
+        let settings = VolumeSettingsSection::default();
+        let cases = [
+            (FileFlags::empty(), EntryDisposition::Full),
+            (
+                FileFlags::HIDDEN | FileFlags::ARCHIVE,
+                EntryDisposition::Full,
+            ),
+            (FileFlags::IS_DIR, EntryDisposition::MetadataOnly),
+            (FileFlags::REPARSE, EntryDisposition::Skip),
+            (
+                FileFlags::IS_DIR | FileFlags::REPARSE,
+                EntryDisposition::Skip,
+            ),
+            (FileFlags::OFFLINE, EntryDisposition::MetadataOnly),
+            (
+                FileFlags::REPARSE | FileFlags::OFFLINE,
+                EntryDisposition::Skip,
+            ),
+        ];
+        for (flags, expected) in cases {
+            assert_eq!(entry_disposition(flags, &settings), expected, "{flags:?}");
         }
     }
 
+    #[test]
+    fn disposition_honors_toggles() {
+        let settings = VolumeSettingsSection {
+            index_reparse_points: true,
+            index_offline_files: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            entry_disposition(FileFlags::REPARSE, &settings),
+            EntryDisposition::Full
+        );
+        assert_eq!(
+            entry_disposition(FileFlags::IS_DIR | FileFlags::REPARSE, &settings),
+            EntryDisposition::MetadataOnly
+        );
+        assert_eq!(
+            entry_disposition(FileFlags::OFFLINE, &settings),
+            EntryDisposition::Skip
+        );
+        // Even when reparse points are indexed, a cloud placeholder is never opened.
+        let all = VolumeSettingsSection {
+            index_reparse_points: true,
+            index_offline_files: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            entry_disposition(FileFlags::REPARSE | FileFlags::OFFLINE, &all),
+            EntryDisposition::MetadataOnly
+        );
+    }
+
+    #[test]
+    fn offline_files_never_get_content_jobs() {
+        let meta = FileMeta::new(
+            core_types::DocKey::from_parts(1, 7),
+            1,
+            None,
+            "report.docx".into(),
+            Some("C:\\OneDrive\\report.docx".into()),
+            1024,
+            0,
+            0,
+            FileFlags::OFFLINE,
+        );
+        assert!(content_job_from_meta(&meta, &ExtractSection::default()).is_none());
+    }
+
     #[test]
     fn enqueue_without_runtime_increments_dropped() {
         // Ensure we start from a clean slate in case another test initialized the runtime.
@@ -1,22 +1,121 @@
-use anyhow::Result;
-use content_index::{ContentIndex, open_or_create as open_content};
+use crate::caller::caller_can_read;
+use crate::paging::{
+    DOC_KEY_FIELD, HideKeys, Page, PageCursor, SortValue, collect_page, collect_sorted_page,
+    compare_ranked, count_matches,
+};
+use crate::planner::{
+    ModePlan, QueryFields, QueryPlanner, configured_default_fields, exclude_paths, flags_query,
+    to_tantivy,
+};
+use crate::query_cache::{CacheKey, Generation, QueryCache};
+use crate::query_log::{MAX_LOG_BYTES, QUERY_LOG_FILE, QueryLog};
+use crate::snippet_cache::{SnippetCache, terms_hash};
+use crate::status::served_by;
+use crate::suggest::{name_suggestions, prefix_completions};
+use anyhow::{Context, Result};
+use content_extractor::{ExtractContext, ExtractorStack, enforce_limits_str};
+use content_index::content_analysis::ContentAnalysis;
+use content_index::{ContentIndex, open_or_recreate_with as open_content};
+use core_types::config::AppConfig;
+use core_types::{DocKey, FileFlags, FileId, FileKind, VolumeId};
 use ipc::{
-    FieldKind, QueryExpr, SearchHit, SearchMode, SearchRequest, SearchResponse, TermExpr,
-    TermModifier,
+    ChildrenRequest, ChildrenResponse, CompleteRequest, CompleteResponse, FieldKind, GetDocRequest,
+    GetDocResponse, PlanRequest, PlanResponse, PreviewRequest, PreviewResponse, QueryExpr,
+    ReindexScope, ResultGroup, ScanRequest, ScanResponse, SearchHit, SearchMode, SearchRequest,
+    SearchResponse, SortBy,
 };
-use meta_index::{MetaFields, MetaIndex, open_or_create_index, open_reader};
-use std::path::Path;
-use std::sync::OnceLock;
-use std::time::Instant;
+use meta_index::delta::{DeltaIndex, DeltaSnapshot};
+use meta_index::{MetaFields, MetaIndex, open_reader};
+use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 use tantivy::collector::{Count, TopDocs};
-use tantivy::query::{BooleanQuery, Occur, Query, QueryParser, TermQuery};
-use tantivy::schema::{Document, IndexRecordOption, TantivyDocument, Value};
-use tantivy::{IndexReader, Score, Term};
-use tracing::warn;
+use tantivy::query::{BooleanQuery, Query, RangeQuery, TermQuery};
+use tantivy::schema::{Document, Field, IndexRecordOption, TantivyDocument, Value};
+use tantivy::snippet::SnippetGenerator;
+use tantivy::{DocAddress, IndexReader, Order, Score, Searcher, Term};
+use tracing::{debug, instrument, warn};
 
 /// Trait for handling search requests.
 pub trait SearchHandler: Send + Sync {
-    fn search(&self, req: SearchRequest) -> SearchResponse;
+    /// Run `req`. Fails only when its query can't be built, e.g. a range with reversed bounds;
+    /// an index that fails while searching still answers, with no hits.
+    fn try_search(&self, req: SearchRequest) -> Result<SearchResponse>;
+
+    /// [`try_search`](Self::try_search) for callers with nowhere to report a bad query.
+    fn search(&self, req: SearchRequest) -> SearchResponse {
+        let id = req.id;
+        self.try_search(req).unwrap_or_else(|err| {
+            warn!(error = %err, "failed to build query");
+            empty_response(id)
+        })
+    }
+
+    /// Look up a single document by key; handlers without an index find nothing.
+    fn get_doc(&self, req: GetDocRequest) -> GetDocResponse {
+        GetDocResponse {
+            id: req.id,
+            meta: None,
+            content: None,
+        }
+    }
+
+    /// Report the mode an `Auto` search of `req.query` would run under, without running it.
+    fn plan(&self, req: PlanRequest) -> PlanResponse {
+        let plan = QueryPlanner::choose_mode(&QueryPlanner::optimize(req.query));
+        PlanResponse {
+            id: req.id,
+            chosen_mode: plan.mode,
+            reasons: plan.reasons,
+            estimated_cost: plan.estimated_cost,
+        }
+    }
+
+    /// Extract preview text for `req.key`; handlers without an index find nothing.
+    fn preview(&self, req: PreviewRequest) -> PreviewResponse {
+        PreviewResponse {
+            id: req.id,
+            text: None,
+            truncated: false,
+            kind: FileKind::Other,
+        }
+    }
+
+    /// Complete the word being typed; handlers without an index have no terms to offer.
+    fn complete(&self, req: CompleteRequest) -> CompleteResponse {
+        CompleteResponse {
+            id: req.id,
+            completions: Vec::new(),
+        }
+    }
+
+    /// List the direct children of a directory; handlers without an index find none.
+    fn children(&self, req: ChildrenRequest) -> ChildrenResponse {
+        ChildrenResponse {
+            id: req.id,
+            hits: Vec::new(),
+            total: 0,
+        }
+    }
+
+    /// Page through every indexed doc in `DocKey` order; handlers without an index are empty.
+    fn scan(&self, req: ScanRequest) -> ScanResponse {
+        ScanResponse {
+            id: req.id,
+            hits: Vec::new(),
+            next: None,
+        }
+    }
+
+    /// Give back memory held for speed (cached responses, snippets, stale searchers) when the
+    /// service is over its memory ceiling. Handlers without caches have nothing to trim.
+    fn trim_caches(&self) {}
+
+    /// Pick up settings from a reloaded config. Handlers that keep none have nothing to do.
+    fn apply_config(&self, _cfg: &AppConfig) {}
 }
 
 /// Simple placeholder handler that returns an empty response.
@@ -24,15 +123,26 @@ pub trait SearchHandler: Send + Sync {
 pub struct StubSearchHandler;
 
 impl SearchHandler for StubSearchHandler {
-    fn search(&self, req: SearchRequest) -> SearchResponse {
-        SearchResponse {
-            id: req.id,
-            hits: Vec::new(),
-            total: 0,
-            truncated: false,
-            took_ms: 0,
-            served_by: Some("service-stub".into()),
-        }
+    fn try_search(&self, req: SearchRequest) -> Result<SearchResponse> {
+        Ok(empty_response(req.id))
+    }
+}
+
+/// A response with no hits, as the stub handler gives.
+fn empty_response(id: uuid::Uuid) -> SearchResponse {
+    SearchResponse {
+        id,
+        hits: Vec::new(),
+        total: 0,
+        truncated: false,
+        took_ms: 0,
+        served_by: Some("service-stub".into()),
+        next_cursor: None,
+        explanation: None,
+        timed_out: false,
+        degraded: None,
+        suggestions: Vec::new(),
+        groups: Vec::new(),
     }
 }
 
@@ -40,7 +150,26 @@ impl SearchHandler for StubSearchHandler {
 pub struct UnifiedSearchHandler {
     meta: MetaIndex,
     meta_reader: IndexReader,
+    /// RAM tier holding recent metadata changes (`features.delta_index`), unioned with
+    /// `meta_reader` by name searches, counts and `get_doc`.
+    delta: Option<Arc<DeltaIndex>>,
+    /// `delta`'s merge count when `meta_reader` was last reloaded for it.
+    delta_merges: Mutex<u64>,
     content: Option<(ContentIndex, IndexReader)>,
+    cache: QueryCache,
+    /// Content hit snippets, sized by `search.snippet_cache_size`.
+    snippets: SnippetCache,
+    /// `search.excluded_paths`, dropped from results unless a request sets `include_system`.
+    excluded_paths: Vec<String>,
+    /// `search.default_fields`; empty keeps each index's built-in fields.
+    default_fields: Vec<(FieldKind, f32)>,
+    /// `search.max_limit`: the most hits one request may ask for.
+    max_limit: u32,
+    /// `metrics.slow_query_ms`; `None` when disabled.
+    slow_query: Option<Duration>,
+    /// Anonymized record of every executed search (`features.log_dataset_mode`); follows
+    /// config reloads.
+    query_log: Mutex<Option<Arc<QueryLog>>>,
 }
 
 impl UnifiedSearchHandler {
@@ -49,17 +178,37 @@ impl UnifiedSearchHandler {
             "UnifiedSearchHandler: opening meta index at {:?}",
             meta_path
         );
-        let meta = open_or_create_index(meta_path)?;
-        let meta_reader = open_reader(&meta)?;
+        let meta = crate::meta_ingest::open_meta_index(meta_path)?;
 
-        let content = match open_content(content_path) {
-            Ok(idx) => {
+        let cfg = core_types::config::get_current_config();
+        let content = match open_content(
+            content_path,
+            &cfg.search,
+            cfg.paths.recreate_corrupt_indexes,
+        ) {
+            Ok((mut idx, outcome)) => {
+                // Recreated empty: rescan so every file's content is queued again.
+                crate::reindex::repopulate_if_recreated(&outcome, ReindexScope::ContentOnly);
+                idx.set_doc_type_analyzers(cfg.features.doc_type_analyzers);
+                idx.set_path_segments(cfg.features.path_segments);
+                let wanted = ContentAnalysis::from_search(&cfg.search);
+                let built = idx.content_analysis();
+                if built != Some(wanted) {
+                    warn!(
+                        ?built,
+                        ?wanted,
+                        "content index at {} was built with different content_stemming, \
+                         content_stopwords or content_max_token_len; rebuild it (delete the \
+                         directory and run `ultrasearch reindex --content-only`) to apply the \
+                         configured ones",
+                        content_path.display()
+                    );
+                }
                 tracing::info!(
                     "UnifiedSearchHandler: opened content index at {:?}",
                     content_path
                 );
-                let reader = content_index::open_reader(&idx)?;
-                Some((idx, reader))
+                Some(idx)
             }
             Err(e) => {
                 warn!("failed to open content index at {:?}: {}", content_path, e);
@@ -67,246 +216,334 @@ impl UnifiedSearchHandler {
             }
         };
 
+        Self::from_indexes(meta, content, &cfg)
+    }
+
+    /// Serve searches from indexes that are already open, such as in-memory ones in tests.
+    /// `cfg` supplies the `[search]` and `[metrics]` settings [`try_new`](Self::try_new) takes
+    /// from the live config.
+    pub fn from_indexes(
+        meta: MetaIndex,
+        content: Option<ContentIndex>,
+        cfg: &AppConfig,
+    ) -> Result<Self> {
+        let meta_reader = open_reader(&meta)?;
+        let content = match content {
+            Some(idx) => {
+                let reader = content_index::open_reader(&idx)?;
+                Some((idx, reader))
+            }
+            None => None,
+        };
+
         Ok(Self {
             meta,
             meta_reader,
+            delta: None,
+            delta_merges: Mutex::new(0),
             content,
+            cache: QueryCache::default(),
+            snippets: SnippetCache::new(cfg.search.snippet_cache_size),
+            excluded_paths: cfg.search.excluded_paths.clone(),
+            default_fields: configured_default_fields(&cfg.search),
+            max_limit: cfg.search.max_limit.max(1),
+            slow_query: (cfg.metrics.slow_query_ms > 0)
+                .then(|| Duration::from_millis(cfg.metrics.slow_query_ms)),
+            query_log: Mutex::new(query_log_path(cfg).map(new_query_log)),
         })
     }
 
-    fn build_meta_query(&self, expr: &QueryExpr) -> Result<Box<dyn Query>> {
-        self.build_query(expr, &self.meta.fields, &self.meta.index)
+    /// Union name searches with `delta`, whose changes are searchable as soon as it commits
+    /// them rather than after they are merged into the main index.
+    pub fn with_delta(mut self, delta: Arc<DeltaIndex>) -> Self {
+        self.delta = Some(delta);
+        self
     }
 
-    fn build_query(
-        &self,
-        expr: &QueryExpr,
-        fields: &MetaFields,
-        index: &tantivy::Index,
-    ) -> Result<Box<dyn Query>> {
-        Ok(match expr {
-            QueryExpr::Term(t) => self.term_query(t, fields, index)?,
-            QueryExpr::Range(_) => Box::new(BooleanQuery::new(vec![])),
-            QueryExpr::Not(inner) => Box::new(BooleanQuery::new(vec![(
-                Occur::MustNot,
-                self.build_query(inner, fields, index)?,
-            )])),
-            QueryExpr::And(items) => Box::new(BooleanQuery::new(
-                items
-                    .iter()
-                    .map(|q| Ok((Occur::Must, self.build_query(q, fields, index)?)))
-                    .collect::<Result<Vec<_>>>()?,
-            )),
-            QueryExpr::Or(items) => Box::new(BooleanQuery::new(
-                items
-                    .iter()
-                    .map(|q| Ok((Occur::Should, self.build_query(q, fields, index)?)))
-                    .collect::<Result<Vec<_>>>()?,
-            )),
-        })
+    /// Reader generations a cached response must have been computed against. Both counters
+    /// in the meta component only grow, so a delta commit changes it as a main commit does.
+    fn generation(&self) -> Generation {
+        let meta = self.meta_reader.searcher().generation().generation_id()
+            + self.delta.as_ref().map_or(0, |delta| delta.version());
+        let content = self.content.as_ref().map_or(0, |(_, reader)| {
+            reader.searcher().generation().generation_id()
+        });
+        (meta, content)
     }
 
-    fn term_query(
-        &self,
-        term: &TermExpr,
-        fields: &MetaFields,
-        index: &tantivy::Index,
-    ) -> Result<Box<dyn Query>> {
-        let value = term.value.trim();
-        if value.is_empty() {
-            return Ok(Box::new(BooleanQuery::new(vec![])));
-        }
-
-        let target_fields: Vec<FieldKind> = match term.field {
-            Some(f) => vec![f],
-            None => vec![FieldKind::Name, FieldKind::Path],
-        };
-
-        let mut clauses = Vec::new();
-        for field in target_fields {
-            match field {
-                FieldKind::Ext => {
-                    let t = Term::from_field_text(fields.ext, value);
-                    clauses.push((
-                        Occur::Should,
-                        Box::new(TermQuery::new(t, IndexRecordOption::WithFreqs)) as Box<dyn Query>,
-                    ));
-                }
-                FieldKind::Name | FieldKind::Path => match term.modifier {
-                    TermModifier::Prefix => {
-                        let pf = if matches!(field, FieldKind::Name) {
-                            fields.name
-                        } else {
-                            fields.path
-                        };
-                        // Fallback for PrefixQuery removal
-                        let t = Term::from_field_text(pf, value);
-                        clauses.push((
-                            Occur::Should,
-                            Box::new(TermQuery::new(t, IndexRecordOption::WithFreqs))
-                                as Box<dyn Query>,
-                        ));
-                    }
-                    _ => {
-                        let mut parser = QueryParser::for_index(
-                            index,
-                            vec![if matches!(field, FieldKind::Name) {
-                                fields.name
-                            } else {
-                                fields.path
-                            }],
-                        );
-                        parser.set_conjunction_by_default();
-                        if let Ok(q) = parser.parse_query(value) {
-                            clauses.push((Occur::Should, q));
-                        }
-                    }
-                },
-                _ => {}
-            }
-        }
-
-        Ok(Box::new(BooleanQuery::new(clauses)))
-    }
-
-    fn build_content_query(&self, expr: &QueryExpr) -> Result<Box<dyn Query>> {
-        if let Some((idx, _)) = &self.content {
-            // For content query, default fields might include content + name/path
-            // We can map QueryExpr fields to ContentFields
-            // This requires a specialized build_query or mapping logic.
-            // For simplicity, we reuse build_query but we need to map MetaFields-like structure or make build_query generic.
-            // Since MetaFields and ContentFields are different structs, we can't pass them easily unless we have a trait or adapter.
-            // But term_query matches on FieldKind. We can just reimplement term_query for ContentFields.
-
-            Ok(match expr {
-                QueryExpr::Term(t) => self.term_query_content(t, &idx.fields, &idx.index)?,
-                QueryExpr::Range(_) => Box::new(BooleanQuery::new(vec![])),
-                QueryExpr::Not(inner) => Box::new(BooleanQuery::new(vec![(
-                    Occur::MustNot,
-                    self.build_content_query(inner)?,
-                )])),
-                QueryExpr::And(items) => Box::new(BooleanQuery::new(
-                    items
-                        .iter()
-                        .map(|q| Ok((Occur::Must, self.build_content_query(q)?)))
-                        .collect::<Result<Vec<_>>>()?,
-                )),
-                QueryExpr::Or(items) => Box::new(BooleanQuery::new(
-                    items
-                        .iter()
-                        .map(|q| Ok((Occur::Should, self.build_content_query(q)?)))
-                        .collect::<Result<Vec<_>>>()?,
-                )),
-            })
-        } else {
-            Err(anyhow::anyhow!("content index not available"))
+    /// False when the content index failed to open and only metadata is searchable.
+    pub fn has_content_index(&self) -> bool {
+        self.content.is_some()
+    }
+
+    /// Why content searches cannot run: the index failed to open, or holds no documents yet
+    /// (content indexing disabled or still building). `None` when it is searchable.
+    fn content_unavailable(&self) -> Option<&'static str> {
+        match &self.content {
+            None => Some("content index unavailable"),
+            Some((_, reader)) if reader.searcher().num_docs() == 0 => Some("content index empty"),
+            Some(_) => None,
+        }
+    }
+
+    /// [`QueryPlanner::choose_mode`], falling back to the metadata index when there is no
+    /// content index to consult.
+    fn choose_mode(&self, query: &QueryExpr) -> ModePlan {
+        let mut plan = QueryPlanner::choose_mode(query);
+        if plan.mode != SearchMode::NameOnly
+            && let Some(reason) = self.content_unavailable()
+        {
+            plan.mode = SearchMode::NameOnly;
+            plan.reasons.push(format!("{reason}; metadata only"));
         }
+        plan
+    }
+
+    fn build_meta_query(&self, req: &SearchRequest) -> Result<Box<dyn Query>> {
+        let fields = QueryFields::from(&self.meta.fields).with_default_fields(&self.default_fields);
+        let query = to_tantivy(&req.query, &fields, &self.meta.index, req.case_sensitive)?;
+        Ok(self.exclude_system(query, req, &fields, &self.meta.index))
+    }
+
+    fn build_content_query(&self, req: &SearchRequest) -> Result<Box<dyn Query>> {
+        let (idx, _) = self
+            .content
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("content index not available"))?;
+        let fields = QueryFields::from(&idx.fields).with_default_fields(&self.default_fields);
+        let query = to_tantivy(&req.query, &fields, &idx.index, false)?;
+        Ok(self.exclude_system(query, req, &fields, &idx.index))
     }
 
-    fn term_query_content(
+    fn exclude_system(
         &self,
-        term: &TermExpr,
-        fields: &content_index::ContentFields,
+        query: Box<dyn Query>,
+        req: &SearchRequest,
+        fields: &QueryFields,
         index: &tantivy::Index,
-    ) -> Result<Box<dyn Query>> {
-        let value = term.value.trim();
-        if value.is_empty() {
-            return Ok(Box::new(BooleanQuery::new(vec![])));
-        }
-
-        let target_fields: Vec<FieldKind> = match term.field {
-            Some(f) => vec![f],
-            None => vec![FieldKind::Name, FieldKind::Content], // Default to Name + Content
-        };
-
-        let mut clauses = Vec::new();
-        for field in target_fields {
-            // Map FieldKind to tantivy::schema::Field in ContentFields
-            let t_field = match field {
-                FieldKind::Name => Some(fields.name),
-                FieldKind::Path => Some(fields.path),
-                FieldKind::Ext => Some(fields.ext),
-                FieldKind::Content => Some(fields.content),
-                // Other fields like size/modified handled in ranges or ignored for text search
-                _ => None,
-            };
+    ) -> Box<dyn Query> {
+        if req.include_system {
+            return query;
+        }
+        exclude_paths(query, fields, index, &self.excluded_paths)
+    }
 
-            if let Some(tf) = t_field {
-                match term.modifier {
-                    TermModifier::Prefix => {
-                        let t = Term::from_field_text(tf, value);
-                        clauses.push((
-                            Occur::Should,
-                            Box::new(TermQuery::new(t, IndexRecordOption::WithFreqs))
-                                as Box<dyn Query>,
-                        ));
-                    }
-                    _ => {
-                        let mut parser = QueryParser::for_index(index, vec![tf]);
-                        parser.set_conjunction_by_default();
-                        if let Ok(q) = parser.parse_query(value) {
-                            clauses.push((Occur::Should, q));
-                        }
-                    }
+    /// The main index's searcher plus, while the delta tier holds changes, a snapshot of it.
+    /// The snapshot is taken first and the main reader reloaded whenever a merge finished since
+    /// the last search, so a change just merged out of the delta is never missing from both.
+    fn meta_searchers(&self) -> (Searcher, Option<DeltaSnapshot>) {
+        let Some(delta) = &self.delta else {
+            return (self.meta_reader.searcher(), None);
+        };
+        let snapshot = delta.snapshot();
+        {
+            let mut seen = self.delta_merges.lock();
+            if *seen != snapshot.merges {
+                if let Err(err) = self.meta_reader.reload() {
+                    warn!(error = %err, "failed to reload the meta index after a delta merge");
                 }
+                *seen = snapshot.merges;
             }
         }
-        Ok(Box::new(BooleanQuery::new(clauses)))
+        let snapshot = (!snapshot.is_empty()).then_some(snapshot);
+        (self.meta_reader.searcher(), snapshot)
+    }
+
+    /// [`meta_searchers`](Self::meta_searchers) as the tiers to search in turn, each with the
+    /// keys to leave out: the main index minus what the delta shadows, then the delta.
+    fn meta_tiers(&self) -> Vec<(Searcher, Option<Arc<HashSet<DocKey>>>)> {
+        match self.meta_searchers() {
+            (main, Some(delta)) => vec![
+                (main, Some(Arc::clone(&delta.shadowed))),
+                (delta.searcher, None),
+            ],
+            (main, None) => vec![(main, None)],
+        }
+    }
+
+    /// Collect one page of `query` from a meta-index tier, by `req.sort` when the index can
+    /// sort by it and by score otherwise.
+    #[allow(clippy::too_many_arguments)]
+    fn collect_meta(
+        &self,
+        req: &SearchRequest,
+        searcher: &Searcher,
+        query: &dyn Query,
+        limit: usize,
+        offset: usize,
+        deadline: Option<Instant>,
+        hidden: Option<Arc<HashSet<DocKey>>>,
+    ) -> Result<Page> {
+        match sort_column(req, &self.meta.index) {
+            Some((field, value, descending)) => collect_sorted_page(
+                searcher, query, field, value, descending, limit, offset, deadline, hidden,
+            ),
+            None => collect_page(
+                searcher,
+                query,
+                limit,
+                offset,
+                request_cursor(req),
+                deadline,
+                hidden,
+            ),
+        }
     }
 
-    fn search_meta(&self, req: &SearchRequest) -> SearchResponse {
+    fn search_meta(
+        &self,
+        req: &SearchRequest,
+        deadline: Option<Instant>,
+    ) -> Result<SearchResponse> {
         let start = Instant::now();
         let limit = req.limit.max(1) as usize;
         let offset = req.offset as usize;
 
-        let searcher = self.meta_reader.searcher();
-        let query = match self.build_meta_query(&req.query) {
-            Ok(q) => q,
-            Err(err) => {
-                warn!(error = %err, "failed to build meta query");
-                return StubSearchHandler.search(req.clone());
-            }
-        };
+        let (searcher, delta) = self.meta_searchers();
+        let query = self
+            .build_meta_query(req)
+            .context("failed to build meta query")?;
 
         tracing::info!("executing meta query: {:?}", query);
 
-        let top_k = limit.saturating_add(offset);
-        let (hits, total) = match searcher.search(&query, &(TopDocs::with_limit(top_k), Count)) {
-            Ok(r) => r,
+        if let Some(delta) = delta {
+            return Ok(self.search_meta_tiers(
+                req,
+                query.as_ref(),
+                &searcher,
+                &delta,
+                deadline,
+                start,
+            ));
+        }
+
+        let page = match self.collect_meta(
+            req,
+            &searcher,
+            query.as_ref(),
+            limit,
+            offset,
+            deadline,
+            None,
+        ) {
+            Ok(p) => p,
             Err(err) => {
                 warn!(error = %err, "meta search execution failed");
-                return StubSearchHandler.search(req.clone());
+                return Ok(empty_response(req.id));
             }
         };
 
         tracing::info!(
             "meta search found {} total hits (returned {})",
-            total,
-            hits.len()
+            page.total,
+            page.hits.len()
         );
 
-        let out = hits
+        let out = page
+            .hits
             .into_iter()
-            .skip(offset)
-            .filter_map(|(score, addr)| {
+            .filter_map(|(score, _, addr)| {
                 let retrieved = searcher.doc::<TantivyDocument>(addr).ok()?;
-                to_hit(&retrieved, &self.meta.fields, score)
+                to_hit(&retrieved, &self.meta.fields, score, req.fields.as_deref())
             })
             .collect();
 
-        SearchResponse {
-            id: req.id,
-            hits: out,
-            total: total as u64,
-            truncated: false, // MVP
-            took_ms: start.elapsed().as_millis().min(u32::MAX as u128) as u32,
-            served_by: None,
+        Ok(meta_response(
+            req,
+            start,
+            out,
+            page.total,
+            page.next_cursor,
+            page.timed_out,
+        ))
+    }
+
+    /// [`search_meta`](Self::search_meta) while the delta tier holds changes. Both tiers are
+    /// collected down to `offset + limit`, with main-index docs the delta shadows hidden, and
+    /// the union is ordered like a single-index page before `offset` and `limit` apply. Scores
+    /// come from each tier's own term statistics.
+    fn search_meta_tiers(
+        &self,
+        req: &SearchRequest,
+        query: &dyn Query,
+        main: &Searcher,
+        delta: &DeltaSnapshot,
+        deadline: Option<Instant>,
+        start: Instant,
+    ) -> SearchResponse {
+        let limit = req.limit.max(1) as usize;
+        let sort = sort_column(req, &self.meta.index);
+        // A cursor already excludes everything served, as `offset` would.
+        let offset = match (sort, request_cursor(req)) {
+            (None, Some(_)) => 0,
+            _ => req.offset as usize,
+        };
+        let depth = limit.saturating_add(offset);
+        let hidden = Some(Arc::clone(&delta.shadowed));
+        let pages = self
+            .collect_meta(req, main, query, depth, 0, deadline, hidden)
+            .and_then(|main_page| {
+                let delta_page =
+                    self.collect_meta(req, &delta.searcher, query, depth, 0, deadline, None)?;
+                Ok((main_page, delta_page))
+            });
+        let (main_page, delta_page) = match pages {
+            Ok(pages) => pages,
+            Err(err) => {
+                warn!(error = %err, "meta search execution failed");
+                return empty_response(req.id);
+            }
+        };
+        let total = main_page.total + delta_page.total;
+        let timed_out = main_page.timed_out || delta_page.timed_out;
+        let mut more = main_page.next_cursor.is_some() || delta_page.next_cursor.is_some();
+
+        let mut ranked: Vec<(Score, DocKey, Option<i128>, &Searcher, DocAddress)> = Vec::new();
+        for (searcher, page) in [(main, main_page), (&delta.searcher, delta_page)] {
+            for (score, key, addr) in page.hits {
+                let value =
+                    sort.and_then(|(field, kind, _)| sort_value(searcher, addr, field, kind));
+                ranked.push((score, key, value, searcher, addr));
+            }
+        }
+        match sort {
+            Some((_, _, descending)) => ranked.sort_by(|a, b| {
+                let by_value = if descending {
+                    b.2.cmp(&a.2)
+                } else {
+                    a.2.cmp(&b.2)
+                };
+                by_value.then_with(|| a.1.0.cmp(&b.1.0))
+            }),
+            None => ranked.sort_by(|a, b| compare_ranked(a.0, a.1, b.0, b.1)),
         }
+        more |= ranked.len() > depth;
+
+        let hits: Vec<SearchHit> = ranked
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .filter_map(|(score, _, _, searcher, addr)| {
+                let retrieved = searcher.doc::<TantivyDocument>(addr).ok()?;
+                to_hit(&retrieved, &self.meta.fields, score, req.fields.as_deref())
+            })
+            .collect();
+        let next_cursor = match hits.last() {
+            Some(last) if more && sort.is_none() && !timed_out => {
+                Some(PageCursor::new(last.score, last.key))
+            }
+            _ => None,
+        };
+        meta_response(req, start, hits, total, next_cursor, timed_out)
     }
 
-    fn search_content(&self, req: &SearchRequest) -> SearchResponse {
+    fn search_content(
+        &self,
+        req: &SearchRequest,
+        deadline: Option<Instant>,
+    ) -> Result<SearchResponse> {
         let Some((content_idx, reader)) = &self.content else {
-            return StubSearchHandler.search(req.clone());
+            return Ok(empty_response(req.id));
         };
 
         let start = Instant::now();
@@ -314,183 +551,945 @@ impl UnifiedSearchHandler {
         let offset = req.offset as usize;
 
         let searcher = reader.searcher();
-        let query = match self.build_content_query(&req.query) {
-            Ok(q) => q,
-            Err(err) => {
-                warn!(error = %err, "failed to build content query");
-                return StubSearchHandler.search(req.clone());
-            }
-        };
+        let query = self
+            .build_content_query(req)
+            .context("failed to build content query")?;
 
-        let top_k = limit.saturating_add(offset);
-        let (hits, total) = match searcher.search(&query, &(TopDocs::with_limit(top_k), Count)) {
-            Ok(r) => r,
+        let collected = match sort_column(req, &content_idx.index) {
+            Some((field, value, descending)) => collect_sorted_page(
+                &searcher,
+                query.as_ref(),
+                field,
+                value,
+                descending,
+                limit,
+                offset,
+                deadline,
+                None,
+            ),
+            None => collect_page(
+                &searcher,
+                query.as_ref(),
+                limit,
+                offset,
+                request_cursor(req),
+                deadline,
+                None,
+            ),
+        };
+        let page = match collected {
+            Ok(p) => p,
             Err(err) => {
                 warn!(error = %err, "content search execution failed");
-                return StubSearchHandler.search(req.clone());
+                return Ok(empty_response(req.id));
             }
         };
 
-        let out = hits
+        let wants_snippet = wants_field(req.fields.as_deref(), FieldKind::Content);
+        let terms = terms_hash(query.as_ref());
+        let generation = searcher.generation().generation_id();
+        // Built on the first cache miss only; it scores every query term against the index.
+        let mut generator = None;
+        let out = page
+            .hits
             .into_iter()
-            .skip(offset)
-            .filter_map(|(score, addr)| {
+            .filter_map(|(score, _, addr)| {
                 let retrieved = searcher.doc::<TantivyDocument>(addr).ok()?;
-                // We need to_hit equivalent for content fields
-                to_hit_content(&retrieved, &content_idx.fields, score)
+                let mut hit = to_hit_content(
+                    &retrieved,
+                    &content_idx.fields,
+                    score,
+                    req.fields.as_deref(),
+                )?;
+                if wants_snippet {
+                    hit.snippet =
+                        self.snippets
+                            .get_or_insert_with(hit.key, terms, generation, || {
+                                if generator.is_none() {
+                                    generator = SnippetGenerator::create(
+                                        &searcher,
+                                        query.as_ref(),
+                                        content_idx.fields.content,
+                                    )
+                                    .map_err(|err| warn!(error = %err, "snippet generator failed"))
+                                    .ok();
+                                }
+                                let snippet = generator.as_ref()?.snippet_from_doc(&retrieved);
+                                let text = snippet.fragment().trim();
+                                (!text.is_empty()).then(|| text.to_string())
+                            });
+                }
+                Some(hit)
             })
             .collect();
 
-        SearchResponse {
+        Ok(SearchResponse {
             id: req.id,
             hits: out,
-            total: total as u64,
-            truncated: false,
+            total: page.total as u64,
+            truncated: page.timed_out,
             took_ms: start.elapsed().as_millis().min(u32::MAX as u128) as u32,
             served_by: None,
-        }
+            next_cursor: page.next_cursor.map(|c| c.encode()),
+            explanation: None,
+            timed_out: page.timed_out,
+            degraded: None,
+            suggestions: Vec::new(),
+            groups: Vec::new(),
+        })
     }
 
-    fn search_hybrid(&self, req: &SearchRequest) -> SearchResponse {
-        // Parallel execution? For MVP, sequential.
-        // 1. Meta search
-        // 2. Content search
-        // 3. Merge by DocKey
-
+    /// Merge meta and content hits into one page. A doc's merged score is the better of its
+    /// two, so the top `n` of each side hold the merged top `n` with exact scores: each side is
+    /// collected from the top down to `offset + limit + 1`. Resuming from a cursor widens that
+    /// depth until a full page lies past it, so deep cursor pages cost what offset pages do.
+    fn search_hybrid(
+        &self,
+        req: &SearchRequest,
+        deadline: Option<Instant>,
+    ) -> Result<SearchResponse> {
         let start = Instant::now();
         let limit = req.limit.max(1) as usize;
+        // Field-sorted pages have no cursor.
+        let cursor = request_cursor(req).filter(|_| req.sort.is_none());
+        let offset = if cursor.is_some() {
+            0
+        } else {
+            req.offset as usize
+        };
 
-        // Fetch more to allow merging
-        let fetch_limit = limit * 2;
-
-        // Create sub-requests
-        let mut meta_req = req.clone();
-        meta_req.limit = fetch_limit as u32;
-        meta_req.offset = 0; // We handle paging after merge? Or simple approach: no deep paging in hybrid for now.
+        let mut depth = offset.saturating_add(limit).saturating_add(1);
+        let (merged, sub_total, timed_out) = loop {
+            let mut sub_req = req.clone();
+            sub_req.limit = u32::try_from(depth).unwrap_or(u32::MAX);
+            sub_req.offset = 0;
+            sub_req.cursor = None;
 
-        let meta_resp = self.search_meta(&meta_req);
+            let meta_resp = self.search_meta(&sub_req, deadline)?;
+            let mut sub_total = meta_resp.total;
+            let mut timed_out = meta_resp.timed_out;
+            let mut exhausted = meta_resp.hits.len() < depth;
+            let mut content_hits = Vec::new();
+            if self.content.is_some() {
+                let content_resp = self.search_content(&sub_req, deadline)?;
+                sub_total = sub_total.max(content_resp.total);
+                timed_out |= content_resp.timed_out;
+                exhausted &= content_resp.hits.len() < depth;
+                content_hits = content_resp.hits;
+            }
 
-        let mut hits_map: std::collections::HashMap<core_types::DocKey, SearchHit> =
-            std::collections::HashMap::new();
+            let mut merged = merge_hits(meta_resp.hits, content_hits);
+            if let Some(sort) = req.sort {
+                sort_hits(&mut merged, sort);
+            }
+            // Past `depth` a doc's other score may not have been collected, unless both sides
+            // were collected in full.
+            if !exhausted {
+                merged.truncate(depth);
+            }
+            let past_cursor = cursor.map_or(merged.len(), |c| {
+                merged
+                    .iter()
+                    .filter(|h| c.is_before(h.score, h.key))
+                    .count()
+            });
+            if past_cursor > limit || exhausted || timed_out || depth >= u32::MAX as usize {
+                break (merged, sub_total, timed_out);
+            }
+            depth = depth.saturating_mul(2);
+        };
 
-        for hit in meta_resp.hits {
-            hits_map.insert(hit.key, hit);
-        }
+        let total = (merged.len() as u64).max(sub_total);
+        let mut hits: Vec<SearchHit> = merged
+            .into_iter()
+            .filter(|h| cursor.is_none_or(|c| c.is_before(h.score, h.key)))
+            .skip(offset)
+            .take(limit + 1)
+            .collect();
+        let next_cursor = if hits.len() > limit && !timed_out && req.sort.is_none() {
+            hits.truncate(limit);
+            hits.last()
+                .map(|h| PageCursor::new(h.score, h.key).encode())
+        } else {
+            hits.truncate(limit);
+            None
+        };
 
-        if self.content.is_some() {
-            let mut content_req = req.clone();
-            content_req.limit = fetch_limit as u32;
-            content_req.offset = 0;
-            let content_resp = self.search_content(&content_req);
+        Ok(SearchResponse {
+            id: req.id,
+            hits,
+            total, // Approx
+            truncated: timed_out,
+            took_ms: start.elapsed().as_millis().min(u32::MAX as u128) as u32,
+            served_by: None,
+            next_cursor,
+            explanation: None,
+            timed_out,
+            degraded: None,
+            suggestions: Vec::new(),
+            groups: Vec::new(),
+        })
+    }
 
-            for hit in content_resp.hits {
-                hits_map
-                    .entry(hit.key)
-                    .and_modify(|e| {
-                        e.score = e.score.max(hit.score); // Max score strategy? Or sum? Max is safer for boolean queries.
-                        if e.snippet.is_none() {
-                            e.snippet = hit.snippet.clone();
-                        }
-                    })
-                    .or_insert(hit);
+    /// Answer a `count_only` request: run `Count` over each consulted index and return no
+    /// hits, so nothing is ranked and no stored fields are read. Like hybrid `total`, the
+    /// count across both indexes is the larger of the two.
+    fn count(&self, req: &SearchRequest, deadline: Option<Instant>) -> Result<SearchResponse> {
+        let start = Instant::now();
+        let mut total = 0;
+        let mut timed_out = false;
+        let mut record = |label: &str, counted: Result<(usize, bool)>| match counted {
+            Ok((n, expired)) => {
+                total = total.max(n as u64);
+                timed_out |= expired;
             }
-        }
-
-        let mut merged: Vec<SearchHit> = hits_map.into_values().collect();
-        merged.sort_by(|a, b| {
-            b.score
-                .partial_cmp(&a.score)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
+            Err(err) => warn!(error = %err, "{label} count failed"),
+        };
 
-        let offset = req.offset as usize;
-        let total = merged.len();
-        let hits = merged.into_iter().skip(offset).take(limit).collect();
+        if !matches!(req.mode, SearchMode::Content) {
+            let (searcher, delta) = self.meta_searchers();
+            let q = self
+                .build_meta_query(req)
+                .context("failed to build meta query")?;
+            let hidden = delta.as_ref().map(|d| Arc::clone(&d.shadowed));
+            let counted = count_matches(&searcher, q.as_ref(), deadline, hidden).and_then(
+                |(mut n, mut expired)| {
+                    if let Some(delta) = &delta {
+                        let (m, delta_expired) =
+                            count_matches(&delta.searcher, q.as_ref(), deadline, None)?;
+                        n += m;
+                        expired |= delta_expired;
+                    }
+                    Ok((n, expired))
+                },
+            );
+            record("meta", counted);
+        }
+        if let (false, Some((_, reader))) =
+            (matches!(req.mode, SearchMode::NameOnly), &self.content)
+        {
+            let searcher = reader.searcher();
+            let q = self
+                .build_content_query(req)
+                .context("failed to build content query")?;
+            record(
+                "content",
+                count_matches(&searcher, q.as_ref(), deadline, None),
+            );
+        }
 
-        SearchResponse {
+        Ok(SearchResponse {
             id: req.id,
-            hits,
-            total: total as u64, // Approx
-            truncated: false,
+            hits: Vec::new(),
+            total,
+            truncated: timed_out,
             took_ms: start.elapsed().as_millis().min(u32::MAX as u128) as u32,
             served_by: None,
-        }
+            next_cursor: None,
+            explanation: None,
+            timed_out,
+            degraded: None,
+            suggestions: Vec::new(),
+            groups: Vec::new(),
+        })
     }
-}
 
-impl SearchHandler for UnifiedSearchHandler {
-    fn search(&self, req: SearchRequest) -> SearchResponse {
-        match req.mode {
-            SearchMode::NameOnly => self.search_meta(&req),
-            SearchMode::Content => self.search_content(&req),
-            SearchMode::Hybrid | SearchMode::Auto => self.search_hybrid(&req),
+    /// Bucket the name-index matches of `req` by `field` for `SearchResponse::groups`. Only the
+    /// best [`GROUP_SCAN_LIMIT`] matches are bucketed, so counts are exact below that.
+    fn group_meta(
+        &self,
+        req: &SearchRequest,
+        field: FieldKind,
+        deadline: Option<Instant>,
+    ) -> Vec<ResultGroup> {
+        if !matches!(field, FieldKind::Path | FieldKind::Ext | FieldKind::Kind)
+            || deadline.is_some_and(|d| Instant::now() >= d)
+        {
+            return Vec::new();
+        }
+        let query = match self.build_meta_query(req) {
+            Ok(query) => query,
+            Err(err) => {
+                warn!(error = %err, "grouping search failed");
+                return Vec::new();
+            }
+        };
+        let mut top: Vec<(Score, TantivyDocument)> = Vec::new();
+        for (searcher, hidden) in self.meta_tiers() {
+            let collector = HideKeys::new(TopDocs::with_limit(GROUP_SCAN_LIMIT), hidden);
+            match searcher.search(query.as_ref(), &collector) {
+                Ok(found) => top.extend(found.into_iter().filter_map(|(score, addr)| {
+                    searcher
+                        .doc::<TantivyDocument>(addr)
+                        .ok()
+                        .map(|d| (score, d))
+                })),
+                Err(err) => {
+                    warn!(error = %err, "grouping search failed");
+                    return Vec::new();
+                }
+            }
+        }
+        top.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        top.truncate(GROUP_SCAN_LIMIT);
+
+        let mut groups: Vec<ResultGroup> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        for (score, doc) in top {
+            let Some(key) =
+                to_hit(&doc, &self.meta.fields, score, None).and_then(|hit| group_key(&hit, field))
+            else {
+                continue;
+            };
+            let slot = *index.entry(key.clone()).or_insert_with(|| {
+                groups.push(ResultGroup {
+                    key,
+                    count: 0,
+                    top_hits: Vec::new(),
+                });
+                groups.len() - 1
+            });
+            let group = &mut groups[slot];
+            group.count += 1;
+            if group.top_hits.len() < HITS_PER_GROUP {
+                group.top_hits.extend(to_hit(
+                    &doc,
+                    &self.meta.fields,
+                    score,
+                    req.fields.as_deref(),
+                ));
+            }
         }
+        // Stable: equal-sized groups keep the order of their best hit.
+        groups.sort_by_key(|g| std::cmp::Reverse(g.count));
+        groups.truncate(MAX_GROUPS);
+        groups
     }
-}
 
-// Helper to map content doc to SearchHit
-fn to_hit_content<D: Document>(
-    doc: &D,
-    fields: &content_index::ContentFields,
-    score: Score,
-) -> Option<SearchHit> {
-    let mut key = None;
-    let mut name = None;
-    let mut path = None;
-    let mut ext = None;
-    let mut size = None;
-    let mut modified = None;
-    let snippet = None; // TODO: snippet generation
+    /// Describe how `req` (already planned) runs: mode, the Tantivy query per consulted index,
+    /// and the score breakdown of the top content hit.
+    fn explain(&self, req: &SearchRequest) -> String {
+        let consult_meta = !matches!(req.mode, SearchMode::Content);
+        let consult_content = !matches!(req.mode, SearchMode::NameOnly) && self.content.is_some();
+        let indexes: Vec<&str> = [(consult_meta, "meta"), (consult_content, "content")]
+            .into_iter()
+            .filter_map(|(on, name)| on.then_some(name))
+            .collect();
 
-    for (field, value) in doc.iter_fields_and_values() {
-        match field {
-            f if f == fields.doc_key => {
-                if let Some(v) = value.as_u64() {
-                    key = Some(core_types::DocKey(v));
+        let mut lines = vec![
+            format!("mode: {:?}", req.mode),
+            format!("planned: {:?}", req.query),
+            format!("case_sensitive: {}", req.case_sensitive),
+            format!("include_system: {}", req.include_system),
+            format!(
+                "indexes: {}",
+                if indexes.is_empty() {
+                    "none (content index unavailable)".to_string()
+                } else {
+                    indexes.join(", ")
                 }
+            ),
+        ];
+        if consult_meta {
+            match self.build_meta_query(req) {
+                Ok(q) => lines.push(format!("meta query: {q:?}")),
+                Err(err) => lines.push(format!("meta query: failed to build: {err}")),
+            }
+        }
+        if let (true, Some((_, reader))) = (consult_content, &self.content) {
+            match self.build_content_query(req) {
+                Ok(q) => {
+                    lines.push(format!("content query: {q:?}"));
+                    let searcher = reader.searcher();
+                    let top = searcher
+                        .search(q.as_ref(), &TopDocs::with_limit(1))
+                        .ok()
+                        .and_then(|hits| hits.into_iter().next());
+                    match top.map(|(score, addr)| (score, q.explain(&searcher, addr))) {
+                        Some((score, Ok(expl))) => lines.push(format!(
+                            "top content hit (score {score:.4}):\n{}",
+                            expl.to_pretty_json()
+                        )),
+                        Some((_, Err(err))) => lines.push(format!("top content hit: {err}")),
+                        None => lines.push("top content hit: none".to_string()),
+                    }
+                }
+                Err(err) => lines.push(format!("content query: failed to build: {err}")),
             }
-            f if f == fields.name => name = value.as_str().map(|s| s.to_string()),
-            f if f == fields.path => path = value.as_str().map(|s| s.to_string()),
-            f if f == fields.ext => ext = value.as_str().map(|s| s.to_string()),
-            f if f == fields.size => size = value.as_u64(),
-            f if f == fields.modified => modified = value.as_i64(),
-            // TODO: snippet from content field
-            _ => {}
         }
+        lines.join("\n")
     }
-
-    key.map(|doc_key| SearchHit {
-        key: doc_key,
-        score,
-        name,
-        path,
-        ext,
-        size,
-        modified,
-        snippet,
-    })
 }
 
-static HANDLER: OnceLock<Box<dyn SearchHandler>> = OnceLock::new();
-
-pub fn set_search_handler(handler: Box<dyn SearchHandler>) {
-    tracing::info!("Global search handler installed.");
-    let _ = HANDLER.set(handler);
-}
+impl SearchHandler for UnifiedSearchHandler {
+    fn try_search(&self, mut req: SearchRequest) -> Result<SearchResponse> {
+        let start = Instant::now();
+        let key = CacheKey::for_request(&req);
+        let generation = self.generation();
+        if let Some(mut resp) = self.cache.get(&key, generation) {
+            resp.id = req.id;
+            resp.took_ms = start.elapsed().as_millis() as u32;
+            return Ok(resp);
+        }
+        // Past the deadline, collectors drop remaining matches and the response is flagged
+        // partial.
+        let deadline = req.timeout.map(|t| start + t);
+        let clamped = req.limit > self.max_limit;
+        if clamped {
+            debug!(
+                requested = req.limit,
+                max = self.max_limit,
+                "clamping search limit"
+            );
+        }
+        req.limit = req.limit.clamp(1, self.max_limit);
+        // Keep `offset + limit` representable; pages that deep are empty anyway.
+        req.offset = req.offset.min(u32::MAX - req.limit);
+        req.query = QueryPlanner::plan_with_case(
+            std::mem::take(&mut req.query),
+            req.mode,
+            req.case_sensitive,
+        );
+        if req.mode == SearchMode::Auto {
+            req.mode = QueryPlanner::choose_mode(&req.query).mode;
+        }
+        // Without content to search, answer from file names and say so instead of returning
+        // nothing.
+        let degraded = match (req.mode, self.content_unavailable()) {
+            (SearchMode::Content | SearchMode::Hybrid, Some(reason)) => {
+                req.mode = SearchMode::NameOnly;
+                Some(format!("{reason}; searched file names only"))
+            }
+            _ => None,
+        };
+        let mut resp = match req.mode {
+            _ if req.count_only => self.count(&req, deadline),
+            SearchMode::NameOnly => self.search_meta(&req, deadline),
+            SearchMode::Content => self.search_content(&req, deadline),
+            SearchMode::Hybrid | SearchMode::Auto => self.search_hybrid(&req, deadline),
+        }?;
+        if req.explain {
+            resp.explanation = Some(self.explain(&req));
+        }
+        resp.served_by = Some(served_by());
+        resp.degraded = degraded;
+        resp.truncated |= clamped;
+        if req.normalize_scores {
+            normalize_scores(&mut resp.hits);
+        }
+        if let Some(field) = req.group_by
+            && req.mode == SearchMode::NameOnly
+            && !req.count_only
+        {
+            resp.groups = self.group_meta(&req, field, deadline);
+        }
+        if req.suggest && req.mode == SearchMode::NameOnly && resp.total == 0 {
+            let tiers = self.meta_tiers();
+            resp.suggestions = name_suggestions(
+                &tiers.iter().map(|(s, _)| s).collect::<Vec<_>>(),
+                &self.meta.index,
+                self.meta.fields.name,
+                &req.query,
+            );
+        }
+        let elapsed = start.elapsed();
+        if self.slow_query.is_some_and(|limit| elapsed > limit) {
+            warn!(
+                query = ?req.query,
+                mode = ?req.mode,
+                hits = resp.hits.len(),
+                total = resp.total,
+                elapsed_ms = elapsed.as_millis() as u64,
+                "slow query"
+            );
+            crate::metrics::record_slow_query_global();
+        }
+        let query_log = self.query_log.lock().clone();
+        if let Some(log) = query_log {
+            log.append(&req.query, req.mode, resp.total, elapsed);
+        }
+        // Partial results depend on the timeout, not just the query; only cache complete ones.
+        if !resp.timed_out {
+            self.cache.insert(key, generation, resp.clone());
+        }
+        Ok(resp)
+    }
 
-pub fn search(req: SearchRequest) -> SearchResponse {
-    tracing::info!("Received search request id={} mode={:?}", req.id, req.mode);
-    if let Some(h) = HANDLER.get() {
-        h.search(req)
-    } else {
-        tracing::warn!("No search handler installed, using StubSearchHandler.");
-        StubSearchHandler.search(req)
+    fn plan(&self, req: PlanRequest) -> PlanResponse {
+        let plan = self.choose_mode(&QueryPlanner::optimize(req.query));
+        PlanResponse {
+            id: req.id,
+            chosen_mode: plan.mode,
+            reasons: plan.reasons,
+            estimated_cost: plan.estimated_cost,
+        }
     }
-}
 
-fn to_hit<D: Document>(doc: &D, fields: &MetaFields, score: Score) -> Option<SearchHit> {
-    let mut key = None;
-    let mut name = None;
+    fn get_doc(&self, req: GetDocRequest) -> GetDocResponse {
+        let (main, delta) = self.meta_searchers();
+        // A key the delta shadows is looked up there only: it was renamed or removed since the
+        // main index last saw it.
+        let searcher = match &delta {
+            Some(delta) if delta.shadowed.contains(&req.key) => &delta.searcher,
+            _ => &main,
+        };
+        let meta = match lookup_key(searcher, self.meta.fields.doc_key, req.key) {
+            Ok(doc) => doc.and_then(|d| to_hit(&d, &self.meta.fields, 0.0, None)),
+            Err(err) => {
+                warn!(error = %err, key = %req.key, "meta lookup failed");
+                None
+            }
+        };
+        // Stored content is the file's text: only a caller that may read the file gets it.
+        let readable = meta
+            .as_ref()
+            .and_then(|m| m.path.as_deref())
+            .is_some_and(|path| caller_can_read(Path::new(path)));
+        let content = match (&self.content, req.include_content && readable) {
+            (Some((idx, reader)), true) => {
+                match lookup_key(&reader.searcher(), idx.fields.doc_key, req.key) {
+                    Ok(doc) => doc.and_then(|d| {
+                        d.get_first(idx.fields.content)
+                            .and_then(|v| v.as_str())
+                            .map(str::to_string)
+                    }),
+                    Err(err) => {
+                        warn!(error = %err, key = %req.key, "content lookup failed");
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+        GetDocResponse {
+            id: req.id,
+            meta,
+            content,
+        }
+    }
+
+    fn preview(&self, req: PreviewRequest) -> PreviewResponse {
+        let meta = self
+            .get_doc(GetDocRequest {
+                id: req.id,
+                key: req.key,
+                include_content: false,
+            })
+            .meta;
+        let ext = meta.as_ref().and_then(|m| m.ext.clone());
+        let (text, truncated) = match meta.and_then(|m| m.path) {
+            Some(path) if caller_can_read(Path::new(&path)) => {
+                extract_preview(req.key, &path, ext.as_deref(), req.max_bytes as usize)
+            }
+            Some(path) => {
+                debug!(key = %req.key, path, "preview withheld: the caller cannot read the file");
+                (None, false)
+            }
+            None => (None, false),
+        };
+        PreviewResponse {
+            id: req.id,
+            text,
+            truncated,
+            kind: FileKind::from_ext(ext.as_deref()),
+        }
+    }
+
+    fn complete(&self, req: CompleteRequest) -> CompleteResponse {
+        let field = match req.field {
+            FieldKind::Name => self.meta.fields.name,
+            FieldKind::Ext => self.meta.fields.ext,
+            _ => {
+                return CompleteResponse {
+                    id: req.id,
+                    completions: Vec::new(),
+                };
+            }
+        };
+        let tiers = self.meta_tiers();
+        CompleteResponse {
+            id: req.id,
+            completions: prefix_completions(
+                &tiers.iter().map(|(s, _)| s).collect::<Vec<_>>(),
+                &self.meta.index,
+                field,
+                // Extensions are indexed lowercase and verbatim, without the dot.
+                &match req.field {
+                    FieldKind::Ext => req.prefix.trim_start_matches('.').to_ascii_lowercase(),
+                    _ => req.prefix.clone(),
+                },
+                req.limit,
+            ),
+        }
+    }
+
+    fn children(&self, req: ChildrenRequest) -> ChildrenResponse {
+        let fields = &self.meta.fields;
+        let empty = ChildrenResponse {
+            id: req.id,
+            hits: Vec::new(),
+            total: 0,
+        };
+        // Indexes built before `parent` was indexed have nothing to look up.
+        let Some(parent) = fields.parent else {
+            return empty;
+        };
+        let mut query: Box<dyn Query> = Box::new(TermQuery::new(
+            Term::from_field_u64(parent, req.parent.0),
+            IndexRecordOption::Basic,
+        ));
+        if req.dirs_only {
+            query = Box::new(BooleanQuery::intersection(vec![
+                query,
+                flags_query(fields.flags, FileFlags::IS_DIR),
+            ]));
+        }
+        let limit = req.limit.clamp(1, MAX_CHILDREN) as usize;
+        let mut hits: Vec<SearchHit> = Vec::new();
+        let mut total = 0;
+        for (searcher, hidden) in self.meta_tiers() {
+            let collector = HideKeys::new((TopDocs::with_limit(limit), Count), hidden);
+            let (top, count) = match searcher.search(&query, &collector) {
+                Ok(found) => found,
+                Err(err) => {
+                    warn!(error = %err, parent = %req.parent, "children lookup failed");
+                    return empty;
+                }
+            };
+            total += count;
+            hits.extend(
+                top.into_iter()
+                    .filter_map(|(_, addr)| searcher.doc::<TantivyDocument>(addr).ok())
+                    .filter_map(|doc| to_hit(&doc, fields, 0.0, None)),
+            );
+        }
+        hits.sort_by_cached_key(|h| (h.name.as_deref().map(str::to_lowercase), h.key.0));
+        hits.truncate(limit);
+        ChildrenResponse {
+            id: req.id,
+            hits,
+            total: total as u64,
+        }
+    }
+
+    fn scan(&self, req: ScanRequest) -> ScanResponse {
+        let fields = &self.meta.fields;
+        let empty = ScanResponse {
+            id: req.id,
+            hits: Vec::new(),
+            next: None,
+        };
+        let (first, last) = match req.volume {
+            Some(volume) => (
+                DocKey::from_parts(volume, 0).0,
+                DocKey::from_parts(volume, u64::MAX).0,
+            ),
+            None => (0, u64::MAX),
+        };
+        let first = match req.after {
+            Some(after) if after.0 >= last => return empty,
+            Some(after) => first.max(after.0 + 1),
+            None => first,
+        };
+        // `doc_key` is a fast field, so the range runs on its column; results come back in key
+        // order without scoring.
+        let query = RangeQuery::new(
+            Bound::Included(Term::from_field_u64(fields.doc_key, first)),
+            Bound::Included(Term::from_field_u64(fields.doc_key, last)),
+        );
+        let limit = req.limit.clamp(1, MAX_SCAN) as usize;
+        // Each tier's first `limit` keys; a key lives in one tier only, so the merged first
+        // `limit` are the page.
+        let mut found: Vec<(u64, Option<TantivyDocument>)> = Vec::new();
+        for (searcher, hidden) in self.meta_tiers() {
+            let collector = HideKeys::new(
+                TopDocs::with_limit(limit).order_by_fast_field::<u64>(DOC_KEY_FIELD, Order::Asc),
+                hidden,
+            );
+            match searcher.search(&query, &collector) {
+                Ok(top) => found.extend(
+                    top.into_iter()
+                        .map(|(key, addr)| (key, searcher.doc::<TantivyDocument>(addr).ok())),
+                ),
+                Err(err) => {
+                    warn!(error = %err, after = ?req.after, "scan failed");
+                    return empty;
+                }
+            }
+        }
+        found.sort_by_key(|(key, _)| *key);
+        found.truncate(limit);
+        // Resume from the last key collected, even if its doc failed to load.
+        let next = (found.len() == limit)
+            .then(|| found.last().map(|&(key, _)| DocKey(key)))
+            .flatten();
+        let hits = found
+            .into_iter()
+            .filter_map(|(_, doc)| doc)
+            .filter_map(|doc| to_hit(&doc, fields, 0.0, None))
+            .collect();
+        ScanResponse {
+            id: req.id,
+            hits,
+            next,
+        }
+    }
+
+    fn trim_caches(&self) {
+        let (responses, snippets) = (self.cache.len(), self.snippets.len());
+        self.cache.clear();
+        self.snippets.clear();
+        // Reloading swaps in a fresh searcher, releasing segments a merge has replaced once
+        // the last search using the old one finishes.
+        if let Err(err) = self.meta_reader.reload() {
+            warn!(error = %err, "failed to reload the meta index while trimming");
+        }
+        if let Some((_, reader)) = &self.content
+            && let Err(err) = reader.reload()
+        {
+            warn!(error = %err, "failed to reload the content index while trimming");
+        }
+        tracing::info!(responses, snippets, "trimmed search caches");
+    }
+
+    fn apply_config(&self, cfg: &AppConfig) {
+        let wanted = query_log_path(cfg);
+        let mut log = self.query_log.lock();
+        if log.as_ref().map(|l| l.path()) != wanted.as_deref() {
+            *log = wanted.map(new_query_log);
+        }
+    }
+}
+
+/// Where `cfg` wants searches logged; `None` with `features.log_dataset_mode` off.
+fn query_log_path(cfg: &AppConfig) -> Option<PathBuf> {
+    cfg.features
+        .log_dataset_mode
+        .then(|| Path::new(&cfg.app.data_dir).join(QUERY_LOG_FILE))
+}
+
+fn new_query_log(path: PathBuf) -> Arc<QueryLog> {
+    Arc::new(QueryLog::new(path, MAX_LOG_BYTES))
+}
+
+/// Cap on `ChildrenRequest::limit`.
+pub const MAX_CHILDREN: u32 = 5_000;
+
+/// Cap on `ScanRequest::limit`.
+pub const MAX_SCAN: u32 = 10_000;
+
+/// Groups returned per response, largest first.
+pub const MAX_GROUPS: usize = 50;
+
+/// Hits kept per group.
+pub const HITS_PER_GROUP: usize = 3;
+
+/// Matches examined when grouping.
+const GROUP_SCAN_LIMIT: usize = 10_000;
+
+/// The `group_by` value of `hit`: its parent directory, its extension (empty when none) or
+/// its [`FileKind`] label.
+fn group_key(hit: &SearchHit, field: FieldKind) -> Option<String> {
+    match field {
+        FieldKind::Path => {
+            let path = hit.path.as_deref()?;
+            let parent = match path.rfind(['\\', '/']) {
+                // Keep the separator of a root (`C:\`, `/`) so it stays a directory path.
+                Some(i) if i == 0 || path[..i].ends_with(':') => &path[..=i],
+                Some(i) => &path[..i],
+                None => "",
+            };
+            Some(parent.to_string())
+        }
+        FieldKind::Ext => Some(hit.ext.clone().unwrap_or_default()),
+        FieldKind::Kind => Some(FileKind::from_ext(hit.ext.as_deref()).as_str().to_string()),
+        _ => None,
+    }
+}
+
+/// Largest file read for a preview; bigger files get no text rather than a slow read.
+const PREVIEW_READ_LIMIT: usize = 16 * 1024 * 1024;
+/// Previews are interactive, so give up on a stuck extraction well before the worker would.
+const PREVIEW_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Run the extractor stack over `path` and cut the text to `max_bytes`. Returns `(None, false)`
+/// for binary or unsupported files (the fallback backend yields no text) and on failure.
+fn extract_preview(
+    key: DocKey,
+    path: &str,
+    ext: Option<&str>,
+    max_bytes: usize,
+) -> (Option<String>, bool) {
+    let ctx = ExtractContext {
+        path,
+        max_bytes: PREVIEW_READ_LIMIT,
+        max_chars: PREVIEW_READ_LIMIT,
+        ext_hint: ext,
+        mime_hint: None,
+        deadline: Some(Instant::now() + PREVIEW_TIMEOUT),
+    };
+    match ExtractorStack::with_defaults().extract(key, &ctx) {
+        Ok(out) if !out.text.is_empty() => {
+            let cap = ExtractContext {
+                max_bytes,
+                max_chars: usize::MAX,
+                ..ctx
+            };
+            let (text, cut, _) = enforce_limits_str(&out.text, &cap);
+            (Some(text), cut || out.truncated)
+        }
+        Ok(_) => (None, false),
+        Err(err) => {
+            debug!(error = %err, %key, path, "preview extraction failed");
+            (None, false)
+        }
+    }
+}
+
+/// Fetch the document stored under `key`. `doc_key` is a fast field rather than an indexed
+/// term, so this is a single-value range query.
+fn lookup_key(
+    searcher: &tantivy::Searcher,
+    doc_key: Field,
+    key: core_types::DocKey,
+) -> Result<Option<TantivyDocument>> {
+    let term = Term::from_field_u64(doc_key, key.0);
+    let query = RangeQuery::new(Bound::Included(term.clone()), Bound::Included(term));
+    let top = searcher.search(&query, &TopDocs::with_limit(1))?;
+    match top.first() {
+        Some((_, addr)) => Ok(Some(searcher.doc(*addr)?)),
+        None => Ok(None),
+    }
+}
+
+/// Merge meta and content hits for one document into a single ranked list. Hits are the same
+/// document only when volume and FRN both match ([`SearchHit::stable_id`]); the same FRN on
+/// another volume is a different file and stays a separate hit.
+fn merge_hits(meta: Vec<SearchHit>, content: Vec<SearchHit>) -> Vec<SearchHit> {
+    let mut hits_map: HashMap<(VolumeId, FileId), SearchHit> = HashMap::new();
+
+    for hit in meta {
+        hits_map.insert(hit.stable_id(), hit);
+    }
+
+    for hit in content {
+        hits_map
+            .entry(hit.stable_id())
+            .and_modify(|e| {
+                e.score = e.score.max(hit.score); // Max score strategy? Or sum? Max is safer for boolean queries.
+                if e.snippet.is_none() {
+                    e.snippet = hit.snippet.clone();
+                }
+            })
+            .or_insert(hit);
+    }
+
+    let mut merged: Vec<SearchHit> = hits_map.into_values().collect();
+    merged.sort_by(|a, b| compare_ranked(a.score, a.key, b.score, b.key));
+    merged
+}
+
+/// Fast field, value type and direction backing `req.sort`, when `index` has that column.
+/// Unsortable fields (and `Created` on the content index) fall back to score order.
+fn sort_column(
+    req: &SearchRequest,
+    index: &tantivy::Index,
+) -> Option<(&'static str, SortValue, bool)> {
+    let sort = req.sort?;
+    let (field, value) = match sort.field {
+        FieldKind::Modified => ("modified", SortValue::I64),
+        FieldKind::Created => ("created", SortValue::I64),
+        FieldKind::Size => ("size", SortValue::U64),
+        _ => return None,
+    };
+    index.schema().get_field(field).ok()?;
+    Some((field, value, sort.descending))
+}
+
+/// Value of the sort column `field` for the doc at `addr`, to merge field-sorted pages of the
+/// meta index's two tiers.
+fn sort_value(
+    searcher: &Searcher,
+    addr: DocAddress,
+    field: &str,
+    value: SortValue,
+) -> Option<i128> {
+    let columns = searcher.segment_reader(addr.segment_ord).fast_fields();
+    match value {
+        SortValue::I64 => columns.i64(field).ok()?.first(addr.doc_id).map(i128::from),
+        SortValue::U64 => columns.u64(field).ok()?.first(addr.doc_id).map(i128::from),
+    }
+}
+
+/// Response carrying one page of name-index hits.
+fn meta_response(
+    req: &SearchRequest,
+    start: Instant,
+    hits: Vec<SearchHit>,
+    total: usize,
+    next_cursor: Option<PageCursor>,
+    timed_out: bool,
+) -> SearchResponse {
+    SearchResponse {
+        id: req.id,
+        hits,
+        total: total as u64,
+        truncated: timed_out,
+        took_ms: start.elapsed().as_millis().min(u32::MAX as u128) as u32,
+        served_by: None,
+        next_cursor: next_cursor.map(|c| c.encode()),
+        explanation: None,
+        timed_out,
+        degraded: None,
+        suggestions: Vec::new(),
+        groups: Vec::new(),
+    }
+}
+
+/// Re-order merged hybrid hits by the `modified` or `size` they carry; hits without the value
+/// go last. Other fields keep score order.
+fn sort_hits(hits: &mut [SearchHit], sort: SortBy) {
+    let value = |h: &SearchHit| match sort.field {
+        FieldKind::Modified => h.modified.map(i128::from),
+        FieldKind::Size => h.size.map(i128::from),
+        _ => None,
+    };
+    if !matches!(sort.field, FieldKind::Modified | FieldKind::Size) {
+        return;
+    }
+    hits.sort_by(|a, b| {
+        let by_value = match (value(a), value(b)) {
+            (Some(x), Some(y)) if sort.descending => y.cmp(&x),
+            (Some(x), Some(y)) => x.cmp(&y),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        };
+        by_value.then_with(|| a.key.0.cmp(&b.key.0))
+    });
+}
+
+fn request_cursor(req: &SearchRequest) -> Option<PageCursor> {
+    let raw = req.cursor.as_deref()?;
+    match PageCursor::decode(raw) {
+        Ok(c) => Some(c),
+        Err(err) => {
+            warn!(error = %err, "ignoring invalid search cursor");
+            None
+        }
+    }
+}
+
+// Helper to map content doc to SearchHit
+fn to_hit_content<D: Document>(
+    doc: &D,
+    fields: &content_index::ContentFields,
+    score: Score,
+    projection: Option<&[FieldKind]>,
+) -> Option<SearchHit> {
+    let wants = |kind| wants_field(projection, kind);
+    let mut key = None;
+    let mut name = None;
     let mut path = None;
     let mut ext = None;
     let mut size = None;
@@ -503,27 +1502,176 @@ fn to_hit<D: Document>(doc: &D, fields: &MetaFields, score: Score) -> Option<Sea
                     key = Some(core_types::DocKey(v));
                 }
             }
-            f if f == fields.name => {
-                if let Some(s) = value.as_str() {
-                    name = Some(s.to_string());
+            f if f == fields.name && wants(FieldKind::Name) => {
+                name = value.as_str().map(|s| s.to_string())
+            }
+            f if f == fields.path && wants(FieldKind::Path) => {
+                path = value.as_str().map(|s| s.to_string())
+            }
+            f if f == fields.ext && wants(FieldKind::Ext) => {
+                ext = value.as_str().map(|s| s.to_string())
+            }
+            f if f == fields.size && wants(FieldKind::Size) => size = value.as_u64(),
+            f if f == fields.modified && wants(FieldKind::Modified) => modified = value.as_i64(),
+            _ => {}
+        }
+    }
+
+    key.map(|doc_key| SearchHit {
+        key: doc_key,
+        score,
+        name,
+        path,
+        ext,
+        size,
+        modified,
+        // Filled in by `search_content`, through the snippet cache.
+        snippet: None,
+    })
+}
+
+/// Scale scores so the best hit is 1 and the rest keep their ratio to it. Hits without a
+/// positive best score (sorted or empty-query results) are left as they are.
+fn normalize_scores(hits: &mut [SearchHit]) {
+    let top = hits.iter().map(|h| h.score).fold(0.0_f32, f32::max);
+    if top > 0.0 {
+        for hit in hits {
+            hit.score /= top;
+        }
+    }
+}
+
+static HANDLER: OnceLock<Box<dyn SearchHandler>> = OnceLock::new();
+
+pub fn set_search_handler(handler: Box<dyn SearchHandler>) {
+    tracing::info!("Global search handler installed.");
+    let _ = HANDLER.set(handler);
+}
+
+#[instrument(skip_all, fields(request_id = %req.id))]
+pub fn get_doc(req: GetDocRequest) -> GetDocResponse {
+    tracing::info!("Received get_doc request id={} key={}", req.id, req.key);
+    match HANDLER.get() {
+        Some(h) => h.get_doc(req),
+        None => StubSearchHandler.get_doc(req),
+    }
+}
+
+#[instrument(skip_all, fields(request_id = %req.id))]
+pub fn plan(req: PlanRequest) -> PlanResponse {
+    match HANDLER.get() {
+        Some(h) => h.plan(req),
+        None => StubSearchHandler.plan(req),
+    }
+}
+
+#[instrument(skip_all, fields(request_id = %req.id))]
+pub fn preview(req: PreviewRequest) -> PreviewResponse {
+    match HANDLER.get() {
+        Some(h) => h.preview(req),
+        None => StubSearchHandler.preview(req),
+    }
+}
+
+#[instrument(skip_all, fields(request_id = %req.id))]
+pub fn complete(req: CompleteRequest) -> CompleteResponse {
+    match HANDLER.get() {
+        Some(h) => h.complete(req),
+        None => StubSearchHandler.complete(req),
+    }
+}
+
+#[instrument(skip_all, fields(request_id = %req.id))]
+pub fn children(req: ChildrenRequest) -> ChildrenResponse {
+    match HANDLER.get() {
+        Some(h) => h.children(req),
+        None => StubSearchHandler.children(req),
+    }
+}
+
+#[instrument(skip_all, fields(request_id = %req.id))]
+pub fn scan(req: ScanRequest) -> ScanResponse {
+    match HANDLER.get() {
+        Some(h) => h.scan(req),
+        None => StubSearchHandler.scan(req),
+    }
+}
+
+/// [`SearchHandler::apply_config`] on the installed handler, if any.
+pub fn apply_config(cfg: &AppConfig) {
+    if let Some(h) = HANDLER.get() {
+        h.apply_config(cfg);
+    }
+}
+
+/// [`SearchHandler::trim_caches`] on the installed handler, if any.
+pub fn trim_caches() {
+    if let Some(h) = HANDLER.get() {
+        h.trim_caches();
+    }
+}
+
+/// Entry point for IPC searches. Everything logged while serving `req` (planning, query
+/// execution, failures) carries its `request_id`, so one search can be traced end to end.
+#[instrument(skip_all, fields(request_id = %req.id))]
+pub fn search(req: SearchRequest) -> Result<SearchResponse> {
+    tracing::info!("Received search request id={} mode={:?}", req.id, req.mode);
+    if let Some(h) = HANDLER.get() {
+        h.try_search(req)
+    } else {
+        tracing::warn!("No search handler installed, using StubSearchHandler.");
+        StubSearchHandler.try_search(req)
+    }
+}
+
+/// Whether a hit should carry `kind` under the request's field projection (`None` = all).
+fn wants_field(projection: Option<&[FieldKind]>, kind: FieldKind) -> bool {
+    projection.is_none_or(|p| p.contains(&kind))
+}
+
+fn to_hit<D: Document>(
+    doc: &D,
+    fields: &MetaFields,
+    score: Score,
+    projection: Option<&[FieldKind]>,
+) -> Option<SearchHit> {
+    let wants = |kind| wants_field(projection, kind);
+    let mut key = None;
+    let mut name = None;
+    let mut path = None;
+    let mut ext = None;
+    let mut size = None;
+    let mut modified = None;
+
+    for (field, value) in doc.iter_fields_and_values() {
+        match field {
+            f if f == fields.doc_key => {
+                if let Some(v) = value.as_u64() {
+                    key = Some(core_types::DocKey(v));
                 }
             }
-            f if f == fields.path => {
+            f if f == fields.name => {
                 if let Some(s) = value.as_str() {
-                    path = Some(s.to_string());
+                    // `ext` is indexed but not stored, so it comes from the name.
+                    if wants(FieldKind::Ext) {
+                        ext = core_types::file_ext(s);
+                    }
+                    if wants(FieldKind::Name) {
+                        name = Some(s.to_string());
+                    }
                 }
             }
-            f if f == fields.ext => {
+            f if f == fields.path && wants(FieldKind::Path) => {
                 if let Some(s) = value.as_str() {
-                    ext = Some(s.to_string());
+                    path = Some(s.to_string());
                 }
             }
-            f if f == fields.size => {
+            f if f == fields.size && wants(FieldKind::Size) => {
                 if let Some(v) = value.as_u64() {
                     size = Some(v);
                 }
             }
-            f if f == fields.modified => {
+            f if f == fields.modified && wants(FieldKind::Modified) => {
                 if let Some(v) = value.as_i64() {
                     modified = Some(v);
                 }
@@ -543,3 +1691,1659 @@ fn to_hit<D: Document>(doc: &D, fields: &MetaFields, score: Score) -> Option<Sea
         snippet: None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::caller::{NoAccess, with_caller};
+    use crate::meta_ingest::ingest_file_meta_batch;
+    use core_types::{DocKey, FileFlags, FileMeta};
+    use ipc::{FieldKind, QueryExpr, RangeExpr, RangeOp, RangeValue, TermExpr, TermModifier};
+    use std::collections::HashSet;
+    use tempfile::tempdir;
+
+    const DOCS: u64 = 1000;
+
+    fn handler_with(dir: &Path, metas: impl IntoIterator<Item = FileMeta>) -> UnifiedSearchHandler {
+        let meta_path = dir.join("meta");
+        std::fs::create_dir_all(&meta_path).unwrap();
+        ingest_file_meta_batch(&meta_path, metas, None).unwrap();
+        UnifiedSearchHandler::try_new(&meta_path, &dir.join("missing-content")).unwrap()
+    }
+
+    fn file(frn: u64, name: &str) -> FileMeta {
+        FileMeta::new(
+            DocKey::from_parts(1, frn),
+            1,
+            None,
+            name.to_string(),
+            Some(format!("C:\\docs\\{name}")),
+            0,
+            0,
+            0,
+            FileFlags::empty(),
+        )
+    }
+
+    /// Swap in a manually reloaded meta reader. The default reader also reloads once in the
+    /// background right after opening, which would change the searcher mid-test.
+    fn pin_meta_reader(handler: &mut UnifiedSearchHandler) {
+        handler.meta_reader = handler
+            .meta
+            .index
+            .reader_builder()
+            .reload_policy(tantivy::ReloadPolicy::Manual)
+            .try_into()
+            .unwrap();
+    }
+
+    fn seeded_handler(dir: &Path) -> UnifiedSearchHandler {
+        // Scatter keys so DocKey order differs from insertion (doc id) order.
+        handler_with(
+            dir,
+            (0..DOCS).map(|i| file((i * 7919) % DOCS + 1, &format!("report_{i}.txt"))),
+        )
+    }
+
+    fn report_query(mode: SearchMode) -> SearchRequest {
+        SearchRequest::with_query(QueryExpr::Term(TermExpr {
+            field: None,
+            value: "report".into(),
+            modifier: TermModifier::Term,
+            boost: None,
+        }))
+        .with_limit(64)
+        .with_mode(mode)
+    }
+
+    fn page_all(handler: &UnifiedSearchHandler, mode: SearchMode) -> Vec<DocKey> {
+        let mut seen = Vec::new();
+        let mut req = report_query(mode);
+        loop {
+            let resp = handler.search(req.clone());
+            assert_eq!(resp.total, DOCS);
+            assert!(resp.hits.len() <= 64);
+            seen.extend(resp.hits.iter().map(|h| h.key));
+            match resp.next_cursor {
+                Some(c) => req = req.with_cursor(c),
+                None => break,
+            }
+        }
+        seen
+    }
+
+    #[test]
+    fn cursor_paging_visits_every_doc_once() {
+        let dir = tempdir().unwrap();
+        let handler = seeded_handler(dir.path());
+
+        for mode in [SearchMode::NameOnly, SearchMode::Hybrid] {
+            let seen = page_all(&handler, mode);
+            let unique: HashSet<_> = seen.iter().copied().collect();
+            assert_eq!(seen.len() as u64, DOCS, "mode {mode:?}");
+            assert_eq!(unique.len(), seen.len(), "duplicates in mode {mode:?}");
+        }
+    }
+
+    #[test]
+    fn cursor_pages_match_offset_pages() {
+        let dir = tempdir().unwrap();
+        let handler = seeded_handler(dir.path());
+
+        let first = handler.search(report_query(SearchMode::NameOnly));
+        let cursor = first.next_cursor.clone().expect("more pages");
+        let by_cursor = handler.search(report_query(SearchMode::NameOnly).with_cursor(cursor));
+        let by_offset = handler.search(report_query(SearchMode::NameOnly).with_offset(64));
+
+        let keys = |r: &SearchResponse| r.hits.iter().map(|h| h.key).collect::<Vec<_>>();
+        assert_eq!(keys(&by_cursor), keys(&by_offset));
+    }
+
+    const HYBRID_DOCS: usize = 45;
+
+    /// `report_{i}.txt` in the meta index for 40 files, every third of them also with content
+    /// mentioning "report" a varying number of times, plus 5 content-only memos: hybrid pages
+    /// merge hits of both indexes with scores that differ per side.
+    fn hybrid_handler(dir: &Path) -> UnifiedSearchHandler {
+        let meta_path = dir.join("meta");
+        let content_path = dir.join("content");
+        std::fs::create_dir_all(&meta_path).unwrap();
+        std::fs::create_dir_all(&content_path).unwrap();
+        ingest_file_meta_batch(
+            &meta_path,
+            (0..40).map(|i| file(i + 1, &format!("report_{i}.txt"))),
+            None,
+        )
+        .unwrap();
+
+        let content = content_index::open_or_create(&content_path).unwrap();
+        let mut writer =
+            content_index::create_writer(&content, &content_index::WriterConfig::default())
+                .unwrap();
+        let with_content = (0..40u64)
+            .step_by(3)
+            .map(|i| (i + 1, format!("report_{i}.txt"), i % 4 + 1))
+            .chain((0..5u64).map(|i| (100 + i, format!("memo_{i}.txt"), i + 1)));
+        for (frn, name, mentions) in with_content {
+            let doc = content_index::ContentDoc {
+                key: DocKey::from_parts(1, frn),
+                volume: 1,
+                path: Some(format!("C:\\docs\\{name}")),
+                name: Some(name),
+                ext: Some("txt".into()),
+                size: 0,
+                modified: 0,
+                content_lang: None,
+                content: format!(
+                    "{} and other notes",
+                    vec!["report"; mentions as usize].join(" ")
+                ),
+            };
+            content_index::upsert_content_doc(&mut writer, &content.fields, &doc).unwrap();
+        }
+        writer.commit().unwrap();
+        drop(writer);
+        UnifiedSearchHandler::try_new(&meta_path, &content_path).unwrap()
+    }
+
+    fn keys(resp: &SearchResponse) -> Vec<DocKey> {
+        resp.hits.iter().map(|h| h.key).collect()
+    }
+
+    #[test]
+    fn hybrid_cursor_pages_merge_both_indexes_without_repeats() {
+        let dir = tempdir().unwrap();
+        let handler = hybrid_handler(dir.path());
+        let all = handler.search(report_query(SearchMode::Hybrid).with_limit(100));
+        assert_eq!(all.hits.len(), HYBRID_DOCS);
+        assert!(
+            all.hits.iter().any(|h| h.score != all.hits[0].score),
+            "fixture needs distinct merged scores"
+        );
+
+        let mut seen = Vec::new();
+        let mut pages = 0;
+        let mut req = report_query(SearchMode::Hybrid).with_limit(4);
+        loop {
+            let resp = handler.search(req.clone());
+            // The larger side's count until a page collects the whole union.
+            assert!((40..=HYBRID_DOCS as u64).contains(&resp.total));
+            seen.extend(keys(&resp));
+            pages += 1;
+            match resp.next_cursor {
+                Some(c) => req = req.with_cursor(c),
+                None => break,
+            }
+        }
+        assert_eq!(pages, HYBRID_DOCS.div_ceil(4));
+        assert_eq!(
+            seen,
+            keys(&all),
+            "cursor pages follow the single-page order"
+        );
+    }
+
+    #[test]
+    fn hybrid_offset_pages_reach_past_twice_the_limit() {
+        let dir = tempdir().unwrap();
+        let handler = hybrid_handler(dir.path());
+        let all = keys(&handler.search(report_query(SearchMode::Hybrid).with_limit(100)));
+
+        for offset in [4, 8, 13, 40] {
+            let page = handler.search(
+                report_query(SearchMode::Hybrid)
+                    .with_limit(4)
+                    .with_offset(offset),
+            );
+            let offset = offset as usize;
+            assert!((40..=HYBRID_DOCS as u64).contains(&page.total));
+            assert_eq!(
+                keys(&page),
+                all[offset..(offset + 4).min(HYBRID_DOCS)],
+                "offset {offset}"
+            );
+        }
+    }
+
+    #[test]
+    fn last_page_has_no_cursor() {
+        let dir = tempdir().unwrap();
+        let handler = seeded_handler(dir.path());
+
+        let resp = handler.search(report_query(SearchMode::NameOnly).with_limit(DOCS as u32));
+        assert_eq!(resp.hits.len() as u64, DOCS);
+        assert!(resp.next_cursor.is_none());
+    }
+
+    #[test]
+    fn limits_over_the_server_max_are_clamped_and_reported() {
+        let dir = tempdir().unwrap();
+        let mut handler = seeded_handler(dir.path());
+        handler.max_limit = 10;
+
+        let resp = handler.search(report_query(SearchMode::NameOnly).with_limit(u32::MAX));
+        assert_eq!(resp.hits.len(), 10);
+        assert!(resp.truncated);
+        let resp = handler.search(report_query(SearchMode::NameOnly).with_limit(10));
+        assert_eq!(resp.hits.len(), 10);
+        assert!(!resp.truncated);
+
+        // `offset + limit` past u32::MAX is an empty page, not an overflow.
+        for mode in [SearchMode::NameOnly, SearchMode::Hybrid] {
+            let resp = handler.search(
+                report_query(mode)
+                    .with_limit(u32::MAX)
+                    .with_offset(u32::MAX),
+            );
+            assert!(resp.hits.is_empty(), "mode {mode:?}");
+            assert_eq!(resp.total, DOCS, "mode {mode:?}");
+        }
+    }
+
+    #[test]
+    fn grouping_by_path_buckets_matches_per_directory() {
+        let dir = tempdir().unwrap();
+        let in_dir = |frn, dir: &str, name: &str| {
+            let mut meta = file(frn, name);
+            meta.path = Some(format!("{dir}\\{name}"));
+            meta
+        };
+        let handler = handler_with(
+            dir.path(),
+            [
+                in_dir(1, r"C:\work", "report_a.txt"),
+                in_dir(2, r"C:\work", "report_b.txt"),
+                in_dir(3, r"C:\work", "report_c.txt"),
+                in_dir(4, r"C:\work", "report_d.txt"),
+                in_dir(5, r"D:\archive", "report_old.txt"),
+                in_dir(6, r"D:\archive", "notes.txt"),
+            ],
+        );
+
+        let resp = handler.search(
+            report_query(SearchMode::NameOnly)
+                .with_limit(2)
+                .with_group_by(FieldKind::Path),
+        );
+        assert_eq!(resp.total, 5);
+        assert_eq!(resp.hits.len(), 2);
+        let groups: Vec<(&str, u64, usize)> = resp
+            .groups
+            .iter()
+            .map(|g| (g.key.as_str(), g.count, g.top_hits.len()))
+            .collect();
+        assert_eq!(
+            groups,
+            [(r"C:\work", 4, HITS_PER_GROUP), (r"D:\archive", 1, 1)]
+        );
+
+        let ungrouped = handler.search(report_query(SearchMode::NameOnly));
+        assert!(ungrouped.groups.is_empty());
+    }
+
+    #[test]
+    fn children_lists_direct_entries_of_a_directory() {
+        let dir = tempdir().unwrap();
+        let folder = DocKey::from_parts(1, 10);
+        let child = |frn, name: &str, parent| {
+            let mut meta = file(frn, name);
+            meta.parent = Some(parent);
+            meta
+        };
+        let mut sub = child(12, "Drafts", folder);
+        sub.flags = FileFlags::IS_DIR;
+        let mut projects = file(10, "Projects");
+        projects.flags = FileFlags::IS_DIR;
+        let handler = handler_with(
+            dir.path(),
+            [
+                projects,
+                child(11, "plan.txt", folder),
+                sub,
+                child(13, "nested.txt", DocKey::from_parts(1, 12)),
+                child(14, "elsewhere.txt", DocKey::from_parts(1, 99)),
+            ],
+        );
+        let children = |limit, dirs_only| {
+            handler.children(ChildrenRequest {
+                id: uuid::Uuid::new_v4(),
+                parent: folder,
+                limit,
+                dirs_only,
+            })
+        };
+
+        let resp = children(100, false);
+        assert_eq!(resp.total, 2);
+        let names: Vec<_> = resp.hits.iter().filter_map(|h| h.name.as_deref()).collect();
+        assert_eq!(names, ["Drafts", "plan.txt"]);
+        assert_eq!(resp.hits[0].key, DocKey::from_parts(1, 12));
+
+        let dirs = children(100, true);
+        assert_eq!((dirs.total, dirs.hits.len()), (1, 1));
+        let first = children(1, false);
+        assert_eq!((first.total, first.hits.len()), (2, 1));
+    }
+
+    #[test]
+    fn flag_filters_match_attribute_bits() {
+        let dir = tempdir().unwrap();
+        let flagged = |frn, name: &str, flags| {
+            let mut meta = file(frn, name);
+            meta.flags = flags;
+            meta
+        };
+        let handler = handler_with(
+            dir.path(),
+            [
+                flagged(1, "reports", FileFlags::IS_DIR),
+                flagged(2, "report.txt", FileFlags::empty()),
+                flagged(3, "report.bak", FileFlags::HIDDEN | FileFlags::ARCHIVE),
+                flagged(4, "old reports", FileFlags::IS_DIR | FileFlags::HIDDEN),
+            ],
+        );
+        let names = |query: &str| {
+            let req = SearchRequest::with_query(ipc::query::parse_query(query).unwrap())
+                .with_mode(SearchMode::NameOnly);
+            let mut names: Vec<String> = handler
+                .search(req)
+                .hits
+                .into_iter()
+                .filter_map(|h| h.name)
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(names("flags:dir"), ["old reports", "reports"]);
+        assert_eq!(names("NOT flags:hidden"), ["report.txt", "reports"]);
+        assert_eq!(names("flags:dir -flags:hidden"), ["reports"]);
+    }
+
+    #[test]
+    fn count_only_returns_total_without_reading_stored_fields() {
+        let dir = tempdir().unwrap();
+        let mut handler = seeded_handler(dir.path());
+        pin_meta_reader(&mut handler);
+        let store_reads = || {
+            let stats = handler.meta_reader.searcher().doc_store_cache_stats();
+            stats.cache_hits + stats.cache_misses
+        };
+
+        let before = store_reads();
+        let resp = handler.search(report_query(SearchMode::NameOnly).with_count_only());
+        assert_eq!(resp.total, DOCS);
+        assert!(resp.hits.is_empty());
+        assert!(resp.next_cursor.is_none() && !resp.timed_out);
+        assert_eq!(
+            store_reads(),
+            before,
+            "count-only must not touch the doc store"
+        );
+
+        let hybrid = handler.search(report_query(SearchMode::Hybrid).with_count_only());
+        assert_eq!((hybrid.total, hybrid.hits.len()), (DOCS, 0));
+        assert_eq!(store_reads(), before);
+
+        // A regular search over the same query loads the page's docs.
+        let full = handler.search(report_query(SearchMode::NameOnly));
+        assert_eq!((full.total, full.hits.len()), (DOCS, 64));
+        assert!(store_reads() > before);
+    }
+
+    #[test]
+    fn equivalent_queries_share_a_cache_entry_until_commit() {
+        let dir = tempdir().unwrap();
+        let mut handler = handler_with(dir.path(), [file(1, "alpha beta.txt")]);
+        pin_meta_reader(&mut handler);
+        let name = |value: &str| {
+            QueryExpr::Term(TermExpr {
+                field: Some(FieldKind::Name),
+                value: value.into(),
+                modifier: TermModifier::Term,
+                boost: None,
+            })
+        };
+        let ab = SearchRequest::with_query(QueryExpr::And(vec![name("alpha"), name("beta")]))
+            .with_mode(SearchMode::NameOnly);
+        let mut ba = SearchRequest::with_query(QueryExpr::And(vec![name("beta"), name("alpha")]))
+            .with_mode(SearchMode::NameOnly);
+        ba.id = uuid::Uuid::new_v4();
+
+        assert_eq!(handler.search(ab.clone()).total, 1);
+        let cached = handler.search(ba.clone());
+        assert_eq!((cached.total, cached.id), (1, ba.id));
+        assert_eq!((handler.cache.hits(), handler.cache.len()), (1, 1));
+
+        ingest_file_meta_batch(&dir.path().join("meta"), [file(2, "beta alpha.txt")], None)
+            .unwrap();
+        handler.meta_reader.reload().unwrap();
+        assert_eq!(
+            handler.search(ab).total,
+            2,
+            "commit must invalidate the entry"
+        );
+        assert_eq!(handler.cache.hits(), 1);
+    }
+
+    #[test]
+    fn excluded_paths_are_dropped_unless_requested() {
+        let dir = tempdir().unwrap();
+        let mut binned = file(2, "report.txt");
+        binned.path = Some(r"C:\$Recycle.Bin\S-1-5-21\report.txt".into());
+        // Shares the excluded segment's words, but not the segment itself.
+        let lookalike = file(3, "recycle_bin_report.txt");
+        let handler = handler_with(dir.path(), [file(1, "report.txt"), binned, lookalike]);
+        let keys = |req: SearchRequest| {
+            let mut keys: Vec<DocKey> = handler.search(req).hits.iter().map(|h| h.key).collect();
+            keys.sort();
+            keys
+        };
+
+        let req = report_query(SearchMode::NameOnly);
+        assert_eq!(
+            keys(req.clone()),
+            [DocKey::from_parts(1, 1), DocKey::from_parts(1, 3)]
+        );
+        assert_eq!(
+            keys(req.with_include_system()),
+            [
+                DocKey::from_parts(1, 1),
+                DocKey::from_parts(1, 2),
+                DocKey::from_parts(1, 3)
+            ]
+        );
+    }
+
+    #[test]
+    fn configured_default_fields_limit_unqualified_terms() {
+        let dir = tempdir().unwrap();
+        let mut in_report_dir = file(2, "notes.txt");
+        in_report_dir.path = Some(r"C:\report\notes.txt".into());
+        let mut handler = handler_with(dir.path(), [file(1, "report.txt"), in_report_dir]);
+        let keys = |handler: &UnifiedSearchHandler| {
+            let req = report_query(SearchMode::NameOnly);
+            let mut keys: Vec<DocKey> = handler.search(req).hits.iter().map(|h| h.key).collect();
+            keys.sort();
+            keys
+        };
+
+        assert_eq!(
+            keys(&handler),
+            [DocKey::from_parts(1, 1), DocKey::from_parts(1, 2)],
+            "name + path by default"
+        );
+        handler.default_fields = vec![(FieldKind::Name, 1.0)];
+        handler.cache = QueryCache::default();
+        assert_eq!(keys(&handler), [DocKey::from_parts(1, 1)]);
+    }
+
+    fn hit(key: DocKey, score: f32, snippet: Option<&str>) -> SearchHit {
+        SearchHit {
+            key,
+            score,
+            name: Some("report.txt".into()),
+            path: None,
+            ext: None,
+            size: None,
+            modified: None,
+            snippet: snippet.map(Into::into),
+        }
+    }
+
+    #[test]
+    fn preview_extracts_the_file_behind_a_key() {
+        let dir = tempdir().unwrap();
+        let on_disk = |frn: u64, name: &str, body: &[u8]| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, body).unwrap();
+            let mut meta = file(frn, name);
+            meta.path = Some(path.to_string_lossy().into_owned());
+            meta
+        };
+        let handler = handler_with(
+            dir.path(),
+            [
+                on_disk(1, "notes.txt", b"hello preview pane"),
+                on_disk(2, "blob.png", &[0x89, b'P', b'N', b'G', 0, 0, 1, 2]),
+            ],
+        );
+        let preview = |frn: u64, max_bytes: u32| {
+            handler.preview(PreviewRequest {
+                id: uuid::Uuid::new_v4(),
+                key: DocKey::from_parts(1, frn),
+                max_bytes,
+            })
+        };
+
+        let full = preview(1, 4096);
+        assert_eq!(full.text.as_deref(), Some("hello preview pane"));
+        assert!(!full.truncated);
+        assert_eq!(full.kind, FileKind::Text);
+
+        let cut = preview(1, 5);
+        assert_eq!((cut.text.as_deref(), cut.truncated), (Some("hello"), true));
+
+        let binary = preview(2, 4096);
+        assert_eq!((binary.text, binary.kind), (None, FileKind::Image));
+
+        let missing = preview(99, 4096);
+        assert_eq!((missing.text, missing.kind), (None, FileKind::Other));
+
+        // A client that can't read the file gets no text, though the service could.
+        let denied = with_caller(Some(Arc::new(NoAccess)), || preview(1, 4096));
+        assert_eq!((denied.text, denied.kind), (None, FileKind::Text));
+    }
+
+    #[test]
+    fn same_frn_on_two_volumes_is_not_merged() {
+        let (c, d) = (DocKey::from_parts(1, 42), DocKey::from_parts(2, 42));
+        let merged = merge_hits(
+            vec![hit(c, 1.0, None), hit(d, 0.5, None)],
+            vec![hit(c, 2.0, Some("body")), hit(d, 0.25, Some("other"))],
+        );
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].stable_id(), (1, 42));
+        assert_eq!(
+            (merged[0].score, merged[0].snippet.as_deref()),
+            (2.0, Some("body"))
+        );
+        assert_eq!(merged[1].stable_id(), (2, 42));
+        assert_eq!(
+            (merged[1].score, merged[1].snippet.as_deref()),
+            (0.5, Some("other"))
+        );
+
+        let dir = tempdir().unwrap();
+        let mut on_d = file(42, "report.txt");
+        (on_d.key, on_d.volume) = (d, 2);
+        on_d.path = Some(r"D:\docs\report.txt".into());
+        let handler = handler_with(dir.path(), [file(42, "report.txt"), on_d]);
+        let mut ids: Vec<_> = handler
+            .search(report_query(SearchMode::Hybrid))
+            .hits
+            .iter()
+            .map(SearchHit::stable_id)
+            .collect();
+        ids.sort();
+        assert_eq!(ids, [(1, 42), (2, 42)]);
+    }
+
+    /// Collects formatted log output for assertions.
+    #[derive(Clone, Default)]
+    struct LogBuf(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogBuf {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn slow_queries_are_logged_and_counted() {
+        let metrics = crate::metrics::ServiceMetrics::new(&Default::default()).unwrap();
+        crate::metrics::set_global_metrics(std::sync::Arc::new(metrics));
+        let slow_total = || crate::metrics::with_global_metrics(|m| m.slow_queries.get()).unwrap();
+
+        let dir = tempdir().unwrap();
+        let mut handler = handler_with(dir.path(), [file(1, "report.txt")]);
+        let logs = LogBuf::default();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer({
+                let logs = logs.clone();
+                move || logs.clone()
+            })
+            .with_ansi(false)
+            .finish();
+        let log_text = || String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+
+        let before = slow_total();
+        tracing::subscriber::with_default(subscriber, || {
+            handler.search(report_query(SearchMode::NameOnly));
+            assert!(
+                !log_text().contains("slow query"),
+                "default threshold is 1s"
+            );
+
+            handler.slow_query = Some(Duration::from_nanos(1));
+            // Another limit, so the response is not served from the cache.
+            handler.search(report_query(SearchMode::NameOnly).with_limit(1));
+        });
+        let line = log_text();
+        assert!(line.contains("slow query"), "{line}");
+        assert!(
+            line.contains("mode=NameOnly") && line.contains("hits=1 total=1 elapsed_ms="),
+            "{line}"
+        );
+        assert!(slow_total() > before);
+    }
+
+    #[test]
+    fn dataset_mode_appends_one_anonymized_record_per_search() {
+        let dir = tempdir().unwrap();
+        let log_path = dir.path().join(crate::query_log::QUERY_LOG_FILE);
+        let mut cfg = AppConfig::default();
+        cfg.app.data_dir = dir.path().to_string_lossy().to_string();
+        let search = |cfg: &AppConfig| {
+            let meta = meta_index::create_in_ram().unwrap();
+            let handler = UnifiedSearchHandler::from_indexes(meta, None, cfg).unwrap();
+            handler.search(report_query(SearchMode::NameOnly));
+        };
+
+        search(&cfg);
+        assert!(!log_path.exists(), "off by default");
+
+        cfg.features.log_dataset_mode = true;
+        search(&cfg);
+        let log = std::fs::read_to_string(&log_path).unwrap();
+        let lines: Vec<&str> = log.lines().collect();
+        assert_eq!(lines.len(), 1, "{log}");
+        let record: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(record["mode"], "NameOnly");
+        assert_eq!(record["hit_count"], 0);
+        assert!(record["latency_ms"].is_u64());
+        assert!(record["query"].is_object());
+        assert!(!lines[0].contains("report"), "term text must not be logged");
+    }
+
+    #[test]
+    fn config_reload_toggles_the_query_log() {
+        let dir = tempdir().unwrap();
+        let log_path = dir.path().join(crate::query_log::QUERY_LOG_FILE);
+        let mut cfg = AppConfig::default();
+        cfg.app.data_dir = dir.path().to_string_lossy().to_string();
+        let meta = meta_index::create_in_ram().unwrap();
+        let handler = UnifiedSearchHandler::from_indexes(meta, None, &cfg).unwrap();
+        let lines = || {
+            std::fs::read_to_string(&log_path)
+                .map(|log| log.lines().count())
+                .unwrap_or(0)
+        };
+        // Distinct limits, so no search is served from the cache.
+        let search = |limit| {
+            handler.search(report_query(SearchMode::NameOnly).with_limit(limit));
+        };
+
+        search(1);
+        assert_eq!(lines(), 0);
+        cfg.features.log_dataset_mode = true;
+        handler.apply_config(&cfg);
+        search(2);
+        assert_eq!(lines(), 1);
+        assert!(
+            dir.path()
+                .join(crate::query_log::QUERY_LOG_SALT_FILE)
+                .exists()
+        );
+        cfg.features.log_dataset_mode = false;
+        handler.apply_config(&cfg);
+        search(3);
+        assert_eq!(lines(), 1);
+    }
+
+    #[test]
+    fn expired_timeout_returns_flagged_partial_response() {
+        let dir = tempdir().unwrap();
+        let handler = seeded_handler(dir.path());
+
+        for mode in [SearchMode::NameOnly, SearchMode::Hybrid] {
+            let started = Instant::now();
+            let resp = handler.search(report_query(mode).with_timeout_ms(0));
+            assert!(resp.timed_out && resp.truncated, "mode {mode:?}");
+            assert!(resp.next_cursor.is_none());
+            assert!((resp.hits.len() as u64) < DOCS);
+            assert!(started.elapsed() < std::time::Duration::from_secs(5));
+        }
+
+        let resp = handler.search(report_query(SearchMode::NameOnly).with_timeout_ms(60_000));
+        assert!(!resp.timed_out && !resp.truncated);
+        assert_eq!(resp.hits.len(), 64);
+    }
+
+    #[test]
+    fn field_projection_returns_only_requested_fields() {
+        let dir = tempdir().unwrap();
+        let handler = seeded_handler(dir.path());
+        let full = handler.search(report_query(SearchMode::NameOnly));
+        let paths =
+            handler.search(report_query(SearchMode::NameOnly).with_fields([FieldKind::Path]));
+
+        assert_eq!(paths.hits.len(), full.hits.len());
+        for (hit, all) in paths.hits.iter().zip(&full.hits) {
+            assert_eq!((hit.key, hit.score), (all.key, all.score));
+            assert_eq!(hit.path, all.path);
+            assert!(hit.path.is_some());
+            assert!(hit.name.is_none() && hit.ext.is_none());
+            assert!(hit.size.is_none() && hit.modified.is_none());
+        }
+
+        let wire = |resp: &SearchResponse| bincode::serialize(resp).unwrap().len();
+        assert!(
+            wire(&paths) < wire(&full),
+            "{} >= {}",
+            wire(&paths),
+            wire(&full)
+        );
+
+        // Hybrid merges content hits into meta hits; the projection holds for both.
+        let hybrid =
+            handler.search(report_query(SearchMode::Hybrid).with_fields([FieldKind::Name]));
+        assert!(
+            hybrid
+                .hits
+                .iter()
+                .all(|h| h.name.is_some() && h.path.is_none())
+        );
+    }
+
+    #[test]
+    fn served_by_is_set_and_survives_ipc_roundtrip() {
+        let dir = tempdir().unwrap();
+        let handler = handler_with(dir.path(), [file(1, "report.txt")]);
+
+        let resp = handler.search(report_query(SearchMode::NameOnly));
+        let label = resp.served_by.clone().expect("served_by set");
+        assert!(!label.is_empty());
+        assert_eq!(label, served_by());
+
+        // Same encoding the named-pipe server uses.
+        let framed = ipc::framing::encode_frame(&bincode::serialize(&resp).unwrap()).unwrap();
+        let (payload, rest) = ipc::framing::decode_frame(&framed).unwrap();
+        assert!(rest.is_empty());
+        let back: SearchResponse = bincode::deserialize(&payload).unwrap();
+        assert_eq!(back.id, resp.id);
+        assert_eq!(back.served_by.as_deref(), Some(label.as_str()));
+    }
+
+    fn name_search(
+        handler: &UnifiedSearchHandler,
+        value: &str,
+        modifier: TermModifier,
+    ) -> Vec<String> {
+        let req = SearchRequest::with_query(QueryExpr::Term(TermExpr {
+            field: Some(FieldKind::Name),
+            value: value.into(),
+            modifier,
+            boost: None,
+        }))
+        .with_mode(SearchMode::NameOnly);
+        let mut names: Vec<String> = handler
+            .search(req)
+            .hits
+            .into_iter()
+            .filter_map(|h| h.name)
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn delta_changes_are_searchable_at_once_and_merge_into_one_doc() {
+        let dir = tempdir().unwrap();
+        let meta_path = dir.path().join("meta");
+        let handler = handler_with(dir.path(), [file(1, "draft.txt"), file(2, "budget.xlsx")]);
+        let delta = Arc::new(DeltaIndex::for_main(&handler.meta).unwrap());
+        let handler = handler.with_delta(Arc::clone(&delta));
+        let total = |value: &str| {
+            let mut req = SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                field: Some(FieldKind::Name),
+                value: value.into(),
+                modifier: TermModifier::Term,
+                boost: None,
+            }))
+            .with_mode(SearchMode::NameOnly);
+            req.count_only = true;
+            handler.search(req).total
+        };
+        assert_eq!(
+            name_search(&handler, "draft", TermModifier::Term),
+            ["draft.txt"]
+        );
+
+        let events = [ntfs_watcher::FileEvent::Renamed {
+            from: DocKey::from_parts(1, 1),
+            to: file(1, "final.txt"),
+        }];
+        assert_eq!(
+            crate::meta_ingest::apply_renames_to_delta(&delta, &events).unwrap(),
+            1
+        );
+        delta.apply([], [file(3, "final-notes.txt")]).unwrap();
+
+        // Visible before any merge; the stale main-index copy is hidden.
+        assert_eq!(
+            name_search(&handler, "final", TermModifier::Term),
+            ["final-notes.txt", "final.txt"]
+        );
+        assert!(name_search(&handler, "draft", TermModifier::Term).is_empty());
+        assert_eq!(total("final"), 2);
+        let resp = handler.get_doc(GetDocRequest {
+            id: uuid::Uuid::new_v4(),
+            key: DocKey::from_parts(1, 1),
+            include_content: false,
+        });
+        assert_eq!(resp.meta.unwrap().name.as_deref(), Some("final.txt"));
+
+        assert_eq!(crate::delta_tier::merge_now(&delta, &meta_path).unwrap(), 2);
+        assert!(delta.is_empty());
+        assert_eq!(
+            name_search(&handler, "final", TermModifier::Term),
+            ["final-notes.txt", "final.txt"]
+        );
+        assert_eq!(total("final"), 2);
+        assert_eq!(total("draft"), 0);
+        let main = open_reader(&handler.meta).unwrap().searcher();
+        assert_eq!(
+            main.num_docs(),
+            3,
+            "each file is in the main index exactly once"
+        );
+    }
+
+    #[test]
+    fn browsing_and_completion_see_the_delta_tier() {
+        let dir = tempdir().unwrap();
+        let folder = DocKey::from_parts(1, 10);
+        let mut stale = file(1, "notes_old.txt");
+        stale.parent = Some(folder);
+        let handler = handler_with(dir.path(), [stale]);
+        let delta = Arc::new(DeltaIndex::for_main(&handler.meta).unwrap());
+        let handler = handler.with_delta(Arc::clone(&delta));
+        let child = |frn, name: &str| {
+            let mut meta = file(frn, name);
+            meta.parent = Some(folder);
+            meta
+        };
+        delta
+            .apply(
+                [DocKey::from_parts(1, 1)],
+                [child(2, "report_a.txt"), child(3, "report_b.txt")],
+            )
+            .unwrap();
+
+        let completions = handler
+            .complete(CompleteRequest {
+                id: uuid::Uuid::nil(),
+                prefix: "rep".into(),
+                field: FieldKind::Name,
+                limit: 10,
+            })
+            .completions;
+        assert_eq!(completions, ["report"]);
+
+        let children = handler.children(ChildrenRequest {
+            id: uuid::Uuid::new_v4(),
+            parent: folder,
+            limit: 100,
+            dirs_only: false,
+        });
+        let names: Vec<_> = children
+            .hits
+            .iter()
+            .filter_map(|h| h.name.as_deref())
+            .collect();
+        assert_eq!(
+            (children.total, names),
+            (2, vec!["report_a.txt", "report_b.txt"])
+        );
+
+        let scan = handler.scan(ScanRequest {
+            id: uuid::Uuid::new_v4(),
+            after: None,
+            limit: 2,
+            volume: None,
+        });
+        let keys: Vec<_> = scan.hits.iter().map(|h| h.key).collect();
+        assert_eq!(keys, [DocKey::from_parts(1, 2), DocKey::from_parts(1, 3)]);
+        assert_eq!(scan.next, Some(DocKey::from_parts(1, 3)));
+
+        let grouped =
+            handler.search(report_query(SearchMode::NameOnly).with_group_by(FieldKind::Path));
+        let groups: Vec<(&str, u64)> = grouped
+            .groups
+            .iter()
+            .map(|g| (g.key.as_str(), g.count))
+            .collect();
+        assert_eq!(groups, [(r"C:\docs", 2)]);
+
+        let typo = SearchRequest::with_query(QueryExpr::Term(TermExpr {
+            field: None,
+            value: "reprot".into(),
+            modifier: TermModifier::Term,
+            boost: None,
+        }))
+        .with_mode(SearchMode::NameOnly)
+        .with_suggestions();
+        let resp = handler.search(typo);
+        assert_eq!(resp.total, 0);
+        assert_eq!(resp.suggestions, ["report"]);
+    }
+
+    #[test]
+    fn equal_scores_order_by_doc_key_across_merges() {
+        let dir = tempdir().unwrap();
+        let meta_path = dir.path().join("meta");
+        std::fs::create_dir_all(&meta_path).unwrap();
+        // Larger key first, in its own segment, so index order disagrees with key order.
+        ingest_file_meta_batch(&meta_path, [file(9, "report.txt")], None).unwrap();
+        ingest_file_meta_batch(&meta_path, [file(3, "report.txt")], None).unwrap();
+        let handler =
+            UnifiedSearchHandler::try_new(&meta_path, &dir.path().join("missing-content")).unwrap();
+        let ranked = |mode| {
+            let req = SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                field: Some(FieldKind::Name),
+                value: "report".into(),
+                modifier: TermModifier::Term,
+                boost: None,
+            }))
+            .with_mode(mode);
+            let hits = handler.search(req).hits;
+            assert_eq!(hits.len(), 2);
+            assert_eq!(hits[0].score, hits[1].score);
+            hits.into_iter().map(|h| h.key).collect::<Vec<_>>()
+        };
+        let expected = [DocKey::from_parts(1, 3), DocKey::from_parts(1, 9)];
+        assert_eq!(handler.meta_reader.searcher().segment_readers().len(), 2);
+        for mode in [SearchMode::NameOnly, SearchMode::Hybrid] {
+            assert_eq!(ranked(mode), expected, "{mode:?} before merge");
+        }
+
+        let mut writer = meta_index::create_writer(&handler.meta, &Default::default()).unwrap();
+        let segments = handler.meta.index.searchable_segment_ids().unwrap();
+        writer.merge(&segments).wait().unwrap();
+        writer.wait_merging_threads().unwrap();
+        handler.meta_reader.reload().unwrap();
+        assert_eq!(handler.meta_reader.searcher().segment_readers().len(), 1);
+        for mode in [SearchMode::NameOnly, SearchMode::Hybrid] {
+            assert_eq!(ranked(mode), expected, "{mode:?} after merge");
+        }
+    }
+
+    #[test]
+    fn fuzzy_term_matches_within_distance() {
+        let dir = tempdir().unwrap();
+        let handler = handler_with(dir.path(), [file(1, "report.txt"), file(2, "summary.txt")]);
+
+        assert_eq!(
+            name_search(&handler, "repot", TermModifier::Fuzzy(1)),
+            vec!["report.txt"]
+        );
+        assert!(name_search(&handler, "repot", TermModifier::Term).is_empty());
+    }
+
+    #[test]
+    fn fuzzy_distance_zero_is_exact() {
+        let dir = tempdir().unwrap();
+        let handler = handler_with(dir.path(), [file(1, "report.txt"), file(2, "repot.txt")]);
+
+        assert_eq!(
+            name_search(&handler, "report", TermModifier::Fuzzy(0)),
+            name_search(&handler, "report", TermModifier::Term)
+        );
+        assert_eq!(
+            name_search(&handler, "repot", TermModifier::Fuzzy(0)),
+            vec!["repot.txt"]
+        );
+    }
+
+    #[test]
+    fn explain_names_field_modifier_and_index() {
+        let dir = tempdir().unwrap();
+        let handler = handler_with(dir.path(), [file(1, "report.txt")]);
+        let req = SearchRequest::with_query(QueryExpr::Term(TermExpr {
+            field: Some(FieldKind::Name),
+            value: "repot".into(),
+            modifier: TermModifier::Fuzzy(1),
+            boost: None,
+        }))
+        .with_mode(SearchMode::NameOnly);
+
+        assert!(handler.search(req.clone()).explanation.is_none());
+        let explanation = handler.search(req.with_explain()).explanation.unwrap();
+        assert!(explanation.contains("mode: NameOnly"), "{explanation}");
+        assert!(explanation.contains("Some(Name)"), "{explanation}");
+        assert!(explanation.contains("Fuzzy(1)"), "{explanation}");
+        assert!(explanation.contains("indexes: meta\n"), "{explanation}");
+        assert!(explanation.contains("FuzzyTermQuery"), "{explanation}");
+    }
+
+    #[test]
+    fn auto_runs_the_mode_plan_reports() {
+        let dir = tempdir().unwrap();
+        let handler = handler_with(dir.path(), [file(1, "report.pdf")]);
+        let plan = |query| {
+            handler.plan(PlanRequest {
+                id: uuid::Uuid::new_v4(),
+                query,
+            })
+        };
+        let ext = QueryExpr::Term(TermExpr {
+            field: Some(FieldKind::Ext),
+            value: "pdf".into(),
+            modifier: TermModifier::Term,
+            boost: None,
+        });
+
+        let resp = plan(ext.clone());
+        assert_eq!(resp.chosen_mode, SearchMode::NameOnly, "{:?}", resp.reasons);
+        // Without a content index, ambiguous queries still only consult metadata.
+        let resp = plan(report_query(SearchMode::Auto).query);
+        assert_eq!(resp.chosen_mode, SearchMode::NameOnly);
+        assert!(
+            resp.reasons
+                .last()
+                .unwrap()
+                .contains("content index unavailable"),
+            "{:?}",
+            resp.reasons
+        );
+
+        let explanation = handler
+            .search(SearchRequest::with_query(ext).with_explain())
+            .explanation
+            .unwrap();
+        assert!(explanation.contains("mode: NameOnly"), "{explanation}");
+    }
+
+    #[test]
+    fn content_modes_degrade_to_names_without_content_index() {
+        let dir = tempdir().unwrap();
+        let handler = handler_with(dir.path(), [file(1, "report.txt"), file(2, "notes.md")]);
+
+        for mode in [SearchMode::Content, SearchMode::Hybrid] {
+            let resp = handler.search(report_query(mode));
+            let names: Vec<_> = resp.hits.iter().filter_map(|h| h.name.as_deref()).collect();
+            assert_eq!(names, ["report.txt"], "{mode:?}");
+            let reason = resp.degraded.expect("degradation is reported");
+            assert!(reason.contains("content index unavailable"), "{reason}");
+        }
+        assert!(
+            handler
+                .search(report_query(SearchMode::NameOnly))
+                .degraded
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn zero_hit_name_search_suggests_corrections() {
+        let dir = tempdir().unwrap();
+        let handler = handler_with(
+            dir.path(),
+            [
+                file(1, "report.txt"),
+                file(2, "reports.md"),
+                file(3, "notes.txt"),
+            ],
+        );
+        let typo = || {
+            SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                field: None,
+                value: "reprot".into(),
+                modifier: TermModifier::Term,
+                boost: None,
+            }))
+            .with_mode(SearchMode::NameOnly)
+        };
+
+        let plain = handler.search(typo());
+        assert_eq!(plain.total, 0);
+        assert!(plain.suggestions.is_empty(), "off unless requested");
+
+        let resp = handler.search(typo().with_suggestions());
+        assert_eq!(resp.total, 0);
+        assert_eq!(resp.suggestions, ["report", "reports"]);
+
+        let found = handler.search(report_query(SearchMode::NameOnly).with_suggestions());
+        assert!(found.total > 0);
+        assert!(
+            found.suggestions.is_empty(),
+            "only empty results get suggestions"
+        );
+    }
+
+    #[test]
+    fn completes_name_and_ext_prefixes() {
+        let dir = tempdir().unwrap();
+        let handler = handler_with(
+            dir.path(),
+            [
+                file(1, "report.txt"),
+                file(2, "Report.pdf"),
+                file(3, "repository.md"),
+                file(4, "replay.txt"),
+                file(5, "notes.txt"),
+            ],
+        );
+        let complete = |prefix: &str, field, limit| {
+            handler
+                .complete(CompleteRequest {
+                    id: uuid::Uuid::nil(),
+                    prefix: prefix.into(),
+                    field,
+                    limit,
+                })
+                .completions
+        };
+
+        // Most frequent first, then lexical.
+        assert_eq!(
+            complete("rep", FieldKind::Name, 10),
+            ["report", "replay", "repository"]
+        );
+        assert_eq!(complete("annual REP", FieldKind::Name, 1), ["report"]);
+        assert_eq!(complete(".T", FieldKind::Ext, 10), ["txt"]);
+        assert!(complete("", FieldKind::Name, 10).is_empty());
+        assert!(complete("rep", FieldKind::Content, 10).is_empty());
+    }
+
+    #[test]
+    fn explain_scores_top_content_hit() {
+        let dir = tempdir().unwrap();
+        let meta_path = dir.path().join("meta");
+        let content_path = dir.path().join("content");
+        std::fs::create_dir_all(&meta_path).unwrap();
+        std::fs::create_dir_all(&content_path).unwrap();
+        let content = content_index::open_or_create(&content_path).unwrap();
+        let mut writer =
+            content_index::create_writer(&content, &content_index::WriterConfig::default())
+                .unwrap();
+        let doc = content_index::ContentDoc {
+            key: DocKey::from_parts(1, 5),
+            volume: 1,
+            name: Some("notes.txt".into()),
+            path: Some("C:\\docs\\notes.txt".into()),
+            ext: Some("txt".into()),
+            size: 32,
+            modified: 0,
+            content_lang: None,
+            content: "quarterly revenue summary".into(),
+        };
+        content_index::upsert_content_doc(&mut writer, &content.fields, &doc).unwrap();
+        writer.commit().unwrap();
+        let handler = UnifiedSearchHandler::try_new(&meta_path, &content_path).unwrap();
+
+        let req = SearchRequest::with_query(QueryExpr::Term(TermExpr {
+            field: Some(FieldKind::Content),
+            value: "revenue".into(),
+            modifier: TermModifier::Term,
+            boost: None,
+        }))
+        .with_mode(SearchMode::Content)
+        .with_explain();
+        let resp = handler.search(req);
+        assert_eq!(resp.hits.len(), 1);
+        let explanation = resp.explanation.unwrap();
+        assert!(explanation.contains("Some(Content)"), "{explanation}");
+        assert!(explanation.contains("indexes: content"), "{explanation}");
+        assert!(explanation.contains("content query:"), "{explanation}");
+        assert!(
+            explanation.contains("top content hit (score"),
+            "{explanation}"
+        );
+    }
+
+    #[test]
+    fn get_doc_fetches_meta_and_content_by_key() {
+        let dir = tempdir().unwrap();
+        let meta_path = dir.path().join("meta");
+        let content_path = dir.path().join("content");
+        std::fs::create_dir_all(&meta_path).unwrap();
+        std::fs::create_dir_all(&content_path).unwrap();
+        ingest_file_meta_batch(&meta_path, [file(5, "notes.txt")], None).unwrap();
+        let content = content_index::open_or_create(&content_path).unwrap();
+        let mut writer =
+            content_index::create_writer(&content, &content_index::WriterConfig::default())
+                .unwrap();
+        let doc = content_index::ContentDoc {
+            key: DocKey::from_parts(1, 5),
+            volume: 1,
+            name: Some("notes.txt".into()),
+            path: Some("C:\\docs\\notes.txt".into()),
+            ext: Some("txt".into()),
+            size: 32,
+            modified: 0,
+            content_lang: None,
+            content: "quarterly revenue summary".into(),
+        };
+        content_index::upsert_content_doc(&mut writer, &content.fields, &doc).unwrap();
+        writer.commit().unwrap();
+        let handler = UnifiedSearchHandler::try_new(&meta_path, &content_path).unwrap();
+        let get = |key, include_content| {
+            handler.get_doc(GetDocRequest {
+                id: uuid::Uuid::new_v4(),
+                key,
+                include_content,
+            })
+        };
+
+        let resp = get(DocKey::from_parts(1, 5), true);
+        let meta = resp.meta.unwrap();
+        assert_eq!(meta.key, DocKey::from_parts(1, 5));
+        assert_eq!(meta.name.as_deref(), Some("notes.txt"));
+        assert_eq!(resp.content.as_deref(), Some("quarterly revenue summary"));
+
+        assert!(get(DocKey::from_parts(1, 5), false).content.is_none());
+
+        // A client that can't read the file still sees its metadata, but not its text.
+        let denied = with_caller(Some(Arc::new(NoAccess)), || {
+            get(DocKey::from_parts(1, 5), true)
+        });
+        assert!(denied.meta.is_some());
+        assert!(denied.content.is_none());
+
+        let missing = get(DocKey::from_parts(1, 6), true);
+        assert!(missing.meta.is_none());
+        assert!(missing.content.is_none());
+    }
+
+    #[test]
+    fn snippets_are_cached_per_doc_and_query_until_a_commit() {
+        let dir = tempdir().unwrap();
+        let meta_path = dir.path().join("meta");
+        let content_path = dir.path().join("content");
+        std::fs::create_dir_all(&meta_path).unwrap();
+        std::fs::create_dir_all(&content_path).unwrap();
+        let content = content_index::open_or_create(&content_path).unwrap();
+        let mut writer =
+            content_index::create_writer(&content, &content_index::WriterConfig::default())
+                .unwrap();
+        let mut doc = content_index::ContentDoc {
+            key: DocKey::from_parts(1, 5),
+            volume: 1,
+            name: Some("notes.txt".into()),
+            path: Some("C:\\docs\\notes.txt".into()),
+            ext: Some("txt".into()),
+            size: 32,
+            modified: 0,
+            content_lang: None,
+            content: "quarterly revenue summary".into(),
+        };
+        content_index::upsert_content_doc(&mut writer, &content.fields, &doc).unwrap();
+        writer.commit().unwrap();
+        let mut handler = UnifiedSearchHandler::try_new(&meta_path, &content_path).unwrap();
+        let snippet = |handler: &mut UnifiedSearchHandler| {
+            // Each search misses the response cache, as paging to another offset would.
+            handler.cache = QueryCache::default();
+            let req = SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                field: Some(FieldKind::Content),
+                value: "revenue".into(),
+                modifier: TermModifier::Term,
+                boost: None,
+            }))
+            .with_mode(SearchMode::Content);
+            handler.search(req).hits[0].snippet.clone().unwrap()
+        };
+
+        assert!(snippet(&mut handler).contains("quarterly revenue"));
+        assert!(snippet(&mut handler).contains("quarterly revenue"));
+        assert_eq!(
+            (handler.snippets.generated(), handler.snippets.hits()),
+            (1, 1)
+        );
+
+        doc.content = "annual revenue forecast".into();
+        content_index::upsert_content_doc(&mut writer, &content.fields, &doc).unwrap();
+        writer.commit().unwrap();
+        handler.content.as_ref().unwrap().1.reload().unwrap();
+        let fresh = snippet(&mut handler);
+        assert!(fresh.contains("annual revenue"), "{fresh}");
+        assert_eq!(
+            handler.snippets.generated(),
+            2,
+            "commit must invalidate the entry"
+        );
+    }
+
+    /// Content hits for `query` on a one-doc content index created with `search`.
+    fn stemmed_content_hits(search: &core_types::config::SearchSection, query: &str) -> usize {
+        let dir = tempdir().unwrap();
+        let meta_path = dir.path().join("meta");
+        let content_path = dir.path().join("content");
+        std::fs::create_dir_all(&meta_path).unwrap();
+        std::fs::create_dir_all(&content_path).unwrap();
+        ingest_file_meta_batch(&meta_path, [file(5, "log.txt")], None).unwrap();
+        let content = content_index::open_or_create_with(&content_path, search).unwrap();
+        let mut writer =
+            content_index::create_writer(&content, &content_index::WriterConfig::default())
+                .unwrap();
+        let doc = content_index::ContentDoc {
+            key: DocKey::from_parts(1, 5),
+            volume: 1,
+            name: Some("log.txt".into()),
+            path: Some("C:\\docs\\log.txt".into()),
+            ext: Some("txt".into()),
+            size: 16,
+            modified: 0,
+            content_lang: None,
+            content: "kept running".into(),
+        };
+        content_index::upsert_content_doc(&mut writer, &content.fields, &doc).unwrap();
+        writer.commit().unwrap();
+        drop(writer);
+        let handler = UnifiedSearchHandler::try_new(&meta_path, &content_path).unwrap();
+
+        let req = SearchRequest::with_query(QueryExpr::Term(TermExpr {
+            field: Some(FieldKind::Content),
+            value: query.into(),
+            modifier: TermModifier::Term,
+            boost: None,
+        }))
+        .with_mode(SearchMode::Content);
+        handler.search(req).hits.len()
+    }
+
+    #[test]
+    fn english_stemming_matches_inflected_content() {
+        use core_types::config::{ContentStemming, SearchSection};
+
+        let stemmed = SearchSection {
+            content_stemming: ContentStemming::En,
+            ..SearchSection::default()
+        };
+        assert_eq!(stemmed_content_hits(&stemmed, "run"), 1);
+        assert_eq!(stemmed_content_hits(&stemmed, "running"), 1);
+
+        let plain = SearchSection::default();
+        assert_eq!(stemmed_content_hits(&plain, "run"), 0);
+        assert_eq!(stemmed_content_hits(&plain, "running"), 1);
+    }
+
+    #[test]
+    fn rename_changes_the_name_and_keeps_the_content_doc() {
+        let dir = tempdir().unwrap();
+        let meta_path = dir.path().join("meta");
+        let content_path = dir.path().join("content");
+        std::fs::create_dir_all(&meta_path).unwrap();
+        std::fs::create_dir_all(&content_path).unwrap();
+        ingest_file_meta_batch(
+            &meta_path,
+            [file(5, "notes.txt"), file(6, "other.txt")],
+            None,
+        )
+        .unwrap();
+        let content = content_index::open_or_create(&content_path).unwrap();
+        let mut writer =
+            content_index::create_writer(&content, &content_index::WriterConfig::default())
+                .unwrap();
+        let doc = content_index::ContentDoc {
+            key: DocKey::from_parts(1, 5),
+            volume: 1,
+            name: Some("notes.txt".into()),
+            path: Some("C:\\docs\\notes.txt".into()),
+            ext: Some("txt".into()),
+            size: 32,
+            modified: 0,
+            content_lang: None,
+            content: "quarterly revenue summary".into(),
+        };
+        content_index::upsert_content_doc(&mut writer, &content.fields, &doc).unwrap();
+        writer.commit().unwrap();
+        drop(writer);
+
+        let events = [ntfs_watcher::FileEvent::Renamed {
+            from: DocKey::from_parts(1, 5),
+            to: file(5, "minutes.md"),
+        }];
+        assert_eq!(
+            crate::meta_ingest::apply_renames(&meta_path, &events).unwrap(),
+            1
+        );
+        let handler = UnifiedSearchHandler::try_new(&meta_path, &content_path).unwrap();
+
+        assert_eq!(
+            name_search(&handler, "minutes", TermModifier::Term),
+            ["minutes.md"]
+        );
+        assert!(name_search(&handler, "notes", TermModifier::Term).is_empty());
+        let resp = handler.get_doc(GetDocRequest {
+            id: uuid::Uuid::new_v4(),
+            key: DocKey::from_parts(1, 5),
+            include_content: true,
+        });
+        let meta = resp.meta.unwrap();
+        assert_eq!(meta.name.as_deref(), Some("minutes.md"));
+        assert_eq!(meta.ext.as_deref(), Some("md"));
+        assert_eq!(resp.content.as_deref(), Some("quarterly revenue summary"));
+
+        let req = SearchRequest::with_query(QueryExpr::Term(TermExpr {
+            field: Some(FieldKind::Content),
+            value: "revenue".into(),
+            modifier: TermModifier::Term,
+            boost: None,
+        }))
+        .with_mode(SearchMode::Content);
+        let hits = handler.search(req).hits;
+        assert_eq!(
+            hits.iter().map(|h| h.key).collect::<Vec<_>>(),
+            [DocKey::from_parts(1, 5)]
+        );
+    }
+
+    fn stamped(frn: u64, name: &str, size: u64, modified: i64) -> FileMeta {
+        FileMeta {
+            size,
+            modified,
+            ..file(frn, name)
+        }
+    }
+
+    fn range_names(handler: &UnifiedSearchHandler, range: RangeExpr) -> Vec<String> {
+        let req =
+            SearchRequest::with_query(QueryExpr::Range(range)).with_mode(SearchMode::NameOnly);
+        let mut names: Vec<String> = handler
+            .search(req)
+            .hits
+            .into_iter()
+            .filter_map(|h| h.name)
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn size_range_selects_large_docs() {
+        let dir = tempdir().unwrap();
+        let handler = handler_with(
+            dir.path(),
+            [
+                stamped(1, "tiny.txt", 10, 0),
+                stamped(2, "edge.bin", 1000, 0),
+                stamped(3, "large.iso", 5000, 0),
+            ],
+        );
+        let range = RangeExpr {
+            field: FieldKind::Size,
+            op: RangeOp::Ge,
+            value: RangeValue::U64 { lo: 1000, hi: None },
+        };
+        assert_eq!(range_names(&handler, range), ["edge.bin", "large.iso"]);
+    }
+
+    #[test]
+    fn modified_between_selects_middle_doc() {
+        let dir = tempdir().unwrap();
+        let handler = handler_with(
+            dir.path(),
+            [
+                stamped(1, "old.txt", 0, 1_600_000_000),
+                stamped(2, "mid.txt", 0, 1_650_000_000),
+                stamped(3, "new.txt", 0, 1_700_000_000),
+            ],
+        );
+        let between = |hi| RangeExpr {
+            field: FieldKind::Modified,
+            op: RangeOp::Between,
+            value: RangeValue::I64 {
+                lo: 1_620_000_000,
+                hi,
+            },
+        };
+        assert_eq!(
+            range_names(&handler, between(Some(1_680_000_000))),
+            ["mid.txt"]
+        );
+        // A Between without `hi` is rejected rather than treated as open-ended.
+        let open = SearchRequest::with_query(QueryExpr::Range(between(None)))
+            .with_mode(SearchMode::NameOnly);
+        let err = handler.try_search(open).unwrap_err();
+        assert!(format!("{err:#}").contains("no upper bound"), "{err:#}");
+    }
+
+    #[test]
+    fn malformed_range_is_an_error_not_an_empty_page() {
+        let dir = tempdir().unwrap();
+        let handler = handler_with(dir.path(), [stamped(1, "edge.bin", 1000, 0)]);
+        let reversed = SearchRequest::with_query(QueryExpr::Range(RangeExpr {
+            field: FieldKind::Size,
+            op: RangeOp::Between,
+            value: RangeValue::U64 {
+                lo: 5000,
+                hi: Some(10),
+            },
+        }));
+        for req in [
+            reversed.clone().with_mode(SearchMode::NameOnly),
+            reversed.clone().with_mode(SearchMode::Hybrid),
+            SearchRequest {
+                count_only: true,
+                ..reversed.clone()
+            },
+        ] {
+            let err = handler.try_search(req).unwrap_err();
+            assert!(format!("{err:#}").contains("greater than hi"), "{err:#}");
+        }
+        // Callers without an error channel get an empty page.
+        let id = reversed.id;
+        let page = handler.search(reversed.with_mode(SearchMode::NameOnly));
+        assert_eq!((page.id, page.total), (id, 0));
+    }
+
+    #[test]
+    fn empty_query_sorted_by_modified_returns_newest_first() {
+        let dir = tempdir().unwrap();
+        let handler = handler_with(
+            dir.path(),
+            [
+                stamped(1, "old.txt", 0, 1_600_000_000),
+                stamped(2, "new.txt", 0, 1_700_000_000),
+                stamped(3, "mid.txt", 0, 1_650_000_000),
+            ],
+        );
+        let recent = |offset| {
+            let req = SearchRequest::default()
+                .with_sort(FieldKind::Modified, true)
+                .with_limit(2)
+                .with_offset(offset);
+            handler.search(req)
+        };
+
+        let first = recent(0);
+        assert_eq!(first.total, 3);
+        assert!(first.next_cursor.is_none());
+        let names: Vec<_> = first.hits.into_iter().filter_map(|h| h.name).collect();
+        assert_eq!(names, ["new.txt", "mid.txt"]);
+
+        let names: Vec<_> = recent(2).hits.into_iter().filter_map(|h| h.name).collect();
+        assert_eq!(names, ["old.txt"]);
+    }
+
+    #[test]
+    fn case_sensitive_override_uses_cased_names() {
+        let dir = tempdir().unwrap();
+        let handler = handler_with(dir.path(), [file(1, "Report.txt"), file(2, "report.md")]);
+        let query = |value: &str| {
+            SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                field: Some(FieldKind::Name),
+                value: value.into(),
+                modifier: TermModifier::Term,
+                boost: None,
+            }))
+            .with_mode(SearchMode::NameOnly)
+        };
+        let names = |req: SearchRequest| {
+            let mut names: Vec<_> = handler
+                .search(req)
+                .hits
+                .into_iter()
+                .filter_map(|h| h.name)
+                .collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(names(query("Report")), ["Report.txt", "report.md"]);
+        assert_eq!(names(query("Report").with_case_sensitive()), ["Report.txt"]);
+        assert_eq!(names(query("report").with_case_sensitive()), ["report.md"]);
+    }
+
+    #[test]
+    fn substring_uses_ngram_field_when_enabled() {
+        let names = [
+            file(1, "report.txt"),
+            file(2, "passport.pdf"),
+            file(3, "notes.md"),
+        ];
+
+        let plain = tempdir().unwrap();
+        let handler = handler_with(plain.path(), names.clone());
+        assert!(name_search(&handler, "port", TermModifier::Substring).is_empty());
+
+        let dir = tempdir().unwrap();
+        let meta_path = dir.path().join("meta");
+        std::fs::create_dir_all(&meta_path).unwrap();
+        meta_index::open_or_create_index_with(
+            &meta_path,
+            &core_types::config::SearchSection {
+                name_ngrams: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let handler = handler_with(dir.path(), names);
+        assert_eq!(
+            name_search(&handler, "port", TermModifier::Substring),
+            vec!["passport.pdf", "report.txt"]
+        );
+        // Longer than the largest gram: every 5-gram window must match.
+        assert_eq!(
+            name_search(&handler, "ssport", TermModifier::Substring),
+            vec!["passport.pdf"]
+        );
+        assert!(name_search(&handler, "port", TermModifier::Term).is_empty());
+    }
+
+    #[test]
+    fn folded_index_matches_accents_for_term_and_fuzzy() {
+        let dir = tempdir().unwrap();
+        let meta_path = dir.path().join("meta");
+        std::fs::create_dir_all(&meta_path).unwrap();
+        meta_index::open_or_create_index_with(
+            &meta_path,
+            &core_types::config::SearchSection {
+                name_analyzer: core_types::config::NameAnalyzer::AsciiFolded,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let handler = handler_with(dir.path(), [file(1, "resumé.docx"), file(2, "notes.txt")]);
+
+        assert_eq!(
+            name_search(&handler, "resume", TermModifier::Term),
+            vec!["resumé.docx"]
+        );
+        assert_eq!(
+            name_search(&handler, "RÉSUMÉ", TermModifier::Term),
+            vec!["resumé.docx"]
+        );
+        assert_eq!(
+            name_search(&handler, "resumè", TermModifier::Fuzzy(1)),
+            vec!["resumé.docx"]
+        );
+    }
+}
@@ -0,0 +1,155 @@
+//! LRU cache of content snippets (`search.snippet_cache_size`).
+//!
+//! Paging through content results, or the UI re-rendering a page, asks for the same document's
+//! snippet under the same query again and again, and each one means loading the stored text and
+//! re-running the highlighter. Snippets are cached per `(DocKey, query terms)` and stamped with
+//! the content searcher generation they were built at: once a commit reloads the reader, older
+//! entries no longer match and are regenerated on next use.
+
+use core_types::DocKey;
+use lru::LruCache;
+use parking_lot::Mutex;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tantivy::Term;
+use tantivy::query::Query;
+
+/// Snippets kept before the least recently used one is evicted.
+pub const DEFAULT_CAPACITY: usize = 1024;
+
+struct Entry {
+    generation: u64,
+    snippet: Option<String>,
+}
+
+/// Snippets by `(doc, query terms hash)`; a capacity of 0 disables caching.
+pub struct SnippetCache {
+    entries: Option<Mutex<LruCache<(DocKey, u64), Entry>>>,
+    generated: AtomicU64,
+    hits: AtomicU64,
+}
+
+impl Default for SnippetCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl SnippetCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: NonZeroUsize::new(capacity).map(|cap| Mutex::new(LruCache::new(cap))),
+            generated: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+        }
+    }
+
+    /// The snippet of `key` for the query hashed to `terms`, calling `make` unless one built at
+    /// `generation` is cached. `None` (no highlightable text) is cached like any snippet.
+    pub fn get_or_insert_with(
+        &self,
+        key: DocKey,
+        terms: u64,
+        generation: u64,
+        make: impl FnOnce() -> Option<String>,
+    ) -> Option<String> {
+        if let Some(entries) = &self.entries
+            && let Some(entry) = entries.lock().get(&(key, terms))
+            && entry.generation == generation
+        {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return entry.snippet.clone();
+        }
+
+        // Built without the lock held; a concurrent miss for the same doc just builds it twice.
+        // A stale entry is replaced below.
+        let snippet = make();
+        self.generated.fetch_add(1, Ordering::Relaxed);
+        if let Some(entries) = &self.entries {
+            entries.lock().put(
+                (key, terms),
+                Entry {
+                    generation,
+                    snippet: snippet.clone(),
+                },
+            );
+        }
+        snippet
+    }
+
+    /// Snippets built so far, cached or not.
+    pub fn generated(&self) -> u64 {
+        self.generated.load(Ordering::Relaxed)
+    }
+
+    /// Lookups answered from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries
+            .as_ref()
+            .map_or(0, |entries| entries.lock().len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop every cached snippet.
+    pub fn clear(&self) {
+        if let Some(entries) = &self.entries {
+            entries.lock().clear();
+        }
+    }
+}
+
+/// Hash of the terms `query` highlights, independent of how the query combines them, so
+/// `a AND b` and `b a` share snippets.
+pub fn terms_hash(query: &dyn Query) -> u64 {
+    let mut terms: Vec<Term> = Vec::new();
+    query.query_terms(&mut |term, _| terms.push(term.clone()));
+    terms.sort();
+    terms.dedup();
+    let mut hasher = DefaultHasher::new();
+    terms.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generation_change_rebuilds_and_zero_capacity_never_caches() {
+        let cache = SnippetCache::new(4);
+        let key = DocKey::from_parts(1, 5);
+        let build = |text: &str| Some(text.to_string());
+
+        assert_eq!(
+            cache.get_or_insert_with(key, 7, 1, || build("old")),
+            build("old")
+        );
+        assert_eq!(
+            cache.get_or_insert_with(key, 7, 1, || build("unused")),
+            build("old")
+        );
+        assert_eq!((cache.generated(), cache.hits()), (1, 1));
+
+        // Other query terms, or a newer generation, miss.
+        cache.get_or_insert_with(key, 8, 1, || build("other"));
+        assert_eq!(
+            cache.get_or_insert_with(key, 7, 2, || build("new")),
+            build("new")
+        );
+        assert_eq!((cache.generated(), cache.hits(), cache.len()), (3, 1, 2));
+
+        let off = SnippetCache::new(0);
+        off.get_or_insert_with(key, 7, 1, || build("a"));
+        off.get_or_insert_with(key, 7, 1, || build("a"));
+        assert_eq!((off.generated(), off.hits(), off.len()), (2, 0, 0));
+    }
+}
@@ -1,5 +1,5 @@
-use ipc::{MetricsSnapshot, StatusResponse, VolumeStatus};
-use std::{env, time::SystemTime};
+use ipc::{MetricsSnapshot, Readiness, StatusResponse, VolumeStatus};
+use std::{env, sync::OnceLock, time::SystemTime};
 
 /// Build a StatusResponse from provided fragments.
 ///
@@ -15,6 +15,8 @@ pub fn make_status_response(
     content_jobs_remaining: Option<u64>,
     content_bytes_total: Option<u64>,
     content_bytes_remaining: Option<u64>,
+    readiness: Readiness,
+    queue_depth_by_category: Option<[(String, u64); 3]>,
 ) -> StatusResponse {
     StatusResponse {
         id,
@@ -26,7 +28,9 @@ pub fn make_status_response(
         content_bytes_total,
         content_bytes_remaining,
         metrics,
-        served_by: Some(host_label()),
+        served_by: Some(served_by()),
+        readiness,
+        queue_depth_by_category,
     }
 }
 
@@ -37,10 +41,18 @@ fn now_ts() -> Option<i64> {
         .map(|d| d.as_secs() as i64)
 }
 
-fn host_label() -> String {
-    env::var("COMPUTERNAME")
-        .or_else(|_| env::var("HOSTNAME"))
-        .unwrap_or_else(|_| "service".into())
+/// Identifies this service instance in responses as `<host>:<pid>`, so clients and logs can
+/// tell which process answered.
+pub fn served_by() -> String {
+    static LABEL: OnceLock<String> = OnceLock::new();
+    LABEL
+        .get_or_init(|| {
+            let host = env::var("COMPUTERNAME")
+                .or_else(|_| env::var("HOSTNAME"))
+                .unwrap_or_else(|_| "service".into());
+            format!("{host}:{}", std::process::id())
+        })
+        .clone()
 }
 
 #[cfg(test)]
@@ -60,8 +72,19 @@ mod tests {
             None,
             None,
             None,
+            Readiness::Ready,
+            None,
         );
         assert!(resp.last_index_commit_ts.is_some());
-        assert!(resp.served_by.is_some());
+        assert_eq!(resp.readiness, Readiness::Ready);
+        assert_eq!(resp.served_by, Some(served_by()));
+    }
+
+    #[test]
+    fn served_by_names_host_and_pid() {
+        let label = served_by();
+        let (host, pid) = label.rsplit_once(':').expect("host:pid");
+        assert!(!host.is_empty());
+        assert_eq!(pid, std::process::id().to_string());
     }
 }
@@ -1,5 +1,7 @@
-use crate::metrics::global_metrics_snapshot;
-use ipc::{MetricsSnapshot, VolumeStatus};
+use crate::metrics::{global_metrics_snapshot, with_global_metrics};
+use ipc::{MetricsSnapshot, Readiness, VolumeStatus};
+use scheduler::{DiskSpace, depth_by_category};
+use std::collections::HashMap;
 use std::sync::{Arc, OnceLock, RwLock};
 
 /// Snapshot of service status used by IPC responses.
@@ -13,6 +15,41 @@ pub struct StatusSnapshot {
     pub content_jobs_remaining: Option<u64>,
     pub content_bytes_total: Option<u64>,
     pub content_bytes_remaining: Option<u64>,
+    pub readiness: Readiness,
+    pub queue_depth_by_category: Option<[(String, u64); 3]>,
+}
+
+/// Open state of one index as seen by [`ReadinessTracker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IndexState {
+    #[default]
+    Pending,
+    Open,
+    Failed,
+}
+
+/// Derives [`Readiness`] from index opens and initial scan progress.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReadinessTracker {
+    pub meta_index: IndexState,
+    pub content_index: IndexState,
+    pub initial_scan_done: bool,
+}
+
+impl ReadinessTracker {
+    /// A failed index wins over everything else: the service will not recover on its own.
+    pub fn readiness(&self) -> Readiness {
+        let indexes = [self.meta_index, self.content_index];
+        if indexes.contains(&IndexState::Failed) {
+            Readiness::Degraded
+        } else if indexes.contains(&IndexState::Pending) {
+            Readiness::Starting
+        } else if !self.initial_scan_done {
+            Readiness::Indexing
+        } else {
+            Readiness::Ready
+        }
+    }
 }
 
 pub trait StatusProvider: Send + Sync {
@@ -50,6 +87,8 @@ pub fn status_snapshot() -> StatusSnapshot {
         content_jobs_remaining: None,
         content_bytes_total: None,
         content_bytes_remaining: None,
+        readiness: Readiness::Starting,
+        queue_depth_by_category: None,
     }
 }
 
@@ -60,6 +99,18 @@ pub fn update_status_volumes(volumes: Vec<VolumeStatus>) {
     }
 }
 
+pub fn update_status_volume_cursor(volume: u16, last_usn: u64, journal_id: u64) {
+    if let Some(p) = BASIC_PROVIDER.get() {
+        p.update_volume_cursor(volume, last_usn, journal_id);
+    }
+}
+
+pub fn update_status_volume_space(space: &HashMap<u16, DiskSpace>) {
+    if let Some(p) = BASIC_PROVIDER.get() {
+        p.update_volume_space(space);
+    }
+}
+
 pub fn update_status_scheduler_state(state: impl Into<String>) {
     if let Some(p) = BASIC_PROVIDER.get() {
         p.update_scheduler_state(state);
@@ -90,6 +141,13 @@ pub fn update_status_queue_state(
     }
 }
 
+/// Record the scheduler's `(critical, metadata, content)` queue lengths.
+pub fn update_status_queue_by_category(counts: (usize, usize, usize)) {
+    if let Some(p) = BASIC_PROVIDER.get() {
+        p.update_queue_by_category(counts);
+    }
+}
+
 pub fn update_status_last_commit(ts: Option<i64>) {
     if let Some(p) = BASIC_PROVIDER.get() {
         p.update_last_index_commit(ts);
@@ -114,11 +172,24 @@ pub fn update_content_remaining(queue_depth: u64, active_workers: u32) {
     }
 }
 
+pub fn update_status_index_states(meta: IndexState, content: IndexState) {
+    if let Some(p) = BASIC_PROVIDER.get() {
+        p.update_index_states(meta, content);
+    }
+}
+
+pub fn mark_initial_scan_complete() {
+    if let Some(p) = BASIC_PROVIDER.get() {
+        p.mark_initial_scan_complete();
+    }
+}
+
 /// Basic in-memory status provider that other modules can update.
 #[derive(Debug, Default)]
 pub struct BasicStatusProvider {
     state: RwLock<StatusSnapshot>,
     avg_content_job_bytes: RwLock<Option<u64>>,
+    readiness: RwLock<ReadinessTracker>,
 }
 
 impl BasicStatusProvider {
@@ -133,8 +204,24 @@ impl BasicStatusProvider {
                 content_jobs_remaining: None,
                 content_bytes_total: None,
                 content_bytes_remaining: None,
+                readiness: Readiness::Starting,
+                queue_depth_by_category: None,
             }),
             avg_content_job_bytes: RwLock::new(None),
+            readiness: RwLock::new(ReadinessTracker::default()),
+        }
+    }
+
+    pub fn update_index_states(&self, meta: IndexState, content: IndexState) {
+        if let Ok(mut guard) = self.readiness.write() {
+            guard.meta_index = meta;
+            guard.content_index = content;
+        }
+    }
+
+    pub fn mark_initial_scan_complete(&self) {
+        if let Ok(mut guard) = self.readiness.write() {
+            guard.initial_scan_done = true;
         }
     }
 
@@ -144,6 +231,44 @@ impl BasicStatusProvider {
         }
     }
 
+    /// Record the journal position the index has committed up to for `volume`, adding the
+    /// volume if no scan has reported it yet.
+    pub fn update_volume_cursor(&self, volume: u16, last_usn: u64, journal_id: u64) {
+        if let Ok(mut guard) = self.state.write() {
+            let entry = match guard.volumes.iter().position(|v| v.volume == volume) {
+                Some(i) => &mut guard.volumes[i],
+                None => {
+                    guard.volumes.push(VolumeStatus {
+                        volume,
+                        indexed_files: 0,
+                        indexed_bytes: 0,
+                        pending_files: 0,
+                        pending_bytes: 0,
+                        last_usn: None,
+                        journal_id: None,
+                        free_bytes: None,
+                        total_bytes: None,
+                    });
+                    guard.volumes.last_mut().expect("just pushed")
+                }
+            };
+            entry.last_usn = Some(last_usn);
+            entry.journal_id = Some(journal_id);
+        }
+    }
+
+    /// Fill in sampled free/total bytes; volumes without a sample keep their last values.
+    pub fn update_volume_space(&self, space: &HashMap<u16, DiskSpace>) {
+        if let Ok(mut guard) = self.state.write() {
+            for volume in &mut guard.volumes {
+                if let Some(s) = space.get(&volume.volume) {
+                    volume.free_bytes = Some(s.free_bytes);
+                    volume.total_bytes = Some(s.total_bytes);
+                }
+            }
+        }
+    }
+
     pub fn update_scheduler_state(&self, state: impl Into<String>) {
         if let Ok(mut guard) = self.state.write() {
             guard.scheduler_state = state.into();
@@ -173,15 +298,29 @@ impl BasicStatusProvider {
                 active_workers: None,
                 content_enqueued: None,
                 content_dropped: None,
+                extract_p50_ms: None,
+                extract_p95_ms: None,
+                extract_by_kind: Vec::new(),
             });
             snap.queue_depth = queue_depth;
             snap.active_workers = active_workers;
             snap.content_enqueued = content_enqueued;
             snap.content_dropped = content_dropped;
+            if let Some((p50, p95, by_kind)) = with_global_metrics(|m| m.extract_summary()) {
+                snap.extract_p50_ms = p50;
+                snap.extract_p95_ms = p95;
+                snap.extract_by_kind = by_kind;
+            }
             guard.metrics = Some(snap);
         }
     }
 
+    pub fn update_queue_by_category(&self, counts: (usize, usize, usize)) {
+        if let Ok(mut guard) = self.state.write() {
+            guard.queue_depth_by_category = Some(depth_by_category(counts));
+        }
+    }
+
     pub fn update_last_index_commit(&self, ts: Option<i64>) {
         if let Ok(mut guard) = self.state.write() {
             guard.last_index_commit_ts = ts;
@@ -251,9 +390,23 @@ impl BasicStatusProvider {
 
 impl StatusProvider for BasicStatusProvider {
     fn snapshot(&self) -> StatusSnapshot {
+        let readiness = self
+            .readiness
+            .read()
+            .map(|r| r.readiness())
+            .unwrap_or(Readiness::Degraded);
+        // The scheduler rewrites its state every tick; a running reindex is layered on top.
+        let reindex = crate::reindex::active_reindex();
         self.state
             .read()
-            .map(|s| s.clone())
+            .map(|s| StatusSnapshot {
+                readiness,
+                scheduler_state: match &reindex {
+                    Some(r) => format!("{r}; {}", s.scheduler_state),
+                    None => s.scheduler_state.clone(),
+                },
+                ..s.clone()
+            })
             .unwrap_or_else(|_| StatusSnapshot {
                 volumes: Vec::new(),
                 scheduler_state: "initializing".into(),
@@ -263,6 +416,8 @@ impl StatusProvider for BasicStatusProvider {
                 content_jobs_remaining: None,
                 content_bytes_total: None,
                 content_bytes_remaining: None,
+                readiness,
+                queue_depth_by_category: None,
             })
     }
 }
@@ -283,6 +438,22 @@ mod tests {
         assert_eq!(metrics.content_dropped, Some(1));
     }
 
+    #[test]
+    fn queue_by_category_sums_to_the_queue_depth() {
+        let provider = BasicStatusProvider::new();
+        assert_eq!(provider.snapshot().queue_depth_by_category, None);
+        provider.update_queue_state(Some(9), Some(0), None, None);
+        provider.update_queue_by_category((2, 3, 4));
+
+        let snap = provider.snapshot();
+        let depths = snap.queue_depth_by_category.unwrap();
+        assert_eq!(depths[2], ("content".to_string(), 4));
+        assert_eq!(
+            depths.iter().map(|(_, n)| n).sum::<u64>(),
+            snap.metrics.unwrap().queue_depth.unwrap()
+        );
+    }
+
     #[test]
     fn update_metrics_none_does_not_clear_queue_state() {
         let provider = init_basic_status_provider();
@@ -293,4 +464,40 @@ mod tests {
         assert_eq!(metrics.queue_depth, Some(3));
         assert_eq!(metrics.active_workers, Some(1));
     }
+
+    #[test]
+    fn readiness_walks_starting_indexing_ready() {
+        let provider = BasicStatusProvider::new();
+        assert_eq!(provider.snapshot().readiness, Readiness::Starting);
+
+        // Scan finishing first is not enough while indexes are still opening.
+        provider.mark_initial_scan_complete();
+        assert_eq!(provider.snapshot().readiness, Readiness::Starting);
+
+        let provider = BasicStatusProvider::new();
+        provider.update_index_states(IndexState::Open, IndexState::Pending);
+        assert_eq!(provider.snapshot().readiness, Readiness::Starting);
+        provider.update_index_states(IndexState::Open, IndexState::Open);
+        assert_eq!(provider.snapshot().readiness, Readiness::Indexing);
+        provider.mark_initial_scan_complete();
+        assert_eq!(provider.snapshot().readiness, Readiness::Ready);
+    }
+
+    #[test]
+    fn failed_index_degrades_in_any_phase() {
+        for (meta, content) in [
+            (IndexState::Failed, IndexState::Pending),
+            (IndexState::Failed, IndexState::Open),
+            (IndexState::Open, IndexState::Failed),
+        ] {
+            for initial_scan_done in [false, true] {
+                let tracker = ReadinessTracker {
+                    meta_index: meta,
+                    content_index: content,
+                    initial_scan_done,
+                };
+                assert_eq!(tracker.readiness(), Readiness::Degraded, "{tracker:?}");
+            }
+        }
+    }
 }
@@ -0,0 +1,208 @@
+//! Term-dictionary lookups for the search box: "did you mean" corrections for name searches
+//! that found nothing, and as-you-type prefix completions.
+//!
+//! For corrections, each word of the query that is missing from the `name` dictionary is
+//! compared against the indexed terms by edit distance (adjacent transpositions count once, so
+//! `reprot` is one edit from `report`). Closer terms win, then terms used by more files.
+
+use crate::planner::{analyzed_tokens, fuzzy_distance};
+use ipc::{FieldKind, QueryExpr, TermModifier};
+use std::collections::HashMap;
+use tantivy::schema::Field;
+use tantivy::{Index, Searcher};
+
+/// Corrections returned per response.
+pub const MAX_SUGGESTIONS: usize = 3;
+
+/// Dictionary terms examined per word across all segments, so a typo against a large volume
+/// still answers quickly.
+const MAX_SCANNED_TERMS: usize = 250_000;
+
+/// Cap on `CompleteRequest::limit`.
+pub const MAX_COMPLETIONS: u32 = 50;
+
+/// Terms examined per segment when completing a prefix; keeps short prefixes like `a` cheap.
+const MAX_COMPLETION_SCAN: usize = 10_000;
+
+/// Indexed terms of `field` starting with the last word of `prefix`, most frequent first.
+pub fn prefix_completions(
+    searchers: &[&Searcher],
+    index: &Index,
+    field: Field,
+    prefix: &str,
+    limit: u32,
+) -> Vec<String> {
+    let Some(stem) = analyzed_tokens(index, field, prefix)
+        .pop()
+        .filter(|s| !s.is_empty())
+    else {
+        return Vec::new();
+    };
+    let mut freqs: HashMap<String, u32> = HashMap::new();
+    for segment in searchers.iter().flat_map(|s| s.segment_readers()) {
+        let Ok(inverted) = segment.inverted_index(field) else {
+            continue;
+        };
+        let Ok(mut stream) = inverted.terms().range().ge(stem.as_bytes()).into_stream() else {
+            continue;
+        };
+        let mut scanned = 0;
+        while scanned < MAX_COMPLETION_SCAN && stream.advance() {
+            scanned += 1;
+            let Ok(term) = std::str::from_utf8(stream.key()) else {
+                continue;
+            };
+            // Terms are sorted, so the first one past the prefix ends the run.
+            if !term.starts_with(stem.as_str()) {
+                break;
+            }
+            *freqs.entry(term.to_string()).or_default() += stream.value().doc_freq;
+        }
+    }
+
+    let mut ranked: Vec<(String, u32)> = freqs.into_iter().collect();
+    ranked.sort_by(|(a, fa), (b, fb)| fb.cmp(fa).then(a.cmp(b)));
+    ranked
+        .into_iter()
+        .take(limit.clamp(1, MAX_COMPLETIONS) as usize)
+        .map(|(term, _)| term)
+        .collect()
+}
+
+/// Up to [`MAX_SUGGESTIONS`] indexed name terms close to the query's name words.
+pub fn name_suggestions(
+    searchers: &[&Searcher],
+    index: &Index,
+    name: Field,
+    query: &QueryExpr,
+) -> Vec<String> {
+    let mut words = Vec::new();
+    name_words(query, false, &mut words);
+
+    // term -> (distance, doc_freq summed over segments)
+    let mut candidates: HashMap<String, (u8, u32)> = HashMap::new();
+    for word in words {
+        for token in analyzed_tokens(index, name, &word) {
+            let max = fuzzy_distance(&token, u8::MAX);
+            if max == 0 {
+                continue;
+            }
+            let found = close_terms(searchers, name, &token, max);
+            // A word already in the dictionary is spelled fine; other clauses emptied the result.
+            if found.contains_key(&token) {
+                continue;
+            }
+            for (term, (distance, freq)) in found {
+                let entry = candidates.entry(term).or_insert((distance, 0));
+                entry.0 = entry.0.min(distance);
+                entry.1 += freq;
+            }
+        }
+    }
+
+    let mut ranked: Vec<(String, (u8, u32))> = candidates.into_iter().collect();
+    ranked.sort_by(|(a, (da, fa)), (b, (db, fb))| da.cmp(db).then(fb.cmp(fa)).then(a.cmp(b)));
+    ranked
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(term, _)| term)
+        .collect()
+}
+
+/// Positive, unqualified or `name:` term values; negated words are not worth correcting.
+fn name_words(expr: &QueryExpr, negated: bool, out: &mut Vec<String>) {
+    match expr {
+        QueryExpr::Term(t)
+            if !negated
+                && matches!(t.field, None | Some(FieldKind::Name))
+                && matches!(t.modifier, TermModifier::Term | TermModifier::Fuzzy(_)) =>
+        {
+            out.push(t.value.clone());
+        }
+        QueryExpr::Not(inner) => name_words(inner, !negated, out),
+        QueryExpr::And(subs) | QueryExpr::Or(subs) => {
+            subs.iter().for_each(|sub| name_words(sub, negated, out))
+        }
+        _ => {}
+    }
+}
+
+/// Terms of `field` within `max` edits of `token`, with their doc frequency.
+fn close_terms(
+    searchers: &[&Searcher],
+    field: Field,
+    token: &str,
+    max: u8,
+) -> HashMap<String, (u8, u32)> {
+    let target: Vec<char> = token.chars().collect();
+    let mut found = HashMap::new();
+    let mut scanned = 0;
+    for segment in searchers.iter().flat_map(|s| s.segment_readers()) {
+        let Ok(inverted) = segment.inverted_index(field) else {
+            continue;
+        };
+        let Ok(mut stream) = inverted.terms().stream() else {
+            continue;
+        };
+        while stream.advance() {
+            scanned += 1;
+            if scanned > MAX_SCANNED_TERMS {
+                return found;
+            }
+            let Ok(term) = std::str::from_utf8(stream.key()) else {
+                continue;
+            };
+            if let Some(distance) = edit_distance(&target, term, max) {
+                let entry = found.entry(term.to_string()).or_insert((distance, 0));
+                entry.1 += stream.value().doc_freq;
+            }
+        }
+    }
+    found
+}
+
+/// Optimal-string-alignment distance between `a` and `b`, or `None` once it exceeds `max`.
+fn edit_distance(a: &[char], b: &str, max: u8) -> Option<u8> {
+    let b: Vec<char> = b.chars().collect();
+    let max = max as usize;
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    let mut before: Vec<usize> = Vec::new();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (prev[j] + 1).min(row[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(before[j - 2] + 1);
+            }
+        }
+        if row.iter().min().is_some_and(|&m| m > max) {
+            return None;
+        }
+        before = std::mem::replace(&mut prev, row);
+    }
+    let distance = prev[b.len()];
+    (distance <= max).then_some(distance as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn distance(a: &str, b: &str) -> Option<u8> {
+        edit_distance(&a.chars().collect::<Vec<_>>(), b, 2)
+    }
+
+    #[test]
+    fn transpositions_cost_one_edit() {
+        assert_eq!(distance("reprot", "report"), Some(1));
+        assert_eq!(distance("repot", "report"), Some(1));
+        assert_eq!(distance("report", "report"), Some(0));
+        assert_eq!(distance("rpeort", "report"), Some(1));
+        assert_eq!(distance("rpt", "report"), None);
+        assert_eq!(distance("notes", "report"), None);
+    }
+}
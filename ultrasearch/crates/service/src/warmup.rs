@@ -0,0 +1,164 @@
+//! Startup preload of recent searches (`search.preload_history`).
+//!
+//! The first query after a start pays for cold readers: term dictionaries and postings are
+//! paged in from disk on demand. Re-running the queries the UI last saw, before the handler is
+//! installed, moves that cost out of the user's first search. Responses are thrown away.
+
+use crate::search_handler::SearchHandler;
+use core_types::config::AppConfig;
+use ipc::SearchRequest;
+use std::path::Path;
+use std::time::Instant;
+
+/// Run the newest `search.preload_queries` entries of the UI history against `handler`.
+/// Returns how many ran; a missing or unreadable history runs none.
+pub fn preload_from_history(handler: &dyn SearchHandler, cfg: &AppConfig) -> usize {
+    let Some(path) = cfg.search_history_path() else {
+        return 0;
+    };
+    let queries = load_history(&path, cfg.search.preload_queries);
+    preload(handler, &queries)
+}
+
+/// Up to `limit` queries from a history file, newest first.
+pub fn load_history(path: &Path, limit: usize) -> Vec<String> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(_) => return Vec::new(),
+    };
+    match serde_json::from_slice::<Vec<String>>(&bytes) {
+        Ok(mut queries) => {
+            queries.truncate(limit);
+            queries
+        }
+        Err(e) => {
+            tracing::warn!(
+                "skipping query preload, bad history {}: {e}",
+                path.display()
+            );
+            Vec::new()
+        }
+    }
+}
+
+/// Search each of `queries` once, as the UI would have sent it. Queries that no longer parse
+/// are skipped. Returns how many ran.
+pub fn preload(handler: &dyn SearchHandler, queries: &[String]) -> usize {
+    let started = Instant::now();
+    let mut ran = 0;
+    for text in queries {
+        let query = match ipc::query::parse_query(text) {
+            Ok(query) => query,
+            Err(e) => {
+                tracing::debug!("preload: skipping {text:?}: {e}");
+                continue;
+            }
+        };
+        let mut req = SearchRequest::with_query(query);
+        req.id = uuid::Uuid::new_v4();
+        handler.search(req);
+        ran += 1;
+    }
+    if ran > 0 {
+        tracing::info!(
+            "preloaded {ran} recent queries in {} ms",
+            started.elapsed().as_millis()
+        );
+    }
+    ran
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search_handler::UnifiedSearchHandler;
+    use core_types::{DocKey, FileFlags, FileMeta};
+    use ipc::SearchResponse;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Passes searches through, counting them.
+    struct Counting<H> {
+        inner: H,
+        searches: AtomicUsize,
+    }
+
+    impl<H: SearchHandler> SearchHandler for Counting<H> {
+        fn try_search(&self, req: SearchRequest) -> anyhow::Result<SearchResponse> {
+            self.searches.fetch_add(1, Ordering::SeqCst);
+            self.inner.try_search(req)
+        }
+    }
+
+    fn handler() -> UnifiedSearchHandler {
+        let cfg = AppConfig::default();
+        let meta = meta_index::create_in_ram_with(&cfg.search).unwrap();
+        let mut writer =
+            meta_index::create_writer(&meta, &meta_index::WriterConfig::default()).unwrap();
+        let files: Vec<FileMeta> = ["report.pdf", "notes.txt"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                FileMeta::new(
+                    DocKey::from_parts(1, i as u64 + 1),
+                    1,
+                    None,
+                    name.to_string(),
+                    Some(format!("C:\\docs\\{name}")),
+                    1,
+                    0,
+                    0,
+                    FileFlags::empty(),
+                )
+            })
+            .collect();
+        meta_index::add_file_meta_batch(&mut writer, &meta.fields, files).unwrap();
+        writer.commit().unwrap();
+        drop(writer);
+        UnifiedSearchHandler::from_indexes(meta, None, &cfg).unwrap()
+    }
+
+    fn names(handler: &dyn SearchHandler, query: &str) -> Vec<Option<String>> {
+        let req = SearchRequest::with_query(ipc::query::parse_query(query).unwrap());
+        handler
+            .search(req)
+            .hits
+            .into_iter()
+            .map(|h| h.name)
+            .collect()
+    }
+
+    #[test]
+    fn preload_runs_the_stored_queries_without_changing_results() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.json");
+        std::fs::write(&path, r#"["report", "notes", "ext:pdf"]"#).unwrap();
+
+        let handler = Counting {
+            inner: handler(),
+            searches: AtomicUsize::new(0),
+        };
+        let before = names(&handler, "report");
+
+        let queries = load_history(&path, 2);
+        assert_eq!(queries, ["report", "notes"]);
+        assert_eq!(preload(&handler, &queries), 2);
+        assert_eq!(handler.searches.load(Ordering::SeqCst), 3);
+
+        assert_eq!(names(&handler, "report"), before);
+        assert_eq!(before, [Some("report.pdf".to_string())]);
+    }
+
+    #[test]
+    fn missing_history_and_unparsable_queries_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(load_history(&dir.path().join("missing.json"), 20).is_empty());
+
+        let handler = Counting {
+            inner: crate::search_handler::StubSearchHandler,
+            searches: AtomicUsize::new(0),
+        };
+        let queries = ["(unclosed".to_string(), "ok".to_string()];
+        assert_eq!(preload(&handler, &queries), 1);
+        assert_eq!(handler.searches.load(Ordering::SeqCst), 1);
+    }
+}
@@ -1,2 +1,3 @@
 pub mod management;
+pub mod pipe_security;
 pub mod service_main;
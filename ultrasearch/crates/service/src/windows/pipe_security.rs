@@ -0,0 +1,147 @@
+//! Security descriptor of the service's named pipe (`ipc.pipe_access`).
+//!
+//! Without an explicit descriptor a pipe created by a LocalSystem service only admits SYSTEM,
+//! administrators and the creator, so an unelevated UI can't connect. The descriptors below
+//! grant that explicitly:
+//!
+//! | `pipe_access` | SDDL |
+//! |---|---|
+//! | `interactive` | `D:(A;;GA;;;SY)(A;;GA;;;BA)(A;;0x12019b;;;IU)` |
+//! | `admins_only` | `D:(A;;GA;;;SY)(A;;GA;;;BA)` |
+//!
+//! `SY` is LocalSystem, `BA` the built-in Administrators and `IU` anyone logged on
+//! interactively. `0x12019b` is generic read and write minus `FILE_CREATE_PIPE_INSTANCE`, so a
+//! user can talk to the service but can't create a competing instance of its pipe. Remote
+//! clients are rejected regardless, by `PIPE_REJECT_REMOTE_CLIENTS`.
+
+use anyhow::Result;
+use core_types::config::PipeAccess;
+use tokio::net::windows::named_pipe::NamedPipeServer;
+
+/// SDDL for the pipe's DACL under `access`.
+pub fn pipe_sddl(access: PipeAccess) -> &'static str {
+    match access {
+        PipeAccess::Interactive => "D:(A;;GA;;;SY)(A;;GA;;;BA)(A;;0x12019b;;;IU)",
+        PipeAccess::AdminsOnly => "D:(A;;GA;;;SY)(A;;GA;;;BA)",
+    }
+}
+
+/// Create one instance of pipe `name` with the descriptor for `access`. `first` claims the
+/// name, failing if another process already serves it.
+pub fn create_secure_pipe(name: &str, first: bool, access: PipeAccess) -> Result<NamedPipeServer> {
+    use std::ffi::OsStr;
+    use std::os::windows::ffi::OsStrExt;
+    use windows::Win32::Foundation::{HLOCAL, INVALID_HANDLE_VALUE, LocalFree};
+    use windows::Win32::Security::{
+        Authorization::ConvertStringSecurityDescriptorToSecurityDescriptorW, PSECURITY_DESCRIPTOR,
+        SECURITY_ATTRIBUTES,
+    };
+    use windows::Win32::Storage::FileSystem::{
+        FILE_FLAG_FIRST_PIPE_INSTANCE, FILE_FLAG_OVERLAPPED, FILE_FLAGS_AND_ATTRIBUTES,
+        PIPE_ACCESS_DUPLEX,
+    };
+    use windows::Win32::System::Pipes::{
+        CreateNamedPipeW, NAMED_PIPE_MODE, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS,
+        PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+    };
+    use windows::core::PCWSTR;
+
+    let sddl_wide: Vec<u16> = pipe_sddl(access)
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+
+    let mut sd: PSECURITY_DESCRIPTOR = PSECURITY_DESCRIPTOR::default();
+
+    unsafe {
+        ConvertStringSecurityDescriptorToSecurityDescriptorW(
+            PCWSTR(sddl_wide.as_ptr()),
+            1, // SDDL_REVISION_1
+            &mut sd,
+            None,
+        )?;
+    }
+
+    // Ensure we free the SD
+    struct SdGuard(PSECURITY_DESCRIPTOR);
+    impl Drop for SdGuard {
+        fn drop(&mut self) {
+            // sd.0 is *mut c_void. HLOCAL wraps *mut c_void.
+            unsafe {
+                let _ = LocalFree(HLOCAL(self.0.0));
+            }
+        }
+    }
+    let _guard = SdGuard(sd);
+
+    let sa = SECURITY_ATTRIBUTES {
+        nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+        lpSecurityDescriptor: sd.0 as *mut _,
+        bInheritHandle: windows::Win32::Foundation::FALSE,
+    };
+
+    let mut name_wide: Vec<u16> = OsStr::new(name).encode_wide().collect();
+    name_wide.push(0);
+
+    let mut open_mode = PIPE_ACCESS_DUPLEX.0 | FILE_FLAG_OVERLAPPED.0;
+    if first {
+        open_mode |= FILE_FLAG_FIRST_PIPE_INSTANCE.0;
+    }
+
+    let handle = unsafe {
+        CreateNamedPipeW(
+            PCWSTR(name_wide.as_ptr()),
+            FILE_FLAGS_AND_ATTRIBUTES(open_mode),
+            NAMED_PIPE_MODE(
+                PIPE_TYPE_BYTE.0
+                    | PIPE_READMODE_BYTE.0
+                    | PIPE_WAIT.0
+                    | PIPE_REJECT_REMOTE_CLIENTS.0,
+            ),
+            PIPE_UNLIMITED_INSTANCES,
+            65536,
+            65536,
+            0,
+            Some(&sa),
+        )
+    };
+
+    if handle == INVALID_HANDLE_VALUE {
+        return Err(anyhow::Error::from(windows::core::Error::from_win32()));
+    }
+
+    // Wrap in Tokio
+    let server = unsafe { NamedPipeServer::from_raw_handle(handle.0 as *mut _) }?;
+    Ok(server)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::windows::named_pipe::ClientOptions;
+
+    fn unique_pipe() -> String {
+        format!(r"\\.\pipe\ultrasearch-acl-test-{}", uuid::Uuid::new_v4())
+    }
+
+    /// Test runs are not elevated, so this is the UI's situation: a normal user connecting to
+    /// a pipe carrying the `interactive` descriptor.
+    #[tokio::test]
+    async fn unelevated_client_can_open_the_interactive_pipe() {
+        let name = unique_pipe();
+        let server = create_secure_pipe(&name, true, PipeAccess::Interactive).unwrap();
+        let connected = tokio::spawn(async move { server.connect().await });
+
+        let client = ClientOptions::new().open(&name);
+        assert!(client.is_ok(), "open failed: {:?}", client.err());
+        connected.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn both_descriptors_are_valid_sddl() {
+        for access in [PipeAccess::Interactive, PipeAccess::AdminsOnly] {
+            create_secure_pipe(&unique_pipe(), true, access)
+                .unwrap_or_else(|e| panic!("{access:?}: {e}"));
+        }
+    }
+}
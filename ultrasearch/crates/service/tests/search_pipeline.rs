@@ -0,0 +1,366 @@
+//! Query AST -> results through the real planner and `UnifiedSearchHandler`, on in-memory
+//! indexes. Nothing here touches a pipe, the filesystem or the live config, so the search
+//! pipeline is covered on every platform.
+
+use content_index::ContentDoc;
+use core_types::config::AppConfig;
+use core_types::{DocKey, FileFlags, FileMeta, Timestamp};
+use ipc::{
+    FieldKind, QueryExpr, RangeExpr, RangeOp, RangeValue, ScanRequest, ScanResponse, SearchMode,
+    SearchRequest, SearchResponse, TermExpr, TermModifier,
+};
+use service::{SearchHandler, UnifiedSearchHandler};
+
+/// Seeded indexes plus the handler that searches them.
+struct TestWorld {
+    handler: UnifiedSearchHandler,
+}
+
+#[derive(Default)]
+struct TestWorldBuilder {
+    config: AppConfig,
+    files: Vec<FileMeta>,
+    texts: Vec<(String, String)>,
+}
+
+impl TestWorld {
+    fn builder() -> TestWorldBuilder {
+        TestWorldBuilder::default()
+    }
+
+    fn search_req(&self, req: SearchRequest) -> SearchResponse {
+        self.handler.search(req)
+    }
+
+    fn scan(&self, after: Option<DocKey>, limit: u32, volume: Option<u16>) -> ScanResponse {
+        self.handler.scan(ScanRequest {
+            id: uuid::Uuid::new_v4(),
+            after,
+            limit,
+            volume,
+        })
+    }
+
+    /// Names of the files `query` finds under `mode`, sorted so ranking doesn't matter.
+    fn names(&self, query: QueryExpr, mode: SearchMode) -> Vec<String> {
+        let resp = self.search_req(SearchRequest::with_query(query).with_mode(mode));
+        let mut names: Vec<String> = resp.hits.into_iter().filter_map(|h| h.name).collect();
+        names.sort();
+        names
+    }
+}
+
+impl TestWorldBuilder {
+    fn config(mut self, edit: impl FnOnce(&mut AppConfig)) -> Self {
+        edit(&mut self.config);
+        self
+    }
+
+    /// A file under `C:\docs`; keys are assigned in insertion order.
+    fn file(self, name: &str, size: u64, modified: Timestamp) -> Self {
+        let key = DocKey::from_parts(1, self.files.len() as u64 + 1);
+        self.keyed(key, name, size, modified)
+    }
+
+    /// Like [`file`](Self::file), under an explicit key (and so volume).
+    fn keyed(mut self, key: DocKey, name: &str, size: u64, modified: Timestamp) -> Self {
+        self.files.push(FileMeta::new(
+            key,
+            key.volume(),
+            None,
+            name.to_string(),
+            Some(format!("C:\\docs\\{name}")),
+            size,
+            modified,
+            modified,
+            FileFlags::empty(),
+        ));
+        self
+    }
+
+    /// Extracted text for the file called `name`, added with [`file`](Self::file).
+    fn text(mut self, name: &str, content: &str) -> Self {
+        self.texts.push((name.to_string(), content.to_string()));
+        self
+    }
+
+    fn build(self) -> TestWorld {
+        let search = &self.config.search;
+        let meta = meta_index::create_in_ram_with(search).unwrap();
+        let mut writer =
+            meta_index::create_writer(&meta, &meta_index::WriterConfig::default()).unwrap();
+        meta_index::add_file_meta_batch(&mut writer, &meta.fields, self.files.clone()).unwrap();
+        writer.commit().unwrap();
+        drop(writer);
+
+        let content = content_index::create_in_ram_with(search).unwrap();
+        let mut writer =
+            content_index::create_writer(&content, &content_index::WriterConfig::default())
+                .unwrap();
+        for (name, text) in &self.texts {
+            let file = self
+                .files
+                .iter()
+                .find(|f| &f.name == name)
+                .unwrap_or_else(|| panic!("text for unknown file {name}"));
+            let doc = ContentDoc {
+                key: file.key,
+                volume: file.volume,
+                name: Some(file.name.clone()),
+                path: file.path.clone(),
+                ext: file.ext.clone(),
+                size: file.size,
+                modified: file.modified,
+                content_lang: None,
+                content: text.clone(),
+            };
+            content_index::add_content_doc(&mut writer, &content.fields, &doc).unwrap();
+        }
+        writer.commit().unwrap();
+        drop(writer);
+
+        let handler =
+            UnifiedSearchHandler::from_indexes(meta, Some(content), &self.config).unwrap();
+        TestWorld { handler }
+    }
+}
+
+fn term(value: &str) -> QueryExpr {
+    field_term(None, value)
+}
+
+fn field_term(field: Option<FieldKind>, value: &str) -> QueryExpr {
+    QueryExpr::Term(TermExpr {
+        field,
+        value: value.into(),
+        modifier: TermModifier::Term,
+        boost: None,
+    })
+}
+
+fn range(field: FieldKind, op: RangeOp, value: RangeValue) -> QueryExpr {
+    QueryExpr::Range(RangeExpr { field, op, value })
+}
+
+fn world() -> TestWorld {
+    TestWorld::builder()
+        .file("report_q1.pdf", 120_000, 1_600_000_000)
+        .file("report_q2.docx", 800, 1_650_000_000)
+        .file("budget.xlsx", 40_000, 1_700_000_000)
+        .file("notes.txt", 300, 1_710_000_000)
+        .text("notes.txt", "quarterly revenue grew in the second quarter")
+        .text("report_q1.pdf", "first quarter revenue and churn")
+        .build()
+}
+
+#[test]
+fn name_terms_match_tokens_of_the_file_name() {
+    let world = world();
+    assert_eq!(
+        world.names(term("report"), SearchMode::NameOnly),
+        ["report_q1.pdf", "report_q2.docx"]
+    );
+    assert_eq!(
+        world.names(
+            field_term(Some(FieldKind::Ext), "xlsx"),
+            SearchMode::NameOnly
+        ),
+        ["budget.xlsx"]
+    );
+    assert!(
+        world
+            .names(term("invoice"), SearchMode::NameOnly)
+            .is_empty()
+    );
+}
+
+#[test]
+fn content_terms_search_extracted_text() {
+    let world = world();
+    let revenue = field_term(Some(FieldKind::Content), "revenue");
+    assert_eq!(
+        world.names(revenue, SearchMode::Content),
+        ["notes.txt", "report_q1.pdf"]
+    );
+    assert_eq!(
+        world.names(
+            field_term(Some(FieldKind::Content), "churn"),
+            SearchMode::Content
+        ),
+        ["report_q1.pdf"]
+    );
+}
+
+#[test]
+fn ranges_filter_on_size_and_modified() {
+    let world = world();
+    let large = range(
+        FieldKind::Size,
+        RangeOp::Ge,
+        RangeValue::U64 {
+            lo: 40_000,
+            hi: None,
+        },
+    );
+    assert_eq!(
+        world.names(large, SearchMode::NameOnly),
+        ["budget.xlsx", "report_q1.pdf"]
+    );
+
+    let recent = range(
+        FieldKind::Modified,
+        RangeOp::Between,
+        RangeValue::I64 {
+            lo: 1_650_000_000,
+            hi: Some(1_700_000_000),
+        },
+    );
+    assert_eq!(
+        world.names(recent, SearchMode::NameOnly),
+        ["budget.xlsx", "report_q2.docx"]
+    );
+}
+
+#[test]
+fn boolean_operators_combine_clauses() {
+    let world = world();
+    let small = range(
+        FieldKind::Size,
+        RangeOp::Lt,
+        RangeValue::U64 {
+            lo: 1_000,
+            hi: None,
+        },
+    );
+    assert_eq!(
+        world.names(
+            QueryExpr::And(vec![term("report"), small.clone()]),
+            SearchMode::NameOnly
+        ),
+        ["report_q2.docx"]
+    );
+    assert_eq!(
+        world.names(
+            QueryExpr::Or(vec![term("budget"), term("notes")]),
+            SearchMode::NameOnly
+        ),
+        ["budget.xlsx", "notes.txt"]
+    );
+    assert_eq!(
+        world.names(
+            QueryExpr::And(vec![small, QueryExpr::Not(Box::new(term("report")))]),
+            SearchMode::NameOnly
+        ),
+        ["notes.txt"]
+    );
+}
+
+#[test]
+fn count_only_reports_totals_without_hits() {
+    let world = world();
+    let resp = world.search_req(
+        SearchRequest::with_query(term("report"))
+            .with_mode(SearchMode::NameOnly)
+            .with_count_only(),
+    );
+    assert_eq!(resp.total, 2);
+    assert!(resp.hits.is_empty());
+}
+
+#[test]
+fn config_shapes_the_indexes() {
+    use core_types::config::NameAnalyzer;
+
+    let world = TestWorld::builder()
+        .config(|cfg| cfg.search.name_analyzer = NameAnalyzer::Exact)
+        .file("Report.txt", 1, 0)
+        .build();
+    let exact = |value| {
+        world.names(
+            field_term(Some(FieldKind::Name), value),
+            SearchMode::NameOnly,
+        )
+    };
+    assert_eq!(exact("Report"), ["Report.txt"]);
+    assert!(exact("report").is_empty());
+}
+
+#[test]
+fn normalized_scores_are_relative_to_the_top_hit() {
+    let world = TestWorld::builder()
+        .file("report.txt", 1, 0)
+        .file("quarterly_report_final_draft.txt", 1, 0)
+        .file("report_q3.md", 1, 0)
+        .build();
+    let req = SearchRequest::with_query(term("report")).with_mode(SearchMode::NameOnly);
+    let raw = world.search_req(req.clone());
+    let normalized = world.search_req(req.with_normalized_scores());
+    assert_eq!(raw.hits.len(), 3);
+    let top = raw.hits.iter().map(|h| h.score).fold(0.0, f32::max);
+    assert!(
+        raw.hits.iter().any(|h| h.score < top),
+        "fixture needs distinct scores"
+    );
+
+    assert_eq!(normalized.hits[0].score, 1.0);
+    for (raw, scaled) in raw.hits.iter().zip(&normalized.hits) {
+        assert_eq!(raw.key, scaled.key, "normalizing keeps the order");
+        assert!((scaled.score - raw.score / top).abs() < 1e-6);
+        assert!(scaled.score > 0.0 && scaled.score <= 1.0);
+    }
+}
+
+#[test]
+fn scan_pages_visit_every_doc_once_in_key_order() {
+    const DOCS: u64 = 1000;
+    // Scatter keys so DocKey order differs from insertion (doc id) order.
+    let world = (0..DOCS)
+        .fold(TestWorld::builder(), |b, i| {
+            let key = DocKey::from_parts(1, (i * 7919) % DOCS + 1);
+            b.keyed(key, &format!("report_{i}.txt"), 0, 0)
+        })
+        .build();
+    let mut seen = Vec::new();
+    let mut after = None;
+    loop {
+        let resp = world.scan(after, 64, None);
+        assert!(resp.hits.len() <= 64);
+        seen.extend(resp.hits.iter().map(|h| h.key));
+        match resp.next {
+            Some(next) => {
+                assert_eq!(Some(next), seen.last().copied());
+                after = Some(next);
+            }
+            None => break,
+        }
+    }
+    assert_eq!(seen.len() as u64, DOCS);
+    assert!(
+        seen.windows(2).all(|w| w[0].0 < w[1].0),
+        "strictly ascending"
+    );
+    assert_eq!(seen.first(), Some(&DocKey::from_parts(1, 1)));
+}
+
+#[test]
+fn scan_can_be_limited_to_one_volume() {
+    let world = TestWorld::builder()
+        .keyed(DocKey::from_parts(1, 5), "a.txt", 0, 0)
+        .keyed(DocKey::from_parts(2, 3), "b.txt", 0, 0)
+        .keyed(DocKey::from_parts(2, 9), "c.txt", 0, 0)
+        .keyed(DocKey::from_parts(3, 1), "d.txt", 0, 0)
+        .build();
+    let keys = |resp: ScanResponse| resp.hits.iter().map(|h| h.key).collect::<Vec<_>>();
+
+    assert_eq!(
+        keys(world.scan(None, 10, Some(2))),
+        [DocKey::from_parts(2, 3), DocKey::from_parts(2, 9)]
+    );
+    assert_eq!(
+        keys(world.scan(Some(DocKey::from_parts(2, 3)), 10, Some(2))),
+        [DocKey::from_parts(2, 9)]
+    );
+    let all = world.scan(Some(DocKey::from_parts(1, 5)), 10, None);
+    assert_eq!(all.next, None, "a short page ends the scan");
+    assert_eq!(keys(all).len(), 3);
+}
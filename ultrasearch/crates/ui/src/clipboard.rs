@@ -0,0 +1,107 @@
+//! "Copy file" on Windows: put files on the clipboard as `CF_HDROP`, the format Explorer
+//! pastes as a file copy.
+//!
+//! The clipboard data is a `DROPFILES` header followed by the file list: each path in UTF-16,
+//! NUL-terminated, with one more NUL closing the list.
+
+use anyhow::{bail, Context, Result};
+use windows::Win32::Foundation::HGLOBAL;
+use windows::Win32::System::Memory::{
+    GlobalAlloc, GlobalFree, GlobalLock, GlobalUnlock, GMEM_MOVEABLE,
+};
+
+/// Bytes in `DROPFILES`: `pFiles` (u32), `pt` (two i32), `fNC` and `fWide` (BOOL each).
+pub const DROPFILES_SIZE: usize = 20;
+
+/// The `CF_HDROP` payload for `paths`: the header, with the list right after it and marked
+/// wide, then the double-NUL-terminated UTF-16 paths.
+pub fn dropfiles_buffer<S: AsRef<str>>(paths: &[S]) -> Vec<u8> {
+    let mut buffer = Vec::with_capacity(DROPFILES_SIZE + 2);
+    buffer.extend_from_slice(&(DROPFILES_SIZE as u32).to_le_bytes()); // pFiles
+    buffer.extend_from_slice(&0i32.to_le_bytes()); // pt.x
+    buffer.extend_from_slice(&0i32.to_le_bytes()); // pt.y
+    buffer.extend_from_slice(&0i32.to_le_bytes()); // fNC
+    buffer.extend_from_slice(&1i32.to_le_bytes()); // fWide
+    for path in paths {
+        for unit in path.as_ref().encode_utf16().chain([0]) {
+            buffer.extend_from_slice(&unit.to_le_bytes());
+        }
+    }
+    buffer.extend_from_slice(&0u16.to_le_bytes());
+    buffer
+}
+
+/// Copy `bytes` into a new movable global memory block, the form clipboard and drag-and-drop
+/// data is handed over in. The caller owns the block until something takes it over.
+pub fn global_copy(bytes: &[u8]) -> Result<HGLOBAL> {
+    unsafe {
+        let global = GlobalAlloc(GMEM_MOVEABLE, bytes.len()).context("GlobalAlloc failed")?;
+        let dest = GlobalLock(global);
+        if dest.is_null() {
+            let _ = GlobalFree(global);
+            bail!("GlobalLock failed");
+        }
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), dest.cast::<u8>(), bytes.len());
+        // Reports an "error" once the lock count reaches zero, which is the expected outcome.
+        let _ = GlobalUnlock(global);
+        Ok(global)
+    }
+}
+
+/// Replace the clipboard contents with `paths` as files.
+pub fn copy_files<S: AsRef<str>>(paths: &[S]) -> Result<()> {
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::System::DataExchange::{
+        CloseClipboard, EmptyClipboard, OpenClipboard, SetClipboardData,
+    };
+    use windows::Win32::System::Ole::CF_HDROP;
+
+    if paths.is_empty() {
+        bail!("no files to copy");
+    }
+    let global = global_copy(&dropfiles_buffer(paths))?;
+    unsafe {
+        if let Err(err) = OpenClipboard(None) {
+            let _ = GlobalFree(global);
+            return Err(err).context("OpenClipboard failed");
+        }
+        let result = EmptyClipboard()
+            .and_then(|()| SetClipboardData(u32::from(CF_HDROP.0), HANDLE(global.0)));
+        let _ = CloseClipboard();
+        // Once set, the clipboard owns the memory; only free it if that failed.
+        if let Err(err) = result {
+            let _ = GlobalFree(global);
+            return Err(err).context("SetClipboardData(CF_HDROP) failed");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dropfiles_buffer_lists_wide_paths_after_the_header() {
+        use windows::Win32::UI::Shell::DROPFILES;
+        assert_eq!(std::mem::size_of::<DROPFILES>(), DROPFILES_SIZE);
+
+        let buffer = dropfiles_buffer(&[r"C:\a.txt", r"D:\Ünïcode\b"]);
+        let word = |at: usize| i32::from_le_bytes(buffer[at..at + 4].try_into().unwrap());
+        assert_eq!(word(0) as usize, DROPFILES_SIZE);
+        assert_eq!((word(4), word(8), word(12), word(16)), (0, 0, 0, 1));
+
+        let units: Vec<u16> = buffer[DROPFILES_SIZE..]
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        let expected: Vec<u16> = r"C:\a.txt"
+            .encode_utf16()
+            .chain([0])
+            .chain(r"D:\Ünïcode\b".encode_utf16())
+            .chain([0, 0])
+            .collect();
+        assert_eq!(units, expected);
+        assert_eq!((buffer.len() - DROPFILES_SIZE) % 2, 0);
+    }
+}
@@ -0,0 +1,106 @@
+//! Dragging results out of the app on Windows: the dragged rows become a shell data object
+//! carrying `CF_HDROP`, which Explorer and most other drop targets take as files.
+//!
+//! The payload is the same `DROPFILES` list "Copy file" puts on the clipboard
+//! ([`crate::clipboard::dropfiles_buffer`]). Drags only offer a copy, so dropping onto a folder
+//! never moves an indexed file away from under the index.
+
+use crate::clipboard::{dropfiles_buffer, global_copy};
+use anyhow::{bail, Context, Result};
+use std::mem::ManuallyDrop;
+use windows::Win32::System::Com::{
+    IDataObject, DVASPECT_CONTENT, FORMATETC, STGMEDIUM, STGMEDIUM_0, TYMED_HGLOBAL,
+};
+use windows::Win32::System::Memory::GlobalFree;
+use windows::Win32::System::Ole::{IDropSource, CF_HDROP, DROPEFFECT_COPY};
+use windows::Win32::UI::Shell::{SHCreateDataObject, SHDoDragDrop};
+
+/// The format the data object offers: a `CF_HDROP` list in global memory.
+pub fn hdrop_format() -> FORMATETC {
+    FORMATETC {
+        cfFormat: CF_HDROP.0,
+        ptd: std::ptr::null_mut(),
+        dwAspect: DVASPECT_CONTENT.0,
+        lindex: -1,
+        tymed: TYMED_HGLOBAL.0 as u32,
+    }
+}
+
+/// A shell data object holding `paths` as `CF_HDROP`.
+pub fn data_object<S: AsRef<str>>(paths: &[S]) -> Result<IDataObject> {
+    if paths.is_empty() {
+        bail!("no files to drag");
+    }
+    let global = global_copy(&dropfiles_buffer(paths))?;
+    unsafe {
+        let data: IDataObject = match SHCreateDataObject(None, None, None::<&IDataObject>) {
+            Ok(data) => data,
+            Err(err) => {
+                let _ = GlobalFree(global);
+                return Err(err).context("SHCreateDataObject failed");
+            }
+        };
+        let medium = STGMEDIUM {
+            tymed: TYMED_HGLOBAL.0 as u32,
+            u: STGMEDIUM_0 { hGlobal: global },
+            pUnkForRelease: ManuallyDrop::new(None),
+        };
+        // Released by the data object from here on; only free it if it was not taken.
+        if let Err(err) = data.SetData(&hdrop_format(), &medium, true) {
+            let _ = GlobalFree(global);
+            return Err(err).context("IDataObject::SetData(CF_HDROP) failed");
+        }
+        Ok(data)
+    }
+}
+
+/// Drag `paths` until the user drops or cancels; returns whether they were copied somewhere.
+/// Blocks in the shell's drag loop, which keeps pumping the window's messages meanwhile.
+pub fn drag_files<S: AsRef<str>>(paths: &[S]) -> Result<bool> {
+    let data = data_object(paths)?;
+    // Without a drop source of our own the shell supplies its default one.
+    let effect = unsafe { SHDoDragDrop(None, &data, None::<&IDropSource>, DROPEFFECT_COPY) }
+        .context("SHDoDragDrop failed")?;
+    Ok(effect.contains(DROPEFFECT_COPY))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use windows::Win32::System::Com::{CoInitializeEx, COINIT_APARTMENTTHREADED};
+    use windows::Win32::System::Memory::{GlobalLock, GlobalSize, GlobalUnlock};
+    use windows::Win32::System::Ole::ReleaseStgMedium;
+    use windows::Win32::UI::Shell::{DragQueryFileW, HDROP};
+
+    #[test]
+    fn data_object_offers_the_paths_as_hdrop() {
+        let format = hdrop_format();
+        assert_eq!(format.cfFormat, CF_HDROP.0);
+        assert_eq!(format.tymed, TYMED_HGLOBAL.0 as u32);
+        assert_eq!(format.lindex, -1);
+        assert!(data_object::<&str>(&[]).is_err());
+
+        let paths = [r"C:\a.txt", r"D:\Ünïcode\b"];
+        unsafe {
+            let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+            let data = data_object(&paths).unwrap();
+            data.QueryGetData(&format).ok().unwrap();
+
+            let mut medium = data.GetData(&format).unwrap();
+            let global = medium.u.hGlobal;
+            let expected = dropfiles_buffer(&paths);
+            assert!(GlobalSize(global) >= expected.len());
+            let bytes = GlobalLock(global).cast::<u8>();
+            assert_eq!(std::slice::from_raw_parts(bytes, expected.len()), expected);
+            let _ = GlobalUnlock(global);
+
+            // The shell reads it back as the same file list.
+            let drop = HDROP(global.0);
+            assert_eq!(DragQueryFileW(drop, u32::MAX, None), 2);
+            let mut name = [0u16; 64];
+            let len = DragQueryFileW(drop, 1, Some(&mut name)) as usize;
+            assert_eq!(String::from_utf16_lossy(&name[..len]), paths[1]);
+            ReleaseStgMedium(&mut medium);
+        }
+    }
+}
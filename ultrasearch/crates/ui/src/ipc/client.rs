@@ -1,7 +1,9 @@
 use anyhow::Result;
+use futures::future::{BoxFuture, FutureExt};
 use ipc::{
-    ReloadConfigRequest, ReloadConfigResponse, RescanRequest, RescanResponse, SearchRequest,
-    SearchResponse, StatusRequest, StatusResponse,
+    CompleteRequest, CompleteResponse, PreviewRequest, PreviewResponse, ReloadConfigRequest,
+    ReloadConfigResponse, RescanRequest, RescanResponse, SearchRequest, SearchResponse,
+    StatusRequest, StatusResponse,
 };
 #[cfg(windows)]
 use std::sync::Arc;
@@ -9,6 +11,17 @@ use std::sync::Arc;
 #[cfg(windows)]
 use ipc::client::PipeClient;
 
+/// The service calls the UI model makes, so tests can swap in a mock for the pipe client.
+pub trait IpcTransport: Send + Sync + 'static {
+    fn search(&self, req: SearchRequest) -> BoxFuture<'static, Result<SearchResponse>>;
+    fn status(&self, req: StatusRequest) -> BoxFuture<'static, Result<StatusResponse>>;
+    fn reload_config(
+        &self,
+        req: ReloadConfigRequest,
+    ) -> BoxFuture<'static, Result<ReloadConfigResponse>>;
+    fn rescan(&self, req: RescanRequest) -> BoxFuture<'static, Result<RescanResponse>>;
+}
+
 /// A handle to the IPC client that can be used from GPUI models.
 #[derive(Clone)]
 pub struct IpcClient {
@@ -36,7 +49,7 @@ impl IpcClient {
     pub async fn search(&self, req: SearchRequest) -> Result<SearchResponse> {
         #[cfg(windows)]
         {
-            self.inner.search(req).await
+            Ok(self.inner.search(req).await?)
         }
         #[cfg(not(windows))]
         {
@@ -48,6 +61,12 @@ impl IpcClient {
                 truncated: false,
                 took_ms: 0,
                 served_by: Some("ui-stub".into()),
+                next_cursor: None,
+                explanation: None,
+                timed_out: false,
+                degraded: None,
+                suggestions: Vec::new(),
+                groups: Vec::new(),
             })
         }
     }
@@ -55,7 +74,7 @@ impl IpcClient {
     pub async fn status(&self, req: StatusRequest) -> Result<StatusResponse> {
         #[cfg(windows)]
         {
-            self.inner.status(req).await
+            Ok(self.inner.status(req).await?)
         }
         #[cfg(not(windows))]
         {
@@ -70,6 +89,40 @@ impl IpcClient {
                 content_bytes_remaining: Some(0),
                 metrics: None,
                 served_by: Some("ui-stub".into()),
+                readiness: ipc::Readiness::Ready,
+                queue_depth_by_category: None,
+            })
+        }
+    }
+
+    /// File text for the preview pane, extracted by the service.
+    pub async fn preview(&self, req: PreviewRequest) -> Result<PreviewResponse> {
+        #[cfg(windows)]
+        {
+            Ok(self.inner.preview(req).await?)
+        }
+        #[cfg(not(windows))]
+        {
+            Ok(PreviewResponse {
+                id: req.id,
+                text: None,
+                truncated: false,
+                kind: core_types::FileKind::Other,
+            })
+        }
+    }
+
+    /// Search-box completions for the word being typed.
+    pub async fn complete(&self, req: CompleteRequest) -> Result<CompleteResponse> {
+        #[cfg(windows)]
+        {
+            Ok(self.inner.complete(req).await?)
+        }
+        #[cfg(not(windows))]
+        {
+            Ok(CompleteResponse {
+                id: req.id,
+                completions: Vec::new(),
             })
         }
     }
@@ -77,7 +130,7 @@ impl IpcClient {
     pub async fn rescan(&self, req: RescanRequest) -> Result<RescanResponse> {
         #[cfg(windows)]
         {
-            self.inner.rescan(req).await
+            Ok(self.inner.rescan(req).await?)
         }
         #[cfg(not(windows))]
         {
@@ -92,7 +145,7 @@ impl IpcClient {
     pub async fn reload_config(&self, req: ReloadConfigRequest) -> Result<ReloadConfigResponse> {
         #[cfg(windows)]
         {
-            self.inner.reload_config(req).await
+            Ok(self.inner.reload_config(req).await?)
         }
         #[cfg(not(windows))]
         {
@@ -105,6 +158,31 @@ impl IpcClient {
     }
 }
 
+impl IpcTransport for IpcClient {
+    fn search(&self, req: SearchRequest) -> BoxFuture<'static, Result<SearchResponse>> {
+        let client = self.clone();
+        async move { IpcClient::search(&client, req).await }.boxed()
+    }
+
+    fn status(&self, req: StatusRequest) -> BoxFuture<'static, Result<StatusResponse>> {
+        let client = self.clone();
+        async move { IpcClient::status(&client, req).await }.boxed()
+    }
+
+    fn reload_config(
+        &self,
+        req: ReloadConfigRequest,
+    ) -> BoxFuture<'static, Result<ReloadConfigResponse>> {
+        let client = self.clone();
+        async move { IpcClient::reload_config(&client, req).await }.boxed()
+    }
+
+    fn rescan(&self, req: RescanRequest) -> BoxFuture<'static, Result<RescanResponse>> {
+        let client = self.clone();
+        async move { IpcClient::rescan(&client, req).await }.boxed()
+    }
+}
+
 impl Default for IpcClient {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,207 @@
+//! In-memory [`IpcTransport`] for model tests: records requests and answers searches with one
+//! hit named after the query (bucketed as kind `other` when the request groups by kind), or
+//! with pages of a fixed result set when built [`paged`](MockIpcClient::paged).
+
+use super::client::IpcTransport;
+use anyhow::{anyhow, Result};
+use futures::channel::oneshot;
+use futures::future::{BoxFuture, FutureExt};
+use ipc::{
+    ClientError, FieldKind, QueryExpr, ReloadConfigRequest, ReloadConfigResponse, RescanRequest,
+    RescanResponse, ResultGroup, SearchHit, SearchRequest, SearchResponse, StatusRequest,
+    StatusResponse,
+};
+use std::sync::Mutex;
+
+#[derive(Default)]
+pub struct MockIpcClient {
+    state: Mutex<MockState>,
+}
+
+#[derive(Default)]
+struct MockState {
+    searches: Vec<SearchRequest>,
+    failing: bool,
+    /// Error searches fail with instead of the generic one, when set.
+    search_error: Option<fn() -> ClientError>,
+    hold: bool,
+    /// Matches every query has when set; searches then return `limit` of them from the
+    /// request's cursor or offset.
+    total: Option<u64>,
+    /// Gates of held searches, in request order; `None` once released.
+    gates: Vec<Option<oneshot::Sender<()>>>,
+}
+
+impl MockIpcClient {
+    /// Every call fails as if the service were down.
+    pub fn failing() -> Self {
+        let mock = Self::default();
+        mock.state.lock().unwrap().failing = true;
+        mock
+    }
+
+    /// Searches fail with the client error `make` builds; other calls succeed.
+    pub fn failing_with(make: fn() -> ClientError) -> Self {
+        let mock = Self::default();
+        mock.state.lock().unwrap().search_error = Some(make);
+        mock
+    }
+
+    /// Searches wait until [`release`](Self::release)d, so tests control the answer order.
+    pub fn holding_searches() -> Self {
+        let mock = Self::default();
+        mock.state.lock().unwrap().hold = true;
+        mock
+    }
+
+    /// Every query matches `total` hits, named `"<query> <n>"`, served a page at a time with
+    /// a `next_cursor` holding the next offset.
+    pub fn paged(total: u64) -> Self {
+        let mock = Self::default();
+        mock.state.lock().unwrap().total = Some(total);
+        mock
+    }
+
+    /// Let the `n`-th (0-based) held search answer.
+    pub fn release(&self, n: usize) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(gate) = state.gates.get_mut(n).and_then(Option::take) {
+            let _ = gate.send(());
+        }
+    }
+
+    /// The search requests received so far.
+    pub fn searches(&self) -> Vec<SearchRequest> {
+        self.state.lock().unwrap().searches.clone()
+    }
+}
+
+/// The text of a plain term query.
+pub fn query_text(req: &SearchRequest) -> String {
+    match &req.query {
+        QueryExpr::Term(term) => term.value.clone(),
+        other => format!("{other:?}"),
+    }
+}
+
+impl IpcTransport for MockIpcClient {
+    fn search(&self, req: SearchRequest) -> BoxFuture<'static, Result<SearchResponse>> {
+        let mut state = self.state.lock().unwrap();
+        state.searches.push(req.clone());
+        let failing = state.failing;
+        let search_error = state.search_error;
+        let total = state.total;
+        let gate = state.hold.then(|| {
+            let (tx, rx) = oneshot::channel();
+            state.gates.push(Some(tx));
+            rx
+        });
+        async move {
+            if let Some(gate) = gate {
+                let _ = gate.await;
+            }
+            if failing {
+                return Err(anyhow!("mock: service unavailable"));
+            }
+            if let Some(make) = search_error {
+                return Err(make().into());
+            }
+            let hit = |n: u64, name: String| SearchHit {
+                key: core_types::DocKey::from_parts(1, n + 1),
+                score: 1.0,
+                name: Some(name),
+                path: None,
+                ext: None,
+                size: None,
+                modified: None,
+                snippet: None,
+            };
+            let (hits, total, next_cursor) = match total {
+                Some(total) => {
+                    let start = req
+                        .cursor
+                        .as_deref()
+                        .map_or(u64::from(req.offset), |c| c.parse().unwrap());
+                    let end = (start + u64::from(req.limit)).min(total);
+                    let hits = (start..end)
+                        .map(|n| hit(n, format!("{} {n}", query_text(&req))))
+                        .collect();
+                    (hits, total, (end < total).then(|| end.to_string()))
+                }
+                None => (vec![hit(0, query_text(&req))], 1, None),
+            };
+            let groups = match req.group_by {
+                Some(FieldKind::Kind) => vec![ResultGroup {
+                    key: "other".into(),
+                    count: total,
+                    top_hits: hits.clone(),
+                }],
+                _ => Vec::new(),
+            };
+            Ok(SearchResponse {
+                id: req.id,
+                hits,
+                total,
+                truncated: false,
+                took_ms: 0,
+                served_by: Some("mock".into()),
+                next_cursor,
+                explanation: None,
+                timed_out: false,
+                degraded: None,
+                suggestions: Vec::new(),
+                groups,
+            })
+        }
+        .boxed()
+    }
+
+    fn status(&self, req: StatusRequest) -> BoxFuture<'static, Result<StatusResponse>> {
+        let failing = self.state.lock().unwrap().failing;
+        async move {
+            if failing {
+                return Err(anyhow!("mock: service unavailable"));
+            }
+            Ok(StatusResponse {
+                id: req.id,
+                volumes: vec![],
+                last_index_commit_ts: None,
+                scheduler_state: "mock".into(),
+                content_jobs_total: Some(0),
+                content_jobs_remaining: Some(0),
+                content_bytes_total: Some(0),
+                content_bytes_remaining: Some(0),
+                metrics: None,
+                served_by: Some("mock".into()),
+                readiness: ipc::Readiness::Ready,
+                queue_depth_by_category: None,
+            })
+        }
+        .boxed()
+    }
+
+    fn reload_config(
+        &self,
+        req: ReloadConfigRequest,
+    ) -> BoxFuture<'static, Result<ReloadConfigResponse>> {
+        async move {
+            Ok(ReloadConfigResponse {
+                id: req.id,
+                success: true,
+                message: None,
+            })
+        }
+        .boxed()
+    }
+
+    fn rescan(&self, req: RescanRequest) -> BoxFuture<'static, Result<RescanResponse>> {
+        async move {
+            Ok(RescanResponse {
+                id: req.id,
+                success: true,
+                message: None,
+            })
+        }
+        .boxed()
+    }
+}
@@ -1 +1,3 @@
 pub mod client;
+#[cfg(test)]
+pub mod mock;
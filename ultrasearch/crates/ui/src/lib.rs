@@ -1,6 +1,10 @@
 pub mod actions;
 pub mod background;
+#[cfg(target_os = "windows")]
+pub mod clipboard;
 pub mod components;
+#[cfg(target_os = "windows")]
+pub mod dnd;
 pub mod globals;
 pub mod icon_cache;
 pub mod ipc;
@@ -155,11 +155,21 @@ impl UltraSearchWindow {
     }
 
     fn on_select_next(&mut self, _: &SelectNext, _window: &mut Window, cx: &mut Context<Self>) {
-        self.model.update(cx, |model, cx| model.select_next(cx));
+        match self.model.update(cx, |model, cx| model.history_next(cx)) {
+            Some(query) => self
+                .search_view
+                .update(cx, |view, cx| view.show_text(&query, cx)),
+            None => self.model.update(cx, |model, cx| model.select_next(cx)),
+        }
     }
 
     fn on_select_prev(&mut self, _: &SelectPrev, _window: &mut Window, cx: &mut Context<Self>) {
-        self.model.update(cx, |model, cx| model.select_previous(cx));
+        match self.model.update(cx, |model, cx| model.history_prev(cx)) {
+            Some(query) => self
+                .search_view
+                .update(cx, |view, cx| view.show_text(&query, cx)),
+            None => self.model.update(cx, |model, cx| model.select_previous(cx)),
+        }
     }
 
     fn on_submit_search(&mut self, _: &SubmitSearch, window: &mut Window, cx: &mut Context<Self>) {
@@ -213,15 +223,22 @@ impl UltraSearchWindow {
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        // For now, copy path to clipboard. TODO: add file drop clipboard on Windows.
-        if let Some(path) = self
+        let Some(path) = self
             .model
             .read(cx)
             .selected_row()
             .and_then(|hit| hit.path.clone())
-        {
-            cx.write_to_clipboard(ClipboardItem::new_string(path));
+        else {
+            return;
+        };
+        // Explorer pastes a CF_HDROP as a copy of the file; elsewhere, or if that fails, the
+        // path text is the best we can offer.
+        #[cfg(target_os = "windows")]
+        match ui::clipboard::copy_files(&[&path]) {
+            Ok(()) => return,
+            Err(err) => tracing::warn!("copying {path} as a file failed: {err:#}"),
         }
+        cx.write_to_clipboard(ClipboardItem::new_string(path));
     }
 
     fn on_quit(&mut self, _: &QuitApp, _window: &mut Window, cx: &mut Context<Self>) {
@@ -252,25 +269,8 @@ impl UltraSearchWindow {
             .selected_row()
             .and_then(|hit| hit.path.clone())
         {
-            #[cfg(target_os = "windows")]
-            {
-                let _ = std::process::Command::new("explorer")
-                    .arg("/select,")
-                    .arg(&path)
-                    .spawn();
-            }
-            #[cfg(target_os = "macos")]
-            {
-                let _ = std::process::Command::new("open")
-                    .arg("-R")
-                    .arg(&path)
-                    .spawn();
-            }
-            #[cfg(target_os = "linux")]
-            {
-                if let Some(parent) = std::path::Path::new(&path).parent() {
-                    let _ = std::process::Command::new("xdg-open").arg(parent).spawn();
-                }
+            if let Err(e) = core_types::open_target::reveal_path(&path) {
+                tracing::warn!("show in folder failed: {e:#}");
             }
         }
     }
@@ -345,17 +345,8 @@ impl UltraSearchWindow {
             .selected_row()
             .and_then(|hit| hit.path.clone())
         {
-            #[cfg(target_os = "windows")]
-            {
-                let _ = std::process::Command::new("explorer").arg(&path).spawn();
-            }
-            #[cfg(target_os = "macos")]
-            {
-                let _ = std::process::Command::new("open").arg(&path).spawn();
-            }
-            #[cfg(target_os = "linux")]
-            {
-                let _ = std::process::Command::new("xdg-open").arg(&path).spawn();
+            if let Err(e) = core_types::open_target::open_path(&path) {
+                tracing::warn!("open failed: {e:#}");
             }
         }
     }
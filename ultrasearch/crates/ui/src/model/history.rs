@@ -0,0 +1,167 @@
+//! Recent search queries, most recent first, persisted as a JSON list under the data dir so
+//! they survive restarts.
+
+use anyhow::Result;
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Queries kept in memory and on disk.
+pub const MAX_HISTORY: usize = 50;
+
+#[derive(Debug, Clone, Default)]
+pub struct SearchHistory {
+    entries: VecDeque<String>,
+    /// Entry shown by up/down recall; `None` when not browsing.
+    cursor: Option<usize>,
+}
+
+impl SearchHistory {
+    /// Read a saved history; a missing or unreadable file gives an empty one.
+    pub fn load(path: &Path) -> Self {
+        let entries: Vec<String> = match fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|err| {
+                tracing::warn!(
+                    "ignoring unreadable search history {}: {err}",
+                    path.display()
+                );
+                Vec::new()
+            }),
+            Err(_) => Vec::new(),
+        };
+        let mut history = Self::default();
+        // Oldest first, so the newest ends up at the front.
+        for query in entries.iter().rev() {
+            history.push(query);
+        }
+        history
+    }
+
+    /// Write the list through a temp file so a crash never leaves a torn history behind.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(&self.entries)?)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Record `query` as the most recent one. A repeat moves to the front instead of appearing
+    /// twice; the oldest entries fall off past [`MAX_HISTORY`]. Returns whether the list changed.
+    pub fn push(&mut self, query: &str) -> bool {
+        self.cursor = None;
+        let trimmed = query.trim();
+        if trimmed.is_empty() || self.entries.front().is_some_and(|q| q == trimmed) {
+            return false;
+        }
+        self.entries.retain(|q| q != trimmed);
+        self.entries.push_front(trimmed.to_string());
+        self.entries.truncate(MAX_HISTORY);
+        true
+    }
+
+    /// Step to the next older entry, stopping at the oldest.
+    pub fn older(&mut self) -> Option<&str> {
+        let last = self.entries.len().checked_sub(1)?;
+        let next = self.cursor.map_or(0, |i| (i + 1).min(last));
+        self.cursor = Some(next);
+        self.current()
+    }
+
+    /// Step to the next newer entry; past the newest, browsing ends and `None` comes back.
+    pub fn newer(&mut self) -> Option<&str> {
+        self.cursor = self.cursor.and_then(|i| i.checked_sub(1));
+        self.current()
+    }
+
+    /// The entry the cursor is on, if browsing.
+    pub fn current(&self) -> Option<&str> {
+        self.cursor
+            .and_then(|i| self.entries.get(i))
+            .map(String::as_str)
+    }
+
+    pub fn reset_cursor(&mut self) {
+        self.cursor = None;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &String> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// `<data_dir>/ui/history.json`, or `None` while the data dir is still an unexpanded default.
+pub fn default_history_path() -> Option<PathBuf> {
+    core_types::config::get_current_config().search_history_path()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(history: &SearchHistory) -> Vec<&str> {
+        history.iter().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn repeats_move_to_the_front_and_the_oldest_fall_off() {
+        let mut history = SearchHistory::default();
+        assert!(history.push("report"));
+        assert!(history.push("  invoice "));
+        assert!(!history.push("invoice"));
+        assert!(!history.push("   "));
+        assert!(history.push("report"));
+        assert_eq!(entries(&history), ["report", "invoice"]);
+
+        for i in 0..MAX_HISTORY + 5 {
+            history.push(&format!("q{i}"));
+        }
+        assert_eq!(history.len(), MAX_HISTORY);
+        assert_eq!(
+            history.iter().next().unwrap(),
+            &format!("q{}", MAX_HISTORY + 4)
+        );
+        assert!(!history.iter().any(|q| q == "report"));
+    }
+
+    #[test]
+    fn older_and_newer_walk_the_list() {
+        let mut history = SearchHistory::default();
+        assert_eq!(history.older(), None);
+        history.push("a");
+        history.push("b");
+        assert_eq!(history.older(), Some("b"));
+        assert_eq!(history.older(), Some("a"));
+        assert_eq!(history.older(), Some("a"));
+        assert_eq!(history.newer(), Some("b"));
+        assert_eq!(history.newer(), None);
+        assert_eq!(history.current(), None);
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ui").join("history.json");
+        assert!(SearchHistory::load(&path).is_empty());
+
+        let mut history = SearchHistory::default();
+        history.push("older");
+        history.push("newer");
+        history.save(&path).unwrap();
+        let loaded = SearchHistory::load(&path);
+        assert_eq!(entries(&loaded), ["newer", "older"]);
+
+        fs::write(&path, b"not json").unwrap();
+        assert!(SearchHistory::load(&path).is_empty());
+    }
+}
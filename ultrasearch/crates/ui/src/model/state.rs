@@ -1,11 +1,15 @@
 use crate::background::{set_tray_status, TrayState};
-use crate::ipc::client::IpcClient;
+use crate::ipc::client::{IpcClient, IpcTransport};
+use crate::model::history::{default_history_path, SearchHistory};
+use core_types::config::{SavedSearch, SavedSearchMode};
+use core_types::FileKind;
 use gpui::*;
 use ipc::{
-    MetricsSnapshot, QueryExpr, SearchHit, SearchMode, SearchRequest, StatusRequest, TermExpr,
-    TermModifier, VolumeStatus,
+    ClientError, FieldKind, MetricsSnapshot, QueryExpr, ResultGroup, SearchHit, SearchMode,
+    SearchRequest, StatusRequest, TermExpr, TermModifier, VolumeStatus,
 };
-use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use uuid::Uuid;
 
@@ -52,6 +56,106 @@ impl From<BackendMode> for SearchMode {
     }
 }
 
+impl From<BackendMode> for SavedSearchMode {
+    fn from(mode: BackendMode) -> Self {
+        match mode {
+            BackendMode::MetadataOnly => SavedSearchMode::Name,
+            BackendMode::Mixed => SavedSearchMode::Hybrid,
+            BackendMode::ContentOnly => SavedSearchMode::Content,
+        }
+    }
+}
+
+impl From<SavedSearchMode> for BackendMode {
+    fn from(mode: SavedSearchMode) -> Self {
+        match mode {
+            SavedSearchMode::Name => BackendMode::MetadataOnly,
+            SavedSearchMode::Content => BackendMode::ContentOnly,
+            SavedSearchMode::Auto | SavedSearchMode::Hybrid => BackendMode::Mixed,
+        }
+    }
+}
+
+/// File kinds the type filter offers, in display order.
+pub const KIND_FILTERS: [FileKind; 6] = [
+    FileKind::Office,
+    FileKind::Pdf,
+    FileKind::Image,
+    FileKind::Text,
+    FileKind::Html,
+    FileKind::Archive,
+];
+
+/// Name the type filter shows for `kind`.
+pub fn kind_label(kind: FileKind) -> &'static str {
+    match kind {
+        FileKind::Office => "Documents",
+        FileKind::Pdf => "PDFs",
+        FileKind::Image => "Images",
+        FileKind::Text => "Text & code",
+        FileKind::Html => "Web pages",
+        FileKind::Archive => "Archives",
+        FileKind::Other => "Other",
+    }
+}
+
+/// The request sent for the search box text `query`. A type filter ANDs a `kind:` term onto
+/// the text; without one the name matches are also bucketed by kind for the filter's counts.
+pub fn search_request(query: &str, mode: BackendMode, kind: Option<FileKind>) -> SearchRequest {
+    let text = QueryExpr::Term(TermExpr {
+        field: None,
+        value: query.to_string(),
+        modifier: TermModifier::Term,
+        boost: None,
+    });
+    let query = match kind {
+        Some(kind) => QueryExpr::And(vec![
+            text,
+            QueryExpr::Term(TermExpr {
+                field: Some(FieldKind::Kind),
+                value: kind.as_str().to_string(),
+                modifier: TermModifier::Term,
+                boost: None,
+            }),
+        ]),
+        None => text,
+    };
+    SearchRequest {
+        id: Uuid::new_v4(),
+        query,
+        limit: 100,
+        mode: mode.into(),
+        timeout: Some(Duration::from_secs(5)),
+        offset: 0,
+        cursor: None,
+        explain: false,
+        case_sensitive: false,
+        count_only: false,
+        fields: None,
+        include_system: false,
+        sort: None,
+        suggest: false,
+        group_by: kind.is_none().then_some(FieldKind::Kind),
+        // The results table and preview show scores as percentages.
+        normalize_scores: true,
+    }
+}
+
+/// The search box text to send: trimmed, or `None` when there is nothing to search for
+/// (whitespace, or only parentheses and punctuation).
+pub fn effective_query(text: &str) -> Option<&str> {
+    let trimmed = text.trim();
+    (!ipc::query::is_blank(trimmed)).then_some(trimmed)
+}
+
+/// Per-kind match counts from a response grouped by `Kind`, largest first.
+fn kind_counts(groups: &[ResultGroup]) -> Vec<(FileKind, u64)> {
+    groups
+        .iter()
+        .filter_map(|g| FileKind::from_name(&g.key).map(|kind| (kind, g.count)))
+        .collect()
+}
+
 #[derive(Clone)]
 pub struct SearchStatus {
     pub total: u64,
@@ -95,16 +199,33 @@ pub struct SearchAppModel {
     pub query: String,
     pub results: Vec<SearchHit>,
     pub status: SearchStatus,
+    /// Only show results of this kind; `None` shows every kind.
+    pub kind_filter: Option<FileKind>,
+    /// Matches per kind for the current query, from its last unfiltered search. Only name
+    /// searches are bucketed, so other modes leave this empty.
+    pub kind_counts: Vec<(FileKind, u64)>,
+    /// Resume token for the hits after `results`, from the last page received.
+    pub next_cursor: Option<String>,
+    /// A [`load_more`](Self::load_more) request is on the wire.
+    pub loading_more: bool,
     pub selected_index: Option<usize>,
     pub page_size: usize,
     pub page: usize,
     pub updates: UpdateState,
     pub hotkey_conflict: Option<String>,
-    pub history: VecDeque<String>,
+    pub history: SearchHistory,
+    /// Where `history` is saved; `None` keeps it in memory only.
+    pub history_path: Option<PathBuf>,
+    pub saved_searches: Vec<SavedSearch>,
+    /// Config file that saved searches are written to; `None` keeps them in memory only.
+    pub config_path: Option<PathBuf>,
     pub show_shortcuts: bool,
     pub ipc_recent_reconnect: bool,
-    pub client: IpcClient,
+    pub client: Arc<dyn IpcTransport>,
     pub search_debounce: Option<Task<()>>,
+    /// Bumped by every `set_query`; a search result is applied only if its generation is
+    /// still current, so a slow answer for an older query never replaces a newer one.
+    pub search_generation: u64,
     pub status_task: Option<Task<()>>,
     pub last_search: Option<Instant>,
     pub show_onboarding: bool,
@@ -113,22 +234,40 @@ pub struct SearchAppModel {
 
 impl SearchAppModel {
     pub fn new(cx: &mut Context<SearchAppModel>) -> Self {
-        let client = IpcClient::new();
+        let mut model = Self::with_client(Arc::new(IpcClient::new()), cx);
+        if let Some(path) = default_history_path() {
+            model.history = SearchHistory::load(&path);
+            model.history_path = Some(path);
+        }
+        model.saved_searches = core_types::config::get_current_config().saved_searches;
+        model.config_path = Some(core_types::config::default_config_path());
+        model
+    }
 
+    /// Build the model over any transport; tests pass a mock in place of the named pipe.
+    pub fn with_client(client: Arc<dyn IpcTransport>, cx: &mut Context<SearchAppModel>) -> Self {
         let mut model = Self {
             query: String::new(),
             results: Vec::new(),
             status: SearchStatus::default(),
+            kind_filter: None,
+            kind_counts: Vec::new(),
+            next_cursor: None,
+            loading_more: false,
             selected_index: None,
             page_size: 50,
             page: 0,
             updates: UpdateState::default(),
             hotkey_conflict: None,
-            history: VecDeque::new(),
+            history: SearchHistory::default(),
+            history_path: None,
+            saved_searches: Vec::new(),
+            config_path: None,
             show_shortcuts: false,
             ipc_recent_reconnect: false,
             client,
             search_debounce: None,
+            search_generation: 0,
             status_task: None,
             last_search: None,
             show_onboarding: false,
@@ -154,7 +293,10 @@ impl SearchAppModel {
             let async_app = cx.clone();
             async move {
                 loop {
-                    tokio::time::sleep(Duration::from_secs(2)).await;
+                    async_app
+                        .background_executor()
+                        .timer(Duration::from_secs(2))
+                        .await;
                     let req = StatusRequest { id: Uuid::new_v4() };
                     match client.status(req).await {
                         Ok(resp) => {
@@ -244,29 +386,57 @@ impl SearchAppModel {
     }
 
     pub fn set_query(&mut self, query: String, cx: &mut Context<SearchAppModel>) {
+        // Filtered searches are not bucketed, so counts for the old text would linger.
+        if self.kind_filter.is_some() && query != self.query {
+            self.kind_counts.clear();
+        }
         self.query = query;
+        self.search_generation += 1;
+        // A page still loading belongs to the old query; its answer is dropped on arrival.
+        self.loading_more = false;
+        let generation = self.search_generation;
 
-        // Cancel previous debounce task
+        // Let the previous task run out: it stops after its debounce if superseded, and a
+        // search already on the wire is left to finish (its answer is discarded below) rather
+        // than cut off mid-exchange.
         if let Some(task) = self.search_debounce.take() {
-            drop(task);
+            task.detach();
         }
 
-        let query_clone = self.query.clone();
+        let query_clone = effective_query(&self.query).map(str::to_owned);
         let client = self.client.clone();
         let mode = self.status.backend_mode;
+        let kind = self.kind_filter;
 
         self.search_debounce = Some(cx.spawn(
             move |this: WeakEntity<SearchAppModel>, cx: &mut AsyncApp| {
                 let async_app = cx.clone();
                 async move {
-                    tokio::time::sleep(Duration::from_millis(150)).await;
+                    async_app
+                        .background_executor()
+                        .timer(Duration::from_millis(150))
+                        .await;
+
+                    // A newer keystroke arrived during the debounce; its own task will search.
+                    let current = async_app.update(|app| {
+                        this.update(app, |model: &mut SearchAppModel, _| {
+                            model.search_generation == generation
+                        })
+                    });
+                    if !matches!(current, Ok(Ok(true))) {
+                        return;
+                    }
 
-                    if query_clone.is_empty() {
+                    // Blank input clears the results rather than sending a query that matches
+                    // nothing (or everything).
+                    let Some(query_clone) = query_clone else {
                         let _ = async_app.update(|app| {
                             this.update(
                                 app,
                                 |model: &mut SearchAppModel, cx: &mut Context<SearchAppModel>| {
                                     model.results.clear();
+                                    model.kind_counts.clear();
+                                    model.next_cursor = None;
                                     model.status.total = 0;
                                     model.status.shown = 0;
                                     model.page = 0;
@@ -276,21 +446,11 @@ impl SearchAppModel {
                             )
                         });
                         return;
-                    }
-
-                    let req = SearchRequest {
-                        id: Uuid::new_v4(),
-                        query: QueryExpr::Term(TermExpr {
-                            field: None,
-                            value: query_clone.clone(),
-                            modifier: TermModifier::Term,
-                        }),
-                        limit: 100,
-                        mode: mode.into(),
-                        timeout: Some(Duration::from_secs(5)),
-                        offset: 0,
                     };
 
+                    let req = search_request(&query_clone, mode, kind);
+                    let faceted = req.group_by.is_some();
+
                     let start = Instant::now();
                     let _ = async_app.update(|app| {
                         this.update(
@@ -309,9 +469,16 @@ impl SearchAppModel {
                                     app,
                                     |model: &mut SearchAppModel,
                                      cx: &mut Context<SearchAppModel>| {
+                                        if model.search_generation != generation {
+                                            return;
+                                        }
                                         model.status.in_flight = false;
-                                       model.results = resp.hits;
-                                       model.status.total = resp.total;
+                                        if faceted {
+                                            model.kind_counts = kind_counts(&resp.groups);
+                                        }
+                                        model.results = resp.hits;
+                                        model.status.total = resp.total;
+                                        model.next_cursor = resp.next_cursor;
                                         model.page = 0;
                                         model.status.shown = model.current_page_results().len();
                                         model.status.last_latency_ms = Some(latency);
@@ -325,15 +492,20 @@ impl SearchAppModel {
                         }
                         Err(err) => {
                             tracing::warn!("search request failed: {err}");
+                            let (message, unreachable) = search_failure(&err);
                             let _ = async_app.update(|app| {
                                 this.update(
                                     app,
                                     |model: &mut SearchAppModel,
                                      cx: &mut Context<SearchAppModel>| {
+                                        if model.search_generation != generation {
+                                            return;
+                                        }
                                         model.status.in_flight = false;
-                                        model.status.connected = false;
-                                        model.status.indexing_state =
-                                            "Disconnected (search)".to_string();
+                                        if unreachable {
+                                            model.status.connected = false;
+                                        }
+                                        model.status.indexing_state = message.to_string();
                                         model.status.last_latency_ms = None;
                                         cx.notify();
                                     },
@@ -356,10 +528,78 @@ impl SearchAppModel {
         cx.notify();
     }
 
+    /// Narrow results to `kind` (or show every kind again) and re-run the current query.
+    pub fn set_kind_filter(&mut self, kind: Option<FileKind>, cx: &mut Context<SearchAppModel>) {
+        if self.kind_filter == kind {
+            return;
+        }
+        self.kind_filter = kind;
+        if !self.query.is_empty() {
+            let query = self.query.clone();
+            self.set_query(query, cx);
+        }
+        cx.notify();
+    }
+
+    /// The service matched more hits than `results` holds.
+    pub fn has_more(&self) -> bool {
+        self.status.total > self.results.len() as u64
+    }
+
+    /// Fetch the hits after `results` and append them, leaving the selection and page alone.
+    /// Resumes from `next_cursor`, or by offset when the service sent none. Does nothing while
+    /// a page is already loading or everything is loaded.
+    pub fn load_more(&mut self, cx: &mut Context<SearchAppModel>) {
+        let Some(query) = effective_query(&self.query) else {
+            return;
+        };
+        if self.loading_more || !self.has_more() {
+            return;
+        }
+        let mut req = search_request(query, self.status.backend_mode, self.kind_filter);
+        req.group_by = None;
+        match self.next_cursor.clone() {
+            Some(cursor) => req.cursor = Some(cursor),
+            None => req.offset = self.results.len() as u32,
+        }
+        self.loading_more = true;
+        let generation = self.search_generation;
+        let client = self.client.clone();
+        cx.spawn(move |this: WeakEntity<SearchAppModel>, cx: &mut AsyncApp| {
+            let async_app = cx.clone();
+            async move {
+                let result = client.search(req).await;
+                let _ = async_app.update(|app| {
+                    this.update(app, |model: &mut SearchAppModel, cx| {
+                        if model.search_generation != generation {
+                            return;
+                        }
+                        model.loading_more = false;
+                        match result {
+                            Ok(resp) => {
+                                model.results.extend(resp.hits);
+                                model.status.total = resp.total;
+                                model.next_cursor = resp.next_cursor;
+                                model.status.shown = model.current_page_results().len();
+                            }
+                            Err(err) => tracing::warn!("loading more results failed: {err}"),
+                        }
+                        cx.notify();
+                    })
+                });
+            }
+        })
+        .detach();
+        cx.notify();
+    }
+
     pub fn select_next(&mut self, cx: &mut Context<SearchAppModel>) {
         if self.results.is_empty() {
             return;
         }
+        if self.selected_index == Some(self.results.len() - 1) {
+            self.load_more(cx);
+        }
         self.selected_index = Some(match self.selected_index {
             Some(i) if i < self.results.len() - 1 => i + 1,
             Some(i) => i,
@@ -406,8 +646,14 @@ impl SearchAppModel {
     }
 
     pub fn current_page_results(&self) -> &[SearchHit] {
-        let start = self.page_start();
-        let end = (start + self.page_size).min(self.results.len());
+        self.visible_range(self.page_start(), self.page_size)
+    }
+
+    /// Up to `count` hits starting at `start`, for lists that render only the rows on screen.
+    /// The model keeps every hit; ranges past the end come back short or empty.
+    pub fn visible_range(&self, start: usize, count: usize) -> &[SearchHit] {
+        let start = start.min(self.results.len());
+        let end = start.saturating_add(count).min(self.results.len());
         &self.results[start..end]
     }
 
@@ -561,20 +807,85 @@ impl SearchAppModel {
     }
 
     pub fn push_history(&mut self, query: &str) {
-        let trimmed = query.trim();
-        if trimmed.is_empty() {
+        if !self.history.push(query) {
             return;
         }
-        if let Some(front) = self.history.front() {
-            if front == trimmed {
-                return;
+        if let Some(path) = &self.history_path {
+            if let Err(err) = self.history.save(path) {
+                tracing::warn!("failed to save search history: {err}");
             }
         }
-        self.history.push_front(trimmed.to_string());
-        const MAX_HISTORY: usize = 10;
-        if self.history.len() > MAX_HISTORY {
-            self.history.pop_back();
+    }
+
+    /// Up-arrow recall: load the next older query into the box. Works from an empty box or
+    /// while already browsing; returns the recalled query for the input to display.
+    pub fn history_prev(&mut self, cx: &mut Context<SearchAppModel>) -> Option<String> {
+        if self.query.is_empty() {
+            self.history.reset_cursor();
+        } else if self.history.current() != Some(self.query.as_str()) {
+            return None;
         }
+        let query = self.history.older()?.to_string();
+        self.set_query(query.clone(), cx);
+        Some(query)
+    }
+
+    /// Save the current query and mode as `name`, replacing a saved search of the same name.
+    pub fn save_current_search(&mut self, name: &str, cx: &mut Context<SearchAppModel>) {
+        let name = name.trim();
+        if name.is_empty() || self.query.trim().is_empty() {
+            return;
+        }
+        let saved = SavedSearch {
+            name: name.to_string(),
+            query: self.query.clone(),
+            mode: self.status.backend_mode.into(),
+        };
+        if let Some(path) = &self.config_path {
+            let result =
+                core_types::config::load_or_create_config(Some(path)).and_then(|mut cfg| {
+                    cfg.add_saved_search(saved.clone());
+                    core_types::config::save_config_atomic(path, &cfg)
+                });
+            if let Err(err) = result {
+                tracing::warn!("failed to save search {name:?}: {err}");
+            }
+        }
+        match self
+            .saved_searches
+            .iter_mut()
+            .find(|s| s.name == saved.name)
+        {
+            Some(existing) => *existing = saved,
+            None => self.saved_searches.push(saved),
+        }
+        cx.notify();
+    }
+
+    /// Run the saved search `name` as if typed: switch to its mode and search its query.
+    /// Returns the query for the search box to display.
+    pub fn recall_saved_search(
+        &mut self,
+        name: &str,
+        cx: &mut Context<SearchAppModel>,
+    ) -> Option<String> {
+        let saved = self.saved_searches.iter().find(|s| s.name == name)?.clone();
+        self.status.backend_mode = saved.mode.into();
+        self.set_query(saved.query.clone(), cx);
+        self.push_history(&saved.query);
+        cx.notify();
+        Some(saved.query)
+    }
+
+    /// Down-arrow recall: the next newer query, or an empty box past the newest. `None` when
+    /// not browsing history.
+    pub fn history_next(&mut self, cx: &mut Context<SearchAppModel>) -> Option<String> {
+        if self.query.is_empty() || self.history.current() != Some(self.query.as_str()) {
+            return None;
+        }
+        let query = self.history.newer().unwrap_or_default().to_string();
+        self.set_query(query.clone(), cx);
+        Some(query)
     }
 }
 
@@ -594,3 +905,295 @@ impl Drop for SearchAppModel {
         }
     }
 }
+
+/// Status text for a failed search, and whether it means the service can't be reached. A
+/// timeout or a rejected query comes from a service that is still up.
+fn search_failure(err: &anyhow::Error) -> (&'static str, bool) {
+    match err.downcast_ref::<ClientError>() {
+        Some(ClientError::NotConnected(_)) => ("Service not running", true),
+        Some(ClientError::Timeout(_)) => ("Query timed out", false),
+        Some(ClientError::Server(_)) => ("Query rejected by the service", false),
+        Some(ClientError::Protocol(_)) => ("Unexpected response from the service", true),
+        Some(ClientError::Io(_)) | None => ("Disconnected (search)", true),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ipc::mock::{query_text, MockIpcClient};
+
+    fn model_with(mock: Arc<MockIpcClient>, cx: &mut TestAppContext) -> Entity<SearchAppModel> {
+        cx.new(|cx| SearchAppModel::with_client(mock, cx))
+    }
+
+    #[gpui::test]
+    fn typing_sends_one_search_after_the_debounce(cx: &mut TestAppContext) {
+        let mock = Arc::new(MockIpcClient::default());
+        let model = model_with(mock.clone(), cx);
+
+        model.update(cx, |m, cx| {
+            m.set_query("r".into(), cx);
+            m.set_query("re".into(), cx);
+            m.set_query("report".into(), cx);
+        });
+        cx.run_until_parked();
+        assert!(mock.searches().is_empty());
+
+        cx.executor().advance_clock(Duration::from_millis(150));
+        cx.run_until_parked();
+        let searches = mock.searches();
+        assert_eq!(searches.len(), 1);
+        assert_eq!(query_text(&searches[0]), "report");
+        model.read_with(cx, |m, _| {
+            assert_eq!(m.results[0].name.as_deref(), Some("report"));
+            assert!(m.status.connected);
+        });
+    }
+
+    #[gpui::test]
+    fn a_slow_answer_for_an_older_query_is_discarded(cx: &mut TestAppContext) {
+        let mock = Arc::new(MockIpcClient::holding_searches());
+        let model = model_with(mock.clone(), cx);
+
+        model.update(cx, |m, cx| m.set_query("old".into(), cx));
+        cx.executor().advance_clock(Duration::from_millis(150));
+        cx.run_until_parked();
+        model.update(cx, |m, cx| m.set_query("new".into(), cx));
+        cx.executor().advance_clock(Duration::from_millis(150));
+        cx.run_until_parked();
+        assert_eq!(mock.searches().len(), 2);
+
+        mock.release(1);
+        cx.run_until_parked();
+        mock.release(0);
+        cx.run_until_parked();
+        model.read_with(cx, |m, _| {
+            assert_eq!(m.results.len(), 1);
+            assert_eq!(m.results[0].name.as_deref(), Some("new"));
+            assert!(!m.status.in_flight);
+        });
+    }
+
+    #[gpui::test]
+    fn visible_range_slices_and_clamps(cx: &mut TestAppContext) {
+        let model = model_with(Arc::new(MockIpcClient::default()), cx);
+        model.update(cx, |m, cx| m.load_mock_results(1_200, cx));
+        model.read_with(cx, |m, _| {
+            assert_eq!(m.results.len(), 1_200);
+            let rows = m.visible_range(600, 25);
+            assert_eq!(rows.len(), 25);
+            assert_eq!(rows[0].name.as_deref(), Some("Design Spec 601"));
+            assert_eq!(m.visible_range(1_190, 25).len(), 10);
+            assert!(m.visible_range(1_200, 25).is_empty());
+            assert!(m.visible_range(usize::MAX, usize::MAX).is_empty());
+            assert_eq!(m.visible_range(0, usize::MAX).len(), 1_200);
+            assert!(m.visible_range(10, 0).is_empty());
+        });
+    }
+
+    #[gpui::test]
+    fn saved_searches_are_stored_and_recalled(cx: &mut TestAppContext) {
+        let mock = Arc::new(MockIpcClient::default());
+        let model = model_with(mock.clone(), cx);
+        model.update(cx, |m, cx| {
+            m.status.backend_mode = BackendMode::ContentOnly;
+            m.set_query("quarterly report".into(), cx);
+            m.save_current_search("reports", cx);
+            m.status.backend_mode = BackendMode::MetadataOnly;
+            m.set_query("other".into(), cx);
+        });
+        model.read_with(cx, |m, _| {
+            assert_eq!(m.saved_searches.len(), 1);
+            assert_eq!(m.saved_searches[0].mode, SavedSearchMode::Content);
+        });
+
+        let recalled = model.update(cx, |m, cx| m.recall_saved_search("reports", cx));
+        assert_eq!(recalled.as_deref(), Some("quarterly report"));
+        assert!(model
+            .update(cx, |m, cx| m.recall_saved_search("missing", cx))
+            .is_none());
+        cx.executor().advance_clock(Duration::from_millis(150));
+        cx.run_until_parked();
+
+        let last = mock.searches().pop().unwrap();
+        assert_eq!(query_text(&last), "quarterly report");
+        assert_eq!(last.mode, SearchMode::Content);
+    }
+
+    #[gpui::test]
+    fn kind_filter_ands_a_kind_term_onto_the_query(cx: &mut TestAppContext) {
+        let mock = Arc::new(MockIpcClient::default());
+        let model = model_with(mock.clone(), cx);
+        let text = QueryExpr::Term(TermExpr {
+            field: None,
+            value: "report".into(),
+            modifier: TermModifier::Term,
+            boost: None,
+        });
+
+        model.update(cx, |m, cx| {
+            m.status.backend_mode = BackendMode::MetadataOnly;
+            m.set_query("report".into(), cx);
+        });
+        cx.executor().advance_clock(Duration::from_millis(150));
+        cx.run_until_parked();
+        let unfiltered = mock.searches().pop().unwrap();
+        assert_eq!(unfiltered.query, text);
+        assert_eq!(unfiltered.group_by, Some(FieldKind::Kind));
+        model.read_with(cx, |m, _| {
+            assert_eq!(m.kind_counts, [(FileKind::Other, 1)]);
+        });
+
+        model.update(cx, |m, cx| m.set_kind_filter(Some(FileKind::Image), cx));
+        cx.executor().advance_clock(Duration::from_millis(150));
+        cx.run_until_parked();
+        let filtered = mock.searches().pop().unwrap();
+        assert_eq!(
+            filtered.query,
+            QueryExpr::And(vec![
+                text.clone(),
+                QueryExpr::Term(TermExpr {
+                    field: Some(FieldKind::Kind),
+                    value: "image".into(),
+                    modifier: TermModifier::Term,
+                    boost: None,
+                }),
+            ])
+        );
+        assert_eq!(filtered.mode, SearchMode::NameOnly);
+        assert_eq!(filtered.group_by, None);
+        model.read_with(cx, |m, _| {
+            assert_eq!(
+                m.kind_counts,
+                [(FileKind::Other, 1)],
+                "counts stay those of the unfiltered query"
+            );
+        });
+
+        model.update(cx, |m, cx| m.set_kind_filter(None, cx));
+        cx.executor().advance_clock(Duration::from_millis(150));
+        cx.run_until_parked();
+        assert_eq!(mock.searches().pop().unwrap().query, text);
+        assert_eq!(mock.searches().len(), 3);
+    }
+
+    #[gpui::test]
+    fn blank_queries_clear_results_without_searching(cx: &mut TestAppContext) {
+        let mock = Arc::new(MockIpcClient::default());
+        let model = model_with(mock.clone(), cx);
+        model.update(cx, |m, cx| m.set_query("  report ".into(), cx));
+        cx.executor().advance_clock(Duration::from_millis(150));
+        cx.run_until_parked();
+        assert_eq!(query_text(&mock.searches()[0]), "report", "sent trimmed");
+        model.read_with(cx, |m, _| assert_eq!(m.results.len(), 1));
+
+        for blank in ["   ", "()"] {
+            model.update(cx, |m, cx| m.set_query(blank.into(), cx));
+            cx.executor().advance_clock(Duration::from_millis(150));
+            cx.run_until_parked();
+            assert_eq!(mock.searches().len(), 1, "{blank:?} must not be sent");
+            model.read_with(cx, |m, _| {
+                assert!(m.results.is_empty());
+                assert_eq!(m.status.total, 0);
+                assert_eq!(m.selected_index, None);
+            });
+        }
+    }
+
+    #[gpui::test]
+    fn load_more_appends_the_next_page_and_keeps_the_selection(cx: &mut TestAppContext) {
+        let mock = Arc::new(MockIpcClient::paged(250));
+        let model = model_with(mock.clone(), cx);
+        model.update(cx, |m, cx| m.set_query("spec".into(), cx));
+        cx.executor().advance_clock(Duration::from_millis(150));
+        cx.run_until_parked();
+        model.update(cx, |m, cx| {
+            assert_eq!((m.results.len(), m.status.total), (100, 250));
+            assert!(m.has_more());
+            m.selected_index = Some(42);
+            m.load_more(cx);
+            // Already loading: no second request.
+            m.load_more(cx);
+        });
+        cx.run_until_parked();
+
+        let searches = mock.searches();
+        assert_eq!(searches.len(), 2);
+        assert_eq!(searches[1].cursor.as_deref(), Some("100"));
+        assert_eq!(searches[1].query, searches[0].query);
+        model.update(cx, |m, cx| {
+            assert_eq!(m.results.len(), 200);
+            assert_eq!(m.results[100].name.as_deref(), Some("spec 100"));
+            assert_eq!((m.selected_index, m.page), (Some(42), 0));
+
+            // Moving down from the last loaded row fetches the rest.
+            m.selected_index = Some(199);
+            m.select_next(cx);
+        });
+        cx.run_until_parked();
+        model.update(cx, |m, cx| {
+            assert_eq!(m.results.len(), 250);
+            assert!(!m.has_more());
+            m.select_next(cx);
+            assert_eq!(m.selected_index, Some(200));
+            m.load_more(cx);
+        });
+        cx.run_until_parked();
+        assert_eq!(mock.searches().len(), 3, "nothing left to load");
+    }
+
+    #[gpui::test]
+    fn failed_search_marks_the_service_disconnected(cx: &mut TestAppContext) {
+        let model = model_with(Arc::new(MockIpcClient::failing()), cx);
+        model.update(cx, |m, cx| {
+            m.status.connected = true;
+            m.set_query("report".into(), cx);
+        });
+
+        cx.executor().advance_clock(Duration::from_millis(150));
+        cx.run_until_parked();
+        model.read_with(cx, |m, _| {
+            assert!(!m.status.connected);
+            assert!(!m.status.in_flight);
+        });
+    }
+
+    #[gpui::test]
+    fn timed_out_search_keeps_the_service_connected(cx: &mut TestAppContext) {
+        let mock = MockIpcClient::failing_with(|| ClientError::Timeout(Duration::from_millis(750)));
+        let model = model_with(Arc::new(mock), cx);
+        model.update(cx, |m, cx| {
+            m.status.connected = true;
+            m.set_query("report".into(), cx);
+        });
+
+        cx.executor().advance_clock(Duration::from_millis(150));
+        cx.run_until_parked();
+        model.read_with(cx, |m, _| {
+            assert!(m.status.connected);
+            assert_eq!(m.status.indexing_state, "Query timed out");
+        });
+    }
+
+    #[test]
+    fn search_failures_map_to_specific_messages() {
+        let refused = std::io::Error::from(std::io::ErrorKind::ConnectionRefused);
+        let not_running = anyhow::Error::new(ClientError::connect_failed(refused));
+        assert_eq!(search_failure(&not_running), ("Service not running", true));
+
+        let rejected = anyhow::Error::new(ClientError::Server(ipc::ErrorResponse::new(
+            Uuid::nil(),
+            ipc::ErrorCode::Unsupported,
+            "unknown request",
+        )));
+        assert_eq!(
+            search_failure(&rejected),
+            ("Query rejected by the service", false)
+        );
+        assert_eq!(
+            search_failure(&anyhow::anyhow!("mock: service unavailable")),
+            ("Disconnected (search)", true)
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::actions::FinishOnboarding;
+use crate::ipc::client::IpcTransport;
 use crate::model::state::SearchAppModel;
 use crate::theme;
 use gpui::prelude::FluentBuilder;
@@ -126,12 +127,7 @@ impl OnboardingView {
             config.volumes = selected;
             config.content_index_volumes = content_enabled;
             let target = core_types::config::default_config_path();
-            if let Some(parent) = target.parent() {
-                let _ = std::fs::create_dir_all(parent);
-            }
-            if let Ok(toml) = toml::to_string_pretty(&config) {
-                let _ = std::fs::write(&target, toml);
-            }
+            let _ = core_types::config::save_config_atomic(&target, &config);
         }
 
         let client = self.model.read(cx).client.clone();
@@ -1,31 +1,23 @@
 use crate::model::state::SearchAppModel;
 use crate::theme;
+use core_types::{open_target, FileId, VolumeId};
 use gpui::prelude::*;
 use gpui::{InteractiveElement, UniformListScrollHandle, *};
-use std::process::Command;
+use ipc::SearchHit;
 
 pub struct PreviewView {
     model: Entity<SearchAppModel>,
     snippet_scroll: UniformListScrollHandle,
-    last_item_id: Option<String>,
+    last_item_id: Option<(VolumeId, FileId)>,
 }
 
 impl PreviewView {
     pub fn new(model: Entity<SearchAppModel>, cx: &mut Context<PreviewView>) -> Self {
         cx.observe(&model, |this: &mut PreviewView, model, cx| {
-            let selected_path = model
-                .read(cx)
-                .selected_row()
-                .and_then(|hit| hit.path.clone())
-                .unwrap_or_default();
-            if this
-                .last_item_id
-                .as_ref()
-                .map(|p| p != &selected_path)
-                .unwrap_or(true)
-            {
+            let selected = model.read(cx).selected_row().map(SearchHit::stable_id);
+            if this.last_item_id != selected {
                 this.snippet_scroll = UniformListScrollHandle::new();
-                this.last_item_id = Some(selected_path);
+                this.last_item_id = selected;
                 cx.notify();
             }
         })
@@ -38,41 +30,14 @@ impl PreviewView {
     }
 
     fn open_in_explorer(&mut self, path: &str) {
-        #[cfg(target_os = "windows")]
-        {
-            Command::new("explorer")
-                .arg("/select,")
-                .arg(path)
-                .spawn()
-                .ok();
-        }
-        #[cfg(target_os = "macos")]
-        {
-            Command::new("open").arg("-R").arg(path).spawn().ok();
-        }
-        #[cfg(target_os = "linux")]
-        {
-            if let Some(parent) = std::path::Path::new(path).parent() {
-                Command::new("xdg-open").arg(parent).spawn().ok();
-            }
+        if let Err(e) = open_target::reveal_path(path) {
+            tracing::warn!("show in folder failed: {e:#}");
         }
     }
 
     fn open_file(&mut self, path: &str) {
-        #[cfg(target_os = "windows")]
-        {
-            Command::new("cmd")
-                .args(["/C", "start", "", path])
-                .spawn()
-                .ok();
-        }
-        #[cfg(target_os = "macos")]
-        {
-            Command::new("open").arg(path).spawn().ok();
-        }
-        #[cfg(target_os = "linux")]
-        {
-            Command::new("xdg-open").arg(path).spawn().ok();
+        if let Err(e) = open_target::open_path(path) {
+            tracing::warn!("open failed: {e:#}");
         }
     }
 
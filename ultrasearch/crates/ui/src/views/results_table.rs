@@ -7,18 +7,28 @@ use crate::views::context_menu::{ContextMenu, ContextMenuItem};
 use gpui::prelude::*;
 use gpui::{InteractiveElement, *};
 use ipc::SearchHit;
-use std::process::Command;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 fn row_height() -> Pixels {
     px(48.)
 }
 
+/// How far the pointer has to travel with the button held before a press on a row becomes a
+/// drag.
+fn drag_threshold() -> Pixels {
+    px(4.)
+}
+
 pub struct ResultsView {
     model: Entity<SearchAppModel>,
     icon_cache: Entity<IconCache>,
     list_state: ListState,
     hover_index: Option<usize>,
+    /// `(search generation, page, rows)` the list was last reset for; appended pages change
+    /// none of them, so loading more keeps the scroll position.
+    shown: (u64, usize, usize),
+    /// Row and position of a left press that may still turn into a drag.
+    drag_origin: Option<(usize, Point<Pixels>)>,
 }
 
 impl ResultsView {
@@ -28,12 +38,33 @@ impl ResultsView {
 
         cx.observe(&model, |this: &mut Self, model, cx| {
             let read = model.read(cx);
-            let count = read.current_page_results().len();
-            this.list_state.reset(count);
+            let shown = (
+                read.search_generation,
+                read.page,
+                read.current_page_results().len(),
+            );
+            if shown != this.shown {
+                this.shown = shown;
+                this.list_state.reset(shown.2);
+            }
             cx.notify();
         })
         .detach();
 
+        // Scrolling to the bottom of the last loaded page fetches the next one.
+        let scroll_model = model.downgrade();
+        list_state.set_scroll_handler(move |event, _window, cx| {
+            if event.visible_range.end < event.count {
+                return;
+            }
+            let _ = scroll_model.update(cx, |model, cx| {
+                let last_page = model.page_start() + model.page_size >= model.results.len();
+                if last_page {
+                    model.load_more(cx);
+                }
+            });
+        });
+
         cx.observe(&icon_cache, |_, _, cx| {
             cx.notify();
         })
@@ -44,6 +75,8 @@ impl ResultsView {
             icon_cache,
             list_state,
             hover_index: None,
+            shown: (0, 0, 0),
+            drag_origin: None,
         }
     }
 
@@ -148,6 +181,30 @@ impl ResultsView {
         });
     }
 
+    /// Drag the row pressed at `index` out of the app. Only a copy is offered; see `crate::dnd`.
+    ///
+    /// The shell's drag loop blocks until the drop, so it runs deferred, after the mouse
+    /// listener that started it has returned.
+    fn start_drag(&mut self, index: usize, window: &mut Window, cx: &mut Context<Self>) {
+        self.handle_click(index, cx);
+        let Some(path) = self
+            .model
+            .read(cx)
+            .selected_row()
+            .and_then(|hit| hit.path.clone())
+        else {
+            return;
+        };
+        window.defer(cx, move |_, _| {
+            #[cfg(target_os = "windows")]
+            if let Err(err) = crate::dnd::drag_files(&[&path]) {
+                tracing::warn!("dragging {path} failed: {err:#}");
+            }
+            #[cfg(not(target_os = "windows"))]
+            tracing::debug!("dragging files out is only supported on Windows: {path}");
+        });
+    }
+
     fn handle_context_menu(
         &mut self,
         index: usize,
@@ -244,21 +301,8 @@ impl ResultsView {
 
     #[allow(dead_code)]
     fn open_file(&self, path: &str) {
-        #[cfg(target_os = "windows")]
-        {
-            Command::new("explorer")
-                .arg("/select,")
-                .arg(path)
-                .spawn()
-                .ok();
-        }
-        #[cfg(target_os = "macos")]
-        {
-            Command::new("open").arg(path).spawn().ok();
-        }
-        #[cfg(target_os = "linux")]
-        {
-            Command::new("xdg-open").arg(path).spawn().ok();
+        if let Err(e) = core_types::open_target::open_path(path) {
+            tracing::warn!("open failed: {e:#}");
         }
     }
 
@@ -381,15 +425,35 @@ impl ResultsView {
             .tab_stop(true)
             .tab_index(0)
             .focus_visible(|style| style.border_color(colors.match_highlight).border_2())
-            .on_mouse_move(cx.listener(move |this, _, _, cx| {
+            .on_mouse_move(cx.listener(move |this, event: &MouseMoveEvent, window, cx| {
                 if this.hover_index != Some(index) {
                     this.hover_index = Some(index);
                     cx.notify();
                 }
+                if event.pressed_button != Some(MouseButton::Left) {
+                    this.drag_origin = None;
+                    return;
+                }
+                if let Some((row, origin)) = this.drag_origin {
+                    let moved = event.position - origin;
+                    if row == index
+                        && (moved.x.abs() > drag_threshold() || moved.y.abs() > drag_threshold())
+                    {
+                        this.drag_origin = None;
+                        this.start_drag(index, window, cx);
+                    }
+                }
             }))
+            .on_mouse_up(
+                MouseButton::Left,
+                cx.listener(|this, _, _, _| {
+                    this.drag_origin = None;
+                }),
+            )
             .on_mouse_down(
                 MouseButton::Left,
                 cx.listener(move |this, event: &MouseDownEvent, _, cx| {
+                    this.drag_origin = Some((index, event.position));
                     this.handle_click(index, cx);
                     if event.click_count >= 2 {
                         this.handle_double_click(index, cx);
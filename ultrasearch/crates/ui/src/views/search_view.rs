@@ -1,6 +1,7 @@
 use crate::actions::{MinimizeToTray, ToggleShortcuts};
-use crate::model::state::{BackendMode, SearchAppModel};
+use crate::model::state::{kind_label, BackendMode, SearchAppModel, KIND_FILTERS};
 use crate::theme;
+use core_types::FileKind;
 use gpui::prelude::*;
 use gpui::{InteractiveElement, *};
 
@@ -201,6 +202,14 @@ impl SearchView {
         cx.notify();
     }
 
+    /// Show `text` without re-sending it to the model, e.g. a query recalled from history.
+    pub fn show_text(&mut self, text: &str, cx: &mut Context<Self>) {
+        self.input_text = SharedString::from(text.to_owned());
+        self.cursor = text.len();
+        self.selection = None;
+        cx.notify();
+    }
+
     pub fn clear_search(&mut self, cx: &mut Context<Self>) {
         self.input_text = "".into();
         self.cursor = 0;
@@ -217,6 +226,12 @@ impl SearchView {
         });
     }
 
+    fn set_kind_filter(&mut self, kind: Option<FileKind>, cx: &mut Context<Self>) {
+        self.model.update(cx, |model, cx| {
+            model.set_kind_filter(kind, cx);
+        });
+    }
+
     fn format_number(n: u64) -> String {
         if n >= 1_000_000 {
             format!("{:.1}M", n as f64 / 1_000_000.0)
@@ -286,6 +301,45 @@ impl SearchView {
                 cx.listener(move |this, _, _, cx| this.set_mode(mode, cx)),
             )
     }
+
+    /// One type filter chip; `kind: None` is "All". `count` comes from the kind facet.
+    fn render_kind_chip(
+        &self,
+        kind: Option<FileKind>,
+        count: Option<u64>,
+        current: Option<FileKind>,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let is_active = kind == current;
+        let colors = theme::active_colors(cx);
+        let label = kind.map_or("All", kind_label);
+        let text = match count {
+            Some(n) => format!("{label} ({})", Self::format_number(n)),
+            None => label.to_string(),
+        };
+
+        div()
+            .px_2()
+            .py_0p5()
+            .rounded_md()
+            .text_size(px(12.))
+            .tab_stop(true)
+            .tab_index(0)
+            .when(is_active, |this| {
+                this.bg(colors.selection_bg).text_color(colors.text_primary)
+            })
+            .when(!is_active, |this| {
+                this.text_color(colors.text_secondary)
+                    .hover(|style| style.bg(colors.panel_bg).text_color(colors.text_primary))
+            })
+            .focus_visible(|style| style.border_1().border_color(colors.match_highlight))
+            .cursor_pointer()
+            .child(text)
+            .on_mouse_down(
+                MouseButton::Left,
+                cx.listener(move |this, _, _, cx| this.set_kind_filter(kind, cx)),
+            )
+    }
 }
 
 impl Render for SearchView {
@@ -293,6 +347,8 @@ impl Render for SearchView {
         let model = self.model.read(cx);
         let status = model.status.clone();
         let query = model.query.clone();
+        let kind_filter = model.kind_filter;
+        let kind_counts = model.kind_counts.clone();
         let ipc_recovered = model.ipc_recent_reconnect;
         let colors = theme::active_colors(cx);
         let totals = status
@@ -650,6 +706,23 @@ impl Render for SearchView {
                             )),
                     ),
             )
+            .child(
+                // Type filter, with per-kind counts when the last search was bucketed
+                div()
+                    .px_4()
+                    .pb_1()
+                    .flex()
+                    .items_center()
+                    .gap_1()
+                    .child(self.render_kind_chip(None, None, kind_filter, cx))
+                    .children(KIND_FILTERS.into_iter().map(|kind| {
+                        let count = kind_counts
+                            .iter()
+                            .find(|(k, _)| *k == kind)
+                            .map(|&(_, n)| n);
+                        self.render_kind_chip(Some(kind), count, kind_filter, cx)
+                    })),
+            )
             .child(
                 // Inline helper tips
                 div()